@@ -0,0 +1,60 @@
+// benches/geometry.rs
+// 热点路径的基准：时间轴状态重建（reconstruct_state_at_time）、CPU 端的线路生成（SceneGeometry）和悬停拾取的线段网格。
+// 运行：cargo bench（只运行其中一组：cargo bench -- reconstruct_state_at_time）
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+
+use wdmview::bench_support::{
+    reconstruct_state_at_time, synthetic_events, time_at_fraction, GeometryFixture, SegmentGridFixture, ServiceIntervalSemantics,
+};
+
+const SEED: u64 = 1;
+
+fn bench_reconstruct_state(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reconstruct_state_at_time");
+    for event_count in [10_000, 100_000, 500_000] {
+        let events = synthetic_events(event_count, SEED);
+        for (position, fraction) in [("early", 0.1), ("middle", 0.5), ("late", 0.9)] {
+            let time = time_at_fraction(&events, fraction);
+            group.bench_with_input(BenchmarkId::new(position, event_count), &time, |b, &time| {
+                b.iter(|| reconstruct_state_at_time(black_box(&events), black_box(time), ServiceIntervalSemantics::default()));
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_regenerate_geometry(c: &mut Criterion) {
+    let mut group = c.benchmark_group("regenerate_geometry");
+    group.sample_size(20); // 10k 节点的单次迭代较慢
+    for nodes in [1_000, 10_000] {
+        for highlight in [false, true] {
+            let fixture = GeometryFixture::new(nodes, highlight, SEED);
+            let name = if highlight { "highlight" } else { "plain" };
+            group.bench_function(BenchmarkId::new(name, nodes), |b| {
+                b.iter(|| black_box(fixture.regenerate()));
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_segment_grid(c: &mut Criterion) {
+    let mut group = c.benchmark_group("segment_grid");
+    let segments = 10_000;
+    let fixture = SegmentGridFixture::new(segments, SEED);
+    group.bench_function(BenchmarkId::new("build", segments), |b| {
+        b.iter(|| black_box(fixture.build()));
+    });
+    // 每次迭代 4096 个查询点
+    group.bench_function(BenchmarkId::new("nearest", segments), |b| {
+        b.iter(|| black_box(fixture.nearest_all()));
+    });
+    group.bench_function(BenchmarkId::new("brute_force", segments), |b| {
+        b.iter(|| black_box(fixture.nearest_all_brute_force()));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_reconstruct_state, bench_regenerate_geometry, bench_segment_grid);
+criterion_main!(benches);
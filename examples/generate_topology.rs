@@ -0,0 +1,30 @@
+// examples/generate_topology.rs
+// 生成合成拓扑 JSON，用于在没有仿真数据时做性能测试，例如：
+//   cargo run --release --example generate_topology -- --nodes 5000 --layout random --seed 7 --output big.json
+// 参数（均可省略）：--nodes / --connections / --services / --channels / --layout grid|random / --seed /
+// --holding（服务平均持续时间，秒）/ --realloc（碎片整理概率）/ --output（省略时写到标准输出）
+use anyhow::{anyhow, Context};
+use wdmview::synthetic::{generate, SyntheticParams};
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let mut params = SyntheticParams::default();
+    let mut output = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let key = flag.strip_prefix("--").ok_or_else(|| anyhow!("Unexpected argument '{}'", flag))?;
+        let value = args.next().ok_or_else(|| anyhow!("Missing value for {}", flag))?;
+        match key {
+            "output" => output = Some(value),
+            _ => params.set(key, &value).map_err(anyhow::Error::msg)?,
+        }
+    }
+
+    let json = serde_json::to_string(&generate(&params))?;
+    match output {
+        Some(path) => std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path))?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
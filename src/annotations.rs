@@ -0,0 +1,254 @@
+// src/annotations.rs
+// 地图注释（addAnnotation）：分析人员在拓扑上标注的自由文字，例如 "fiber cut here"。每条注释绘制为一个带底色块的文字标签（文字按底色块亮度取黑色或白色），
+// 底色块位于目标点右上方固定的屏幕偏移处，以一条细引线连接到目标点：anchor_element_id 指定时为该节点的当前位置（拖动节点时跟随），
+// 否则为 world_position。标签按锚定节点（或基础节点半径）的屏幕大小渐显，与节点标签使用相同的 LOD 设置；关闭文字渲染时不显示。
+// 注释与时间轴无关，不随事件重新加载清空；替换拓扑并适配视角时清空，保持视角的重新加载和合并后保留锚定节点仍然存在的注释。
+// 注释包含在会话导出中。标签的屏幕矩形在每次绘制后记录，点击标签时发送 annotationClicked 通知（不再执行拾取）。
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::State;
+use crate::layout_history::LayoutPosition;
+use crate::models::{CircleInstance, LineVertex};
+use crate::notifications::ViewNotification;
+use crate::overlay::{push_screen_line, push_screen_rect};
+use crate::settings::{deserialize_hex_color, serialize_hex_color};
+
+/// 标签左下角相对目标点的屏幕偏移
+const LEADER_OFFSET_PX: Vec2 = Vec2::new(16.0, -20.0);
+const CHIP_PADDING_PX: Vec2 = Vec2::new(5.0, 3.0);
+const LEADER_WIDTH_PX: f32 = 1.0;
+/// 目标点处小方块的边长
+const TARGET_MARKER_PX: f32 = 4.0;
+/// 底色块的不透明度（乘以 LOD 渐显）
+const CHIP_ALPHA: f32 = 0.85;
+/// 底色块相对亮度高于此值时文字用黑色，否则用白色；约 0.179 时黑白两种文字的 WCAG 对比度相等
+const DARK_TEXT_LUMINANCE: f32 = 0.179;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    /// 同一 ID 再次添加时替换原来的注释
+    pub id: String,
+    /// 坐标约定与拓扑数据中的 location 相同；有锚定节点时 listAnnotations 返回该节点的当前位置
+    pub world_position: LayoutPosition,
+    pub text: String,
+    /// 引线和底色块的颜色，JSON 中为 "#rrggbb"
+    #[serde(default = "default_annotation_color", serialize_with = "serialize_hex_color", deserialize_with = "deserialize_hex_color")]
+    pub color: [f32; 4],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor_element_id: Option<String>,
+}
+
+fn default_annotation_color() -> [f32; 4] {
+    [0.9, 0.45, 0.05, 1.0] // 琥珀色（线性空间）
+}
+
+impl Annotation {
+    /// 标签文字颜色（sRGB）：按底色块的相对亮度选择黑色或白色，color 已在线性空间，直接加权
+    pub fn text_color(&self) -> [u8; 3] {
+        let [r, g, b, _] = self.color;
+        let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        if luminance > DARK_TEXT_LUMINANCE { [0, 0, 0] } else { [255, 255, 255] }
+    }
+}
+
+/// 一帧中绘制的注释标签（屏幕像素），由 render_to_view 在文字排版后生成
+#[derive(Debug, Clone)]
+pub struct AnnotationPlacement {
+    pub annotation_idx: usize,
+    pub target: Vec2,
+    pub chip_min: Vec2,
+    pub chip_max: Vec2,
+    pub alpha: f32,
+}
+
+impl AnnotationPlacement {
+    /// text_size 为排版后的文字宽高；返回的位置同时是 TextArea 的 left / top
+    pub fn new(annotation_idx: usize, target: Vec2, text_size: Vec2, alpha: f32) -> (Self, Vec2) {
+        let text_origin = target + LEADER_OFFSET_PX - Vec2::new(0.0, text_size.y);
+        let placement = Self {
+            annotation_idx,
+            target,
+            chip_min: text_origin - CHIP_PADDING_PX,
+            chip_max: text_origin + text_size + CHIP_PADDING_PX,
+            alpha,
+        };
+        (placement, text_origin)
+    }
+
+    fn offset(&self, offset: Vec2) -> Self {
+        Self { target: self.target + offset, chip_min: self.chip_min + offset, chip_max: self.chip_max + offset, ..*self }
+    }
+
+    fn contains(&self, pos: Vec2) -> bool {
+        pos.cmpge(self.chip_min).all() && pos.cmple(self.chip_max).all()
+    }
+}
+
+impl State {
+    /// addAnnotation：锚定节点不在当前拓扑中时拒绝
+    pub fn add_annotation(&mut self, annotation: Annotation) -> Result<(), String> {
+        if annotation.id.is_empty() {
+            return Err("Annotation id must not be empty".to_string());
+        }
+        if !annotation.world_position.x.is_finite() || !annotation.world_position.y.is_finite() {
+            return Err(format!("Annotation '{}' has a non-finite world_position", annotation.id));
+        }
+        if let Some(anchor_id) = &annotation.anchor_element_id && !self.node_id_to_idx.contains_key(anchor_id) {
+            return Err(format!("Unknown anchor element ID '{}'", anchor_id));
+        }
+        match self.annotations.iter_mut().find(|existing| existing.id == annotation.id) {
+            Some(existing) => *existing = annotation,
+            None => self.annotations.push(annotation),
+        }
+        Ok(())
+    }
+
+    /// removeAnnotation：返回是否存在该注释
+    pub fn remove_annotation(&mut self, id: &str) -> bool {
+        let count = self.annotations.len();
+        self.annotations.retain(|annotation| annotation.id != id);
+        self.annotation_placements.clear(); // 索引已经变化，下一帧重新生成
+        self.annotations.len() != count
+    }
+
+    /// listAnnotations：按添加顺序，锚定的注释使用节点的当前位置
+    pub fn list_annotations(&self) -> Vec<Annotation> {
+        self.annotations.iter().map(|annotation| {
+            let mut annotation = annotation.clone();
+            if let Some(anchor) = self.annotation_anchor(&annotation) {
+                annotation.world_position = LayoutPosition::from_world(Vec2::from_array(anchor.position));
+            }
+            annotation
+        }).collect()
+    }
+
+    pub fn clear_annotations(&mut self) {
+        self.annotations.clear();
+        self.annotation_placements.clear();
+    }
+
+    /// 拓扑重新加载或合并后调用：删除锚定节点已经不存在的注释
+    pub fn prune_annotations(&mut self) {
+        let count = self.annotations.len();
+        self.annotations.retain(|annotation| {
+            annotation.anchor_element_id.as_ref().is_none_or(|anchor_id| self.node_id_to_idx.contains_key(anchor_id))
+        });
+        if self.annotations.len() != count {
+            log::info!("Removed {} annotation(s) anchored to nodes that are no longer in the topology.", count - self.annotations.len());
+            self.annotation_placements.clear();
+        }
+    }
+
+    /// 锚定节点的当前实例（位置包括拖动）
+    fn annotation_anchor(&self, annotation: &Annotation) -> Option<&CircleInstance> {
+        let idx = *self.node_id_to_idx.get(annotation.anchor_element_id.as_ref()?)?;
+        self.geometry.circle_instances.get(idx)
+    }
+
+    /// 需要绘制的注释：(索引, 目标点的屏幕坐标, 不透明度)。目标点在画布外或 LOD 不透明度为 0 时跳过
+    pub fn visible_annotations(&self) -> Vec<(usize, Vec2, f32)> {
+        let viewport_size = self.camera.viewport_size;
+        self.annotations.iter().enumerate().filter_map(|(annotation_idx, annotation)| {
+            let (world_pos, world_radius) = match self.annotation_anchor(annotation) {
+                Some(anchor) => (Vec2::from_array(anchor.position), anchor.radius_scale),
+                None => (annotation.world_position.to_world(), self.node_radius),
+            };
+            let alpha = self.label_settings.label_alpha(self.camera.world_radius_to_screen_pixels(world_radius));
+            let target = self.camera.world_to_screen(world_pos);
+            let on_screen = target.cmpge(Vec2::ZERO).all() && target.cmple(viewport_size).all();
+            (on_screen && alpha > 0.0).then_some((annotation_idx, target, alpha))
+        }).collect()
+    }
+
+    /// 引线、目标点标记和底色块（NDC 三角形）；对比视图中 mirror_offset 为右半部分的偏移，两边各绘制一份
+    pub fn push_annotation_vertices(&self, vertices: &mut Vec<LineVertex>, canvas_size: Vec2, mirror_offset: Option<Vec2>) {
+        let copies = std::iter::once(Vec2::ZERO).chain(mirror_offset);
+        for offset in copies {
+            for placement in self.annotation_placements.iter().map(|placement| placement.offset(offset)) {
+                let Some(annotation) = self.annotations.get(placement.annotation_idx) else {
+                    continue;
+                };
+                let [r, g, b, a] = annotation.color;
+                let line_color = [r, g, b, a * placement.alpha];
+                let leader_end = Vec2::new(placement.chip_min.x, placement.chip_max.y);
+                push_screen_line(vertices, placement.target, leader_end, LEADER_WIDTH_PX, canvas_size, line_color);
+                let marker_half = Vec2::splat(TARGET_MARKER_PX / 2.0);
+                push_screen_rect(vertices, placement.target - marker_half, placement.target + marker_half, canvas_size, line_color);
+                push_screen_rect(vertices, placement.chip_min, placement.chip_max, canvas_size, [r, g, b, a * CHIP_ALPHA * placement.alpha]);
+            }
+        }
+    }
+
+    /// pos 为对比视图中光标所在一半的本地坐标（mouse_current_pos_screen）；重叠时后绘制的标签优先
+    pub fn annotation_at(&self, pos: Vec2) -> Option<&Annotation> {
+        self.annotation_placements.iter().rev()
+            .find(|placement| placement.contains(pos))
+            .and_then(|placement| self.annotations.get(placement.annotation_idx))
+    }
+
+    /// 点击位置有注释标签时发送 annotationClicked 通知并返回 true，调用方不再执行拾取
+    pub fn click_annotation(&mut self, pos: Vec2) -> bool {
+        let Some(annotation_id) = self.annotation_at(pos).map(|annotation| annotation.id.clone()) else {
+            return false;
+        };
+        log::info!("Annotation {} clicked.", annotation_id);
+        self.pending_notifications.push(ViewNotification::AnnotationClicked { annotation_id });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotation(color: [f32; 4]) -> Annotation {
+        Annotation { id: "a".to_string(), world_position: LayoutPosition { x: 0.0, y: 0.0 }, text: "fiber cut".to_string(), color, anchor_element_id: None }
+    }
+
+    #[test]
+    fn placement_chip_sits_above_right_of_target() {
+        let target = Vec2::new(100.0, 200.0);
+        let text_size = Vec2::new(40.0, 12.0);
+        let (placement, text_origin) = AnnotationPlacement::new(0, target, text_size, 1.0);
+        assert_eq!(text_origin, Vec2::new(116.0, 168.0));
+        assert_eq!(placement.chip_min, text_origin - CHIP_PADDING_PX);
+        assert_eq!(placement.chip_max, text_origin + text_size + CHIP_PADDING_PX);
+        // 引线终点（底色块左下角）在目标点右上方
+        assert!(placement.chip_min.x > target.x && placement.chip_max.y < target.y);
+    }
+
+    #[test]
+    fn contains_is_inclusive_at_chip_edges() {
+        let (placement, _) = AnnotationPlacement::new(0, Vec2::new(100.0, 200.0), Vec2::new(40.0, 12.0), 1.0);
+        let (min, max) = (placement.chip_min, placement.chip_max);
+        for corner in [min, max, Vec2::new(min.x, max.y), Vec2::new(max.x, min.y)] {
+            assert!(placement.contains(corner), "corner {corner:?}");
+        }
+        assert!(placement.contains((min + max) / 2.0));
+        for outside in [min - Vec2::new(0.01, 0.0), min - Vec2::new(0.0, 0.01), max + Vec2::new(0.01, 0.0), max + Vec2::new(0.0, 0.01)] {
+            assert!(!placement.contains(outside), "outside {outside:?}");
+        }
+        // 目标点本身不属于标签
+        assert!(!placement.contains(placement.target));
+    }
+
+    #[test]
+    fn offset_placement_moves_hit_area() {
+        let (placement, _) = AnnotationPlacement::new(0, Vec2::new(100.0, 200.0), Vec2::new(40.0, 12.0), 1.0);
+        let offset = Vec2::new(300.0, 0.0);
+        let mirrored = placement.offset(offset);
+        assert!(mirrored.contains(placement.chip_min + offset));
+        assert!(!mirrored.contains(placement.chip_min));
+    }
+
+    #[test]
+    fn text_color_follows_chip_luminance() {
+        assert_eq!(annotation(default_annotation_color()).text_color(), [0, 0, 0]);
+        assert_eq!(annotation([1.0, 1.0, 1.0, 1.0]).text_color(), [0, 0, 0]);
+        assert_eq!(annotation([1.0, 1.0, 0.0, 1.0]).text_color(), [0, 0, 0]);
+        assert_eq!(annotation([0.0, 0.0, 0.0, 1.0]).text_color(), [255, 255, 255]);
+        assert_eq!(annotation([0.01, 0.02, 0.3, 1.0]).text_color(), [255, 255, 255]);
+        assert_eq!(annotation([0.6, 0.0, 0.0, 1.0]).text_color(), [255, 255, 255]);
+    }
+}
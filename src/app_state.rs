@@ -1,84 +1,257 @@
-use std::{collections::HashMap, sync::Arc, sync::Mutex};
+use std::{collections::BTreeMap, collections::HashMap, sync::Arc, sync::Mutex};
 use winit::{
     event::*,
-    window::Window,
+    window::{CursorIcon, Window},
 };
 use instant::Instant;
 use glam::Vec2;
-use bevy_color::{ColorToComponents, LinearRgba, Oklcha, Srgba};
-use wgpu::util::DeviceExt;
+use bevy_color::{ColorToComponents, LinearRgba, Srgba};
 
 
-use crate::models::{Vertex2D, CircleInstance, LineVertex};
+use crate::models::{CircleInstance, LineVertex, ThickLineVertex};
 use crate::camera::{Camera, CameraUniform};
 use crate::scene::connection::ConnectionData;
-use crate::scene::defrag_event::{reconstruct_state_at_time, AnyEvent};
+use crate::scene::defrag_event::{count_active_services_at_times, reconstruct_state_at_time, AnyEvent, EventCheckpoints, EventKind, ServiceDiff};
 use crate::scene::service::ServiceData; // 引入 ServiceData
+use crate::scene::blocked_demand::BlockedDemand;
 use crate::scene::element::ElementData;
-use crate::scene::text_label::TextLabel; // 引入 ElementData
-
-
-pub const BASE_NODE_RADIUS: f32 = 20.0;
-const LINES_WGSL: &str = include_str!("./shaders/lines.wgsl");
-const CIRCLES_WGSL: &str = include_str!("./shaders/circles.wgsl");
-
+use crate::settings::{Background, ChannelPlan, ColorMode, HighlightLineStyle, HighlightStyle, LabelSettings, LodLevel, LodSettings, OpacityMode, ServiceIntervalSemantics, ThicknessMode};
+use crate::node_icons::{NodeIconMapping, ICON_FONT_FAMILY};
+use crate::notifications::{TimeChangeReason, ViewNotification};
+use crate::errors::{self, ViewError};
+use crate::scene::validation::{validate_timeline_events, ValidationReport};
+use crate::scene::auto_layout::fill_missing_locations;
+use crate::scene::edge_bundles::{EdgeBundles, EdgeBundlingJob};
+use crate::scene::wavelength_colors::WavelengthColorLut;
+use crate::bookmarks::TimeBookmark;
+use crate::saved_views::SavedView;
+use crate::capture::PendingCapture;
+use crate::recording::TimelineRecording;
+use crate::keymap::Keymap;
+use crate::measurement::MeasurementState;
+use crate::geometry_update::GeometryUpdate;
+use crate::topology_export::TopologyExport;
+use crate::compare::CompareView;
+use crate::quality::QualityGovernor;
+use crate::service_panel::ServicePanel;
+use crate::timeline_strip::TimelineStrip;
+use crate::service_template::ServiceTemplate;
+use crate::overlay::{LabelChips, OverlayTheme, Tooltip};
+use crate::renderer_info::RendererInfo;
+use crate::layout_history::{LayoutEdit, LayoutHistory, LayoutImportSummary, LayoutPosition, NodeLayout};
+use crate::link_inspector::InspectedLink;
+use crate::node_groups::NodeGroups;
+use crate::annotations::{Annotation, AnnotationPlacement};
+use crate::picking::{decode_pick_id, GpuPicker, PickSegment, PickedEntity, PICK_ID_NONE};
+use crate::renderer::{OverlayCounts, Renderer, SceneSection};
+use crate::gpu_profiler::GpuProfiler;
+use crate::text_resources::{TextResources, MAX_LABEL_BUFFERS};
+use crate::scene::geometry::SceneGeometry;
+use crate::scene::svg::{SvgBounds, SvgSnapshot};
+use crate::ui_events::StepDirection;
+pub use crate::scene::geometry::BASE_NODE_RADIUS;
+use crate::scene::geometry::NODE_INSTANCE_RADIUS_FACTOR;
+
+
+// 节点屏幕半径小于该值时不显示图标 (LOD)；标签按 LabelSettings 的渐显区间显示
+const MIN_DISPLAY_SCREEN_RADIUS: f32 = 60.0;
+const SELECTION_RING_RADIUS_FACTOR: f32 = 1.3;
+const SELECTION_NODE_RADIUS_FACTOR: f32 = 1.1;
+pub const CLICK_MAX_DRAG_PX: f32 = 4.0; // 按下和松开之间移动小于该距离时视为点击
+// 标签和节点图标共用的文字颜色
+const TEXT_COLOR: glyphon::Color = glyphon::Color::rgb(230, 230, 230);
+const ANNOTATION_FONT_SIZE: f32 = 16.0; // 注释文字（路径跳数等）的屏幕字号
+const HIGHLIGHT_PULSE_HZ: f32 = 1.0;
+const HIGHLIGHT_PULSE_MIN_ALPHA: f32 = 0.35; // 脉冲最暗时高亮线路的透明度
+const THICKNESS_REGENERATE_ZOOM_RATIO: f32 = 1.25; // 按码率加粗时，缩放相对生成时变化超过该倍数后重新生成线路
+
+
+pub async fn request_device(adapter: &wgpu::Adapter) -> anyhow::Result<(wgpu::Device, wgpu::Queue)> {
+    let device_and_queue = adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: None,
+            // 时间戳查询只在 setGpuProfiling 开启时使用，适配器不支持时不请求
+            required_features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+            experimental_features: wgpu::ExperimentalFeatures::disabled(),
+            required_limits: wgpu::Limits::default(),
+            memory_hints: Default::default(),
+            trace: wgpu::Trace::Off,
+        })
+        .await?;
+    let (device, queue) = device_and_queue;
+    // 默认的处理方式会直接 panic；改为通过错误回调报告（见 errors.rs）
+    device.on_uncaptured_error(Arc::new(|error: wgpu::Error| {
+        errors::report(ViewError::error("gpu_error", error.to_string()));
+    }));
+    device.set_device_lost_callback(|reason, message| {
+        // 销毁视图时设备随 State 一起释放，不是错误
+        if reason != wgpu::DeviceLostReason::Destroyed {
+            errors::report(
+                ViewError::fatal("device_lost", format!("GPU device lost: {}", message))
+                    .with_context(serde_json::json!({ "reason": format!("{:?}", reason) })),
+            );
+        }
+    });
+    Ok((device, queue))
+}
 
 pub struct State {
-    pub surface: wgpu::Surface<'static>,
+    pub surface: Option<wgpu::Surface<'static>>, // None 表示离屏渲染（见 headless 模块）
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
     pub is_surface_configured: bool,
+    pub is_minimized: bool, // 窗口最小化或画布尺寸为 0 时不渲染，恢复时重新配置 surface
 
     // Glyphon related fields
-    pub glyphon_font_system: glyphon::FontSystem,
-    pub glyphon_viewport: glyphon::Viewport,
-    pub glyphon_swash_cache: glyphon::SwashCache,
-    pub glyphon_atlas: glyphon::TextAtlas,
-    pub glyphon_renderer: glyphon::TextRenderer,
-    pub glyphon_buffers: Vec<glyphon::Buffer>,
+    pub text_resources: Option<TextResources>, // 第一帧有文字时创建（见 text_resources.rs）
+    pub node_icon_mapping: NodeIconMapping,
 
     pub camera: Camera,
-    pub camera_buffer: wgpu::Buffer,
-    pub camera_bind_group: wgpu::BindGroup,
     pub camera_uniform: CameraUniform,
     pub camera_needs_update: bool,
 
-    pub line_render_pipeline: wgpu::RenderPipeline,
-    pub circle_render_pipeline: wgpu::RenderPipeline,
-
-    pub circle_instances: Vec<CircleInstance>,
-    pub circle_instance_buffer: wgpu::Buffer,
-    pub quad_vertex_buffer: wgpu::Buffer,
-    pub quad_index_buffer: wgpu::Buffer,
-
-    pub line_vertices: Vec<LineVertex>,
-    pub line_vertex_buffer: wgpu::Buffer,
+    pub renderer: Renderer, // 管线和 GPU 缓冲区
+    pub geometry: SceneGeometry, // 节点实例和线路顶点，topology_needs_update 时重新生成
 
     // --- 新增时间轴和拓扑数据管理字段 ---
     pub all_elements: Vec<ElementData>, // 存储所有节点数据
     pub all_connections: Vec<ConnectionData>,
     pub all_events: Vec<AnyEvent>, // 存储所有事件变化数据
-    pub num_channels: u32,
+    pub events_generation: u64, // all_events 每次被替换、合并或清空时加一，见 mark_events_changed
+    pub event_checkpoints: EventCheckpoints, // 热度轨迹窗口平均的回放检查点，见 refresh_event_checkpoints
+    pub channel_plan: ChannelPlan, // 每条链路的波长数和服务线路的展开角度，见 channel_plan.rs
+    pub wavelength_colors: WavelengthColorLut, // 按 channel_plan 和 highlight_style 预计算的服务颜色，见 scene/wavelength_colors.rs
+    pub node_radius: f32, // 节点的基础半径（世界单位），见 node_radius.rs
+    pub node_radius_setting: Option<f32>, // setNodeRadius 指定的半径，None 时为自动推算或默认半径
+    pub auto_node_radius: bool, // setAutoNodeRadius：没有固定半径时按节点间距自动推算（默认关闭）
+    pub node_radius_overrides: HashMap<String, f32>, // 按 element_id 覆盖的半径
+    pub separate_overlapping_nodes: bool, // 把位置重合的节点分开显示，见 node_overlap.rs
+    pub node_overlap_groups: Vec<Vec<usize>>, // 位置重合的节点索引，每组至少两个
+    pub node_overlap_offsets: Vec<Vec2>, // 与 circle_instances 一一对应，显示位置 = 原始位置 + 偏移
+    pub edge_bundling_enabled: bool, // 链路和服务线路沿绑定后的折线绘制，见 edge_bundling.rs
+    pub edge_bundling_strength: f32,
+    pub edge_bundles: Option<EdgeBundles>, // 按当前节点位置计算好的绑定形状
+    pub edge_bundling_job: Option<EdgeBundlingJob>, // 未完成的绑定计算
     // 用于快速查找节点 ID 对应的 circle_instances 索引
     pub node_id_to_idx: HashMap<String, usize>,
-    pub current_time_selection: f32, // 当前时间轴选中的时刻
-
-    pub highlight_service_id_list: Option<Vec<i32>>, // 当前选中的碎片整理过程，围绕这一 id，需要高亮
-    pub highlight_line_render_pipeline: wgpu::RenderPipeline, // 新增高亮线路渲染管线
-    pub highlight_line_vertices: Vec<LineVertex>,             // 新增高亮线路顶点数据
-    pub highlight_line_vertex_buffer: wgpu::Buffer,           // 新增高亮线路顶点缓冲区
-    pub highlight_node_color: [f32; 4], // 高亮节点的颜色
-    pub world_text_labels: Vec<TextLabel>,
+    pub connection_endpoints: Vec<(usize, usize)>, // 与 all_connections 一一对应的两端节点索引，见 scene/path_index.rs
+    pub current_time_selection: f64, // 当前时间轴选中的时刻（秒，f64 以保留大时间戳的精度）
+    pub service_interval: ServiceIntervalSemantics, // 服务在离开时刻是否仍活跃，默认 [arrival, departure)
+
+    pub highlight_service_id_list: Option<Vec<i32>>, // 当前选中的碎片整理过程，第一个为碎片整理服务本身，其余为被它移动的服务
+    pub highlight_multi_select: bool, // 高亮列表由点击选择（见 service_selection.rs），每条服务使用不同的色相
+    pub pick_toggles_highlight: bool, // 发起拾取时是否按住 Ctrl / Cmd（GPU 拾取的结果在之后的帧中处理）
+    pub pick_screen_pos: Vec2, // 发起拾取的屏幕坐标，没有命中节点和服务线段时按此检测链路
+    pub highlight_line_style: HighlightLineStyle,             // 高亮线路样式（实线 / 虚线 / 流动虚线）
+    pub color_mode: ColorMode, // 服务线路按波长或 GSNR 余量着色
+    pub thickness_mode: ThicknessMode, // 服务线路为 1 像素或按码率加粗
+    pub opacity_mode: OpacityMode, // 服务线路不透明或按利用率变淡
+    pub animation_start_instant: instant::Instant,
+    pub highlight_style: HighlightStyle, // 高亮节点和服务的颜色、线宽
+    pub highlighted_path: Option<Vec<usize>>, // highlightPathBetween 求出的最短路径（节点索引），与服务高亮互不影响
+    pub measurement: MeasurementState, // 测量模式和测量结果，见 measurement.rs
+
+    // 链路中点的容量条，随缩放重新生成（见 capacity_bars.rs）
+    pub show_capacity_bars: bool,
+    pub capacity_bar_vertices: Vec<ThickLineVertex>,
+    pub capacity_bars_need_update: bool,
+
+    // 热度轨迹模式：链路按时间窗口内的平均占用率着色（见 heat_trail.rs）
+    pub heat_trail_enabled: bool,
+    pub blocked_demands: Vec<BlockedDemand>, // 按时间排序，见 blocked_demands.rs
+    pub show_blocked_demands: bool,
+    pub blocked_demand_window: Option<f64>, // 以当前时刻为中心的窗口总长度（秒），None 为时间轴跨度的 5%
+    pub node_groups: NodeGroups, // setNodeGroups 设置的节点分组（见 node_groups.rs）
+    // 由 node_groups 按节点索引解析（resolve_node_groups），没有分组时为空
+    pub node_group_colors: Vec<Option<[f32; 4]>>,
+    pub hidden_nodes: Vec<bool>,
+    pub annotations: Vec<Annotation>, // addAnnotation 添加的注释，按添加顺序（见 annotations.rs）
+    pub annotation_placements: Vec<AnnotationPlacement>, // 上一帧绘制的注释标签，用于点击命中测试
+    pub heat_trail_window: Option<f64>, // 窗口长度（秒），None 为时间轴跨度的 10%
+
+    pub service_diff: Option<ServiceDiff>, // setDiffTimes 计算的 t1 到 t2 的服务差异（见 service_diff.rs）
+    // 流动动画：圆点沿服务线路移动，开启时持续渲染（见 flow_animation.rs）
+    pub flow_animation_enabled: bool,
+    pub flow_dot_instances: Vec<CircleInstance>,
+    // 光标下的服务及其强调线（见 service_hover.rs），不影响 highlight_service_id_list
+    pub hovered_service_id: Option<i32>,
+    pub hover_line_vertices: Vec<ThickLineVertex>,
+    pub inspected_link: Option<InspectedLink>, // 点击链路列出的服务，见 link_inspector.rs
+
+    pub compare: Option<CompareView>, // 左右对比两个时刻时右半部分的几何和缓冲区（见 compare.rs）
+
+    // 当前选中的节点：绘制一个外圈 + 略微放大的节点副本，与服务高亮互不影响
+    pub selected_node_idx: Option<usize>,
+    pub focused_node_idx: Option<usize>, // 键盘焦点（Tab 循环），同样绘制外圈
+    pub selection_accent_color: [f32; 4],
+    pub selection_instances: Vec<CircleInstance>,
+    pub selection_needs_update: bool,
+
+    pub lod_settings: LodSettings,
+    pub lod_level: LodLevel,
+
+    // 预览服务（尚未分配的需求），与时间轴无关，以虚线绘制
+    pub preview_services: Vec<ServiceData>,
 
     pub topology_needs_update: bool, // 标记拓扑（主要是服务线路）是否需要因时间变化而更新
 
+    // 拾取：对象 ID 和服务线段表在 geometry 中
+    pub gpu_picker: Option<GpuPicker>, // None 表示不支持 GPU 拾取，使用 CPU 命中测试
+    pub gpu_profiling_enabled: bool, // setGpuProfiling，见 gpu_profiler.rs
+    pub gpu_profiler: Option<GpuProfiler>, // 开启计时且设备支持时间戳查询时才创建
+    pub text_rendering_enabled: bool, // setTextRendering，关闭时不生成也不绘制任何文字
+    pub text_area_count: usize, // 上一帧提交的文字区域数（getRenderStats）
+    pub render_once_waiters: Vec<flume::Sender<()>>, // renderOnce：下一帧 present 之后通知
+    pub last_picked_entity: Option<PickedEntity>,
+
     pub mouse_current_pos_screen: Vec2,
+    pub mouse_canvas_pos_screen: Vec2, // 未换算到对比视图一半的光标位置，用于画布叠加层的命中测试
     pub is_mouse_left_pressed: bool,
+    pub keyboard_modifiers: winit::keyboard::ModifiersState,
+    pub keymap: Keymap,
+    pub mouse_press_pos_screen: Vec2, // 用于区分点击和拖动平移
+    pub dragged_node: Option<(usize, Vec2)>, // 正在拖动的节点索引及其拖动前的位置
+    pub layout_history: LayoutHistory,
+    pub cursor_tracking_enabled: bool, // 开启后向 JS 报告光标的世界坐标
+    pub last_cursor_report_instant: instant::Instant,
+
+    pub pending_notifications: Vec<ViewNotification>, // 由 App 取出并转发给 JS 回调
+    pub validation_report: ValidationReport, // 最近一次加载拓扑时发现的问题
+    pub time_bookmarks: Vec<TimeBookmark>, // 按添加顺序排列，索引即 JS 中的书签编号
+    pub saved_views: BTreeMap<String, SavedView>, // 命名视图（见 saved_views.rs），加载新拓扑时保留
+    pub pending_captures: Vec<PendingCapture>, // 等待回读的离屏截图
+    pub recording: Option<TimelineRecording>, // 进行中的时间轴录制
+    pub geometry_update: Option<GeometryUpdate>, // 未完成的分帧几何更新
+    pub geometry_world_to_pixels: f32, // 最近一次开始生成几何时的缩放（按码率加粗的线路宽度据此换算为世界单位）
+    pub topology_export: Option<TopologyExport>, // 未完成的 getFullTopology 导出（见 topology_export.rs）
 
     pub last_frame_instant: instant::Instant,
     pub frame_count_in_second: u32,
     pub current_fps: u32,
+    pub quality_governor: QualityGovernor, // 帧时间超出预算时依次关闭可选效果（见 quality.rs）
+    pub frame_work_start: Option<Instant>, // 本帧 update() 开始的时刻，render_to_view 据此计算 CPU 工作时间
+    pub show_stats_overlay: bool,
+    pub show_color_test_pattern: bool, // sRGB 参考色块（renderColorTestPattern）
+    pub background: Background, // 清屏颜色和不透明度（setBackground）
+    pub surface_alpha_modes: Vec<wgpu::CompositeAlphaMode>, // surface 支持的合成方式，透明背景时从中选择
+    pub service_panel: ServicePanel, // 画布内的活跃服务列表（见 service_panel.rs）
+    pub timeline_strip: TimelineStrip, // 画布底部的时间轴条（见 timeline_strip.rs）
+    pub tooltip: Option<Tooltip>, // 由 set_tooltip 设置，下一帧绘制
+    pub overlay_theme: OverlayTheme, // 叠加层的背景、边框和文字颜色
+    pub label_settings: LabelSettings,
+    pub service_tooltip_template: ServiceTemplate, // 服务提示框的内容（setServiceTooltipTemplate）
+    pub renderer_info: RendererInfo, // 适配器、后端和表面格式（getRendererInfo）
+}
+
+/// 节点未被高亮时的颜色
+pub fn default_node_color() -> [f32; 4] {
+    LinearRgba::from(Srgba::rgb_u8(0x00, 0x5d, 0x5d)).to_f32_array()
+}
+
+/// 标签渐显时的文字颜色：alpha 乘到原有的不透明度上
+fn with_alpha(color: glyphon::Color, alpha: f32) -> glyphon::Color {
+    glyphon::Color::rgba(color.r(), color.g(), color.b(), (color.a() as f32 * alpha).round() as u8)
 }
 
 impl State {
@@ -107,17 +280,7 @@ impl State {
             .unwrap();
         let adapter_info = adapter.get_info();
 
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                label: None,
-                required_features: wgpu::Features::empty(),
-                experimental_features: wgpu::ExperimentalFeatures::disabled(),
-                required_limits: wgpu::Limits::default(),
-                memory_hints: Default::default(),
-                trace: wgpu::Trace::Off,
-            })
-            .await
-            .unwrap();
+        let (device, queue) = request_device(&adapter).await?;
 
         let surface_caps = surface.get_capabilities(&adapter);
         let texture_format = surface_caps.formats
@@ -150,233 +313,68 @@ impl State {
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
-        surface.configure(&device, &config);
-
-        // --- Glyphon Initialization ---
-        let mut glyphon_font_system = glyphon::FontSystem::new_with_fonts([
-            glyphon::fontdb::Source::Binary(Arc::new(include_bytes!(
-                "../assets/fonts/Iced-Icons.ttf"
-            ))),
-            glyphon::fontdb::Source::Binary(Arc::new(include_bytes!(
-                "../assets/fonts/Roboto-Regular.ttf"
-            ))),
-            glyphon::fontdb::Source::Binary(Arc::new(include_bytes!(
-                "../assets/fonts/bootstrap-icons.ttf"
-            ))),
-        ]);
-        let glyphon_swash_cache = glyphon::SwashCache::new();
-        let glyphon_cache = glyphon::Cache::new(&device);
-        let glyphon_viewport = glyphon::Viewport::new(&device, &glyphon_cache);
-        let color_mode = if needs_shader_srgb_output_conversion {
-            glyphon::ColorMode::Web
-        } else {
-            glyphon::ColorMode::Accurate
-        };
-        let mut glyphon_atlas = glyphon::TextAtlas::with_color_mode(&device, &queue, &glyphon_cache, texture_format, color_mode);
-        let glyphon_renderer = glyphon::TextRenderer::new(&mut glyphon_atlas, &device, wgpu::MultisampleState::default(), None);
+        // surface 在 with_gpu 末尾按初始尺寸配置，尺寸为 0 时推迟到第一次 Resized
+
+        let mut state = Self::with_gpu(&adapter, device, queue, Some(surface), config)?;
+        state.surface_alpha_modes = surface_caps.alpha_modes;
+        Ok(state)
+    }
+
+    /// 在已有的设备上创建 State，不依赖 winit。`config` 决定渲染目标的格式和尺寸；
+    /// `surface` 为 None 时由调用方通过 `render_to_view` 渲染到自己的纹理。
+    pub fn with_gpu(
+        adapter: &wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        surface: Option<wgpu::Surface<'static>>,
+        config: wgpu::SurfaceConfiguration,
+    ) -> anyhow::Result<State> {
+        let texture_format = config.format;
+        let needs_shader_srgb_output_conversion = !texture_format.is_srgb();
+        let renderer_info = RendererInfo::new(adapter, &device, &config, surface.is_some());
+        let surface_alpha_modes = vec![config.alpha_mode]; // State::new 替换为 surface 支持的全部方式
+        // 时间轴条只在原生窗口中默认显示：网页中默认使用页面的滑块，离屏渲染（headless 快照）不显示
+        let show_timeline_strip = cfg!(not(target_arch = "wasm32")) && surface.is_some();
+        // 字体和文字缓冲区在第一帧有文字时才创建（见 text_resources.rs）
 
-        // Create text buffers
-        let buffer_num = 4000 as usize;
-        let mut glyphon_buffers = Vec::with_capacity(buffer_num);
-        for _i in 0..buffer_num {
-            let text_buffer = glyphon::Buffer::new(&mut glyphon_font_system, glyphon::Metrics::relative(10.0, 16.0));
-            glyphon_buffers.push(text_buffer);
-        }
-        
         #[allow(unused_mut)]
-        let mut camera = Camera::new(size.width, size.height);
+        let mut camera = Camera::new(config.width, config.height);
         let camera_uniform = CameraUniform {
             view_proj: camera.build_view_projection_matrix().to_cols_array_2d(),
             needs_srgb_output_conversion: needs_shader_srgb_output_conversion as u32,
-            _padding: [0; 3],
+            elapsed_time: 0.0,
+            world_to_pixels: camera.world_radius_to_screen_pixels(1.0),
+            highlight_pulse: 1.0,
         };
 
-        let camera_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Camera Buffer"),
-                contents: bytemuck::cast_slice(&[camera_uniform]),
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            }
-        );
-
-        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }
-            ],
-            label: Some("Camera Bind Group Layout"),
-        });
-
-        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &camera_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: camera_buffer.as_entire_binding(),
-                }
-            ],
-            label: Some("Camera Bind Group"),
-        });
-
-        // --- 着色器模块 ---
-        let lines_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Lines Shader"),
-            source: wgpu::ShaderSource::Wgsl(LINES_WGSL.into()),
-        });
-
-        let circles_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Circles Shader"),
-            source: wgpu::ShaderSource::Wgsl(CIRCLES_WGSL.into()),
-        });
-
-        // --- 渲染管线布局 ---
-        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[
-                &camera_bind_group_layout,
-            ],
-            push_constant_ranges: &[],
-        });
-
-        // --- 线段渲染管线 ---
-        let line_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Line Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &lines_shader_module,
-                entry_point: Some("vs_main"),
-                buffers: &[
-                    LineVertex::layout(),
-                ],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &lines_shader_module,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: texture_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::LineList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
-        });
-
-        // --- 圆形渲染管线 ---
-        let circle_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Circle Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &circles_shader_module,
-                entry_point: Some("vs_main"),
-                buffers: &[
-                    Vertex2D::layout(),
-                    CircleInstance::layout(),
-                ],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &circles_shader_module,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: texture_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
-        });
-
         // --- 初始图形数据准备 (示例) ---
         let circle_instances = vec![
             CircleInstance {
                 position: [-200.0, 0.0].into(),
                 radius_scale: BASE_NODE_RADIUS,
                 color: LinearRgba::from(Srgba::rgb_u8(255, 0, 0)).to_f32_array(),
+                glow: 0.0,
             },
             CircleInstance {
                 position: [0.0, 0.0].into(),
                 radius_scale: BASE_NODE_RADIUS,
                 color: LinearRgba::from(Srgba::rgb_u8(0, 255, 0)).to_f32_array(),
+                glow: 0.0,
             },
             CircleInstance {
                 position: [200.0, 0.0].into(),
                 radius_scale: BASE_NODE_RADIUS,
                 color: LinearRgba::from(Srgba::rgb_u8(0, 0, 255)).to_f32_array(),
+                glow: 0.0,
             },
             CircleInstance {
                 position: [0.0, 150.0].into(),
                 radius_scale: BASE_NODE_RADIUS * 1.5,
                 color: LinearRgba::from(Srgba::rgb_u8(255, 200, 0)).to_f32_array(),
+                glow: 0.0,
             },
         ];
 
-        let circle_instance_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Circle Instance Buffer"),
-                contents: bytemuck::cast_slice(&circle_instances),
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            }
-        );
-
-        let quad_vertex_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Quad Vertex Buffer"),
-                contents: bytemuck::cast_slice(Vertex2D::QUAD_VERTICES.as_slice()),
-                usage: wgpu::BufferUsages::VERTEX,
-            }
-        );
-
-        let quad_index_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Quad Index Buffer"),
-                contents: bytemuck::cast_slice(Vertex2D::QUAD_INDICES.as_slice()),
-                usage: wgpu::BufferUsages::INDEX,
-            }
-        );
-
         let line_vertices = vec![
             LineVertex { position: circle_instances[0].position.into(), color: LinearRgba::from(Srgba::rgb_u8(200, 200, 200)).to_f32_array() },
             LineVertex { position: circle_instances[1].position.into(), color: LinearRgba::from(Srgba::rgb_u8(200, 200, 200)).to_f32_array() },
@@ -386,446 +384,838 @@ impl State {
             LineVertex { position: circle_instances[3].position.into(), color: LinearRgba::from(Srgba::rgb_u8(200, 200, 200)).to_f32_array() },
         ];
 
-        let line_vertex_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Line Vertex Buffer"),
-                contents: bytemuck::cast_slice(&line_vertices),
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            }
-        );
-
-        // --- 高亮线段着色器模块 ---
-        let highlight_lines_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Highlight Lines Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("./shaders/highlight_lines.wgsl").into()),
-        });
-
-        // --- 高亮线段渲染管线 ---
-        let highlight_line_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Highlight Line Render Pipeline"),
-            layout: Some(&render_pipeline_layout), // 共用布局
-            vertex: wgpu::VertexState {
-                module: &highlight_lines_shader_module,
-                entry_point: Some("vs_main"), // 可以是与 lines.wgsl 相同的 vs_main
-                buffers: &[
-                    LineVertex::layout(), // 同样使用 LineVertex 布局
-                ],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &highlight_lines_shader_module,
-                entry_point: Some("fs_main"), // 可以是与 lines.wgsl 相同的 fs_main
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: texture_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList, // 关键：使用 TriangleList
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None, // 双面渲染，因为四边形可能被裁剪
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
-        });
-
-        let highlight_line_vertex_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Highlight Line Vertex Buffer"),
-                contents: bytemuck::cast_slice(&[] as &[LineVertex]), // 初始为空
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            }
-        );
-
-        Ok( Self {
-            surface, device, queue, config, is_surface_configured: false,
-            glyphon_font_system, glyphon_swash_cache, glyphon_viewport,
-            glyphon_atlas, glyphon_renderer, glyphon_buffers,
-            camera, camera_buffer, camera_bind_group, camera_uniform, camera_needs_update: true,
-            line_render_pipeline, circle_render_pipeline,
-            circle_instances, circle_instance_buffer, quad_vertex_buffer, quad_index_buffer,
-            line_vertices, line_vertex_buffer,
-            mouse_current_pos_screen: Vec2::ZERO, is_mouse_left_pressed: false,
+        let line_pick_ids = vec![PICK_ID_NONE; line_vertices.len()]; // 示例线条不可拾取
+        let mut geometry = SceneGeometry { circle_instances, line_vertices, line_pick_ids, ..Default::default() };
+        geometry.cull_circle_instances(None); // 视口确定后由 refresh_node_culling 裁剪
+
+        let renderer = Renderer::new(&device, texture_format, &camera_uniform, &geometry);
+        let gpu_picker = GpuPicker::new(adapter, &device, &renderer.camera_bind_group_layout);
+
+        let mut state = Self {
+            surface, device, queue, config, is_surface_configured: false, is_minimized: false,
+            text_resources: None, node_icon_mapping: NodeIconMapping::default(),
+            camera, camera_uniform, camera_needs_update: true,
+            renderer, geometry,
+            mouse_current_pos_screen: Vec2::ZERO, mouse_canvas_pos_screen: Vec2::ZERO, is_mouse_left_pressed: false,
+            keyboard_modifiers: winit::keyboard::ModifiersState::empty(), keymap: Keymap::default(),
+            mouse_press_pos_screen: Vec2::ZERO,
+            dragged_node: None, layout_history: LayoutHistory::default(),
+            cursor_tracking_enabled: false, last_cursor_report_instant: Instant::now(),
+            pending_notifications: Vec::new(),
+            time_bookmarks: Vec::new(),
+            saved_views: BTreeMap::new(),
+            pending_captures: Vec::new(),
+            recording: None,
+            geometry_update: None,
+            geometry_world_to_pixels: 1.0, // 在 start_geometry_update 中设置
+            topology_export: None,
+            validation_report: ValidationReport::default(),
             last_frame_instant: Instant::now(), frame_count_in_second: 0, current_fps: 0,
+            quality_governor: QualityGovernor::new(),
+            frame_work_start: None,
+            show_stats_overlay: false,
+            show_color_test_pattern: false,
+            background: Background::default(),
+            surface_alpha_modes,
+            service_panel: ServicePanel::default(),
+            timeline_strip: TimelineStrip::new(show_timeline_strip),
+            tooltip: None,
+            overlay_theme: OverlayTheme::default(),
+            label_settings: LabelSettings::default(),
+            service_tooltip_template: ServiceTemplate::default(),
+            renderer_info,
             // --- 新增字段初始化 ---
             all_elements: Vec::new(),
             all_connections: Vec::new(),
             all_events: Vec::new(),
-            num_channels: 80,
+            events_generation: 0,
+            event_checkpoints: EventCheckpoints::default(),
+            channel_plan: ChannelPlan::default(),
+            wavelength_colors: WavelengthColorLut::default(),
+            node_radius: BASE_NODE_RADIUS,
+            node_radius_setting: None,
+            auto_node_radius: false,
+            node_radius_overrides: HashMap::new(),
+            separate_overlapping_nodes: true,
+            node_overlap_groups: Vec::new(),
+            node_overlap_offsets: Vec::new(),
+            edge_bundling_enabled: false,
+            edge_bundling_strength: 1.0,
+            edge_bundles: None,
+            edge_bundling_job: None,
             node_id_to_idx: HashMap::new(),
+            connection_endpoints: Vec::new(),
             current_time_selection: 0.0, // 默认初始时间为 0
+            service_interval: ServiceIntervalSemantics::default(),
             highlight_service_id_list: None,
-            highlight_line_render_pipeline,
-            highlight_line_vertices: Vec::new(),
-            highlight_line_vertex_buffer,
-            highlight_node_color: LinearRgba::from(Srgba::rgb_u8(0xd2, 0xa1, 0x06)).to_f32_array(), // 黄色 40
-            world_text_labels: Vec::new(),
+            highlight_multi_select: false,
+            pick_toggles_highlight: false,
+            pick_screen_pos: Vec2::ZERO,
+            highlight_line_style: HighlightLineStyle::Solid,
+            color_mode: ColorMode::default(),
+            thickness_mode: ThicknessMode::default(),
+            opacity_mode: OpacityMode::default(),
+            animation_start_instant: Instant::now(),
+            highlight_style: HighlightStyle::default(),
+            highlighted_path: None,
+            measurement: MeasurementState::Off,
+            show_capacity_bars: false,
+            capacity_bar_vertices: Vec::new(),
+            capacity_bars_need_update: false,
+            heat_trail_enabled: false,
+            blocked_demands: Vec::new(),
+            show_blocked_demands: true,
+            blocked_demand_window: None,
+            node_groups: NodeGroups::new(),
+            node_group_colors: Vec::new(),
+            hidden_nodes: Vec::new(),
+            annotations: Vec::new(),
+            annotation_placements: Vec::new(),
+            heat_trail_window: None,
+            service_diff: None,
+            flow_animation_enabled: false,
+            flow_dot_instances: Vec::new(),
+            hovered_service_id: None,
+            hover_line_vertices: Vec::new(),
+            inspected_link: None,
+            compare: None,
+            selected_node_idx: None,
+            focused_node_idx: None,
+            selection_accent_color: LinearRgba::from(Srgba::rgb_u8(0x33, 0xb1, 0xff)).to_f32_array(), // 青色 40
+            selection_instances: Vec::new(),
+            selection_needs_update: false,
+            lod_settings: LodSettings::default(),
+            lod_level: LodLevel::Detailed,
+            preview_services: Vec::new(),
             topology_needs_update: false,
-        })
+            gpu_picker,
+            gpu_profiling_enabled: false,
+            gpu_profiler: None,
+            text_rendering_enabled: true,
+            text_area_count: 0,
+            render_once_waiters: Vec::new(),
+            last_picked_entity: None,
+        };
+        // 按初始尺寸配置 surface、文字缓冲区和相机宽高比，第一帧即可绘制，不必等待 Resized 事件
+        let (width, height) = (state.config.width, state.config.height);
+        if width > 0 && height > 0 {
+            state.resize(width, height);
+        } else {
+            log::info!("Initial surface size is 0x0; configuring the surface after the first resize.");
+        }
+        Ok(state)
     }
 
+    /// 尺寸与已配置的相同时不做任何事，因此创建 State 之后再次按初始尺寸调用是安全的。
+    /// 宽或高为 0（最小化、display:none）时进入 is_minimized 状态，期间 render() 直接返回
     pub fn resize(&mut self, width: u32, height: u32) {
-        if width > 0 && height > 0 {
-            log::info!("Resize {}, {}", width, height);
-            self.config.width = width;
-            self.config.height = height;
-            self.surface.configure(&self.device, &self.config);
+        if width == 0 || height == 0 {
+            // 保留原来的配置，恢复时按新尺寸重新配置
+            if self.is_surface_configured && !self.is_minimized {
+                log::debug!("Surface size is 0x0; rendering paused.");
+            }
+            self.is_minimized = self.is_surface_configured;
+            return;
+        }
+        if std::mem::take(&mut self.is_minimized) && width == self.config.width && height == self.config.height {
+            // 恢复到最小化之前的尺寸：部分平台最小化期间 surface 已失效
+            self.reconfigure_surface();
+            return;
+        }
+        if self.is_surface_configured && width == self.config.width && height == self.config.height {
+            return;
+        }
+        log::info!("Resize {}, {}", width, height);
+        self.config.width = width;
+        self.config.height = height;
+        if let Some(surface) = self.surface.as_ref() {
+            surface.configure(&self.device, &self.config);
+        }
 
-            // Update glyphon buffer size
-            for glyphon_buffer in self.glyphon_buffers.iter_mut() {
+        // Update glyphon buffer size
+        if let Some(text) = self.text_resources.as_mut() {
+            for glyphon_buffer in text.label_buffers.iter_mut() {
                 glyphon_buffer.set_size(
-                    &mut self.glyphon_font_system,
+                    &mut text.font_system,
                     Some(width as f32),
                     Some(height as f32),
                 );
-                glyphon_buffer.shape_until_scroll(&mut self.glyphon_font_system, false);
+                glyphon_buffer.shape_until_scroll(&mut text.font_system, false);
             }
+        }
 
-            self.camera.update_aspect_ratio(width, height);
-            self.camera_needs_update = true;
-            self.is_surface_configured = true;
-            // No request_redraw here, it's App's responsibility
+        self.apply_view_size(); // 对比视图中为半幅宽度
+        self.camera_needs_update = true;
+        self.is_surface_configured = true;
+        // No request_redraw here, it's App's responsibility
+    }
+
+    /// 按当前尺寸重新配置 surface（SurfaceError::Lost / Outdated 之后）
+    pub fn reconfigure_surface(&mut self) {
+        if let Some(surface) = self.surface.as_ref().filter(|_| self.is_surface_configured) {
+            surface.configure(&self.device, &self.config);
         }
     }
 
     pub fn update(&mut self) -> bool {
+        self.frame_work_start = Some(Instant::now());
         let mut needs_redraw = false;
 
+        // 流动虚线 / 脉冲动画：仅在有高亮线路时推进时间并持续请求新帧
+        let mut highlight_pulse = 1.0;
+        if self.is_highlight_animating() {
+            let elapsed_time = self.animation_start_instant.elapsed().as_secs_f32() % 3600.0;
+            self.camera_uniform.elapsed_time = elapsed_time;
+            if self.highlight_style.pulse {
+                highlight_pulse = HIGHLIGHT_PULSE_MIN_ALPHA + (1.0 - HIGHLIGHT_PULSE_MIN_ALPHA) * 0.5 * (1.0 + (std::f32::consts::TAU * HIGHLIGHT_PULSE_HZ * elapsed_time).cos());
+            }
+            self.camera_needs_update = true;
+        }
+        // 关闭脉冲或清除高亮后恢复不透明
+        if self.camera_uniform.highlight_pulse != highlight_pulse {
+            self.camera_uniform.highlight_pulse = highlight_pulse;
+            self.camera_needs_update = true;
+        }
+
+        // 平滑缩放：每帧向目标缩放靠近，直到到达目标
+        if self.camera.step_zoom_animation() {
+            self.camera_needs_update = true;
+        }
+
         if self.camera_needs_update {
-            self.camera_uniform.view_proj = self.camera.build_view_projection_matrix().to_cols_array_2d();
-            self.queue.write_buffer(
-                &self.camera_buffer,
-                0,
-                bytemuck::cast_slice(&[self.camera_uniform]),
-            );
+            self.upload_camera_uniform();
+            self.refresh_node_culling(); // 视口移出裁剪范围时重新裁剪节点实例
+            self.refresh_service_hover(); // 强调线的宽度随缩放变化
             self.camera_needs_update = false;
             needs_redraw = true;
+            self.capacity_bars_need_update |= self.show_capacity_bars; // 容量条的大小随缩放变化
+
+            // 缩放变化可能跨越 LOD 阈值，跨越时需要重新生成线路
+            let node_screen_radius = self.camera.world_radius_to_screen_pixels(self.node_radius);
+            let next_lod_level = self.lod_settings.next_level(self.lod_level, node_screen_radius);
+            if next_lod_level != self.lod_level {
+                log::debug!("LOD level changed: {:?} -> {:?} (node radius {:.1}px)", self.lod_level, next_lod_level, node_screen_radius);
+                self.lod_level = next_lod_level;
+                self.topology_needs_update = true;
+            }
+
+            // 按码率加粗的线路宽度在生成时换算为世界单位，缩放变化较大时重新生成以保持像素宽度
+            if self.thickness_mode == ThicknessMode::Bitrate {
+                let zoom_ratio = self.camera.world_radius_to_screen_pixels(1.0) / self.geometry_world_to_pixels;
+                if !(1.0 / THICKNESS_REGENERATE_ZOOM_RATIO..=THICKNESS_REGENERATE_ZOOM_RATIO).contains(&zoom_ratio) {
+                    self.topology_needs_update = true;
+                }
+            }
         }
         
         // 如果拓扑（主要是服务线路）需要更新
         if self.topology_needs_update {
             log::debug!("Updating topology due to time change or initial load. Time: {}", self.current_time_selection);
-            self.generate_all_lines_for_current_time();
-            self.update_gpu_buffers(); // Upload new line vertices to GPU
+            self.refresh_edge_bundling(); // 节点位置变化后重新计算绑定，完成前按直线绘制
+            self.service_panel.rows_need_update = true; // 面板显示时由 start_geometry_update 用同一次重建的服务填充
+            self.start_geometry_update(); // 大型拓扑分多帧完成，见 geometry_update.rs
+            self.update_compare_geometry();
             self.topology_needs_update = false;
             needs_redraw = true; // Request redraw to show updated lines
+            self.selection_needs_update = true; // 节点颜色可能已被高亮改变
+        } else if self.is_geometry_update_pending() {
+            self.advance_geometry_update();
+            needs_redraw = true; // 完成时显示新的几何，未完成时继续请求新帧
+        }
+
+        // 服务列表面板只在显示时重新生成
+        if self.service_panel.visible && self.service_panel.rows_need_update {
+            self.refresh_service_panel_rows();
+            needs_redraw = true;
+        }
+        self.refresh_timeline_histogram();
+
+        // 边绑定分多帧计算，完成时重新生成线路
+        if self.is_edge_bundling_pending() {
+            self.advance_edge_bundling();
+            needs_redraw = true;
+        }
+
+        // 大型拓扑的导出分多帧完成，未完成时继续请求新帧
+        if self.is_topology_export_pending() {
+            self.advance_topology_export();
+            needs_redraw = true;
+        }
+
+        // 流动动画每帧推进；关闭后再更新一次以清除圆点
+        if self.is_flow_animation_active() || !self.flow_dot_instances.is_empty() {
+            self.update_flow_dots();
+            needs_redraw = true;
+        }
+
+        // 等待拾取纹素回读完成
+        if let Some(gpu_picker) = self.gpu_picker.as_mut() && gpu_picker.is_pending() {
+            if let Some(pick_id) = gpu_picker.poll_result(&self.device) {
+                let entity = decode_pick_id(pick_id, &self.geometry.pick_segments, &self.geometry.visible_circle_indices);
+                self.apply_pick_result(entity);
+            }
+            needs_redraw = true; // 继续轮询，直到回读完成
+        }
+
+        // 等待 GPU 计时回读完成
+        if self.poll_gpu_profiler() {
+            needs_redraw = true;
+        }
+
+        // 等待截图回读完成
+        if self.poll_frame_captures() {
+            needs_redraw = true;
+        }
+
+        if self.capacity_bars_need_update {
+            self.update_capacity_bars();
+            self.capacity_bars_need_update = false;
+            needs_redraw = true;
+        }
+
+        // 选中节点变化时只更新选中实例，不重新生成线路
+        if self.selection_needs_update {
+            self.update_selection_instances();
+            self.selection_needs_update = false;
+            needs_redraw = true;
+        }
+
+        // 录制：等待上一帧回读、推进时间或截取下一帧
+        if self.advance_recording() {
+            needs_redraw = true;
         }
 
         needs_redraw
     }
 
-    pub fn update_gpu_buffers(&mut self) {
-        let circle_data = bytemuck::cast_slice(&self.circle_instances);
-        let line_data = bytemuck::cast_slice(&self.line_vertices);
-
-        // (Re)create circle instance buffer if size changes, otherwise write
-        if self.circle_instance_buffer.size() < circle_data.len() as u64 {
-            self.circle_instance_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Circle Instance Buffer (Resized)"),
-                contents: circle_data,
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            });
-        } else {
-            self.queue.write_buffer(&self.circle_instance_buffer, 0, circle_data);
+    /// 拾取屏幕坐标处的对象。GPU 拾取是异步的，结果在之后的 update() 中处理。
+    pub fn request_pick(&mut self, screen_pos: Vec2) {
+        if screen_pos.x < 0.0 || screen_pos.y < 0.0 {
+            return;
         }
+        self.pick_toggles_highlight = self.keyboard_modifiers.control_key() || self.keyboard_modifiers.super_key();
+        self.pick_screen_pos = screen_pos;
+        let size = self.view_size(); // 对比视图中按左半部分拾取
+        let Some(gpu_picker) = self.gpu_picker.as_mut() else {
+            let entity = self.cpu_pick(screen_pos);
+            self.apply_pick_result(entity);
+            return;
+        };
 
-        // (Re)create line vertex buffer if size changes, otherwise write
-        if self.line_vertex_buffer.size() < line_data.len() as u64 {
-            self.line_vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Line Vertex Buffer (Resized)"),
-                contents: line_data,
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            });
-        } else {
-            self.queue.write_buffer(&self.line_vertex_buffer, 0, line_data);
+        let scene = self.renderer.pick_scene(&self.geometry);
+        if !gpu_picker.request_pick(&self.device, &self.queue, &scene, size, screen_pos.x as u32, screen_pos.y as u32) {
+            log::debug!("Pick at {:?} ignored: a previous pick is still pending or the position is outside the surface.", screen_pos);
         }
+    }
 
-        let highlight_line_data = bytemuck::cast_slice(&self.highlight_line_vertices);
-        if self.highlight_line_vertex_buffer.size() < highlight_line_data.len() as u64 {
-            self.highlight_line_vertex_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Highlight Line Vertex Buffer (Resized)"),
-                contents: highlight_line_data,
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            });
+    /// 光标形状：拖动时为 Grabbing，悬停在可点击的节点、服务线段或链路上为 Pointer，空白处为 Grab（可平移）。
+    /// 悬停检测使用 CPU 命中测试，只在光标移动或按键时调用
+    pub fn cursor_icon(&self) -> CursorIcon {
+        if self.dragged_node.is_some() || self.camera.is_panning() {
+            CursorIcon::Grabbing
+        } else if self.timeline_strip.scrubbing || self.timeline_strip_contains(self.mouse_canvas_pos_screen) {
+            CursorIcon::ColResize
+        } else if self.annotation_at(self.mouse_current_pos_screen).is_some() {
+            CursorIcon::Pointer
+        } else if self.service_panel_contains(self.mouse_canvas_pos_screen) {
+            if self.service_panel_service_at(self.mouse_canvas_pos_screen).is_some() { CursorIcon::Pointer } else { CursorIcon::Default }
+        } else if self.cpu_pick(self.mouse_current_pos_screen).is_some() || self.connection_at(self.mouse_current_pos_screen).is_some() {
+            CursorIcon::Pointer
         } else {
-            self.queue.write_buffer(&self.highlight_line_vertex_buffer, 0, highlight_line_data);
+            CursorIcon::Grab
         }
     }
 
-    // Helper to generate a thick line (quad) from two points
-    fn add_thick_line_segment(
-        &mut self,
-        start_pos: Vec2,
-        end_pos: Vec2,
-        color: [f32; 4],
-        thickness: f32, // 世界单位厚度
-    ) {
-        let dir = end_pos - start_pos;
-        let length = dir.length();
-
-        if length < f32::EPSILON {
-            return; // Avoid division by zero for zero-length lines
+    /// CPU 命中测试：与 GPU 拾取的绘制顺序一致，线段（后绘制）优先于节点
+    fn cpu_pick(&self, screen_pos: Vec2) -> Option<PickedEntity> {
+        if let Some(segment) = self.service_segment_at(screen_pos) {
+            return Some(PickedEntity::ServiceSegment { service_id: segment.service_id, segment_index: segment.segment_index });
         }
+        self.node_at_world_pos(self.camera.screen_to_world(screen_pos)).map(PickedEntity::Node)
+    }
 
-        let normalized_dir = dir.normalize();
-        let perpendicular_dir = Vec2::new(-normalized_dir.y, normalized_dir.x); // 旋转90度
+    /// 光标附近（PICK_TOLERANCE_PX 像素内）最近的服务线段，使用 pick_segment_grid 查找
+    pub fn service_segment_at(&self, screen_pos: Vec2) -> Option<&PickSegment> {
+        const PICK_TOLERANCE_PX: f32 = 3.0;
+        let world_pos = self.camera.screen_to_world(screen_pos);
+        let tolerance = PICK_TOLERANCE_PX / self.camera.world_radius_to_screen_pixels(1.0);
+        self.geometry.pick_segment_grid
+            .nearest(&self.geometry.pick_segments, world_pos, tolerance)
+            .map(|index| &self.geometry.pick_segments[index])
+    }
 
-        let half_thickness_offset = perpendicular_dir * (thickness / 2.0); // 注意：厚度需要反比例于缩放，以在屏幕上保持一致的像素宽度
+    pub fn node_at_world_pos(&self, world_pos: Vec2) -> Option<usize> {
+        // 后绘制的节点在上层，因此逆序查找；隐藏的节点不能拾取
+        self.geometry.circle_instances
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(idx, _)| !self.is_node_hidden(*idx))
+            .find(|(_, instance)| world_pos.distance(Vec2::from_array(instance.position)) <= instance.radius_scale)
+            .map(|(idx, _)| idx)
+    }
 
-        let p1_minus_offset = start_pos - half_thickness_offset;
-        let p1_plus_offset = start_pos + half_thickness_offset;
-        let p2_plus_offset = end_pos + half_thickness_offset;
-        let p2_minus_offset = end_pos - half_thickness_offset;
+    pub fn begin_node_drag(&mut self, idx: usize) {
+        if let Some(instance) = self.geometry.circle_instances.get(idx) {
+            self.dragged_node = Some((idx, Vec2::from_array(instance.position)));
+        }
+    }
 
-        // 添加构成两个三角形的六个顶点
-        self.highlight_line_vertices.push(LineVertex { position: p1_minus_offset.into(), color });
-        self.highlight_line_vertices.push(LineVertex { position: p1_plus_offset.into(), color });
-        self.highlight_line_vertices.push(LineVertex { position: p2_plus_offset.into(), color }); // Triangle 1: (p1-, p1+, p2+)
+    pub fn drag_node_to(&mut self, world_pos: Vec2) {
+        if let Some((idx, _)) = self.dragged_node {
+            self.move_node(idx, world_pos);
+        }
+    }
 
-        self.highlight_line_vertices.push(LineVertex { position: p1_minus_offset.into(), color });
-        self.highlight_line_vertices.push(LineVertex { position: p2_plus_offset.into(), color });
-        self.highlight_line_vertices.push(LineVertex { position: p2_minus_offset.into(), color }); // Triangle 2: (p1-, p2+, p2-)
+    /// 结束拖动；节点确实移动过时记录一条撤销条目
+    pub fn end_node_drag(&mut self) {
+        let Some((idx, old_position)) = self.dragged_node.take() else {
+            return;
+        };
+        let new_position = Vec2::from_array(self.geometry.circle_instances[idx].position);
+        if new_position != old_position && let Some(element) = self.all_elements.get(idx) {
+            self.layout_history.record(LayoutEdit { element_id: element.element_id.clone(), old_position, new_position });
+        }
     }
 
-    /// 根据当前时间轴选择，重新生成所有链接和服务的线条。
-    fn generate_all_lines_for_current_time(&mut self) {
-        self.line_vertices.clear();
-        self.highlight_line_vertices.clear(); // 清除高亮线条数据
-
-        let radius_inside = BASE_NODE_RADIUS;
-        const LINK_BOUNDARY_ROTATE_ANGLE: f32 = std::f32::consts::PI / 16.0;
-        const HIGHLIGHT_LINE_THICKNESS: f32 = 0.5; // 世界单位厚度
-        const NORMAL_LINE_COLOR: [f32; 4] = [0.784, 0.784, 0.784, 1.0]; // 灰色，从 Srgba::rgb_u8(200, 200, 200).to_f32_array()
-
-        // 追踪所有被高亮服务触及的节点ID
-        let mut nodes_in_highlighted_services: std::collections::HashSet<String> = std::collections::HashSet::new();
-        if let Some(ref highlight_ids) = self.highlight_service_id_list {
-            let reconstructed_service_dict = reconstruct_state_at_time(&self.all_events, self.current_time_selection);
-            for service_id in highlight_ids {
-                if let Some(service) = reconstructed_service_dict.get(service_id) {
-                    // Collect all nodes in path for highlighting
-                    for node_id in &service.path {
-                        nodes_in_highlighted_services.insert(node_id.clone());
-                    }
-                }
-            }
+    /// 以世界坐标设置节点位置并记录撤销条目
+    pub fn set_node_position(&mut self, element_id: &str, world_pos: Vec2) -> bool {
+        let Some(&idx) = self.node_id_to_idx.get(element_id) else {
+            return false;
+        };
+        let old_position = Vec2::from_array(self.geometry.circle_instances[idx].position);
+        self.move_node(idx, world_pos);
+        self.layout_history.record(LayoutEdit { element_id: element_id.to_string(), old_position, new_position: world_pos });
+        true
+    }
+
+    pub fn undo_layout_edit(&mut self) -> bool {
+        match self.layout_history.undo() {
+            Some(edit) => self.apply_layout_edit(&edit.element_id, edit.old_position),
+            None => false,
         }
+    }
 
-        // --- 1. 更新节点颜色 ---
-        // 首先恢复所有节点为默认颜色
-        for instance in self.circle_instances.iter_mut() {
-            instance.color = LinearRgba::from(Srgba::rgb_u8(0x00, 0x5d, 0x5d)).to_f32_array();
+    pub fn redo_layout_edit(&mut self) -> bool {
+        match self.layout_history.redo() {
+            Some(edit) => self.apply_layout_edit(&edit.element_id, edit.new_position),
+            None => false,
         }
-        // 然后根据高亮列表重新着色
-        for (node_id, &instance_idx) in &self.node_id_to_idx {
-            if nodes_in_highlighted_services.contains(node_id) {
-                self.circle_instances[instance_idx].color = self.highlight_node_color;
+    }
+
+    fn apply_layout_edit(&mut self, element_id: &str, world_pos: Vec2) -> bool {
+        match self.node_id_to_idx.get(element_id) {
+            Some(&idx) => {
+                self.move_node(idx, world_pos);
+                true
+            }
+            None => {
+                log::warn!("Layout edit references unknown node {}.", element_id);
+                false
             }
         }
+    }
 
+    /// 当前时间轴时刻活跃的服务，按 service_id 排序
+    pub fn active_services_at_current_time(&self) -> Vec<ServiceData> {
+        self.active_services_at(self.current_time_selection)
+    }
 
-        // --- 2. 渲染固定的链路边界 (普通细线) ---
-        for link in &self.all_connections {
-            if let (Some(&source_idx), Some(&target_idx)) = (
-                self.node_id_to_idx.get(&link.from_node),
-                self.node_id_to_idx.get(&link.to_node),
-            ) {
-                let link_boundary_color = LinearRgba::from(Srgba::rgb_u8(180, 180, 180));
-                let source_position_center = Vec2::from_array(self.circle_instances[source_idx].position);
-                let destination_position_center = Vec2::from_array(self.circle_instances[target_idx].position);
-                let dir_vec = destination_position_center - source_position_center;
-                let length = dir_vec.length();
+    /// 任意时刻活跃的服务，按 service_id 排序；不改变 current_time_selection
+    pub fn active_services_at(&self, time: f64) -> Vec<ServiceData> {
+        let mut active_services: Vec<ServiceData> = reconstruct_state_at_time(&self.all_events, time, self.service_interval)
+            .into_values()
+            .filter(|service| self.service_interval.contains(service.arrival_time, service.departure_time, time))
+            .collect();
+        active_services.sort_by_key(|service| service.service_id);
+        active_services
+    }
 
-                if length < f32::EPSILON {
-                    continue;
-                }
+    /// 把时间移动到 current_time_selection 之后（Forward）或之前（Backward）最近的事件，
+    /// filter 为 Some 时只考虑该类型的事件。时间恰好落在事件时间戳上：该事件在此刻已经生效。
+    /// 没有更多事件时保持时间不变并返回 None
+    pub fn step_to_event(&mut self, direction: StepDirection, filter: Option<EventKind>) -> Option<AnyEvent> {
+        let current_time = self.current_time_selection;
+        let matches_filter = |event: &&AnyEvent| filter.is_none_or(|kind| event.kind() == kind);
+        let target = match direction {
+            StepDirection::Forward => {
+                let first_after = self.all_events.partition_point(|event| event.timestamp() <= current_time);
+                self.all_events[first_after..].iter().find(matches_filter)
+            }
+            StepDirection::Backward => {
+                let first_not_before = self.all_events.partition_point(|event| event.timestamp() < current_time);
+                self.all_events[..first_not_before].iter().rev().find(matches_filter)
+            }
+        }?.clone();
 
-                let normalized_dir = dir_vec.normalize();
-                let radius_dir_outward = normalized_dir * radius_inside;
+        log::debug!("Stepped {:?} to {:?} event at {}", direction, target.kind(), target.timestamp());
+        self.set_time_selection(target.timestamp(), TimeChangeReason::Step);
+        self.highlight_service_id_list = None; // 跳数标签在重新生成线路时一起清除
+        Some(target)
+    }
 
-                let rotate_vector = Vec2::from_angle(LINK_BOUNDARY_ROTATE_ANGLE);
-                let reverse_rotate_vector = Vec2::from_angle(-LINK_BOUNDARY_ROTATE_ANGLE);
+    /// 复制导出 SVG 所需的几何数据；bounds 为 Topology 但没有节点时退回到当前视图
+    pub fn svg_snapshot(&self, bounds: SvgBounds) -> SvgSnapshot {
+        let view_bounds = self.camera.get_world_clip_bounds();
+        let bounds = match bounds {
+            SvgBounds::View => view_bounds,
+            SvgBounds::Topology => SvgSnapshot::topology_bounds(&self.geometry.circle_instances).unwrap_or(view_bounds),
+        };
+        SvgSnapshot::new(&self.geometry, bounds, self.camera.world_radius_to_screen_pixels(1.0))
+    }
 
-                self.line_vertices.push(LineVertex {
-                    position: (source_position_center + radius_dir_outward.rotate(rotate_vector)).into(),
-                    color: link_boundary_color.to_f32_array(),
-                });
-                self.line_vertices.push(LineVertex {
-                    position: (destination_position_center - radius_dir_outward.rotate(reverse_rotate_vector)).into(),
-                    color: link_boundary_color.to_f32_array(),
-                });
+    /// 批量统计多个时刻的活跃服务数，结果与 `times` 一一对应
+    pub fn active_service_counts(&self, times: &[f64]) -> Vec<u32> {
+        count_active_services_at_times(&self.all_events, times, self.service_interval)
+    }
 
-                self.line_vertices.push(LineVertex {
-                    position: (source_position_center + radius_dir_outward.rotate(reverse_rotate_vector)).into(),
-                    color: link_boundary_color.to_f32_array(),
-                });
-                self.line_vertices.push(LineVertex {
-                    position: (destination_position_center - radius_dir_outward.rotate(rotate_vector)).into(),
-                    color: link_boundary_color.to_f32_array(),
-                });
-            } else {
-                log::warn!("Link references non-existent node ID. Source: {}, Target: {}", link.from_node, link.to_node);
-            }
+    /// 替换节点和链路。缺少位置的节点放在已连接的邻居附近（或后备网格中），元数据本身保持不变。
+    /// 已加载的事件只在其路径中的节点全部仍然存在时保留，否则清空并记录到验证报告中。
+    pub fn apply_topology_structure(&mut self, elements: Vec<ElementData>, connections: Vec<ConnectionData>) {
+        self.finish_topology_export(); // 未完成的导出针对旧拓扑
+        self.node_id_to_idx = elements
+            .iter()
+            .enumerate()
+            .map(|(i, element)| (element.element_id.clone(), i))
+            .collect();
+
+        self.all_elements = elements;
+        self.all_connections = connections;
+        self.validation_report = ValidationReport::default();
+
+        let mut locations: Vec<Option<Vec2>> = self.all_elements
+            .iter()
+            .map(|element| element.metadata.location.as_ref().map(|location| Vec2::new(location.x, location.y)))
+            .collect();
+        for element in self.all_elements.iter().filter(|element| element.metadata.location.is_none()) {
+            self.validation_report.warn(
+                "missing_location",
+                format!("Element {} has no metadata.location and was placed automatically.", element.element_id),
+                Some(element.element_id.clone()),
+            );
         }
+        let element_ids: Vec<String> = self.all_elements.iter().map(|element| element.element_id.clone()).collect();
+        fill_missing_locations(&element_ids, &mut locations, &self.all_connections);
 
-        // --- 3. 渲染当前时间活跃的服务线条 ---
-        let num_channels = self.num_channels;
-        const SERVICE_MAX_SPREAD_ANGLE: f32 = LINK_BOUNDARY_ROTATE_ANGLE * 0.95;
+        // 初始化（或重置）所有节点的默认颜色
+        let default_node_color = default_node_color();
+        self.geometry.circle_instances = locations
+            .iter()
+            .map(|location| {
+                let location = location.unwrap_or(Vec2::ZERO);
+                CircleInstance {
+                    position: [location.x, -location.y],
+                    radius_scale: self.node_radius * NODE_INSTANCE_RADIUS_FACTOR, // 由下面的 update_auto_node_radius 设置
+                    color: default_node_color, // 初始颜色
+                    glow: 0.0,
+                }
+            })
+            .collect();
+        self.node_overlap_groups.clear(); // 属于旧拓扑的节点索引，新的节点实例还没有偏移
+        self.node_overlap_offsets.clear();
+        self.update_auto_node_radius();
+        self.detect_overlapping_nodes();
+
+        if let Some(unknown_node_id) = self.first_unknown_event_node() {
+            self.validation_report.warn(
+                "events_cleared",
+                format!("Cleared {} timeline events: node {} is not in the new topology structure.", self.all_events.len(), unknown_node_id),
+                Some(unknown_node_id),
+            );
+            self.all_events.clear();
+            self.mark_events_changed();
+            self.highlight_service_id_list = None;
+            self.service_diff = None;
+        }
 
-        let reconstructed_service_dict = reconstruct_state_at_time(&self.all_events, self.current_time_selection);
+        self.geometry.line_vertices.clear();
+        self.geometry.highlight_line_vertices.clear(); // 清空高亮线条
+        self.geometry.hop_labels.clear();
+        self.geometry.node_labels.clear(); // 属于旧拓扑的节点
+
+        self.topology_needs_update = true;
+        self.selected_node_idx = None; // 旧拓扑的节点索引已失效
+        self.highlighted_path = None;
+        self.inspect_link(None); // 链路索引属于旧拓扑
+        self.measurement = MeasurementState::Off;
+        self.dragged_node = None;
+        self.layout_history.clear();
+        if self.focused_node_idx.take().is_some() {
+            self.pending_notifications.push(ViewNotification::NodeFocused { node_id: None });
+        }
+        self.selection_needs_update = true;
+        self.resolve_topology_indices();
+        self.fit_view_to_topology();
+    }
 
-        for service in reconstructed_service_dict.values() {
-            let departure_time = service.departure_time;
-            // 检查服务是否在当前时间活跃
-            if self.current_time_selection >= service.arrival_time && self.current_time_selection < departure_time {
-                let wavelength = service.wavelength;
-                let effective_wavelength = (wavelength as f32).min((num_channels - 1) as f32);
+    /// all_events 被替换、合并、清空或过滤之后调用：依赖事件下标的缓存（回放检查点）随之失效
+    pub fn mark_events_changed(&mut self) {
+        self.events_generation += 1;
+    }
 
-                let hue_color = (effective_wavelength + 0.5) / (num_channels as f32) * 180.0 + 30.0;
+    /// 替换时间轴事件并把时间重置为 0；节点和链路保持不变。
+    /// 事件相关的验证问题（代码以 events_ 开头）会被重新生成，结构相关的问题保留；
+    /// 服务路径少于 2 个节点的事件被丢弃（见 validate_timeline_events）。
+    pub fn apply_timeline_events(&mut self, defrag_timeline_events: Vec<AnyEvent>) {
+        self.finish_topology_export();
+        self.all_events = defrag_timeline_events;
+        self.service_diff = None; // 差异针对旧的时间线
+        self.time_bookmarks.clear(); // 书签针对旧的时间线
+        self.validation_report.issues.retain(|issue| !issue.code.starts_with("events_"));
+        validate_timeline_events(&mut self.all_events, self.channel_plan.max_wavelengths, &mut self.validation_report);
+        self.mark_events_changed();
+
+        if self.all_elements.is_empty() && !self.all_events.is_empty() {
+            self.validation_report.warn(
+                "events_without_structure",
+                format!("{} timeline events were set before any topology structure; service paths cannot be drawn.", self.all_events.len()),
+                None,
+            );
+        } else if let Some(unknown_node_id) = self.first_unknown_event_node() {
+            self.validation_report.warn(
+                "events_unknown_node",
+                format!("Timeline events reference node {}, which is not in the topology; those hops are skipped.", unknown_node_id),
+                Some(unknown_node_id),
+            );
+        }
 
-                let is_highlighted = match &self.highlight_service_id_list {
-                    Some(highlight_service_id_list) => highlight_service_id_list.iter().any(|&srv_id| srv_id == service.service_id),
-                    None => false,
-                };
+        self.resolve_topology_indices();
+        self.topology_needs_update = true;
+        if self.current_time_selection != 0.0 {
+            self.set_time_selection(0.0, TimeChangeReason::Reset); // Reset time to 0
+        }
+        self.highlight_service_id_list = None; // Clear highlight
+    }
 
-                let service_color_oklcha = if is_highlighted {
-                    // 高亮服务的颜色可以更鲜明，例如保持高饱和度，但亮度适中，或者采用完全不同的颜色
-                    Oklcha::lch(0.75, 0.2, hue_color) // 更亮的颜色
-                } else {
-                    if self.highlight_service_id_list.iter().len() == 0{
-                        Oklcha::lch(0.6, 0.11, hue_color)
-                    }
-                    else {
-                        Oklcha::lch(0.4, 0.11, hue_color)
-                    }
-                };
-                let service_color_f32 = LinearRgba::from(service_color_oklcha).to_f32_array();
-                // 如果不是高亮服务，亮度调整回默认的0.6。
-                // `service_color_f32` will be determined by `is_highlighted`.
-
-                let normalized_wavelength_factor = (effective_wavelength - ((num_channels as f32 - 1.0) / 2.0)) / ((num_channels as f32 - 1.0) / 2.0);
-                let wavelength_rotate_angle = normalized_wavelength_factor * SERVICE_MAX_SPREAD_ANGLE;
-
-                for i in 0..(service.path.len() - 1) {
-                    let source_node_id = &service.path[i];
-                    let target_node_id = &service.path[i + 1];
-
-                    if let (Some(&source_idx), Some(&target_idx)) = (
-                        self.node_id_to_idx.get(source_node_id),
-                        self.node_id_to_idx.get(target_node_id),
-                    ) {
-                        let source_pos_center = Vec2::from_array(self.circle_instances[source_idx].position);
-                        let target_pos_center = Vec2::from_array(self.circle_instances[target_idx].position);
-
-                        let dir_vec = target_pos_center - source_pos_center;
-                        let length = dir_vec.length();
-
-                        if length < f32::EPSILON {
-                            continue;
-                        }
-
-                        let normalized_dir = dir_vec.normalize();
-                        let radius_vec_along_link = normalized_dir * radius_inside;
-
-                        let upward_sacle: f32 = if normalized_dir.y >= 0.0 { 1.0 } else { -1.0 };
-                        let service_start_pos = source_pos_center + radius_vec_along_link.rotate(Vec2::from_angle(wavelength_rotate_angle * upward_sacle));
-                        let service_end_pos = target_pos_center - radius_vec_along_link.rotate(Vec2::from_angle( - wavelength_rotate_angle * upward_sacle));
-
-                        if is_highlighted {
-                            self.add_thick_line_segment(service_start_pos, service_end_pos, service_color_f32, HIGHLIGHT_LINE_THICKNESS);
-                            self.world_text_labels.push(TextLabel { content: format!("{}", i), radius_scale: BASE_NODE_RADIUS, position: source_pos_center.into() });
-                            if i == service.path.len() - 2 {
-                                self.world_text_labels.push(TextLabel { content: format!("{}", i + 1), radius_scale: BASE_NODE_RADIUS, position: target_pos_center.into() });
-                            }
-                        } else {
-                            self.line_vertices.push(LineVertex { position: service_start_pos.into(), color: service_color_f32 });
-                            self.line_vertices.push(LineVertex { position: service_end_pos.into(), color: service_color_f32 });
-                        }
-                    } else {
-                        log::warn!(
-                            "Service {} path references non-existent node ID. Segment: {} -> {}",
-                            service.service_id, source_node_id, target_node_id
-                        );
-                    }
+    /// 事件路径中第一个不在当前拓扑中的节点 ID
+    pub fn first_unknown_event_node(&self) -> Option<String> {
+        self.all_events
+            .iter()
+            .filter_map(|event| event.service())
+            .flat_map(|service| service.path.iter())
+            .find(|node_id| !self.node_id_to_idx.contains_key(node_id.as_str()))
+            .cloned()
+    }
+
+    pub fn export_layout(&self) -> NodeLayout {
+        self.node_id_to_idx
+            .iter()
+            .map(|(element_id, &idx)| {
+                (element_id.clone(), LayoutPosition::from_world(self.raw_node_position(idx)))
+            })
+            .collect()
+    }
+
+    /// 应用导入的布局；未知的节点 ID 被忽略并计数。导入会清空撤销历史。
+    pub fn import_layout(&mut self, layout: &NodeLayout) -> LayoutImportSummary {
+        let mut summary = LayoutImportSummary { applied: 0, unknown_ids: 0 };
+        for (element_id, position) in layout {
+            match self.node_id_to_idx.get(element_id) {
+                Some(&idx) => {
+                    self.geometry.circle_instances[idx].position = position.to_world().into();
+                    self.clear_overlap_offset(idx);
+                    summary.applied += 1;
                 }
+                None => summary.unknown_ids += 1,
+            }
+        }
+        if summary.unknown_ids > 0 {
+            log::warn!("Layout import ignored {} unknown node ID(s).", summary.unknown_ids);
+        }
+        self.dragged_node = None;
+        self.layout_history.clear();
+        self.topology_needs_update = true;
+        summary
+    }
 
-                // Processing the segments inside the circle (if any)
-                for i in 0..(service.path.len() - 2) {
-                    let source_node_id = &service.path[i];
-                    let middle_node_id = &service.path[i + 1];
-                    let target_node_id = &service.path[i + 2];
-
-                    if let (Some(&source_idx), Some(&middle_idx), Some(&target_idx)) = (
-                        self.node_id_to_idx.get(source_node_id),
-                        self.node_id_to_idx.get(middle_node_id),
-                        self.node_id_to_idx.get(target_node_id),
-                    ) {
-                        let source_pos_center = Vec2::from_array(self.circle_instances[source_idx].position);
-                        let middle_pos_center = Vec2::from_array(self.circle_instances[middle_idx].position);
-                        let target_pos_center = Vec2::from_array(self.circle_instances[target_idx].position);
-
-                        let source_middle_dir_vec = target_pos_center - middle_pos_center;
-                        let middle_target_dir_vec = middle_pos_center - source_pos_center;
-
-                        let normalized_source_middle_dir = source_middle_dir_vec.normalize();
-                        let normalized_middle_target_dir = middle_target_dir_vec.normalize();
-
-                        let radius_source_middle_vec_along_link = normalized_source_middle_dir * radius_inside;
-                        let radius_middle_target_vec_along_link = normalized_middle_target_dir * radius_inside;
-
-                        let source_middle_upward_sacle: f32 = if normalized_source_middle_dir.y >= 0.0 { 1.0 } else { -1.0 };
-                        let middle_target_upward_sacle: f32 = if normalized_middle_target_dir.y >= 0.0 { 1.0 } else { -1.0 };
-
-                        let middle_start_pos = middle_pos_center + radius_source_middle_vec_along_link.rotate(Vec2::from_angle(wavelength_rotate_angle * source_middle_upward_sacle));
-                        let middle_end_pos = middle_pos_center - radius_middle_target_vec_along_link.rotate(Vec2::from_angle( - wavelength_rotate_angle * middle_target_upward_sacle));
-
-                        if is_highlighted {
-                            self.add_thick_line_segment(middle_start_pos, middle_end_pos, service_color_f32, HIGHLIGHT_LINE_THICKNESS);
-                        } else {
-                            self.line_vertices.push(LineVertex { position: middle_start_pos.into(), color: service_color_f32 });
-                            self.line_vertices.push(LineVertex { position: middle_end_pos.into(), color: service_color_f32 });
-                        }
-                    } else {
-                        log::warn!(
-                            "Service {} path references non-existent node ID. Segment: {} -> {} -> {}",
-                            service.service_id, source_node_id, middle_node_id, target_node_id
-                        );
-                    }
+    fn move_node(&mut self, idx: usize, world_pos: Vec2) {
+        self.geometry.circle_instances[idx].position = world_pos.into();
+        self.clear_overlap_offset(idx);
+        self.topology_needs_update = true; // 重新生成相连的链路和服务线路，并上传节点实例
+    }
+
+    /// 光标移动时调用（仅在开启光标跟踪时），最多约 30 Hz 报告一次
+    pub fn report_cursor_position(&mut self) {
+        const CURSOR_REPORT_INTERVAL_SECS: f32 = 1.0 / 30.0;
+        if self.last_cursor_report_instant.elapsed().as_secs_f32() < CURSOR_REPORT_INTERVAL_SECS {
+            return;
+        }
+        self.last_cursor_report_instant = Instant::now();
+
+        let world_pos = self.camera.screen_to_world(self.mouse_current_pos_screen);
+        let hovered_node_id = self.node_at_world_pos(world_pos)
+            .and_then(|idx| self.all_elements.get(idx))
+            .map(|element| element.element_id.clone());
+        self.pending_notifications.push(ViewNotification::CursorMoved {
+            x: world_pos.x,
+            y: world_pos.y,
+            hovered_node_id,
+        });
+    }
+
+    /// 修改时间轴选中的时刻并通知 JS。同一批通知中只保留最后一次时间变化，
+    /// 连续修改时间（例如逐帧推进）时每次分发最多发送一个 TimeChanged
+    pub fn set_time_selection(&mut self, time: f64, reason: TimeChangeReason) {
+        self.current_time_selection = time;
+        self.topology_needs_update = true;
+        self.pending_notifications.retain(|notification| !matches!(notification, ViewNotification::TimeChanged { .. }));
+        self.pending_notifications.push(ViewNotification::TimeChanged { time, reason });
+        self.refresh_inspected_link();
+    }
+
+    pub fn take_notifications(&mut self) -> Vec<ViewNotification> {
+        std::mem::take(&mut self.pending_notifications)
+    }
+
+    fn apply_pick_result(&mut self, entity: Option<PickedEntity>) {
+        match entity {
+            Some(PickedEntity::Node(idx)) => {
+                if let Some(element) = self.all_elements.get(idx) {
+                    log::info!("Picked node {} ({})", element.element_id, element.node_type);
                 }
+                self.selected_node_idx = Some(idx);
+                self.add_measurement_endpoint(idx);
+                self.inspect_link(None);
+            }
+            Some(PickedEntity::ServiceSegment { service_id, segment_index }) => {
+                log::info!("Picked service {} segment {}", service_id, segment_index);
+                self.select_service(service_id, self.pick_toggles_highlight);
+                self.inspect_link(None);
+            }
+            None => {
+                self.selected_node_idx = None; // 点击空白处取消选中
+                self.inspect_link(self.connection_at(self.pick_screen_pos)); // 点在链路上时列出经过它的服务
             }
         }
+        self.selection_needs_update = true;
+        self.last_picked_entity = entity;
     }
 
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        if !self.is_surface_configured {
-            return Ok(());
+    fn update_selection_instances(&mut self) {
+        self.selection_instances.clear();
+        let mut ring_node_indices = self.selected_node_idx.into_iter().collect::<Vec<_>>();
+        if self.focused_node_idx != self.selected_node_idx {
+            ring_node_indices.extend(self.focused_node_idx);
         }
+        for idx in ring_node_indices {
+            let Some(node) = self.geometry.circle_instances.get(idx) else {
+                continue;
+            };
+            // 外圈：1.3 倍半径的强调色圆，先绘制，被下方放大的节点副本覆盖后只剩一圈
+            self.selection_instances.push(CircleInstance {
+                radius_scale: node.radius_scale * SELECTION_RING_RADIUS_FACTOR,
+                color: self.selection_accent_color,
+                glow: 0.0,
+                ..*node
+            });
+            self.selection_instances.push(CircleInstance {
+                radius_scale: node.radius_scale * SELECTION_NODE_RADIUS_FACTOR,
+                ..*node
+            });
+        }
+        self.renderer.upload_selection_instances(&self.queue, &self.selection_instances);
+        if let Some(compare) = &self.compare {
+            compare.renderer.upload_selection_instances(&self.queue, &self.selection_instances);
+        }
+    }
 
-        // Update glyphon viewport
-        let width = self.config.width;
-        let height = self.config.height;
+    /// Tab / Shift+Tab：按 element_id 顺序循环键盘焦点，到达两端时回绕
+    pub fn cycle_node_focus(&mut self, backward: bool) {
+        if self.all_elements.is_empty() {
+            return;
+        }
+        let mut order: Vec<usize> = (0..self.all_elements.len()).collect();
+        order.sort_by(|&a, &b| self.all_elements[a].element_id.cmp(&self.all_elements[b].element_id));
+
+        let next_pos = match self.focused_node_idx.and_then(|idx| order.iter().position(|&i| i == idx)) {
+            Some(pos) if backward => (pos + order.len() - 1) % order.len(),
+            Some(pos) => (pos + 1) % order.len(),
+            None if backward => order.len() - 1,
+            None => 0,
+        };
+        let idx = order[next_pos];
+        self.focused_node_idx = Some(idx);
+        self.selection_needs_update = true;
+        self.ensure_node_visible(idx);
+        self.pending_notifications.push(ViewNotification::NodeFocused {
+            node_id: Some(self.all_elements[idx].element_id.clone()),
+        });
+    }
 
-        if width == 0 || height == 0 {
+    /// Enter：对焦点节点执行与点击相同的操作
+    pub fn activate_focused_node(&mut self) {
+        if let Some(idx) = self.focused_node_idx {
+            self.apply_pick_result(Some(PickedEntity::Node(idx)));
+        }
+    }
+
+    /// 节点不在可见区域内时，将相机中心移到该节点
+    fn ensure_node_visible(&mut self, idx: usize) {
+        let Some(instance) = self.geometry.circle_instances.get(idx) else {
+            return;
+        };
+        let position = Vec2::from_array(instance.position);
+        let (world_visible_min, world_visible_max) = self.camera.get_world_clip_bounds();
+        let margin = Vec2::splat(instance.radius_scale);
+        let is_visible = position.cmpge(world_visible_min + margin).all() && position.cmple(world_visible_max - margin).all();
+        if !is_visible {
+            self.camera.position = position;
+            self.camera_needs_update = true;
+        }
+    }
+
+    /// 根据相机当前状态重新计算并上传相机 Uniform
+    pub fn upload_camera_uniform(&mut self) {
+        self.camera_uniform.view_proj = self.camera.build_view_projection_matrix().to_cols_array_2d();
+        self.camera_uniform.world_to_pixels = self.camera.world_radius_to_screen_pixels(1.0);
+        self.renderer.write_camera_uniform(&self.queue, &self.camera_uniform);
+        if let Some(compare) = &self.compare {
+            compare.renderer.write_camera_uniform(&self.queue, &self.camera_uniform);
+        }
+    }
+
+    pub fn is_highlight_animating(&self) -> bool {
+        (self.highlight_line_style == HighlightLineStyle::Marching || self.highlight_style.pulse)
+            && !self.geometry.highlight_line_vertices.is_empty()
+    }
+
+    pub fn update_gpu_buffers(&mut self) {
+        self.geometry.cull_circle_instances(Some(self.node_cull_region()));
+        self.renderer.upload_geometry(&self.device, &self.queue, &self.geometry);
+    }
+
+    /// 绘制并呈现一帧；返回是否真正呈现（未配置、最小化、没有 surface 或尺寸为零时跳过，返回 Ok(false)）
+    pub fn render(&mut self) -> Result<bool, wgpu::SurfaceError> {
+        if !self.is_surface_configured || self.is_minimized {
+            return Ok(false);
+        }
+        let Some(surface) = self.surface.as_ref() else {
+            return Ok(false);
+        };
+
+        if self.config.width == 0 || self.config.height == 0 {
             log::warn!("Attempting to render with zero width or height, skipping.");
-            return Ok(());
+            return Ok(false);
         }
 
-        self.glyphon_viewport.update(&self.queue, glyphon::Resolution { width, height });
+        let output = surface.get_current_texture()?;
+        let view = output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        self.render_to_view(&view);
+        output.present();
+        // 跳过绘制（未配置、最小化）或获取纹理失败时不通知，等待下一次成功的帧
+        for waiter in self.render_once_waiters.drain(..) {
+            let _ = waiter.send(());
+        }
+        Ok(true)
+    }
 
-        // --- Prepare Glyphon Text Areas ---
-        let mut text_areas = Vec::new();
+    /// 将一帧绘制到给定的纹理视图，其格式和尺寸必须与 `config` 一致
+    pub fn render_to_view(&mut self, view: &wgpu::TextureView) {
+        let width = self.config.width;
+        let height = self.config.height;
+        let half_width = self.view_size().0; // 对比视图中每一半的宽度
 
         // --- FPS Calculation ---
         self.frame_count_in_second += 1;
@@ -838,95 +1228,400 @@ impl State {
             self.last_frame_instant = now;
         }
         // --- End FPS Calculation ---
+        // 没有经过 update() 的绘制（截图等）只计入 render_to_view 本身
+        let frame_work_start = self.frame_work_start.take().unwrap_or(now);
+
+        // 叠加层文本需要在 text_areas 借用字体缓冲区之前生成
+        let stats_text = (self.show_stats_overlay && self.text_rendering_enabled).then(|| {
+            let cursor_world_pos = self.camera.screen_to_world(self.mouse_current_pos_screen);
+            let hovered_node_id = self.node_at_world_pos(cursor_world_pos)
+                .and_then(|idx| self.all_elements.get(idx))
+                .map_or("-", |element| element.element_id.as_str());
+            let gpu_timings = self.gpu_profiler.as_ref().and_then(|profiler| profiler.latest).map_or(String::new(), |timings| {
+                format!(
+                    "\nGPU ms: circles {:.2}  lines {:.2}  highlights {:.2}  text {:.2}",
+                    timings.circles_ms, timings.lines_ms, timings.highlights_ms, timings.text_ms
+                )
+            });
+            format!(
+                "FPS: {}\nLOD: {}  Quality: {}\nNodes: {}  Line vertices: {}\nBlocked demands: {} ({} shown)\nCursor: ({:.1}, {:.1})  Node: {}{}",
+                self.current_fps,
+                self.effective_lod_level().as_str(),
+                self.quality_governor.level.as_str(),
+                self.geometry.circle_instances.len(),
+                self.geometry.line_vertices.len(),
+                self.blocked_demands.len(),
+                self.visible_blocked_demand_count(),
+                cursor_world_pos.x,
+                cursor_world_pos.y,
+                hovered_node_id,
+                gpu_timings,
+            )
+        });
+
+        // 叠加层的背景在场景之后、文字之前绘制：服务列表面板，然后是悬停提示
+        let mut overlay_vertices = Vec::new();
+        if let Some(layout) = self.timeline_strip_layout() {
+            self.push_timeline_strip_vertices(&layout, &mut overlay_vertices);
+        }
+        let service_panel = self.service_panel_layout().map(|layout| {
+            self.push_service_panel_vertices(&layout, &mut overlay_vertices);
+            (layout, self.service_panel_text(&layout))
+        });
+        let tooltip_placement = self.prepare_tooltip(&mut overlay_vertices);
+        if self.show_color_test_pattern {
+            self.push_color_test_pattern_vertices(&mut overlay_vertices);
+        }
+        // 文字标签的底色块按排版后的文字范围生成，上传推迟到 text_areas 生成之后
+        let mut label_chips = LabelChips::default();
 
         // 获取相机在世界坐标中可见的区域，用于粗粒度裁剪
         let (world_visible_min, world_visible_max) = self.camera.get_world_clip_bounds();
 
-        // Node Labels (e.g., radius)
-        for (i, (instance, glyphon_buffer)) in self.world_text_labels.iter().zip(self.glyphon_buffers.iter_mut()).enumerate() {
-            // 1. 粗粒度世界坐标裁剪
-            if instance.position[0] < world_visible_min.x - instance.radius_scale * 2.0 || // 加上半径的裕量
-               instance.position[0] > world_visible_max.x + instance.radius_scale * 2.0 ||
-               instance.position[1] < world_visible_min.y - instance.radius_scale * 2.0 ||
-               instance.position[1] > world_visible_max.y + instance.radius_scale * 2.0 {
-                continue; // 节点超出世界可见范围，不渲染文本
+        // Node icons (居中绘制在节点内部)
+        let mut visible_icons = Vec::new();
+        let labels_enabled = self.labels_enabled() && self.text_rendering_enabled; // 画质降级或关闭文字渲染时隐藏图标和标签
+        for (idx, (instance, element)) in self.geometry.circle_instances.iter().zip(self.all_elements.iter()).enumerate().filter(|_| labels_enabled) {
+            if self.is_node_hidden(idx) {
+                continue;
+            }
+            let Some(icon) = self.node_icon_mapping.icon_for(element) else {
+                continue;
+            };
+            if instance.position[0] < world_visible_min.x - instance.radius_scale ||
+               instance.position[0] > world_visible_max.x + instance.radius_scale ||
+               instance.position[1] < world_visible_min.y - instance.radius_scale ||
+               instance.position[1] > world_visible_max.y + instance.radius_scale {
+                continue;
             }
-
-            let screen_pos = self.camera.world_to_screen(instance.position.into());
             let screen_radius = self.camera.world_radius_to_screen_pixels(instance.radius_scale);
-
-            // 3. 级别细节 (LOD) 裁剪：如果节点太小，不显示标签
-            const MIN_DISPLAY_SCREEN_RADIUS: f32 = 60.0;
             if screen_radius < MIN_DISPLAY_SCREEN_RADIUS {
                 continue;
             }
+            let screen_pos = self.camera.world_to_screen(instance.position.into());
+            visible_icons.push((icon, screen_pos, screen_radius));
+        }
+        // Node Labels（节点名称和类型）与跳数标签。先裁剪再分配文字缓冲区，缓冲区只用于可见的标签
+        let camera = &self.camera;
+        let label_settings = self.label_settings;
+        let mut visible_labels: Vec<_> = self.geometry.world_text_labels().filter(|_| labels_enabled).filter_map(|instance| {
+            // 1. 粗粒度世界坐标裁剪（加上半径的裕量）
+            let inside = instance.position[0] >= world_visible_min.x - instance.radius_scale * 2.0 &&
+                instance.position[0] <= world_visible_max.x + instance.radius_scale * 2.0 &&
+                instance.position[1] >= world_visible_min.y - instance.radius_scale * 2.0 &&
+                instance.position[1] <= world_visible_max.y + instance.radius_scale * 2.0;
+            // 2. 级别细节 (LOD)：节点太小时不显示标签，渐显区间内按透明度绘制
+            let alpha = label_settings.label_alpha(camera.world_radius_to_screen_pixels(instance.radius_scale));
+            (inside && alpha > 0.0).then_some((instance, alpha))
+        }).collect();
+        visible_labels.truncate(MAX_LABEL_BUFFERS);
+        let visible_annotations = if labels_enabled { self.visible_annotations() } else { Vec::new() };
+        let mut annotation_placements = Vec::new();
+
+        // 第一帧有文字时才创建字体和文字缓冲区（见 text_resources.rs），之前的帧只绘制节点和线路；
+        // 关闭文字渲染时既不排版也不调用 prepare/render
+        let has_text = !visible_icons.is_empty() || !visible_labels.is_empty() || !self.geometry.annotation_labels.is_empty() || !visible_annotations.is_empty()
+            || self.compare.is_some() || stats_text.is_some() || service_panel.is_some() || tooltip_placement.is_some();
+        self.text_area_count = 0;
+        let text_resources = if !self.text_rendering_enabled {
+            None
+        } else if has_text {
+            Some(TextResources::get_or_create(&mut self.text_resources, &self.device, &self.queue, self.config.format))
+        } else {
+            self.text_resources.as_mut()
+        };
+        if let Some(text) = text_resources {
+            text.viewport.update(&self.queue, glyphon::Resolution { width, height });
+            text.reserve_label_buffers(visible_labels.len());
 
-            // --- 动态字体大小和定位 ---
-            let target_base_font_size_world = 8.0; // 世界坐标系下，文本的“理想”高度单位
-            let actual_font_size_screen = target_base_font_size_world * self.camera.zoom * (self.config.height as f32 / 2.0);
-            let clamped_font_size = actual_font_size_screen.clamp(10.0, 40.0); // 限制字体大小在合理范围
-
-            let label_text = &instance.content; // 文本内容
-
-            // 只有当文本内容、字体大小或布局参数变化时才更新 TextBuffer
-            // 否则，Glyphon会使用其内部缓存
-            // 此处无法直接检测文本内容变化，所以如果每次都格式化字符串，则假定每次都可能变
-            // 真正的 dirty flag 应该包含文本内容的 hash 或引用
-            let metrics = glyphon::Metrics::new(clamped_font_size, clamped_font_size * 1.2); // 行高稍大一点
-            
-            glyphon_buffer.set_metrics(&mut self.glyphon_font_system, metrics);
-            glyphon_buffer.set_size(
-                &mut self.glyphon_font_system,
-                Some(screen_radius), // 给一个足够宽的矩形来防止不必要的换行，或者计算实际可用宽度
-                None, // 不需要固定高度，让 Glyphon 自动计算
-            );
-            glyphon_buffer.set_text(
-                &mut self.glyphon_font_system,
-                label_text,
-                &glyphon::Attrs::new().family(glyphon::Family::SansSerif),
-                glyphon::Shaping::Advanced,
-            );
-            glyphon_buffer.shape_until_scroll(&mut self.glyphon_font_system, false);
-
-            // 获取文本的实际宽度以便准确居中
-            let mut text_width = 0.0;
-            let mut text_height = 0.0;
-            if let Some(run) = glyphon_buffer.layout_runs().next() {
-                text_width = run.line_w;
-                text_height = run.line_height * glyphon_buffer.layout_runs().count() as f32; // Sum of all line heights
+            // --- Prepare Glyphon Text Areas ---
+            let mut text_areas = Vec::new();
+
+            while text.icon_buffers.len() < visible_icons.len() {
+                text.icon_buffers.push(glyphon::Buffer::new(&mut text.font_system, glyphon::Metrics::new(16.0, 16.0)));
+            }
+            for (&(icon, screen_pos, screen_radius), icon_buffer) in visible_icons.iter().zip(text.icon_buffers.iter_mut()) {
+                // 图标高度约为节点直径的一半
+                let icon_size = screen_radius;
+                icon_buffer.set_metrics(&mut text.font_system, glyphon::Metrics::new(icon_size, icon_size));
+                icon_buffer.set_size(&mut text.font_system, Some(icon_size * 2.0), None);
+                icon_buffer.set_text(
+                    &mut text.font_system,
+                    icon.encode_utf8(&mut [0; 4]),
+                    &glyphon::Attrs::new().family(glyphon::Family::Name(ICON_FONT_FAMILY)),
+                    glyphon::Shaping::Basic,
+                );
+                icon_buffer.shape_until_scroll(&mut text.font_system, false);
+
+                let icon_width = icon_buffer.layout_runs().next().map(|run| run.line_w).unwrap_or(icon_size);
+                text_areas.push(glyphon::TextArea {
+                    buffer: icon_buffer,
+                    left: screen_pos.x - icon_width / 2.0,
+                    top: screen_pos.y - icon_size / 2.0,
+                    scale: 1.0,
+                    bounds: glyphon::TextBounds::default(),
+                    default_color: TEXT_COLOR,
+                    custom_glyphs: &[]
+                });
             }
 
-            // 根据屏幕半径和实际文本大小调整位置
-            let text_left = screen_pos.x - text_width / 2.0; // 文本中心与节点中心对齐
-            let text_top = screen_pos.y - text_height / 2.0; // 文本放在节点上方，留 5 像素间距
-
-            // 将文本区域添加到待渲染列表
-            text_areas.push(glyphon::TextArea {
-                buffer: glyphon_buffer,
-                left: text_left,
-                top: text_top,
-                scale: 1.0, // scale 1.0 是指 buffer 内部的字体大小已经是最终屏幕尺寸
-                bounds: glyphon::TextBounds::default(), // 可以在这里设置裁剪矩形
-                default_color: glyphon::Color::rgb(230, 230, 230),
-                custom_glyphs: &[]
-            });
-        }
+            for (&(instance, alpha), glyphon_buffer) in visible_labels.iter().zip(text.label_buffers.iter_mut()) {
+                let screen_pos = camera.world_to_screen(instance.position.into());
+                let screen_radius = camera.world_radius_to_screen_pixels(instance.radius_scale);
+
+                // --- 动态字体大小和定位 ---
+                let target_base_font_size_world = 8.0 * self.node_radius / BASE_NODE_RADIUS; // 世界坐标系下，文本的“理想”高度单位（随节点半径缩放）
+                let actual_font_size_screen = target_base_font_size_world * self.camera.zoom * (self.config.height as f32 / 2.0);
+                let clamped_font_size = actual_font_size_screen.clamp(10.0, 40.0); // 限制字体大小在合理范围
+
+                // 附加行（节点类型）有更高的显示阈值，每行按自己的比例缩小字号
+                let detail_lines = if screen_radius >= label_settings.detail_lines_min_node_px { instance.detail_lines.as_slice() } else { &[] };
+                let detail_texts: Vec<String> = detail_lines.iter().map(|line| format!("\n{}", line.content)).collect();
+                let line_attrs = |font_scale: f32| {
+                    let font_size = clamped_font_size * font_scale;
+                    glyphon::Attrs::new()
+                        .family(glyphon::Family::SansSerif)
+                        .metrics(glyphon::Metrics::new(font_size, font_size * 1.2)) // 行高稍大一点
+                };
+                let spans = std::iter::once((instance.content.as_str(), line_attrs(1.0)))
+                    .chain(detail_texts.iter().zip(detail_lines).map(|(text, line)| (text.as_str(), line_attrs(line.font_scale))));
+
+                glyphon_buffer.set_metrics(&mut text.font_system, glyphon::Metrics::new(clamped_font_size, clamped_font_size * 1.2));
+                // 先不限宽度排版以测量最宽的一行，再以该宽度居中对齐各行
+                glyphon_buffer.set_size(&mut text.font_system, None, None);
+                glyphon_buffer.set_rich_text(
+                    &mut text.font_system,
+                    spans,
+                    &line_attrs(1.0),
+                    glyphon::Shaping::Advanced,
+                    Some(glyphon::cosmic_text::Align::Center),
+                );
+                glyphon_buffer.shape_until_scroll(&mut text.font_system, false);
+                let text_width = glyphon_buffer.layout_runs().map(|run| run.line_w).fold(0.0, f32::max);
+                if !detail_lines.is_empty() {
+                    glyphon_buffer.set_size(&mut text.font_system, Some(text_width.ceil()), None);
+                    glyphon_buffer.shape_until_scroll(&mut text.font_system, false);
+                }
+                let text_height: f32 = glyphon_buffer.layout_runs().map(|run| run.line_height).sum();
+
+                // 根据屏幕半径和实际文本大小调整位置
+                let text_left = screen_pos.x - text_width / 2.0; // 文本中心与节点中心对齐
+                let text_top = screen_pos.y - text_height / 2.0; // 文本放在节点上方，留 5 像素间距
+
+                // 将文本区域添加到待渲染列表
+                text_areas.push(glyphon::TextArea {
+                    buffer: glyphon_buffer,
+                    left: text_left,
+                    top: text_top,
+                    scale: 1.0, // scale 1.0 是指 buffer 内部的字体大小已经是最终屏幕尺寸
+                    bounds: glyphon::TextBounds::default(), // 可以在这里设置裁剪矩形
+                    default_color: with_alpha(TEXT_COLOR, alpha),
+                    custom_glyphs: &[]
+                });
+                label_chips.push_faded(text_left, text_top, Vec2::new(text_width, text_height), alpha);
+            }
 
-        // Prepare glyphon text for rendering (uploads glyph textures)
-        self.glyphon_renderer.prepare(
-            &self.device,
-            &self.queue,
-            &mut self.glyphon_font_system,
-            &mut self.glyphon_atlas,
-            &self.glyphon_viewport,
-            text_areas, // Pass the vector of TextAreas
-            &mut self.glyphon_swash_cache,
-        ).unwrap();
+            // 注释文字（例如路径跳数）：固定屏幕字号，居中显示在标注点上方
+            while text.annotation_buffers.len() < self.geometry.annotation_labels.len() {
+                text.annotation_buffers.push(glyphon::Buffer::new(&mut text.font_system, glyphon::Metrics::new(ANNOTATION_FONT_SIZE, ANNOTATION_FONT_SIZE * 1.2)));
+            }
+            for (label, annotation_buffer) in self.geometry.annotation_labels.iter().zip(text.annotation_buffers.iter_mut()) {
+                let screen_pos = self.camera.world_to_screen(label.position.into());
+                if screen_pos.x < 0.0 || screen_pos.y < 0.0 || screen_pos.x > width as f32 || screen_pos.y > height as f32 {
+                    continue;
+                }
+                annotation_buffer.set_size(&mut text.font_system, None, None);
+                annotation_buffer.set_text(
+                    &mut text.font_system,
+                    &label.content,
+                    &glyphon::Attrs::new().family(glyphon::Family::SansSerif),
+                    glyphon::Shaping::Advanced,
+                );
+                annotation_buffer.shape_until_scroll(&mut text.font_system, false);
+
+                let text_width = annotation_buffer.layout_runs().map(|run| run.line_w).fold(0.0, f32::max);
+                let text_height: f32 = annotation_buffer.layout_runs().map(|run| run.line_height).sum();
+                let (text_left, text_top) = (screen_pos.x - text_width / 2.0, screen_pos.y - ANNOTATION_FONT_SIZE * 2.0);
+                text_areas.push(glyphon::TextArea {
+                    buffer: annotation_buffer,
+                    left: text_left,
+                    top: text_top,
+                    scale: 1.0,
+                    bounds: glyphon::TextBounds::default(),
+                    default_color: TEXT_COLOR,
+                    custom_glyphs: &[]
+                });
+                label_chips.push(text_left, text_top, Vec2::new(text_width, text_height));
+            }
 
-        let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+            // 地图注释：固定屏幕字号，底色块使用注释的颜色（与引线一起在 push_annotation_vertices 中生成）
+            while text.map_annotation_buffers.len() < visible_annotations.len() {
+                text.map_annotation_buffers.push(glyphon::Buffer::new(&mut text.font_system, glyphon::Metrics::new(ANNOTATION_FONT_SIZE, ANNOTATION_FONT_SIZE * 1.2)));
+            }
+            for (&(annotation_idx, target, alpha), annotation_buffer) in visible_annotations.iter().zip(text.map_annotation_buffers.iter_mut()) {
+                annotation_buffer.set_size(&mut text.font_system, None, None);
+                annotation_buffer.set_text(
+                    &mut text.font_system,
+                    &self.annotations[annotation_idx].text,
+                    &glyphon::Attrs::new().family(glyphon::Family::SansSerif),
+                    glyphon::Shaping::Advanced,
+                );
+                annotation_buffer.shape_until_scroll(&mut text.font_system, false);
+
+                let text_width = annotation_buffer.layout_runs().map(|run| run.line_w).fold(0.0, f32::max);
+                let text_height: f32 = annotation_buffer.layout_runs().map(|run| run.line_height).sum();
+                let (placement, text_origin) = AnnotationPlacement::new(annotation_idx, target, Vec2::new(text_width, text_height), alpha);
+                annotation_placements.push(placement);
+                let [r, g, b] = self.annotations[annotation_idx].text_color();
+                text_areas.push(glyphon::TextArea {
+                    buffer: annotation_buffer,
+                    left: text_origin.x,
+                    top: text_origin.y,
+                    scale: 1.0,
+                    bounds: glyphon::TextBounds::default(),
+                    default_color: with_alpha(glyphon::Color::rgb(r, g, b), alpha),
+                    custom_glyphs: &[]
+                });
+            }
+
+            // 对比视图：以上文本按左半部分的本地坐标生成，裁剪到左半部分后复制到右半部分，再在两边顶部标注时刻
+            if let Some(compare) = &self.compare {
+                let world_text_count = text_areas.len();
+                for i in 0..world_text_count {
+                    text_areas[i].bounds = glyphon::TextBounds { left: 0, top: 0, right: half_width as i32, bottom: height as i32 };
+                    let area = &text_areas[i];
+                    let mirrored = glyphon::TextArea {
+                        buffer: area.buffer,
+                        left: area.left + half_width as f32,
+                        top: area.top,
+                        scale: area.scale,
+                        bounds: glyphon::TextBounds { left: half_width as i32, top: 0, right: width as i32, bottom: height as i32 },
+                        default_color: area.default_color,
+                        custom_glyphs: area.custom_glyphs,
+                    };
+                    text_areas.push(mirrored);
+                }
+                label_chips.mirror_for_compare(half_width as f32);
+
+                while text.compare_label_buffers.len() < 2 {
+                    text.compare_label_buffers.push(glyphon::Buffer::new(&mut text.font_system, glyphon::Metrics::new(ANNOTATION_FONT_SIZE, ANNOTATION_FONT_SIZE * 1.2)));
+                }
+                let labels = [format!("t1 = {}", self.current_time_selection), format!("t2 = {}", compare.time)];
+                for (i, (label, label_buffer)) in labels.iter().zip(text.compare_label_buffers.iter_mut()).enumerate() {
+                    label_buffer.set_size(&mut text.font_system, None, None);
+                    label_buffer.set_text(
+                        &mut text.font_system,
+                        label,
+                        &glyphon::Attrs::new().family(glyphon::Family::SansSerif),
+                        glyphon::Shaping::Basic,
+                    );
+                    label_buffer.shape_until_scroll(&mut text.font_system, false);
+                    let text_width = label_buffer.layout_runs().map(|run| run.line_w).fold(0.0, f32::max);
+                    let text_height: f32 = label_buffer.layout_runs().map(|run| run.line_height).sum();
+                    let text_left = (i as f32 + 0.5) * half_width as f32 - text_width / 2.0;
+                    label_chips.push(text_left, 8.0, Vec2::new(text_width, text_height));
+                    text_areas.push(glyphon::TextArea {
+                        buffer: label_buffer,
+                        left: text_left,
+                        top: 8.0,
+                        scale: 1.0,
+                        bounds: glyphon::TextBounds::default(),
+                        default_color: TEXT_COLOR,
+                        custom_glyphs: &[]
+                    });
+                }
+            }
+
+            // Stats overlay (屏幕左上角)
+            if let Some(stats_text) = stats_text {
+                text.stats_buffer.set_size(&mut text.font_system, Some(width as f32), None);
+                text.stats_buffer.set_text(
+                    &mut text.font_system,
+                    &stats_text,
+                    &glyphon::Attrs::new().family(glyphon::Family::Monospace),
+                    glyphon::Shaping::Basic,
+                );
+                text.stats_buffer.shape_until_scroll(&mut text.font_system, false);
+                let text_width = text.stats_buffer.layout_runs().map(|run| run.line_w).fold(0.0, f32::max);
+                let text_height: f32 = text.stats_buffer.layout_runs().map(|run| run.line_height).sum();
+                label_chips.push(8.0, 8.0, Vec2::new(text_width, text_height));
+                text_areas.push(glyphon::TextArea {
+                    buffer: &text.stats_buffer,
+                    left: 8.0,
+                    top: 8.0,
+                    scale: 1.0,
+                    bounds: glyphon::TextBounds::default(),
+                    default_color: glyphon::Color::rgb(255, 255, 255),
+                    custom_glyphs: &[]
+                });
+            }
+
+            // 服务列表面板 (屏幕右上角)
+            if let Some((layout, panel_text)) = service_panel {
+                let text_origin = layout.text_origin();
+                text.service_panel_buffer.set_size(&mut text.font_system, Some(layout.max.x - text_origin.x), None);
+                text.service_panel_buffer.set_text(
+                    &mut text.font_system,
+                    &panel_text,
+                    &glyphon::Attrs::new().family(glyphon::Family::Monospace),
+                    glyphon::Shaping::Advanced, // λ、→ 和 … 需要字体回退
+                );
+                text.service_panel_buffer.shape_until_scroll(&mut text.font_system, false);
+                text_areas.push(glyphon::TextArea {
+                    buffer: &text.service_panel_buffer,
+                    left: text_origin.x,
+                    top: text_origin.y,
+                    scale: 1.0,
+                    bounds: glyphon::TextBounds {
+                        left: layout.min.x as i32,
+                        top: layout.min.y as i32,
+                        right: layout.max.x as i32,
+                        bottom: layout.max.y as i32,
+                    },
+                    default_color: self.overlay_theme.text,
+                    custom_glyphs: &[]
+                });
+            }
+
+            // 悬停提示（文字已在 prepare_tooltip 中排版）
+            if let Some((text_origin, bounds)) = tooltip_placement {
+                text_areas.push(glyphon::TextArea {
+                    buffer: &text.tooltip_buffer,
+                    left: text_origin.x,
+                    top: text_origin.y,
+                    scale: 1.0,
+                    bounds,
+                    default_color: self.overlay_theme.text,
+                    custom_glyphs: &[]
+                });
+            }
+
+            // Prepare glyphon text for rendering (uploads glyph textures)
+            self.text_area_count = text_areas.len();
+            text.renderer.prepare(
+                &self.device,
+                &self.queue,
+                &mut text.font_system,
+                &mut text.atlas,
+                &text.viewport,
+                text_areas, // Pass the vector of TextAreas
+                &mut text.swash_cache,
+            ).unwrap();
+        }
+
+        // 底色块在面板和提示框之前绘制，被它们覆盖
+        let mut chip_vertices = Vec::new();
+        if self.label_settings.background_chips {
+            label_chips.push_vertices(&mut chip_vertices, Vec2::new(width as f32, height as f32), self.overlay_theme.label_chip);
+        }
+        self.annotation_placements = annotation_placements;
+        let mirror_offset = self.compare.is_some().then(|| Vec2::new(half_width as f32, 0.0));
+        self.push_annotation_vertices(&mut chip_vertices, Vec2::new(width as f32, height as f32), mirror_offset);
+        chip_vertices.append(&mut overlay_vertices);
+        let overlay_vertex_count = chip_vertices.len() as u32;
+        if overlay_vertex_count > 0 {
+            self.renderer.upload_overlay_vertices(&self.device, &self.queue, &chip_vertices);
+        }
 
         let mut encoder = self
             .device
@@ -934,63 +1629,90 @@ impl State {
                 label: Some("Render Encoder"),
             });
 
-        {
+        // GPU 计时时节点、线路、高亮和文字各用一个渲染通道（见 gpu_profiler.rs），否则整帧一个渲染通道
+        let timestamp_query_set = self.gpu_profiler.as_ref().and_then(|profiler| profiler.frame_query_set());
+        let pass_sections: &[&[SceneSection]] = if timestamp_query_set.is_some() {
+            &[&[SceneSection::Circles], &[SceneSection::Lines], &[SceneSection::Highlights], &[]]
+        } else {
+            &[&SceneSection::ALL]
+        };
+        for (pass_index, &sections) in pass_sections.iter().enumerate() {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        load: if pass_index == 0 { wgpu::LoadOp::Clear(self.background_clear_color()) } else { wgpu::LoadOp::Load },
                         store: wgpu::StoreOp::Store,
                     },
                     depth_slice: None,
                 })],
                 depth_stencil_attachment: None,
-                timestamp_writes: None,
+                timestamp_writes: timestamp_query_set.map(|query_set| GpuProfiler::timestamp_writes(query_set, pass_index)),
                 occlusion_query_set: None,
             });
 
-            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
-
-            // 1. 绘制圆形（节点）
-            render_pass.set_pipeline(&self.circle_render_pipeline);
-            render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
-            render_pass.set_vertex_buffer(1, self.circle_instance_buffer.slice(..));
-            render_pass.set_index_buffer(self.quad_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            render_pass.draw_indexed(
-                0..Vertex2D::QUAD_INDICES.len() as u32,
-                0,
-                0..self.circle_instances.len() as u32,
-            );
+            if !sections.is_empty() {
+                let overlay_counts = OverlayCounts {
+                    selection_instances: self.selection_instances.len() as u32,
+                    capacity_bar_vertices: self.capacity_bar_vertices.len() as u32,
+                    hover_line_vertices: self.hover_line_vertices.len() as u32,
+                    flow_dots: self.flow_dot_instances.len() as u32,
+                };
+                match &self.compare {
+                    Some(compare) => {
+                        // 两半使用同一个相机 uniform，各自设置视口
+                        let half_width = self.view_size().0 as f32;
+                        render_pass.set_viewport(0.0, 0.0, half_width, height as f32, 0.0, 1.0);
+                        self.renderer.draw_scene(&mut render_pass, &self.geometry, sections, overlay_counts);
+                        render_pass.set_viewport(half_width, 0.0, half_width, height as f32, 0.0, 1.0);
+                        let compare_counts = OverlayCounts { selection_instances: overlay_counts.selection_instances, ..Default::default() };
+                        compare.renderer.draw_scene(&mut render_pass, &compare.geometry, sections, compare_counts);
+                        render_pass.set_viewport(0.0, 0.0, width as f32, height as f32, 0.0, 1.0);
+                    }
+                    None => {
+                        self.renderer.draw_scene(&mut render_pass, &self.geometry, sections, overlay_counts);
+                    }
+                }
+            }
 
-            // 2. 绘制普通线段 (链路边界和服务)
-            render_pass.set_pipeline(&self.line_render_pipeline);
-            render_pass.set_vertex_buffer(0, self.line_vertex_buffer.slice(..));
-            render_pass.draw(0..self.line_vertices.len() as u32, 0..1);
+            // 叠加层和文字在最后一个渲染通道中绘制
+            if pass_index + 1 == pass_sections.len() {
+                self.renderer.draw_overlay(&mut render_pass, overlay_vertex_count);
 
-            // 3. 绘制高亮线段 (覆盖在普通线段之上)
-            if self.highlight_line_vertices.len() != 0 {
-                render_pass.set_pipeline(&self.highlight_line_render_pipeline);
-                render_pass.set_vertex_buffer(0, self.highlight_line_vertex_buffer.slice(..));
-                render_pass.draw(0..self.highlight_line_vertices.len() as u32, 0..1);
+                // --- Draw Glyphon Text ---
+                if let Some(text) = self.text_resources.as_ref().filter(|_| self.text_rendering_enabled) {
+                    text.renderer.render(&text.atlas, &text.viewport, &mut render_pass).unwrap();
+                }
             }
-            
-            // --- Draw Glyphon Text ---
-            self.glyphon_renderer.render(&self.glyphon_atlas, &self.glyphon_viewport, &mut render_pass).unwrap();
+        }
+        let profiled = timestamp_query_set.is_some();
+        if let Some(profiler) = self.gpu_profiler.as_mut().filter(|_| profiled) {
+            profiler.resolve(&mut encoder);
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+        if let Some(profiler) = self.gpu_profiler.as_mut() {
+            profiler.request_readback();
+        }
 
-        self.glyphon_atlas.trim();
+        let work_end = Instant::now();
+        let cpu_ms = (work_end - frame_work_start).as_secs_f32() * 1000.0;
+        let gpu_ms = self.gpu_profiler.as_ref().and_then(|profiler| profiler.latest).map(|timings| timings.total_ms());
+        if let Some(level) = self.quality_governor.record_frame(work_end, cpu_ms, gpu_ms) {
+            self.apply_quality_level(level);
+        }
 
-        Ok(())
+        if let Some(text) = self.text_resources.as_mut() {
+            text.atlas.trim();
+        }
     }
 
         /// 根据当前拓扑（`circle_instances`）调整相机位置和缩放，使其全部可见。
     pub fn fit_view_to_topology(&mut self) {
-        if self.circle_instances.is_empty() {
+        self.camera.stop_zoom_animation();
+        if self.geometry.circle_instances.is_empty() {
             // 如果没有节点，则将相机重置到默认视图
             self.camera.position = glam::Vec2::ZERO;
             self.camera.zoom = 1.0;
@@ -1005,7 +1727,7 @@ impl State {
         let mut max_node_radius = 0.0f32;
 
         // 遍历所有节点实例以确定边界
-        for instance in &self.circle_instances {
+        for instance in &self.geometry.circle_instances {
             min_x = min_x.min(instance.position[0]);
             max_x = max_x.max(instance.position[0]);
             // 注意：拓扑数据中的y坐标在加载时反转了 (-element.metadata.location.y)
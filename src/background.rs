@@ -0,0 +1,75 @@
+// src/background.rs
+// 画布背景（setBackground）：清屏颜色和不透明度。不透明度小于 1 时把 surface 的 alpha_mode 切换为浏览器支持的
+// PreMultiplied（或 PostMultiplied），画布下面的页面内容（例如地图瓦片）透过背景显示；节点、线路和叠加层
+// 输出预乘 alpha 的颜色（见 lines.wgsl），glyphon 的文字混合在预乘的目标上结果相同。
+// 不支持透明合成的适配器退回不透明背景并报告警告；原生窗口没有下层内容，始终按不透明的颜色清屏。
+use bevy_color::{ColorToComponents, LinearRgba, Srgba};
+
+use crate::app_state::State;
+use crate::errors::{self, ViewError};
+use crate::settings::Background;
+
+impl State {
+    pub fn set_background(&mut self, background: Background) {
+        if let Err(e) = background.validate() {
+            errors::report(ViewError::warning("invalid_background", format!("Ignoring invalid background: {}", e)));
+            return;
+        }
+        let mut background = background;
+        if background.opacity < 1.0 {
+            match self.transparent_alpha_mode() {
+                Some(alpha_mode) => self.set_surface_alpha_mode(alpha_mode),
+                None if cfg!(target_arch = "wasm32") => {
+                    errors::report(ViewError::warning(
+                        "transparent_background_unsupported",
+                        format!("The canvas does not support alpha compositing (modes {:?}); using an opaque background.", self.surface_alpha_modes),
+                    ));
+                    background.opacity = 1.0;
+                }
+                None => background.opacity = 1.0,
+            }
+        } else {
+            let opaque = if self.surface_alpha_modes.contains(&wgpu::CompositeAlphaMode::Opaque) {
+                wgpu::CompositeAlphaMode::Opaque
+            } else {
+                self.surface_alpha_modes.first().copied().unwrap_or(wgpu::CompositeAlphaMode::Auto)
+            };
+            self.set_surface_alpha_mode(opaque);
+        }
+        log::info!("Background set to {:?} (alpha mode {:?}).", background, self.config.alpha_mode);
+        self.background = background;
+    }
+
+    /// 原生窗口始终不透明；浏览器中优先使用与着色器输出一致的 PreMultiplied
+    fn transparent_alpha_mode(&self) -> Option<wgpu::CompositeAlphaMode> {
+        if !cfg!(target_arch = "wasm32") {
+            return None;
+        }
+        [wgpu::CompositeAlphaMode::PreMultiplied, wgpu::CompositeAlphaMode::PostMultiplied]
+            .into_iter()
+            .find(|mode| self.surface_alpha_modes.contains(mode))
+    }
+
+    fn set_surface_alpha_mode(&mut self, alpha_mode: wgpu::CompositeAlphaMode) {
+        if self.config.alpha_mode == alpha_mode {
+            return;
+        }
+        self.config.alpha_mode = alpha_mode;
+        if let Some(surface) = self.surface.as_ref() && self.config.width > 0 && self.config.height > 0 {
+            surface.configure(&self.device, &self.config);
+        }
+    }
+
+    /// render_to_view 的清屏颜色：表面不是 sRGB 格式时与着色器一样先做 sRGB 编码；PostMultiplied 之外按预乘 alpha 给出
+    pub fn background_clear_color(&self) -> wgpu::Color {
+        let color = LinearRgba::from_f32_array(self.background.color);
+        let [r, g, b] = if self.camera_uniform.needs_srgb_output_conversion == 1 {
+            Srgba::from(color).to_f32_array_no_alpha()
+        } else {
+            color.to_f32_array_no_alpha()
+        };
+        let alpha = self.background.opacity;
+        let scale = if self.config.alpha_mode == wgpu::CompositeAlphaMode::PostMultiplied { 1.0 } else { alpha };
+        wgpu::Color { r: (r * scale) as f64, g: (g * scale) as f64, b: (b * scale) as f64, a: alpha as f64 }
+    }
+}
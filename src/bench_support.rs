@@ -0,0 +1,228 @@
+// src/bench_support.rs
+// 基准测试（benches/）使用的入口和数据：重新导出被测的热点函数，并用 synthetic 生成可复现的拓扑和时间轴。
+// 几何生成只使用 CPU 端的 SceneGeometry，不需要 GPU。
+use std::collections::HashMap;
+
+use glam::Vec2;
+
+use crate::models::CircleInstance;
+use crate::picking::PickSegment;
+pub use crate::scene::defrag_event::{reconstruct_state_at_time, AnyEvent};
+use crate::scene::defrag_event::{reallocation_chain, EventCheckpoints};
+use crate::scene::edge_bundles::EdgeBundles;
+use crate::scene::geometry::{GeometryInputs, SceneGeometry, BASE_NODE_RADIUS, NODE_INSTANCE_RADIUS_FACTOR};
+use crate::scene::network::FullTopologyData;
+use crate::scene::path_index::{resolve_connections, resolve_event_paths};
+use crate::scene::segment_grid::{segment_distance, SegmentGrid};
+use crate::scene::wavelength_colors::WavelengthColorLut;
+pub use crate::settings::ServiceIntervalSemantics;
+use crate::settings::{ChannelPlan, ColorMode, HighlightLineStyle, HighlightStyle, LodLevel, OpacityMode, ThicknessMode};
+use crate::synthetic::{generate, SyntheticParams};
+
+/// 时间轴事件重建的测试数据使用的节点数：路径较短，生成大量事件也很快
+const EVENT_FIXTURE_NODES: usize = 400;
+/// 服务平均持续时间（秒），约为同时活跃的服务数；足够小，几乎没有请求因波长不足被阻塞
+const EVENT_FIXTURE_HOLDING_TIME: f64 = 1000.0;
+
+/// 约 event_count 个按时间排序的事件（每个服务一个分配和一个释放事件，另有少量重分配）
+pub fn synthetic_events(event_count: usize, seed: u64) -> Vec<AnyEvent> {
+    let params = SyntheticParams {
+        nodes: EVENT_FIXTURE_NODES,
+        services: Some(event_count / 2),
+        mean_holding_time: Some(EVENT_FIXTURE_HOLDING_TIME),
+        seed,
+        ..Default::default()
+    };
+    generate(&params).defrag_timeline_events
+}
+
+/// 事件时间戳范围内 fraction（0 到 1）处的时刻
+pub fn time_at_fraction(events: &[AnyEvent], fraction: f64) -> f64 {
+    match (events.first(), events.last()) {
+        (Some(first), Some(last)) => first.timestamp() + (last.timestamp() - first.timestamp()) * fraction,
+        _ => 0.0,
+    }
+}
+
+/// 一个合成拓扑及生成其几何所需的状态，相当于不带 GPU 资源的 State
+pub struct GeometryFixture {
+    topology: FullTopologyData,
+    node_id_to_idx: HashMap<String, usize>,
+    connection_endpoints: Vec<(usize, usize)>,
+    event_checkpoints: EventCheckpoints,
+    wavelength_colors: WavelengthColorLut,
+    circle_instances: Vec<CircleInstance>,
+    highlight_service_ids: Option<Vec<i32>>,
+    edge_bundles: Option<EdgeBundles>,
+    pub time: f64,
+}
+
+impl GeometryFixture {
+    /// nodes 个节点的网格拓扑（服务数为 2 × nodes），时间位于时间轴中点。
+    /// highlight 为 true 时高亮第一个碎片整理服务及被它移动的服务
+    pub fn new(nodes: usize, highlight: bool, seed: u64) -> Self {
+        let mut topology = generate(&SyntheticParams { nodes, seed, ..Default::default() });
+        let node_id_to_idx = topology.elements
+            .iter()
+            .enumerate()
+            .map(|(i, element)| (element.element_id.clone(), i))
+            .collect();
+        // 与 State::resolve_topology_indices 相同，在加载时解析一次
+        let (connection_endpoints, _) = resolve_connections(&topology.connections, &node_id_to_idx);
+        resolve_event_paths(&mut topology.defrag_timeline_events, &node_id_to_idx);
+        let circle_instances = topology.elements
+            .iter()
+            .map(|element| {
+                let location = element.metadata.location.as_ref().map_or(Vec2::ZERO, |location| Vec2::new(location.x, location.y));
+                CircleInstance { position: [location.x, -location.y], radius_scale: BASE_NODE_RADIUS * NODE_INSTANCE_RADIUS_FACTOR, color: [0.0; 4], glow: 0.0 }
+            })
+            .collect();
+
+        let highlight_service_ids = highlight.then(|| {
+            let defrag_service_id = topology.defrag_timeline_events.iter().find_map(|event| match event {
+                AnyEvent::Reallocation { details, .. } => Some(details.defrag_service_id),
+                _ => None,
+            });
+            match defrag_service_id {
+                Some(defrag_service_id) => std::iter::once(defrag_service_id)
+                    .chain(reallocation_chain(&topology.defrag_timeline_events, defrag_service_id).into_iter().map(|(service_id, _)| service_id))
+                    .collect(),
+                None => vec![0],
+            }
+        });
+
+        let time = time_at_fraction(&topology.defrag_timeline_events, 0.5);
+        let event_checkpoints = EventCheckpoints::new(&topology.defrag_timeline_events, 0);
+        let wavelength_colors = WavelengthColorLut::new(SyntheticParams::default().channels, &HighlightStyle::default());
+        Self { topology, node_id_to_idx, connection_endpoints, event_checkpoints, wavelength_colors, circle_instances, highlight_service_ids, edge_bundles: None, time }
+    }
+
+    /// 按当前时间一次性重新生成所有线路（与 State::start_geometry_update 的三步相同，不分帧），返回顶点总数
+    pub fn regenerate(&self) -> usize {
+        let mut geometry = self.empty_geometry();
+        self.regenerate_into(&mut geometry, usize::MAX);
+        geometry.line_vertices.len() + geometry.highlight_line_vertices.len()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn events(&self) -> &[AnyEvent] {
+        &self.topology.defrag_timeline_events
+    }
+
+    /// 按节点位置一次性完成边绑定，之后生成的线路沿绑定后的折线绘制
+    #[cfg(test)]
+    pub(crate) fn bundle_edges(&mut self) {
+        let node_positions = self.circle_instances.iter().map(|instance| Vec2::from_array(instance.position)).collect();
+        let mut job = crate::scene::edge_bundles::EdgeBundlingJob::new(node_positions, self.connection_endpoints.clone());
+        self.edge_bundles = job.advance(f64::INFINITY);
+    }
+
+    /// 只有节点实例的几何，相当于 start_geometry_update 中的 staging
+    pub(crate) fn empty_geometry(&self) -> SceneGeometry {
+        SceneGeometry { circle_instances: self.circle_instances.clone(), ..Default::default() }
+    }
+
+    /// 在 geometry 上重新生成线路，push_services 每批最多 vertex_budget 个顶点（与分帧更新相同）。
+    /// 返回 begin_regenerate 重建的活跃服务 ID（排序）
+    pub(crate) fn regenerate_into(&self, geometry: &mut SceneGeometry, vertex_budget: usize) -> Vec<i32> {
+        let inputs = self.inputs();
+        let mut build = geometry.begin_regenerate(&inputs);
+        let service_ids = build.services().iter().map(|service| service.service_id).collect();
+        while !geometry.push_services(&mut build, &inputs, vertex_budget) {}
+        geometry.finish_regenerate(build, &inputs);
+        service_ids
+    }
+
+    fn inputs(&self) -> GeometryInputs<'_> {
+        GeometryInputs {
+            node_id_to_idx: &self.node_id_to_idx,
+            connection_endpoints: &self.connection_endpoints,
+            events: &self.topology.defrag_timeline_events,
+            event_checkpoints: &self.event_checkpoints,
+            preview_services: &[],
+            current_time: self.time,
+            service_interval: ServiceIntervalSemantics::default(),
+            channel_plan: ChannelPlan { max_wavelengths: SyntheticParams::default().channels, ..ChannelPlan::default() },
+            color_mode: ColorMode::Wavelength,
+            wavelength_colors: &self.wavelength_colors,
+            thickness_mode: ThicknessMode::Uniform,
+            opacity_mode: OpacityMode::Uniform,
+            world_to_pixels: 1.0,
+            node_radius: BASE_NODE_RADIUS,
+            highlight_service_ids: self.highlight_service_ids.as_deref(),
+            highlight_palette: false,
+            highlight_style: HighlightStyle::default(),
+            highlight_line_style: HighlightLineStyle::Solid,
+            lod_level: LodLevel::Detailed,
+            highlight_path: None,
+            measurement: None,
+            heat_trail_window: None,
+            blocked_demands: &[],
+            blocked_demand_window: None,
+            node_group_colors: &[],
+            hidden_nodes: &[],
+            service_diff: None,
+            flow_animation: false,
+            edge_bundles: self.edge_bundles.as_ref(),
+        }
+    }
+}
+
+/// 拾取线段网格的测试数据：合成拓扑中活跃服务的前 count 条拾取线段，以及覆盖线段范围（向外扩展 10%）的 64 × 64 个查询点
+pub struct SegmentGridFixture {
+    segments: Vec<PickSegment>,
+    grid: SegmentGrid,
+    queries: Vec<Vec2>,
+    /// 查询容差，约为线段范围的 1/200（相当于几个像素）
+    pub tolerance: f32,
+}
+
+impl SegmentGridFixture {
+    pub fn new(count: usize, seed: u64) -> Self {
+        let mut nodes = 256;
+        let segments = loop {
+            let fixture = GeometryFixture::new(nodes, false, seed);
+            let mut geometry = fixture.empty_geometry();
+            fixture.regenerate_into(&mut geometry, usize::MAX);
+            if geometry.pick_segments.len() >= count {
+                break geometry.pick_segments[..count].to_vec();
+            }
+            nodes *= 2;
+        };
+        let (min, max) = segments.iter().fold((Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)), |(min, max), segment| {
+            (min.min(segment.start).min(segment.end), max.max(segment.start).max(segment.end))
+        });
+        let margin = (max - min) * 0.1;
+        let queries = (0..64 * 64)
+            .map(|i| min - margin + (max - min + margin * 2.0) * Vec2::new((i % 64) as f32, (i / 64) as f32) / 63.0)
+            .collect();
+        let grid = SegmentGrid::new(&segments);
+        Self { segments, grid, queries, tolerance: (max - min).max_element() / 200.0 }
+    }
+
+    /// 重新建立网格，返回线段数
+    pub fn build(&self) -> usize {
+        std::hint::black_box(SegmentGrid::new(&self.segments));
+        self.segments.len()
+    }
+
+    /// 用网格查询所有查询点，返回命中的次数
+    pub fn nearest_all(&self) -> usize {
+        self.queries.iter().filter(|&&pos| self.grid.nearest(&self.segments, pos, self.tolerance).is_some()).count()
+    }
+
+    /// 逐条检查所有查询点（网格之前的做法），返回命中的次数
+    pub fn nearest_all_brute_force(&self) -> usize {
+        self.queries
+            .iter()
+            .filter(|&&pos| {
+                self.segments
+                    .iter()
+                    .map(|segment| segment_distance(segment, pos))
+                    .filter(|&distance| distance <= self.tolerance)
+                    .min_by(f32::total_cmp)
+                    .is_some()
+            })
+            .count()
+    }
+}
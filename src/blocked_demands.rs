@@ -0,0 +1,87 @@
+// src/blocked_demands.rs
+// 被阻塞需求图层：拓扑数据中的 blocked_demands 在 current_time_selection 前后的时间窗口内以红色虚线绘制在源和目的节点之间
+// （见 SceneGeometry::finish_regenerate），与预览服务共用虚线缓冲区。需求按时间排序保存，窗口查询用二分查找；
+// 图层开关和窗口长度只需要重新生成几何。需求的两端不在拓扑中时记录到验证报告，绘制时跳过；
+// 时间戳不是有限值（NaN、无穷）的需求无法排序和按窗口查询，直接丢弃并报告错误。
+use crate::app_state::State;
+use crate::errors::{self, ViewError};
+use crate::scene::blocked_demand::{blocked_demands_in_range, BlockedDemand};
+use crate::settings::is_positive_finite;
+
+/// 未指定窗口长度时，使用整个时间轴跨度的这一比例
+const DEFAULT_BLOCKED_DEMAND_WINDOW_FRACTION: f64 = 0.05;
+
+impl State {
+    /// 替换（merge 为 false）或追加被阻塞的需求，按时间重新排序。时间戳不是有限值的需求被丢弃（invalid_blocked_demand 错误）
+    pub fn set_blocked_demands(&mut self, blocked_demands: Vec<BlockedDemand>, merge: bool) {
+        if !merge {
+            self.blocked_demands.clear();
+        }
+        let (valid, invalid): (Vec<BlockedDemand>, Vec<BlockedDemand>) =
+            blocked_demands.into_iter().partition(|demand| demand.timestamp.is_finite());
+        if let Some(first) = invalid.first() {
+            errors::report(ViewError::error(
+                "invalid_blocked_demand",
+                format!(
+                    "Ignoring {} blocked demand(s) with a non-finite timestamp (first: {} -> {}, timestamp {}).",
+                    invalid.len(), first.source_id, first.destination_id, first.timestamp
+                ),
+            ));
+        }
+        self.blocked_demands.extend(valid);
+        self.blocked_demands.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+
+        self.validation_report.issues.retain(|issue| issue.code != "blocked_demand_unknown_node");
+        let unknown = self.blocked_demands
+            .iter()
+            .flat_map(|demand| [&demand.source_id, &demand.destination_id])
+            .find(|node_id| !self.node_id_to_idx.contains_key(*node_id))
+            .cloned();
+        if let Some(unknown_node_id) = unknown {
+            self.validation_report.warn(
+                "blocked_demand_unknown_node",
+                format!("Blocked demands reference node {}, which is not in the topology; those demands are not drawn.", unknown_node_id),
+                Some(unknown_node_id),
+            );
+        }
+        self.topology_needs_update = true;
+    }
+
+    /// window 为窗口总长度（秒，以当前时刻为中心），None 表示时间轴跨度的 5%
+    pub fn set_blocked_demand_layer(&mut self, visible: bool, window: Option<f64>) -> Result<(), String> {
+        if let Some(window) = window && !is_positive_finite(window) {
+            return Err(format!("Blocked demand window must be positive, got {}", window));
+        }
+        self.show_blocked_demands = visible;
+        self.blocked_demand_window = window;
+        self.topology_needs_update = true;
+        Ok(())
+    }
+
+    /// 图层显示时的窗口长度（秒）；图层关闭、没有需求或时间轴跨度为零时返回 None
+    pub fn blocked_demand_window_seconds(&self) -> Option<f64> {
+        if !self.show_blocked_demands || self.blocked_demands.is_empty() {
+            return None;
+        }
+        if let Some(window) = self.blocked_demand_window {
+            return Some(window);
+        }
+        let timestamps = self.all_events.iter().map(|event| event.timestamp())
+            .chain(self.blocked_demands.iter().map(|demand| demand.timestamp));
+        let (first, last) = timestamps.fold((f64::INFINITY, f64::NEG_INFINITY), |(first, last), t| (first.min(t), last.max(t)));
+        let span = last - first;
+        (span > 0.0).then_some(span * DEFAULT_BLOCKED_DEMAND_WINDOW_FRACTION)
+    }
+
+    /// getBlockedDemandsInRange：时间戳在 [start, end] 内的需求，按时间排序
+    pub fn blocked_demands_between(&self, start: f64, end: f64) -> &[BlockedDemand] {
+        blocked_demands_in_range(&self.blocked_demands, start, end)
+    }
+
+    /// 当前窗口内（绘制的）需求数；图层关闭时为 0
+    pub fn visible_blocked_demand_count(&self) -> usize {
+        self.blocked_demand_window_seconds().map_or(0, |window| {
+            self.blocked_demands_between(self.current_time_selection - window / 2.0, self.current_time_selection + window / 2.0).len()
+        })
+    }
+}
@@ -0,0 +1,9 @@
+// src/bookmarks.rs
+// 时间轴书签：分析过程中标记的时刻及其说明，随视图保存，加载新的事件时间线时清空
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeBookmark {
+    pub time: f64,
+    pub label: String,
+}
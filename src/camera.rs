@@ -3,6 +3,12 @@
 use glam::{Mat4, Vec2, Vec3, Vec4}; // 引入 glam 库的向量和矩阵类型
 use glam::Vec4Swizzles;
 use bytemuck::{Pod, Zeroable};
+use instant::Instant;
+
+// 平滑缩放的时间常数（秒）：约 3 个时间常数（~120ms）后基本到达目标缩放
+const ZOOM_SMOOTHING_TIME_CONSTANT: f32 = 0.04;
+// 与目标缩放的对数差小于该值时直接对齐目标并结束动画
+const ZOOM_SNAP_LOG_EPSILON: f32 = 1e-3;
 
 // 将发送到 GPU 的相机 Uniform 数据结构
 #[repr(C)]
@@ -10,7 +16,9 @@ use bytemuck::{Pod, Zeroable};
 pub struct CameraUniform {
     pub view_proj: [[f32; 4]; 4], // 视图投影矩阵
     pub needs_srgb_output_conversion: u32, // 0 for false, 1 for true
-    pub _padding: [u32; 3], // 填充到 16 字节边界，使 CameraUniform 总大小为 80 字节
+    pub elapsed_time: f32, // 动画时间 (秒)，仅在有动画时更新
+    pub world_to_pixels: f32, // 每个世界单位对应的屏幕像素数
+    pub highlight_pulse: f32, // 高亮线路的透明度系数，1.0 为不闪烁；同时使 CameraUniform 总大小为 80 字节
 }
 
 #[derive(Debug)]
@@ -23,6 +31,15 @@ pub struct Camera {
     // 鼠标交互状态
     is_panning: bool,
     last_mouse_pos_screen: Option<Vec2>, // 上次鼠标位置 (屏幕坐标) 用于拖拽平移
+    zoom_animation: Option<ZoomAnimation>, // 进行中的平滑缩放
+}
+
+/// 平滑缩放：zoom 按指数衰减趋近 target_zoom，屏幕上 screen_anchor 处的世界点保持不动
+#[derive(Debug, Clone, Copy)]
+struct ZoomAnimation {
+    target_zoom: f32,
+    screen_anchor: Vec2,
+    last_step: Instant,
 }
 
 impl Camera {
@@ -35,6 +52,7 @@ impl Camera {
             viewport_size: Vec2::new(viewport_width as f32, viewport_height as f32),
             is_panning: false,
             last_mouse_pos_screen: None,
+            zoom_animation: None,
         }
     }
 
@@ -98,29 +116,36 @@ impl Camera {
         self.last_mouse_pos_screen = Some(screen_pos);
     }
 
+    pub fn is_panning(&self) -> bool {
+        self.is_panning
+    }
+
     /// 执行平移操作
     pub fn pan(&mut self, current_screen_pos: Vec2) {
         if self.is_panning {
             if let Some(last_pos) = self.last_mouse_pos_screen {
-                let screen_delta = current_screen_pos - last_pos;
+                self.pan_by_screen_delta(current_screen_pos - last_pos);
+            }
+            self.last_mouse_pos_screen = Some(current_screen_pos);
+        }
+    }
 
-                // 计算每个像素在世界坐标中的实际距离
-                let world_visible_width = (2.0 / self.zoom) * self.aspect_ratio;
-                let world_visible_height = 2.0 / self.zoom;
+    /// 按屏幕像素位移平移视图，画面随位移方向移动（与拖拽相同）
+    pub fn pan_by_screen_delta(&mut self, screen_delta: Vec2) {
+        // 计算每个像素在世界坐标中的实际距离
+        let world_visible_width = (2.0 / self.zoom) * self.aspect_ratio;
+        let world_visible_height = 2.0 / self.zoom;
 
-                let world_units_per_pixel_x = world_visible_width / self.viewport_size.x;
-                let world_units_per_pixel_y = world_visible_height / self.viewport_size.y;
+        let world_units_per_pixel_x = world_visible_width / self.viewport_size.x;
+        let world_units_per_pixel_y = world_visible_height / self.viewport_size.y;
 
-                let world_delta_x = screen_delta.x * world_units_per_pixel_x;
-                let world_delta_y = screen_delta.y * world_units_per_pixel_y;
+        let world_delta_x = screen_delta.x * world_units_per_pixel_x;
+        let world_delta_y = screen_delta.y * world_units_per_pixel_y;
 
-                // 更新相机位置。鼠标向右移动 (screen_delta.x > 0)，相机（视图）向左移动 (position.x 减小)
-                // 鼠标向下移动 (screen_delta.y > 0)，相机（视图）向上移动 (position.y 增大，因为世界 Y 轴向上)
-                self.position.x -= world_delta_x;
-                self.position.y += world_delta_y;
-            }
-            self.last_mouse_pos_screen = Some(current_screen_pos);
-        }
+        // 更新相机位置。鼠标向右移动 (screen_delta.x > 0)，相机（视图）向左移动 (position.x 减小)
+        // 鼠标向下移动 (screen_delta.y > 0)，相机（视图）向上移动 (position.y 增大，因为世界 Y 轴向上)
+        self.position.x -= world_delta_x;
+        self.position.y += world_delta_y;
     }
 
     /// 结束平移操作
@@ -141,6 +166,46 @@ impl Camera {
         self.position = world_focus + offset / (self.zoom / old_zoom);
     }
 
+    /// 平滑缩放：把目标缩放乘以 factor，之后由 step_zoom_animation 逐帧趋近。
+    /// 动画进行中再次调用时在当前目标的基础上累积并更新锚点，而不是排队
+    pub fn zoom_smoothly(&mut self, factor: f32, screen_anchor: Vec2) {
+        let (target_zoom, last_step) = match self.zoom_animation {
+            Some(animation) => (animation.target_zoom, animation.last_step),
+            None => (self.zoom, Instant::now()),
+        };
+        self.zoom_animation = Some(ZoomAnimation {
+            target_zoom: (target_zoom * factor).clamp(0.001, 1000.0),
+            screen_anchor,
+            last_step,
+        });
+    }
+
+    /// 推进平滑缩放一帧，返回缩放是否发生了变化（到达目标后返回 false）
+    pub fn step_zoom_animation(&mut self) -> bool {
+        let Some(animation) = self.zoom_animation else {
+            return false;
+        };
+        let now = Instant::now();
+        let dt = (now - animation.last_step).as_secs_f32();
+        let remaining_log_zoom = (animation.target_zoom / self.zoom).ln();
+        let factor = if remaining_log_zoom.abs() < ZOOM_SNAP_LOG_EPSILON {
+            self.zoom_animation = None;
+            animation.target_zoom / self.zoom
+        } else {
+            self.zoom_animation = Some(ZoomAnimation { last_step: now, ..animation });
+            (remaining_log_zoom * (1.0 - (-dt / ZOOM_SMOOTHING_TIME_CONSTANT).exp())).exp()
+        };
+        // 每帧按锚点当前对应的世界坐标重新计算位置修正（期间可能发生了拖拽平移）
+        let anchor_world = self.screen_to_world(animation.screen_anchor);
+        self.zoom_by(factor, anchor_world);
+        true
+    }
+
+    /// 直接设置缩放（适配视图、键盘缩放等）前调用，放弃未完成的平滑缩放
+    pub fn stop_zoom_animation(&mut self) {
+        self.zoom_animation = None;
+    }
+
     /// 构建视图投影矩阵
     pub fn build_view_projection_matrix(&self) -> Mat4 {
         // 正交投影矩阵: 定义世界空间中可见的区域。
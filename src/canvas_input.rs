@@ -0,0 +1,87 @@
+// src/canvas_input.rs
+// 画布上的浏览器指针事件（仅 wasm）：拖动（平移、拖动节点）期间捕获指针，光标离开画布后拖动不会中断；
+// 拖动期间阻止浏览器右键菜单。监听器在 Drop 时移除，destroyView 后不会残留。
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Event, HtmlCanvasElement, PointerEvent};
+
+pub struct CanvasInput {
+    canvas: HtmlCanvasElement,
+    /// 最近一次按下的指针 id（winit 的 MouseInput 不带指针 id）
+    last_pointer_id: Rc<Cell<Option<i32>>>,
+    /// 正在拖动时捕获的指针 id
+    captured_pointer_id: Cell<Option<i32>>,
+    drag_active: Rc<Cell<bool>>,
+    on_pointer_down: Closure<dyn FnMut(PointerEvent)>,
+    on_context_menu: Closure<dyn FnMut(Event)>,
+}
+
+impl CanvasInput {
+    pub fn new(canvas: HtmlCanvasElement) -> Self {
+        let last_pointer_id = Rc::new(Cell::new(None));
+        let drag_active = Rc::new(Cell::new(false));
+
+        let on_pointer_down = {
+            let last_pointer_id = last_pointer_id.clone();
+            Closure::<dyn FnMut(PointerEvent)>::new(move |event: PointerEvent| {
+                last_pointer_id.set(Some(event.pointer_id()));
+            })
+        };
+        let on_context_menu = {
+            let drag_active = drag_active.clone();
+            Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+                if drag_active.get() {
+                    event.prevent_default();
+                }
+            })
+        };
+
+        if let Err(e) = canvas.add_event_listener_with_callback("pointerdown", on_pointer_down.as_ref().unchecked_ref()) {
+            log::warn!("Failed to add pointerdown listener: {:?}", e);
+        }
+        if let Err(e) = canvas.add_event_listener_with_callback("contextmenu", on_context_menu.as_ref().unchecked_ref()) {
+            log::warn!("Failed to add contextmenu listener: {:?}", e);
+        }
+
+        Self {
+            canvas,
+            last_pointer_id,
+            captured_pointer_id: Cell::new(None),
+            drag_active,
+            on_pointer_down,
+            on_context_menu,
+        }
+    }
+
+    /// 开始平移或拖动节点时调用
+    pub fn begin_drag(&self) {
+        self.drag_active.set(true);
+        let Some(pointer_id) = self.last_pointer_id.get() else {
+            return;
+        };
+        match self.canvas.set_pointer_capture(pointer_id) {
+            Ok(()) => self.captured_pointer_id.set(Some(pointer_id)),
+            Err(e) => log::debug!("Failed to capture pointer {}: {:?}", pointer_id, e),
+        }
+    }
+
+    /// 松开按键时调用
+    pub fn end_drag(&self) {
+        self.drag_active.set(false);
+        if let Some(pointer_id) = self.captured_pointer_id.take() {
+            if self.canvas.has_pointer_capture(pointer_id) {
+                let _ = self.canvas.release_pointer_capture(pointer_id);
+            }
+        }
+    }
+}
+
+impl Drop for CanvasInput {
+    fn drop(&mut self) {
+        self.end_drag();
+        let _ = self.canvas.remove_event_listener_with_callback("pointerdown", self.on_pointer_down.as_ref().unchecked_ref());
+        let _ = self.canvas.remove_event_listener_with_callback("contextmenu", self.on_context_menu.as_ref().unchecked_ref());
+    }
+}
@@ -0,0 +1,77 @@
+// src/capacity_bars.rs
+// 链路容量条：在每条链路中点绘制一个与链路垂直的小条，填充比例为当前时刻占用的波长数 / 信道规划的波长数（max_wavelengths）。
+// 条的大小随缩放变化但限制在可读的屏幕尺寸内，因此在相机或几何变化时按当前缩放重新生成（每条链路 4 个三角形），
+// 不进入 SceneGeometry；节点在屏幕上过小时不绘制。
+use bevy_color::{ColorToComponents, LinearRgba, Srgba};
+use glam::Vec2;
+
+use crate::app_state::State;
+use crate::models::ThickLineVertex;
+use crate::scene::edge_bundles::polyline_midpoint;
+use crate::scene::geometry::{occupancy_color, push_thick_line_segment};
+
+/// 节点屏幕半径小于该值时不绘制容量条
+const CAPACITY_BAR_MIN_NODE_RADIUS_PX: f32 = 8.0;
+/// 容量条的世界长度（节点半径的倍数，随缩放变化），屏幕长度限制在 [MIN, MAX] 像素内
+const CAPACITY_BAR_LENGTH_NODE_RADII: f32 = 1.5;
+const CAPACITY_BAR_MIN_LENGTH_PX: f32 = 24.0;
+const CAPACITY_BAR_MAX_LENGTH_PX: f32 = 64.0;
+const CAPACITY_BAR_ASPECT: f32 = 0.2; // 宽度与长度之比
+
+impl State {
+    pub fn set_capacity_bars(&mut self, visible: bool) {
+        self.show_capacity_bars = visible;
+        self.capacity_bars_need_update = true;
+    }
+
+    /// 按当前缩放和 geometry.link_occupancy 重新生成容量条并上传
+    pub fn update_capacity_bars(&mut self) {
+        self.capacity_bar_vertices.clear();
+        let world_to_pixels = self.camera.world_radius_to_screen_pixels(1.0);
+        let node_screen_radius = world_to_pixels * self.node_radius;
+        if self.show_capacity_bars && node_screen_radius >= CAPACITY_BAR_MIN_NODE_RADIUS_PX {
+            let bar_length_px = (CAPACITY_BAR_LENGTH_NODE_RADII * self.node_radius * world_to_pixels).clamp(CAPACITY_BAR_MIN_LENGTH_PX, CAPACITY_BAR_MAX_LENGTH_PX);
+            let bar_length = bar_length_px / world_to_pixels;
+            let bar_width = bar_length * CAPACITY_BAR_ASPECT;
+            let background_color = LinearRgba::from(Srgba::rgba_u8(90, 90, 90, 200)).to_f32_array();
+            let (world_visible_min, world_visible_max) = self.camera.get_world_clip_bounds();
+
+            for link in &self.geometry.link_occupancy {
+                let (Some(source), Some(target)) = (
+                    self.geometry.circle_instances.get(link.source_idx),
+                    self.geometry.circle_instances.get(link.target_idx),
+                ) else {
+                    continue;
+                };
+                let source_pos = Vec2::from_array(source.position);
+                let target_pos = Vec2::from_array(target.position);
+                // 开启边绑定时放在绑定后折线的中点，与该处的线段垂直
+                let bundled = self.edge_bundles
+                    .as_ref()
+                    .filter(|_| self.edge_bundling_enabled)
+                    .and_then(|bundles| bundles.bend(link.source_idx, link.target_idx, source_pos, target_pos, source_pos, target_pos));
+                let points = bundled.unwrap_or_else(|| vec![source_pos, target_pos]);
+                let Some((midpoint, link_dir)) = polyline_midpoint(&points) else {
+                    continue;
+                };
+                if (midpoint + bar_length).cmplt(world_visible_min).any() || (midpoint - bar_length).cmpgt(world_visible_max).any() {
+                    continue;
+                }
+
+                // 条沿链路的法线方向，从一端开始填充
+                let bar_dir = link_dir.perp();
+                let bar_start = midpoint - bar_dir * (bar_length / 2.0);
+                let fraction = link.fraction(self.channel_plan.max_wavelengths);
+                push_thick_line_segment(
+                    &mut self.capacity_bar_vertices, bar_start, bar_start + bar_dir * bar_length,
+                    background_color, bar_width, 0.0, ThickLineVertex::SOLID,
+                );
+                push_thick_line_segment(
+                    &mut self.capacity_bar_vertices, bar_start, bar_start + bar_dir * (bar_length * fraction),
+                    occupancy_color(fraction), bar_width, 0.0, ThickLineVertex::SOLID,
+                );
+            }
+        }
+        self.renderer.upload_capacity_bar_vertices(&self.device, &self.queue, &self.capacity_bar_vertices);
+    }
+}
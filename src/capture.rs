@@ -0,0 +1,169 @@
+// src/capture.rs
+// 离屏截图：把当前帧绘制到与 surface 同格式的纹理（不影响屏幕上的帧）并异步读回像素。
+// 回读方式与 GpuPicker 相同：提交后由 State::update() 轮询映射结果，WebGPU 上回调由浏览器事件循环触发。
+use anyhow::{anyhow, Context};
+
+use crate::app_state::State;
+
+/// 一帧渲染结果，RGBA8（sRGB），按行紧密排列
+#[derive(Debug)]
+pub struct Snapshot {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Snapshot {
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn encode_png(&self) -> anyhow::Result<Vec<u8>> {
+        let image = image::RgbaImage::from_raw(self.width, self.height, self.pixels.clone())
+            .ok_or_else(|| anyhow!("Snapshot pixel buffer does not match its {}x{} size", self.width, self.height))?;
+        let mut png = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut png, image::ImageOutputFormat::Png)?;
+        Ok(png.into_inner())
+    }
+}
+
+/// 已提交、等待映射的截图回读
+pub struct PendingCapture {
+    readback_buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    is_bgra: bool,
+    map_receiver: flume::Receiver<Result<(), wgpu::BufferAsyncError>>,
+    reply: flume::Sender<anyhow::Result<Snapshot>>,
+}
+
+impl PendingCapture {
+    /// 映射完成时把结果发送给请求方并返回 true；仍在等待时返回 false
+    fn poll(&self) -> bool {
+        let result = match self.map_receiver.try_recv() {
+            Ok(result) => result,
+            Err(flume::TryRecvError::Empty) => return false,
+            Err(flume::TryRecvError::Disconnected) => Err(wgpu::BufferAsyncError),
+        };
+        let snapshot = result.context("Failed to map screenshot readback buffer").map(|()| self.read_pixels());
+        let _ = self.reply.send(snapshot);
+        true
+    }
+
+    fn read_pixels(&self) -> Snapshot {
+        let unpadded_bytes_per_row = (self.width * 4) as usize;
+        let mut pixels: Vec<u8> = {
+            let padded = self.readback_buffer.slice(..).get_mapped_range();
+            padded
+                .chunks(self.padded_bytes_per_row as usize)
+                .flat_map(|row| &row[..unpadded_bytes_per_row])
+                .copied()
+                .collect()
+        };
+        self.readback_buffer.unmap();
+        if self.is_bgra {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        Snapshot { width: self.width, height: self.height, pixels }
+    }
+}
+
+impl State {
+    /// 把当前视图绘制到离屏纹理并请求回读，结果在之后的 update()（或 poll_frame_captures）中通过 reply 发送。
+    /// size 为 None 时分辨率与 surface 相同（物理像素）；指定其他尺寸时相机中心和缩放不变，只改变宽高比
+    pub fn request_frame_capture(&mut self, size: Option<(u32, u32)>, reply: flume::Sender<anyhow::Result<Snapshot>>) {
+        let format = self.config.format;
+        let is_bgra = match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            other => {
+                let _ = reply.send(Err(anyhow!("Screenshots are not supported for surface format {:?}", other)));
+                return;
+            }
+        };
+
+        let surface_size = (self.config.width, self.config.height);
+        let (width, height) = size.unwrap_or(surface_size);
+        let max_dimension = self.device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max_dimension || height > max_dimension {
+            let _ = reply.send(Err(anyhow!("Capture size {}x{} must be between 1 and {}", width, height, max_dimension)));
+            return;
+        }
+        let texture_size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Texture"),
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        if (width, height) == surface_size {
+            self.render_to_view(&view);
+        } else {
+            // 临时按截图尺寸绘制（不重新配置 surface），之后恢复，下一次 update() 重新上传相机
+            self.config.width = width;
+            self.config.height = height;
+            self.apply_view_size();
+            self.upload_camera_uniform();
+            self.render_to_view(&view);
+            self.config.width = surface_size.0;
+            self.config.height = surface_size.1;
+            self.apply_view_size();
+            self.camera_needs_update = true;
+        }
+
+        // 拷贝时每行字节数必须按 COPY_BYTES_PER_ROW_ALIGNMENT 对齐，读回后再去掉填充
+        let padded_bytes_per_row = (width * 4).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Readback Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Capture Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            texture_size,
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let (map_sender, map_receiver) = flume::bounded(1);
+        readback_buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let _ = map_sender.send(result);
+        });
+        self.pending_captures.push(PendingCapture {
+            readback_buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            is_bgra,
+            map_receiver,
+            reply,
+        });
+    }
+
+    /// 处理已完成的截图回读，返回是否还有未完成的截图
+    pub fn poll_frame_captures(&mut self) -> bool {
+        if self.pending_captures.is_empty() {
+            return false;
+        }
+        // WebGPU 上映射回调由浏览器事件循环触发，这里的 poll 没有作用
+        let _ = self.device.poll(wgpu::PollType::Poll);
+        self.pending_captures.retain(|capture| !capture.poll());
+        !self.pending_captures.is_empty()
+    }
+}
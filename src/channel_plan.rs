@@ -0,0 +1,43 @@
+// src/channel_plan.rs
+// 信道规划（settings::ChannelPlan）的应用：setChannelPlan、setNumChannels、拓扑数据中的 channel_plan 字段和会话导入
+// 都经过 State::set_channel_plan。波长数决定服务线路的颜色、在链路内的位置以及占用率的分母，
+// 展开角度决定线路和链路边界在节点圆周上的位置，因此变化后重新生成所有线路。
+// 波长数变化后已加载事件的波长检查（events_wavelength_out_of_range 等）按新的波长数重新运行。
+use crate::app_state::State;
+use crate::errors::{self, ViewError};
+use crate::scene::validation::{check_timeline_events, ValidationIssue, TIMELINE_CHECK_CODES};
+use crate::settings::ChannelPlan;
+
+impl State {
+    /// 无效的规划（包括超过 MAX_WAVELENGTHS 的波长数）报告警告后忽略，保持当前设置。
+    /// 波长数变化后重新检查已加载的事件（波长是否在范围内），验证报告因此变化时返回 true
+    pub fn set_channel_plan(&mut self, channel_plan: ChannelPlan) -> bool {
+        if let Err(e) = channel_plan.validate() {
+            errors::report(ViewError::warning("invalid_channel_plan", format!("Ignoring invalid channel plan: {}", e)));
+            return false;
+        }
+        if self.channel_plan == channel_plan {
+            return false;
+        }
+        let wavelengths_changed = self.channel_plan.max_wavelengths != channel_plan.max_wavelengths;
+        log::info!(
+            "Channel plan set to {} wavelengths (spread {:.4} rad, boundary {:.4} rad).",
+            channel_plan.max_wavelengths, channel_plan.spread_angle, channel_plan.boundary_angle
+        );
+        self.channel_plan = channel_plan;
+        self.topology_needs_update = true;
+        self.capacity_bars_need_update = true; // 占用率的分母变化
+        wavelengths_changed && self.recheck_timeline_events()
+    }
+
+    /// 按当前信道数重新运行事件检查，替换上一次的结果；结果变化时返回 true
+    fn recheck_timeline_events(&mut self) -> bool {
+        let check_messages = |issues: &[ValidationIssue]| -> Vec<String> {
+            issues.iter().filter(|issue| TIMELINE_CHECK_CODES.contains(&issue.code)).map(|issue| issue.message.clone()).collect()
+        };
+        let previous = check_messages(&self.validation_report.issues);
+        self.validation_report.issues.retain(|issue| !TIMELINE_CHECK_CODES.contains(&issue.code));
+        check_timeline_events(&self.all_events, self.channel_plan.max_wavelengths, &mut self.validation_report);
+        previous != check_messages(&self.validation_report.issues)
+    }
+}
@@ -0,0 +1,112 @@
+// src/color_legend.rs
+// 服务线路颜色的图例（getColorLegend）：波长模式列出若干波长编号的颜色，GSNR 模式列出 dB 余量刻度，
+// 并给出没有 GSNR 数据的服务使用的灰色。颜色与未高亮时的服务线路相同，为 "#rrggbb"（sRGB）。
+// 按利用率设置透明度（OpacityMode::Utilization）时另外列出利用率到不透明度的映射。
+use bevy_color::{Oklcha, Srgba};
+use serde::Serialize;
+
+use crate::app_state::State;
+use crate::scene::geometry::{wavelength_hue, SERVICE_CHROMA, SERVICE_LIGHTNESS};
+use crate::settings::{ColorMode, OpacityMode};
+
+/// 波长模式中列出的波长编号数（均匀分布在 0 到 max_wavelengths - 1 之间）
+const WAVELENGTH_LEGEND_STOPS: u32 = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ColorLegend {
+    /// "wavelength" 或 "gsnr"
+    pub mode: &'static str,
+    /// 刻度值的单位："channel" 或 "dB"（GSNR 余量 gsnr - snr_requirement）
+    pub unit: &'static str,
+    pub stops: Vec<LegendStop>,
+    /// GSNR 模式中 gsnr 为 NaN 或 0 的服务的颜色
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_data_color: Option<String>,
+    /// 按利用率设置透明度时的映射；不透明模式下省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<OpacityLegend>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpacityLegend {
+    /// "utilization"
+    pub mode: &'static str,
+    pub stops: Vec<OpacityStop>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpacityStop {
+    /// 利用率（0 到 1）
+    pub value: f32,
+    pub label: String,
+    pub alpha: f32,
+}
+
+/// 不透明度图例中列出的利用率刻度
+const UTILIZATION_LEGEND_STOPS: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LegendStop {
+    pub value: f32,
+    pub label: String,
+    pub color: String,
+}
+
+fn service_color_hex(hue: f32, chroma: f32) -> String {
+    Srgba::from(Oklcha::lch(SERVICE_LIGHTNESS, chroma, hue)).to_hex()
+}
+
+impl State {
+    pub fn color_legend(&self) -> ColorLegend {
+        ColorLegend { opacity: self.opacity_legend(), ..self.color_stops_legend() }
+    }
+
+    fn opacity_legend(&self) -> Option<OpacityLegend> {
+        (self.opacity_mode == OpacityMode::Utilization).then(|| OpacityLegend {
+            mode: self.opacity_mode.as_str(),
+            stops: UTILIZATION_LEGEND_STOPS
+                .into_iter()
+                .map(|utilization| OpacityStop {
+                    value: utilization,
+                    label: format!("{:.0}%", utilization * 100.0),
+                    alpha: self.opacity_mode.alpha(utilization),
+                })
+                .collect(),
+        })
+    }
+
+    fn color_stops_legend(&self) -> ColorLegend {
+        match self.color_mode {
+            ColorMode::Wavelength => {
+                let num_channels = self.channel_plan.max_wavelengths;
+                let max_wavelength = num_channels.saturating_sub(1);
+                let stops = (0..WAVELENGTH_LEGEND_STOPS)
+                    .map(|i| (max_wavelength * i / (WAVELENGTH_LEGEND_STOPS - 1)) as i32)
+                    .map(|wavelength| LegendStop {
+                        value: wavelength as f32,
+                        label: format!("λ{}", wavelength),
+                        color: service_color_hex(wavelength_hue(wavelength, num_channels), SERVICE_CHROMA),
+                    })
+                    .collect();
+                ColorLegend { mode: self.color_mode.as_str(), unit: "channel", stops, no_data_color: None, opacity: None }
+            }
+            ColorMode::Gsnr { ramp } => {
+                let stops = [ramp.red_below_db, ramp.red_below_db / 2.0, 0.0, ramp.green_above_db / 2.0, ramp.green_above_db]
+                    .into_iter()
+                    .map(|margin_db| LegendStop {
+                        value: margin_db,
+                        label: format!("{:+.1} dB", margin_db),
+                        color: service_color_hex(ramp.hue(margin_db), SERVICE_CHROMA),
+                    })
+                    .collect();
+                ColorLegend {
+                    mode: self.color_mode.as_str(),
+                    unit: "dB",
+                    stops,
+                    no_data_color: Some(service_color_hex(0.0, 0.0)),
+                    opacity: None,
+                }
+            }
+        }
+    }
+}
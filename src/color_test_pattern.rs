@@ -0,0 +1,76 @@
+// src/color_test_pattern.rs
+// sRGB 输出路径的调试工具：renderColorTestPattern 在画布左下角绘制一排已知 sRGB 值的色块（叠加层管线），
+// forceSrgbConversion 在运行时覆盖 CameraUniform::needs_srgb_output_conversion，便于对比颜色发灰是标志设置错误还是转换本身有误。
+// 无论表面格式是否为 sRGB，路径正确时截图读回的每个色块都应等于 COLOR_TEST_SWATCHES 中的 sRGB 值（±1），
+// headless::check_color_test_pattern 据此检查 Rgba8Unorm（着色器转换）和 Rgba8UnormSrgb（硬件转换）两种目标。
+// 文字由 glyphon 按创建时的表面格式选择颜色模式，不受覆盖影响。
+use bevy_color::{ColorToComponents, LinearRgba, Srgba};
+use glam::Vec2;
+
+use crate::app_state::State;
+use crate::models::LineVertex;
+use crate::overlay::push_screen_rect;
+
+const SWATCH_SIZE_PX: f32 = 40.0;
+const SWATCH_MARGIN_PX: f32 = 8.0;
+
+/// 参考色块，srgb 为期望读回的 8 位 sRGB 值
+#[derive(Debug, Clone, Copy)]
+pub struct ColorSwatch {
+    pub name: &'static str,
+    pub srgb: [u8; 3],
+}
+
+pub const COLOR_TEST_SWATCHES: [ColorSwatch; 7] = [
+    ColorSwatch { name: "gray_50", srgb: [128, 128, 128] },
+    ColorSwatch { name: "red", srgb: [255, 0, 0] },
+    ColorSwatch { name: "green", srgb: [0, 255, 0] },
+    ColorSwatch { name: "blue", srgb: [0, 0, 255] },
+    ColorSwatch { name: "white", srgb: [255, 255, 255] },
+    ColorSwatch { name: "black", srgb: [0, 0, 0] },
+    ColorSwatch { name: "node", srgb: [0x00, 0x5d, 0x5d] }, // 未高亮节点的颜色（default_node_color）
+];
+
+/// 各色块在画布上的像素矩形（左下角起一行），画布放不下的色块被跳过
+pub fn swatch_rects(canvas_size: Vec2) -> impl Iterator<Item = (ColorSwatch, Vec2, Vec2)> {
+    COLOR_TEST_SWATCHES.into_iter().enumerate().filter_map(move |(i, swatch)| {
+        let min = Vec2::new(
+            SWATCH_MARGIN_PX + i as f32 * (SWATCH_SIZE_PX + SWATCH_MARGIN_PX),
+            canvas_size.y - SWATCH_MARGIN_PX - SWATCH_SIZE_PX,
+        );
+        let max = min + Vec2::splat(SWATCH_SIZE_PX);
+        (min.y >= 0.0 && max.x <= canvas_size.x).then_some((swatch, min, max))
+    })
+}
+
+impl State {
+    /// renderColorTestPattern
+    pub fn set_color_test_pattern(&mut self, visible: bool) {
+        log::info!("Color test pattern {}.", if visible { "shown" } else { "hidden" });
+        self.show_color_test_pattern = visible;
+    }
+
+    /// forceSrgbConversion：Some 强制开启 / 关闭着色器的 sRGB 编码，None 恢复为按表面格式决定
+    pub fn force_srgb_conversion(&mut self, force: Option<bool>) {
+        let enabled = force.unwrap_or(!self.config.format.is_srgb());
+        log::info!(
+            "Shader sRGB output conversion {} ({}, surface format {:?}).",
+            if enabled { "enabled" } else { "disabled" },
+            if force.is_some() { "forced" } else { "automatic" },
+            self.config.format
+        );
+        self.camera_uniform.needs_srgb_output_conversion = enabled as u32;
+        self.renderer_info.shader_srgb_conversion = enabled;
+        self.upload_camera_uniform();
+    }
+
+    /// 色块在叠加层中最后绘制，覆盖面板和提示框
+    pub fn push_color_test_pattern_vertices(&self, vertices: &mut Vec<LineVertex>) {
+        let canvas_size = Vec2::new(self.config.width as f32, self.config.height as f32);
+        for (swatch, min, max) in swatch_rects(canvas_size) {
+            let [r, g, b] = swatch.srgb;
+            let color = LinearRgba::from(Srgba::rgb_u8(r, g, b)).to_f32_array();
+            push_screen_rect(vertices, min, max, canvas_size, color);
+        }
+    }
+}
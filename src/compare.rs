@@ -0,0 +1,96 @@
+// src/compare.rs
+// 对比视图：画布左右各一半，左半部分为 current_time_selection（t1）的拓扑，右半部分为 t2 的拓扑。
+// 两半共用一个相机（按半幅宽度计算宽高比），分别通过 set_viewport 绘制；右半部分使用单独的一套几何和
+// GPU 缓冲区（第二个 Renderer）。高亮、最短路径和测量在两边同时显示；鼠标坐标换算到所在一半的本地坐标，
+// 因此平移、缩放和拾取对两边同样生效（拾取使用左半部分的服务线路）。流动动画只在左半部分显示。
+use glam::Vec2;
+
+use crate::app_state::State;
+use crate::notifications::TimeChangeReason;
+use crate::renderer::Renderer;
+use crate::scene::geometry::{GeometryInputs, SceneGeometry};
+
+pub struct CompareView {
+    pub time: f64, // 右半部分的时刻 t2
+    pub geometry: SceneGeometry,
+    pub renderer: Renderer,
+}
+
+impl State {
+    pub fn is_comparing(&self) -> bool {
+        self.compare.is_some()
+    }
+
+    /// 左半部分跳到 t1（发送 reason 为 "api" 的 timeChanged 通知），右半部分显示 t2；高亮保持不变
+    pub fn set_compare_times(&mut self, t1: f64, t2: f64) -> Result<(), String> {
+        if !t1.is_finite() || !t2.is_finite() {
+            return Err(format!("Compare times must be finite, got {} and {}", t1, t2));
+        }
+        match self.compare.as_mut() {
+            Some(compare) => compare.time = t2,
+            None => {
+                let renderer = Renderer::new(&self.device, self.config.format, &self.camera_uniform, &SceneGeometry::default());
+                self.compare = Some(CompareView { time: t2, geometry: SceneGeometry::default(), renderer });
+                self.apply_view_size();
+                self.camera_needs_update = true;
+                self.selection_needs_update = true;
+            }
+        }
+        log::info!("Comparing t1 = {} with t2 = {}.", t1, t2);
+        self.set_time_selection(t1, TimeChangeReason::Api);
+        Ok(())
+    }
+
+    pub fn clear_compare(&mut self) {
+        if self.compare.take().is_some() {
+            self.apply_view_size();
+            self.camera_needs_update = true;
+        }
+    }
+
+    /// 每一半的尺寸（物理像素）；不对比时为整个画布
+    pub fn view_size(&self) -> (u32, u32) {
+        if self.is_comparing() {
+            ((self.config.width / 2).max(1), self.config.height)
+        } else {
+            (self.config.width, self.config.height)
+        }
+    }
+
+    /// 按 view_size 更新相机的宽高比
+    pub fn apply_view_size(&mut self) {
+        let (width, height) = self.view_size();
+        self.camera.update_aspect_ratio(width, height);
+    }
+
+    /// 把画布坐标换算为所在一半的本地坐标
+    pub fn view_local_position(&self, screen_pos: Vec2) -> Vec2 {
+        let half_width = self.view_size().0 as f32;
+        if self.is_comparing() && screen_pos.x >= half_width {
+            Vec2::new(screen_pos.x - half_width, screen_pos.y)
+        } else {
+            screen_pos
+        }
+    }
+
+    /// 在 t2 重新生成右半部分的几何并上传。与左半部分不同，一次生成完（不分帧）
+    pub fn update_compare_geometry(&mut self) {
+        let Some(mut compare) = self.compare.take() else {
+            return;
+        };
+        let mut staging = SceneGeometry {
+            circle_instances: self.geometry.circle_instances.clone(),
+            ..Default::default()
+        };
+        self.refresh_wavelength_colors();
+        let inputs = GeometryInputs { current_time: compare.time, flow_animation: false, ..self.geometry_inputs() };
+        let mut build = staging.begin_regenerate(&inputs);
+        staging.push_services(&mut build, &inputs, usize::MAX);
+        staging.finish_regenerate(build, &inputs);
+        staging.cull_circle_instances(None); // 对比视图只在生成时上传节点实例，不随平移重新裁剪
+        compare.geometry = staging;
+        compare.renderer.upload_geometry(&self.device, &self.queue, &compare.geometry);
+        compare.renderer.upload_selection_instances(&self.queue, &self.selection_instances);
+        self.compare = Some(compare);
+    }
+}
@@ -0,0 +1,101 @@
+// src/edge_bundling.rs
+// 边绑定开关（setEdgeBundling）：开启后链路边界、服务线路、链路占用四边形、最短路径和容量条沿绑定后的折线绘制，
+// 见 scene/edge_bundles.rs。绑定在加载拓扑和节点移动后按节点位置重新计算，每帧最多占用 BUNDLING_FRAME_BUDGET_MS 毫秒，
+// 完成前按直线绘制；结果缓存在 State::edge_bundles 中，只改变强度时不重新计算。
+// 绑定只改变线路的形状，拾取和占用率仍然对应原来的链路和服务的每一跳。
+use glam::Vec2;
+
+use crate::app_state::State;
+use crate::errors::{self, ViewError};
+use crate::scene::edge_bundles::{EdgeBundlingJob, MAX_BUNDLED_EDGES};
+use crate::scene::path_index::known_hop;
+
+/// 每帧用于计算绑定的时间上限（毫秒）
+const BUNDLING_FRAME_BUDGET_MS: f64 = 4.0;
+
+pub fn validate_bundling_strength(strength: f32) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&strength) {
+        return Err(format!("edge bundling strength must be between 0 and 1, got {}", strength));
+    }
+    Ok(())
+}
+
+impl State {
+    /// setEdgeBundling：strength 为 0 到 1，0 相当于直线
+    pub fn set_edge_bundling(&mut self, enabled: bool, strength: f32) {
+        if let Err(e) = validate_bundling_strength(strength) {
+            errors::report(ViewError::warning("invalid_edge_bundling", format!("Ignoring edge bundling settings: {}", e)));
+            return;
+        }
+        log::info!("Edge bundling {} (strength {:.2}).", if enabled { "enabled" } else { "disabled" }, strength);
+        self.edge_bundling_enabled = enabled;
+        self.edge_bundling_strength = strength;
+        if let Some(bundles) = self.edge_bundles.as_mut() {
+            bundles.strength = strength;
+        }
+        if !enabled {
+            self.edge_bundling_job = None;
+        }
+        self.topology_needs_update = true;
+        self.capacity_bars_need_update = true;
+    }
+
+    fn node_positions(&self) -> Vec<Vec2> {
+        self.geometry.circle_instances.iter().map(|instance| Vec2::from_array(instance.position)).collect()
+    }
+
+    /// 重新生成几何之前调用：节点位置或链路与缓存的绑定（或正在进行的计算）不一致时丢弃旧结果并重新开始计算，
+    /// 完成前按直线绘制。链路超过 MAX_BUNDLED_EDGES 条时不绑定，链路变化后提示一次（拖动节点不重复提示）
+    pub fn refresh_edge_bundling(&mut self) {
+        if !self.edge_bundling_enabled {
+            return;
+        }
+        let node_positions = self.node_positions();
+        let links = self.bundled_links();
+        let previous = match &self.edge_bundling_job {
+            Some(job) => Some((&job.node_positions, &job.links)),
+            None => self.edge_bundles.as_ref().map(|bundles| (&bundles.node_positions, &bundles.links)),
+        };
+        if previous.is_some_and(|previous| previous == (&node_positions, &links)) {
+            return;
+        }
+        let links_changed = previous.is_none_or(|(_, previous_links)| *previous_links != links);
+        log::debug!("Starting edge bundling for {} links.", links.len());
+        let job = EdgeBundlingJob::new(node_positions, links);
+        if job.over_edge_limit && links_changed {
+            errors::report(ViewError::warning(
+                "edge_bundling_too_many_links",
+                format!("Edge bundling is limited to {} links; drawing {} links as straight lines.", MAX_BUNDLED_EDGES, job.links.len()),
+            ));
+        }
+        self.edge_bundles = None;
+        self.edge_bundling_job = Some(job);
+    }
+
+    pub fn is_edge_bundling_pending(&self) -> bool {
+        self.edge_bundling_job.is_some()
+    }
+
+    /// 每帧调用一次；完成时缓存结果并按绑定后的形状重新生成线路
+    pub fn advance_edge_bundling(&mut self) {
+        let Some(job) = self.edge_bundling_job.as_mut() else {
+            return;
+        };
+        if let Some(mut bundles) = job.advance(BUNDLING_FRAME_BUDGET_MS) {
+            log::info!("Edge bundling finished for {} links.", bundles.links.len());
+            bundles.strength = self.edge_bundling_strength;
+            self.edge_bundles = Some(bundles);
+            self.edge_bundling_job = None;
+            self.topology_needs_update = true;
+            self.capacity_bars_need_update = true;
+        }
+    }
+
+    /// 链路两端的节点索引（引用不存在节点的链路被忽略）
+    fn bundled_links(&self) -> Vec<(usize, usize)> {
+        self.connection_endpoints
+            .iter()
+            .filter_map(|&(source_idx, target_idx)| known_hop(source_idx, target_idx))
+            .collect()
+    }
+}
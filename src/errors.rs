@@ -0,0 +1,160 @@
+// src/errors.rs
+// 运行时错误通道：渲染错误、GPU 错误（包括设备丢失）和命令校验失败除了写入日志，还转发给 JS 回调
+// （WasmApi::setErrorCallback），以便宿主页面提示用户。
+// 错误先进入队列，在当前任务结束后的微任务中统一回调，不会在渲染或命令处理过程中同步调用 JS；
+// 同一错误（code 和 message 相同）在 DEDUP_WINDOW_SECS 内只报告一次，之后再次报告时附带期间被丢弃的次数。
+use std::sync::Mutex;
+
+use instant::Instant;
+use serde::Serialize;
+
+/// 相同错误的去重窗口（秒）
+const DEDUP_WINDOW_SECS: f32 = 5.0;
+/// 最多记录的不同错误数，超出时丢弃窗口已过期的记录
+const MAX_RECENT_ERRORS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorSeverity {
+    /// 命令被忽略或部分生效，画面不受影响
+    Warning,
+    /// 某一帧或某个操作失败，之后可能恢复
+    Error,
+    /// 视图无法继续工作（例如 GPU 设备丢失），需要重新创建
+    Fatal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ViewError {
+    pub severity: ErrorSeverity,
+    /// 机器可读的错误类别，例如 "unknown_node"
+    pub code: &'static str,
+    pub message: String,
+    /// 与错误相关的数据，例如出错的节点 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<serde_json::Value>,
+    /// 上次报告之后因去重被丢弃的相同错误数
+    pub repeated: u32,
+}
+
+impl ViewError {
+    pub fn new(severity: ErrorSeverity, code: &'static str, message: impl Into<String>) -> Self {
+        Self { severity, code, message: message.into(), context: None, repeated: 0 }
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(ErrorSeverity::Warning, code, message)
+    }
+
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(ErrorSeverity::Error, code, message)
+    }
+
+    pub fn fatal(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(ErrorSeverity::Fatal, code, message)
+    }
+
+    pub fn with_context(mut self, context: serde_json::Value) -> Self {
+        self.context = Some(context);
+        self
+    }
+}
+
+struct RecentError {
+    code: &'static str,
+    message: String,
+    reported_at: Instant,
+    suppressed: u32,
+}
+
+/// 去重记录和待回调的错误。GPU 回调可能在其他线程中调用，因此使用 Mutex 而不是 thread_local
+struct ErrorQueue {
+    recent: Vec<RecentError>,
+    pending: Vec<ViewError>,
+    flush_scheduled: bool,
+}
+
+static ERROR_QUEUE: Mutex<ErrorQueue> = Mutex::new(ErrorQueue { recent: Vec::new(), pending: Vec::new(), flush_scheduled: false });
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    static ERROR_CALLBACK: std::cell::RefCell<Option<js_sys::Function>> = const { std::cell::RefCell::new(None) };
+}
+
+/// 注册或取消（None）错误回调。可随时调用：取消后队列中尚未回调的错误被丢弃
+#[cfg(target_arch = "wasm32")]
+pub fn set_error_callback(callback: Option<js_sys::Function>) {
+    ERROR_CALLBACK.with(|cell| *cell.borrow_mut() = callback);
+}
+
+/// 写入日志，并（在去重之后）排队等待回调
+pub fn report(mut error: ViewError) {
+    match error.severity {
+        ErrorSeverity::Warning => log::warn!("[{}] {}", error.code, error.message),
+        ErrorSeverity::Error | ErrorSeverity::Fatal => log::error!("[{}] {}", error.code, error.message),
+    }
+
+    let Ok(mut queue) = ERROR_QUEUE.lock() else {
+        return;
+    };
+    let now = Instant::now();
+    match queue.recent.iter_mut().find(|recent| recent.code == error.code && recent.message == error.message) {
+        Some(recent) if now.duration_since(recent.reported_at).as_secs_f32() < DEDUP_WINDOW_SECS => {
+            recent.suppressed += 1;
+            return;
+        }
+        Some(recent) => {
+            error.repeated = recent.suppressed;
+            recent.suppressed = 0;
+            recent.reported_at = now;
+        }
+        None => {
+            if queue.recent.len() >= MAX_RECENT_ERRORS {
+                queue.recent.retain(|recent| now.duration_since(recent.reported_at).as_secs_f32() < DEDUP_WINDOW_SECS);
+            }
+            queue.recent.push(RecentError { code: error.code, message: error.message.clone(), reported_at: now, suppressed: 0 });
+        }
+    }
+
+    // 原生平台没有回调，只写日志
+    if cfg!(target_arch = "wasm32") {
+        queue.pending.push(error);
+        if !queue.flush_scheduled {
+            queue.flush_scheduled = true;
+            #[cfg(target_arch = "wasm32")]
+            wasm_bindgen_futures::spawn_local(async { flush() });
+        }
+    }
+}
+
+/// 视图销毁时调用：丢弃尚未回调的错误和去重记录，回调本身保持注册
+pub fn clear() {
+    if let Ok(mut queue) = ERROR_QUEUE.lock() {
+        queue.recent.clear();
+        queue.pending.clear();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn flush() {
+    let pending = match ERROR_QUEUE.lock() {
+        Ok(mut queue) => {
+            queue.flush_scheduled = false;
+            std::mem::take(&mut queue.pending)
+        }
+        Err(_) => return,
+    };
+    // 先取出回调再调用，回调中可以再次调用 setErrorCallback
+    let Some(callback) = ERROR_CALLBACK.with(|cell| cell.borrow().clone()) else {
+        return; // 没有注册回调时直接丢弃
+    };
+    for error in &pending {
+        let payload = match serde_json::to_string(error).map(|json| js_sys::JSON::parse(&json)) {
+            Ok(Ok(payload)) => payload,
+            _ => continue,
+        };
+        if let Err(e) = callback.call1(&wasm_bindgen::JsValue::NULL, &payload) {
+            log::warn!("Error callback threw: {:?}", e);
+        }
+    }
+}
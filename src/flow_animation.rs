@@ -0,0 +1,58 @@
+// src/flow_animation.rs
+// 流动动画：小圆点以固定的速度（按节点半径计）沿每条可见服务线路从源节点移向目的节点，到达终点后从起点重新出发，
+// 用于表现业务方向。折线在生成几何时记录（SceneGeometry::flow_paths），圆点每帧按经过的时间重新生成，
+// 复用节点的圆形管线。开启时渲染循环持续请求新帧；可见服务过多或画质降级时不绘制圆点。
+use glam::Vec2;
+
+use crate::app_state::State;
+use crate::models::CircleInstance;
+use crate::quality::QualityLevel;
+
+const FLOW_DOTS_PER_SERVICE: usize = 3;
+/// 可见服务的圆点总数超过该值时不绘制
+const MAX_FLOW_DOTS: usize = 3000;
+// 速度和圆点大小以节点半径为单位，与拓扑的坐标尺度无关
+const FLOW_SPEED_NODE_RADII_PER_SECOND: f64 = 3.0;
+const FLOW_DOT_RADIUS_FACTOR: f32 = 0.15;
+
+impl State {
+    pub fn set_flow_animation(&mut self, enabled: bool) {
+        if self.flow_animation_enabled == enabled {
+            return;
+        }
+        self.flow_animation_enabled = enabled;
+        self.topology_needs_update = true; // 折线只在开启时生成
+    }
+
+    /// 开启且没有因画质降级而暂停
+    pub fn is_flow_animation_active(&self) -> bool {
+        self.flow_animation_enabled && self.quality_governor.level < QualityLevel::NoFlowAnimation
+    }
+
+    /// 按当前时间和视图重新生成圆点并上传
+    pub fn update_flow_dots(&mut self) {
+        self.flow_dot_instances.clear();
+        if self.is_flow_animation_active() {
+            let (world_visible_min, world_visible_max) = self.camera.get_world_clip_bounds();
+            let is_visible = |min: Vec2, max: Vec2| !(max.cmplt(world_visible_min).any() || min.cmpgt(world_visible_max).any());
+            let visible_paths: Vec<_> = self.geometry.flow_paths.iter().filter(|path| is_visible(path.bounds_min, path.bounds_max)).collect();
+
+            if visible_paths.len() * FLOW_DOTS_PER_SERVICE <= MAX_FLOW_DOTS {
+                let travelled = self.animation_start_instant.elapsed().as_secs_f64() * FLOW_SPEED_NODE_RADII_PER_SECOND * self.node_radius as f64;
+                for path in visible_paths {
+                    let length = path.length as f64;
+                    for k in 0..FLOW_DOTS_PER_SERVICE {
+                        let distance = (travelled + length * k as f64 / FLOW_DOTS_PER_SERVICE as f64) % length;
+                        self.flow_dot_instances.push(CircleInstance {
+                            position: path.point_at(distance as f32).into(),
+                            radius_scale: self.node_radius * FLOW_DOT_RADIUS_FACTOR,
+                            color: path.color,
+                            glow: 0.0,
+                        });
+                    }
+                }
+            }
+        }
+        self.renderer.upload_flow_dot_instances(&self.device, &self.queue, &self.flow_dot_instances);
+    }
+}
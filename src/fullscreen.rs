@@ -0,0 +1,53 @@
+// src/fullscreen.rs
+// 原生窗口的全屏（无边框，占满当前显示器）：F11 切换，Escape 退出，或用 --fullscreen 启动。
+// 进入全屏前记录窗口的位置和尺寸，退出时恢复。全屏切换产生的 Resized 事件与普通调整大小一样经过 State::resize。
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::window::{Fullscreen, Window, WindowAttributes};
+
+pub struct FullscreenToggle {
+    start_fullscreen: bool,
+    /// 进入全屏之前的窗口位置（部分平台无法获取）和内部尺寸
+    windowed: Option<(Option<PhysicalPosition<i32>>, PhysicalSize<u32>)>,
+}
+
+impl FullscreenToggle {
+    pub fn new(start_fullscreen: bool) -> Self {
+        Self { start_fullscreen, windowed: None }
+    }
+
+    /// 创建窗口时调用：--fullscreen 启动时直接以全屏创建
+    pub fn apply_to_attributes(&self, attributes: WindowAttributes) -> WindowAttributes {
+        if self.start_fullscreen {
+            attributes.with_fullscreen(Some(Fullscreen::Borderless(None)))
+        } else {
+            attributes
+        }
+    }
+
+    pub fn toggle(&mut self, window: &Window) {
+        if window.fullscreen().is_some() {
+            self.exit(window);
+        } else {
+            self.windowed = Some((window.outer_position().ok(), window.inner_size()));
+            window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+            log::info!("Entered fullscreen.");
+        }
+    }
+
+    /// 不在全屏时返回 false
+    pub fn exit(&mut self, window: &Window) -> bool {
+        if window.fullscreen().is_none() {
+            return false;
+        }
+        window.set_fullscreen(None);
+        // 以 --fullscreen 启动时没有之前的位置和尺寸，使用窗口系统的默认值
+        if let Some((position, size)) = self.windowed.take() {
+            let _ = window.request_inner_size(size);
+            if let Some(position) = position {
+                window.set_outer_position(position);
+            }
+        }
+        log::info!("Left fullscreen.");
+        true
+    }
+}
@@ -0,0 +1,198 @@
+// src/geometry_update.rs
+// 几何的分帧更新：大型拓扑（例如 10 万节点、30 万服务线段）一次生成并上传会让这一帧卡顿数百毫秒。
+// 每帧只生成约 GEOMETRY_CHUNK_VERTICES 个顶点，生成完后再分块写入新的 GPU 缓冲区，全部完成后才替换
+// State::geometry 和正在使用的缓冲区；期间继续绘制之前的几何，平移缩放不受影响。
+// 小型拓扑在第一帧内就能完成，与一次性更新相同。
+use crate::app_state::State;
+use crate::models::LineVertex;
+use crate::notifications::ViewNotification;
+use crate::renderer::GeometryUpload;
+use crate::scene::defrag_event::EventCheckpoints;
+use crate::scene::geometry::{GeometryBuild, GeometryInputs, SceneGeometry};
+use crate::scene::path_index::{resolve_connections, resolve_event_paths};
+use crate::scene::wavelength_colors::WavelengthColorLut;
+use crate::scene::text_label::TextLabel;
+
+/// 每帧生成（以及上传）的顶点数上限
+const GEOMETRY_CHUNK_VERTICES: usize = 20_000;
+
+/// 未完成的分帧更新，staging 完成前不参与绘制
+pub enum GeometryUpdate {
+    Generating { staging: SceneGeometry, build: GeometryBuild },
+    Uploading { staging: SceneGeometry, upload: GeometryUpload },
+}
+
+impl State {
+    pub fn geometry_inputs(&self) -> GeometryInputs<'_> {
+        GeometryInputs {
+            node_id_to_idx: &self.node_id_to_idx,
+            connection_endpoints: &self.connection_endpoints,
+            events: &self.all_events,
+            event_checkpoints: self.event_checkpoints.for_generation(self.events_generation),
+            preview_services: &self.preview_services,
+            current_time: self.current_time_selection,
+            service_interval: self.service_interval,
+            channel_plan: self.channel_plan,
+            node_radius: self.node_radius,
+            color_mode: self.color_mode,
+            wavelength_colors: &self.wavelength_colors,
+            thickness_mode: self.thickness_mode,
+            opacity_mode: self.opacity_mode,
+            world_to_pixels: self.geometry_world_to_pixels,
+            highlight_service_ids: self.highlight_service_id_list.as_deref(),
+            highlight_palette: self.highlight_multi_select,
+            highlight_style: self.highlight_style,
+            highlight_line_style: self.highlight_line_style,
+            lod_level: self.effective_lod_level(),
+            highlight_path: self.highlighted_path.as_deref(),
+            measurement: self.completed_measurement(),
+            heat_trail_window: self.heat_trail_window_seconds(),
+            blocked_demands: &self.blocked_demands,
+            blocked_demand_window: self.blocked_demand_window_seconds(),
+            node_group_colors: &self.node_group_colors,
+            hidden_nodes: &self.hidden_nodes,
+            service_diff: self.active_service_diff(),
+            flow_animation: self.flow_animation_enabled,
+            edge_bundles: self.edge_bundles.as_ref().filter(|_| self.edge_bundling_enabled),
+        }
+    }
+
+    /// 拓扑、事件或合并之后调用：把链路两端和事件中服务的路径解析为节点索引（见 scene/path_index.rs），
+    /// 未知节点在这里统一警告一次，之后生成几何时直接跳过
+    pub fn resolve_topology_indices(&mut self) {
+        let (endpoints, unknown_links) = resolve_connections(&self.all_connections, &self.node_id_to_idx);
+        self.connection_endpoints = endpoints;
+        if unknown_links > 0 {
+            log::warn!("{} link(s) reference nodes that are not in the topology and are not drawn.", unknown_links);
+        }
+        let unknown_paths = resolve_event_paths(&mut self.all_events, &self.node_id_to_idx);
+        if unknown_paths > 0 {
+            log::warn!("{} service path(s) reference nodes that are not in the topology; those hops are skipped.", unknown_paths);
+        }
+        self.prune_annotations();
+        if !self.node_groups.is_empty() {
+            let summary = self.resolve_node_groups();
+            if summary.unknown_ids > 0 {
+                log::warn!("{} node group member(s) are not in the topology.", summary.unknown_ids);
+            }
+        }
+    }
+
+    /// 重新生成几何之前调用：通道数或高亮亮度变化后重建波长颜色查找表
+    pub fn refresh_wavelength_colors(&mut self) {
+        let num_channels = self.channel_plan.max_wavelengths;
+        if !self.wavelength_colors.matches(num_channels, &self.highlight_style) {
+            log::debug!("Rebuilding wavelength color table for {} channels.", num_channels);
+            self.wavelength_colors = WavelengthColorLut::new(num_channels, &self.highlight_style);
+        }
+    }
+
+    /// 热度轨迹模式下，事件变化后重建回放检查点（见 EventCheckpoints）；瞬时模式不需要
+    pub fn refresh_event_checkpoints(&mut self) {
+        if self.heat_trail_window_seconds().is_some() && !self.event_checkpoints.matches(self.events_generation) {
+            self.event_checkpoints = EventCheckpoints::new(&self.all_events, self.events_generation);
+        }
+    }
+
+    pub fn is_geometry_update_pending(&self) -> bool {
+        self.geometry_update.is_some()
+    }
+
+    /// 根据当前时间轴选择重新生成线路。未完成的分帧更新直接丢弃（拓扑、时间或节点位置已经变化）
+    pub fn start_geometry_update(&mut self) {
+        self.geometry_update = None;
+
+        // 节点位置以当前几何为准（可能刚被拖动）；节点标签跟随节点位置和半径，跳数标签随线路重新生成
+        let node_labels = self.geometry.circle_instances.iter()
+            .zip(self.all_elements.iter())
+            .enumerate()
+            .filter(|(idx, _)| !self.is_node_hidden(*idx))
+            .map(|(_, (instance, element))| TextLabel::for_node(element, instance))
+            .collect();
+        let mut staging = SceneGeometry {
+            circle_instances: self.geometry.circle_instances.clone(),
+            node_labels,
+            ..Default::default()
+        };
+        self.refresh_wavelength_colors();
+        self.refresh_event_checkpoints();
+        // 分帧生成的各批服务使用相同的缩放换算线宽
+        self.geometry_world_to_pixels = self.camera.world_radius_to_screen_pixels(1.0);
+        let inputs = self.geometry_inputs();
+        let mut build = staging.begin_regenerate(&inputs);
+        // 服务列表面板使用同一次重建的活跃服务，不再为面板重放一次事件
+        let panel_services = (self.service_panel.visible && self.service_panel.rows_need_update).then(|| build.services().to_vec());
+        let finished = staging.push_services(&mut build, &inputs, GEOMETRY_CHUNK_VERTICES);
+        if let Some(services) = panel_services {
+            self.set_service_panel_rows(services);
+        }
+        if finished {
+            let inputs = self.geometry_inputs();
+            staging.finish_regenerate(build, &inputs);
+            self.geometry = staging;
+            self.update_gpu_buffers();
+            self.refresh_service_hover();
+            self.capacity_bars_need_update = true; // 占用率可能已经变化
+            return;
+        }
+
+        let (processed, total) = build.progress();
+        log::info!("Generating geometry over multiple frames ({} of {} active services in the first frame).", processed, total);
+        // 节点数量和位置可能已经变化，先上传节点实例，线路在之后的帧中更新
+        self.geometry.cull_circle_instances(Some(self.node_cull_region()));
+        self.renderer.upload_circle_instances(&self.device, &self.queue, &self.geometry.visible_circle_instances);
+        self.geometry_update = Some(GeometryUpdate::Generating { staging, build });
+        self.report_geometry_progress();
+    }
+
+    /// 推进分帧更新一步，完成时替换当前几何
+    pub fn advance_geometry_update(&mut self) {
+        let Some(update) = self.geometry_update.take() else {
+            return;
+        };
+        self.geometry_update = match update {
+            GeometryUpdate::Generating { mut staging, mut build } => {
+                let inputs = self.geometry_inputs();
+                if staging.push_services(&mut build, &inputs, GEOMETRY_CHUNK_VERTICES) {
+                    staging.finish_regenerate(build, &inputs);
+                    staging.cull_circle_instances(Some(self.node_cull_region()));
+                    let upload = self.renderer.begin_geometry_upload(&self.device, &staging);
+                    Some(GeometryUpdate::Uploading { staging, upload })
+                } else {
+                    Some(GeometryUpdate::Generating { staging, build })
+                }
+            }
+            GeometryUpdate::Uploading { staging, mut upload } => {
+                let byte_budget = GEOMETRY_CHUNK_VERTICES * std::mem::size_of::<LineVertex>();
+                if upload.write_chunk(&self.queue, &staging, byte_budget) {
+                    self.renderer.finish_geometry_upload(upload);
+                    self.geometry = staging;
+                    self.refresh_node_culling(); // 上传期间视口可能已经移出裁剪范围
+                    self.refresh_service_hover();
+                    self.selection_needs_update = true; // 节点颜色可能已被高亮改变
+                    self.capacity_bars_need_update = true;
+                    None
+                } else {
+                    Some(GeometryUpdate::Uploading { staging, upload })
+                }
+            }
+        };
+        self.report_geometry_progress();
+    }
+
+    /// 生成占前一半进度，上传占后一半；完成时报告 1.0
+    fn report_geometry_progress(&mut self) {
+        let progress = match &self.geometry_update {
+            None => 1.0,
+            Some(GeometryUpdate::Generating { build, .. }) => {
+                let (processed, total) = build.progress();
+                0.5 * processed as f32 / total.max(1) as f32
+            }
+            Some(GeometryUpdate::Uploading { staging, upload }) => {
+                let (written, total) = upload.progress(staging);
+                0.5 + 0.5 * written as f32 / total.max(1) as f32
+            }
+        };
+        self.pending_notifications.push(ViewNotification::GeometryProgress { progress });
+    }
+}
@@ -0,0 +1,183 @@
+// src/gpu_profiler.rs
+// GPU 计时（setGpuProfiling）：CPU 端的帧时间无法区分节点、线路还是文字绘制是瓶颈。开启后若设备支持
+// wgpu::Features::TIMESTAMP_QUERY，render_to_view 把一帧拆成节点、线路、高亮和文字四个渲染通道，在每个通道的开始和结束
+// 写入时间戳，解析后异步读回，换算为毫秒后通过 getRenderStats 和统计叠加层报告。
+// 不支持时（包括未开放 timestamp-query 的 WebGPU）只记录一条日志，不报告计时；关闭时不写入任何查询。
+// 上一帧的时间戳尚未读回时按普通方式（单个渲染通道）绘制，因此不是每一帧都写入查询。
+use serde::Serialize;
+
+use crate::app_state::State;
+
+/// 计时的渲染通道数：节点、线路、高亮、文字（与 GpuPassTimings 的字段顺序相同）
+pub const PROFILED_PASS_COUNT: usize = 4;
+const QUERY_COUNT: u32 = PROFILED_PASS_COUNT as u32 * 2;
+const TIMESTAMP_BYTES: wgpu::BufferAddress = std::mem::size_of::<u64>() as wgpu::BufferAddress;
+
+/// 最近一次读回的各渲染通道 GPU 耗时（毫秒）
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct GpuPassTimings {
+    /// 节点和选中外圈
+    pub circles_ms: f32,
+    /// 链路边界、服务线路、占用率、容量条、预览、最短路径和测量线
+    pub lines_ms: f32,
+    /// 高亮线路和流动圆点
+    pub highlights_ms: f32,
+    /// 叠加层和文字
+    pub text_ms: f32,
+}
+
+impl GpuPassTimings {
+    /// 一帧中所有计时通道的 GPU 耗时之和
+    pub fn total_ms(&self) -> f32 {
+        self.circles_ms + self.lines_ms + self.highlights_ms + self.text_ms
+    }
+}
+
+pub struct GpuProfiler {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// 每个时间戳刻度的纳秒数
+    timestamp_period: f32,
+    pending: Option<flume::Receiver<Result<(), wgpu::BufferAsyncError>>>,
+    /// 本帧是否写入了时间戳（resolve 之后才能请求读回）
+    resolved: bool,
+    pub latest: Option<GpuPassTimings>,
+}
+
+impl GpuProfiler {
+    /// 设备没有启用 TIMESTAMP_QUERY 时返回 None
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Pass Timestamp Query Set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        let buffer_size = QUERY_COUNT as wgpu::BufferAddress * TIMESTAMP_BYTES;
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Resolve Buffer"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Readback Buffer"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            timestamp_period: queue.get_timestamp_period(),
+            pending: None,
+            resolved: false,
+            latest: None,
+        })
+    }
+
+    /// 本帧可以写入时间戳时返回查询集（上一次读回尚未完成时返回 None）
+    pub fn frame_query_set(&self) -> Option<&wgpu::QuerySet> {
+        self.pending.is_none().then_some(&self.query_set)
+    }
+
+    /// 第 pass_index 个渲染通道的开始和结束时间戳
+    pub fn timestamp_writes(query_set: &wgpu::QuerySet, pass_index: usize) -> wgpu::RenderPassTimestampWrites<'_> {
+        wgpu::RenderPassTimestampWrites {
+            query_set,
+            beginning_of_pass_write_index: Some(pass_index as u32 * 2),
+            end_of_pass_write_index: Some(pass_index as u32 * 2 + 1),
+        }
+    }
+
+    /// 写入时间戳的渲染通道之后调用：把查询结果解析并拷贝到读回缓冲区
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..QUERY_COUNT, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.readback_buffer, 0, self.resolve_buffer.size());
+        self.resolved = true;
+    }
+
+    /// 提交之后调用：请求读回本帧解析的时间戳
+    pub fn request_readback(&mut self) {
+        if !std::mem::take(&mut self.resolved) {
+            return;
+        }
+        let (sender, receiver) = flume::bounded(1);
+        self.readback_buffer.map_async(wgpu::MapMode::Read, .., move |result| {
+            let _ = sender.send(result);
+        });
+        self.pending = Some(receiver);
+    }
+
+    /// 检查读回是否完成；完成时更新 latest 并返回 true（读回失败时记录警告）
+    pub fn poll_result(&mut self, device: &wgpu::Device) -> bool {
+        let Some(receiver) = self.pending.as_ref() else {
+            return false;
+        };
+        // WebGPU 上映射回调由浏览器事件循环触发，这里的 poll 没有作用
+        let _ = device.poll(wgpu::PollType::Poll);
+
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(flume::TryRecvError::Empty) => return false,
+            Err(flume::TryRecvError::Disconnected) => Err(wgpu::BufferAsyncError),
+        };
+        self.pending = None;
+
+        if let Err(e) = result {
+            log::warn!("Failed to read back GPU timestamps: {}", e);
+            return false;
+        }
+        let timestamps: Vec<u64> = {
+            let mapped = self.readback_buffer.slice(..).get_mapped_range();
+            mapped.chunks_exact(TIMESTAMP_BYTES as usize)
+                .map(|bytes| u64::from_ne_bytes(bytes.try_into().expect("chunks of 8 bytes")))
+                .collect()
+        };
+        self.readback_buffer.unmap();
+
+        // 结束时间戳早于开始（计数器回绕或驱动返回无效值）时丢弃这一帧的结果
+        let pass_ms = |pass_index: usize| {
+            let ticks = timestamps[pass_index * 2 + 1].checked_sub(timestamps[pass_index * 2])?;
+            Some(ticks as f32 * self.timestamp_period / 1.0e6)
+        };
+        let (Some(circles_ms), Some(lines_ms), Some(highlights_ms), Some(text_ms)) = (pass_ms(0), pass_ms(1), pass_ms(2), pass_ms(3)) else {
+            log::debug!("Discarding GPU timestamps that go backwards: {:?}", timestamps);
+            return false;
+        };
+        self.latest = Some(GpuPassTimings { circles_ms, lines_ms, highlights_ms, text_ms });
+        true
+    }
+}
+
+impl State {
+    /// setGpuProfiling：设备不支持时间戳查询时只记录日志，getRenderStats 中的 gpu_pass_ms 保持为 null
+    pub fn set_gpu_profiling(&mut self, enabled: bool) {
+        if enabled == self.gpu_profiling_enabled {
+            return;
+        }
+        self.gpu_profiling_enabled = enabled;
+        if !enabled {
+            log::info!("GPU pass profiling disabled.");
+            self.gpu_profiler = None;
+            return;
+        }
+        self.gpu_profiler = GpuProfiler::new(&self.device, &self.queue);
+        match self.gpu_profiler {
+            Some(_) => log::info!("GPU pass profiling enabled."),
+            None => log::info!("GPU timestamp queries are not supported by this device; GPU pass timings are unavailable."),
+        }
+    }
+
+    /// 每帧调用：读回完成时请求重绘以更新统计叠加层
+    pub fn poll_gpu_profiler(&mut self) -> bool {
+        let Some(profiler) = self.gpu_profiler.as_mut() else {
+            return false;
+        };
+        profiler.poll_result(&self.device) && self.show_stats_overlay
+    }
+}
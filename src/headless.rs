@@ -0,0 +1,591 @@
+// src/headless.rs
+// 离屏渲染：不创建窗口和 surface，使用与 State::new 相同的管线和截图回读（capture.rs）把拓扑绘制到 Rgba8UnormSrgb 纹理并读回像素。
+// 配合 compare_with_golden 在 CI 中发现渲染回归（例如高亮四边形的绕序问题）；
+// render_color_test_pattern / check_color_test_pattern 检查 sRGB 输出路径在 Unorm 和 UnormSrgb 目标上的结果是否一致。
+use std::path::Path;
+use anyhow::{anyhow, Context};
+use glam::Vec2;
+
+use crate::app_state::{request_device, State};
+use crate::color_test_pattern::swatch_rects;
+pub use crate::capture::Snapshot;
+pub use crate::scene::network::FullTopologyData;
+
+pub const SNAPSHOT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+/// 设置该环境变量（任意值）时，compare_with_golden 用当前结果覆盖 golden 文件而不是比较
+pub const UPDATE_GOLDENS_ENV: &str = "WDMVIEW_UPDATE_GOLDENS";
+/// 没有可用 GPU 适配器时 render_snapshot 等返回的错误，测试据此跳过而不是失败
+pub const NO_ADAPTER_ERROR: &str = "No GPU adapter available for headless rendering";
+
+#[derive(Debug, Clone, Copy)]
+pub enum SnapshotCamera {
+    /// 与加载拓扑后的默认视角相同
+    FitToTopology,
+    At { position: [f32; 2], zoom: f32 },
+}
+
+/// 加载拓扑（包括其中的信道规划，时间为 0），按给定相机渲染一帧并返回像素
+pub fn render_snapshot(topology: FullTopologyData, camera: SnapshotCamera, width: u32, height: u32) -> anyhow::Result<Snapshot> {
+    pollster::block_on(render_snapshot_async(topology, camera, width, height))
+}
+
+async fn render_snapshot_async(topology: FullTopologyData, camera: SnapshotCamera, width: u32, height: u32) -> anyhow::Result<Snapshot> {
+    let mut state = create_state(SNAPSHOT_FORMAT, width, height).await?;
+    load_snapshot_topology(&mut state, topology, camera);
+    capture_frame(&mut state).await
+}
+
+fn load_snapshot_topology(state: &mut State, topology: FullTopologyData, camera: SnapshotCamera) {
+    if let Some(channel_plan) = topology.channel_plan {
+        state.set_channel_plan(channel_plan);
+    }
+    state.apply_topology_structure(topology.elements, topology.connections);
+    state.apply_timeline_events(topology.defrag_timeline_events);
+    if let SnapshotCamera::At { position, zoom } = camera {
+        state.camera.position = Vec2::from_array(position);
+        state.camera.zoom = zoom;
+        state.camera_needs_update = true;
+    }
+}
+
+/// 只绘制 sRGB 参考色块（color_test_pattern.rs）的一帧。format 为 Rgba8Unorm 时由着色器做 sRGB 编码，
+/// Rgba8UnormSrgb 时由硬件编码；force_srgb_conversion 与 forceSrgbConversion 相同。结果用 check_color_test_pattern 检查
+pub fn render_color_test_pattern(format: wgpu::TextureFormat, force_srgb_conversion: Option<bool>, width: u32, height: u32) -> anyhow::Result<Snapshot> {
+    pollster::block_on(async {
+        let mut state = create_state(format, width, height).await?;
+        state.geometry.circle_instances.clear(); // 不绘制示例节点
+        state.update_gpu_buffers();
+        state.set_color_test_pattern(true);
+        if force_srgb_conversion.is_some() {
+            state.force_srgb_conversion(force_srgb_conversion);
+        }
+        capture_frame(&mut state).await
+    })
+}
+
+/// 检查每个色块中心的像素与期望的 sRGB 值相差不超过 tolerance
+pub fn check_color_test_pattern(snapshot: &Snapshot, tolerance: u8) -> anyhow::Result<()> {
+    let canvas_size = Vec2::new(snapshot.width as f32, snapshot.height as f32);
+    let mut mismatches = Vec::new();
+    let mut checked = 0;
+    for (swatch, min, max) in swatch_rects(canvas_size) {
+        let center = ((min + max) / 2.0).as_uvec2();
+        let offset = ((center.y * snapshot.width + center.x) * 4) as usize;
+        let actual = &snapshot.pixels[offset..offset + 3];
+        checked += 1;
+        if actual.iter().zip(swatch.srgb).any(|(a, e)| a.abs_diff(e) > tolerance) {
+            mismatches.push(format!("{} expected {:?}, got {:?}", swatch.name, swatch.srgb, actual));
+        }
+    }
+    if checked == 0 {
+        return Err(anyhow!("Snapshot {}x{} is too small for the color test pattern", snapshot.width, snapshot.height));
+    }
+    if !mismatches.is_empty() {
+        return Err(anyhow!("Color test pattern mismatch (tolerance {}): {}", tolerance, mismatches.join("; ")));
+    }
+    Ok(())
+}
+
+async fn create_state(format: wgpu::TextureFormat, width: u32, height: u32) -> anyhow::Result<State> {
+    if width == 0 || height == 0 {
+        return Err(anyhow!("Snapshot size must be non-zero, got {}x{}", width, height));
+    }
+
+    // 没有 Vulkan / Metal / DX12 的 CI 机器上退回 GL（例如 Mesa 的 llvmpipe 软件渲染）
+    let gpu = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::PRIMARY | wgpu::Backends::GL,
+        ..Default::default()
+    });
+    let adapter = gpu
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        })
+        .await
+        .context(NO_ADAPTER_ERROR)?;
+    let (device, queue) = request_device(&adapter).await?;
+
+    let config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        format,
+        width,
+        height,
+        present_mode: wgpu::PresentMode::Fifo,
+        alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+        view_formats: vec![],
+        desired_maximum_frame_latency: 2,
+    };
+    let mut state = State::with_gpu(&adapter, device, queue, None, config)?;
+    state.resize(width, height);
+    Ok(state)
+}
+
+async fn capture_frame(state: &mut State) -> anyhow::Result<Snapshot> {
+    state.update();
+    // 大型拓扑的几何分多帧生成和上传
+    while state.is_geometry_update_pending() {
+        state.update();
+    }
+
+    let (capture_sender, capture_receiver) = flume::bounded(1);
+    state.request_frame_capture(None, capture_sender);
+    state.device.poll(wgpu::PollType::wait_indefinitely())?;
+    state.poll_frame_captures();
+    capture_receiver.recv_async().await?
+}
+
+/// 与 golden PNG 比较，每个通道允许 `tolerance` 的差异（不同 GPU 的光栅化和混合略有差别）。
+/// 设置 UPDATE_GOLDENS_ENV 时改为写入 golden 文件。
+pub fn compare_with_golden(snapshot: &Snapshot, golden_path: &Path, tolerance: u8) -> anyhow::Result<()> {
+    let image = image::RgbaImage::from_raw(snapshot.width, snapshot.height, snapshot.pixels.clone())
+        .ok_or_else(|| anyhow!("Snapshot pixel buffer does not match its {}x{} size", snapshot.width, snapshot.height))?;
+
+    if std::env::var_os(UPDATE_GOLDENS_ENV).is_some() {
+        if let Some(parent) = golden_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        image.save(golden_path)
+            .with_context(|| format!("Failed to write golden image {}", golden_path.display()))?;
+        log::info!("Updated golden image {}", golden_path.display());
+        return Ok(());
+    }
+
+    let golden = image::open(golden_path)
+        .with_context(|| format!("Failed to read golden image {} (set {} to create it)", golden_path.display(), UPDATE_GOLDENS_ENV))?
+        .to_rgba8();
+    if golden.dimensions() != image.dimensions() {
+        return Err(anyhow!(
+            "Snapshot is {}x{} but golden image {} is {}x{}",
+            snapshot.width, snapshot.height, golden_path.display(), golden.width(), golden.height()
+        ));
+    }
+
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+    for (actual, expected) in image.pixels().zip(golden.pixels()) {
+        let difference = actual.0.iter().zip(expected.0.iter()).map(|(a, e)| a.abs_diff(*e)).max().unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        if difference > tolerance {
+            mismatched_pixels += 1;
+        }
+    }
+    if mismatched_pixels > 0 {
+        return Err(anyhow!(
+            "{} pixel(s) differ from golden image {} by more than {} (max difference {})",
+            mismatched_pixels, golden_path.display(), tolerance, max_difference
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_color::{ColorToComponents, ColorToPacked, LinearRgba, Srgba};
+    use serde_json::json;
+
+    use crate::annotations::Annotation;
+    use crate::layout_history::LayoutPosition;
+    use crate::node_groups::{NodeGroup, NodeGroups};
+    use crate::notifications::ViewNotification;
+    use crate::scene::blocked_demand::{blocked_demands_in_range, BlockedDemand};
+    use crate::scene::defrag_event::AnyEvent;
+    use crate::scene::geometry::{lane_radius, BASE_NODE_RADIUS};
+    use crate::ui_events::UserCommand;
+    use crate::settings::{ChannelPlan, OpacityMode, ThicknessMode, MAX_WAVELENGTHS, MIN_UTILIZATION_ALPHA};
+
+    /// 没有 GPU 适配器时打印原因并返回 None（测试直接通过），其他错误仍然失败
+    fn skip_without_adapter<T>(result: anyhow::Result<T>) -> Option<T> {
+        match result {
+            Err(e) if e.to_string() == NO_ADAPTER_ERROR => {
+                eprintln!("Skipping headless rendering test: {}", e);
+                None
+            }
+            result => Some(result.expect("headless rendering failed")),
+        }
+    }
+
+    /// nodes 为 (ID, x, y)；services 为 (service_id, 路径, 利用率)，都在时刻 0 分配、使用波长 0
+    fn topology(nodes: &[(&str, f32, f32)], links: &[(&str, &str)], services: &[(i32, &[&str], Option<f32>)]) -> FullTopologyData {
+        let elements: Vec<_> = nodes.iter().map(|&(id, x, y)| json!({
+            "name": id, "type": "Roadm", "type_variety": "default", "metadata": {"location": {"x": x, "y": y}}, "element_id": id,
+        })).collect();
+        let connections: Vec<_> = links.iter().map(|&(from, to)| json!({
+            "from_node": from, "to_node": to, "connection_id": format!("{}-{}", from, to),
+        })).collect();
+        let events: Vec<_> = services.iter().map(|&(service_id, path, utilization)| {
+            let mut details = json!({
+                "service_id": service_id, "source_id": path[0], "destination_id": path[path.len() - 1],
+                "arrival_time": 0.0, "departure_time": 10.0, "bit_rate": 100.0, "power": 0.0,
+                "path": path, "wavelength": 0, "snr_requirement": 10.0, "gsnr": 15.0,
+            });
+            if let Some(utilization) = utilization {
+                details["utilization"] = json!(utilization);
+            }
+            json!({"event_type": "ALLOCATION", "timestamp": 0.0, "service_id": service_id, "details": details})
+        }).collect();
+        let topology = json!({
+            "elements": elements, "connections": connections, "defrag_timeline_events": events,
+            "channel_plan": {"max_wavelengths": 1}, // 只有一个波长时服务线路沿链路中心
+        });
+        FullTopologyData::from_json_str(&topology.to_string()).expect("fixture topology parses")
+    }
+
+    /// 与 render_snapshot 相同，但关闭文字（字形光栅化随平台和字体不同），并在渲染前用 configure 调整设置。
+    /// 同时返回 State，测试按生成的几何计算要检查的像素位置
+    fn render_with(
+        topology: FullTopologyData,
+        camera: SnapshotCamera,
+        (width, height): (u32, u32),
+        configure: impl FnOnce(&mut State),
+    ) -> Option<(State, Snapshot)> {
+        skip_without_adapter(pollster::block_on(async {
+            let mut state = create_state(SNAPSHOT_FORMAT, width, height).await?;
+            load_snapshot_topology(&mut state, topology, camera);
+            state.text_rendering_enabled = false;
+            configure(&mut state);
+            let snapshot = capture_frame(&mut state).await?;
+            Ok((state, snapshot))
+        }))
+    }
+
+    /// 只加载拓扑、不渲染，用于检查命令处理的结果
+    fn loaded_state(topology: FullTopologyData) -> Option<State> {
+        skip_without_adapter(pollster::block_on(async {
+            let mut state = create_state(SNAPSHOT_FORMAT, 64, 64).await?;
+            load_snapshot_topology(&mut state, topology, SnapshotCamera::FitToTopology);
+            Ok(state)
+        }))
+    }
+
+    /// 世界坐标处的像素（RGB）
+    fn pixel_at(state: &State, snapshot: &Snapshot, world_pos: Vec2) -> [u8; 3] {
+        let screen = state.camera.world_to_screen(world_pos).floor().as_uvec2();
+        let offset = ((screen.y * snapshot.width + screen.x) * 4) as usize;
+        snapshot.pixels[offset..offset + 3].try_into().unwrap()
+    }
+
+    /// exportGeoJson 使用节点的当前位置：拖动后的节点导出新坐标（拓扑数据的坐标约定，y 轴向下）
+    #[test]
+    fn geojson_export_uses_dragged_positions() {
+        let fixture = topology(&[("A", 0.0, 0.0), ("B", 100.0, 40.0)], &[("A", "B")], &[]);
+        let Some(mut state) = loaded_state(fixture) else {
+            return;
+        };
+        state.process_command(UserCommand::SetNodePosition { element_id: "B".to_string(), x: 150.0, y: 60.0 });
+        let (reply, receiver) = flume::bounded(1);
+        state.process_command(UserCommand::ExportGeoJson { include_services: false, reply });
+        let geojson = serde_json::to_value(receiver.recv().unwrap().to_feature_collection()).unwrap();
+
+        assert_eq!(geojson["features"][0]["geometry"]["coordinates"], json!([0.0, 0.0]));
+        assert_eq!(geojson["features"][1]["geometry"]["coordinates"], json!([150.0, 60.0]));
+        assert_eq!(geojson["features"][2]["geometry"]["coordinates"], json!([[0.0, 0.0], [150.0, 60.0]]));
+    }
+
+    /// 服务路径以任一方向经过链路都计入；时间改变后被检查链路的服务列表随之更新
+    #[test]
+    fn services_on_link_counts_both_directions() {
+        let fixture = topology(
+            &[("A", 0.0, 0.0), ("B", 100.0, 0.0), ("C", 200.0, 0.0)],
+            &[("A", "B"), ("B", "C")],
+            &[(1, &["A", "B", "C"], None), (2, &["C", "B", "A"], None), (3, &["B", "C"], None)],
+        );
+        let Some(mut state) = loaded_state(fixture) else {
+            return;
+        };
+        let service_ids = |services: Vec<crate::link_inspector::LinkService>| services.iter().map(|service| service.service_id).collect::<Vec<_>>();
+        assert_eq!(service_ids(state.services_on_link(0, 5.0).unwrap()), vec![1, 2]);
+        assert_eq!(service_ids(state.services_on_connection("B-C", 5.0).unwrap()), vec![1, 2, 3]);
+        assert!(state.services_on_link(0, 20.0).unwrap().is_empty());
+        assert!(state.services_on_link(2, 5.0).is_err());
+        let service = serde_json::to_value(&state.services_on_link(0, 5.0).unwrap()[1]).unwrap();
+        assert_eq!(service, json!({"service_id": 2, "wavelength": 0, "source_id": "C", "destination_id": "A"}));
+
+        state.process_command(UserCommand::SetTimeSelection(5.0));
+        state.inspect_link(Some(0));
+        assert_eq!(state.inspected_link.as_ref().unwrap().service_ids, vec![1, 2]);
+        state.pending_notifications.clear();
+        state.process_command(UserCommand::SetTimeSelection(20.0));
+        assert!(state.inspected_link.as_ref().unwrap().service_ids.is_empty());
+        let inspected: Vec<_> = state.pending_notifications.iter().filter(|notification| matches!(notification, ViewNotification::LinkInspected { .. })).collect();
+        assert!(matches!(inspected[..], [ViewNotification::LinkInspected { connection_id: Some(_), services, .. }] if services.is_empty()));
+        // 服务列表不变时不重复通知
+        state.pending_notifications.clear();
+        state.process_command(UserCommand::SetTimeSelection(30.0));
+        assert!(!state.pending_notifications.iter().any(|notification| matches!(notification, ViewNotification::LinkInspected { .. })));
+    }
+
+    /// 乱序的需求按时间排序保存（合并时也一样），时间戳不是有限值的需求被丢弃
+    #[test]
+    fn blocked_demands_are_sorted_and_non_finite_timestamps_rejected() {
+        let Some(mut state) = loaded_state(topology(&[("A", 0.0, 0.0), ("B", 100.0, 0.0)], &[("A", "B")], &[])) else {
+            return;
+        };
+        let demands = |timestamps: &[f64]| -> Vec<BlockedDemand> {
+            timestamps.iter().map(|&timestamp| BlockedDemand { timestamp, source_id: "A".into(), destination_id: "B".into(), bit_rate: 1.0 }).collect()
+        };
+        let timestamps = |state: &State| state.blocked_demands.iter().map(|demand| demand.timestamp).collect::<Vec<_>>();
+        state.set_blocked_demands(demands(&[5.0, f64::NAN, 1.0, f64::INFINITY, 3.0, f64::NEG_INFINITY]), false);
+        assert_eq!(timestamps(&state), vec![1.0, 3.0, 5.0]);
+        state.set_blocked_demands(demands(&[4.0, f64::NAN, 0.0]), true);
+        assert_eq!(timestamps(&state), vec![0.0, 1.0, 3.0, 4.0, 5.0]);
+        assert_eq!(blocked_demands_in_range(&state.blocked_demands, 1.0, 4.0).len(), 3);
+        state.set_blocked_demands(demands(&[f64::NAN]), false);
+        assert!(state.blocked_demands.is_empty());
+    }
+
+    /// 未知的节点 ID 只计数；属于多个组的节点使用组名排序最后的组的颜色，任一所属组隐藏时节点隐藏且不绘制
+    #[test]
+    fn node_groups_resolve_unknown_ids_overlaps_and_hidden_groups() {
+        let Some(mut state) = loaded_state(topology(&[("A", 0.0, 0.0), ("B", 100.0, 0.0), ("C", 200.0, 0.0)], &[("A", "B"), ("B", "C")], &[])) else {
+            return;
+        };
+        let (north, south) = ([1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]);
+        let group = |color: [f32; 4], element_ids: &[&str], visible: bool| NodeGroup {
+            color,
+            element_ids: element_ids.iter().map(|id| id.to_string()).collect(),
+            visible,
+        };
+        let groups = NodeGroups::from([
+            ("south".to_string(), group(south, &["B", "C", "Y"], false)),
+            ("north".to_string(), group(north, &["A", "B", "X"], true)),
+        ]);
+        let summary = state.set_node_groups(groups);
+        assert_eq!((summary.groups, summary.assigned_nodes, summary.unknown_ids), (2, 3, 2));
+        assert_eq!(state.node_group_colors, vec![Some(north), Some(south), Some(south)]);
+        assert_eq!(state.hidden_nodes, vec![false, true, true]);
+        state.update();
+        while state.is_geometry_update_pending() {
+            state.update();
+        }
+        assert_eq!(state.geometry.visible_circle_indices, vec![0]);
+
+        // 北组隐藏也隐藏 B；南组重新显示后只剩 A、B 隐藏
+        state.set_group_visibility("north", false).unwrap();
+        state.set_group_visibility("south", true).unwrap();
+        assert_eq!(state.hidden_nodes, vec![true, true, false]);
+        assert!(state.set_group_visibility("east", false).is_err());
+
+        let summary = state.set_node_groups(NodeGroups::new());
+        assert_eq!((summary.groups, summary.assigned_nodes, summary.unknown_ids), (0, 0, 0));
+        assert!(state.node_group_colors.is_empty() && state.hidden_nodes.is_empty());
+        assert!(!state.is_node_hidden(1));
+    }
+
+    /// addAnnotation 拒绝空 ID、非有限坐标和未知锚定节点，同一 ID 替换原注释；重新加载拓扑后删除锚定节点已不存在的注释
+    #[test]
+    fn annotations_are_validated_and_pruned_with_their_anchor() {
+        let Some(mut state) = loaded_state(topology(&[("A", 0.0, 0.0), ("B", 100.0, 0.0)], &[("A", "B")], &[])) else {
+            return;
+        };
+        let annotation = |id: &str, x: f32, anchor: Option<&str>| Annotation {
+            id: id.to_string(),
+            world_position: LayoutPosition { x, y: 0.0 },
+            text: format!("note {}", id),
+            color: [1.0, 1.0, 1.0, 1.0],
+            anchor_element_id: anchor.map(str::to_string),
+        };
+        assert!(state.add_annotation(annotation("", 0.0, None)).is_err());
+        assert!(state.add_annotation(annotation("nan", f32::NAN, None)).is_err());
+        assert!(state.add_annotation(annotation("inf", f32::INFINITY, None)).is_err());
+        assert!(state.add_annotation(annotation("ghost", 0.0, Some("Z"))).is_err());
+        assert!(state.annotations.is_empty());
+
+        state.add_annotation(annotation("free", 10.0, None)).unwrap();
+        state.add_annotation(annotation("on_b", 0.0, Some("B"))).unwrap();
+        state.add_annotation(annotation("free", 20.0, None)).unwrap();
+        let ids = |state: &State| state.annotations.iter().map(|annotation| annotation.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&state), vec!["free", "on_b"]);
+        assert_eq!(state.annotations[0].world_position.x, 20.0);
+
+        // 保留 B 时注释不变；B 被移除后锚定在 B 上的注释被删除，自由注释保留
+        let reload = |state: &mut State, nodes: &[(&str, f32, f32)]| {
+            let fixture = topology(nodes, &[], &[]);
+            state.apply_topology_structure(fixture.elements, fixture.connections);
+        };
+        reload(&mut state, &[("A", 0.0, 0.0), ("B", 100.0, 0.0)]);
+        assert_eq!(ids(&state), vec!["free", "on_b"]);
+        reload(&mut state, &[("A", 0.0, 0.0)]);
+        assert_eq!(ids(&state), vec!["free"]);
+    }
+
+    /// 默认使用固定的 BASE_NODE_RADIUS，开启自动推算后才按节点间距缩放；固定半径优先
+    #[test]
+    fn node_radius_is_auto_sized_only_when_enabled() {
+        let fixture = topology(&[("A", 0.0, 0.0), ("B", 1000.0, 0.0), ("C", 0.0, 1000.0)], &[("A", "B")], &[]);
+        let Some(mut state) = loaded_state(fixture) else {
+            return;
+        };
+        assert_eq!(state.node_radius, BASE_NODE_RADIUS);
+        assert_eq!(state.validation_report.derived_node_radius, Some(200.0));
+
+        state.process_command(UserCommand::SetAutoNodeRadius(true));
+        assert_eq!(state.node_radius, 200.0);
+        state.process_command(UserCommand::SetNodeRadius(Some(7.0)));
+        assert_eq!(state.node_radius, 7.0);
+        state.process_command(UserCommand::SetNodeRadius(None));
+        assert_eq!(state.node_radius, 200.0);
+        state.process_command(UserCommand::SetAutoNodeRadius(false));
+        assert_eq!(state.node_radius, BASE_NODE_RADIUS);
+    }
+
+    /// 波长数变化后重新检查事件的波长；超过上限的波长数被拒绝
+    #[test]
+    fn channel_plan_change_rechecks_event_wavelengths() {
+        let mut fixture = topology(&[("A", 0.0, 0.0), ("B", 100.0, 0.0)], &[("A", "B")], &[(1, &["A", "B"], None)]);
+        for event in &mut fixture.defrag_timeline_events {
+            if let AnyEvent::Allocation { details, .. } = event {
+                details.wavelength = 3;
+            }
+        }
+        let Some(mut state) = loaded_state(fixture) else {
+            return;
+        };
+        let out_of_range = |state: &State| state.validation_report.issues.iter().any(|issue| issue.code == "events_wavelength_out_of_range");
+        let validated_notifications = |state: &mut State| {
+            state.pending_notifications.drain(..).filter(|notification| matches!(notification, ViewNotification::TopologyValidated { .. })).count()
+        };
+        assert!(out_of_range(&state));
+        validated_notifications(&mut state);
+
+        state.process_command(UserCommand::SetNumChannels { num_channels: 8 });
+        assert!(!out_of_range(&state));
+        assert_eq!(validated_notifications(&mut state), 1);
+
+        // 只改变展开角度：检查结果不变，不重复通知
+        state.process_command(UserCommand::SetChannelPlan(ChannelPlan { spread_angle: 0.1, ..state.channel_plan }));
+        assert_eq!(validated_notifications(&mut state), 0);
+
+        state.process_command(UserCommand::SetNumChannels { num_channels: 2 });
+        assert!(out_of_range(&state));
+        assert_eq!(state.validation_report.issues.iter().filter(|issue| issue.code == "events_wavelength_out_of_range").count(), 1);
+        assert_eq!(validated_notifications(&mut state), 1);
+
+        state.process_command(UserCommand::SetNumChannels { num_channels: MAX_WAVELENGTHS + 1 });
+        assert_eq!(state.channel_plan.max_wavelengths, 2);
+    }
+
+    #[test]
+    fn two_link_topology_matches_golden() {
+        let fixture = topology(
+            &[("A", 0.0, 0.0), ("B", 100.0, 40.0), ("C", 200.0, 0.0)],
+            &[("A", "B"), ("B", "C")],
+            &[(1, &["A", "B", "C"], None)],
+        );
+        let Some((_, snapshot)) = render_with(fixture, SnapshotCamera::FitToTopology, (160, 96), |_| {}) else {
+            return;
+        };
+        let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/two_links.png");
+        compare_with_golden(&snapshot, &golden, 8).unwrap();
+    }
+
+    /// 着色器编码（Rgba8Unorm）和硬件编码（Rgba8UnormSrgb）读回的参考色块都与期望的 sRGB 值一致；
+    /// 在 Unorm 目标上关闭着色器编码时结果偏暗，检查能发现
+    #[test]
+    fn color_test_pattern_matches_on_unorm_and_srgb_targets() {
+        for format in [wgpu::TextureFormat::Rgba8Unorm, wgpu::TextureFormat::Rgba8UnormSrgb] {
+            let Some(snapshot) = skip_without_adapter(render_color_test_pattern(format, None, 256, 256)) else {
+                return;
+            };
+            check_color_test_pattern(&snapshot, 2).unwrap_or_else(|e| panic!("{:?}: {}", format, e));
+        }
+
+        let Some(unconverted) = skip_without_adapter(render_color_test_pattern(wgpu::TextureFormat::Rgba8Unorm, Some(false), 256, 256)) else {
+            return;
+        };
+        assert!(check_color_test_pattern(&unconverted, 2).is_err());
+    }
+
+    /// 两条 50% 不透明度的服务在原点交叉：交叉处的像素等于在背景上依次叠加两层预乘 alpha 颜色的结果
+    #[test]
+    fn crossing_translucent_lines_composite_analytically() {
+        let half_alpha_utilization = (0.5 - MIN_UTILIZATION_ALPHA) / (1.0 - MIN_UTILIZATION_ALPHA);
+        let fixture = topology(
+            &[("A", -100.0, 0.0), ("B", 100.0, 0.0), ("C", 0.0, -100.0), ("D", 0.0, 100.0)],
+            &[("A", "B"), ("C", "D")],
+            &[(1, &["A", "B"], Some(half_alpha_utilization)), (2, &["C", "D"], Some(half_alpha_utilization))],
+        );
+        let Some((state, snapshot)) = render_with(fixture, SnapshotCamera::FitToTopology, (128, 128), |state| {
+            state.opacity_mode = OpacityMode::Utilization;
+            state.thickness_mode = ThicknessMode::Bitrate; // 码率相同时为最大宽度，交叉处是一块实心区域
+        }) else {
+            return;
+        };
+
+        let service_color = state.geometry.service_quad_vertices.first().expect("services are drawn as quads").color;
+        let [r, g, b, alpha] = service_color;
+        assert!((alpha - 0.5).abs() < 1e-3, "service alpha is {}", alpha);
+        let background = LinearRgba::from_f32_array(state.background.color).to_f32_array_no_alpha();
+        let composite = |layers: i32| {
+            let coverage = 1.0 - (1.0 - alpha).powi(layers);
+            let [r, g, b] = [r, g, b].map(|c| c * coverage);
+            let [br, bg, bb] = background.map(|c| c * (1.0 - coverage));
+            Srgba::from(LinearRgba::rgb(r + br, g + bg, b + bb)).to_u8_array_no_alpha()
+        };
+
+        let assert_close = |actual: [u8; 3], expected: [u8; 3], what: &str| {
+            assert!(
+                actual.iter().zip(expected).all(|(a, e)| a.abs_diff(e) <= 2),
+                "{}: expected {:?}, got {:?}", what, expected, actual
+            );
+        };
+        assert_close(pixel_at(&state, &snapshot, Vec2::new(-50.0, 0.0)), composite(1), "single line");
+        assert_close(pixel_at(&state, &snapshot, Vec2::ZERO), composite(2), "intersection");
+    }
+
+    /// 折线形高亮路径在每个拐角处没有缺口：线路端点（前后两跳与节点内转接线段的连接处）周围半个线宽以内的像素
+    /// 都是高亮颜色（高亮线路绘制在节点之上，缺口处会露出节点或背景），沿每一跳线路的中心线也没有断开
+    #[test]
+    fn zig_zag_highlighted_path_has_no_gaps() {
+        let nodes = [("A", 0.0, 0.0), ("B", 40.0, 120.0), ("C", 80.0, 0.0), ("D", 120.0, 120.0), ("E", 160.0, 0.0)];
+        let path = ["A", "B", "C", "D", "E"];
+        let links: Vec<_> = path.windows(2).map(|hop| (hop[0], hop[1])).collect();
+        let fixture = topology(&nodes, &links, &[(1, &path, None)]);
+        let Some((state, snapshot)) = render_with(fixture, SnapshotCamera::FitToTopology, (320, 240), |state| {
+            state.highlight_style.thickness = 10.0; // 线宽较大、拐角较急时缺口有几个像素宽
+            state.highlight_service_id_list = Some(vec![1]);
+            state.topology_needs_update = true;
+        }) else {
+            return;
+        };
+        assert!(!state.geometry.highlight_line_vertices.is_empty(), "the service should be highlighted");
+
+        let highlight_color = LinearRgba::from_f32_array(state.geometry.highlight_line_vertices[0].color);
+        let highlight_color = Srgba::from(highlight_color).to_u8_array_no_alpha();
+        let is_covered = |world_pos: Vec2| {
+            let actual = pixel_at(&state, &snapshot, world_pos);
+            actual.iter().zip(highlight_color).all(|(a, e)| a.abs_diff(e) <= 2)
+        };
+
+        let instances = &state.geometry.circle_instances;
+        let half_width = state.highlight_style.thickness / 2.0;
+        // 每像素约一个采样点，圆盘比半个线宽小一个像素，避开边缘的光栅化差异
+        let pixel = 1.0 / state.camera.world_radius_to_screen_pixels(1.0);
+        let disc_radius = half_width - pixel;
+        let last_hop = instances.len() - 2;
+        for (i, hop) in instances.windows(2).enumerate() {
+            let (source, target) = (Vec2::from_array(hop[0].position), Vec2::from_array(hop[1].position));
+            let direction = (target - source).normalize();
+            let start = source + direction * lane_radius(&hop[0]);
+            let end = target - direction * lane_radius(&hop[1]);
+
+            // 两端各留一个像素：路径起止处的线路是平头，端点所在的像素可能只覆盖一半
+            let steps = (start.distance(end) / pixel) as u32;
+            for step in 1..steps {
+                let point = start.lerp(end, step as f32 / steps as f32);
+                assert!(is_covered(point), "gap along the hop at {:?}", point);
+            }
+            let joins = [(i > 0).then_some(start), (i < last_hop).then_some(end)];
+            for join in joins.into_iter().flatten() {
+                let samples = (disc_radius / pixel).ceil() as i32;
+                for (dx, dy) in (-samples..=samples).flat_map(|dx| (-samples..=samples).map(move |dy| (dx, dy))) {
+                    let offset = Vec2::new(dx as f32, dy as f32) * pixel;
+                    if offset.length() <= disc_radius {
+                        assert!(is_covered(join + offset), "gap at the join {:?} (offset {:?})", join, offset);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+// src/heat_trail.rs
+// 热度轨迹模式：链路按 current_time_selection 之前一段时间窗口内的平均占用率着色，用于发现长期拥塞的链路。
+// 平均值在重新生成几何时由已加载的事件积分得到（scene::defrag_event::time_averaged_link_occupancy），
+// 切换模式或修改窗口只需要重新生成几何，不需要重新加载事件。
+use crate::app_state::State;
+use crate::settings::is_positive_finite;
+
+/// 未指定窗口长度时，使用整个时间轴跨度的这一比例
+const DEFAULT_HEAT_TRAIL_WINDOW_FRACTION: f64 = 0.1;
+
+impl State {
+    /// window 为窗口长度（秒），None 表示时间轴跨度的 10%
+    pub fn set_heat_trail(&mut self, enabled: bool, window: Option<f64>) -> Result<(), String> {
+        if let Some(window) = window && !is_positive_finite(window) {
+            return Err(format!("Heat trail window must be positive, got {}", window));
+        }
+        self.heat_trail_enabled = enabled;
+        self.heat_trail_window = window;
+        self.topology_needs_update = true;
+        Ok(())
+    }
+
+    /// 热度轨迹模式下的窗口长度（秒）；瞬时模式或时间轴为空时返回 None
+    pub fn heat_trail_window_seconds(&self) -> Option<f64> {
+        if !self.heat_trail_enabled {
+            return None;
+        }
+        if let Some(window) = self.heat_trail_window {
+            return Some(window);
+        }
+        let (first, last) = (self.all_events.first()?, self.all_events.last()?);
+        let span = last.timestamp() - first.timestamp();
+        (span > 0.0).then_some(span * DEFAULT_HEAT_TRAIL_WINDOW_FRACTION)
+    }
+}
@@ -0,0 +1,113 @@
+// src/keymap.rs
+// 键盘快捷键：物理按键（KeyCode）到操作（KeyAction）的映射，可通过 setKeymap 整体替换。
+// 按键名称与浏览器 KeyboardEvent.code 相同，例如 "KeyW"、"ArrowUp"、"F3"；空映射表示不处理任何按键。
+use std::collections::HashMap;
+use serde::de::{value::StrDeserializer, IntoDeserializer};
+use serde::Deserialize;
+use winit::keyboard::KeyCode;
+
+/// 快捷键可触发的操作，JSON 中使用 snake_case 名称（"pan_up"、"toggle_stats" 等）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    /// 重新适配视角到整个拓扑
+    ResetView,
+    /// 在日志中输出当前帧率
+    LogFps,
+    /// 在日志中输出渲染后端信息（适配器、表面格式、设备限制）
+    LogRendererInfo,
+    ToggleStats,
+    /// 显示 / 隐藏画布内的活跃服务列表
+    ToggleServicePanel,
+    /// 显示 / 隐藏画布底部的时间轴条
+    ToggleTimelineStrip,
+    /// 导出 SVG 到 wdmview-export.svg（仅桌面端）；按住 Shift 时导出整个拓扑
+    ExportSvg,
+    /// 切换无边框全屏（仅桌面端），全屏时 Escape 也会退出
+    ToggleFullscreen,
+    /// 循环键盘焦点；按住 Shift 时反向
+    CycleFocus,
+    ActivateFocused,
+    /// 需同时按住 Ctrl 才生效；按住 Shift 时重做
+    UndoLayoutEdit,
+    /// 开启 / 取消测量模式（接下来点击的两个节点为端点）
+    ToggleMeasurement,
+    ClearMeasurement,
+    /// 取消测量并清除所有高亮的服务
+    ClearSelection,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyCode, KeyAction>,
+}
+
+impl Default for Keymap {
+    /// 与最初硬编码的快捷键相同
+    fn default() -> Self {
+        let mut bindings = HashMap::from([
+            (KeyCode::KeyW, KeyAction::PanUp),
+            (KeyCode::ArrowUp, KeyAction::PanUp),
+            (KeyCode::KeyS, KeyAction::PanDown),
+            (KeyCode::ArrowDown, KeyAction::PanDown),
+            (KeyCode::KeyA, KeyAction::PanLeft),
+            (KeyCode::ArrowLeft, KeyAction::PanLeft),
+            (KeyCode::KeyD, KeyAction::PanRight),
+            (KeyCode::ArrowRight, KeyAction::PanRight),
+            (KeyCode::KeyQ, KeyAction::ZoomIn),
+            (KeyCode::KeyE, KeyAction::ZoomOut),
+            (KeyCode::KeyR, KeyAction::LogFps),
+            (KeyCode::F2, KeyAction::LogRendererInfo),
+            (KeyCode::F3, KeyAction::ToggleStats),
+            (KeyCode::KeyL, KeyAction::ToggleServicePanel),
+            (KeyCode::KeyT, KeyAction::ToggleTimelineStrip),
+            (KeyCode::Tab, KeyAction::CycleFocus),
+            (KeyCode::Enter, KeyAction::ActivateFocused),
+            (KeyCode::NumpadEnter, KeyAction::ActivateFocused),
+            (KeyCode::KeyM, KeyAction::ToggleMeasurement),
+            (KeyCode::Escape, KeyAction::ClearSelection),
+        ]);
+        // 浏览器中 Ctrl+Z、F4 和 F11 保留给页面，默认只在桌面端绑定
+        if cfg!(not(target_arch = "wasm32")) {
+            bindings.insert(KeyCode::F4, KeyAction::ExportSvg);
+            bindings.insert(KeyCode::F11, KeyAction::ToggleFullscreen);
+            bindings.insert(KeyCode::KeyZ, KeyAction::UndoLayoutEdit);
+        }
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    pub fn action(&self, code: KeyCode) -> Option<KeyAction> {
+        self.bindings.get(&code).copied()
+    }
+
+    /// 解析 `{ "<按键名>": "<操作名>", ... }`，例如 `{"ArrowUp": "pan_up", "Equal": "zoom_in"}`
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let named_bindings: HashMap<String, KeyAction> = serde_json::from_str(json)
+            .map_err(|e| format!("JSON parsing error: {}", e))?;
+        let bindings = named_bindings
+            .into_iter()
+            .map(|(name, action)| Ok((parse_key_name(&name)?, action)))
+            .collect::<Result<_, String>>()?;
+        Ok(Self { bindings })
+    }
+}
+
+/// 按 KeyboardEvent.code 的写法解析按键名
+fn parse_key_name(name: &str) -> Result<KeyCode, String> {
+    let deserializer: StrDeserializer<serde::de::value::Error> = name.into_deserializer();
+    KeyCode::deserialize(deserializer).map_err(|_| {
+        format!(
+            "Unknown key name '{}', expected a KeyboardEvent.code value such as \"KeyW\", \"Digit1\", \"ArrowUp\", \"Equal\" or \"F3\"",
+            name
+        )
+    })
+}
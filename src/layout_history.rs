@@ -0,0 +1,78 @@
+// src/layout_history.rs
+// 节点位置编辑（拖动、setNodePosition）的撤销 / 重做栈，以及布局的导出 / 导入格式
+use std::collections::{BTreeMap, VecDeque};
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// 撤销栈最多保留的条目数，超出后丢弃最早的编辑
+const MAX_LAYOUT_HISTORY: usize = 100;
+
+/// 一次节点位置编辑，坐标为世界坐标（与 CircleInstance::position 相同）
+#[derive(Debug, Clone)]
+pub struct LayoutEdit {
+    pub element_id: String,
+    pub old_position: Vec2,
+    pub new_position: Vec2,
+}
+
+#[derive(Debug, Default)]
+pub struct LayoutHistory {
+    undo_stack: VecDeque<LayoutEdit>,
+    redo_stack: Vec<LayoutEdit>,
+}
+
+impl LayoutHistory {
+    /// 记录新的编辑，同时清空重做栈
+    pub fn record(&mut self, edit: LayoutEdit) {
+        self.redo_stack.clear();
+        self.undo_stack.push_back(edit);
+        if self.undo_stack.len() > MAX_LAYOUT_HISTORY {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    pub fn undo(&mut self) -> Option<LayoutEdit> {
+        let edit = self.undo_stack.pop_back()?;
+        self.redo_stack.push(edit.clone());
+        Some(edit)
+    }
+
+    pub fn redo(&mut self) -> Option<LayoutEdit> {
+        let edit = self.redo_stack.pop()?;
+        self.undo_stack.push_back(edit.clone());
+        Some(edit)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+/// 导出 / 导入的节点位置，坐标约定与拓扑数据中的 location 相同（y 轴向下），
+/// 即 world_position = (x, -y)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LayoutPosition {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl LayoutPosition {
+    pub fn from_world(world_pos: Vec2) -> Self {
+        Self { x: world_pos.x, y: -world_pos.y }
+    }
+
+    pub fn to_world(self) -> Vec2 {
+        Vec2::new(self.x, -self.y)
+    }
+}
+
+/// `{ element_id: { x, y } }`，按 element_id 排序以便导出结果稳定
+pub type NodeLayout = BTreeMap<String, LayoutPosition>;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct LayoutImportSummary {
+    pub applied: usize,
+    /// 布局中存在但当前拓扑中没有的节点数
+    pub unknown_ids: usize,
+}
@@ -4,8 +4,8 @@ use winit::{
     application::ApplicationHandler,
     event::*,
     event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy},
-    keyboard::{KeyCode, PhysicalKey, SmolStr},
-    window::Window,
+    keyboard::{PhysicalKey, SmolStr},
+    window::{CursorIcon, Window},
 };
 use instant::Instant;
 use glam::Vec2;
@@ -25,11 +25,107 @@ mod camera;
 mod scene;
 mod ui_events;
 mod app_state;
+mod settings;
+mod node_icons;
+mod picking;
+mod renderer;
+mod notifications;
+mod errors;
+mod layout_history;
+mod bookmarks;
+mod saved_views;
+mod session;
+mod capture;
+mod recording;
+mod keymap;
+mod viewport;
+mod scene_description;
+mod geometry_update;
+mod path_highlight;
+mod measurement;
+mod capacity_bars;
+mod heat_trail;
+mod compare;
+mod service_diff;
+mod flow_animation;
+mod service_selection;
+mod service_panel;
+mod overlay;
+mod service_template;
+mod color_legend;
+mod channel_plan;
+mod node_radius;
+mod node_overlap;
+mod node_culling;
+mod edge_bundling;
+mod color_test_pattern;
+mod background;
+mod quality;
+mod gpu_profiler;
+mod service_hover;
+mod link_inspector;
+mod blocked_demands;
+mod timeline_strip;
+mod node_groups;
+mod annotations;
+mod text_resources;
+mod renderer_info;
+mod topology_export;
+mod topology_merge;
+#[cfg(target_arch = "wasm32")]
+mod canvas_input;
+#[cfg(target_arch = "wasm32")]
+mod panic_report;
+#[cfg(not(target_arch = "wasm32"))]
+mod topology_file;
+#[cfg(not(target_arch = "wasm32"))]
+mod window_title;
+#[cfg(not(target_arch = "wasm32"))]
+mod fullscreen;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod headless;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod synthetic;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod bench_support;
 
 use ui_events::UserCommand;
-use app_state::State;
 #[cfg(target_arch = "wasm32")]
+use ui_events::StepDirection;
+use app_state::{State, CLICK_MAX_DRAG_PX};
+use keymap::KeyAction;
 use scene::network::FullTopologyData;
+#[cfg(target_arch = "wasm32")]
+use scene::network::{parse_topology_json, TimelineEventsData, TopologyParseError, TopologyStructureData};
+#[cfg(target_arch = "wasm32")]
+use scene::dot::parse_dot;
+#[cfg(target_arch = "wasm32")]
+use scene::defrag_event::EventKind;
+use scene::svg::SvgBounds;
+#[cfg(target_arch = "wasm32")]
+use scene::graph::PathWeight;
+#[cfg(target_arch = "wasm32")]
+use settings::{Background, ChannelPlan, ColorMode, HighlightLineStyle, HighlightStyle, LabelSettings, LodSettings, OpacityMode, ServiceIntervalSemantics, ThicknessMode};
+#[cfg(target_arch = "wasm32")]
+use node_icons::NodeIconOverrides;
+#[cfg(target_arch = "wasm32")]
+use node_radius::{validate_node_radius, NodeRadiusOverrides};
+#[cfg(target_arch = "wasm32")]
+use edge_bundling::validate_bundling_strength;
+#[cfg(target_arch = "wasm32")]
+use scene::service::ServiceData;
+#[cfg(target_arch = "wasm32")]
+use layout_history::NodeLayout;
+#[cfg(target_arch = "wasm32")]
+use node_groups::NodeGroups;
+#[cfg(target_arch = "wasm32")]
+use annotations::Annotation;
+#[cfg(target_arch = "wasm32")]
+use saved_views::SavedView;
+#[cfg(target_arch = "wasm32")]
+use service_template::ServiceTemplate;
+#[cfg(target_arch = "wasm32")]
+use std::collections::BTreeMap;
 
 #[cfg(target_arch = "wasm32")]
 static WASM_API_INSTANCE: OnceCell<WasmApi> = OnceCell::new();
@@ -42,31 +138,73 @@ static WASM_READY_FLUME_CHANNEL: OnceCell<(flume::Sender<()>, flume::Receiver<()
 #[cfg(target_arch = "wasm32")]
 static CANVAS_READY_FLUME_CHANNEL: OnceCell<(flume::Sender<()>, flume::Receiver<()>)> = OnceCell::new();
 
+/// 原生平台按 F4 导出的 SVG 文件（写入当前工作目录）
+#[cfg(not(target_arch = "wasm32"))]
+const SVG_EXPORT_PATH: &str = "wdmview-export.svg";
+
+/// 普通滚轮每格的缩放倍数
+const WHEEL_ZOOM_FACTOR: f32 = 1.1;
+/// 按行滚动（LineDelta）时每行对应的像素数
+const WHEEL_LINE_PX: f32 = 10.0;
+/// Ctrl+滚轮的精细缩放：每滚动一个像素缩放 exp(0.002) 倍，即每行约 2%
+const FINE_ZOOM_PER_PX: f32 = 0.002;
+
 struct App {
     window: Option<Arc<Window>>,
+    cursor_icon: CursorIcon, // 最近一次设置的光标形状，避免重复调用 set_cursor
     state: Arc<Mutex<Option<State>>>, // Wrapped in Arc<Mutex> for interior mutability and potential Send (if State itself were Send)
     #[cfg(target_arch = "wasm32")]
     proxy: Option<EventLoopProxy<UserCommand>>,
+    #[cfg(target_arch = "wasm32")]
+    canvas_input: Option<canvas_input::CanvasInput>, // 画布元素上的指针捕获和右键菜单监听，destroyView 时移除
+    #[cfg(target_arch = "wasm32")]
+    canvas_ready_pending: Option<Instant>, // 收到 AttachCanvas 的时刻；第一帧真正呈现后才 resolve attachCanvasToDom 返回的 Promise
+    #[cfg(not(target_arch = "wasm32"))]
+    startup_command: Option<UserCommand>, // 命令行指定的拓扑或会话文件，窗口创建后加载
+    #[cfg(not(target_arch = "wasm32"))]
+    window_title: window_title::WindowTitle,
+    #[cfg(not(target_arch = "wasm32"))]
+    fullscreen: fullscreen::FullscreenToggle,
 }
 
 impl App {
-    fn new(#[cfg(target_arch = "wasm32")] event_loop: &EventLoop<UserCommand>) -> Self {
+    fn new(
+        #[cfg(target_arch = "wasm32")] event_loop: &EventLoop<UserCommand>,
+        #[cfg(not(target_arch = "wasm32"))] startup_command: Option<UserCommand>,
+        #[cfg(not(target_arch = "wasm32"))] topology_name: Option<String>,
+        #[cfg(not(target_arch = "wasm32"))] start_fullscreen: bool,
+    ) -> Self {
         #[cfg(target_arch = "wasm32")]
         let app_proxy = event_loop.create_proxy();
 
         #[cfg(target_arch = "wasm32")]
         {
-            let wasm_api_instance = WasmApi { proxy: app_proxy.clone() };
+            let wasm_api_instance = WasmApi { proxy: panic_report::CommandProxy::new(app_proxy.clone()) };
             if WASM_API_INSTANCE.set(wasm_api_instance).is_err() {
                 log::warn!("WASM_API_INSTANCE was already set. This should only happen once.");
             }
         }
 
+        let state = Arc::new(Mutex::new(None));
+        #[cfg(target_arch = "wasm32")]
+        panic_report::set_app_state(Arc::downgrade(&state));
+
         Self {
             window: None,
-            state: Arc::new(Mutex::new(None)),
+            cursor_icon: CursorIcon::Default,
+            state,
             #[cfg(target_arch = "wasm32")]
             proxy: Some(app_proxy),
+            #[cfg(target_arch = "wasm32")]
+            canvas_input: None,
+            #[cfg(target_arch = "wasm32")]
+            canvas_ready_pending: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            startup_command,
+            #[cfg(not(target_arch = "wasm32"))]
+            window_title: window_title::WindowTitle::new(topology_name),
+            #[cfg(not(target_arch = "wasm32"))]
+            fullscreen: fullscreen::FullscreenToggle::new(start_fullscreen),
         }
     }
 
@@ -79,7 +217,11 @@ impl App {
     fn create_window_and_state(&mut self, event_loop: &ActiveEventLoop, canvas_id: String) {
         log::info!("Attempting to create window and state for canvas: {}", canvas_id);
         let mut window_attributes = Window::default_attributes()
-            .with_title("WDMView Graph Topology");
+            .with_title("WDMView Graph Topology"); // 原生平台之后由 window_title 更新
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            window_attributes = self.fullscreen.apply_to_attributes(window_attributes);
+        }
 
         #[cfg(target_arch = "wasm32")]
         {
@@ -96,7 +238,9 @@ impl App {
                     return;
                 }
             };
-            let html_canvas_element = canvas.unchecked_into();
+            let html_canvas_element: web_sys::HtmlCanvasElement = canvas.unchecked_into();
+            self.canvas_input = Some(canvas_input::CanvasInput::new(html_canvas_element.clone()));
+            panic_report::set_canvas(Some(html_canvas_element.clone()));
             window_attributes = window_attributes.with_canvas(Some(html_canvas_element));
         }
 
@@ -142,11 +286,32 @@ impl App {
 }
 
 impl ApplicationHandler<UserCommand> for App {
+    /// 每批事件处理完后更新窗口标题；因节流推迟的更新通过 WaitUntil 在到期时完成
+    #[cfg(not(target_arch = "wasm32"))]
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let (Some(window), Some(state)) = (self.window.as_ref(), &*self.state.lock().unwrap()) else {
+            return;
+        };
+        match self.window_title.refresh(window, state) {
+            Some(next_update) => event_loop.set_control_flow(winit::event_loop::ControlFlow::WaitUntil(next_update)),
+            None => event_loop.set_control_flow(winit::event_loop::ControlFlow::Wait),
+        }
+    }
+
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         // -- REMOVE: Do not create a window on startup anymore!
         // self.create_window_and_state(event_loop, String::from_str("canvas").unwrap());
         log::info!("Winit event loop resumed and is active. Waiting for commands.");
 
+        // 原生平台没有 attachCanvasToDom，启动时直接创建窗口
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.window.is_none() {
+            self.create_window_and_state(event_loop, String::from_str("main").unwrap());
+            if let (Some(command), Some(state)) = (self.startup_command.take(), &mut *self.state.lock().unwrap()) {
+                state.process_command(command);
+            }
+        }
+
         // We can signal that the API is ready now, even without a view.
         #[cfg(target_arch = "wasm32")]
         if let Some((sender, _)) = WASM_READY_FLUME_CHANNEL.get() {
@@ -166,18 +331,16 @@ impl ApplicationHandler<UserCommand> for App {
                     return;
                 }
                 log::info!("Received AttachCanvas command for id: {}", canvas_id);
+                // attachCanvasToDom 在第一帧（只有节点和线路，字体尚未加载）呈现后 resolve，并记录启动耗时
+                #[cfg(target_arch = "wasm32")]
+                {
+                    self.canvas_ready_pending = Some(Instant::now());
+                }
                 self.create_window_and_state(event_loop, canvas_id);
             }
 
             UserCommand::StateInitialized => {
                 log::info!("State initialized and ready for rendering.");
-                
-                #[cfg(target_arch = "wasm32")]
-                if let Some((sender, _)) = CANVAS_READY_FLUME_CHANNEL.get() {
-                    if let Err(e) = sender.send(()) {
-                        log::error!("Failed to send CANVAS attach ready signal: {:?}", e);
-                    }
-                }
 
                 if let Some(w_handle) = self.window.as_ref() {
                     w_handle.request_redraw();
@@ -194,6 +357,7 @@ impl ApplicationHandler<UserCommand> for App {
                 }
 
                 log::info!("Destroying window and state.");
+                errors::clear(); // 丢弃针对旧视图、尚未回调的错误
                 // Dropping the State will release wgpu resources.
                 if let Ok(mut state_guard) = self.state.try_lock() {
                     *state_guard = None;
@@ -201,7 +365,18 @@ impl ApplicationHandler<UserCommand> for App {
                     log::error!("Could not lock state to destroy it.");
                 }
                 
+                // 画布元素会保留光标样式，销毁前恢复默认光标
+                if let Some(window) = self.window.as_ref() {
+                    window.set_cursor(CursorIcon::Default);
+                }
+                self.cursor_icon = CursorIcon::Default;
+
                 // Dropping the Window will detach it from the canvas.
+                #[cfg(target_arch = "wasm32")]
+                {
+                    self.canvas_input = None; // 移除画布上的监听器
+                    panic_report::set_canvas(None);
+                }
                 self.window = None;
 
                 // -- IMPORTANT: DO NOT EXIT THE EVENT LOOP!
@@ -211,8 +386,10 @@ impl ApplicationHandler<UserCommand> for App {
             _ => { // All other commands are processed by the state
                 // Lock the state, check if it exists, and then process
                 if let Some(state) = &mut *self.state.lock().unwrap() {
+                    let needs_redraw = !event.is_read_only();
                     state.process_command(event);
-                    if let Some(w_handle) = self.window.as_ref() {
+                    notifications::dispatch(state.take_notifications());
+                    if let Some(w_handle) = self.window.as_ref().filter(|_| needs_redraw) {
                         w_handle.request_redraw();
                     }
                 } else {
@@ -238,7 +415,18 @@ impl ApplicationHandler<UserCommand> for App {
             return;
         };
 
+        // 录制期间相机和时间由录制控制，忽略用户输入
+        if state.is_recording()
+            && matches!(
+                event,
+                WindowEvent::MouseInput { .. } | WindowEvent::CursorMoved { .. } | WindowEvent::MouseWheel { .. } | WindowEvent::KeyboardInput { .. }
+            )
+        {
+            return;
+        }
+
         let mut needs_redraw = false;
+        let mut cursor_may_change = false; // 光标移动或按键后重新检测悬停对象
 
         match event {
             WindowEvent::CloseRequested => event_loop.exit(),
@@ -246,55 +434,185 @@ impl ApplicationHandler<UserCommand> for App {
                 state.resize(size.width, size.height);
                 needs_redraw = true;
             }
+            // 最小化期间仍然推进状态（命令、录制、分帧几何更新等），只跳过绘制；没有 present 限制帧率，
+            // 因此不继续请求重绘，恢复时的 Resized 会重新请求
+            WindowEvent::RedrawRequested if state.is_minimized => {
+                state.update();
+            }
             WindowEvent::RedrawRequested => {
                 if state.update() {
                     needs_redraw = true; // Still need to redraw even if update indicates change
                 }
                 match state.render() {
+                    // 跳过的帧（未配置、最小化、尺寸为零）不算，等到真正呈现的第一帧
+                    #[cfg(target_arch = "wasm32")]
+                    Ok(true) if self.canvas_ready_pending.is_some() => {
+                        if let Some(attached) = self.canvas_ready_pending.take() {
+                            log::info!("First frame presented {:.1} ms after attachCanvasToDom.", attached.elapsed().as_secs_f64() * 1000.0);
+                        }
+                        if let Some((sender, _)) = CANVAS_READY_FLUME_CHANNEL.get() {
+                            if let Err(e) = sender.send(()) {
+                                log::error!("Failed to send CANVAS attach ready signal: {:?}", e);
+                            }
+                        }
+                    }
                     Ok(_) => {}
-                    Err(wgpu::SurfaceError::Lost) => state.resize(state.config.width, state.config.height),
-                    Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
-                    Err(e) => log::error!("{:?}", e),
+                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => state.reconfigure_surface(),
+                    Err(wgpu::SurfaceError::OutOfMemory) => {
+                        errors::report(errors::ViewError::fatal("out_of_memory", "Surface ran out of memory; the view was closed."));
+                        event_loop.exit();
+                    }
+                    // Timeout 等错误可能每帧重复出现，由 errors::report 去重
+                    Err(e) => errors::report(errors::ViewError::error("surface_error", format!("Failed to render frame: {:?}", e))),
                 }
             }
             WindowEvent::MouseInput { state: mouse_button_state, button, .. } => {
+                cursor_may_change = true;
                 match (button, mouse_button_state.is_pressed()) {
+                    // 服务列表面板优先于平移和拖动节点
+                    (MouseButton::Left, true) if state.press_service_panel(state.mouse_canvas_pos_screen) => {
+                        needs_redraw = true;
+                    }
+                    (MouseButton::Left, false) if state.release_service_panel(state.mouse_canvas_pos_screen) => {
+                        needs_redraw = true;
+                    }
+                    // 时间轴条同样优先：按下即跳到对应时刻，拖动期间调整时间
+                    (MouseButton::Left, true) if state.press_timeline_strip(state.mouse_canvas_pos_screen) => {
+                        needs_redraw = true;
+                    }
+                    (MouseButton::Left, false) if state.release_timeline_strip() => {
+                        needs_redraw = true;
+                    }
                     (MouseButton::Left, true) => {
                         state.is_mouse_left_pressed = true;
+                        state.mouse_press_pos_screen = state.mouse_current_pos_screen;
                         log::info!("Mouse screen pos: {}, {}", state.mouse_current_pos_screen[0], state.mouse_current_pos_screen[1]);
                         let mouse_world_pos = state.camera.screen_to_world(state.mouse_current_pos_screen);
                         log::info!("Mouse world pos: {}, {}", mouse_world_pos[0], mouse_world_pos[1]);
-                        state.camera.start_panning(state.mouse_current_pos_screen);
-                        state.camera_needs_update = true;
+                        // 按在节点上时拖动节点，否则平移画布
+                        if let Some(node_idx) = state.node_at_world_pos(mouse_world_pos) {
+                            state.begin_node_drag(node_idx);
+                        } else {
+                            state.camera.start_panning(state.mouse_current_pos_screen);
+                            state.camera_needs_update = true;
+                        }
+                        // 拖动期间捕获指针，光标移出画布也能继续拖动
+                        #[cfg(target_arch = "wasm32")]
+                        if let Some(canvas_input) = &self.canvas_input {
+                            canvas_input.begin_drag();
+                        }
                         needs_redraw = true;
                     }
                     (MouseButton::Left, false) => {
                         state.is_mouse_left_pressed = false;
                         state.camera.end_panning();
+                        state.end_node_drag();
+                        #[cfg(target_arch = "wasm32")]
+                        if let Some(canvas_input) = &self.canvas_input {
+                            canvas_input.end_drag();
+                        }
+                        // 几乎没有移动则视为点击：点在注释标签上时通知 JS，否则执行拾取
+                        if state.mouse_current_pos_screen.distance(state.mouse_press_pos_screen) < CLICK_MAX_DRAG_PX
+                            && !state.click_annotation(state.mouse_current_pos_screen)
+                        {
+                            state.request_pick(state.mouse_current_pos_screen);
+                            needs_redraw = true;
+                        }
                     }
                     _ => {}
                 }
             },
             WindowEvent::CursorMoved { position, .. } => {
-                state.mouse_current_pos_screen = Vec2::new(position.x as f32, position.y as f32);
-                if state.is_mouse_left_pressed {
+                // 对比视图中换算到光标所在一半的本地坐标，两边的平移和缩放相同
+                state.mouse_canvas_pos_screen = Vec2::new(position.x as f32, position.y as f32);
+                state.mouse_current_pos_screen = state.view_local_position(state.mouse_canvas_pos_screen);
+                if state.service_panel.visible {
+                    state.update_service_panel_hover();
+                    needs_redraw |= state.tooltip.is_some(); // 提示框跟随光标
+                }
+                cursor_may_change = true;
+                if state.scrub_timeline_strip(state.mouse_canvas_pos_screen) {
+                    needs_redraw = true;
+                } else if state.dragged_node.is_some() {
+                    // 移动距离低于点击阈值时不拖动，避免点击选中节点时产生微小位移
+                    if state.mouse_current_pos_screen.distance(state.mouse_press_pos_screen) >= CLICK_MAX_DRAG_PX {
+                        let mouse_world_pos = state.camera.screen_to_world(state.mouse_current_pos_screen);
+                        state.drag_node_to(mouse_world_pos);
+                        needs_redraw = true;
+                    }
+                } else if state.is_mouse_left_pressed {
                     state.camera.pan(state.mouse_current_pos_screen);
                     state.camera_needs_update = true;
                     needs_redraw = true;
                 }
+                needs_redraw |= state.update_service_hover();
+                if state.cursor_tracking_enabled {
+                    state.report_cursor_position();
+                }
+                if state.show_stats_overlay {
+                    needs_redraw = true; // 刷新叠加层中的光标坐标
+                }
             },
             WindowEvent::MouseWheel { delta, .. } => {
-                let y_scroll_delta = match delta {
-                    MouseScrollDelta::LineDelta(x, y) => y * 10.0,
-                    MouseScrollDelta::PixelDelta(pos) => pos.y as f32,
+                // 普通滚轮：按固定倍数缩放；Ctrl：按滚动量精细缩放（触控板捏合在浏览器中也以 Ctrl+滚轮上报）；
+                // Shift：水平平移；触控板的像素滚动：双轴平移。缩放都以光标处为中心
+                let modifiers = state.keyboard_modifiers;
+                let (scroll_px, is_pixel_delta) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => (Vec2::new(x, y) * WHEEL_LINE_PX, false),
+                    MouseScrollDelta::PixelDelta(pos) => (Vec2::new(pos.x as f32, pos.y as f32), true),
                 };
 
-                let zoom_factor = if y_scroll_delta > 0.0 { 1.1 } else { 1.0 / 1.1 };
-                let mouse_world_pos = state.camera.screen_to_world(state.mouse_current_pos_screen);
-                state.camera.zoom_by(zoom_factor, mouse_world_pos);
-                state.camera_needs_update = true;
-                needs_redraw = true;
+                if state.service_panel_contains(state.mouse_canvas_pos_screen) {
+                    // 光标在服务列表面板上时滚动列表，不缩放或平移画布
+                    state.scroll_service_panel(-scroll_px.y);
+                    needs_redraw = true;
+                } else if state.timeline_strip_contains(state.mouse_canvas_pos_screen) {
+                    // 时间轴条上的滚轮不缩放画布
+                } else {
+                    let zoom_factor = if modifiers.control_key() {
+                        Some((scroll_px.y * FINE_ZOOM_PER_PX).exp())
+                    } else if modifiers.shift_key() {
+                        // 竖直滚轮按住 Shift 时转为水平方向；部分平台已经把它报告为水平滚动
+                        let horizontal_px = if scroll_px.x != 0.0 { scroll_px.x } else { scroll_px.y };
+                        state.camera.pan_by_screen_delta(Vec2::new(horizontal_px, 0.0));
+                        None
+                    } else if is_pixel_delta {
+                        state.camera.pan_by_screen_delta(scroll_px);
+                        None
+                    } else if scroll_px.y != 0.0 {
+                        Some(if scroll_px.y > 0.0 { WHEEL_ZOOM_FACTOR } else { 1.0 / WHEEL_ZOOM_FACTOR })
+                    } else {
+                        None
+                    };
+
+                    if let Some(zoom_factor) = zoom_factor {
+                        // 不立即缩放，而是累积到目标缩放，由 update() 平滑过渡
+                        state.camera.zoom_smoothly(zoom_factor, state.mouse_current_pos_screen);
+                    }
+                    state.camera_needs_update = true;
+                    needs_redraw = true;
+                }
+            },
+            WindowEvent::CursorLeft { .. } => {
+                needs_redraw |= state.set_hovered_service(None);
+                if self.cursor_icon != CursorIcon::Default {
+                    window_handle.set_cursor(CursorIcon::Default);
+                    self.cursor_icon = CursorIcon::Default;
+                }
+            },
+            WindowEvent::ModifiersChanged(modifiers) => {
+                state.keyboard_modifiers = modifiers.state();
             },
+            // 拖放拓扑文件替换当前场景；已有拓扑时按住 Shift 保持当前相机
+            #[cfg(not(target_arch = "wasm32"))]
+            WindowEvent::DroppedFile(path) => {
+                let preserve_camera = state.keyboard_modifiers.shift_key() && !state.all_elements.is_empty();
+                if let Some(name) = topology_file::load_dropped_file(state, &path, preserve_camera) {
+                    notifications::dispatch(state.take_notifications());
+                    self.window_title.set_topology_name(name);
+                    needs_redraw = true;
+                }
+            }
             WindowEvent::KeyboardInput {
                 event:
                     KeyEvent {
@@ -310,14 +628,66 @@ impl ApplicationHandler<UserCommand> for App {
                     let pan_speed = 1.0 / state.camera.zoom;
                     let zoom_factor = 1.1;
 
-                    match code {
-                        KeyCode::KeyW | KeyCode::ArrowUp => { state.camera.position.y += pan_speed; changed = true; },
-                        KeyCode::KeyS | KeyCode::ArrowDown => { state.camera.position.y -= pan_speed; changed = true; },
-                        KeyCode::KeyA | KeyCode::ArrowLeft => { state.camera.position.x -= pan_speed; changed = true; },
-                        KeyCode::KeyD | KeyCode::ArrowRight => { state.camera.position.x += pan_speed; changed = true; },
-                        KeyCode::KeyQ => { state.camera.zoom *= zoom_factor; changed = true; },
-                        KeyCode::KeyE => { state.camera.zoom /= zoom_factor; changed = true; },
-                        KeyCode::KeyR => { log::info!("FPS: {}", state.current_fps) },
+                    // 全屏时 Escape 只用于退出全屏，不触发绑定的操作
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let exited_fullscreen = code == winit::keyboard::KeyCode::Escape && self.fullscreen.exit(window_handle);
+                    #[cfg(target_arch = "wasm32")]
+                    let exited_fullscreen = false;
+
+                    match state.keymap.action(code).filter(|_| !exited_fullscreen) {
+                        Some(KeyAction::PanUp) => { state.camera.position.y += pan_speed; changed = true; },
+                        Some(KeyAction::PanDown) => { state.camera.position.y -= pan_speed; changed = true; },
+                        Some(KeyAction::PanLeft) => { state.camera.position.x -= pan_speed; changed = true; },
+                        Some(KeyAction::PanRight) => { state.camera.position.x += pan_speed; changed = true; },
+                        Some(KeyAction::ZoomIn) => { state.camera.stop_zoom_animation(); state.camera.zoom *= zoom_factor; changed = true; },
+                        Some(KeyAction::ZoomOut) => { state.camera.stop_zoom_animation(); state.camera.zoom /= zoom_factor; changed = true; },
+                        Some(KeyAction::ResetView) => { state.fit_view_to_topology(); changed = true; },
+                        Some(KeyAction::LogFps) => { log::info!("FPS: {}", state.current_fps) },
+                        Some(KeyAction::LogRendererInfo) => {
+                            match serde_json::to_string_pretty(&state.renderer_info) {
+                                Ok(info) => log::info!("Renderer info:\n{}", info),
+                                Err(e) => log::warn!("Failed to serialize renderer info: {}", e),
+                            }
+                        }
+                        Some(KeyAction::ToggleStats) => { state.show_stats_overlay = !state.show_stats_overlay; needs_redraw = true; },
+                        Some(KeyAction::ToggleServicePanel) => { state.set_service_panel(!state.service_panel.visible); needs_redraw = true; },
+                        Some(KeyAction::ToggleTimelineStrip) => { state.set_timeline_strip(!state.timeline_strip.visible); needs_redraw = true; },
+                        #[cfg(not(target_arch = "wasm32"))]
+                        Some(KeyAction::ExportSvg) => {
+                            // Shift 时导出整个拓扑，否则只导出当前可见区域
+                            let bounds = if state.keyboard_modifiers.shift_key() { SvgBounds::Topology } else { SvgBounds::View };
+                            let svg = state.svg_snapshot(bounds).to_svg();
+                            match std::fs::write(SVG_EXPORT_PATH, svg) {
+                                Ok(()) => log::info!("Exported current view to {}", SVG_EXPORT_PATH),
+                                Err(e) => log::warn!("Failed to write {}: {}", SVG_EXPORT_PATH, e),
+                            }
+                        },
+                        #[cfg(target_arch = "wasm32")]
+                        Some(KeyAction::ExportSvg) => log::warn!("SVG export shortcut is only available on desktop; use exportSvg() instead."),
+                        #[cfg(not(target_arch = "wasm32"))]
+                        Some(KeyAction::ToggleFullscreen) => self.fullscreen.toggle(window_handle),
+                        #[cfg(target_arch = "wasm32")]
+                        Some(KeyAction::ToggleFullscreen) => log::warn!("Fullscreen shortcut is only available on desktop; use the browser's fullscreen instead."),
+                        Some(KeyAction::CycleFocus) => {
+                            state.cycle_node_focus(state.keyboard_modifiers.shift_key());
+                            needs_redraw = true;
+                        },
+                        Some(KeyAction::ActivateFocused) => { state.activate_focused_node(); needs_redraw = true; },
+                        Some(KeyAction::ToggleMeasurement) => state.toggle_measurement_mode(),
+                        Some(KeyAction::ClearMeasurement) => { state.clear_measurement(); needs_redraw = true; },
+                        Some(KeyAction::ClearSelection) => {
+                            state.clear_measurement();
+                            state.clear_service_highlight();
+                            needs_redraw = true;
+                        },
+                        Some(KeyAction::UndoLayoutEdit) if state.keyboard_modifiers.control_key() => {
+                            if state.keyboard_modifiers.shift_key() {
+                                state.redo_layout_edit();
+                            } else {
+                                state.undo_layout_edit();
+                            }
+                            needs_redraw = true;
+                        },
                         _ => {}
                     }
 
@@ -330,6 +700,16 @@ impl ApplicationHandler<UserCommand> for App {
             _ => {}
         }
 
+        if cursor_may_change {
+            let cursor_icon = state.cursor_icon();
+            if cursor_icon != self.cursor_icon {
+                window_handle.set_cursor(cursor_icon);
+                self.cursor_icon = cursor_icon;
+            }
+        }
+
+        notifications::dispatch(state.take_notifications());
+
         if needs_redraw {
             window_handle.request_redraw();
         }
@@ -338,12 +718,46 @@ impl ApplicationHandler<UserCommand> for App {
 
 pub fn run() -> anyhow::Result<()> {
     #[cfg(not(target_arch = "wasm32"))]
-    {
+    let (startup_command, topology_name, start_fullscreen) = {
         env_logger::init();
-    }
+        // 用法: wdmview [TOPOLOGY_FILE]，TOPOLOGY_FILE 为 JSON 或 Graphviz DOT (.dot / .gv)；
+        // 或 wdmview --generate n=5000,seed=7 加载合成拓扑（参数见 synthetic::SyntheticParams::from_spec）；
+        // 或 wdmview --session SESSION_FILE 加载 exportSession 导出的会话
+        // --fullscreen 可以出现在任意位置，以无边框全屏启动
+        let mut args: Vec<std::ffi::OsString> = std::env::args_os().skip(1).collect();
+        let args_before = args.len();
+        args.retain(|arg| arg != "--fullscreen");
+        let start_fullscreen = args.len() != args_before;
+        let mut args = args.into_iter();
+        let set_full_topology = |topology: FullTopologyData| topology_file::set_full_topology_command(topology, true);
+        // 窗口标题中的名称：数据中的 name 字段，缺省为文件名
+        let file_name = |path: &std::ffi::OsStr| std::path::Path::new(path).file_name().map(|name| name.to_string_lossy().into_owned());
+        let (startup_command, topology_name) = match args.next() {
+            Some(flag) if flag == "--generate" => {
+                let spec = args.next().map(|spec| spec.to_string_lossy().into_owned()).unwrap_or_default();
+                let params = synthetic::SyntheticParams::from_spec(&spec).map_err(anyhow::Error::msg)?;
+                let name = format!("synthetic {}", spec);
+                (Some(set_full_topology(synthetic::generate(&params))), Some(name))
+            }
+            Some(flag) if flag == "--session" => {
+                let path = args.next().ok_or_else(|| anyhow::anyhow!("--session requires a file path"))?;
+                let document = topology_file::load_session_file(std::path::Path::new(&path))?;
+                let name = document.topology.name.clone().or_else(|| file_name(&path));
+                (Some(UserCommand::ImportSession(Box::new(document))), name)
+            }
+            Some(path) => {
+                let path = std::path::Path::new(&path);
+                let topology = topology_file::load_topology_file(path)?;
+                let name = topology_file::topology_display_name(&topology, path);
+                (Some(set_full_topology(topology)), name)
+            }
+            None => (None, None),
+        };
+        (startup_command, topology_name, start_fullscreen)
+    };
     #[cfg(target_arch = "wasm32")]
     {
-        console_error_panic_hook::set_once();
+        panic_report::install(); // 包含 console_error_panic_hook 的输出
         console_log::init_with_level(log::Level::Info).unwrap_throw();
         log::info!("Starting WDMView application.");
         let (sender_wasm, receiver_wasm) = flume::unbounded();
@@ -359,6 +773,12 @@ pub fn run() -> anyhow::Result<()> {
     let mut app = App::new(
         #[cfg(target_arch = "wasm32")]
         &event_loop,
+        #[cfg(not(target_arch = "wasm32"))]
+        startup_command,
+        #[cfg(not(target_arch = "wasm32"))]
+        topology_name,
+        #[cfg(not(target_arch = "wasm32"))]
+        start_fullscreen,
     );
     event_loop.run_app(&mut app)?;
 
@@ -400,21 +820,90 @@ pub fn run_web() -> Result<(), wasm_bindgen::JsValue> {
 #[wasm_bindgen]
 #[derive(Clone, Debug)]
 pub struct WasmApi {
-    proxy: EventLoopProxy<UserCommand>,
+    proxy: panic_report::CommandProxy,
+}
+
+#[cfg(target_arch = "wasm32")]
+fn topology_parse_error_to_js(e: TopologyParseError) -> JsValue {
+    serde_json::to_string(&e)
+        .ok()
+        .and_then(|error_json| js_sys::JSON::parse(&error_json).ok())
+        .unwrap_or_else(|| JsValue::from_str(&e.to_string()))
+}
+
+/// PNG 字节包装为 image/png 类型的 Blob
+#[cfg(target_arch = "wasm32")]
+fn png_blob(png: &[u8]) -> Result<JsValue, JsValue> {
+    use js_sys::{Array, Function, Object, Reflect};
+    let blob_options = Object::new();
+    Reflect::set(&blob_options, &JsValue::from_str("type"), &JsValue::from_str("image/png"))?;
+    let blob_class: Function = Reflect::get(&js_sys::global(), &JsValue::from_str("Blob"))?.dyn_into()?;
+    let png_bytes = js_sys::Uint8Array::from(png);
+    Reflect::construct(&blob_class, &Array::of2(&Array::of1(&png_bytes), &blob_options))
+}
+
+/// 通过异步 Clipboard API（navigator.clipboard.write + ClipboardItem）写入 PNG 图片，返回 write 的 Promise。
+/// 必须在用户手势的处理函数中同步调用：Safari 只在手势期间允许写剪贴板，所以 ClipboardItem 直接包装
+/// 尚未完成的 `Promise<Blob>`，截图和编码在 write 开始之后才进行。
+/// 这些 API 只在安全上下文中可用，用 Reflect 访问以便在不支持的浏览器中给出明确的错误
+#[cfg(target_arch = "wasm32")]
+fn write_png_to_clipboard(png_blob_promise: &Promise) -> Result<Promise, JsValue> {
+    use js_sys::{Array, Function, Object, Reflect};
+    let global = js_sys::global();
+    let navigator = Reflect::get(&global, &JsValue::from_str("navigator"))?;
+    let clipboard = Reflect::get(&navigator, &JsValue::from_str("clipboard"))?;
+    let clipboard_item_class = Reflect::get(&global, &JsValue::from_str("ClipboardItem"))?;
+    if clipboard.is_undefined() || clipboard_item_class.is_undefined() {
+        return Err(JsValue::from_str("Clipboard API is not available (it requires a secure context, e.g. https or localhost)."));
+    }
+
+    let item_data = Object::new();
+    Reflect::set(&item_data, &JsValue::from_str("image/png"), png_blob_promise)?;
+    let clipboard_item = Reflect::construct(&clipboard_item_class.dyn_into::<Function>()?, &Array::of1(&item_data))?;
+
+    let write: Function = Reflect::get(&clipboard, &JsValue::from_str("write"))?.dyn_into()?;
+    write.call1(&clipboard, &Array::of1(&clipboard_item))?.dyn_into()
+}
+
+/// stepForward / stepBackward 的共同实现：resolve 为落到的事件，没有更多事件时为 null
+#[cfg(target_arch = "wasm32")]
+fn send_step_to_event(proxy: &panic_report::CommandProxy, direction: StepDirection, filter: Option<String>) -> Result<Promise, JsValue> {
+    let filter = filter
+        .map(|name| name.parse::<EventKind>())
+        .transpose()
+        .map_err(|e| JsValue::from_str(&e))?;
+    let (reply_sender, reply_receiver) = flume::bounded(1);
+    if proxy.send_event(UserCommand::StepToEvent { direction, filter, reply: reply_sender }).is_err() {
+        return Err(JsValue::from_str("Failed to send StepToEvent command to event loop."));
+    }
+    Ok(future_to_promise(async move {
+        let event = reply_receiver.recv_async().await
+            .map_err(|_| JsValue::from_str("Step command was dropped: no view is attached."))?;
+        let event_json = serde_json::to_string(&event)
+            .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+        js_sys::JSON::parse(&event_json)
+    }))
 }
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 impl WasmApi {
+    /// merge 为 true 时并入当前场景：新节点追加，重复的 element_id 保留现有节点，链路按 connection_id 去重，
+    /// 事件合并后重新排序，冲突记录在验证报告中。fit_view 默认在替换时为 true、合并时为 false
     #[wasm_bindgen(js_name = setFullTopology)]
-    pub fn set_full_topology(&self, topology_json: &str) -> Result<(), JsValue> {
-        let parsed_topology: FullTopologyData = serde_json::from_str(topology_json)
-            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+    pub fn set_full_topology(&self, topology_json: &str, merge: Option<bool>, fit_view: Option<bool>) -> Result<(), JsValue> {
+        let merge = merge.unwrap_or(false);
+        // reject 为结构化的 `{ path, message, service_id? }` 对象，便于前端高亮出错的记录
+        let parsed_topology = FullTopologyData::from_json_str(topology_json).map_err(topology_parse_error_to_js)?;
 
         let command = UserCommand::SetFullTopology {
             elements: parsed_topology.elements,
             connections: parsed_topology.connections,
             defrag_timeline_events: parsed_topology.defrag_timeline_events,
+            channel_plan: parsed_topology.channel_plan,
+            blocked_demands: parsed_topology.blocked_demands,
+            merge,
+            fit_view: fit_view.unwrap_or(!merge),
         };
 
         log::info!("Received SetFullTopology command from JS.");
@@ -425,8 +914,94 @@ impl WasmApi {
         Ok(())
     }
 
+    /// strict 为 true 时，之后加载的拓扑中出现未知的 event_type 会直接解析失败；
+    /// 默认（宽松模式）下这些事件被保留为 UNKNOWN 类型、在绘制时跳过，并计入验证报告
+    #[wasm_bindgen(js_name = setStrictEventParsing)]
+    pub fn set_strict_event_parsing(&self, strict: bool) {
+        scene::defrag_event::set_strict_event_parsing(strict);
+    }
+
+    /// 只设置节点和链路：`{ "elements": [...], "connections": [...] }`。
+    /// 已加载的时间轴事件在其路径节点仍然存在时保留，否则被清空（记录在验证报告中）。
+    #[wasm_bindgen(js_name = setTopologyStructure)]
+    pub fn set_topology_structure(&self, structure_json: &str) -> Result<(), JsValue> {
+        let structure: TopologyStructureData = parse_topology_json(structure_json).map_err(topology_parse_error_to_js)?;
+        let command = UserCommand::SetTopologyStructure {
+            elements: structure.elements,
+            connections: structure.connections,
+        };
+        if self.proxy.send_event(command).is_err() {
+            return Err(JsValue::from_str("Failed to send SetTopologyStructure command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 只设置时间轴事件：`{ "defrag_timeline_events": [...] }`，节点和链路保持不变
+    #[wasm_bindgen(js_name = setTimelineEvents)]
+    pub fn set_timeline_events(&self, events_json: &str) -> Result<(), JsValue> {
+        let events: TimelineEventsData = parse_topology_json(events_json).map_err(topology_parse_error_to_js)?;
+        if self.proxy.send_event(UserCommand::SetTimelineEvents(events.defrag_timeline_events)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetTimelineEvents command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 从 MessagePack 编码的 `Uint8Array` 加载拓扑，避免在 JS 中生成巨大的 JSON 字符串。
+    /// 数据结构、字段名和 event_type 标签与 setFullTopology 的 JSON 完全相同，
+    /// 所有对象都编码为 map（例如 `@msgpack/msgpack` 的 `encode(topology)`）。错误格式与 setFullTopology 相同。
+    /// merge 和 fit_view 与 setFullTopology 相同
+    #[wasm_bindgen(js_name = setFullTopologyBinary)]
+    pub fn set_full_topology_binary(&self, bytes: &[u8], merge: Option<bool>, fit_view: Option<bool>) -> Result<(), JsValue> {
+        let merge = merge.unwrap_or(false);
+        let parsed_topology = FullTopologyData::from_msgpack(bytes).map_err(topology_parse_error_to_js)?;
+
+        let command = UserCommand::SetFullTopology {
+            elements: parsed_topology.elements,
+            connections: parsed_topology.connections,
+            defrag_timeline_events: parsed_topology.defrag_timeline_events,
+            channel_plan: parsed_topology.channel_plan,
+            blocked_demands: parsed_topology.blocked_demands,
+            merge,
+            fit_view: fit_view.unwrap_or(!merge),
+        };
+
+        log::info!("Received SetFullTopology command (MessagePack, {} bytes) from JS.", bytes.len());
+
+        if self.proxy.send_event(command).is_err() {
+            return Err(JsValue::from_str("Failed to send command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 从 Graphviz DOT 文本加载拓扑（没有时间轴事件），返回解析警告（字符串数组）
+    #[wasm_bindgen(js_name = setTopologyFromDot)]
+    pub fn set_topology_from_dot(&self, dot_text: &str) -> Result<JsValue, JsValue> {
+        let import = parse_dot(dot_text)
+            .map_err(|e| JsValue::from_str(&format!("DOT parsing error: {}", e)))?;
+        for warning in &import.warnings {
+            log::warn!("DOT import: {}", warning);
+        }
+
+        let command = UserCommand::SetFullTopology {
+            elements: import.topology.elements,
+            connections: import.topology.connections,
+            defrag_timeline_events: import.topology.defrag_timeline_events,
+            channel_plan: import.topology.channel_plan,
+            blocked_demands: import.topology.blocked_demands,
+            merge: false,
+            fit_view: true,
+        };
+        if self.proxy.send_event(command).is_err() {
+            return Err(JsValue::from_str("Failed to send command to event loop."));
+        }
+        Ok(import.warnings.iter().map(|warning| JsValue::from_str(warning)).collect::<js_sys::Array>().into())
+    }
+
+    /// 每条链路的波长数，等同于 setChannelPlan({"max_wavelengths": num_channels})，展开角度保持不变。
+    /// 波长数必须在 1 到 1024（settings::MAX_WAVELENGTHS）之间，否则抛出错误
     #[wasm_bindgen(js_name = setNumChannels)]
     pub fn set_num_channels(&self, num_channels: u32) -> Result<(), JsValue> {
+        ChannelPlan { max_wavelengths: num_channels, ..ChannelPlan::default() }.validate().map_err(|e| JsValue::from_str(&e))?;
         let command = UserCommand::SetNumChannels { num_channels };
 
         log::info!("Received SetNumChannels command from JS.");
@@ -437,9 +1012,80 @@ impl WasmApi {
         Ok(())
     }
 
+    /// 设置信道规划，例如 C+L 波段 `{"max_wavelengths": 160, "spread_angle": 0.19, "boundary_angle": 0.2}`。
+    /// 角度为弧度（默认 boundary_angle = π/16，spread_angle = 0.95 × boundary_angle），省略的字段取默认值；
+    /// 也可以放在拓扑数据的 channel_plan 字段中。波长数须为正数，角度须在 0 到 π/2 之间
+    #[wasm_bindgen(js_name = setChannelPlan)]
+    pub fn set_channel_plan(&self, channel_plan_json: &str) -> Result<(), JsValue> {
+        let channel_plan: ChannelPlan = serde_json::from_str(channel_plan_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        channel_plan.validate().map_err(|e| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetChannelPlan(channel_plan)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetChannelPlan command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 设置节点半径（世界单位）。省略参数时取消固定半径：开启了 setAutoNodeRadius 时使用自动推算的半径，
+    /// 否则使用默认半径。服务线路和链路边界随半径一起移动
+    #[wasm_bindgen(js_name = setNodeRadius)]
+    pub fn set_node_radius(&self, radius: Option<f32>) -> Result<(), JsValue> {
+        if let Some(radius) = radius {
+            validate_node_radius(radius).map_err(|e| JsValue::from_str(&e))?;
+        }
+        if self.proxy.send_event(UserCommand::SetNodeRadius(radius)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetNodeRadius command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 没有用 setNodeRadius 固定半径时，是否按节点间距自动推算半径（默认关闭，使用默认半径 20）：
+    /// 加载拓扑时取节点最近邻距离中位数的 0.2 倍，结果见 TopologyValidated 通知中验证报告的 derived_node_radius
+    #[wasm_bindgen(js_name = setAutoNodeRadius)]
+    pub fn set_auto_node_radius(&self, enabled: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetAutoNodeRadius(enabled)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetAutoNodeRadius command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 按 element_id 单独设置节点半径，例如 `{"ROADM-1": 40, "ILA-7": null}`；
+    /// 与已有的设置合并，null 移除该节点的覆盖（恢复为 setNodeRadius 的半径）
+    #[wasm_bindgen(js_name = setNodeRadiusOverrides)]
+    pub fn set_node_radius_overrides(&self, overrides_json: &str) -> Result<(), JsValue> {
+        let overrides: NodeRadiusOverrides = serde_json::from_str(overrides_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        if self.proxy.send_event(UserCommand::SetNodeRadiusOverrides(overrides)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetNodeRadiusOverrides command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 是否把位置（几乎）重合的节点分开显示（默认开启）；重合的节点列在验证报告的 overlapping_nodes 中，
+    /// 导出的拓扑和布局始终使用原始位置
+    #[wasm_bindgen(js_name = setSeparateOverlappingNodes)]
+    pub fn set_separate_overlapping_nodes(&self, enabled: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetSeparateOverlappingNodes(enabled)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetSeparateOverlappingNodes command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 力导向边绑定：开启后链路和服务线路沿绑定后的折线绘制，密集的网状拓扑更易读。
+    /// strength 为 0 到 1（省略时为 1），只改变强度不会重新计算；拾取和占用率仍对应原来的链路
+    #[wasm_bindgen(js_name = setEdgeBundling)]
+    pub fn set_edge_bundling(&self, enabled: bool, strength: Option<f32>) -> Result<(), JsValue> {
+        let strength = strength.unwrap_or(1.0);
+        validate_bundling_strength(strength).map_err(|e| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetEdgeBundling { enabled, strength }).is_err() {
+            return Err(JsValue::from_str("Failed to send SetEdgeBundling command to event loop."));
+        }
+        Ok(())
+    }
+
     /// 设置当前时间轴选中的时刻
     #[wasm_bindgen(js_name = setTimeSelection)]
-    pub fn set_time_selection(&self, time: f32) -> Result<(), JsValue> {
+    pub fn set_time_selection(&self, time: f64) -> Result<(), JsValue> {
         let command = UserCommand::SetTimeSelection(time);
         log::debug!("Received SetTimeSelection command from JS: {}", time);
         if self.proxy.send_event(command).is_err() {
@@ -448,10 +1094,10 @@ impl WasmApi {
         Ok(())
     }
 
-    /// 设置高亮的服务
+    /// 设置高亮的碎片整理服务；include_moved（默认 true）为 true 时同时高亮被它移动的服务
     #[wasm_bindgen(js_name = setHighlightDefragService)]
-    pub fn set_highlight_defrag_service(&self, service_id: i32) -> Result<(), JsValue> {
-        let command = UserCommand::SetHighlightDefragService(service_id);
+    pub fn set_highlight_defrag_service(&self, service_id: i32, include_moved: Option<bool>) -> Result<(), JsValue> {
+        let command = UserCommand::SetHighlightDefragService { service_id, include_moved: include_moved.unwrap_or(true) };
         log::debug!("Received HighlightDefragEvent command from JS: {}", service_id);
         if self.proxy.send_event(command).is_err() {
             return Err(JsValue::from_str("Failed to send HighlightDefragEvent command to event loop."));
@@ -459,6 +1105,1091 @@ impl WasmApi {
         Ok(())
     }
 
+    /// 清除所有高亮的服务（setHighlightDefragService 或点击选择的），并发送 highlightChanged 通知
+    #[wasm_bindgen(js_name = clearHighlight)]
+    pub fn clear_highlight(&self) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::ClearHighlight).is_err() {
+            return Err(JsValue::from_str("Failed to send ClearHighlight command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 设置服务线路 LOD 阈值，例如 `{"aggregate_below_node_px": 3, "detail_above_node_px": 5}`
+    #[wasm_bindgen(js_name = setLodSettings)]
+    pub fn set_lod_settings(&self, settings_json: &str) -> Result<(), JsValue> {
+        let lod_settings: LodSettings = serde_json::from_str(settings_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        lod_settings.validate().map_err(|e| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetLodSettings(lod_settings)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetLodSettings command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 设置画布文字标签的显示方式，例如 `{"background_chips": false, "detail_lines_min_node_px": 90, "fade_start_node_px": 50, "fade_end_node_px": 70}`：
+    /// 关闭文字后面的底色块；节点屏幕半径在 50 到 70 像素之间时标签逐渐显现，90 像素后才显示节点类型行。省略的字段取默认值
+    #[wasm_bindgen(js_name = setLabelSettings)]
+    pub fn set_label_settings(&self, settings_json: &str) -> Result<(), JsValue> {
+        let label_settings: LabelSettings = serde_json::from_str(settings_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        label_settings.validate().map_err(|e| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetLabelSettings(label_settings)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetLabelSettings command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 设置画布背景，例如 `{"color": "#0b1020", "opacity": 0}`。opacity 小于 1 时画布按预乘 alpha 与页面合成，
+    /// 画布元素下面的页面内容（例如地图瓦片）透过背景显示；浏览器不支持透明合成时退回不透明背景并通过错误回调报告
+    #[wasm_bindgen(js_name = setBackground)]
+    pub fn set_background(&self, background_json: &str) -> Result<(), JsValue> {
+        let background: Background = serde_json::from_str(background_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        background.validate().map_err(|e| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetBackground(background)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetBackground command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 显示或隐藏画布左上角的统计信息
+    #[wasm_bindgen(js_name = setStatsOverlay)]
+    pub fn set_stats_overlay(&self, visible: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetStatsOverlay(visible)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetStatsOverlay command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 调试 sRGB 输出：在画布左下角绘制一排参考色块（50% 灰 #808080、红、绿、蓝、白、黑、节点颜色 #005d5d）。
+    /// 颜色路径正确时截图中的色块与这些值相同；偏亮发灰说明做了两次 sRGB 编码，偏暗说明没有编码
+    #[wasm_bindgen(js_name = renderColorTestPattern)]
+    pub fn render_color_test_pattern(&self, visible: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::RenderColorTestPattern(visible)).is_err() {
+            return Err(JsValue::from_str("Failed to send RenderColorTestPattern command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 覆盖着色器的 sRGB 输出转换：true / false 强制开启 / 关闭，省略参数时恢复为按表面格式决定
+    /// （格式不是 sRGB 时开启）。当前值见 getRendererInfo 的 shader_srgb_conversion；文字颜色不受影响
+    #[wasm_bindgen(js_name = forceSrgbConversion)]
+    pub fn force_srgb_conversion(&self, force: Option<bool>) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::ForceSrgbConversion(force)).is_err() {
+            return Err(JsValue::from_str("Failed to send ForceSrgbConversion command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 显示或隐藏画布右上角的活跃服务列表（滚轮滚动，点击行高亮服务，Ctrl / Cmd + 点击切换）
+    #[wasm_bindgen(js_name = setServicePanel)]
+    pub fn set_service_panel(&self, visible: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetServicePanel(visible)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetServicePanel command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 设置服务提示框的文字模板，`{字段名}` 替换为服务的同名字段，浮点字段可写为 `{gsnr:.1}`，
+    /// `{{` / `}}` 为字面量花括号，`\n` 换行。例如 `"λ{wavelength} {source_id}→{destination_id} GSNR {gsnr:.1}dB"`。
+    /// 字段名无效时抛出错误并列出所有可用字段
+    #[wasm_bindgen(js_name = setServiceTooltipTemplate)]
+    pub fn set_service_tooltip_template(&self, template: &str) -> Result<(), JsValue> {
+        let template: ServiceTemplate = template.parse().map_err(|e: String| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetServiceTooltipTemplate(template)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetServiceTooltipTemplate command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 覆盖节点图标映射，键为 node_type 或 type_variety（不区分大小写），
+    /// 值为单个字符或十六进制码位，例如 `{"roadm": "U+F6EB", "edfa": ""}`；空字符串表示不显示图标。
+    #[wasm_bindgen(js_name = setNodeIconMapping)]
+    pub fn set_node_icon_mapping(&self, mapping_json: &str) -> Result<(), JsValue> {
+        let overrides: NodeIconOverrides = serde_json::from_str(mapping_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        if self.proxy.send_event(UserCommand::SetNodeIconMapping(overrides)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetNodeIconMapping command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 设置高亮样式。参数可以是路径线型名："solid" | "dashed" | "marching"；
+    /// 也可以是 JSON 对象，例如 `{"node_color": "#d2a106", "service_lightness": 0.75,
+    /// "moved_service_lightness": 0.9, "dimmed_lightness": 0.4, "thickness": 0.5, "pulse": false}`，省略的字段取默认值
+    #[wasm_bindgen(js_name = setHighlightStyle)]
+    pub fn set_highlight_style(&self, style: &str) -> Result<(), JsValue> {
+        if style.trim_start().starts_with('{') {
+            let highlight_style: HighlightStyle = serde_json::from_str(style)
+                .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+            highlight_style.validate().map_err(|e| JsValue::from_str(&e))?;
+            if self.proxy.send_event(UserCommand::SetHighlightStyle(highlight_style)).is_err() {
+                return Err(JsValue::from_str("Failed to send SetHighlightStyle command to event loop."));
+            }
+            return Ok(());
+        }
+        let style: HighlightLineStyle = style.parse().map_err(|e: String| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetHighlightLineStyle(style)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetHighlightLineStyle command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 服务线路的着色方式："wavelength"（默认）或 "gsnr"；也可传入 JSON
+    /// `{"mode": "gsnr", "ramp": {"red_below_db": -1, "green_above_db": 3}}` 调整 GSNR 余量（gsnr - snr_requirement）的色阶：
+    /// 余量 <= red_below_db 为红色，0 dB 为黄色，>= green_above_db 为绿色；没有 GSNR 数据的服务为灰色。
+    /// 图例见 getColorLegend()
+    #[wasm_bindgen(js_name = setColorMode)]
+    pub fn set_color_mode(&self, mode: &str) -> Result<(), JsValue> {
+        let color_mode: ColorMode = if mode.trim_start().starts_with('{') {
+            serde_json::from_str(mode).map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?
+        } else {
+            mode.parse().map_err(|e: String| JsValue::from_str(&e))?
+        };
+        color_mode.validate().map_err(|e| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetColorMode(color_mode)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetColorMode command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 未高亮服务线路的宽度："uniform"（默认，1 像素）或 "bitrate"：按 bit_rate 的对数在 1 到 8 像素之间加粗，
+    /// 当前时刻码率最低的服务最细、最高的最粗，码率缺失的服务最细。宽度按屏幕像素保持，缩放后自动重新生成
+    #[wasm_bindgen(js_name = setThicknessMode)]
+    pub fn set_thickness_mode(&self, mode: &str) -> Result<(), JsValue> {
+        let thickness_mode: ThicknessMode = mode.parse().map_err(|e: String| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetThicknessMode(thickness_mode)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetThicknessMode command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 未高亮服务线路的不透明度："uniform"（默认，不透明）或 "utilization"：按 utilization（截断到 [0, 1]）
+    /// 在 0.15 到 1 之间变化，利用率低的服务淡入背景；缺失或 NaN 的服务不透明。与差异模式中未变化服务的半透明相乘，
+    /// 高亮的服务始终不透明。映射见 getColorLegend() 的 opacity 字段
+    #[wasm_bindgen(js_name = setOpacityMode)]
+    pub fn set_opacity_mode(&self, mode: &str) -> Result<(), JsValue> {
+        let opacity_mode: OpacityMode = mode.parse().map_err(|e: String| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetOpacityMode(opacity_mode)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetOpacityMode command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 当前着色方式的图例：`{ mode: "wavelength" | "gsnr", unit: "channel" | "dB",
+    /// stops: [{ value, label, color: "#rrggbb" }], no_data_color?, opacity? }`；
+    /// 按利用率设置透明度时 opacity 为 `{ mode: "utilization", stops: [{ value, label, alpha }] }`
+    #[wasm_bindgen(js_name = getColorLegend)]
+    pub fn get_color_legend(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::QueryColorLegend(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send QueryColorLegend command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let legend = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Color legend query was dropped: no view is attached."))?;
+            let legend_json = serde_json::to_string(&legend)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&legend_json)
+        }))
+    }
+
+    /// 显示 / 隐藏画布底部的时间轴条：事件时间范围内的事件密度直方图、书签刻度和当前时刻的播放头，
+    /// 点击跳到对应时刻，拖动连续调整时间（发送 reason 为 "scrub" 的 timeChanged 通知）。网页中默认隐藏，桌面端默认显示
+    #[wasm_bindgen(js_name = setTimelineStrip)]
+    pub fn set_timeline_strip(&self, visible: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetTimelineStrip(visible)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetTimelineStrip command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 设置服务在离开时刻的活跃语义："half_open"（默认，[arrival, departure)）| "closed"（[arrival, departure]）
+    #[wasm_bindgen(js_name = setServiceIntervalSemantics)]
+    pub fn set_service_interval_semantics(&self, semantics: &str) -> Result<(), JsValue> {
+        let semantics: ServiceIntervalSemantics = semantics.parse().map_err(|e: String| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetServiceIntervalSemantics(semantics)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetServiceIntervalSemantics command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 设置预览服务（尚未分配的候选路径），以虚线绘制，与时间轴无关。
+    /// 参数为 ServiceData 数组的 JSON；传入 `[]` 清除预览。
+    #[wasm_bindgen(js_name = setPreviewServices)]
+    pub fn set_preview_services(&self, services_json: &str) -> Result<(), JsValue> {
+        let services: Vec<ServiceData> = serde_json::from_str(services_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        if self.proxy.send_event(UserCommand::SetPreviewServices(services)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetPreviewServices command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 选中节点（绘制强调色外圈），传入 null / undefined 取消选中
+    #[wasm_bindgen(js_name = highlightNode)]
+    pub fn highlight_node(&self, node_id: Option<String>) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetSelectedNode(node_id)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetSelectedNode command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 开启 / 关闭光标世界坐标报告，结果以 `{ type: "cursorMoved", x, y, hovered_node_id? }`
+    /// 的形式发送给 setEventCallback 注册的回调
+    #[wasm_bindgen(js_name = setCursorTracking)]
+    pub fn set_cursor_tracking(&self, enabled: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetCursorTracking(enabled)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetCursorTracking command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 替换键盘快捷键，参数为 `{ "<按键名>": "<操作名>" }` 形式的 JSON，例如 `{"ArrowUp": "pan_up", "Equal": "zoom_in"}`。
+    /// 按键名与 KeyboardEvent.code 相同；操作名见 keymap.rs 中的 KeyAction（snake_case）。
+    /// 未列出的按键不再响应，传入 `{}` 关闭所有键盘快捷键
+    #[wasm_bindgen(js_name = setKeymap)]
+    pub fn set_keymap(&self, keymap_json: &str) -> Result<(), JsValue> {
+        let keymap = keymap::Keymap::from_json(keymap_json).map_err(|e| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::SetKeymap(keymap)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetKeymap command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 注册接收渲染端通知的回调，每条通知是一个带 `type` 字段的对象；传入 null 取消注册
+    #[wasm_bindgen(js_name = setEventCallback)]
+    pub fn set_event_callback(&self, callback: Option<js_sys::Function>) {
+        notifications::set_event_callback(callback);
+    }
+
+    /// 注册接收运行时错误的回调，参数为 `{ severity, code, message, context?, repeated }`，
+    /// severity 为 "warning"（命令被忽略）、"error"（某帧渲染失败等）或 "fatal"（GPU 设备丢失，需要重新创建视图）。
+    /// 回调在当前任务结束后异步调用；同一错误 5 秒内只报告一次，repeated 为期间被合并的次数。传入 null 取消注册
+    #[wasm_bindgen(js_name = setErrorCallback)]
+    pub fn set_error_callback(&self, callback: Option<js_sys::Function>) {
+        errors::set_error_callback(callback);
+    }
+
+    /// 注册渲染端 panic 时调用的回调，参数为 `{ message, location: { file, line, column } | null }`；
+    /// 画布上同时派发 `wdmview:panic` CustomEvent（detail 相同）。panic 之后其他 WasmApi 调用都会立即抛出错误，
+    /// 需要刷新页面重新创建。传入 null 取消注册
+    #[wasm_bindgen(js_name = setPanicCallback)]
+    pub fn set_panic_callback(&self, callback: Option<js_sys::Function>) {
+        panic_report::set_panic_callback(callback);
+    }
+
+    /// 仅调试构建：让事件循环在处理这条命令时 panic，用于验证 setPanicCallback 和页面的崩溃提示
+    #[cfg(debug_assertions)]
+    #[wasm_bindgen(js_name = debugPanic)]
+    pub fn debug_panic(&self, message: String) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::DebugPanic(message)).is_err() {
+            return Err(JsValue::from_str("Failed to send DebugPanic command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 设置节点位置，坐标约定与拓扑数据中的 location 相同；可通过 undoLayoutEdit 撤销
+    #[wasm_bindgen(js_name = setNodePosition)]
+    pub fn set_node_position(&self, element_id: String, x: f32, y: f32) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetNodePosition { element_id, x, y }).is_err() {
+            return Err(JsValue::from_str("Failed to send SetNodePosition command to event loop."));
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = undoLayoutEdit)]
+    pub fn undo_layout_edit(&self) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::UndoLayoutEdit).is_err() {
+            return Err(JsValue::from_str("Failed to send UndoLayoutEdit command to event loop."));
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = redoLayoutEdit)]
+    pub fn redo_layout_edit(&self) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::RedoLayoutEdit).is_err() {
+            return Err(JsValue::from_str("Failed to send RedoLayoutEdit command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 导出当前节点位置，resolve 为 JSON 字符串 `{ element_id: { x, y } }`，
+    /// 可直接传给 importLayout 还原
+    #[wasm_bindgen(js_name = exportLayout)]
+    pub fn export_layout(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::ExportLayout(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send ExportLayout command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let layout = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Layout export was dropped: no view is attached."))?;
+            let layout_json = serde_json::to_string(&layout)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            Ok(JsValue::from_str(&layout_json))
+        }))
+    }
+
+    /// 导出当前加载的拓扑（JSON 字符串），格式与 setFullTopology 的参数相同，可以直接重新加载。
+    /// 节点位置为当前位置（包括自动布局和拖动后的位置），事件为解析和验证之后保留的事件。
+    /// 大型时间轴分多帧序列化，不会长时间阻塞绘制
+    #[wasm_bindgen(js_name = getFullTopology)]
+    pub fn get_full_topology(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::ExportFullTopology(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send ExportFullTopology command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let topology_json = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Topology export was dropped: no view is attached."))?
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            Ok(JsValue::from_str(&topology_json))
+        }))
+    }
+
+    /// 导出会话（JSON 字符串）：拓扑（包含布局编辑后的节点位置）、相机、时刻、高亮、图层开关、样式设置、
+    /// 命名视图和时间书签，带有格式版本号。可用 importSession 或原生命令行 `wdmview --session FILE` 重现
+    #[wasm_bindgen(js_name = exportSession)]
+    pub fn export_session(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::ExportSession(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send ExportSession command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let session_json = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Session export was dropped: no view is attached."))?
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            Ok(JsValue::from_str(&session_json))
+        }))
+    }
+
+    /// 加载 exportSession 导出的会话，替换当前的全部状态。版本号不一致或数据无效时抛出错误
+    #[wasm_bindgen(js_name = importSession)]
+    pub fn import_session(&self, session_json: &str) -> Result<(), JsValue> {
+        let document = session::parse_session_json(session_json).map_err(|e| JsValue::from_str(&e))?;
+        if self.proxy.send_event(UserCommand::ImportSession(Box::new(document))).is_err() {
+            return Err(JsValue::from_str("Failed to send ImportSession command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 导出 GeoJSON FeatureCollection（JSON 字符串）：每个节点一个 Point，每条链路一个 LineString；
+    /// include_services 为 true 时，当前时刻活跃的服务作为 MultiLineString 一并导出
+    #[wasm_bindgen(js_name = exportGeoJson)]
+    pub fn export_geo_json(&self, include_services: Option<bool>) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        let command = UserCommand::ExportGeoJson { include_services: include_services.unwrap_or(false), reply: reply_sender };
+        if self.proxy.send_event(command).is_err() {
+            return Err(JsValue::from_str("Failed to send ExportGeoJson command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let snapshot = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("GeoJSON export was dropped: no view is attached."))?;
+            let geojson = serde_json::to_string(&snapshot.to_feature_collection())
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            Ok(JsValue::from_str(&geojson))
+        }))
+    }
+
+    /// 导出当前视图为 SVG 文本（世界坐标）。bounds 为 "view"（默认，当前可见区域）
+    /// 或 "topology"（包含所有节点的区域）
+    #[wasm_bindgen(js_name = exportSvg)]
+    pub fn export_svg(&self, bounds: Option<String>) -> Result<Promise, JsValue> {
+        let bounds = match bounds.as_deref() {
+            None | Some("view") => SvgBounds::View,
+            Some("topology") => SvgBounds::Topology,
+            Some(other) => return Err(JsValue::from_str(&format!("Unknown SVG bounds '{}', expected \"view\" or \"topology\"", other))),
+        };
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::ExportSvg { bounds, reply: reply_sender }).is_err() {
+            return Err(JsValue::from_str("Failed to send ExportSvg command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let snapshot = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("SVG export was dropped: no view is attached."))?;
+            Ok(JsValue::from_str(&snapshot.to_svg()))
+        }))
+    }
+
+    /// 把当前视图（设备像素分辨率）编码为 PNG 并复制到剪贴板。截图绘制在离屏纹理上，不影响屏幕上的帧。
+    /// 写入成功后 resolve；浏览器拒绝（权限、非安全上下文等）时以浏览器的错误 reject
+    #[wasm_bindgen(js_name = copyScreenshotToClipboard)]
+    pub fn copy_screenshot_to_clipboard(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::CaptureFrame(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send CaptureFrame command to event loop."));
+        }
+        // ClipboardItem 和 write 在本次调用中同步创建（仍处于用户手势内），Blob 稍后由截图填充
+        let png_blob_promise = future_to_promise(async move {
+            let snapshot = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Screenshot was dropped: no view is attached."))?
+                .map_err(|e| JsValue::from_str(&format!("Screenshot failed: {:#}", e)))?;
+            let png = snapshot.encode_png()
+                .map_err(|e| JsValue::from_str(&format!("PNG encoding error: {}", e)))?;
+            png_blob(&png)
+        });
+        let write_promise = write_png_to_clipboard(&png_blob_promise)?;
+        Ok(future_to_promise(async move {
+            wasm_bindgen_futures::JsFuture::from(write_promise).await?;
+            Ok(JsValue::UNDEFINED)
+        }))
+    }
+
+    /// 录制 [start, end] 内按 fps 采样的帧序列（width x height 像素）。每帧编码为 PNG 后调用
+    /// `on_frame(png: Uint8Array, index, time)`，全部完成后 resolve 为帧数。
+    /// 录制期间忽略鼠标和键盘输入，结束后恢复原来的时间和相机；cancelRecording 或 on_frame 抛出异常时 reject
+    #[wasm_bindgen(js_name = recordTimeline)]
+    pub fn record_timeline(&self, start: f64, end: f64, fps: f64, width: u32, height: u32, on_frame: js_sys::Function) -> Result<Promise, JsValue> {
+        let frame_times = recording::TimelineRecording::frame_times(start, end, fps).map_err(|e| {
+            errors::report(errors::ViewError::error("invalid_recording", e.clone()));
+            JsValue::from_str(&e)
+        })?;
+        // 有界通道：PNG 编码和 on_frame 慢于渲染时事件循环暂停截图（不阻塞），见 advance_recording
+        let (event_sender, event_receiver) = recording::TimelineRecording::event_channel();
+        let command = UserCommand::RecordTimeline { frame_times, width, height, events: event_sender };
+        if self.proxy.send_event(command).is_err() {
+            return Err(JsValue::from_str("Failed to send RecordTimeline command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            loop {
+                let event = event_receiver.recv_async().await
+                    .map_err(|_| JsValue::from_str("Recording was dropped: no view is attached."))?;
+                match event {
+                    recording::RecordingEvent::Frame { index, time, snapshot } => {
+                        let png = snapshot.encode_png()
+                            .map_err(|e| JsValue::from_str(&format!("PNG encoding error: {}", e)))?;
+                        // 返回 Err 时 event_receiver 被丢弃，录制在下一帧停止
+                        on_frame.call3(
+                            &JsValue::NULL,
+                            &js_sys::Uint8Array::from(&png[..]).into(),
+                            &JsValue::from_f64(index as f64),
+                            &JsValue::from_f64(time),
+                        )?;
+                    }
+                    recording::RecordingEvent::Finished { frames } => return Ok(JsValue::from_f64(frames as f64)),
+                    recording::RecordingEvent::Failed(message) => return Err(JsValue::from_str(&message)),
+                }
+            }
+        }))
+    }
+
+    /// 取消进行中的 recordTimeline（其 Promise 以 "Recording cancelled" reject）
+    #[wasm_bindgen(js_name = cancelRecording)]
+    pub fn cancel_recording(&self) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::CancelRecording).is_err() {
+            return Err(JsValue::from_str("Failed to send CancelRecording command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 应用 exportLayout 导出的布局，resolve 为 `{ applied, unknown_ids }`；
+    /// fit_view 为 true 时导入后重新适配视角
+    #[wasm_bindgen(js_name = importLayout)]
+    pub fn import_layout(&self, layout_json: &str, fit_view: Option<bool>) -> Result<Promise, JsValue> {
+        let layout: NodeLayout = serde_json::from_str(layout_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        let command = UserCommand::ImportLayout { layout, fit_view: fit_view.unwrap_or(false), reply: reply_sender };
+        if self.proxy.send_event(command).is_err() {
+            return Err(JsValue::from_str("Failed to send ImportLayout command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let summary = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Layout import was dropped: no view is attached."))?;
+            let summary_json = serde_json::to_string(&summary)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&summary_json)
+        }))
+    }
+
+    /// 查询当前可见区域内的节点，resolve 为 `[{ element_id, name, screen_x, screen_y }]`（屏幕坐标为物理像素）。
+    /// 反映拖动后的节点位置；没有关联视图时 resolve 为空数组
+    #[wasm_bindgen(js_name = getVisibleNodes)]
+    pub fn get_visible_nodes(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::QueryVisibleNodes(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send QueryVisibleNodes command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let Ok(nodes) = reply_receiver.recv_async().await else {
+                return Ok(js_sys::Array::new().into());
+            };
+            let nodes_json = serde_json::to_string(&nodes)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&nodes_json)
+        }))
+    }
+
+    /// 生成当前视图的无障碍描述（供 aria-live 区域使用），resolve 为
+    /// `{ time, visible_node_count, visible_node_names, active_service_count, highlighted_service?, summary }`，
+    /// summary 为英文文字摘要，长列表会被概括（例如 "Frankfurt, Munich, Berlin and 42 more nodes"）
+    #[wasm_bindgen(js_name = getSceneDescription)]
+    pub fn get_scene_description(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::QuerySceneDescription(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send QuerySceneDescription command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let description = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Scene description was dropped: no view is attached."))?;
+            let description_json = serde_json::to_string(&description)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&description_json)
+        }))
+    }
+
+    /// 高亮两个节点之间的最短路径（以单独的颜色沿链路绘制并标注跳数），resolve 为路径上的节点 ID 数组；
+    /// 两个节点不连通时清除路径高亮并 resolve 为 null。weight 为 "hops"（默认，跳数最少）或
+    /// "distance"（按节点间距离加权）。与 setHighlightDefragService 的高亮互不影响
+    #[wasm_bindgen(js_name = highlightPathBetween)]
+    pub fn highlight_path_between(&self, from_id: String, to_id: String, weight: Option<String>) -> Result<Promise, JsValue> {
+        let weight = match weight {
+            Some(name) => PathWeight::from_str(&name).map_err(|e| JsValue::from_str(&e))?,
+            None => PathWeight::default(),
+        };
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        let command = UserCommand::HighlightPathBetween { from_id, to_id, weight, reply: reply_sender };
+        if self.proxy.send_event(command).is_err() {
+            return Err(JsValue::from_str("Failed to send HighlightPathBetween command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let path = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Path query was dropped: no view is attached."))?
+                .map_err(|e| JsValue::from_str(&e))?;
+            Ok(match path {
+                Some(path) => path.iter().map(|id| JsValue::from_str(id)).collect::<js_sys::Array>().into(),
+                None => JsValue::NULL,
+            })
+        }))
+    }
+
+    #[wasm_bindgen(js_name = clearPathHighlight)]
+    pub fn clear_path_highlight(&self) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::ClearPathHighlight).is_err() {
+            return Err(JsValue::from_str("Failed to send ClearPathHighlight command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 在每条链路中点显示与链路垂直的容量条，填充比例为当前时刻占用的波长数 / 波长总数（默认关闭）。
+    /// 缩得过小时不显示
+    #[wasm_bindgen(js_name = setCapacityBars)]
+    pub fn set_capacity_bars(&self, visible: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetCapacityBars(visible)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetCapacityBars command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 开启 / 关闭热度轨迹模式：链路按当前时刻之前 window_seconds 秒内的时间加权平均占用率着色
+    /// （省略时为整个时间轴跨度的 10%）。切换模式不需要重新加载事件
+    #[wasm_bindgen(js_name = setHeatTrail)]
+    pub fn set_heat_trail(&self, enabled: bool, window_seconds: Option<f64>) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetHeatTrail { enabled, window: window_seconds }).is_err() {
+            return Err(JsValue::from_str("Failed to send SetHeatTrail command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 显示 / 隐藏被阻塞需求图层：拓扑数据中 blocked_demands 的时间戳落在以当前时刻为中心、总长 window_seconds 秒的窗口内时，
+    /// 以红色虚线连接源和目的节点（省略时为整个时间轴跨度的 5%）。默认显示
+    #[wasm_bindgen(js_name = setBlockedDemandLayer)]
+    pub fn set_blocked_demand_layer(&self, visible: bool, window_seconds: Option<f64>) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetBlockedDemandLayer { visible, window: window_seconds }).is_err() {
+            return Err(JsValue::from_str("Failed to send SetBlockedDemandLayer command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 设置节点分组，替换之前的所有分组（`{}` 为清除），例如
+    /// `{"north": {"color": "#3a7bd5", "element_ids": ["A", "B"]}}`，可选的 `visible: false` 直接隐藏该组。
+    /// 组颜色作为节点的基础颜色，高亮服务经过的节点仍按高亮颜色显示；不属于任何组的节点使用默认颜色。
+    /// 分组在重新加载拓扑后按节点 ID 重新应用。resolve 为 `{ groups, assigned_nodes, unknown_ids }`，
+    /// unknown_ids 为当前拓扑中不存在的节点 ID 数
+    #[wasm_bindgen(js_name = setNodeGroups)]
+    pub fn set_node_groups(&self, groups_json: &str) -> Result<Promise, JsValue> {
+        let groups: NodeGroups = serde_json::from_str(groups_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::SetNodeGroups { groups, reply: reply_sender }).is_err() {
+            return Err(JsValue::from_str("Failed to send SetNodeGroups command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let summary = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Node groups were dropped: no view is attached."))?;
+            let summary_json = serde_json::to_string(&summary)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&summary_json)
+        }))
+    }
+
+    /// 显示 / 隐藏节点分组：隐藏组内的节点不绘制、不能拾取，与它们相连的链路和服务线段也不绘制。
+    /// 未知的组名报告为 invalid_node_group 警告
+    #[wasm_bindgen(js_name = setGroupVisibility)]
+    pub fn set_group_visibility(&self, group: &str, visible: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetGroupVisibility { group: group.to_string(), visible }).is_err() {
+            return Err(JsValue::from_str("Failed to send SetGroupVisibility command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 查询时间戳在 [start, end] 内的被阻塞需求，resolve 为按时间排序的
+    /// `[{ timestamp, source_id, destination_id, bit_rate }]`（与拓扑数据中的格式相同）；不受图层开关影响
+    #[wasm_bindgen(js_name = getBlockedDemandsInRange)]
+    pub fn get_blocked_demands_in_range(&self, start: f64, end: f64) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::QueryBlockedDemandsInRange { start, end, reply: reply_sender }).is_err() {
+            return Err(JsValue::from_str("Failed to send QueryBlockedDemandsInRange command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let demands = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Blocked demand query was dropped: no view is attached."))?;
+            let demands_json = serde_json::to_string(&demands)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&demands_json)
+        }))
+    }
+
+    /// 左右对比两个时刻：左半部分跳到 t1（与 setTimeSelection 相同，会发送 timeChanged 通知），右半部分显示 t2。
+    /// 两边共用相机，平移缩放同时作用于两边；碎片整理高亮、最短路径和测量在两边同时显示
+    #[wasm_bindgen(js_name = setCompareTimes)]
+    pub fn set_compare_times(&self, t1: f64, t2: f64) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetCompareTimes { t1, t2 }).is_err() {
+            return Err(JsValue::from_str("Failed to send SetCompareTimes command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 退出对比视图，左半部分的时刻保留为当前时刻
+    #[wasm_bindgen(js_name = clearCompare)]
+    pub fn clear_compare(&self) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::ClearCompare).is_err() {
+            return Err(JsValue::from_str("Failed to send ClearCompare command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 差异模式：时间轴跳到 t2（会发送 timeChanged 通知），与 t1 相比新出现的服务为绿色，波长或路径变化的为橙色，
+    /// 未变化的变暗，已离开的服务以红色虚线残影绘制在 t1 时的线路上。resolve 为
+    /// `{ unchanged, changed, appeared, departed }` 各类服务的数量；时间轴离开 t2 后不再显示差异
+    #[wasm_bindgen(js_name = setDiffTimes)]
+    pub fn set_diff_times(&self, t1: f64, t2: f64) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::SetDiffTimes { t1, t2, reply: reply_sender }).is_err() {
+            return Err(JsValue::from_str("Failed to send SetDiffTimes command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let counts = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Service diff was dropped: no view is attached."))?
+                .map_err(|e| JsValue::from_str(&e))?;
+            let counts_json = serde_json::to_string(&counts)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&counts_json)
+        }))
+    }
+
+    #[wasm_bindgen(js_name = clearDiff)]
+    pub fn clear_diff(&self) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::ClearDiff).is_err() {
+            return Err(JsValue::from_str("Failed to send ClearDiff command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 流动动画（默认关闭）：小圆点沿每条可见服务线路从源节点移向目的节点，表现业务方向。
+    /// 开启时持续渲染，关闭后恢复按需渲染；可见服务过多时不绘制圆点
+    #[wasm_bindgen(js_name = setFlowAnimation)]
+    pub fn set_flow_animation(&self, enabled: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetFlowAnimation(enabled)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetFlowAnimation command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 自适应画质（默认开启）：平均帧时间超过 budgetMs（默认 20）时依次暂停流动动画、改为聚合绘制服务线路、
+    /// 隐藏标签，帧时间恢复后逐级打开。传入 false 关闭并恢复完整画质；省略 budgetMs 时保持当前预算
+    #[wasm_bindgen(js_name = setQualityGovernor)]
+    pub fn set_quality_governor(&self, enabled: bool, budget_ms: Option<f32>) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetQualityGovernor { enabled, budget_ms }).is_err() {
+            return Err(JsValue::from_str("Failed to send SetQualityGovernor command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// GPU 计时（默认关闭）：开启后按节点、线路、高亮和文字分别测量每个渲染通道的 GPU 耗时，
+    /// 结果在 getRenderStats 的 gpu_pass_ms 和统计叠加层中。设备不支持时间戳查询时没有效果（gpu_pass_ms 保持为 null）
+    #[wasm_bindgen(js_name = setGpuProfiling)]
+    pub fn set_gpu_profiling(&self, enabled: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetGpuProfiling(enabled)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetGpuProfiling command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 文字渲染（默认开启）：页面自己在画布上方绘制 HTML 标签时传入 false，不再生成节点图标、标签、注释、
+    /// 统计叠加层、服务列表面板和悬停提示，也不加载字体（在第一帧文字之前关闭时）。重新打开后下一帧恢复
+    #[wasm_bindgen(js_name = setTextRendering)]
+    pub fn set_text_rendering(&self, enabled: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetTextRendering(enabled)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetTextRendering command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 请求视图重绘一帧（例如与下方地图的渲染循环同步），与内部的按需重绘合并，不会额外出帧。
+    /// 重绘请求发出后 resolve；没有附着视图时 reject
+    #[wasm_bindgen(js_name = requestRedraw)]
+    pub fn request_redraw(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::RequestRedraw(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send RequestRedraw command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Redraw request was dropped: no view is attached."))?;
+            Ok(JsValue::NULL)
+        }))
+    }
+
+    /// 请求重绘，返回的 Promise 在下一帧绘制并 present 之后 resolve。视图最小化或画布尺寸为 0 时等到恢复后的第一帧；
+    /// 没有附着视图或在出帧之前调用 destroyView 时 reject
+    #[wasm_bindgen(js_name = renderOnce)]
+    pub fn render_once(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::RenderOnce(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send RenderOnce command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Render request was dropped: no view is attached."))?;
+            Ok(JsValue::NULL)
+        }))
+    }
+
+    /// 渲染统计：`{ fps, average_frame_ms, frame_budget_ms, quality_governor_enabled, quality_level, lod_level, node_count, line_vertex_count,
+    /// gpu_buffer_bytes, gpu_buffer_total_bytes, gpu_profiling_enabled, gpu_pass_ms, text_rendering_enabled, text_area_count }`，
+    /// quality_level 为 "full"、"no_flow_animation"、"aggregated_services" 或 "no_labels"；
+    /// gpu_pass_ms 为 `{ circles_ms, lines_ms, highlights_ms, text_ms }` 或 null（见 setGpuProfiling）
+    #[wasm_bindgen(js_name = getRenderStats)]
+    pub fn get_render_stats(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::QueryRenderStats(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send QueryRenderStats command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let stats = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Render stats query was dropped: no view is attached."))?;
+            let stats_json = serde_json::to_string(&stats)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&stats_json)
+        }))
+    }
+
+    /// 渲染后端信息：`{ adapter_name, vendor, device, device_type, driver, driver_info, backend, texture_format,
+    /// shader_srgb_conversion, present_mode, limits: { max_texture_dimension_2d, max_buffer_size, ... } }`。
+    /// attachCanvasToDom 的 Promise resolve 之后即可查询，重新附着画布后反映新的设备
+    #[wasm_bindgen(js_name = getRendererInfo)]
+    pub fn get_renderer_info(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::QueryRendererInfo(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send QueryRendererInfo command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let info = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Renderer info query was dropped: no view is attached."))?;
+            let info_json = serde_json::to_string(&info)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&info_json)
+        }))
+    }
+
+    /// 开启测量模式：接下来点击的两个节点为端点，两者之间绘制虚线并在中点标注距离和跳数，
+    /// 同时发送 `{ type: "measurementCompleted", from_node_id, to_node_id, distance, hop_count }` 通知。
+    /// 开启时丢弃之前的测量；传入 false 只取消尚未完成的测量
+    #[wasm_bindgen(js_name = setMeasurementMode)]
+    pub fn set_measurement_mode(&self, enabled: bool) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SetMeasurementMode(enabled)).is_err() {
+            return Err(JsValue::from_str("Failed to send SetMeasurementMode command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 清除测量线并退出测量模式（与 Escape 键相同）
+    #[wasm_bindgen(js_name = clearMeasurement)]
+    pub fn clear_measurement(&self) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::ClearMeasurement).is_err() {
+            return Err(JsValue::from_str("Failed to send ClearMeasurement command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 查询某一时刻活跃的服务，resolve 为按 service_id 排序的 ServiceData 数组；
+    /// 离开时刻是否仍活跃按 setServiceIntervalSemantics 的设置。不改变当前时间选择，也不触发重绘
+    #[wasm_bindgen(js_name = getServicesAtTime)]
+    pub fn get_services_at_time(&self, time: f64) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::QueryServicesAtTime { time, reply: reply_sender }).is_err() {
+            return Err(JsValue::from_str("Failed to send QueryServicesAtTime command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let services = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Active service query was dropped: no view is attached."))?;
+            let services_json = serde_json::to_string(&services)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&services_json)
+        }))
+    }
+
+    /// 查询某一时刻活跃的服务 id，resolve 为按 id 排序的 Int32Array。
+    /// 不改变当前时间选择，也不触发重绘
+    #[wasm_bindgen(js_name = getActiveServiceIds)]
+    pub fn get_active_service_ids(&self, time: f64) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::QueryActiveServiceIds { time, reply: reply_sender }).is_err() {
+            return Err(JsValue::from_str("Failed to send QueryActiveServiceIds command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let ids = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Active service query was dropped: no view is attached."))?;
+            Ok(js_sys::Int32Array::from(&ids[..]).into())
+        }))
+    }
+
+    /// 一次调用统计多个时刻的活跃服务数，resolve 为与 times 一一对应的 Uint32Array。
+    /// 不改变当前时间选择，也不触发重绘
+    #[wasm_bindgen(js_name = getActiveServiceCounts)]
+    pub fn get_active_service_counts(&self, times: &[f64]) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        let command = UserCommand::QueryActiveServiceCounts { times: times.to_vec(), reply: reply_sender };
+        if self.proxy.send_event(command).is_err() {
+            return Err(JsValue::from_str("Failed to send QueryActiveServiceCounts command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let counts = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Active service query was dropped: no view is attached."))?;
+            Ok(js_sys::Uint32Array::from(&counts[..]).into())
+        }))
+    }
+
+    /// 查询 time 时刻经过链路（路径以任一方向经过都计入）的服务，resolve 为按波长排序的
+    /// `[{ service_id, wavelength, source_id, destination_id }]`；未知的 connection_id 时 reject。
+    /// 与点击链路时 linkInspected 通知中的列表相同，但不改变当前时间选择和画面
+    #[wasm_bindgen(js_name = getServicesOnLink)]
+    pub fn get_services_on_link(&self, connection_id: String, time: f64) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        let command = UserCommand::QueryServicesOnLink { connection_id, time, reply: reply_sender };
+        if self.proxy.send_event(command).is_err() {
+            return Err(JsValue::from_str("Failed to send QueryServicesOnLink command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let services = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Link query was dropped: no view is attached."))?
+                .map_err(|e| JsValue::from_str(&e))?;
+            let services_json = serde_json::to_string(&services)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&services_json)
+        }))
+    }
+
+    /// 查询时间戳在 [start, end] 内的事件，resolve 为 `{ events, truncated }`；
+    /// types 为事件类型名（"ALLOCATION" | "RELEASE_EXPIRED" | "REALLOCATION" | "UNKNOWN"），省略时返回所有类型。
+    /// 未知类型的事件原样返回（即加载时的原始事件对象）。
+    /// 最多返回 10000 个事件，超出时 truncated 为 true
+    #[wasm_bindgen(js_name = getEventsInRange)]
+    pub fn get_events_in_range(&self, start: f64, end: f64, types: Option<Vec<String>>) -> Result<Promise, JsValue> {
+        let kinds = types
+            .map(|types| types.iter().map(|name| name.parse()).collect::<Result<Vec<EventKind>, String>>())
+            .transpose()
+            .map_err(|e| JsValue::from_str(&e))?;
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        let command = UserCommand::QueryEventsInRange { start, end, kinds, reply: reply_sender };
+        if self.proxy.send_event(command).is_err() {
+            return Err(JsValue::from_str("Failed to send QueryEventsInRange command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let range = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Event range query was dropped: no view is attached."))?;
+            let range_json = serde_json::to_string(&range)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&range_json)
+        }))
+    }
+
+    /// 在给定时刻添加书签，编号为添加顺序（从 0 开始）
+    #[wasm_bindgen(js_name = addTimeBookmark)]
+    pub fn add_time_bookmark(&self, time: f64, label: String) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::AddTimeBookmark { time, label }).is_err() {
+            return Err(JsValue::from_str("Failed to send AddTimeBookmark command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// resolve 为 `[{ time, label }]`，数组下标即书签编号
+    #[wasm_bindgen(js_name = listTimeBookmarks)]
+    pub fn list_time_bookmarks(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::ListTimeBookmarks(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send ListTimeBookmarks command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let bookmarks = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Bookmark query was dropped: no view is attached."))?;
+            let bookmarks_json = serde_json::to_string(&bookmarks)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&bookmarks_json)
+        }))
+    }
+
+    /// 添加地图注释（同一 id 再次添加时替换），例如
+    /// `{"id": "cut-1", "world_position": {"x": 120, "y": 80}, "text": "fiber cut here", "color": "#e07020", "anchor_element_id": "ROADM-3"}`。
+    /// 注释绘制为带底色块的文字标签，以细引线连接到 world_position（坐标约定与拓扑数据中的 location 相同），
+    /// 指定 anchor_element_id 时改为连接到该节点并随节点拖动。color 省略时为琥珀色。注释与时间轴无关，包含在会话导出中；
+    /// 保持视角的拓扑重新加载后，锚定节点仍然存在的注释保留。点击标签时发送 annotationClicked 通知。
+    /// JSON 无效时抛出错误，锚定节点不存在时报告为 invalid_annotation 警告
+    #[wasm_bindgen(js_name = addAnnotation)]
+    pub fn add_annotation(&self, annotation_json: &str) -> Result<(), JsValue> {
+        let annotation: Annotation = serde_json::from_str(annotation_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        if self.proxy.send_event(UserCommand::AddAnnotation(annotation)).is_err() {
+            return Err(JsValue::from_str("Failed to send AddAnnotation command to event loop."));
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = removeAnnotation)]
+    pub fn remove_annotation(&self, id: String) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::RemoveAnnotation(id)).is_err() {
+            return Err(JsValue::from_str("Failed to send RemoveAnnotation command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// resolve 为按添加顺序的 `[{ id, world_position, text, color, anchor_element_id? }]`，
+    /// 锚定的注释的 world_position 为节点的当前位置
+    #[wasm_bindgen(js_name = listAnnotations)]
+    pub fn list_annotations(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::ListAnnotations(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send ListAnnotations command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let annotations = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("Annotation query was dropped: no view is attached."))?;
+            let annotations_json = serde_json::to_string(&annotations)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            js_sys::JSON::parse(&annotations_json)
+        }))
+    }
+
+    /// 删除书签，之后的书签编号依次前移
+    #[wasm_bindgen(js_name = removeTimeBookmark)]
+    pub fn remove_time_bookmark(&self, index: usize) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::RemoveTimeBookmark(index)).is_err() {
+            return Err(JsValue::from_str("Failed to send RemoveTimeBookmark command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 把时间设置到书签所在时刻（会发送 reason 为 "bookmark" 的 timeChanged 通知）
+    #[wasm_bindgen(js_name = jumpToBookmark)]
+    pub fn jump_to_bookmark(&self, index: usize) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::JumpToBookmark(index)).is_err() {
+            return Err(JsValue::from_str("Failed to send JumpToBookmark command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 以 name 保存当前视图（相机、时刻、服务和路径高亮、选中节点、容量条 / 热度轨迹 / 流动动画开关），
+    /// 同名视图被覆盖
+    #[wasm_bindgen(js_name = saveView)]
+    pub fn save_view(&self, name: String) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::SaveView(name)).is_err() {
+            return Err(JsValue::from_str("Failed to send SaveView command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 恢复视图（会发送 reason 为 "view" 的 timeChanged 通知），视图不存在时 reject
+    #[wasm_bindgen(js_name = restoreView)]
+    pub fn restore_view(&self, name: String) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::RestoreView { name, reply: reply_sender }).is_err() {
+            return Err(JsValue::from_str("Failed to send RestoreView command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("View restore was dropped: no view is attached."))?
+                .map_err(|e| JsValue::from_str(&e))?;
+            Ok(JsValue::UNDEFINED)
+        }))
+    }
+
+    /// resolve 为按名称排序的视图名数组
+    #[wasm_bindgen(js_name = listViews)]
+    pub fn list_views(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::ListViews(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send ListViews command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let names = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("View query was dropped: no view is attached."))?;
+            Ok(names.iter().map(|name| JsValue::from_str(name)).collect::<js_sys::Array>().into())
+        }))
+    }
+
+    #[wasm_bindgen(js_name = deleteView)]
+    pub fn delete_view(&self, name: String) -> Result<(), JsValue> {
+        if self.proxy.send_event(UserCommand::DeleteView(name)).is_err() {
+            return Err(JsValue::from_str("Failed to send DeleteView command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 导出所有视图（JSON 字符串，视图名到视图的对象），可用 importViews 重新导入
+    #[wasm_bindgen(js_name = exportViews)]
+    pub fn export_views(&self) -> Result<Promise, JsValue> {
+        let (reply_sender, reply_receiver) = flume::bounded(1);
+        if self.proxy.send_event(UserCommand::ExportViews(reply_sender)).is_err() {
+            return Err(JsValue::from_str("Failed to send ExportViews command to event loop."));
+        }
+        Ok(future_to_promise(async move {
+            let views = reply_receiver.recv_async().await
+                .map_err(|_| JsValue::from_str("View export was dropped: no view is attached."))?;
+            let views_json = serde_json::to_string(&views)
+                .map_err(|e| JsValue::from_str(&format!("JSON serialization error: {}", e)))?;
+            Ok(JsValue::from_str(&views_json))
+        }))
+    }
+
+    /// 导入 exportViews 导出的视图，同名视图被覆盖，其余视图保留
+    #[wasm_bindgen(js_name = importViews)]
+    pub fn import_views(&self, views_json: &str) -> Result<(), JsValue> {
+        let views: BTreeMap<String, SavedView> = serde_json::from_str(views_json)
+            .map_err(|e| JsValue::from_str(&format!("JSON parsing error: {}", e)))?;
+        for (name, view) in &views {
+            view.validate().map_err(|e| JsValue::from_str(&format!("View '{}': {}", name, e)))?;
+        }
+        if self.proxy.send_event(UserCommand::ImportViews(views)).is_err() {
+            return Err(JsValue::from_str("Failed to send ImportViews command to event loop."));
+        }
+        Ok(())
+    }
+
+    /// 跳到当前时间之后的下一个事件（filter 为事件类型名，例如 "REALLOCATION"），
+    /// resolve 为该事件；已经没有后续事件时时间不变，resolve 为 null
+    #[wasm_bindgen(js_name = stepForward)]
+    pub fn step_forward(&self, filter: Option<String>) -> Result<Promise, JsValue> {
+        send_step_to_event(&self.proxy, StepDirection::Forward, filter)
+    }
+
+    /// 跳到当前时间之前的上一个事件，用法同 stepForward
+    #[wasm_bindgen(js_name = stepBackward)]
+    pub fn step_backward(&self, filter: Option<String>) -> Result<Promise, JsValue> {
+        send_step_to_event(&self.proxy, StepDirection::Backward, filter)
+    }
+
     // ++ NEW: The function to attach to the DOM, returning a promise.
     #[wasm_bindgen(js_name = attachCanvasToDom)]
     pub fn attach_canvas_to_dom(&self, canvas_id: &str) -> Result<Promise, JsValue> {
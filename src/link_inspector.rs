@@ -0,0 +1,159 @@
+// src/link_inspector.rs
+// 链路占用检查：点击两个节点圆之间的链路（没有命中服务线段和节点时），列出当前时刻经过该链路的所有服务（服务 ID 和波长），
+// 通过 linkInspected 通知发给 JS；getServicesOnLink(connection_id, time) 是不改变画面的等价查询。
+// 经过链路的判断与链路占用统计相同（scene/path_index.rs 的 path_link_keys），路径以任一方向经过都计入。
+// 被检查的链路和这些服务以强调线绘制，与悬停强调共用同一个顶点缓冲区（见 service_hover.rs）；
+// 时间选择改变时按新时刻重新列出，列表变化时再次发送 linkInspected。点击其他位置或加载新拓扑时取消。
+use glam::Vec2;
+use serde::Serialize;
+
+use crate::app_state::State;
+use crate::models::ThickLineVertex;
+use crate::notifications::ViewNotification;
+use crate::scene::geometry::{lane_radius, link_center_segment, push_thick_line_segment};
+use crate::scene::path_index::{known_hop, path_link_keys, service_path_indices, undirected_link_key};
+
+/// 链路命中测试的最小屏幕容差（链路在屏幕上很细时）
+const LINK_PICK_TOLERANCE_PX: f32 = 4.0;
+/// 被检查链路的强调线屏幕宽度
+const INSPECTED_LINK_WIDTH_PX: f32 = 6.0;
+/// 经过该链路的服务的强调线屏幕宽度
+const INSPECTED_SERVICE_WIDTH_PX: f32 = 3.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkService {
+    pub service_id: i32,
+    pub wavelength: i32,
+    pub source_id: String,
+    pub destination_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct InspectedLink {
+    pub connection_idx: usize,
+    pub service_ids: Vec<i32>,
+}
+
+impl State {
+    /// 屏幕坐标处的链路在 all_connections 中的索引：到两个节点中心连线的距离在容差内，且不在任一节点圆内
+    pub fn connection_at(&self, screen_pos: Vec2) -> Option<usize> {
+        let world_pos = self.camera.screen_to_world(screen_pos);
+        let min_tolerance = LINK_PICK_TOLERANCE_PX / self.camera.world_radius_to_screen_pixels(1.0);
+        let nodes = &self.geometry.circle_instances;
+        self.connection_endpoints
+            .iter()
+            .enumerate()
+            .filter_map(|(connection_idx, &(source_idx, target_idx))| {
+                let (source_idx, target_idx) = known_hop(source_idx, target_idx)?;
+                if self.is_node_hidden(source_idx) || self.is_node_hidden(target_idx) {
+                    return None; // 隐藏分组的链路不绘制
+                }
+                let (source, target) = (nodes.get(source_idx)?, nodes.get(target_idx)?);
+                let (start, end) = (Vec2::from_array(source.position), Vec2::from_array(target.position));
+                if world_pos.distance(start) <= source.radius_scale || world_pos.distance(end) <= target.radius_scale {
+                    return None;
+                }
+                let link_vec = end - start;
+                let t = ((world_pos - start).dot(link_vec) / link_vec.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+                let distance = world_pos.distance(start + link_vec * t);
+                let tolerance = lane_radius(source).min(lane_radius(target)).max(min_tolerance);
+                (distance <= tolerance).then_some((connection_idx, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(connection_idx, _)| connection_idx)
+    }
+
+    /// 在 time 时刻经过链路（任一方向）的活跃服务，按波长和服务 ID 排序
+    pub fn services_on_link(&self, connection_idx: usize, time: f64) -> Result<Vec<LinkService>, String> {
+        let connection = self.all_connections.get(connection_idx).ok_or_else(|| format!("Unknown connection index {}", connection_idx))?;
+        let (source_idx, target_idx) = self.connection_endpoints.get(connection_idx)
+            .and_then(|&(source_idx, target_idx)| known_hop(source_idx, target_idx))
+            .ok_or_else(|| format!("Connection '{}' references a node that is not in the topology", connection.connection_id))?;
+        let key = undirected_link_key(source_idx, target_idx);
+
+        let mut services: Vec<LinkService> = self.active_services_at(time)
+            .into_iter()
+            .filter(|service| path_link_keys(&service_path_indices(service, &self.node_id_to_idx)).any(|hop_key| hop_key == key))
+            .map(|service| LinkService {
+                service_id: service.service_id,
+                wavelength: service.wavelength,
+                source_id: service.source_id,
+                destination_id: service.destination_id,
+            })
+            .collect();
+        services.sort_by_key(|service| (service.wavelength, service.service_id));
+        Ok(services)
+    }
+
+    /// getServicesOnLink：按 connection_id 查询
+    pub fn services_on_connection(&self, connection_id: &str, time: f64) -> Result<Vec<LinkService>, String> {
+        let connection_idx = self.all_connections.iter()
+            .position(|connection| connection.connection_id == connection_id)
+            .ok_or_else(|| format!("Unknown connection ID '{}'", connection_id))?;
+        self.services_on_link(connection_idx, time)
+    }
+
+    /// 检查链路（None 为取消）：列出当前时刻经过它的服务，发送 linkInspected 通知并更新强调线
+    pub fn inspect_link(&mut self, connection_idx: Option<usize>) {
+        let inspection = connection_idx.and_then(|connection_idx| {
+            match self.services_on_link(connection_idx, self.current_time_selection) {
+                Ok(services) => Some((connection_idx, services)),
+                Err(e) => {
+                    log::warn!("Cannot inspect link: {}", e);
+                    None
+                }
+            }
+        });
+        if inspection.is_none() && self.inspected_link.is_none() {
+            return;
+        }
+
+        let notification = match &inspection {
+            Some((connection_idx, services)) => {
+                let connection_id = self.all_connections[*connection_idx].connection_id.clone();
+                log::info!("Link {} carries {} service(s) at t={}.", connection_id, services.len(), self.current_time_selection);
+                ViewNotification::LinkInspected { connection_id: Some(connection_id), time: self.current_time_selection, services: services.clone() }
+            }
+            None => ViewNotification::LinkInspected { connection_id: None, time: self.current_time_selection, services: Vec::new() },
+        };
+        self.pending_notifications.push(notification);
+        self.inspected_link = inspection.map(|(connection_idx, services)| InspectedLink {
+            connection_idx,
+            service_ids: services.iter().map(|service| service.service_id).collect(),
+        });
+        self.rebuild_emphasis_lines();
+    }
+
+    /// 时间选择改变后调用：按新时刻重新列出被检查链路上的服务，服务变化时更新强调线并发送 linkInspected
+    pub fn refresh_inspected_link(&mut self) {
+        let Some(inspected) = &self.inspected_link else {
+            return;
+        };
+        let service_ids = self.services_on_link(inspected.connection_idx, self.current_time_selection)
+            .map(|services| services.iter().map(|service| service.service_id).collect::<Vec<_>>());
+        if service_ids.is_ok_and(|service_ids| service_ids == inspected.service_ids) {
+            return;
+        }
+        self.inspect_link(Some(inspected.connection_idx));
+    }
+
+    /// 被检查链路及其服务的强调线，在悬停强调线之前（下层）生成
+    pub fn push_link_inspection_lines(&self, vertices: &mut Vec<ThickLineVertex>) {
+        let Some(inspected) = &self.inspected_link else {
+            return;
+        };
+        let pixel = 1.0 / self.camera.world_radius_to_screen_pixels(1.0);
+        let nodes = &self.geometry.circle_instances;
+        let link_segment = self.connection_endpoints.get(inspected.connection_idx)
+            .and_then(|&(source_idx, target_idx)| known_hop(source_idx, target_idx))
+            .and_then(|(source_idx, target_idx)| link_center_segment(nodes.get(source_idx)?, nodes.get(target_idx)?));
+        if let Some((start, end)) = link_segment {
+            push_thick_line_segment(vertices, start, end, self.selection_accent_color, INSPECTED_LINK_WIDTH_PX * pixel, 0.0, ThickLineVertex::SOLID);
+        }
+
+        let [r, g, b, _] = self.selection_accent_color;
+        for segment in self.geometry.pick_segments.iter().filter(|segment| inspected.service_ids.contains(&segment.service_id)) {
+            push_thick_line_segment(vertices, segment.start, segment.end, [r, g, b, 0.6], INSPECTED_SERVICE_WIDTH_PX * pixel, 0.0, ThickLineVertex::SOLID);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// src/measurement.rs
+// 测量模式：开启后接下来点击的两个节点作为端点，在两者之间绘制虚线，并在中点标注世界坐标距离和最短路径跳数。
+// 测量结果保留到 clearMeasurement()（或 Escape）为止，完成时同时发送 measurementCompleted 通知。
+use glam::Vec2;
+
+use crate::app_state::State;
+use crate::notifications::ViewNotification;
+use crate::scene::graph::{PathWeight, TopologyGraph};
+
+/// 已完成的测量；距离在生成几何时按当前节点位置计算（节点可能被拖动）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    pub from: usize,
+    pub to: usize,
+    /// 两个节点不连通时为 None
+    pub hop_count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeasurementState {
+    #[default]
+    Off,
+    /// 等待点击端点，first 为已选的第一个节点
+    Picking { first: Option<usize> },
+    Measured(Measurement),
+}
+
+impl State {
+    /// 开启测量模式时丢弃之前的测量结果；关闭时只取消尚未完成的测量
+    pub fn set_measurement_mode(&mut self, enabled: bool) {
+        if enabled {
+            self.clear_measurement();
+            self.measurement = MeasurementState::Picking { first: None };
+            log::info!("Measurement mode: click two nodes.");
+        } else if matches!(self.measurement, MeasurementState::Picking { .. }) {
+            self.measurement = MeasurementState::Off;
+        }
+    }
+
+    pub fn toggle_measurement_mode(&mut self) {
+        let picking = matches!(self.measurement, MeasurementState::Picking { .. });
+        self.set_measurement_mode(!picking);
+    }
+
+    pub fn clear_measurement(&mut self) {
+        if matches!(self.measurement, MeasurementState::Measured(_)) {
+            self.topology_needs_update = true;
+        }
+        self.measurement = MeasurementState::Off;
+    }
+
+    /// 测量模式下点击（或用 Enter 激活）节点时调用
+    pub fn add_measurement_endpoint(&mut self, idx: usize) {
+        let MeasurementState::Picking { first } = self.measurement else {
+            return;
+        };
+        let Some(from) = first else {
+            self.measurement = MeasurementState::Picking { first: Some(idx) };
+            return;
+        };
+        if from == idx {
+            return; // 第二个端点必须是另一个节点
+        }
+
+        let graph = TopologyGraph::new(self.geometry.circle_instances.len(), &self.node_id_to_idx, &self.all_connections);
+        let hop_count = graph.shortest_path(from, idx, PathWeight::Hops, &[]).map(|path| path.len() - 1);
+        let measurement = Measurement { from, to: idx, hop_count };
+        self.measurement = MeasurementState::Measured(measurement);
+        self.topology_needs_update = true;
+
+        let node_position = |idx: usize| Vec2::from_array(self.geometry.circle_instances[idx].position);
+        let distance = node_position(from).distance(node_position(idx));
+        let from_node_id = self.all_elements[from].element_id.clone();
+        let to_node_id = self.all_elements[idx].element_id.clone();
+        log::info!("Measured {} -> {}: distance {:.1}, hops {:?}.", from_node_id, to_node_id, distance, hop_count);
+        self.pending_notifications.push(ViewNotification::MeasurementCompleted { from_node_id, to_node_id, distance, hop_count });
+    }
+
+    pub fn completed_measurement(&self) -> Option<&Measurement> {
+        match &self.measurement {
+            MeasurementState::Measured(measurement) => Some(measurement),
+            _ => None,
+        }
+    }
+}
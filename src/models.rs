@@ -45,6 +45,7 @@ pub struct CircleInstance {
     pub position: [f32; 2], // 节点中心的世界坐标
     pub radius_scale: f32,  // 节点半径 (世界单位)
     pub color: [f32; 4],    // RGBA 颜色 (线性空间)
+    pub glow: f32,          // 光晕强度 (0.0 表示无光晕，用于高亮节点)
 }
 
 impl CircleInstance {
@@ -70,6 +71,12 @@ impl CircleInstance {
                     shader_location: 3, // location 3 for instance color
                     format: wgpu::VertexFormat::Float32x4,
                 },
+                wgpu::VertexAttribute {
+                    offset: (mem::size_of::<[f32; 2]>() + mem::size_of::<f32>() + mem::size_of::<[f32; 4]>())
+                        as wgpu::BufferAddress,
+                    shader_location: 4, // location 4 for instance glow intensity
+                    format: wgpu::VertexFormat::Float32,
+                },
             ],
         }
     }
@@ -104,3 +111,51 @@ impl LineVertex {
         }
     }
 }
+
+// --- Vertex Data for Thick Lines (triangle quads: highlights, occupancy bars) ---
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct ThickLineVertex {
+    pub position: [f32; 2],  // 顶点世界坐标
+    pub color: [f32; 4],     // RGBA 颜色 (线性空间)
+    pub path_distance: f32,  // 沿路径的累计距离 (世界单位)，用于虚线图案
+    pub dash_pattern: u32,   // ThickLineVertex::SOLID / DASHED / MARCHING
+}
+
+impl ThickLineVertex {
+    pub const SOLID: u32 = 0;
+    pub const DASHED: u32 = 1;
+    pub const MARCHING: u32 = 2; // 随时间沿路径移动的虚线
+
+    pub fn layout<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0, // location 0 for position
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1, // location 1 for color
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: (mem::size_of::<[f32; 2]>() + mem::size_of::<[f32; 4]>())
+                        as wgpu::BufferAddress,
+                    shader_location: 2, // location 2 for distance along path
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: (mem::size_of::<[f32; 2]>() + mem::size_of::<[f32; 4]>() + mem::size_of::<f32>())
+                        as wgpu::BufferAddress,
+                    shader_location: 3, // location 3 for dash pattern
+                    format: wgpu::VertexFormat::Uint32,
+                },
+            ],
+        }
+    }
+}
@@ -0,0 +1,47 @@
+// src/node_culling.rs
+// 节点实例的视口裁剪：10 万节点的全国拓扑放大到街道级别时，视口外的节点仍然被上传和光栅化（四边形变换后被裁掉）。
+// 上传前只保留与裁剪范围相交的节点实例（SceneGeometry::visible_circle_instances），裁剪范围为视口向四周各扩大
+// CULL_MARGIN_FRACTION 倍的区域；平移缩放时只有视口移出该区域才重新裁剪并重新上传节点实例。
+// GPU 拾取通过 visible_circle_indices 映射回节点索引，node_id_to_idx、适应视图和导出仍使用完整的 circle_instances。
+use glam::Vec2;
+
+use crate::app_state::State;
+
+/// 裁剪范围在视口的每一侧扩大视口宽高的这一比例，小幅平移不需要重新裁剪
+const CULL_MARGIN_FRACTION: f32 = 0.5;
+/// 裁剪范围的面积超过视口面积的这一倍数时（放大后范围中大部分节点都在视口外）重新裁剪；
+/// 刚裁剪时为 (1 + 2 × CULL_MARGIN_FRACTION)² = 4 倍，即再放大约 2 倍后重新裁剪
+const MAX_REGION_VIEW_AREA_RATIO: f32 = 16.0;
+
+/// inner 完全位于 outer 之内
+fn region_contains(outer: (Vec2, Vec2), inner: (Vec2, Vec2)) -> bool {
+    inner.0.cmpge(outer.0).all() && inner.1.cmple(outer.1).all()
+}
+
+impl State {
+    /// 按当前视口计算的裁剪范围（世界坐标）
+    pub fn node_cull_region(&self) -> (Vec2, Vec2) {
+        let (world_visible_min, world_visible_max) = self.camera.get_world_clip_bounds();
+        let margin = (world_visible_max - world_visible_min) * CULL_MARGIN_FRACTION;
+        (world_visible_min - margin, world_visible_max + margin)
+    }
+
+    /// 相机变化后调用：视口已经移出当前几何的裁剪范围（或放大到只占范围的一小部分）时重新裁剪并上传节点实例
+    pub fn refresh_node_culling(&mut self) {
+        let view_bounds = self.camera.get_world_clip_bounds();
+        let up_to_date = self.geometry.cull_region.is_some_and(|region| {
+            let region_area = (region.1 - region.0).element_product();
+            let view_area = (view_bounds.1 - view_bounds.0).element_product();
+            region_contains(region, view_bounds) && region_area <= view_area * MAX_REGION_VIEW_AREA_RATIO
+        });
+        if up_to_date {
+            return;
+        }
+        self.geometry.cull_circle_instances(Some(self.node_cull_region()));
+        log::debug!(
+            "Culled node instances: {} of {} inside the view region.",
+            self.geometry.visible_circle_instances.len(), self.geometry.circle_instances.len()
+        );
+        self.renderer.upload_circle_instances(&self.device, &self.queue, &self.geometry.visible_circle_instances);
+    }
+}
@@ -0,0 +1,101 @@
+// src/node_groups.rs
+// 节点分组（setNodeGroups）：JS 按区域等分类把节点分组，每组一个颜色，例如
+// `{"north": {"color": "#3a7bd5", "element_ids": ["A", "B"]}}`。组颜色是节点的基础颜色层：
+// 不属于任何组的节点使用默认颜色，高亮服务经过的节点仍按高亮颜色重新着色（见 SceneGeometry::begin_regenerate）。
+// setGroupVisibility 隐藏组内的节点：节点不绘制也不能拾取，与它相连的链路边界和服务线段在生成几何时跳过。
+// 组按节点 ID 保存，加载或合并拓扑后（resolve_topology_indices）重新解析为每个节点的颜色和隐藏标记；
+// 同一节点属于多个组时使用组名排序最后的组的颜色，任一所属组隐藏时节点隐藏。
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::State;
+use crate::settings::{deserialize_hex_color, serialize_hex_color};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeGroup {
+    /// JSON 中为 "#rrggbb"
+    #[serde(serialize_with = "serialize_hex_color", deserialize_with = "deserialize_hex_color")]
+    pub color: [f32; 4],
+    pub element_ids: Vec<String>,
+    /// 可以在 setNodeGroups 中直接指定，之后用 setGroupVisibility 切换
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+/// 组名到组的映射，按组名排序
+pub type NodeGroups = BTreeMap<String, NodeGroup>;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct NodeGroupSummary {
+    pub groups: usize,
+    /// 至少属于一个组的节点数
+    pub assigned_nodes: usize,
+    /// 组中列出但当前拓扑中没有的节点 ID 数
+    pub unknown_ids: usize,
+}
+
+impl State {
+    /// 替换所有分组（空对象为清除），返回解析结果
+    pub fn set_node_groups(&mut self, groups: NodeGroups) -> NodeGroupSummary {
+        self.node_groups = groups;
+        let summary = self.resolve_node_groups();
+        log::info!(
+            "Node groups set: {} group(s), {} node(s) assigned, {} unknown ID(s).",
+            summary.groups, summary.assigned_nodes, summary.unknown_ids,
+        );
+        summary
+    }
+
+    /// setGroupVisibility：显示或隐藏组内的节点
+    pub fn set_group_visibility(&mut self, group_name: &str, visible: bool) -> Result<(), String> {
+        let group = self.node_groups.get_mut(group_name).ok_or_else(|| format!("Unknown node group '{}'", group_name))?;
+        if group.visible != visible {
+            group.visible = visible;
+            self.resolve_node_groups();
+        }
+        Ok(())
+    }
+
+    /// 按当前拓扑的节点索引重新计算每个节点的组颜色和隐藏标记，并重新生成几何
+    pub fn resolve_node_groups(&mut self) -> NodeGroupSummary {
+        let node_count = self.all_elements.len();
+        let mut colors: Vec<Option<[f32; 4]>> = vec![None; node_count];
+        let mut hidden = vec![false; node_count];
+        let mut unknown_ids = 0;
+        for group in self.node_groups.values() {
+            for element_id in &group.element_ids {
+                match self.node_id_to_idx.get(element_id).filter(|&&idx| idx < node_count) {
+                    Some(&idx) => {
+                        colors[idx] = Some(group.color);
+                        hidden[idx] |= !group.visible;
+                    }
+                    None => unknown_ids += 1,
+                }
+            }
+        }
+        let summary = NodeGroupSummary {
+            groups: self.node_groups.len(),
+            assigned_nodes: colors.iter().filter(|color| color.is_some()).count(),
+            unknown_ids,
+        };
+        // 没有分组时保持为空，生成几何时按没有组颜色、没有隐藏节点处理
+        if self.node_groups.is_empty() {
+            colors.clear();
+            hidden.clear();
+        }
+        self.node_group_colors = colors;
+        self.hidden_nodes = hidden;
+        self.topology_needs_update = true;
+        summary
+    }
+
+    /// 节点是否因所属的组隐藏而不绘制、不能拾取
+    pub fn is_node_hidden(&self, idx: usize) -> bool {
+        self.hidden_nodes.get(idx).copied().unwrap_or(false)
+    }
+}
@@ -0,0 +1,89 @@
+// src/node_icons.rs
+// 节点图标：根据 node_type / type_variety 选择 bootstrap-icons 字体中的字形
+use std::collections::HashMap;
+use serde::Deserialize;
+
+use crate::scene::element::ElementData;
+
+/// 图标所用字体的 family 名称（字体已嵌入 State::new）
+pub const ICON_FONT_FAMILY: &str = "bootstrap-icons";
+
+/// 默认映射，键为小写的 node_type / type_variety
+const DEFAULT_NODE_ICONS: &[(&str, char)] = &[
+    ("roadm", '\u{F6EB}'),       // router-fill
+    ("edfa", '\u{F231}'),        // caret-right-fill，放大器三角符号
+    ("transceiver", '\u{F6D5}'), // ethernet
+    ("fiber", '\u{F1DE}'),       // bullseye，光纤盘
+    ("ramanfiber", '\u{F1DE}'),
+    ("fused", '\u{F2EF}'),       // diamond-fill
+];
+
+#[derive(Debug, Clone)]
+pub struct NodeIconMapping {
+    icons: HashMap<String, char>,
+}
+
+impl Default for NodeIconMapping {
+    fn default() -> Self {
+        Self {
+            icons: DEFAULT_NODE_ICONS
+                .iter()
+                .map(|&(key, icon)| (key.to_string(), icon))
+                .collect(),
+        }
+    }
+}
+
+impl NodeIconMapping {
+    /// 先按 type_variety 查找，找不到再按 node_type 查找
+    pub fn icon_for(&self, element: &ElementData) -> Option<char> {
+        self.icons
+            .get(&element.type_variety.to_lowercase())
+            .or_else(|| self.icons.get(&element.node_type.to_lowercase()))
+            .copied()
+    }
+
+    /// 合并覆盖项，值为 None 的键会移除对应图标
+    pub fn apply_overrides(&mut self, overrides: NodeIconOverrides) {
+        for (key, icon) in overrides.0 {
+            match icon {
+                Some(icon) => { self.icons.insert(key, icon); }
+                None => { self.icons.remove(&key); }
+            }
+        }
+    }
+}
+
+/// 来自 JS 的覆盖项，例如 `{"roadm": "U+F6EB", "edfa": "", "ila": "▶"}`。
+/// 值可以是单个字符，或十六进制码位（可带 `U+` / `0x` 前缀）；空字符串表示不显示图标。
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "HashMap<String, String>")]
+pub struct NodeIconOverrides(pub HashMap<String, Option<char>>);
+
+impl TryFrom<HashMap<String, String>> for NodeIconOverrides {
+    type Error = String;
+
+    fn try_from(raw: HashMap<String, String>) -> Result<Self, Self::Error> {
+        let mut overrides = HashMap::with_capacity(raw.len());
+        for (key, value) in raw {
+            let value = value.trim();
+            let icon = if value.is_empty() {
+                None
+            } else if value.chars().count() == 1 {
+                value.chars().next()
+            } else {
+                let hex = value
+                    .trim_start_matches("U+")
+                    .trim_start_matches("u+")
+                    .trim_start_matches("0x");
+                let icon = u32::from_str_radix(hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("Invalid icon '{}' for node type '{}': expected a single character or a hex codepoint", value, key))?;
+                Some(icon)
+            };
+            overrides.insert(key.to_lowercase(), icon);
+        }
+        Ok(Self(overrides))
+    }
+}
@@ -0,0 +1,178 @@
+// src/node_overlap.rs
+// 位置重合的节点：有些导出数据把两个网元（例如转发器和它所在的 ROADM）放在同一坐标上，一个圆完全盖住另一个，
+// 相连的链路看起来都来自同一个节点。加载或合并拓扑后检测彼此距离在节点半径一定比例以内的节点，
+// 每组列入验证报告（overlapping_nodes），并把组内节点按确定的顺序均匀排列在原位置周围的圆周上。
+// 偏移只作用于显示位置：导出拓扑和布局时减去偏移，元数据中的坐标保持不变。setSeparateOverlappingNodes(false) 恢复原始位置。
+use std::collections::HashMap;
+
+use glam::{IVec2, Vec2};
+
+use crate::app_state::State;
+use crate::scene::geometry::lane_radius;
+use crate::settings::is_positive_finite;
+
+/// 两个节点的距离小于节点半径的这一比例时视为重合
+const OVERLAP_DISTANCE_NODE_RADII: f32 = 0.25;
+/// 分开后相邻两个节点的圆心距离为节点直径的这一倍数，两个圆之间留出一点空隙
+const SEPARATION_GAP_FACTOR: f32 = 1.1;
+
+/// 并查集的根（带路径压缩）
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// 按距离阈值把点分组（距离在阈值内的点传递地归为一组），只返回至少两个点的组；组内和组间都按索引排序
+fn coincident_groups(points: &[Vec2], threshold: f32) -> Vec<Vec<usize>> {
+    if points.len() < 2 || !is_positive_finite(threshold) {
+        return Vec::new();
+    }
+    let cell_of = |p: Vec2| (p / threshold).floor().as_ivec2();
+    let mut grid: HashMap<IVec2, Vec<usize>> = HashMap::new();
+    for (i, &p) in points.iter().enumerate() {
+        grid.entry(cell_of(p)).or_default().push(i);
+    }
+
+    let mut parents: Vec<usize> = (0..points.len()).collect();
+    for (i, &p) in points.iter().enumerate() {
+        let cell = cell_of(p);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let Some(indices) = grid.get(&(cell + IVec2::new(dx, dy))) else {
+                    continue;
+                };
+                for &j in indices.iter().filter(|&&j| j > i && p.distance(points[j]) <= threshold) {
+                    let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                    parents[root_i.max(root_j)] = root_i.min(root_j);
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..points.len() {
+        let root = find_root(&mut parents, i);
+        groups.entry(root).or_default().push(i);
+    }
+    let mut groups: Vec<Vec<usize>> = groups.into_values().filter(|group| group.len() > 1).collect();
+    groups.sort_unstable_by_key(|group| group[0]);
+    groups
+}
+
+impl State {
+    /// setSeparateOverlappingNodes
+    pub fn set_separate_overlapping_nodes(&mut self, enabled: bool) {
+        if self.separate_overlapping_nodes == enabled {
+            return;
+        }
+        log::info!("Automatic separation of overlapping nodes {}.", if enabled { "enabled" } else { "disabled" });
+        self.separate_overlapping_nodes = enabled;
+        self.apply_overlap_offsets();
+    }
+
+    /// 节点未经分开偏移的位置（世界坐标），导出拓扑和布局时使用
+    pub fn raw_node_position(&self, idx: usize) -> Vec2 {
+        let position = Vec2::from_array(self.geometry.circle_instances[idx].position);
+        position - self.node_overlap_offsets.get(idx).copied().unwrap_or(Vec2::ZERO)
+    }
+
+    /// 节点被拖动或导入布局后，其位置由用户指定：不再带有分开偏移，也不再属于重合的组
+    pub fn clear_overlap_offset(&mut self, idx: usize) {
+        if let Some(offset) = self.node_overlap_offsets.get_mut(idx) {
+            *offset = Vec2::ZERO;
+        }
+        for group in &mut self.node_overlap_groups {
+            group.retain(|&member| member != idx);
+        }
+        self.node_overlap_groups.retain(|group| group.len() > 1);
+    }
+
+    /// 加载或合并拓扑后调用（在推算节点半径之后）：按原始位置重新检测重合的节点，写入验证报告并重新计算偏移
+    pub fn detect_overlapping_nodes(&mut self) {
+        self.node_overlap_offsets.resize(self.geometry.circle_instances.len(), Vec2::ZERO);
+        let raw_positions: Vec<Vec2> = (0..self.geometry.circle_instances.len()).map(|idx| self.raw_node_position(idx)).collect();
+        self.node_overlap_groups = coincident_groups(&raw_positions, self.node_radius * OVERLAP_DISTANCE_NODE_RADII);
+        self.validation_report.issues.retain(|issue| issue.code != "overlapping_nodes"); // 合并拓扑时重新列出所有组
+
+        let mut groups = Vec::with_capacity(self.node_overlap_groups.len());
+        for group in &self.node_overlap_groups {
+            let element_ids: Vec<String> = group.iter().map(|&idx| self.all_elements[idx].element_id.clone()).collect();
+            self.validation_report.warn(
+                "overlapping_nodes",
+                format!("Elements {} are at (nearly) the same position.", element_ids.join(", ")),
+                Some(element_ids[0].clone()),
+            );
+            groups.push(element_ids);
+        }
+        self.validation_report.overlapping_nodes = groups;
+        self.apply_overlap_offsets();
+    }
+
+    /// 按当前的组、节点半径和开关重新计算偏移：组内节点按索引顺序从正上方开始均匀排列在组中心周围的圆周上，
+    /// 相邻两个节点的圆心距离为最大节点直径的 SEPARATION_GAP_FACTOR 倍
+    pub fn apply_overlap_offsets(&mut self) {
+        self.node_overlap_offsets.resize(self.geometry.circle_instances.len(), Vec2::ZERO);
+        let mut offsets = vec![Vec2::ZERO; self.geometry.circle_instances.len()];
+        if self.separate_overlapping_nodes {
+            for group in &self.node_overlap_groups {
+                let raw_positions: Vec<Vec2> = group.iter().map(|&idx| self.raw_node_position(idx)).collect();
+                let center = raw_positions.iter().sum::<Vec2>() / group.len() as f32;
+                let radius = group
+                    .iter()
+                    .map(|&idx| lane_radius(&self.geometry.circle_instances[idx]))
+                    .fold(0.0, f32::max);
+                let orbit = radius * SEPARATION_GAP_FACTOR / (std::f32::consts::PI / group.len() as f32).sin();
+                for (k, (&idx, raw_position)) in group.iter().zip(raw_positions).enumerate() {
+                    let angle = std::f32::consts::FRAC_PI_2 - k as f32 / group.len() as f32 * std::f32::consts::TAU;
+                    offsets[idx] = center + Vec2::from_angle(angle) * orbit - raw_position;
+                }
+            }
+        }
+
+        for (idx, offset) in offsets.into_iter().enumerate() {
+            let raw_position = self.raw_node_position(idx);
+            self.geometry.circle_instances[idx].position = (raw_position + offset).into();
+            self.node_overlap_offsets[idx] = offset;
+        }
+        self.topology_needs_update = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 完全重合（距离为零）的点归为一组，阈值是闭区间：恰好等于阈值的点也算重合，稍远一点则不算
+    #[test]
+    fn coincidence_threshold_is_inclusive() {
+        let points = [Vec2::new(5.0, 5.0), Vec2::new(5.0, 5.0), Vec2::new(20.0, 0.0), Vec2::new(20.5, 0.0), Vec2::new(40.0, 0.0), Vec2::new(40.5001, 0.0)];
+        assert_eq!(coincident_groups(&points, 0.5), vec![vec![0, 1], vec![2, 3]]);
+        // 跨过原点的格子边界
+        assert_eq!(coincident_groups(&[Vec2::new(-0.1, -0.1), Vec2::new(0.1, 0.1)], 0.5), vec![vec![0, 1]]);
+        // 阈值无效时不分组，即使点完全重合
+        for threshold in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(coincident_groups(&points, threshold).is_empty(), "{threshold}");
+        }
+    }
+
+    /// 分组是传递的：链上相邻的点在阈值内即同组，首尾相距远超阈值、跨越多个格子也一样；组内和组间按索引排序
+    #[test]
+    fn coincidence_groups_are_transitive() {
+        let chain: Vec<Vec2> = (0..6).map(|k| Vec2::new(k as f32 * 0.9, 0.0)).collect();
+        let mut points = vec![Vec2::new(100.0, 100.0), Vec2::new(100.0, 100.0)];
+        // 链的点倒序插入，与另一组交错
+        for (k, &p) in chain.iter().rev().enumerate() {
+            points.insert(k + 1, p);
+        }
+        let groups = coincident_groups(&points, 1.0);
+        assert_eq!(groups, vec![vec![0, 7], vec![1, 2, 3, 4, 5, 6]]);
+
+        // 两条链在中间相接后合成一组
+        let bridge = [Vec2::new(0.0, 0.0), Vec2::new(3.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(2.0, 0.0)];
+        assert_eq!(coincident_groups(&bridge, 1.0), vec![vec![0, 1, 2, 3]]);
+        assert!(coincident_groups(&bridge[..2], 1.0).is_empty());
+    }
+}
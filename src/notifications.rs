@@ -0,0 +1,117 @@
+// src/notifications.rs
+// 由渲染端发出、转发给 JS 回调（WasmApi::setEventCallback）的通知。原生平台上只写入日志。
+use serde::Serialize;
+
+use crate::link_inspector::LinkService;
+use crate::scene::validation::ValidationReport;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ViewNotification {
+    /// 光标所在的世界坐标（需通过 setCursorTracking(true) 开启）
+    CursorMoved {
+        x: f32,
+        y: f32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hovered_node_id: Option<String>,
+    },
+    /// 键盘焦点 (Tab / Shift+Tab) 移动到的节点；拓扑重新加载后焦点清空
+    NodeFocused {
+        node_id: Option<String>,
+    },
+    /// 渲染端修改了时间轴选中的时刻；reason 为 "api" 时是 setTimeSelection 的回应，前端可据此避免循环更新
+    TimeChanged {
+        time: f64,
+        reason: TimeChangeReason,
+    },
+    /// 大型拓扑分多帧生成和上传几何时的进度（0 到 1），完成时发送 1；期间继续显示之前的画面
+    GeometryProgress {
+        progress: f32,
+    },
+    /// 测量模式下选定第二个端点后发送；distance 为世界坐标距离，两个节点不连通时 hop_count 为 null
+    MeasurementCompleted {
+        from_node_id: String,
+        to_node_id: String,
+        distance: f32,
+        hop_count: Option<usize>,
+    },
+    /// 点击（或 Ctrl+点击）服务线段、clearHighlight 或 Escape 改变了高亮的服务；清除时 service_ids 为空
+    HighlightChanged {
+        service_ids: Vec<i32>,
+    },
+    /// 光标移到另一个服务的线段上或离开服务线段时发送（离开时 service_id 为 null），不改变高亮的服务
+    ServiceHovered {
+        service_id: Option<i32>,
+    },
+    /// 点击链路（两个节点圆之间）时列出 time 时刻经过该链路的服务，按波长排序；
+    /// 点击其他位置取消检查时 connection_id 为 null、services 为空
+    LinkInspected {
+        connection_id: Option<String>,
+        time: f64,
+        services: Vec<LinkService>,
+    },
+    /// 点击 addAnnotation 添加的注释标签（点击不再选中标签下面的节点或服务）
+    AnnotationClicked {
+        annotation_id: String,
+    },
+    /// 每次加载拓扑后发送，列出加载过程中发现的问题（可能为空）；
+    /// setNumChannels / setChannelPlan 改变了事件的波长检查结果时也会发送
+    TopologyValidated {
+        report: ValidationReport,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeChangeReason {
+    /// setTimeSelection
+    Api,
+    /// stepForward / stepBackward
+    Step,
+    /// 高亮碎片整理服务时跳到其到达时刻
+    Highlight,
+    /// jumpToBookmark
+    Bookmark,
+    /// restoreView
+    View,
+    /// 加载新的事件时间线后回到 0
+    Reset,
+    /// 点击或拖动画布内的时间轴条（见 timeline_strip.rs）
+    Scrub,
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    static EVENT_CALLBACK: std::cell::RefCell<Option<js_sys::Function>> = const { std::cell::RefCell::new(None) };
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn set_event_callback(callback: Option<js_sys::Function>) {
+    EVENT_CALLBACK.with(|cell| *cell.borrow_mut() = callback);
+}
+
+pub fn dispatch(notifications: Vec<ViewNotification>) {
+    #[cfg(target_arch = "wasm32")]
+    EVENT_CALLBACK.with(|cell| {
+        let Some(callback) = cell.borrow().clone() else {
+            return; // 没有注册回调时直接丢弃
+        };
+        for notification in &notifications {
+            let payload = match serde_json::to_string(notification).map(|json| js_sys::JSON::parse(&json)) {
+                Ok(Ok(payload)) => payload,
+                _ => {
+                    log::warn!("Failed to serialize notification {:?}", notification);
+                    continue;
+                }
+            };
+            if let Err(e) = callback.call1(&wasm_bindgen::JsValue::NULL, &payload) {
+                log::warn!("Event callback threw: {:?}", e);
+            }
+        }
+    });
+
+    #[cfg(not(target_arch = "wasm32"))]
+    for notification in &notifications {
+        log::debug!("Notification: {:?}", notification);
+    }
+}
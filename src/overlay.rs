@@ -0,0 +1,152 @@
+// src/overlay.rs
+// 屏幕空间叠加层的公共部分：像素矩形到 NDC 三角形的转换（由 Renderer::draw_overlay 绘制）、叠加层配色，
+// 以及悬停提示（Tooltip）。功能代码只需调用 State::set_tooltip，背景和文字在 render_to_view 中生成，不直接接触 wgpu。
+// 提示框的大小按文字排版结果确定，放在锚点右下方；放不下时翻到锚点另一侧，并限制在画布内。
+// 画布上的文字标签可以带底色块（LabelChips），与其它叠加层背景一起绘制在文字之前。
+use bevy_color::{ColorToComponents, LinearRgba, Srgba};
+use glam::Vec2;
+
+use crate::app_state::State;
+use crate::models::LineVertex;
+use crate::text_resources::TextResources;
+
+const TOOLTIP_OFFSET_PX: Vec2 = Vec2::new(14.0, 18.0); // 提示框左上角相对锚点的偏移，避开光标
+const TOOLTIP_PADDING_PX: f32 = 6.0;
+const TOOLTIP_BORDER_PX: f32 = 1.0;
+pub const TOOLTIP_FONT_SIZE: f32 = 13.0;
+pub const TOOLTIP_LINE_HEIGHT_PX: f32 = 17.0;
+const LABEL_CHIP_PADDING_PX: Vec2 = Vec2::new(3.0, 1.0);
+
+/// 叠加层（服务列表面板、悬停提示、标签底色块）的颜色，背景和边框为线性 RGBA
+#[derive(Debug, Clone, Copy)]
+pub struct OverlayTheme {
+    pub background: [f32; 4],
+    pub border: [f32; 4],
+    /// 比面板背景更透明，仍能看出被标签遮住的线路
+    pub label_chip: [f32; 4],
+    pub text: glyphon::Color,
+}
+
+impl Default for OverlayTheme {
+    fn default() -> Self {
+        Self {
+            background: LinearRgba::from(Srgba::rgba_u8(20, 22, 28, 215)).to_f32_array(),
+            border: LinearRgba::from(Srgba::rgba_u8(110, 116, 128, 230)).to_f32_array(),
+            label_chip: LinearRgba::from(Srgba::rgba_u8(20, 22, 28, 160)).to_f32_array(),
+            text: glyphon::Color::rgb(230, 230, 230),
+        }
+    }
+}
+
+/// 悬停提示：anchor_screen 为画布坐标（像素，与 mouse_canvas_pos_screen 相同），每个元素一行
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tooltip {
+    pub anchor_screen: Vec2,
+    pub lines: Vec<String>,
+}
+
+/// 像素矩形转换为两个 NDC 三角形
+pub fn push_screen_rect(vertices: &mut Vec<LineVertex>, min: Vec2, max: Vec2, canvas_size: Vec2, color: [f32; 4]) {
+    let to_ndc = |p: Vec2| [p.x / canvas_size.x * 2.0 - 1.0, 1.0 - p.y / canvas_size.y * 2.0];
+    let (top_left, bottom_right) = (to_ndc(min), to_ndc(max));
+    let top_right = [bottom_right[0], top_left[1]];
+    let bottom_left = [top_left[0], bottom_right[1]];
+    for position in [top_left, bottom_left, bottom_right, top_left, bottom_right, top_right] {
+        vertices.push(LineVertex { position, color });
+    }
+}
+
+/// 屏幕坐标（像素）中从 from 到 to、宽 width 像素的线段，以两个三角形组成的四边形绘制
+pub fn push_screen_line(vertices: &mut Vec<LineVertex>, from: Vec2, to: Vec2, width: f32, canvas_size: Vec2, color: [f32; 4]) {
+    let Some(direction) = (to - from).try_normalize() else {
+        return;
+    };
+    let to_ndc = |p: Vec2| [p.x / canvas_size.x * 2.0 - 1.0, 1.0 - p.y / canvas_size.y * 2.0];
+    let half_normal = direction.perp() * (width / 2.0);
+    let corners = [from + half_normal, from - half_normal, to - half_normal, to + half_normal].map(to_ndc);
+    for i in [0, 1, 2, 0, 2, 3] {
+        vertices.push(LineVertex { position: corners[i], color });
+    }
+}
+
+/// 一帧中提交的文字标签的屏幕矩形（像素，文字排版后的实际范围），render_to_view 据此生成底色块
+#[derive(Debug, Default)]
+pub struct LabelChips {
+    rects: Vec<(Vec2, Vec2, f32)>, // 左上角、右下角和不透明度（随标签渐显）
+}
+
+impl LabelChips {
+    /// left / top 与 TextArea 相同，size 为排版后的文字宽高
+    pub fn push(&mut self, left: f32, top: f32, size: Vec2) {
+        self.push_faded(left, top, size, 1.0);
+    }
+
+    /// alpha 与标签文字的不透明度相同，乘到底色块的颜色上
+    pub fn push_faded(&mut self, left: f32, top: f32, size: Vec2, alpha: f32) {
+        let min = Vec2::new(left, top);
+        self.rects.push((min - LABEL_CHIP_PADDING_PX, min + size + LABEL_CHIP_PADDING_PX, alpha));
+    }
+
+    /// 对比视图：已有的矩形裁剪到左半部分后复制到右半部分，与文字的处理相同
+    pub fn mirror_for_compare(&mut self, half_width: f32) {
+        self.rects.retain_mut(|(min, max, _)| {
+            max.x = max.x.min(half_width);
+            min.x < max.x
+        });
+        let mirrored: Vec<_> = self.rects.iter().map(|&(min, max, alpha)| (min + Vec2::X * half_width, max + Vec2::X * half_width, alpha)).collect();
+        self.rects.extend(mirrored);
+    }
+
+    pub fn push_vertices(&self, vertices: &mut Vec<LineVertex>, canvas_size: Vec2, color: [f32; 4]) {
+        for &(min, max, alpha) in &self.rects {
+            let [r, g, b, a] = color;
+            push_screen_rect(vertices, min, max, canvas_size, [r, g, b, a * alpha]);
+        }
+    }
+}
+
+impl State {
+    /// 显示（Some）或隐藏（None）悬停提示，在下一帧生效
+    pub fn set_tooltip(&mut self, tooltip: Option<Tooltip>) {
+        self.tooltip = tooltip.filter(|tooltip| !tooltip.lines.is_empty());
+    }
+
+    /// render_to_view 在生成 text_areas 之前调用：排版提示文字，把背景和边框追加到 vertices，
+    /// 返回文字的左上角和裁剪范围；没有提示或关闭了文字渲染时返回 None
+    pub fn prepare_tooltip(&mut self, vertices: &mut Vec<LineVertex>) -> Option<(Vec2, glyphon::TextBounds)> {
+        let tooltip = self.tooltip.as_ref().filter(|_| self.text_rendering_enabled)?;
+        let canvas_size = Vec2::new(self.config.width as f32, self.config.height as f32);
+
+        // 有提示时需要排版文字，字体在这里第一次用到时创建
+        let text = TextResources::get_or_create(&mut self.text_resources, &self.device, &self.queue, self.config.format);
+        text.tooltip_buffer.set_size(&mut text.font_system, None, None);
+        text.tooltip_buffer.set_text(
+            &mut text.font_system,
+            &tooltip.lines.join("\n"),
+            &glyphon::Attrs::new().family(glyphon::Family::SansSerif),
+            glyphon::Shaping::Advanced,
+        );
+        text.tooltip_buffer.shape_until_scroll(&mut text.font_system, false);
+        let text_width = text.tooltip_buffer.layout_runs().map(|run| run.line_w).fold(0.0, f32::max);
+        let text_height = text.tooltip_buffer.layout_runs().count() as f32 * TOOLTIP_LINE_HEIGHT_PX;
+        let box_size = Vec2::new(text_width, text_height) + Vec2::splat(2.0 * (TOOLTIP_PADDING_PX + TOOLTIP_BORDER_PX));
+
+        let anchor = tooltip.anchor_screen;
+        let mut min = anchor + TOOLTIP_OFFSET_PX;
+        if min.x + box_size.x > canvas_size.x {
+            min.x = anchor.x - TOOLTIP_OFFSET_PX.x - box_size.x;
+        }
+        if min.y + box_size.y > canvas_size.y {
+            min.y = anchor.y - TOOLTIP_OFFSET_PX.y - box_size.y;
+        }
+        // 比画布还大时靠左上角显示
+        let min = min.clamp(Vec2::ZERO, (canvas_size - box_size).max(Vec2::ZERO));
+        let max = min + box_size;
+
+        push_screen_rect(vertices, min, max, canvas_size, self.overlay_theme.border);
+        push_screen_rect(vertices, min + TOOLTIP_BORDER_PX, max - TOOLTIP_BORDER_PX, canvas_size, self.overlay_theme.background);
+
+        let bounds = glyphon::TextBounds { left: min.x as i32, top: min.y as i32, right: max.x as i32, bottom: max.y as i32 };
+        Some((min + Vec2::splat(TOOLTIP_PADDING_PX + TOOLTIP_BORDER_PX), bounds))
+    }
+}
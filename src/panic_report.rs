@@ -0,0 +1,118 @@
+// src/panic_report.rs
+// 网页端的 panic 处理：除了 console_error_panic_hook 的控制台输出，还通知宿主页面（setPanicCallback 注册的回调，
+// 以及画布上的 `wdmview:panic` CustomEvent，detail 与回调参数相同：`{ message, location: { file, line, column } | null }`），
+// 便于显示"可视化已崩溃，请刷新"之类的提示。
+// panic 之后事件循环不再处理命令，因此设置 POISONED 标志，之后的 WasmApi 调用立即失败而不是一直等待回复；
+// 同时尽量释放 State（GPU 资源和画布上的绘制表面）。
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Weak};
+
+use wasm_bindgen::JsValue;
+use winit::event_loop::{EventLoopClosed, EventLoopProxy};
+
+use crate::app_state::State;
+use crate::ui_events::UserCommand;
+
+/// WasmApi 在 panic 之后返回的错误
+const POISONED_MESSAGE: &str = "WDMView has crashed (see the panic message in the console); reload the page to start a new viewer.";
+
+static POISONED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static PANIC_CALLBACK: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+    /// 当前附着的画布，CustomEvent 在它上面派发；没有画布时派发到 window
+    static CANVAS: RefCell<Option<web_sys::HtmlCanvasElement>> = const { RefCell::new(None) };
+    /// App 持有的 State，panic 时尝试释放
+    static APP_STATE: RefCell<Weak<Mutex<Option<State>>>> = const { RefCell::new(Weak::new()) };
+}
+
+/// 在 run() 中调用一次，替代 console_error_panic_hook::set_once()
+pub fn install() {
+    std::panic::set_hook(Box::new(|info| {
+        console_error_panic_hook::hook(info);
+        // 释放 State 时如果再次 panic，不再重复处理
+        if !POISONED.swap(true, Ordering::AcqRel) {
+            notify_host(info);
+            release_state();
+        }
+    }));
+}
+
+pub fn is_poisoned() -> bool {
+    POISONED.load(Ordering::Acquire)
+}
+
+pub fn set_panic_callback(callback: Option<js_sys::Function>) {
+    PANIC_CALLBACK.with(|cell| *cell.borrow_mut() = callback);
+}
+
+pub fn set_app_state(app_state: Weak<Mutex<Option<State>>>) {
+    APP_STATE.with(|cell| *cell.borrow_mut() = app_state);
+}
+
+pub fn set_canvas(canvas: Option<web_sys::HtmlCanvasElement>) {
+    CANVAS.with(|cell| *cell.borrow_mut() = canvas);
+}
+
+fn notify_host(info: &std::panic::PanicHookInfo) {
+    let detail = serde_json::json!({
+        "message": info.payload_as_str().unwrap_or("Box<dyn Any>"),
+        "location": info.location().map(|location| serde_json::json!({
+            "file": location.file(),
+            "line": location.line(),
+            "column": location.column(),
+        })),
+    });
+    let Ok(detail) = js_sys::JSON::parse(&detail.to_string()) else {
+        return;
+    };
+
+    // 钩子可能在这些 RefCell 被借用时触发，因此只用 try_borrow
+    let callback = PANIC_CALLBACK.with(|cell| cell.try_borrow().ok().and_then(|callback| callback.clone()));
+    if let Some(callback) = callback {
+        if let Err(e) = callback.call1(&JsValue::NULL, &detail) {
+            web_sys::console::error_2(&JsValue::from_str("Panic callback threw:"), &e);
+        }
+    }
+
+    let init = web_sys::CustomEventInit::new();
+    init.set_detail(&detail);
+    let Ok(event) = web_sys::CustomEvent::new_with_event_init_dict("wdmview:panic", &init) else {
+        return;
+    };
+    let canvas = CANVAS.with(|cell| cell.try_borrow().ok().and_then(|canvas| canvas.clone()));
+    let _ = match (canvas, web_sys::window()) {
+        (Some(canvas), _) => canvas.dispatch_event(&event),
+        (None, Some(window)) => window.dispatch_event(&event),
+        (None, None) => Ok(false),
+    };
+}
+
+/// panic 通常发生在持有 State 锁的命令处理或渲染过程中，这时无法释放，只能留给页面刷新
+fn release_state() {
+    let state = APP_STATE.with(|cell| cell.try_borrow().ok().and_then(|state| state.upgrade()));
+    if let Some(state) = state {
+        if let Ok(mut guard) = state.try_lock() {
+            drop(guard.take());
+        }
+    }
+}
+
+/// WasmApi 使用的事件循环代理：panic 之后不再发送命令，直接抛出 POISONED_MESSAGE
+#[derive(Clone, Debug)]
+pub struct CommandProxy(EventLoopProxy<UserCommand>);
+
+impl CommandProxy {
+    pub fn new(proxy: EventLoopProxy<UserCommand>) -> Self {
+        Self(proxy)
+    }
+
+    pub fn send_event(&self, command: UserCommand) -> Result<(), EventLoopClosed<UserCommand>> {
+        if is_poisoned() {
+            // 与返回 Err 对 JS 调用方的效果相同（同步抛出），但错误信息明确说明原因
+            wasm_bindgen::throw_str(POISONED_MESSAGE);
+        }
+        self.0.send_event(command)
+    }
+}
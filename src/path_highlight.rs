@@ -0,0 +1,36 @@
+// src/path_highlight.rs
+// 最短路径高亮：在两个节点之间求最短路径（见 scene/graph.rs），沿链路以单独的颜色绘制并标注跳数。
+// 与碎片整理服务的高亮互不影响，可以同时显示；加载新拓扑时清除。
+use glam::Vec2;
+
+use crate::app_state::State;
+use crate::scene::graph::{PathWeight, TopologyGraph};
+
+impl State {
+    /// 高亮 from_id 到 to_id 的最短路径，返回路径上的节点 ID（按顺序）；两个节点不连通时清除路径高亮并返回 None
+    pub fn highlight_path_between(&mut self, from_id: &str, to_id: &str, weight: PathWeight) -> Result<Option<Vec<String>>, String> {
+        let from = *self.node_id_to_idx.get(from_id).ok_or_else(|| format!("Unknown node ID '{}'", from_id))?;
+        let to = *self.node_id_to_idx.get(to_id).ok_or_else(|| format!("Unknown node ID '{}'", to_id))?;
+
+        let graph = TopologyGraph::new(self.geometry.circle_instances.len(), &self.node_id_to_idx, &self.all_connections);
+        let positions: Vec<Vec2> = self.geometry.circle_instances.iter().map(|instance| Vec2::from_array(instance.position)).collect();
+        let path = graph.shortest_path(from, to, weight, &positions);
+        match &path {
+            Some(path) => log::info!("Shortest path {} -> {}: {} hop(s).", from_id, to_id, path.len() - 1),
+            None => log::info!("No path between {} and {}.", from_id, to_id),
+        }
+
+        let path_ids = path.as_ref().map(|path| {
+            path.iter().map(|&idx| self.all_elements[idx].element_id.clone()).collect()
+        });
+        self.highlighted_path = path;
+        self.topology_needs_update = true;
+        Ok(path_ids)
+    }
+
+    pub fn clear_path_highlight(&mut self) {
+        if self.highlighted_path.take().is_some() {
+            self.topology_needs_update = true;
+        }
+    }
+}
@@ -0,0 +1,380 @@
+// src/picking.rs
+// GPU 拾取：点击时把节点和服务线段渲染到离屏 R32Uint 纹理（每个片元写入对象 ID），
+// 再把光标下的 1×1 纹素拷回 CPU。若设备不支持所需的纹理用途，State 退回到 CPU 命中测试。
+use glam::Vec2;
+
+use crate::models::{CircleInstance, LineVertex, ThickLineVertex, Vertex2D};
+
+const PICKING_WGSL: &str = include_str!("./shaders/picking.wgsl");
+const PICK_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+
+/// 背景（未命中任何对象）
+pub const PICK_ID_NONE: u32 = 0;
+/// 节点 ID = PICK_NODE_FLAG | 实例索引（在裁剪后的节点实例中，见 SceneGeometry::visible_circle_indices）；
+/// 其余非零 ID 为服务线段表的索引 + 1
+const PICK_NODE_FLAG: u32 = 1 << 31;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PickedEntity {
+    Node(usize),
+    /// segment_index 为路径中的第几跳（节点内部的转接弧线归入进入该节点的那一跳）
+    ServiceSegment { service_id: i32, segment_index: usize },
+}
+
+/// 可拾取的服务线段，GPU 拾取用其在表中的位置作为 ID，CPU 回退用其端点做距离测试
+#[derive(Debug, Clone, Copy)]
+pub struct PickSegment {
+    pub service_id: i32,
+    pub segment_index: usize,
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+pub fn segment_pick_id(table_index: usize) -> u32 {
+    table_index as u32 + 1
+}
+
+/// visible_circle_indices 把裁剪后的实例索引映射回节点索引
+pub fn decode_pick_id(pick_id: u32, segments: &[PickSegment], visible_circle_indices: &[u32]) -> Option<PickedEntity> {
+    if pick_id == PICK_ID_NONE {
+        None
+    } else if pick_id & PICK_NODE_FLAG != 0 {
+        visible_circle_indices.get((pick_id & !PICK_NODE_FLAG) as usize).map(|&idx| PickedEntity::Node(idx as usize))
+    } else {
+        segments.get(pick_id as usize - 1).map(|segment| PickedEntity::ServiceSegment {
+            service_id: segment.service_id,
+            segment_index: segment.segment_index,
+        })
+    }
+}
+
+pub fn pick_id_layout<'a>() -> wgpu::VertexBufferLayout<'a> {
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<u32>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &[wgpu::VertexAttribute {
+            offset: 0,
+            shader_location: 5, // location 5，避免与 LineVertex / ThickLineVertex 的属性冲突
+            format: wgpu::VertexFormat::Uint32,
+        }],
+    }
+}
+
+/// 一次拾取渲染所需的场景数据（全部复用主渲染的缓冲区）
+pub struct PickScene<'a> {
+    pub camera_bind_group: &'a wgpu::BindGroup,
+    pub quad_vertex_buffer: &'a wgpu::Buffer,
+    pub quad_index_buffer: &'a wgpu::Buffer,
+    pub circle_instance_buffer: &'a wgpu::Buffer,
+    pub circle_count: u32,
+    pub line_vertex_buffer: &'a wgpu::Buffer,
+    pub line_pick_id_buffer: &'a wgpu::Buffer,
+    pub line_vertex_count: u32,
+    pub highlight_line_vertex_buffer: &'a wgpu::Buffer,
+    pub highlight_line_pick_id_buffer: &'a wgpu::Buffer,
+    pub highlight_line_vertex_count: u32,
+    pub service_quad_vertex_buffer: &'a wgpu::Buffer,
+    pub service_quad_pick_id_buffer: &'a wgpu::Buffer,
+    pub service_quad_vertex_count: u32,
+}
+
+pub struct GpuPicker {
+    circle_pipeline: wgpu::RenderPipeline,
+    line_pipeline: wgpu::RenderPipeline,
+    thick_line_pipeline: wgpu::RenderPipeline,
+    texture: Option<(wgpu::Texture, wgpu::TextureView)>,
+    readback_buffer: wgpu::Buffer,
+    pending: Option<flume::Receiver<Result<(), wgpu::BufferAsyncError>>>,
+}
+
+impl GpuPicker {
+    /// 设备不支持将 R32Uint 用作渲染目标并拷贝回 CPU 时返回 None
+    pub fn new(adapter: &wgpu::Adapter, device: &wgpu::Device, camera_bind_group_layout: &wgpu::BindGroupLayout) -> Option<Self> {
+        let required_usages = wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC;
+        let format_features = adapter.get_texture_format_features(PICK_TEXTURE_FORMAT);
+        if !format_features.allowed_usages.contains(required_usages) {
+            log::warn!("{:?} picking texture is not supported ({:?}), falling back to CPU hit testing.", PICK_TEXTURE_FORMAT, format_features.allowed_usages);
+            return None;
+        }
+
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Picking Shader"),
+            source: wgpu::ShaderSource::Wgsl(PICKING_WGSL.into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Picking Pipeline Layout"),
+            bind_group_layouts: &[camera_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let create_pipeline = |label: &str,
+                               vs_entry: &str,
+                               fs_entry: &str,
+                               buffers: &[wgpu::VertexBufferLayout],
+                               topology: wgpu::PrimitiveTopology| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader_module,
+                    entry_point: Some(vs_entry),
+                    buffers,
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader_module,
+                    entry_point: Some(fs_entry),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: PICK_TEXTURE_FORMAT,
+                        blend: None, // 整数纹理不支持混合，后绘制的对象直接覆盖
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        let circle_pipeline = create_pipeline(
+            "Circle Picking Pipeline", "vs_circle", "fs_circle",
+            &[Vertex2D::layout(), CircleInstance::layout()],
+            wgpu::PrimitiveTopology::TriangleList,
+        );
+        let line_pipeline = create_pipeline(
+            "Line Picking Pipeline", "vs_line", "fs_line",
+            &[LineVertex::layout(), pick_id_layout()],
+            wgpu::PrimitiveTopology::LineList,
+        );
+        let thick_line_pipeline = create_pipeline(
+            "Thick Line Picking Pipeline", "vs_line", "fs_line",
+            &[ThickLineVertex::layout(), pick_id_layout()],
+            wgpu::PrimitiveTopology::TriangleList,
+        );
+
+        // 拷贝时每行字节数必须按 COPY_BYTES_PER_ROW_ALIGNMENT 对齐，即使只读取 1 个纹素
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Picking Readback Buffer"),
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            circle_pipeline,
+            line_pipeline,
+            thick_line_pipeline,
+            texture: None,
+            readback_buffer,
+            pending: None,
+        })
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// 渲染拾取纹理并请求回读 (x, y) 处的纹素。上一次拾取尚未完成时返回 false。
+    pub fn request_pick(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, scene: &PickScene, size: (u32, u32), x: u32, y: u32) -> bool {
+        if self.pending.is_some() || x >= size.0 || y >= size.1 {
+            return false;
+        }
+
+        // 纹理随 surface 尺寸按需（重新）创建
+        let texture_is_stale = self.texture.as_ref()
+            .is_none_or(|(texture, _)| texture.width() != size.0 || texture.height() != size.1);
+        if texture_is_stale {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Picking Texture"),
+                size: wgpu::Extent3d { width: size.0, height: size.1, depth_or_array_layers: 1 },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: PICK_TEXTURE_FORMAT,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            self.texture = Some((texture, view));
+        }
+        let Some((texture, view)) = self.texture.as_ref() else {
+            return false;
+        };
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Picking Encoder"),
+        });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Picking Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT), // 清为 PICK_ID_NONE
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            // 只渲染光标所在的像素
+            render_pass.set_scissor_rect(x, y, 1, 1);
+            render_pass.set_bind_group(0, scene.camera_bind_group, &[]);
+
+            // 与主渲染相同的绘制顺序：节点在下，线段在上
+            render_pass.set_pipeline(&self.circle_pipeline);
+            render_pass.set_vertex_buffer(0, scene.quad_vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, scene.circle_instance_buffer.slice(..));
+            render_pass.set_index_buffer(scene.quad_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..Vertex2D::QUAD_INDICES.len() as u32, 0, 0..scene.circle_count);
+
+            if scene.line_vertex_count > 0 {
+                render_pass.set_pipeline(&self.line_pipeline);
+                render_pass.set_vertex_buffer(0, scene.line_vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, scene.line_pick_id_buffer.slice(..));
+                render_pass.draw(0..scene.line_vertex_count, 0..1);
+            }
+
+            if scene.service_quad_vertex_count > 0 {
+                render_pass.set_pipeline(&self.thick_line_pipeline);
+                render_pass.set_vertex_buffer(0, scene.service_quad_vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, scene.service_quad_pick_id_buffer.slice(..));
+                render_pass.draw(0..scene.service_quad_vertex_count, 0..1);
+            }
+
+            if scene.highlight_line_vertex_count > 0 {
+                render_pass.set_pipeline(&self.thick_line_pipeline);
+                render_pass.set_vertex_buffer(0, scene.highlight_line_vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, scene.highlight_line_pick_id_buffer.slice(..));
+                render_pass.draw(0..scene.highlight_line_vertex_count, 0..1);
+            }
+        }
+
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: Some(1),
+                },
+            },
+            wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = flume::bounded(1);
+        self.readback_buffer.map_async(wgpu::MapMode::Read, 0..std::mem::size_of::<u32>() as wgpu::BufferAddress, move |result| {
+            let _ = sender.send(result);
+        });
+        self.pending = Some(receiver);
+        true
+    }
+
+    /// 检查回读是否完成；完成时返回拾取到的 ID（回读失败时记录警告并放弃本次拾取）
+    pub fn poll_result(&mut self, device: &wgpu::Device) -> Option<u32> {
+        let receiver = self.pending.as_ref()?;
+        // WebGPU 上映射回调由浏览器事件循环触发，这里的 poll 没有作用
+        let _ = device.poll(wgpu::PollType::Poll);
+
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(flume::TryRecvError::Empty) => return None,
+            Err(flume::TryRecvError::Disconnected) => Err(wgpu::BufferAsyncError),
+        };
+        self.pending = None;
+
+        if let Err(e) = result {
+            log::warn!("Failed to read back picking texture: {}", e);
+            return None;
+        }
+        let pick_id = {
+            let mapped = self.readback_buffer.slice(0..std::mem::size_of::<u32>() as wgpu::BufferAddress).get_mapped_range();
+            u32::from_ne_bytes([mapped[0], mapped[1], mapped[2], mapped[3]])
+        };
+        self.readback_buffer.unmap();
+        Some(pick_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::geometry::SceneGeometry;
+
+    /// 一行节点（x = 0, 10, 20, ...），第 glow_idx 个带光晕
+    fn row_of_nodes(count: usize, glow_idx: usize) -> SceneGeometry {
+        let circle_instances = (0..count)
+            .map(|idx| CircleInstance {
+                position: [idx as f32 * 10.0, 0.0],
+                radius_scale: 2.0,
+                color: [1.0; 4],
+                glow: if idx == glow_idx { 1.0 } else { 0.0 },
+            })
+            .collect();
+        SceneGeometry { circle_instances, ..Default::default() }
+    }
+
+    /// 裁剪掉一部分节点后，GPU 写入的实例索引经 visible_circle_indices 映射回原来的节点索引
+    #[test]
+    fn culled_node_pick_ids_map_to_original_indices() {
+        let mut geometry = row_of_nodes(10, 7);
+        geometry.hidden_nodes = vec![false, false, false, false, true];
+        // 区域覆盖 x = 25..=67.5：节点 3、4（隐藏）、5、6；节点 2 和 7 的圆只差一点够不到，但节点 7 的光晕伸进区域
+        geometry.cull_circle_instances(Some((Vec2::new(25.0, -1.0), Vec2::new(67.5, 1.0))));
+        assert_eq!(geometry.visible_circle_indices, vec![3, 5, 6, 7]);
+        for (instance_index, instance) in geometry.visible_circle_instances.iter().enumerate() {
+            let picked = decode_pick_id(PICK_NODE_FLAG | instance_index as u32, &[], &geometry.visible_circle_indices);
+            let Some(PickedEntity::Node(idx)) = picked else {
+                panic!("instance {instance_index} decoded to {picked:?}");
+            };
+            assert_eq!(geometry.circle_instances[idx].position, instance.position);
+        }
+        assert_eq!(decode_pick_id(PICK_NODE_FLAG | 4, &[], &geometry.visible_circle_indices), None);
+        assert_eq!(decode_pick_id(PICK_ID_NONE, &[], &geometry.visible_circle_indices), None);
+
+        // 不裁剪时只跳过隐藏的节点
+        geometry.cull_circle_instances(None);
+        assert_eq!(geometry.visible_circle_indices, vec![0, 1, 2, 3, 5, 6, 7, 8, 9]);
+        assert_eq!(decode_pick_id(PICK_NODE_FLAG | 4, &[], &geometry.visible_circle_indices), Some(PickedEntity::Node(5)));
+    }
+
+    #[test]
+    fn segment_pick_ids_index_the_segment_table() {
+        let segments: Vec<PickSegment> = (0..3)
+            .map(|segment_index| PickSegment { service_id: 42, segment_index, start: Vec2::ZERO, end: Vec2::ONE })
+            .collect();
+        for (table_index, segment) in segments.iter().enumerate() {
+            assert_eq!(
+                decode_pick_id(segment_pick_id(table_index), &segments, &[]),
+                Some(PickedEntity::ServiceSegment { service_id: 42, segment_index: segment.segment_index })
+            );
+        }
+        assert_eq!(decode_pick_id(segment_pick_id(3), &segments, &[]), None);
+    }
+}
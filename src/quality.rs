@@ -0,0 +1,264 @@
+// src/quality.rs
+// 自适应画质：按每帧实际的工作时间（CPU 端从 update() 开始到提交命令为止，开启 GPU 计时时取它与 GPU 耗时中较大者）
+// 的滑动平均估计帧开销，超过预算（默认 20ms）时依次关闭开销最大的可选效果
+// （流动动画 → 单条服务线路 → 标签），余量恢复后再逐级打开。降级和恢复都要求距上次变化保持一段时间，
+// 并且恢复阈值低于预算（迟滞），避免在预算附近来回切换。
+// 不使用 requestAnimationFrame 的帧间隔：它被显示器刷新率和按需渲染的空闲时间限制，不反映绘制本身的开销。
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use instant::Instant;
+use serde::Serialize;
+
+use crate::app_state::State;
+use crate::gpu_profiler::GpuPassTimings;
+use crate::settings::{is_positive_finite, LodLevel};
+
+const DEFAULT_FRAME_BUDGET_MS: f32 = 20.0;
+/// 指数滑动平均中新帧的权重
+const FRAME_TIME_SMOOTHING: f32 = 0.1;
+/// 平均帧时间低于预算的这一比例时才恢复
+const RESTORE_HEADROOM: f32 = 0.6;
+const DEGRADE_HOLD: Duration = Duration::from_secs(1);
+const RESTORE_HOLD: Duration = Duration::from_secs(3);
+
+/// 画质等级，后面的等级在前一级的基础上再关闭一项效果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityLevel {
+    #[default]
+    Full,
+    /// 暂停流动动画
+    NoFlowAnimation,
+    /// 强制聚合 LOD，不绘制单条服务线路
+    AggregatedServices,
+    /// 隐藏节点图标和服务跳数标签
+    NoLabels,
+}
+
+impl QualityLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QualityLevel::Full => "full",
+            QualityLevel::NoFlowAnimation => "no_flow_animation",
+            QualityLevel::AggregatedServices => "aggregated_services",
+            QualityLevel::NoLabels => "no_labels",
+        }
+    }
+
+    fn lower(self) -> Option<Self> {
+        match self {
+            QualityLevel::Full => Some(QualityLevel::NoFlowAnimation),
+            QualityLevel::NoFlowAnimation => Some(QualityLevel::AggregatedServices),
+            QualityLevel::AggregatedServices => Some(QualityLevel::NoLabels),
+            QualityLevel::NoLabels => None,
+        }
+    }
+
+    fn higher(self) -> Option<Self> {
+        match self {
+            QualityLevel::Full => None,
+            QualityLevel::NoFlowAnimation => Some(QualityLevel::Full),
+            QualityLevel::AggregatedServices => Some(QualityLevel::NoFlowAnimation),
+            QualityLevel::NoLabels => Some(QualityLevel::AggregatedServices),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct QualityGovernor {
+    pub enabled: bool,
+    pub frame_budget_ms: f32,
+    pub level: QualityLevel,
+    /// 帧工作时间的滑动平均（毫秒），还没有绘制过帧时为 None
+    pub average_frame_ms: Option<f32>,
+    last_change: Instant,
+}
+
+impl QualityGovernor {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            frame_budget_ms: DEFAULT_FRAME_BUDGET_MS,
+            level: QualityLevel::Full,
+            average_frame_ms: None,
+            last_change: Instant::now(),
+        }
+    }
+
+    /// 每渲染一帧调用一次，cpu_ms 为这一帧 CPU 端的工作时间，gpu_ms 为最近一次读回的 GPU 耗时（未开启计时时为 None）。
+    /// CPU 和 GPU 并行工作，帧开销取两者中较大者。画质等级变化时返回新的等级
+    pub fn record_frame(&mut self, now: Instant, cpu_ms: f32, gpu_ms: Option<f32>) -> Option<QualityLevel> {
+        if !self.enabled {
+            return None;
+        }
+        let frame_ms = gpu_ms.map_or(cpu_ms, |gpu_ms| cpu_ms.max(gpu_ms));
+        let average = match self.average_frame_ms {
+            Some(average) => average + (frame_ms - average) * FRAME_TIME_SMOOTHING,
+            None => frame_ms,
+        };
+        self.average_frame_ms = Some(average);
+
+        let since_change = now - self.last_change;
+        let next = if average > self.frame_budget_ms && since_change >= DEGRADE_HOLD {
+            self.level.lower()
+        } else if average < self.frame_budget_ms * RESTORE_HEADROOM && since_change >= RESTORE_HOLD {
+            self.level.higher()
+        } else {
+            None
+        }?;
+        self.level = next;
+        self.last_change = now;
+        Some(next)
+    }
+}
+
+/// getRenderStats 的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderStats {
+    pub fps: u32,
+    pub average_frame_ms: Option<f32>,
+    pub frame_budget_ms: f32,
+    pub quality_governor_enabled: bool,
+    pub quality_level: QualityLevel,
+    /// 实际使用的 LOD（画质降级时可能被强制为 aggregated）
+    pub lod_level: &'static str,
+    pub node_count: usize,
+    pub line_vertex_count: usize,
+    /// 主视图各 GPU 缓冲区的当前容量（字节），长期过大的缓冲区会被收缩
+    pub gpu_buffer_bytes: BTreeMap<&'static str, u64>,
+    pub gpu_buffer_total_bytes: u64,
+    pub gpu_profiling_enabled: bool,
+    /// 最近一次读回的各渲染通道 GPU 耗时；未开启计时或设备不支持时间戳查询时为 None
+    pub gpu_pass_ms: Option<GpuPassTimings>,
+    pub text_rendering_enabled: bool,
+    /// 上一帧提交的文字区域数（图标、标签、注释和叠加层文字），关闭文字渲染时为 0
+    pub text_area_count: usize,
+}
+
+impl State {
+    /// 关闭时恢复完整画质；budget_ms 为 None 时保持当前预算
+    pub fn set_quality_governor(&mut self, enabled: bool, budget_ms: Option<f32>) -> Result<(), String> {
+        if let Some(budget_ms) = budget_ms {
+            if !is_positive_finite(budget_ms) {
+                return Err(format!("Frame budget must be positive, got {}", budget_ms));
+            }
+            self.quality_governor.frame_budget_ms = budget_ms;
+        }
+        self.quality_governor.enabled = enabled;
+        if !enabled {
+            self.quality_governor.average_frame_ms = None;
+            self.apply_quality_level(QualityLevel::Full);
+        }
+        Ok(())
+    }
+
+    /// 设置画质等级；LOD 可能因此变化，需要重新生成线路
+    pub fn apply_quality_level(&mut self, level: QualityLevel) {
+        if self.quality_governor.level != level {
+            log::info!("Quality level changed: {} -> {} (average frame time {:?} ms)",
+                self.quality_governor.level.as_str(), level.as_str(), self.quality_governor.average_frame_ms);
+        }
+        self.quality_governor.level = level;
+        self.topology_needs_update = true;
+    }
+
+    /// 生成几何使用的 LOD：画质降级到 AggregatedServices 及以下时强制聚合
+    pub fn effective_lod_level(&self) -> LodLevel {
+        if self.quality_governor.level >= QualityLevel::AggregatedServices {
+            LodLevel::Aggregated
+        } else {
+            self.lod_level
+        }
+    }
+
+    pub fn labels_enabled(&self) -> bool {
+        self.quality_governor.level < QualityLevel::NoLabels
+    }
+
+    pub fn render_stats(&self) -> RenderStats {
+        let gpu_buffer_bytes: BTreeMap<&'static str, u64> = self.renderer.buffer_capacities().into_iter().collect();
+        let gpu_buffer_total_bytes = gpu_buffer_bytes.values().sum();
+        RenderStats {
+            fps: self.current_fps,
+            average_frame_ms: self.quality_governor.average_frame_ms,
+            frame_budget_ms: self.quality_governor.frame_budget_ms,
+            quality_governor_enabled: self.quality_governor.enabled,
+            quality_level: self.quality_governor.level,
+            lod_level: self.effective_lod_level().as_str(),
+            node_count: self.geometry.circle_instances.len(),
+            line_vertex_count: self.geometry.line_vertices.len(),
+            gpu_buffer_bytes,
+            gpu_buffer_total_bytes,
+            gpu_profiling_enabled: self.gpu_profiling_enabled,
+            gpu_pass_ms: self.gpu_profiler.as_ref().and_then(|profiler| profiler.latest),
+            text_rendering_enabled: self.text_rendering_enabled,
+            text_area_count: self.text_area_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+    /// 以 FRAME_INTERVAL 的间隔连续记录 frames 帧，返回每次等级变化及其发生的帧序号
+    fn run(governor: &mut QualityGovernor, start: Instant, frames: u32, cpu_ms: f32, gpu_ms: Option<f32>) -> Vec<(u32, QualityLevel)> {
+        (1..=frames)
+            .filter_map(|frame| governor.record_frame(start + FRAME_INTERVAL * frame, cpu_ms, gpu_ms).map(|level| (frame, level)))
+            .collect()
+    }
+
+    fn governor_at(level: QualityLevel, start: Instant) -> QualityGovernor {
+        QualityGovernor { level, last_change: start, ..QualityGovernor::new() }
+    }
+
+    #[test]
+    fn degrades_one_level_per_hold_when_over_budget() {
+        let start = Instant::now();
+        let mut governor = governor_at(QualityLevel::Full, start);
+        // 2.5 秒的超预算帧：第 1 秒和第 2 秒各降一级
+        let changes = run(&mut governor, start, 156, 30.0, None);
+        let frames_per_hold = (DEGRADE_HOLD.as_millis() / FRAME_INTERVAL.as_millis()) as u32;
+        assert_eq!(changes, vec![
+            (frames_per_hold + 1, QualityLevel::NoFlowAnimation),
+            (2 * frames_per_hold + 2, QualityLevel::AggregatedServices),
+        ]);
+    }
+
+    #[test]
+    fn gpu_time_counts_when_it_exceeds_cpu_time() {
+        let start = Instant::now();
+        let mut governor = governor_at(QualityLevel::Full, start);
+        assert_eq!(run(&mut governor, start, 70, 2.0, Some(35.0)).last(), Some(&(63, QualityLevel::NoFlowAnimation)));
+
+        // 只有 CPU 时间时不降级
+        let mut governor = governor_at(QualityLevel::Full, start);
+        assert!(run(&mut governor, start, 200, 2.0, None).is_empty());
+    }
+
+    #[test]
+    fn restores_only_below_headroom_after_hold() {
+        let start = Instant::now();
+        let budget = DEFAULT_FRAME_BUDGET_MS;
+        // 低于预算但高于恢复阈值：迟滞区间内保持不变
+        let mut governor = governor_at(QualityLevel::AggregatedServices, start);
+        assert!(run(&mut governor, start, 500, budget * (RESTORE_HEADROOM + 0.1), None).is_empty());
+
+        // 低于恢复阈值：保持 RESTORE_HOLD 之后才升一级
+        let mut governor = governor_at(QualityLevel::AggregatedServices, start);
+        let changes = run(&mut governor, start, 300, budget * RESTORE_HEADROOM * 0.5, None);
+        let frames_per_hold = (RESTORE_HOLD.as_millis() / FRAME_INTERVAL.as_millis()) as u32;
+        assert_eq!(changes, vec![(frames_per_hold + 1, QualityLevel::NoFlowAnimation)]);
+    }
+
+    #[test]
+    fn disabled_governor_ignores_frames() {
+        let start = Instant::now();
+        let mut governor = QualityGovernor { enabled: false, ..governor_at(QualityLevel::Full, start) };
+        assert!(run(&mut governor, start, 200, 100.0, None).is_empty());
+        assert_eq!(governor.average_frame_ms, None);
+    }
+}
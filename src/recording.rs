@@ -0,0 +1,193 @@
+// src/recording.rs
+// 时间轴录制：逐帧推进 current_time_selection，按指定分辨率离屏绘制并把每帧发送给 JS（由页面自行合成视频）。
+// 录制期间忽略鼠标和键盘输入，结束（完成、取消或出错）后恢复原来的时间和相机。
+use glam::Vec2;
+
+use crate::app_state::State;
+use crate::capture::Snapshot;
+use crate::settings::is_positive_finite;
+
+/// 单次录制的最大帧数（60 fps 下 5 分钟）。更长的录制会占用过多内存和时间，请求时直接拒绝
+pub const MAX_RECORDING_FRAMES: usize = 18_000;
+/// 已截取但请求方尚未取走的帧数上限：请求方（PNG 编码、on_frame）跟不上时暂停截取下一帧，
+/// 而不是在队列中堆积快照。事件通道的容量比它多一个，保证结束事件总能立即发送
+pub const RECORDING_QUEUE_FRAMES: usize = 4;
+
+/// 录制过程中发送给请求方的事件
+#[derive(Debug)]
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub enum RecordingEvent {
+    Frame { index: usize, time: f64, snapshot: Snapshot },
+    Finished { frames: usize },
+    Failed(String),
+}
+
+pub struct TimelineRecording {
+    frame_times: Vec<f64>,
+    next_frame: usize,
+    /// next_frame 对应的时间是否已经设置（几何在下一次 update() 中重新生成后才能截图）
+    time_applied: bool,
+    size: (u32, u32),
+    capture_receiver: Option<flume::Receiver<anyhow::Result<Snapshot>>>,
+    events: flume::Sender<RecordingEvent>,
+    original_time: f64,
+    original_camera: (Vec2, f32),
+}
+
+impl TimelineRecording {
+    /// 录制 [start, end] 内按 fps 采样的时刻（包含 start），超过 MAX_RECORDING_FRAMES 帧时返回错误
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn frame_times(start: f64, end: f64, fps: f64) -> Result<Vec<f64>, String> {
+        if !start.is_finite() || !end.is_finite() || end < start {
+            return Err(format!("Invalid recording range [{}, {}]", start, end));
+        }
+        if !is_positive_finite(fps) {
+            return Err(format!("Recording fps must be positive, got {}", fps));
+        }
+        // 先用 f64 比较，避免巨大的帧数在转换为 usize 时饱和
+        let frame_count = ((end - start) * fps).floor() + 1.0;
+        if frame_count > MAX_RECORDING_FRAMES as f64 {
+            return Err(format!(
+                "Recording of {} frame(s) exceeds the maximum of {} frames; shorten the range or lower the fps",
+                frame_count, MAX_RECORDING_FRAMES,
+            ));
+        }
+        Ok((0..frame_count as usize).map(|index| start + index as f64 / fps).collect())
+    }
+
+    /// 录制事件通道：容量为 RECORDING_QUEUE_FRAMES 帧加一个结束事件
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn event_channel() -> (flume::Sender<RecordingEvent>, flume::Receiver<RecordingEvent>) {
+        flume::bounded(RECORDING_QUEUE_FRAMES + 1)
+    }
+}
+
+impl State {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// 开始录制；已有录制时先取消它
+    pub fn start_recording(&mut self, frame_times: Vec<f64>, size: (u32, u32), events: flume::Sender<RecordingEvent>) {
+        if self.is_recording() {
+            self.finish_recording(RecordingEvent::Failed("Recording was replaced by a new recording".to_string()));
+        }
+        log::info!("Recording {} frame(s) at {}x{}.", frame_times.len(), size.0, size.1);
+
+        // 结束进行中的拖动，录制期间的输入会被忽略
+        self.is_mouse_left_pressed = false;
+        self.camera.end_panning();
+        self.camera.stop_zoom_animation();
+        self.end_node_drag();
+
+        self.recording = Some(TimelineRecording {
+            frame_times,
+            next_frame: 0,
+            time_applied: false,
+            size,
+            capture_receiver: None,
+            events,
+            original_time: self.current_time_selection,
+            original_camera: (self.camera.position, self.camera.zoom),
+        });
+    }
+
+    pub fn cancel_recording(&mut self) {
+        if self.is_recording() {
+            self.finish_recording(RecordingEvent::Failed("Recording cancelled".to_string()));
+        }
+    }
+
+    /// 推进录制状态机，在 update() 重新生成几何之后调用。返回是否仍在录制（需要继续请求新帧）
+    pub fn advance_recording(&mut self) -> bool {
+        let Some(recording) = self.recording.as_mut() else {
+            return false;
+        };
+
+        if let Some(receiver) = recording.capture_receiver.as_ref() {
+            if recording.events.is_disconnected() {
+                // 接收方已不存在（页面不再需要结果），直接停止
+                self.finish_recording(RecordingEvent::Failed("Recording receiver was dropped".to_string()));
+                return false;
+            }
+            // 请求方还没取走足够多的帧：截图留在 capture_receiver 中，稍后再转交
+            if recording.events.len() >= RECORDING_QUEUE_FRAMES {
+                return true;
+            }
+            let snapshot = match receiver.try_recv() {
+                Ok(Ok(snapshot)) => snapshot,
+                Err(flume::TryRecvError::Empty) => return true,
+                Ok(Err(e)) => {
+                    self.finish_recording(RecordingEvent::Failed(format!("Frame capture failed: {:#}", e)));
+                    return false;
+                }
+                Err(flume::TryRecvError::Disconnected) => {
+                    self.finish_recording(RecordingEvent::Failed("Frame capture was dropped".to_string()));
+                    return false;
+                }
+            };
+            let index = recording.next_frame;
+            let frame = RecordingEvent::Frame { index, time: recording.frame_times[index], snapshot };
+            if recording.events.try_send(frame).is_err() {
+                // 接收方已不存在（页面不再需要结果），直接停止
+                self.finish_recording(RecordingEvent::Failed("Recording receiver was dropped".to_string()));
+                return false;
+            }
+            recording.capture_receiver = None;
+            recording.next_frame += 1;
+            recording.time_applied = false;
+        }
+
+        let Some(&time) = recording.frame_times.get(recording.next_frame) else {
+            let frames = recording.frame_times.len();
+            self.finish_recording(RecordingEvent::Finished { frames });
+            return false;
+        };
+
+        if !recording.time_applied {
+            recording.time_applied = true;
+            self.current_time_selection = time;
+            self.topology_needs_update = true;
+        } else if self.geometry_update.is_none() {
+            // 大型拓扑的几何分多帧生成，完成之后才截图
+            let (sender, receiver) = flume::bounded(1);
+            recording.capture_receiver = Some(receiver);
+            let size = recording.size;
+            self.request_frame_capture(Some(size), sender);
+        }
+        true
+    }
+
+    fn finish_recording(&mut self, event: RecordingEvent) {
+        let Some(recording) = self.recording.take() else {
+            return;
+        };
+        log::info!("Recording finished: {:?}", event);
+        // 帧最多占用 RECORDING_QUEUE_FRAMES 个位置，结束事件不会阻塞
+        let _ = recording.events.try_send(event);
+
+        self.current_time_selection = recording.original_time;
+        (self.camera.position, self.camera.zoom) = recording.original_camera;
+        self.camera_needs_update = true;
+        self.topology_needs_update = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_times_are_capped() {
+        let times = TimelineRecording::frame_times(10.0, 11.0, 4.0).unwrap();
+        assert_eq!(times, vec![10.0, 10.25, 10.5, 10.75, 11.0]);
+
+        let max_duration = (MAX_RECORDING_FRAMES - 1) as f64 / 60.0;
+        assert_eq!(TimelineRecording::frame_times(0.0, max_duration, 60.0).unwrap().len(), MAX_RECORDING_FRAMES);
+        assert!(TimelineRecording::frame_times(0.0, max_duration + 1.0, 60.0).is_err());
+        // 帧数超出 usize 范围时也不能饱和后分配
+        assert!(TimelineRecording::frame_times(0.0, 1e300, 1e300).is_err());
+        assert!(TimelineRecording::frame_times(1.0, 0.0, 60.0).is_err());
+        assert!(TimelineRecording::frame_times(0.0, 1.0, 0.0).is_err());
+    }
+}
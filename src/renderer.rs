@@ -0,0 +1,897 @@
+// src/renderer.rs
+// GPU 资源：相机 uniform、三条场景渲染管线、屏幕空间叠加层管线以及场景几何对应的顶点 / 实例缓冲区。
+// 不依赖 winit 和 surface，可用于窗口、离屏渲染和拾取等多个渲染通道。
+use std::collections::HashMap;
+
+use wgpu::util::DeviceExt;
+
+use crate::camera::CameraUniform;
+use crate::models::{Vertex2D, CircleInstance, LineVertex, ThickLineVertex};
+use crate::picking::PickScene;
+use crate::scene::geometry::SceneGeometry;
+
+const LINES_WGSL: &str = include_str!("./shaders/lines.wgsl");
+const CIRCLES_WGSL: &str = include_str!("./shaders/circles.wgsl");
+const HIGHLIGHT_LINES_WGSL: &str = include_str!("./shaders/highlight_lines.wgsl");
+const OVERLAY_WGSL: &str = include_str!("./shaders/overlay.wgsl");
+/// 选中实例缓冲区的容量：(外圈 + 节点副本) × (选中 + 焦点)
+const MAX_SELECTION_INSTANCES: usize = 4;
+
+/// 所需大小低于容量的这一比例时视为过大
+const SHRINK_FRACTION: f64 = 0.25;
+/// 连续这么多次上传都过大时才收缩，大小来回变化时不会反复重建
+const SHRINK_AFTER_UPLOADS: u32 = 3;
+/// 收缩后的容量为所需大小的这一倍数（小于 1 / SHRINK_FRACTION，收缩后不会立即再次满足收缩条件）
+const SHRINK_HEADROOM: f64 = 2.0;
+/// 不超过此容量的缓冲区不收缩
+const MIN_SHRINK_CAPACITY: u64 = 64 * 1024;
+/// 收缩或释放后的最小容量：部分绘制调用无条件绑定缓冲区，而空的缓冲区切片无效
+const MIN_BUFFER_SIZE: u64 = 256;
+
+/// 一次上传对缓冲区的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BufferResize {
+    /// 太小，按数据大小重新创建
+    Grow,
+    /// 原地写入
+    Keep,
+    /// 按给定容量重新创建后写入
+    Shrink(u64),
+}
+
+/// 缓冲区太小时按数据大小重新创建，否则原地写入。容量连续 SHRINK_AFTER_UPLOADS 次超过所需大小的 1 / SHRINK_FRACTION 倍时
+/// 按 SHRINK_HEADROOM 倍的所需大小重新创建（undersized_uploads 为连续次数）；free_when_empty 时数据为空立即释放
+/// （缩小到 MIN_BUFFER_SIZE）
+fn buffer_resize(capacity: u64, required: u64, undersized_uploads: &mut u32, free_when_empty: bool) -> BufferResize {
+    if capacity < required {
+        *undersized_uploads = 0;
+        return BufferResize::Grow;
+    }
+    let shrink_to = if required == 0 && free_when_empty && capacity > MIN_BUFFER_SIZE {
+        Some(MIN_BUFFER_SIZE)
+    } else if capacity > MIN_SHRINK_CAPACITY && (required as f64) < capacity as f64 * SHRINK_FRACTION {
+        *undersized_uploads += 1;
+        (*undersized_uploads >= SHRINK_AFTER_UPLOADS)
+            .then(|| ((required as f64 * SHRINK_HEADROOM) as u64).max(MIN_BUFFER_SIZE).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT))
+    } else {
+        *undersized_uploads = 0;
+        None
+    };
+    match shrink_to {
+        Some(size) => {
+            *undersized_uploads = 0;
+            BufferResize::Shrink(size)
+        }
+        None => BufferResize::Keep,
+    }
+}
+
+/// 按 buffer_resize 的结果重新创建缓冲区或原地写入 data
+fn write_vertex_buffer(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    buffer: &mut wgpu::Buffer,
+    undersized_uploads: &mut u32,
+    data: &[u8],
+    label: &str,
+    free_when_empty: bool,
+) {
+    match buffer_resize(buffer.size(), data.len() as u64, undersized_uploads, free_when_empty) {
+        BufferResize::Grow => {
+            *buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} (Resized)", label)),
+                contents: data,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            });
+            return;
+        }
+        BufferResize::Keep => {}
+        BufferResize::Shrink(size) => {
+            log::debug!("Shrinking {} from {} to {} bytes.", label, buffer.size(), size);
+            *buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{} (Shrunk)", label)),
+                size,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+        }
+    }
+    queue.write_buffer(buffer, 0, data);
+}
+
+const GEOMETRY_BUFFER_COUNT: usize = 10;
+
+/// 场景几何中需要上传的数组及其缓冲区标签
+fn geometry_buffer_contents(geometry: &SceneGeometry) -> [(&'static str, &[u8]); GEOMETRY_BUFFER_COUNT] {
+    [
+        ("Circle Instance Buffer", bytemuck::cast_slice(&geometry.visible_circle_instances)),
+        ("Line Vertex Buffer", bytemuck::cast_slice(&geometry.line_vertices)),
+        ("Highlight Line Vertex Buffer", bytemuck::cast_slice(&geometry.highlight_line_vertices)),
+        ("Line Pick ID Buffer", bytemuck::cast_slice(&geometry.line_pick_ids)),
+        ("Highlight Line Pick ID Buffer", bytemuck::cast_slice(&geometry.highlight_line_pick_ids)),
+        ("Link Occupancy Vertex Buffer", bytemuck::cast_slice(&geometry.link_occupancy_vertices)),
+        ("Preview Line Vertex Buffer", bytemuck::cast_slice(&geometry.preview_line_vertices)),
+        ("Annotation Line Vertex Buffer", bytemuck::cast_slice(&geometry.annotation_line_vertices)),
+        ("Service Quad Vertex Buffer", bytemuck::cast_slice(&geometry.service_quad_vertices)),
+        ("Service Quad Pick ID Buffer", bytemuck::cast_slice(&geometry.service_quad_pick_ids)),
+    ]
+}
+
+/// 大型几何的分块上传：数据先写入新建的缓冲区，全部写完后由 Renderer::finish_geometry_upload 替换
+/// 正在使用的缓冲区，因此上传期间继续绘制之前的几何
+pub struct GeometryUpload {
+    buffers: Vec<wgpu::Buffer>,
+    written_bytes: Vec<usize>,
+}
+
+impl GeometryUpload {
+    /// 写入下一块数据（最多 byte_budget 字节，按 4 字节对齐）；全部写完时返回 true
+    pub fn write_chunk(&mut self, queue: &wgpu::Queue, geometry: &SceneGeometry, byte_budget: usize) -> bool {
+        let alignment = wgpu::COPY_BUFFER_ALIGNMENT as usize;
+        let mut remaining_budget = (byte_budget / alignment).max(1) * alignment;
+        for ((buffer, written), (_, data)) in self.buffers.iter().zip(self.written_bytes.iter_mut()).zip(geometry_buffer_contents(geometry)) {
+            if *written >= data.len() {
+                continue;
+            }
+            if remaining_budget == 0 {
+                return false;
+            }
+            let end = data.len().min(*written + remaining_budget);
+            queue.write_buffer(buffer, *written as wgpu::BufferAddress, &data[*written..end]);
+            remaining_budget -= end - *written;
+            *written = end;
+        }
+        true
+    }
+
+    /// 已写入的字节数和总字节数
+    pub fn progress(&self, geometry: &SceneGeometry) -> (usize, usize) {
+        let total = geometry_buffer_contents(geometry).iter().map(|(_, data)| data.len()).sum();
+        (self.written_bytes.iter().sum(), total)
+    }
+}
+
+/// draw_scene 的三个部分，GPU 计时（gpu_profiler.rs）时各自在单独的渲染通道中绘制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneSection {
+    /// 节点和选中外圈
+    Circles,
+    /// 普通线段、加粗的服务线路、占用率、容量条、预览、最短路径和测量线
+    Lines,
+    /// 高亮线路和流动圆点
+    Highlights,
+}
+
+/// draw_scene 中不属于 SceneGeometry、由 State 单独上传的实例数和顶点数；对比视图的右半部分只绘制选中外圈
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OverlayCounts {
+    pub selection_instances: u32,
+    pub capacity_bar_vertices: u32,
+    pub hover_line_vertices: u32,
+    pub flow_dots: u32,
+}
+
+impl SceneSection {
+    pub const ALL: [SceneSection; 3] = [SceneSection::Circles, SceneSection::Lines, SceneSection::Highlights];
+}
+
+pub struct Renderer {
+    pub camera_bind_group_layout: wgpu::BindGroupLayout,
+    pub camera_buffer: wgpu::Buffer,
+    pub camera_bind_group: wgpu::BindGroup,
+
+    pub line_render_pipeline: wgpu::RenderPipeline,
+    pub circle_render_pipeline: wgpu::RenderPipeline,
+    pub highlight_line_render_pipeline: wgpu::RenderPipeline, // 三角形四边形线路：高亮、按码率加粗的服务、预览、链路占用率、最短路径和测量线
+    pub overlay_render_pipeline: wgpu::RenderPipeline, // 屏幕空间叠加层（NDC 三角形），绘制在场景之后、文字之前
+
+    pub quad_vertex_buffer: wgpu::Buffer,
+    pub quad_index_buffer: wgpu::Buffer,
+    pub circle_instance_buffer: wgpu::Buffer,
+    pub selection_instance_buffer: wgpu::Buffer,
+    pub line_vertex_buffer: wgpu::Buffer,
+    pub highlight_line_vertex_buffer: wgpu::Buffer,
+    pub link_occupancy_vertex_buffer: wgpu::Buffer,
+    pub preview_line_vertex_buffer: wgpu::Buffer,
+    pub annotation_line_vertex_buffer: wgpu::Buffer,
+    pub service_quad_vertex_buffer: wgpu::Buffer,
+    pub capacity_bar_vertex_buffer: wgpu::Buffer, // 按缩放生成，不属于 SceneGeometry，见 capacity_bars.rs
+    pub flow_dot_instance_buffer: wgpu::Buffer, // 每帧生成，见 flow_animation.rs
+    pub hover_line_vertex_buffer: wgpu::Buffer, // 悬停服务的强调线，见 service_hover.rs
+    pub overlay_vertex_buffer: wgpu::Buffer, // 每帧生成，见 service_panel.rs
+    pub line_pick_id_buffer: wgpu::Buffer,
+    pub highlight_line_pick_id_buffer: wgpu::Buffer,
+    pub service_quad_pick_id_buffer: wgpu::Buffer,
+    /// 每个缓冲区（按标签）连续过大的上传次数，见 write_vertex_buffer
+    undersized_uploads: HashMap<&'static str, u32>,
+}
+
+impl Renderer {
+    /// 创建渲染到 `texture_format` 的管线，缓冲区以 `geometry` 的当前内容初始化
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, camera_uniform: &CameraUniform, geometry: &SceneGeometry) -> Self {
+        let camera_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Camera Buffer"),
+                contents: bytemuck::cast_slice(&[*camera_uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ],
+            label: Some("Camera Bind Group Layout"),
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                }
+            ],
+            label: Some("Camera Bind Group"),
+        });
+
+        // --- 着色器模块 ---
+        let lines_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Lines Shader"),
+            source: wgpu::ShaderSource::Wgsl(LINES_WGSL.into()),
+        });
+
+        let circles_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Circles Shader"),
+            source: wgpu::ShaderSource::Wgsl(CIRCLES_WGSL.into()),
+        });
+
+        // --- 渲染管线布局 ---
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[
+                &camera_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        // --- 线段渲染管线 ---
+        let line_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Line Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &lines_shader_module,
+                entry_point: Some("vs_main"),
+                buffers: &[
+                    LineVertex::layout(),
+                ],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &lines_shader_module,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        // --- 圆形渲染管线 ---
+        let circle_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Circle Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &circles_shader_module,
+                entry_point: Some("vs_main"),
+                buffers: &[
+                    Vertex2D::layout(),
+                    CircleInstance::layout(),
+                ],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &circles_shader_module,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let circle_instance_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Circle Instance Buffer"),
+                contents: bytemuck::cast_slice(&geometry.visible_circle_instances),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let selection_instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Selection Instance Buffer"),
+            size: (MAX_SELECTION_INSTANCES * std::mem::size_of::<CircleInstance>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let quad_vertex_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Quad Vertex Buffer"),
+                contents: bytemuck::cast_slice(Vertex2D::QUAD_VERTICES.as_slice()),
+                usage: wgpu::BufferUsages::VERTEX,
+            }
+        );
+
+        let quad_index_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Quad Index Buffer"),
+                contents: bytemuck::cast_slice(Vertex2D::QUAD_INDICES.as_slice()),
+                usage: wgpu::BufferUsages::INDEX,
+            }
+        );
+
+        let line_vertex_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Line Vertex Buffer"),
+                contents: bytemuck::cast_slice(&geometry.line_vertices),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        // --- 高亮线段着色器模块 ---
+        let highlight_lines_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Highlight Lines Shader"),
+            source: wgpu::ShaderSource::Wgsl(HIGHLIGHT_LINES_WGSL.into()),
+        });
+
+        // --- 高亮线段渲染管线 ---
+        let highlight_line_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Highlight Line Render Pipeline"),
+            layout: Some(&render_pipeline_layout), // 共用布局
+            vertex: wgpu::VertexState {
+                module: &highlight_lines_shader_module,
+                entry_point: Some("vs_main"), // 可以是与 lines.wgsl 相同的 vs_main
+                buffers: &[
+                    ThickLineVertex::layout(), // 带沿路径距离，用于虚线图案
+                ],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &highlight_lines_shader_module,
+                entry_point: Some("fs_main"), // 可以是与 lines.wgsl 相同的 fs_main
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList, // 关键：使用 TriangleList
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None, // 双面渲染，因为四边形可能被裁剪
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        // --- 屏幕空间叠加层管线 ---
+        let overlay_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Overlay Shader"),
+            source: wgpu::ShaderSource::Wgsl(OVERLAY_WGSL.into()),
+        });
+
+        let overlay_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Overlay Render Pipeline"),
+            layout: Some(&render_pipeline_layout), // 共用布局，只读取 sRGB 输出标志
+            vertex: wgpu::VertexState {
+                module: &overlay_shader_module,
+                entry_point: Some("vs_main"),
+                buffers: &[
+                    LineVertex::layout(), // position 为 NDC 坐标
+                ],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &overlay_shader_module,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let highlight_line_vertex_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Highlight Line Vertex Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[ThickLineVertex]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let preview_line_vertex_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Preview Line Vertex Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[ThickLineVertex]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let annotation_line_vertex_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Annotation Line Vertex Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[ThickLineVertex]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let service_quad_vertex_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Service Quad Vertex Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[ThickLineVertex]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let capacity_bar_vertex_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Capacity Bar Vertex Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[ThickLineVertex]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let flow_dot_instance_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Flow Dot Instance Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[CircleInstance]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let hover_line_vertex_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Hover Line Vertex Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[ThickLineVertex]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let overlay_vertex_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Overlay Vertex Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[LineVertex]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let link_occupancy_vertex_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Link Occupancy Vertex Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[ThickLineVertex]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let line_pick_id_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Line Pick ID Buffer"),
+                contents: bytemuck::cast_slice(&geometry.line_pick_ids),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+        let highlight_line_pick_id_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Highlight Line Pick ID Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[u32]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+        let service_quad_pick_id_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Service Quad Pick ID Buffer"),
+                contents: bytemuck::cast_slice(&[] as &[u32]), // 初始为空
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        Self {
+            camera_bind_group_layout, camera_buffer, camera_bind_group,
+            line_render_pipeline, circle_render_pipeline, highlight_line_render_pipeline, overlay_render_pipeline,
+            quad_vertex_buffer, quad_index_buffer,
+            circle_instance_buffer, selection_instance_buffer,
+            line_vertex_buffer, highlight_line_vertex_buffer,
+            link_occupancy_vertex_buffer, preview_line_vertex_buffer, annotation_line_vertex_buffer, service_quad_vertex_buffer,
+            capacity_bar_vertex_buffer, flow_dot_instance_buffer, hover_line_vertex_buffer, overlay_vertex_buffer,
+            line_pick_id_buffer, highlight_line_pick_id_buffer, service_quad_pick_id_buffer,
+            undersized_uploads: HashMap::new(),
+        }
+    }
+
+    pub fn write_camera_uniform(&self, queue: &wgpu::Queue, camera_uniform: &CameraUniform) {
+        queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[*camera_uniform]));
+    }
+
+    /// 上传重新生成的几何，缓冲区不够大时重新创建
+    /// 没有高亮时高亮线路的缓冲区立即释放，其余缓冲区长期过大时收缩
+    pub fn upload_geometry(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, geometry: &SceneGeometry) {
+        let (buffers, undersized_uploads) = self.geometry_buffers_mut();
+        for (buffer, (label, data)) in buffers.into_iter().zip(geometry_buffer_contents(geometry)) {
+            let free_when_empty = label.starts_with("Highlight Line");
+            write_vertex_buffer(device, queue, buffer, undersized_uploads.entry(label).or_default(), data, label, free_when_empty);
+        }
+    }
+
+    /// 只上传节点实例（分帧更新开始时节点数量或位置可能已经变化，或视口移出了裁剪范围），传入裁剪后的实例
+    pub fn upload_circle_instances(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, circle_instances: &[CircleInstance]) {
+        let label = "Circle Instance Buffer";
+        write_vertex_buffer(device, queue, &mut self.circle_instance_buffer, self.undersized_uploads.entry(label).or_default(),
+            bytemuck::cast_slice(circle_instances), label, false);
+    }
+
+    /// 为分块上传创建与 geometry 等大的新缓冲区，之后用 GeometryUpload::write_chunk 逐帧写入
+    pub fn begin_geometry_upload(&self, device: &wgpu::Device, geometry: &SceneGeometry) -> GeometryUpload {
+        let contents = geometry_buffer_contents(geometry);
+        let buffers = contents
+            .iter()
+            .map(|(label, data)| device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: (data.len() as wgpu::BufferAddress).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }))
+            .collect();
+        GeometryUpload { buffers, written_bytes: vec![0; contents.len()] }
+    }
+
+    /// 分块上传完成后替换正在使用的缓冲区
+    pub fn finish_geometry_upload(&mut self, upload: GeometryUpload) {
+        let (buffers, undersized_uploads) = self.geometry_buffers_mut();
+        for (buffer, uploaded) in buffers.into_iter().zip(upload.buffers) {
+            *buffer = uploaded; // 与数据等大，重新开始计数
+        }
+        undersized_uploads.clear();
+    }
+
+    /// 与 geometry_buffer_contents 顺序相同；同时返回收缩计数
+    fn geometry_buffers_mut(&mut self) -> ([&mut wgpu::Buffer; GEOMETRY_BUFFER_COUNT], &mut HashMap<&'static str, u32>) {
+        (
+            [
+                &mut self.circle_instance_buffer,
+                &mut self.line_vertex_buffer,
+                &mut self.highlight_line_vertex_buffer,
+                &mut self.line_pick_id_buffer,
+                &mut self.highlight_line_pick_id_buffer,
+                &mut self.link_occupancy_vertex_buffer,
+                &mut self.preview_line_vertex_buffer,
+                &mut self.annotation_line_vertex_buffer,
+                &mut self.service_quad_vertex_buffer,
+                &mut self.service_quad_pick_id_buffer,
+            ],
+            &mut self.undersized_uploads,
+        )
+    }
+
+    /// getRenderStats 报告的各缓冲区当前容量（字节）
+    pub fn buffer_capacities(&self) -> Vec<(&'static str, u64)> {
+        [
+            ("Circle Instance Buffer", &self.circle_instance_buffer),
+            ("Line Vertex Buffer", &self.line_vertex_buffer),
+            ("Highlight Line Vertex Buffer", &self.highlight_line_vertex_buffer),
+            ("Line Pick ID Buffer", &self.line_pick_id_buffer),
+            ("Highlight Line Pick ID Buffer", &self.highlight_line_pick_id_buffer),
+            ("Link Occupancy Vertex Buffer", &self.link_occupancy_vertex_buffer),
+            ("Preview Line Vertex Buffer", &self.preview_line_vertex_buffer),
+            ("Annotation Line Vertex Buffer", &self.annotation_line_vertex_buffer),
+            ("Service Quad Vertex Buffer", &self.service_quad_vertex_buffer),
+            ("Service Quad Pick ID Buffer", &self.service_quad_pick_id_buffer),
+            ("Capacity Bar Vertex Buffer", &self.capacity_bar_vertex_buffer),
+            ("Flow Dot Instance Buffer", &self.flow_dot_instance_buffer),
+            ("Hover Line Vertex Buffer", &self.hover_line_vertex_buffer),
+            ("Overlay Vertex Buffer", &self.overlay_vertex_buffer),
+        ]
+        .into_iter()
+        .map(|(label, buffer)| (label, buffer.size()))
+        .collect()
+    }
+
+    pub fn upload_capacity_bar_vertices(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, vertices: &[ThickLineVertex]) {
+        let label = "Capacity Bar Vertex Buffer";
+        write_vertex_buffer(device, queue, &mut self.capacity_bar_vertex_buffer, self.undersized_uploads.entry(label).or_default(),
+            bytemuck::cast_slice(vertices), label, false);
+    }
+
+    pub fn upload_flow_dot_instances(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, instances: &[CircleInstance]) {
+        let label = "Flow Dot Instance Buffer";
+        write_vertex_buffer(device, queue, &mut self.flow_dot_instance_buffer, self.undersized_uploads.entry(label).or_default(),
+            bytemuck::cast_slice(instances), label, false);
+    }
+
+    pub fn upload_hover_line_vertices(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, vertices: &[ThickLineVertex]) {
+        let label = "Hover Line Vertex Buffer";
+        write_vertex_buffer(device, queue, &mut self.hover_line_vertex_buffer, self.undersized_uploads.entry(label).or_default(),
+            bytemuck::cast_slice(vertices), label, true);
+    }
+
+    pub fn upload_overlay_vertices(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, vertices: &[LineVertex]) {
+        let label = "Overlay Vertex Buffer";
+        write_vertex_buffer(device, queue, &mut self.overlay_vertex_buffer, self.undersized_uploads.entry(label).or_default(),
+            bytemuck::cast_slice(vertices), label, false);
+    }
+
+    /// 上传选中 / 焦点节点的外圈实例（最多 MAX_SELECTION_INSTANCES 个）
+    pub fn upload_selection_instances(&self, queue: &wgpu::Queue, selection_instances: &[CircleInstance]) {
+        let count = selection_instances.len().min(MAX_SELECTION_INSTANCES);
+        if count > 0 {
+            queue.write_buffer(&self.selection_instance_buffer, 0, bytemuck::cast_slice(&selection_instances[..count]));
+        }
+    }
+
+    /// 拾取通道复用的缓冲区
+    pub fn pick_scene<'a>(&'a self, geometry: &SceneGeometry) -> PickScene<'a> {
+        PickScene {
+            camera_bind_group: &self.camera_bind_group,
+            quad_vertex_buffer: &self.quad_vertex_buffer,
+            quad_index_buffer: &self.quad_index_buffer,
+            circle_instance_buffer: &self.circle_instance_buffer,
+            circle_count: geometry.visible_circle_instances.len() as u32,
+            line_vertex_buffer: &self.line_vertex_buffer,
+            line_pick_id_buffer: &self.line_pick_id_buffer,
+            line_vertex_count: geometry.line_vertices.len().min(geometry.line_pick_ids.len()) as u32,
+            highlight_line_vertex_buffer: &self.highlight_line_vertex_buffer,
+            highlight_line_pick_id_buffer: &self.highlight_line_pick_id_buffer,
+            highlight_line_vertex_count: geometry.highlight_line_vertices.len().min(geometry.highlight_line_pick_ids.len()) as u32,
+            service_quad_vertex_buffer: &self.service_quad_vertex_buffer,
+            service_quad_pick_id_buffer: &self.service_quad_pick_id_buffer,
+            service_quad_vertex_count: geometry.service_quad_vertices.len().min(geometry.service_quad_pick_ids.len()) as u32,
+        }
+    }
+
+    /// 绘制节点和所有线路（不含文字），顺序：节点、选中外圈、普通线段、加粗的服务线路、占用率、容量条、预览、最短路径和测量线、高亮、
+    /// 悬停服务、流动圆点。sections 为要绘制的部分（按上述顺序），通常为 SceneSection::ALL
+    pub fn draw_scene(&self, render_pass: &mut wgpu::RenderPass<'_>, geometry: &SceneGeometry, sections: &[SceneSection], counts: OverlayCounts) {
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        for &section in sections {
+            match section {
+                SceneSection::Circles => self.draw_circles(render_pass, geometry, counts.selection_instances),
+                SceneSection::Lines => self.draw_lines(render_pass, geometry, counts.capacity_bar_vertices),
+                SceneSection::Highlights => self.draw_highlights(render_pass, geometry, counts.hover_line_vertices, counts.flow_dots),
+            }
+        }
+    }
+
+    fn draw_circles(&self, render_pass: &mut wgpu::RenderPass<'_>, geometry: &SceneGeometry, selection_instance_count: u32) {
+        // 1. 绘制圆形（节点）
+        render_pass.set_pipeline(&self.circle_render_pipeline);
+        render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.circle_instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.quad_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(
+            0..Vertex2D::QUAD_INDICES.len() as u32,
+            0,
+            0..geometry.visible_circle_instances.len() as u32,
+        );
+
+        // 1.5 选中节点的外圈和放大的节点
+        if selection_instance_count > 0 {
+            render_pass.set_vertex_buffer(1, self.selection_instance_buffer.slice(..));
+            render_pass.draw_indexed(
+                0..Vertex2D::QUAD_INDICES.len() as u32,
+                0,
+                0..selection_instance_count.min(MAX_SELECTION_INSTANCES as u32),
+            );
+        }
+    }
+
+    fn draw_lines(&self, render_pass: &mut wgpu::RenderPass<'_>, geometry: &SceneGeometry, capacity_bar_vertex_count: u32) {
+        // 2. 绘制普通线段 (链路边界和服务)
+        render_pass.set_pipeline(&self.line_render_pipeline);
+        render_pass.set_vertex_buffer(0, self.line_vertex_buffer.slice(..));
+        render_pass.draw(0..geometry.line_vertices.len() as u32, 0..1);
+
+        // 2.1 按码率加粗的服务线路（四边形）
+        if !geometry.service_quad_vertices.is_empty() {
+            render_pass.set_pipeline(&self.highlight_line_render_pipeline);
+            render_pass.set_vertex_buffer(0, self.service_quad_vertex_buffer.slice(..));
+            render_pass.draw(0..geometry.service_quad_vertices.len() as u32, 0..1);
+        }
+
+        // 2.5 聚合 LOD 下的链路占用率四边形
+        if !geometry.link_occupancy_vertices.is_empty() {
+            render_pass.set_pipeline(&self.highlight_line_render_pipeline);
+            render_pass.set_vertex_buffer(0, self.link_occupancy_vertex_buffer.slice(..));
+            render_pass.draw(0..geometry.link_occupancy_vertices.len() as u32, 0..1);
+        }
+
+        // 2.55 链路容量条
+        if capacity_bar_vertex_count > 0 {
+            render_pass.set_pipeline(&self.highlight_line_render_pipeline);
+            render_pass.set_vertex_buffer(0, self.capacity_bar_vertex_buffer.slice(..));
+            render_pass.draw(0..capacity_bar_vertex_count, 0..1);
+        }
+
+        // 2.6 预览服务（虚线）
+        if !geometry.preview_line_vertices.is_empty() {
+            render_pass.set_pipeline(&self.highlight_line_render_pipeline);
+            render_pass.set_vertex_buffer(0, self.preview_line_vertex_buffer.slice(..));
+            render_pass.draw(0..geometry.preview_line_vertices.len() as u32, 0..1);
+        }
+
+        // 2.7 最短路径和测量线
+        if !geometry.annotation_line_vertices.is_empty() {
+            render_pass.set_pipeline(&self.highlight_line_render_pipeline);
+            render_pass.set_vertex_buffer(0, self.annotation_line_vertex_buffer.slice(..));
+            render_pass.draw(0..geometry.annotation_line_vertices.len() as u32, 0..1);
+        }
+    }
+
+    fn draw_highlights(&self, render_pass: &mut wgpu::RenderPass<'_>, geometry: &SceneGeometry, hover_line_vertex_count: u32, flow_dot_count: u32) {
+        // 3. 绘制高亮线段 (覆盖在普通线段之上)
+        if !geometry.highlight_line_vertices.is_empty() {
+            render_pass.set_pipeline(&self.highlight_line_render_pipeline);
+            render_pass.set_vertex_buffer(0, self.highlight_line_vertex_buffer.slice(..));
+            render_pass.draw(0..geometry.highlight_line_vertices.len() as u32, 0..1);
+        }
+
+        // 3.5 悬停服务的强调线
+        if hover_line_vertex_count > 0 {
+            render_pass.set_pipeline(&self.highlight_line_render_pipeline);
+            render_pass.set_vertex_buffer(0, self.hover_line_vertex_buffer.slice(..));
+            render_pass.draw(0..hover_line_vertex_count, 0..1);
+        }
+
+        // 4. 流动动画的圆点（复用节点管线）
+        if flow_dot_count > 0 {
+            render_pass.set_pipeline(&self.circle_render_pipeline);
+            render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.flow_dot_instance_buffer.slice(..));
+            render_pass.set_index_buffer(self.quad_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.draw_indexed(0..Vertex2D::QUAD_INDICES.len() as u32, 0, 0..flow_dot_count);
+        }
+    }
+
+    /// 绘制屏幕空间叠加层（视口应为整个画布）
+    pub fn draw_overlay(&self, render_pass: &mut wgpu::RenderPass<'_>, overlay_vertex_count: u32) {
+        if overlay_vertex_count == 0 {
+            return;
+        }
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_pipeline(&self.overlay_render_pipeline);
+        render_pass.set_vertex_buffer(0, self.overlay_vertex_buffer.slice(..));
+        render_pass.draw(0..overlay_vertex_count, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAPACITY: u64 = 1024 * 1024;
+
+    #[test]
+    fn buffer_grows_when_data_does_not_fit() {
+        let mut undersized_uploads = 2;
+        assert_eq!(buffer_resize(CAPACITY, CAPACITY + 4, &mut undersized_uploads, false), BufferResize::Grow);
+        assert_eq!(undersized_uploads, 0);
+        assert_eq!(buffer_resize(CAPACITY, CAPACITY, &mut undersized_uploads, false), BufferResize::Keep);
+    }
+
+    /// 所需大小在收缩阈值以上、缓冲区本身不大或只短暂变小时原地写入
+    #[test]
+    fn buffer_stays_put_above_threshold_or_briefly_small() {
+        let mut undersized_uploads = 0;
+        for _ in 0..10 {
+            assert_eq!(buffer_resize(CAPACITY, CAPACITY / 3, &mut undersized_uploads, true), BufferResize::Keep);
+            assert_eq!(buffer_resize(MIN_SHRINK_CAPACITY, 0, &mut undersized_uploads, false), BufferResize::Keep);
+        }
+        assert_eq!(undersized_uploads, 0);
+
+        // 变小的上传之间夹着一次正常大小的上传，计数重新开始
+        for _ in 0..5 {
+            for _ in 1..SHRINK_AFTER_UPLOADS {
+                assert_eq!(buffer_resize(CAPACITY, 1024, &mut undersized_uploads, false), BufferResize::Keep);
+            }
+            assert_eq!(buffer_resize(CAPACITY, CAPACITY / 2, &mut undersized_uploads, false), BufferResize::Keep);
+            assert_eq!(undersized_uploads, 0);
+        }
+    }
+
+    /// 连续 SHRINK_AFTER_UPLOADS 次过大后才收缩，收缩后的容量不会立即再次满足收缩条件
+    #[test]
+    fn buffer_shrinks_after_sustained_low_use() {
+        let mut undersized_uploads = 0;
+        let required = 40_000;
+        for _ in 1..SHRINK_AFTER_UPLOADS {
+            assert_eq!(buffer_resize(CAPACITY, required, &mut undersized_uploads, false), BufferResize::Keep);
+        }
+        let BufferResize::Shrink(size) = buffer_resize(CAPACITY, required, &mut undersized_uploads, false) else {
+            panic!("expected a shrink after {SHRINK_AFTER_UPLOADS} undersized uploads");
+        };
+        assert_eq!(size, 80_000);
+        assert_eq!(size % wgpu::COPY_BUFFER_ALIGNMENT, 0);
+        assert_eq!(undersized_uploads, 0);
+        assert_eq!(buffer_resize(size, required, &mut undersized_uploads, false), BufferResize::Keep);
+
+        // 清空时（free_when_empty）立即释放到最小容量
+        assert_eq!(buffer_resize(CAPACITY, 0, &mut undersized_uploads, true), BufferResize::Shrink(MIN_BUFFER_SIZE));
+        assert_eq!(buffer_resize(MIN_BUFFER_SIZE, 0, &mut undersized_uploads, true), BufferResize::Keep);
+    }
+}
@@ -0,0 +1,61 @@
+// src/renderer_info.rs
+// 渲染后端信息（getRendererInfo / F2）：适配器、图形后端、表面格式、是否由着色器做 sRGB 转换、呈现模式和主要的设备限制。
+// 在 State::with_gpu 中与设备一起生成，因此重新创建 State（例如重新附着画布）后反映新的设备。
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RendererInfo {
+    pub adapter_name: String,
+    pub vendor: u32,
+    pub device: u32,
+    /// "DiscreteGpu"、"IntegratedGpu"、"Cpu" 等
+    pub device_type: String,
+    pub driver: String,
+    pub driver_info: String,
+    /// "BrowserWebGpu"、"Vulkan"、"Metal"、"Dx12" 或 "Gl"
+    pub backend: String,
+    pub texture_format: String,
+    /// 表面格式不是 sRGB 时，着色器在输出前自行做 sRGB 编码
+    pub shader_srgb_conversion: bool,
+    /// 离屏渲染（headless）时为 null
+    pub present_mode: Option<String>,
+    pub limits: RendererLimits,
+}
+
+/// 与本渲染器相关的 wgpu::Limits 子集
+#[derive(Debug, Clone, Serialize)]
+pub struct RendererLimits {
+    pub max_texture_dimension_2d: u32,
+    pub max_buffer_size: u64,
+    pub max_vertex_buffers: u32,
+    pub max_bind_groups: u32,
+    pub max_uniform_buffer_binding_size: u32,
+    pub max_storage_buffer_binding_size: u32,
+}
+
+impl RendererInfo {
+    pub fn new(adapter: &wgpu::Adapter, device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, has_surface: bool) -> Self {
+        let adapter_info = adapter.get_info();
+        let limits = device.limits();
+        Self {
+            adapter_name: adapter_info.name,
+            vendor: adapter_info.vendor,
+            device: adapter_info.device,
+            device_type: format!("{:?}", adapter_info.device_type),
+            driver: adapter_info.driver,
+            driver_info: adapter_info.driver_info,
+            backend: format!("{:?}", adapter_info.backend),
+            texture_format: format!("{:?}", config.format),
+            shader_srgb_conversion: !config.format.is_srgb(),
+            present_mode: has_surface.then(|| format!("{:?}", config.present_mode)),
+            limits: RendererLimits {
+                max_texture_dimension_2d: limits.max_texture_dimension_2d,
+                max_buffer_size: limits.max_buffer_size,
+                max_vertex_buffers: limits.max_vertex_buffers,
+                max_bind_groups: limits.max_bind_groups,
+                max_uniform_buffer_binding_size: limits.max_uniform_buffer_binding_size,
+                max_storage_buffer_binding_size: limits.max_storage_buffer_binding_size,
+            },
+        }
+    }
+}
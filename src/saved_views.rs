@@ -0,0 +1,127 @@
+// src/saved_views.rs
+// 命名视图：保存分析时的上下文（相机、时刻、高亮、选中节点和图层开关），之后一次性恢复。
+// 节点以 element_id 保存，因此重新加载同一拓扑后仍然有效；exportViews / importViews 供宿主页面持久化。
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::State;
+use crate::notifications::TimeChangeReason;
+use crate::settings::is_positive_finite;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedView {
+    pub camera_x: f32,
+    pub camera_y: f32,
+    pub zoom: f32,
+    pub time: f64,
+    #[serde(default)]
+    pub highlight_service_ids: Option<Vec<i32>>,
+    /// highlightPathBetween 的路径（节点 ID）
+    #[serde(default)]
+    pub highlighted_path: Option<Vec<String>>,
+    #[serde(default)]
+    pub selected_node_id: Option<String>,
+    #[serde(default)]
+    pub layers: ViewLayers,
+}
+
+/// 可选图层的开关
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewLayers {
+    pub capacity_bars: bool,
+    pub heat_trail: bool,
+    pub heat_trail_window: Option<f64>,
+    pub flow_animation: bool,
+}
+
+impl SavedView {
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn validate(&self) -> Result<(), String> {
+        if !is_positive_finite(self.zoom) {
+            return Err(format!("View zoom must be positive, got {}", self.zoom));
+        }
+        if !self.camera_x.is_finite() || !self.camera_y.is_finite() || !self.time.is_finite() {
+            return Err("View camera position and time must be finite".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl State {
+    /// 当前的相机、时刻、高亮和图层开关
+    pub fn current_view(&self) -> SavedView {
+        SavedView {
+            camera_x: self.camera.position.x,
+            camera_y: self.camera.position.y,
+            zoom: self.camera.zoom,
+            time: self.current_time_selection,
+            highlight_service_ids: self.highlight_service_id_list.clone(),
+            highlighted_path: self.highlighted_path.as_ref().map(|path| {
+                path.iter().map(|&idx| self.all_elements[idx].element_id.clone()).collect()
+            }),
+            selected_node_id: self.selected_node_idx.map(|idx| self.all_elements[idx].element_id.clone()),
+            layers: ViewLayers {
+                capacity_bars: self.show_capacity_bars,
+                heat_trail: self.heat_trail_enabled,
+                heat_trail_window: self.heat_trail_window,
+                flow_animation: self.flow_animation_enabled,
+            },
+        }
+    }
+
+    /// 以 name 保存当前视图，同名视图被覆盖
+    pub fn save_view(&mut self, name: String) {
+        let view = self.current_view();
+        log::info!("Saved view '{}' at time {}.", name, view.time);
+        self.saved_views.insert(name, view);
+    }
+
+    /// 恢复视图：所有状态在同一次命令处理中设置，只重新生成一次几何
+    pub fn restore_view(&mut self, name: &str) -> Result<(), String> {
+        let view = self.saved_views.get(name).cloned().ok_or_else(|| format!("Unknown view '{}'", name))?;
+        self.apply_view(view, true);
+        log::info!("Restored view '{}'.", name);
+        Ok(())
+    }
+
+    /// 应用视图，当前拓扑中不存在的节点被忽略。相机直接移动到保存的中心点；
+    /// animate_zoom 为 true 时缩放平滑过渡，否则直接设置
+    pub fn apply_view(&mut self, view: SavedView, animate_zoom: bool) {
+        self.camera.stop_zoom_animation();
+        self.camera.position = Vec2::new(view.camera_x, view.camera_y);
+        if animate_zoom {
+            let screen_center = self.camera.viewport_size / 2.0;
+            self.camera.zoom_smoothly(view.zoom / self.camera.zoom, screen_center);
+        } else {
+            self.camera.zoom = view.zoom;
+        }
+        self.camera_needs_update = true;
+
+        self.set_time_selection(view.time, TimeChangeReason::View);
+        self.highlight_service_id_list = view.highlight_service_ids;
+        self.highlight_multi_select = false;
+
+        self.highlighted_path = view.highlighted_path.and_then(|path| {
+            let indices: Option<Vec<usize>> = path.iter().map(|node_id| self.node_id_to_idx.get(node_id).copied()).collect();
+            if indices.is_none() {
+                log::warn!("View highlights a path through nodes that are not in the topology; path highlight cleared.");
+            }
+            indices
+        });
+        self.selected_node_idx = view.selected_node_id.and_then(|node_id| self.node_id_to_idx.get(&node_id).copied());
+        self.selection_needs_update = true;
+
+        self.set_capacity_bars(view.layers.capacity_bars);
+        self.heat_trail_enabled = view.layers.heat_trail;
+        self.heat_trail_window = view.layers.heat_trail_window;
+        self.set_flow_animation(view.layers.flow_animation);
+        self.topology_needs_update = true;
+    }
+
+    pub fn delete_view(&mut self, name: &str) {
+        if self.saved_views.remove(name).is_none() {
+            log::warn!("Cannot delete view '{}': no such view.", name);
+        }
+    }
+}
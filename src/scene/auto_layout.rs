@@ -0,0 +1,111 @@
+// src/scene/auto_layout.rs
+// 为缺少位置信息的节点补充位置：优先放在已定位邻居附近，否则放到已定位节点右侧的网格中。
+// 结果是确定性的（只依赖输入顺序），同一份数据每次加载得到相同的布局。
+use std::collections::HashMap;
+use glam::Vec2;
+
+use super::connection::ConnectionData;
+
+/// 相邻节点之间的期望间距（拓扑坐标单位）
+const AUTO_LAYOUT_SPACING: f32 = 100.0;
+/// 黄金角，用于让同一邻居附近的多个节点均匀地散开
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+/// 填充 `locations` 中为 None 的项，返回被自动放置的节点数。
+/// `element_ids` 与 `locations` 一一对应，坐标约定与 Metadata.location 相同。
+pub fn fill_missing_locations(element_ids: &[String], locations: &mut [Option<Vec2>], connections: &[ConnectionData]) -> usize {
+    let id_to_idx: HashMap<&str, usize> = element_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), i))
+        .collect();
+    let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); element_ids.len()];
+    for connection in connections {
+        if let (Some(&a), Some(&b)) = (id_to_idx.get(connection.from_node.as_str()), id_to_idx.get(connection.to_node.as_str())) {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+        }
+    }
+
+    let mut placed_count = 0;
+    // 逐轮放置：每轮只使用上一轮结束时已有位置的邻居，这样未定位节点组成的链会从已定位端逐步展开
+    loop {
+        let snapshot: Vec<Option<Vec2>> = locations.to_vec();
+        let mut progressed = false;
+        for idx in 0..locations.len() {
+            if snapshot[idx].is_some() {
+                continue;
+            }
+            let located_neighbors: Vec<Vec2> = neighbors[idx].iter().filter_map(|&n| snapshot[n]).collect();
+            if located_neighbors.is_empty() {
+                continue;
+            }
+            let centroid = located_neighbors.iter().copied().sum::<Vec2>() / located_neighbors.len() as f32;
+            let offset = Vec2::from_angle(placed_count as f32 * GOLDEN_ANGLE) * AUTO_LAYOUT_SPACING;
+            locations[idx] = Some(centroid + offset);
+            placed_count += 1;
+            progressed = true;
+        }
+        if !progressed {
+            break;
+        }
+    }
+
+    // 与任何已定位节点都不连通的节点：放到已定位区域右侧的网格中
+    let remaining: Vec<usize> = (0..locations.len()).filter(|&i| locations[i].is_none()).collect();
+    if !remaining.is_empty() {
+        let origin = locations
+            .iter()
+            .flatten()
+            .fold(None, |acc: Option<(f32, f32)>, p| Some(acc.map_or((p.x, p.y), |(max_x, min_y)| (max_x.max(p.x), min_y.min(p.y)))))
+            .map_or(Vec2::ZERO, |(max_x, min_y)| Vec2::new(max_x + AUTO_LAYOUT_SPACING, min_y));
+        let columns = (remaining.len() as f32).sqrt().ceil() as usize;
+        for (i, idx) in remaining.into_iter().enumerate() {
+            let cell = Vec2::new((i % columns) as f32, (i / columns) as f32);
+            locations[idx] = Some(origin + cell * AUTO_LAYOUT_SPACING);
+            placed_count += 1;
+        }
+    }
+
+    placed_count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::network::{parse_topology_json, TopologyStructureData};
+
+    #[test]
+    fn places_unlocated_elements_next_to_neighbors() {
+        let element = |id: &str, metadata: &str| format!(
+            r#"{{"name": "{id}", "type": "Roadm", "type_variety": "default", "metadata": {metadata}, "element_id": "{id}"}}"#
+        );
+        let connection = |from: &str, to: &str| format!(r#"{{"from_node": "{from}", "to_node": "{to}", "connection_id": "{from}-{to}"}}"#);
+        let json = format!(
+            r#"{{"elements": [{}, {}, {}, {}, {}], "connections": [{}, {}, {}]}}"#,
+            element("A", r#"{"location": {"x": 0.0, "y": 0.0}}"#),
+            element("B", r#"{"location": {"x": 300.5, "y": -12.25}}"#),
+            element("C", "{}"),
+            element("D", r#"{"location": null}"#),
+            element("E", "{}"),
+            connection("A", "B"),
+            connection("C", "A"),
+            connection("D", "C"),
+        );
+        let structure: TopologyStructureData = parse_topology_json(&json).unwrap();
+        let element_ids: Vec<String> = structure.elements.iter().map(|element| element.element_id.clone()).collect();
+        let mut locations: Vec<Option<Vec2>> = structure.elements
+            .iter()
+            .map(|element| element.metadata.location.as_ref().map(|location| Vec2::new(location.x, location.y)))
+            .collect();
+
+        assert_eq!(fill_missing_locations(&element_ids, &mut locations, &structure.connections), 3);
+        let locations: Vec<Vec2> = locations.into_iter().map(Option::unwrap).collect();
+        assert_eq!(locations[0], Vec2::new(0.0, 0.0));
+        assert_eq!(locations[1], Vec2::new(300.5, -12.25));
+        assert!((locations[2].distance(locations[0]) - AUTO_LAYOUT_SPACING).abs() < 1e-3);
+        assert!((locations[3].distance(locations[2]) - AUTO_LAYOUT_SPACING).abs() < 1e-3);
+        // 不连通的节点放在已定位区域右侧
+        assert!(locations[4].x > 300.5);
+    }
+}
@@ -0,0 +1,66 @@
+// src/scene/blocked_demand.rs
+// 被阻塞的需求：仿真器记录的、因没有可用波长而未能建立的连接请求（FullTopologyData::blocked_demands）。
+// 没有路径和波长，只有尝试的时刻、两端节点和码率；在时间窗口内以红色虚线直接连接源和目的节点绘制。
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockedDemand {
+    pub timestamp: f64,
+    pub source_id: String,
+    pub destination_id: String,
+    pub bit_rate: f32,
+}
+
+/// 按 timestamp 排序的需求中时间戳在 [start, end] 内的部分
+pub fn blocked_demands_in_range(demands: &[BlockedDemand], start: f64, end: f64) -> &[BlockedDemand] {
+    let first = demands.partition_point(|demand| demand.timestamp < start);
+    let last = demands.partition_point(|demand| demand.timestamp <= end);
+    &demands[first..last.max(first)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demands(timestamps: &[f64]) -> Vec<BlockedDemand> {
+        timestamps
+            .iter()
+            .map(|&timestamp| BlockedDemand { timestamp, source_id: "A".into(), destination_id: "B".into(), bit_rate: 100.0 })
+            .collect()
+    }
+
+    fn timestamps(demands: &[BlockedDemand]) -> Vec<f64> {
+        demands.iter().map(|demand| demand.timestamp).collect()
+    }
+
+    #[test]
+    fn empty_input_gives_empty_range() {
+        assert!(blocked_demands_in_range(&[], 0.0, 10.0).is_empty());
+        assert!(blocked_demands_in_range(&[], f64::NEG_INFINITY, f64::INFINITY).is_empty());
+    }
+
+    /// 区间两端都包含在内（包括端点上重复的时间戳）；start > end 或区间在所有需求之外时为空
+    #[test]
+    fn range_bounds_are_inclusive() {
+        let sorted = demands(&[1.0, 2.0, 2.0, 3.0, 5.0, 5.0, 8.0]);
+        assert_eq!(timestamps(blocked_demands_in_range(&sorted, 2.0, 5.0)), vec![2.0, 2.0, 3.0, 5.0, 5.0]);
+        assert_eq!(timestamps(blocked_demands_in_range(&sorted, 2.5, 4.9)), vec![3.0]);
+        assert_eq!(timestamps(blocked_demands_in_range(&sorted, 5.0, 5.0)), vec![5.0, 5.0]);
+        assert_eq!(timestamps(blocked_demands_in_range(&sorted, f64::NEG_INFINITY, 1.0)), vec![1.0]);
+        assert_eq!(blocked_demands_in_range(&sorted, 0.0, 100.0).len(), sorted.len());
+        assert!(blocked_demands_in_range(&sorted, 5.0, 2.0).is_empty());
+        assert!(blocked_demands_in_range(&sorted, 8.5, 100.0).is_empty());
+        assert!(blocked_demands_in_range(&sorted, -10.0, 0.5).is_empty());
+    }
+
+    /// 查询要求按时间排序的输入：与 State::set_blocked_demands 相同先排序，结果与逐条筛选一致
+    #[test]
+    fn unsorted_input_matches_filter_after_sorting() {
+        let mut unsorted = demands(&[7.0, -1.0, 3.0, 3.0, 10.0, 0.0, 4.5]);
+        unsorted.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        for (start, end) in [(0.0, 4.5), (3.0, 3.0), (-5.0, 100.0), (4.6, 6.9)] {
+            let expected: Vec<f64> = timestamps(&unsorted).into_iter().filter(|&t| (start..=end).contains(&t)).collect();
+            assert_eq!(timestamps(blocked_demands_in_range(&unsorted, start, end)), expected, "[{start}, {end}]");
+        }
+    }
+}
@@ -1,13 +1,16 @@
-use serde::Deserialize;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
 use super::service::ServiceData;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::settings::{is_positive_finite, ServiceIntervalSemantics};
 
 
 // ReallocationDetails "inherits" DefragService in Python.
 // In Rust, we represent this with composition. The #[serde(flatten)] attribute
 // tells serde to pull all fields from the `service` field into this struct during
 // deserialization, making it look like a single flat object in the JSON.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ReallocationDetails {
     pub defrag_service_id: i32,
     #[serde(flatten)]
@@ -22,9 +25,9 @@ impl From<ReallocationDetails> for ServiceData {
     }
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ReleaseExpiredDetails {
-    pub departure_time: f32,
+    pub departure_time: f64,
 }
 
 // This is the core of the solution. It mirrors the Pydantic tagged union.
@@ -32,36 +35,182 @@ pub struct ReleaseExpiredDetails {
 // in the JSON to decide which enum variant to create.
 // #[serde(rename_all = "SCREAMING_SNAKE_CASE")] handles the naming convention
 // (e.g., "ALLOCATION" in JSON maps to the `Allocation` variant in Rust).
-#[derive(Deserialize, Debug, Clone)]
+// 反序列化见下方手写的 Deserialize：未知的 event_type 解析为 Unknown，而不是让整个拓扑解析失败。
+#[derive(Serialize, Debug, Clone)]
 #[serde(tag = "event_type")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AnyEvent {
     Allocation {
-        timestamp: f32,
+        timestamp: f64,
         service_id: i32,
         // In this case, AllocationDetails is just a DefragService
         details: ServiceData,
     },
     ReleaseExpired {
-        timestamp: f32,
+        timestamp: f64,
         service_id: i32,
         details: ReleaseExpiredDetails,
     },
     Reallocation {
-        timestamp: f32,
+        timestamp: f64,
         service_id: i32,
         details: ReallocationDetails,
     },
+    /// 本版本不认识的 event_type（例如模拟器新增的事件类型）。raw 为原始事件对象，
+    /// 重建状态和生成几何时跳过，计入验证报告，并可通过 getEventsInRange 查看。
+    /// 序列化时原样输出 raw，因此导出后可以重新加载
+    #[serde(untagged, serialize_with = "serialize_raw_event")]
+    Unknown {
+        timestamp: f64,
+        raw: serde_json::Value,
+    },
+}
+
+fn serialize_raw_event<S: serde::Serializer>(_timestamp: &f64, raw: &serde_json::Value, serializer: S) -> Result<S::Ok, S::Error> {
+    raw.serialize(serializer)
+}
+
+/// AnyEvent 中已知的事件类型，使用 serde 的内部标签枚举解析
+#[derive(Deserialize)]
+#[serde(tag = "event_type")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum TaggedEvent {
+    Allocation { timestamp: f64, service_id: i32, details: ServiceData },
+    ReleaseExpired { timestamp: f64, service_id: i32, details: ReleaseExpiredDetails },
+    Reallocation { timestamp: f64, service_id: i32, details: ReallocationDetails },
+}
+
+impl From<TaggedEvent> for AnyEvent {
+    fn from(event: TaggedEvent) -> Self {
+        match event {
+            TaggedEvent::Allocation { timestamp, service_id, details } => AnyEvent::Allocation { timestamp, service_id, details },
+            TaggedEvent::ReleaseExpired { timestamp, service_id, details } => AnyEvent::ReleaseExpired { timestamp, service_id, details },
+            TaggedEvent::Reallocation { timestamp, service_id, details } => AnyEvent::Reallocation { timestamp, service_id, details },
+        }
+    }
+}
+
+const KNOWN_EVENT_TYPES: &[&str] = &["ALLOCATION", "RELEASE_EXPIRED", "REALLOCATION"];
+
+/// 严格模式：未知的 event_type 直接解析失败（引入 Unknown 之前的行为）
+static STRICT_EVENT_PARSING: AtomicBool = AtomicBool::new(false);
+
+/// 切换严格解析模式，对之后的所有拓扑解析（JSON 和 MessagePack）生效
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub fn set_strict_event_parsing(strict: bool) {
+    STRICT_EVENT_PARSING.store(strict, Ordering::Relaxed);
+}
+
+// 已知类型的事件与派生的内部标签枚举一样先缓冲整个事件再解析，错误信息相同；
+// 未知类型只要求有数值类型的 timestamp（用于排序和按时间查询）
+impl<'de> Deserialize<'de> for AnyEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = serde_json::Value::deserialize(deserializer)?;
+        let event_type = match raw.get("event_type") {
+            Some(serde_json::Value::String(event_type)) => event_type.as_str(),
+            Some(other) => return Err(D::Error::custom(format!("event_type must be a string, got {}", other))),
+            None => return Err(D::Error::missing_field("event_type")),
+        };
+        if KNOWN_EVENT_TYPES.contains(&event_type) {
+            return TaggedEvent::deserialize(raw).map(AnyEvent::from).map_err(D::Error::custom);
+        }
+        if STRICT_EVENT_PARSING.load(Ordering::Relaxed) {
+            return Err(D::Error::unknown_variant(event_type, KNOWN_EVENT_TYPES));
+        }
+        match raw.get("timestamp").and_then(serde_json::Value::as_f64) {
+            Some(timestamp) => Ok(AnyEvent::Unknown { timestamp, raw }),
+            None => Err(D::Error::custom(format!("event of unknown type '{}' has no numeric timestamp", event_type))),
+        }
+    }
+}
+
+/// 已知类型事件中除 event_type 以外的字段，只用于定位解析错误（见 locate_event_error）
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct EventFields<D> {
+    timestamp: f64,
+    service_id: i32,
+    details: D,
+}
+
+/// 已知类型的事件解析失败时，serde 先缓冲整个事件，错误路径只能精确到事件本身。
+/// 按 event_type 把事件重新解析为对应变体的字段，返回事件内部出错字段的路径（例如 `details.wavelength`）。
+/// 重分配事件的 details 中服务字段是 flatten 的，先按 ServiceData 解析以得到服务字段的精确路径
+pub fn locate_event_error(raw: &serde_json::Value) -> Option<String> {
+    fn locate<D: for<'de> Deserialize<'de>>(raw: &serde_json::Value) -> Option<String> {
+        serde_path_to_error::deserialize::<_, EventFields<D>>(raw).err()
+            .map(|e| e.path().to_string())
+            .filter(|path| path != ".") // 事件本身的错误（例如缺少 timestamp），没有更精确的位置
+    }
+    match raw.get("event_type")?.as_str()? {
+        "ALLOCATION" => locate::<ServiceData>(raw),
+        "RELEASE_EXPIRED" => locate::<ReleaseExpiredDetails>(raw),
+        "REALLOCATION" => locate::<ServiceData>(raw).or_else(|| locate::<ReallocationDetails>(raw)),
+        _ => None,
+    }
+}
+
+/// 事件类型，名称与 JSON 中的 event_type 一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Allocation,
+    ReleaseExpired,
+    Reallocation,
+    /// 任何未知类型的事件（AnyEvent::Unknown）
+    Unknown,
+}
+
+impl std::str::FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ALLOCATION" => Ok(EventKind::Allocation),
+            "RELEASE_EXPIRED" => Ok(EventKind::ReleaseExpired),
+            "REALLOCATION" => Ok(EventKind::Reallocation),
+            "UNKNOWN" => Ok(EventKind::Unknown),
+            other => Err(format!("Unknown event type '{}', expected \"ALLOCATION\", \"RELEASE_EXPIRED\", \"REALLOCATION\" or \"UNKNOWN\"", other)),
+        }
+    }
 }
 
 // Helper function to get the timestamp from any event variant without
 // needing to write a full match statement every time.
 impl AnyEvent {
-    pub fn timestamp(&self) -> f32 {
+    pub fn timestamp(&self) -> f64 {
         match self {
             AnyEvent::Allocation { timestamp, .. } => *timestamp,
             AnyEvent::ReleaseExpired { timestamp, .. } => *timestamp,
             AnyEvent::Reallocation { timestamp, .. } => *timestamp,
+            AnyEvent::Unknown { timestamp, .. } => *timestamp,
+        }
+    }
+
+    pub fn kind(&self) -> EventKind {
+        match self {
+            AnyEvent::Allocation { .. } => EventKind::Allocation,
+            AnyEvent::ReleaseExpired { .. } => EventKind::ReleaseExpired,
+            AnyEvent::Reallocation { .. } => EventKind::Reallocation,
+            AnyEvent::Unknown { .. } => EventKind::Unknown,
+        }
+    }
+
+    /// 事件作用的服务（Unknown 没有）
+    pub fn service_id(&self) -> Option<i32> {
+        match self {
+            AnyEvent::Allocation { service_id, .. } => Some(*service_id),
+            AnyEvent::ReleaseExpired { service_id, .. } => Some(*service_id),
+            AnyEvent::Reallocation { service_id, .. } => Some(*service_id),
+            AnyEvent::Unknown { .. } => None,
+        }
+    }
+
+    /// 事件携带的服务数据（ReleaseExpired 和 Unknown 没有）
+    pub fn service(&self) -> Option<&ServiceData> {
+        match self {
+            AnyEvent::Allocation { details, .. } => Some(details),
+            AnyEvent::ReleaseExpired { .. } | AnyEvent::Unknown { .. } => None,
+            AnyEvent::Reallocation { details, .. } => Some(&details.service),
         }
     }
 }
@@ -69,9 +218,11 @@ impl AnyEvent {
 
 /// Reconstructs the service dictionary state at a specific target time
 /// by replaying events from a timeline.
+/// `semantics` decides whether a release exactly at `target_time` has already taken effect.
 pub fn reconstruct_state_at_time(
     timeline_events: &[AnyEvent],
-    target_time: f32,
+    target_time: f64,
+    semantics: ServiceIntervalSemantics,
 ) -> HashMap<i32, ServiceData> {
     // We initialize our state map. The key is the service ID.
     let mut reconstructed_service_dict: HashMap<i32, ServiceData> = HashMap::new();
@@ -92,7 +243,10 @@ pub fn reconstruct_state_at_time(
                 // We clone `details` because the map takes ownership.
                 reconstructed_service_dict.insert(*service_id, details.clone());
             }
-            AnyEvent::ReleaseExpired { service_id, .. } => {
+            AnyEvent::ReleaseExpired { timestamp, service_id, .. } => {
+                if !semantics.release_applies(*timestamp, target_time) {
+                    continue;
+                }
                 // Remove the service from the map.
                 reconstructed_service_dict.remove(service_id);
             }
@@ -102,8 +256,462 @@ pub fn reconstruct_state_at_time(
                 let updated_service: ServiceData = details.clone().into();
                 reconstructed_service_dict.insert(*service_id, updated_service);
             }
+            AnyEvent::Unknown { .. } => {}
         }
     }
 
     reconstructed_service_dict
 }
+
+/// 由碎片整理服务 `defrag_service_id` 触发的重分配事件（按时间顺序），
+/// 包括被移动的服务又作为碎片整理服务触发的重分配（传递闭包）
+pub fn reallocation_chain(timeline_events: &[AnyEvent], defrag_service_id: i32) -> Vec<(i32, &ReallocationDetails)> {
+    let mut causes: HashSet<i32> = HashSet::from([defrag_service_id]);
+    let mut chain = Vec::new();
+    // 重分配只能由此前已经出现在链中的服务触发，按时间顺序遍历一次即可
+    for event in timeline_events {
+        if let AnyEvent::Reallocation { service_id, details, .. } = event && causes.contains(&details.defrag_service_id) {
+            causes.insert(*service_id);
+            chain.push((*service_id, details));
+        }
+    }
+    chain
+}
+
+/// 高亮碎片整理服务 `defrag_service_id` 时的服务 ID 列表（第一个为它本身，include_moved 时其后是被它移动的服务）
+/// 和要跳到的时刻（相关服务中最早的 arrival_time）。没有分配事件、只作为重分配的 defrag_service_id 出现的服务
+/// 以第一条由它触发的重分配的 arrival_time 为起点。两者都没有时返回 None
+pub fn defrag_highlight(timeline_events: &[AnyEvent], defrag_service_id: i32, include_moved: bool) -> Option<(Vec<i32>, f64)> {
+    let chain = reallocation_chain(timeline_events, defrag_service_id);
+    let allocation_arrival = timeline_events.iter().find_map(|event| match event {
+        AnyEvent::Allocation { service_id, details, .. } if *service_id == defrag_service_id => Some(details.arrival_time),
+        _ => None,
+    });
+    let first_reallocation_arrival = chain.iter()
+        .find(|(_, details)| details.defrag_service_id == defrag_service_id)
+        .map(|(_, details)| details.service.arrival_time);
+    let mut arrival_time = allocation_arrival.or(first_reallocation_arrival)?;
+
+    let mut service_ids = vec![defrag_service_id];
+    if include_moved {
+        for (moved_service_id, details) in chain {
+            if !service_ids.contains(&moved_service_id) {
+                service_ids.push(moved_service_id);
+            }
+            arrival_time = arrival_time.min(details.service.arrival_time);
+        }
+    }
+    Some((service_ids, arrival_time))
+}
+
+/// 批量统计多个时刻的活跃服务数，结果与 `times` 一一对应。
+/// 按时间排序后增量回放事件，避免对每个采样时刻从头重建。
+pub fn count_active_services_at_times(
+    timeline_events: &[AnyEvent],
+    times: &[f64],
+    semantics: ServiceIntervalSemantics,
+) -> Vec<u32> {
+    let mut order: Vec<usize> = (0..times.len()).collect();
+    order.sort_by(|&a, &b| times[a].total_cmp(&times[b]));
+
+    let mut counts = vec![0; times.len()];
+    let mut services: HashMap<i32, &ServiceData> = HashMap::new();
+    let mut next_event = 0;
+    for idx in order {
+        let time = times[idx];
+        // 时间戳严格早于 time 的事件在两种语义下都已生效，可以直接推进
+        while let Some(event) = timeline_events.get(next_event).filter(|event| event.timestamp() < time) {
+            next_event += 1;
+            let Some(service_id) = event.service_id() else {
+                continue;
+            };
+            match event.service() {
+                Some(service) => { services.insert(service_id, service); }
+                None => { services.remove(&service_id); }
+            }
+        }
+
+        // 恰好在 time 的事件只对本次计数生效（释放事件是否生效取决于语义），不推进游标
+        let mut overlay: HashMap<i32, Option<&ServiceData>> = HashMap::new();
+        for event in timeline_events[next_event..].iter().take_while(|event| event.timestamp() == time) {
+            let Some(service_id) = event.service_id() else {
+                continue;
+            };
+            match event.service() {
+                Some(service) => { overlay.insert(service_id, Some(service)); }
+                None => {
+                    if semantics.release_applies(time, time) {
+                        overlay.insert(service_id, None);
+                    }
+                }
+            }
+        }
+
+        counts[idx] = services
+            .iter()
+            .filter(|(service_id, _)| !overlay.contains_key(service_id))
+            .map(|(_, service)| *service)
+            .chain(overlay.values().flatten().copied())
+            .filter(|service| semantics.contains(service.arrival_time, service.departure_time, time))
+            .count() as u32;
+    }
+    counts
+}
+
+/// 检查点之间的最少事件数；事件很多时按 MAX_EVENT_CHECKPOINTS 放大间隔，限制内存占用
+const MIN_CHECKPOINT_INTERVAL: usize = 4096;
+const MAX_EVENT_CHECKPOINTS: usize = 64;
+
+/// 事件回放的检查点：每隔固定数量的事件保存一次活跃的服务（服务 ID → 携带其当前数据的事件下标），
+/// 使 time_averaged_link_occupancy 从窗口开始前最近的检查点回放，而不是从第一个事件开始。
+/// 下标只对构建时的事件列表有效，generation 用来判断事件是否已被替换（见 State::events_generation）
+#[derive(Debug, Default)]
+pub struct EventCheckpoints {
+    generation: u64,
+    /// (event_index, services)：回放完 events[..event_index] 之后的状态，按 event_index 递增
+    checkpoints: Vec<(usize, HashMap<i32, usize>)>,
+}
+
+impl EventCheckpoints {
+    pub fn new(timeline_events: &[AnyEvent], generation: u64) -> Self {
+        let interval = MIN_CHECKPOINT_INTERVAL.max(timeline_events.len().div_ceil(MAX_EVENT_CHECKPOINTS));
+        Self::with_interval(timeline_events, generation, interval)
+    }
+
+    fn with_interval(timeline_events: &[AnyEvent], generation: u64, interval: usize) -> Self {
+        let mut checkpoints = Vec::new();
+        let mut services: HashMap<i32, usize> = HashMap::new();
+        for (event_index, event) in timeline_events.iter().enumerate() {
+            if event_index > 0 && event_index % interval == 0 {
+                checkpoints.push((event_index, services.clone()));
+            }
+            let Some(service_id) = event.service_id() else {
+                continue;
+            };
+            match event.service() {
+                Some(_) => { services.insert(service_id, event_index); }
+                None => { services.remove(&service_id); }
+            }
+        }
+        Self { generation, checkpoints }
+    }
+
+    pub fn matches(&self, generation: u64) -> bool {
+        self.generation == generation
+    }
+
+    /// 与 generation 对应时返回自身，已过期时返回没有检查点的空表（从第一个事件开始回放）
+    pub fn for_generation(&self, generation: u64) -> &Self {
+        static NO_CHECKPOINTS: EventCheckpoints = EventCheckpoints { generation: 0, checkpoints: Vec::new() };
+        if self.matches(generation) { self } else { &NO_CHECKPOINTS }
+    }
+
+    /// event_index 之前（含）最近的检查点
+    fn nearest(&self, event_index: usize) -> Option<&(usize, HashMap<i32, usize>)> {
+        let count = self.checkpoints.partition_point(|(checkpoint_index, _)| *checkpoint_index <= event_index);
+        count.checked_sub(1).map(|idx| &self.checkpoints[idx])
+    }
+}
+
+/// 在时间窗口 `[start, end]` 内按时间加权平均每条链路上的活跃服务数（即平均占用的波长数）。
+/// `link_key` 把服务路径中的第几跳映射为链路键（返回 None 的跳被忽略）。
+/// 回放到 start（从 checkpoints 中最近的检查点开始）之后只遍历窗口内的事件：每个服务在离开（释放或被重分配替换）或窗口结束时，
+/// 按它在窗口内的存在时间与 [arrival_time, departure_time) 的交集累加到路径上的每条链路。
+/// 区间端点的开闭只影响零测集，因此与 ServiceIntervalSemantics 无关。窗口长度不为正时返回空表。
+pub fn time_averaged_link_occupancy<K: std::hash::Hash + Eq>(
+    timeline_events: &[AnyEvent],
+    checkpoints: &EventCheckpoints,
+    start: f64,
+    end: f64,
+    link_key: impl Fn(&ServiceData, usize) -> Option<K>,
+) -> HashMap<K, f32> {
+    let mut totals: HashMap<K, f64> = HashMap::new();
+    let window = end - start;
+    if !is_positive_finite(window) {
+        return HashMap::new();
+    }
+
+    let mut add_presence = |service: &ServiceData, from: f64, to: f64| {
+        let overlap = to.min(service.departure_time) - from.max(service.arrival_time);
+        if overlap <= 0.0 {
+            return;
+        }
+        for hop in 0..service.path.len().saturating_sub(1) {
+            if let Some(key) = link_key(service, hop) {
+                *totals.entry(key).or_insert(0.0) += overlap;
+            }
+        }
+    };
+
+    // 窗口开始时的状态，每个服务从 start 起计时
+    let first_in_window = timeline_events.partition_point(|event| event.timestamp() <= start);
+    let (replay_from, mut present): (usize, HashMap<i32, (&ServiceData, f64)>) = match checkpoints.nearest(first_in_window) {
+        Some((checkpoint_index, services)) => {
+            let present = services.iter()
+                .filter_map(|(&service_id, &event_index)| Some((service_id, (timeline_events.get(event_index)?.service()?, start))))
+                .collect();
+            (*checkpoint_index, present)
+        }
+        None => (0, HashMap::new()),
+    };
+    for event in &timeline_events[replay_from..first_in_window] {
+        let Some(service_id) = event.service_id() else {
+            continue;
+        };
+        match event.service() {
+            Some(service) => { present.insert(service_id, (service, start)); }
+            None => { present.remove(&service_id); }
+        }
+    }
+
+    for event in timeline_events[first_in_window..].iter().take_while(|event| event.timestamp() < end) {
+        let Some(service_id) = event.service_id() else {
+            continue;
+        };
+        let time = event.timestamp();
+        if let Some((service, since)) = present.remove(&service_id) {
+            add_presence(service, since, time);
+        }
+        if let Some(service) = event.service() {
+            present.insert(service_id, (service, time));
+        }
+    }
+    for (service, since) in present.into_values() {
+        add_presence(service, since, end);
+    }
+
+    totals.into_iter().map(|(key, total)| (key, (total / window) as f32)).collect()
+}
+
+/// 两个时刻活跃服务的差异（按 service_id 匹配），见 compute_service_diff
+#[derive(Debug, Clone, Default)]
+pub struct ServiceDiff {
+    /// 比较的后一个时刻，差异只在该时刻显示
+    pub t2: f64,
+    /// 两个时刻都活跃，但波长或路径发生了变化
+    pub changed: HashSet<i32>,
+    /// 只在 t2 活跃
+    pub appeared: HashSet<i32>,
+    /// 只在 t1 活跃，保留 t1 时的服务数据用于绘制残影
+    pub departed: Vec<ServiceData>,
+    pub unchanged_count: usize,
+}
+
+/// setDiffTimes 的结果：各类服务的数量
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ServiceDiffCounts {
+    pub unchanged: usize,
+    pub changed: usize,
+    pub appeared: usize,
+    pub departed: usize,
+}
+
+impl ServiceDiff {
+    pub fn counts(&self) -> ServiceDiffCounts {
+        ServiceDiffCounts {
+            unchanged: self.unchanged_count,
+            changed: self.changed.len(),
+            appeared: self.appeared.len(),
+            departed: self.departed.len(),
+        }
+    }
+}
+
+/// 比较 t1 和 t2 时刻的活跃服务（两次重建的结果）。只比较 path 和 wavelength，到达、离开时间的变化不计入
+pub fn compute_service_diff(services_t1: Vec<ServiceData>, t2: f64, services_t2: &[ServiceData]) -> ServiceDiff {
+    let mut remaining: HashMap<i32, ServiceData> = services_t1.into_iter().map(|service| (service.service_id, service)).collect();
+    let mut diff = ServiceDiff { t2, ..Default::default() };
+    for service in services_t2 {
+        match remaining.remove(&service.service_id) {
+            Some(before) if before.path == service.path && before.wavelength == service.wavelength => diff.unchanged_count += 1,
+            Some(_) => { diff.changed.insert(service.service_id); }
+            None => { diff.appeared.insert(service.service_id); }
+        }
+    }
+    diff.departed = remaining.into_values().collect();
+    diff.departed.sort_by_key(|service| service.service_id);
+    diff
+}
+
+/// getEventsInRange 的结果
+#[derive(Debug, Serialize)]
+pub struct EventRange {
+    pub events: Vec<AnyEvent>,
+    /// 匹配的事件超过上限，只返回了前 EVENT_RANGE_LIMIT 个
+    pub truncated: bool,
+}
+
+pub const EVENT_RANGE_LIMIT: usize = 10_000;
+
+/// 时间戳在 `[start, end]` 内的事件（按时间顺序），`kinds` 为 Some 时只保留这些类型。
+/// 与 reconstruct_state_at_time 一样假设事件已按时间排序，用二分查找定位起点。
+pub fn events_in_range(
+    timeline_events: &[AnyEvent],
+    start: f64,
+    end: f64,
+    kinds: Option<&[EventKind]>,
+) -> EventRange {
+    let first = timeline_events.partition_point(|event| event.timestamp() < start);
+    let mut matching = timeline_events[first..]
+        .iter()
+        .take_while(|event| event.timestamp() <= end)
+        .filter(|event| kinds.is_none_or(|kinds| kinds.contains(&event.kind())));
+
+    let events: Vec<AnyEvent> = matching.by_ref().take(EVENT_RANGE_LIMIT).cloned().collect();
+    let truncated = matching.next().is_some();
+    EventRange { events, truncated }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(service_id: i32, arrival_time: f64, departure_time: f64, path: &[&str]) -> ServiceData {
+        ServiceData {
+            service_id,
+            source_id: path.first().unwrap_or(&"").to_string(),
+            destination_id: path.last().unwrap_or(&"").to_string(),
+            arrival_time,
+            departure_time,
+            bit_rate: 100.0,
+            power: 0.0,
+            path: path.iter().map(|id| id.to_string()).collect(),
+            wavelength: 0,
+            snr_requirement: 10.0,
+            gsnr: 15.0,
+            utilization: f32::NAN,
+            path_indices: None,
+        }
+    }
+
+    fn allocation(service: ServiceData) -> AnyEvent {
+        AnyEvent::Allocation { timestamp: service.arrival_time, service_id: service.service_id, details: service }
+    }
+
+    fn release(service_id: i32, timestamp: f64) -> AnyEvent {
+        AnyEvent::ReleaseExpired { timestamp, service_id, details: ReleaseExpiredDetails { departure_time: timestamp } }
+    }
+
+    fn reallocation(timestamp: f64, defrag_service_id: i32, service: ServiceData) -> AnyEvent {
+        AnyEvent::Reallocation { timestamp, service_id: service.service_id, details: ReallocationDetails { defrag_service_id, service } }
+    }
+
+    fn active_ids(events: &[AnyEvent], time: f64, semantics: ServiceIntervalSemantics) -> Vec<i32> {
+        let mut ids: Vec<i32> = reconstruct_state_at_time(events, time, semantics).into_keys().collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn reconstruct_distinguishes_millisecond_steps_at_large_timestamps() {
+        let base = 1.0e9;
+        let events = vec![
+            allocation(service(1, base, base + 0.002, &["A", "B"])),
+            allocation(service(2, base + 0.001, base + 0.003, &["B", "C"])),
+            release(1, base + 0.002),
+            release(2, base + 0.003),
+        ];
+        let semantics = ServiceIntervalSemantics::HalfOpen;
+        assert_eq!(active_ids(&events, base - 0.001, semantics), Vec::<i32>::new());
+        assert_eq!(active_ids(&events, base, semantics), vec![1]);
+        assert_eq!(active_ids(&events, base + 0.001, semantics), vec![1, 2]);
+        assert_eq!(active_ids(&events, base + 0.002, semantics), vec![2]);
+        assert_eq!(active_ids(&events, base + 0.003, semantics), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn reconstruct_and_count_at_interval_boundaries() {
+        let events = vec![
+            allocation(service(1, 10.0, 20.0, &["A", "B"])),
+            allocation(service(2, 20.0, 30.0, &["B", "C"])),
+            release(1, 20.0),
+            release(2, 30.0),
+        ];
+        let times = [10.0, 20.0, 30.0];
+
+        let half_open = ServiceIntervalSemantics::HalfOpen;
+        assert_eq!(active_ids(&events, 10.0, half_open), vec![1]);
+        assert_eq!(active_ids(&events, 20.0, half_open), vec![2]);
+        assert_eq!(active_ids(&events, 30.0, half_open), Vec::<i32>::new());
+        assert_eq!(count_active_services_at_times(&events, &times, half_open), vec![1, 1, 0]);
+
+        let closed = ServiceIntervalSemantics::Closed;
+        assert_eq!(active_ids(&events, 10.0, closed), vec![1]);
+        assert_eq!(active_ids(&events, 20.0, closed), vec![1, 2]);
+        assert_eq!(active_ids(&events, 30.0, closed), vec![2]);
+        assert_eq!(count_active_services_at_times(&events, &times, closed), vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn link_occupancy_weights_presence_inside_window() {
+        let events = vec![
+            allocation(service(2, 0.0, 100.0, &["A", "B", "C"])),
+            allocation(service(3, 1.0, 4.0, &["C", "D"])),
+            release(3, 4.0),
+            // 跨过窗口开始：窗口内存在 5 秒
+            allocation(service(1, 5.0, 15.0, &["A", "B"])),
+            // 窗口内被重分配到另一条路径：原路径 2 秒，新路径 8 秒
+            reallocation(12.0, 99, service(2, 0.0, 100.0, &["A", "D"])),
+            release(1, 15.0),
+            allocation(service(4, 25.0, 30.0, &["B", "C"])),
+        ];
+        let occupancy = time_averaged_link_occupancy(&events, &EventCheckpoints::default(), 10.0, 20.0, |service, hop| {
+            Some((service.path[hop].clone(), service.path[hop + 1].clone()))
+        });
+        let at = |from: &str, to: &str| occupancy.get(&(from.to_string(), to.to_string())).copied();
+        assert_eq!(occupancy.len(), 3);
+        assert!((at("A", "B").unwrap() - 0.7).abs() < 1e-6);
+        assert!((at("B", "C").unwrap() - 0.2).abs() < 1e-6);
+        assert!((at("A", "D").unwrap() - 0.8).abs() < 1e-6);
+        assert_eq!(at("C", "D"), None);
+
+        assert!(time_averaged_link_occupancy(&events, &EventCheckpoints::default(), 20.0, 20.0, |_, hop| Some(hop)).is_empty());
+    }
+
+    /// 从检查点开始回放与从第一个事件开始回放的结果相同
+    #[test]
+    fn link_occupancy_from_checkpoints_matches_full_replay() {
+        let paths: [&[&str]; 3] = [&["A", "B"], &["B", "C"], &["A", "B", "C"]];
+        let mut events = Vec::new();
+        for service_id in 0..40 {
+            let arrival = service_id as f64;
+            let departure = arrival + 7.0 + (service_id % 5) as f64;
+            let path = paths[service_id as usize % paths.len()];
+            events.push(allocation(service(service_id, arrival, departure, path)));
+            if service_id % 4 == 0 {
+                events.push(reallocation(arrival + 2.5, 99, service(service_id, arrival, departure, paths[(service_id as usize + 1) % paths.len()])));
+            }
+            events.push(release(service_id, departure));
+        }
+        events.sort_by(|a, b| a.timestamp().total_cmp(&b.timestamp()));
+
+        let link_key = |service: &ServiceData, hop: usize| Some((service.path[hop].clone(), service.path[hop + 1].clone()));
+        let checkpoints = EventCheckpoints::with_interval(&events, 1, 3);
+        assert!(checkpoints.checkpoints.len() > 10);
+        for (start, end) in [(0.0, 5.0), (3.5, 9.0), (12.0, 30.0), (20.0, 21.0), (45.0, 60.0)] {
+            let full = time_averaged_link_occupancy(&events, &EventCheckpoints::default(), start, end, link_key);
+            let resumed = time_averaged_link_occupancy(&events, &checkpoints, start, end, link_key);
+            assert_eq!(full.len(), resumed.len(), "window [{start}, {end}]");
+            for (key, value) in &full {
+                assert!((resumed[key] - value).abs() < 1e-6, "window [{start}, {end}] link {key:?}");
+            }
+        }
+    }
+
+    /// 碎片整理服务本身没有分配事件时，以它触发的第一条重分配为起点
+    #[test]
+    fn defrag_highlight_without_allocation_uses_first_reallocation() {
+        let events = vec![
+            allocation(service(1, 2.0, 50.0, &["A", "B"])),
+            allocation(service(2, 4.0, 50.0, &["B", "C"])),
+            reallocation(10.0, 99, service(2, 4.0, 50.0, &["B", "D", "C"])),
+            reallocation(11.0, 2, service(1, 2.0, 50.0, &["A", "D", "B"])),
+            reallocation(12.0, 7, service(1, 2.0, 50.0, &["A", "B"])),
+        ];
+        assert_eq!(defrag_highlight(&events, 99, false), Some((vec![99], 4.0)));
+        assert_eq!(defrag_highlight(&events, 99, true), Some((vec![99, 2, 1], 2.0)));
+        // 有分配事件时仍以分配为准
+        assert_eq!(defrag_highlight(&events, 2, false), Some((vec![2], 4.0)));
+        assert_eq!(defrag_highlight(&events, 42, true), None);
+    }
+}
@@ -0,0 +1,548 @@
+// src/scene/dot.rs
+// 宽松的 Graphviz DOT 解析器：节点语句 -> ElementData，边语句 -> ConnectionData。
+// 节点的 pos="x,y" 属性作为位置（Graphviz 的 y 轴向上，转换为拓扑数据的 y 轴向下），
+// 没有 pos 的节点自动布局。子图会被展开、端口会被忽略，这些都记录为警告而不是错误。
+use std::collections::HashMap;
+use glam::Vec2;
+
+use super::auto_layout::fill_missing_locations;
+use super::connection::ConnectionData;
+use super::element::{ElementData, Location, Metadata};
+use super::network::FullTopologyData;
+
+/// 节点没有 type 属性时使用的 node_type
+const DEFAULT_DOT_NODE_TYPE: &str = "Node";
+
+#[derive(Debug)]
+pub struct DotImport {
+    pub topology: FullTopologyData,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Id(String),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Semicolon,
+    Comma,
+    Equals,
+    Colon,
+    Plus,
+    EdgeOp,
+}
+
+type Attributes = HashMap<String, String>;
+
+struct DotNode {
+    id: String,
+    attributes: Attributes,
+}
+
+struct DotEdge {
+    from: String,
+    to: String,
+    attributes: Attributes,
+}
+
+pub fn parse_dot(text: &str) -> Result<DotImport, String> {
+    let mut warnings = Vec::new();
+    let tokens = tokenize(text, &mut warnings);
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        warnings,
+        nodes: Vec::new(),
+        node_idx: HashMap::new(),
+        edges: Vec::new(),
+        warned_subgraph: false,
+        warned_port: false,
+    };
+    parser.parse_graph()?;
+    Ok(parser.into_import())
+}
+
+fn tokenize(text: &str, warnings: &mut Vec<String>) -> Vec<(Token, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    let mut at_line_start = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            at_line_start = true;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token_line = line;
+        let was_line_start = at_line_start;
+        at_line_start = false;
+
+        match c {
+            // 注释和 C 预处理器输出行
+            '#' if was_line_start => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                i += 2;
+            }
+            '"' => {
+                let mut value = String::new();
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    match (chars[i], chars.get(i + 1)) {
+                        ('\\', Some('"')) => { value.push('"'); i += 2; }
+                        ('\\', Some('\n')) => { line += 1; i += 2; } // 续行
+                        (ch, _) => {
+                            if ch == '\n' {
+                                line += 1;
+                            }
+                            value.push(ch);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1; // 结尾的引号
+                tokens.push((Token::Id(value), token_line));
+            }
+            '<' => {
+                // HTML 字符串，按尖括号嵌套深度读取
+                let mut depth = 0;
+                let start = i;
+                while i < chars.len() {
+                    match chars[i] {
+                        '<' => depth += 1,
+                        '>' => depth -= 1,
+                        '\n' => line += 1,
+                        _ => {}
+                    }
+                    i += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                tokens.push((Token::Id(chars[start + 1..i.saturating_sub(1).max(start + 1)].iter().collect()), token_line));
+            }
+            '-' if matches!(chars.get(i + 1), Some('-') | Some('>')) => {
+                tokens.push((Token::EdgeOp, token_line));
+                i += 2;
+            }
+            '{' => { tokens.push((Token::LBrace, token_line)); i += 1; }
+            '}' => { tokens.push((Token::RBrace, token_line)); i += 1; }
+            '[' => { tokens.push((Token::LBracket, token_line)); i += 1; }
+            ']' => { tokens.push((Token::RBracket, token_line)); i += 1; }
+            ';' => { tokens.push((Token::Semicolon, token_line)); i += 1; }
+            ',' => { tokens.push((Token::Comma, token_line)); i += 1; }
+            '=' => { tokens.push((Token::Equals, token_line)); i += 1; }
+            ':' => { tokens.push((Token::Colon, token_line)); i += 1; }
+            '+' => { tokens.push((Token::Plus, token_line)); i += 1; }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                    i += 1;
+                }
+                tokens.push((Token::Id(chars[start..i].iter().collect()), token_line));
+            }
+            other => {
+                warnings.push(format!("line {}: ignoring unexpected character '{}'", token_line, other));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    warnings: Vec<String>,
+    nodes: Vec<DotNode>,
+    node_idx: HashMap<String, usize>,
+    edges: Vec<DotEdge>,
+    warned_subgraph: bool,
+    warned_port: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 读取一个 ID，支持 "a" + "b" 形式的字符串拼接
+    fn id(&mut self) -> Option<String> {
+        let Some(Token::Id(mut value)) = self.peek().cloned() else {
+            return None;
+        };
+        self.pos += 1;
+        while self.peek() == Some(&Token::Plus) {
+            let Some(Token::Id(more)) = self.peek_at(1).cloned() else {
+                break;
+            };
+            value.push_str(&more);
+            self.pos += 2;
+        }
+        Some(value)
+    }
+
+    fn parse_graph(&mut self) -> Result<(), String> {
+        let keyword = self.id().ok_or("DOT input must start with 'graph' or 'digraph'")?;
+        let keyword = if keyword.eq_ignore_ascii_case("strict") {
+            self.id().ok_or("expected 'graph' or 'digraph' after 'strict'")?
+        } else {
+            keyword
+        };
+        if !keyword.eq_ignore_ascii_case("graph") && !keyword.eq_ignore_ascii_case("digraph") {
+            return Err(format!("line {}: expected 'graph' or 'digraph', found '{}'", self.line(), keyword));
+        }
+        let _graph_name = self.id();
+        if !self.eat(&Token::LBrace) {
+            return Err(format!("line {}: expected '{{' to open the graph body", self.line()));
+        }
+        self.parse_stmt_list();
+        if !self.eat(&Token::RBrace) {
+            self.warnings.push("graph body is not closed with '}'".to_string());
+        }
+        if self.peek().is_some() {
+            self.warnings.push(format!("line {}: ignoring content after the end of the graph", self.line()));
+        }
+        Ok(())
+    }
+
+    /// 解析到匹配的 '}' 之前，返回语句中出现的所有节点 ID（用于子图作为边的端点）
+    fn parse_stmt_list(&mut self) -> Vec<String> {
+        let mut mentioned = Vec::new();
+        while let Some(token) = self.peek() {
+            if *token == Token::RBrace {
+                break;
+            }
+            if *token == Token::Semicolon {
+                self.pos += 1;
+                continue;
+            }
+            let start = self.pos;
+            mentioned.extend(self.parse_stmt());
+            if self.pos == start {
+                // 无法识别的语句：跳过一个记号，避免死循环
+                let line = self.line();
+                if let Some(token) = self.next() {
+                    self.warnings.push(format!("line {}: ignoring unexpected {:?}", line, token));
+                }
+            }
+        }
+        mentioned
+    }
+
+    fn parse_stmt(&mut self) -> Vec<String> {
+        match (self.peek().cloned(), self.peek_at(1).cloned()) {
+            // 属性语句 graph/node/edge [...]：默认属性不影响导入，忽略
+            (Some(Token::Id(keyword)), Some(Token::LBracket))
+                if ["graph", "node", "edge"].iter().any(|k| keyword.eq_ignore_ascii_case(k)) =>
+            {
+                self.pos += 1;
+                self.parse_attr_list();
+                Vec::new()
+            }
+            // 图属性 a = b
+            (Some(Token::Id(_)), Some(Token::Equals)) => {
+                self.pos += 2;
+                self.id();
+                Vec::new()
+            }
+            (Some(Token::Id(_)), _) | (Some(Token::LBrace), _) => {
+                let operand = self.parse_edge_operand();
+                if self.peek() == Some(&Token::EdgeOp) {
+                    self.parse_edge_chain(operand)
+                } else {
+                    let is_single_node = operand.len() == 1 && self.peek() == Some(&Token::LBracket);
+                    let attributes = self.parse_attr_list();
+                    if is_single_node {
+                        self.add_node(&operand[0], attributes);
+                    }
+                    operand
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// 边的端点：节点 ID（可带端口）或子图
+    fn parse_edge_operand(&mut self) -> Vec<String> {
+        let is_subgraph = match self.peek() {
+            Some(Token::LBrace) => true,
+            Some(Token::Id(keyword)) => keyword.eq_ignore_ascii_case("subgraph"),
+            _ => false,
+        };
+        if is_subgraph {
+            if !self.warned_subgraph {
+                self.warnings.push(format!("line {}: subgraphs are not supported; their nodes and edges are imported into the main graph", self.line()));
+                self.warned_subgraph = true;
+            }
+            if self.peek() != Some(&Token::LBrace) {
+                self.pos += 1; // subgraph
+                if self.peek() != Some(&Token::LBrace) {
+                    self.id(); // 子图名称
+                }
+            }
+            if !self.eat(&Token::LBrace) {
+                return Vec::new();
+            }
+            let mentioned = self.parse_stmt_list();
+            self.eat(&Token::RBrace);
+            return mentioned;
+        }
+
+        let Some(node_id) = self.id() else {
+            return Vec::new();
+        };
+        if self.peek() == Some(&Token::Colon) {
+            if !self.warned_port {
+                self.warnings.push(format!("line {}: node ports are not supported and are ignored", self.line()));
+                self.warned_port = true;
+            }
+            while self.eat(&Token::Colon) {
+                self.id();
+            }
+        }
+        self.add_node(&node_id, Attributes::new());
+        vec![node_id]
+    }
+
+    fn parse_edge_chain(&mut self, first: Vec<String>) -> Vec<String> {
+        let mut operands = vec![first];
+        while self.eat(&Token::EdgeOp) {
+            operands.push(self.parse_edge_operand());
+        }
+        let attributes = self.parse_attr_list();
+        for pair in operands.windows(2) {
+            for from in &pair[0] {
+                for to in &pair[1] {
+                    self.edges.push(DotEdge { from: from.clone(), to: to.clone(), attributes: attributes.clone() });
+                }
+            }
+        }
+        operands.concat()
+    }
+
+    fn parse_attr_list(&mut self) -> Attributes {
+        let mut attributes = Attributes::new();
+        while self.eat(&Token::LBracket) {
+            loop {
+                match self.peek() {
+                    None => return attributes,
+                    Some(Token::RBracket) => {
+                        self.pos += 1;
+                        break;
+                    }
+                    Some(Token::Comma) | Some(Token::Semicolon) => {
+                        self.pos += 1;
+                    }
+                    Some(Token::Id(_)) => {
+                        let key = self.id().unwrap_or_default();
+                        let value = if self.eat(&Token::Equals) { self.id().unwrap_or_default() } else { "true".to_string() };
+                        attributes.insert(key, value);
+                    }
+                    Some(_) => {
+                        let line = self.line();
+                        if let Some(token) = self.next() {
+                            self.warnings.push(format!("line {}: ignoring unexpected {:?} in attribute list", line, token));
+                        }
+                    }
+                }
+            }
+        }
+        attributes
+    }
+
+    fn add_node(&mut self, node_id: &str, attributes: Attributes) {
+        match self.node_idx.get(node_id) {
+            Some(&idx) => self.nodes[idx].attributes.extend(attributes),
+            None => {
+                self.node_idx.insert(node_id.to_string(), self.nodes.len());
+                self.nodes.push(DotNode { id: node_id.to_string(), attributes });
+            }
+        }
+    }
+
+    fn into_import(mut self) -> DotImport {
+        let mut connection_id_counts: HashMap<String, usize> = HashMap::new();
+        let connections: Vec<ConnectionData> = self.edges
+            .iter()
+            .map(|edge| {
+                let base_id = edge.attributes.get("id").cloned().unwrap_or_else(|| format!("{}-{}", edge.from, edge.to));
+                let count = connection_id_counts.entry(base_id.clone()).or_insert(0);
+                *count += 1;
+                let connection_id = if *count == 1 { base_id } else { format!("{}#{}", base_id, count) };
+                ConnectionData { from_node: edge.from.clone(), to_node: edge.to.clone(), connection_id }
+            })
+            .collect();
+
+        let mut locations: Vec<Option<Vec2>> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let location = node.attributes.get("pos").and_then(|pos| {
+                let parsed = parse_pos(pos);
+                if parsed.is_none() {
+                    self.warnings.push(format!("node '{}': cannot parse pos=\"{}\"", node.id, pos));
+                }
+                parsed
+            });
+            locations.push(location);
+        }
+        let element_ids: Vec<String> = self.nodes.iter().map(|node| node.id.clone()).collect();
+        let auto_placed = fill_missing_locations(&element_ids, &mut locations, &connections);
+        if auto_placed > 0 {
+            self.warnings.push(format!("{} node(s) without a usable pos attribute were placed automatically", auto_placed));
+        }
+
+        let elements = self.nodes
+            .into_iter()
+            .zip(locations)
+            .map(|(node, location)| {
+                let location = location.unwrap_or(Vec2::ZERO);
+                let name = match node.attributes.get("label") {
+                    Some(label) if label != "\\N" => label.clone(),
+                    _ => node.id.clone(),
+                };
+                ElementData {
+                    name,
+                    node_type: node.attributes.get("type").cloned().unwrap_or_else(|| DEFAULT_DOT_NODE_TYPE.to_string()),
+                    type_variety: node.attributes.get("type_variety").cloned().unwrap_or_default(),
+                    metadata: Metadata { location: Some(Location { x: location.x, y: location.y }) },
+                    element_id: node.id,
+                }
+            })
+            .collect();
+
+        DotImport {
+            topology: FullTopologyData { name: None, elements, connections, defrag_timeline_events: Vec::new(), channel_plan: None, blocked_demands: Vec::new() },
+            warnings: self.warnings,
+        }
+    }
+}
+
+/// 解析 pos="x,y" 或 "x,y!"（Graphviz 的固定位置标记），返回拓扑坐标（y 轴向下）
+fn parse_pos(pos: &str) -> Option<Vec2> {
+    let mut parts = pos.trim().trim_end_matches('!').split(',');
+    let x: f32 = parts.next()?.trim().parse().ok()?;
+    let y: f32 = parts.next()?.trim().parse().ok()?;
+    // 忽略可能存在的 z 坐标
+    Some(Vec2::new(x, -y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(import: &DotImport, element_id: &str) -> (f32, f32) {
+        let element = import.topology.elements.iter().find(|element| element.element_id == element_id).expect("node is imported");
+        let location = element.metadata.location.as_ref().expect("every node has a location");
+        (location.x, location.y)
+    }
+
+    fn edges(import: &DotImport) -> Vec<(&str, &str, &str)> {
+        import.topology.connections.iter()
+            .map(|c| (c.from_node.as_str(), c.to_node.as_str(), c.connection_id.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn quoted_ids_and_attributes() {
+        let import = parse_dot(r#"
+            graph "backbone" {
+                "Node A" [pos="10,20!", label="Alpha", type=Roadm];
+                "say \"hi\"" [pos="30,40"];
+                "Node " + "A" -- "say \"hi\"" [id=trunk];
+            }
+        "#).unwrap();
+        assert_eq!(import.topology.elements.len(), 2, "concatenated ids refer to the same node");
+        let alpha = &import.topology.elements[0];
+        assert_eq!((alpha.element_id.as_str(), alpha.name.as_str(), alpha.node_type.as_str()), ("Node A", "Alpha", "Roadm"));
+        assert_eq!(location(&import, "Node A"), (10.0, -20.0), "Graphviz y is flipped");
+        assert_eq!(location(&import, "say \"hi\""), (30.0, -40.0));
+        assert_eq!(import.topology.elements[1].node_type, DEFAULT_DOT_NODE_TYPE);
+        assert_eq!(edges(&import), vec![("Node A", "say \"hi\"", "trunk")]);
+        assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+    }
+
+    #[test]
+    fn undirected_and_directed_edges() {
+        let undirected = parse_dot("graph { a -- b -- c; a -- b }").unwrap();
+        assert_eq!(edges(&undirected), vec![("a", "b", "a-b"), ("b", "c", "b-c"), ("a", "b", "a-b#2")]);
+
+        let directed = parse_dot("strict digraph g { a -> { b c } [color=red] }").unwrap();
+        assert_eq!(edges(&directed), vec![("a", "b", "a-b"), ("a", "c", "a-c")]);
+        assert!(directed.warnings.iter().any(|warning| warning.contains("subgraphs")), "{:?}", directed.warnings);
+        // 没有 pos 的节点自动布局
+        assert!(directed.warnings.iter().any(|warning| warning.contains("placed automatically")), "{:?}", directed.warnings);
+    }
+
+    #[test]
+    fn ports_comments_and_bad_pos_are_warnings() {
+        let import = parse_dot("digraph {\n// comment\n/* block\ncomment */ a:n -> b:s\n a [pos=\"x,y\"] }").unwrap();
+        assert_eq!(edges(&import), vec![("a", "b", "a-b")]);
+        assert!(import.warnings.iter().any(|warning| warning.contains("ports")), "{:?}", import.warnings);
+        assert!(import.warnings.iter().any(|warning| warning.contains("cannot parse pos")), "{:?}", import.warnings);
+    }
+
+    #[test]
+    fn malformed_input_reports_errors() {
+        assert!(parse_dot("").unwrap_err().contains("must start with"));
+        assert_eq!(parse_dot("tree { a }").unwrap_err(), "line 1: expected 'graph' or 'digraph', found 'tree'");
+        assert_eq!(parse_dot("graph g\n\n a -- b").unwrap_err(), "line 3: expected '{' to open the graph body");
+
+        let unclosed = parse_dot("graph { a -- b").unwrap();
+        assert_eq!(edges(&unclosed), vec![("a", "b", "a-b")]);
+        assert!(unclosed.warnings.iter().any(|warning| warning.contains("not closed")), "{:?}", unclosed.warnings);
+        let trailing = parse_dot("graph { a } extra").unwrap();
+        assert!(trailing.warnings.iter().any(|warning| warning.contains("after the end")), "{:?}", trailing.warnings);
+    }
+}
@@ -0,0 +1,385 @@
+// src/scene/edge_bundles.rs
+// 力导向边绑定（Holten & van Wijk 2009, Force-Directed Edge Bundling）：把每条链路细分为若干控制点，
+// 相互兼容（方向相近、长度相近、位置相近）的链路的控制点彼此吸引，同一链路相邻的控制点之间有弹簧，
+// 迭代若干轮后方向相近的链路汇聚成束，密集的网状拓扑不再是一团交叉的直线。
+// 计算量为 O(链路数 × 兼容链路数 × 控制点数 × 迭代次数)，EdgeBundlingJob 按时间预算分多帧完成，结果（EdgeBundles）由调用方缓存。
+// 查找兼容链路需要比较每一对链路，链路数超过 MAX_BUNDLED_EDGES 时不绑定（所有链路按直线绘制）。
+use std::collections::HashMap;
+
+use glam::Vec2;
+use instant::Instant;
+
+/// 兼容度低于该值的两条链路互不吸引
+const COMPATIBILITY_THRESHOLD: f32 = 0.6;
+/// 每条链路最多受这么多条兼容度最高的链路吸引，全网状拓扑中计算量不会随链路数平方增长
+const MAX_COMPATIBLE_EDGES: usize = 32;
+/// 每轮的迭代次数：第一轮 INITIAL_ITERATIONS 次，之后每轮为上一轮的 2/3；每轮控制点数加倍（1、2、4、8）
+const CYCLES: u32 = 4;
+const INITIAL_ITERATIONS: u32 = 50;
+/// 弹簧常数：控制点受相邻控制点的拉力为 SPRING_CONSTANT / (链路长度 × 段数) × 位移，与吸引力一样无量纲
+const SPRING_CONSTANT: f32 = 0.1;
+/// 第一轮每次迭代的步长为平均链路长度的这一比例，之后每轮减半
+const INITIAL_STEP_EDGE_LENGTHS: f32 = 0.002;
+/// 每处理这么多条链路检查一次时间
+const EDGES_PER_TIME_CHECK: usize = 16;
+/// 绑定的链路数上限：兼容度要比较每一对链路，4000 条链路约 800 万次比较，按帧预算分摊后约一秒完成
+pub const MAX_BUNDLED_EDGES: usize = 4000;
+
+/// 绑定后的链路形状，以节点索引对 (较小, 较大) 为键。每个控制点记为其在节点圆心连线上的投影参数 s（0 到 1）
+/// 和相对投影点的偏移；节点位置变化后结果失效，由调用方重新计算
+#[derive(Debug, Clone)]
+pub struct EdgeBundles {
+    bundles: HashMap<(usize, usize), Vec<(f32, Vec2)>>,
+    /// 计算时的节点位置和链路，与当前不一致时结果已经失效
+    pub node_positions: Vec<Vec2>,
+    pub links: Vec<(usize, usize)>,
+    /// 绘制时偏移乘以该系数：0 为直线，1 为完整的绑定形状（改变强度不需要重新计算）
+    pub strength: f32,
+}
+
+impl EdgeBundles {
+    /// 把大致平行于 source → target 节点圆心连线的线段 start → end（一跳服务线路、链路边界或中心线）弯成沿绑定形状的折线。
+    /// 端点保持不变（仍在节点圆周上），只移动两端之间的控制点，因此与节点内部的转接弧线和其他线段衔接处不变。
+    /// 链路没有绑定形状或强度为 0 时返回 None
+    pub fn bend(&self, source_idx: usize, target_idx: usize, source_center: Vec2, target_center: Vec2, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+        if self.strength <= 0.0 {
+            return None;
+        }
+        let control_points = self.bundles.get(&(source_idx.min(target_idx), source_idx.max(target_idx)))?;
+        // 控制点的参数 s 按较小索引的节点到较大索引的节点测量
+        let (chord_start, chord_end) = if source_idx <= target_idx { (source_center, target_center) } else { (target_center, source_center) };
+        let lane = end - start;
+        let lane_length_squared = lane.length_squared();
+        if lane_length_squared < f32::EPSILON {
+            return None;
+        }
+
+        let mut interior: Vec<(f32, Vec2)> = control_points
+            .iter()
+            .filter_map(|&(s, offset)| {
+                let chord_point = chord_start.lerp(chord_end, s);
+                let u = (chord_point - start).dot(lane) / lane_length_squared;
+                // 落在节点圆内（线段端点之外）的控制点不使用
+                (u > 0.0 && u < 1.0).then(|| (u, start + lane * u + offset * self.strength))
+            })
+            .collect();
+        if interior.is_empty() {
+            return None;
+        }
+        interior.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut points = Vec::with_capacity(interior.len() + 2);
+        points.push(start);
+        points.extend(interior.into_iter().map(|(_, point)| point));
+        points.push(end);
+        Some(points)
+    }
+}
+
+/// 折线按长度的中点及该处线段的方向（单位向量）；长度为 0 时为 None
+pub fn polyline_midpoint(points: &[Vec2]) -> Option<(Vec2, Vec2)> {
+    let total_length: f32 = points.windows(2).map(|segment| segment[0].distance(segment[1])).sum();
+    let mut remaining = total_length / 2.0;
+    for segment in points.windows(2) {
+        let segment_length = segment[0].distance(segment[1]);
+        if remaining <= segment_length && segment_length >= f32::EPSILON {
+            return Some((segment[0].lerp(segment[1], remaining / segment_length), (segment[1] - segment[0]) / segment_length));
+        }
+        remaining -= segment_length;
+    }
+    None
+}
+
+/// 待绑定的一条链路：节点圆心之间的线段
+#[derive(Debug, Clone, Copy)]
+struct BundledEdge {
+    key: (usize, usize),
+    start: Vec2,
+    end: Vec2,
+}
+
+impl BundledEdge {
+    fn vector(&self) -> Vec2 {
+        self.end - self.start
+    }
+}
+
+/// 两条链路的兼容度（角度 × 长度 × 位置，均在 0 到 1 之间）
+fn compatibility(p: &BundledEdge, q: &BundledEdge) -> f32 {
+    let (p_vector, q_vector) = (p.vector(), q.vector());
+    let (p_length, q_length) = (p_vector.length(), q_vector.length());
+    if p_length < f32::EPSILON || q_length < f32::EPSILON {
+        return 0.0;
+    }
+    let angle = (p_vector.dot(q_vector) / (p_length * q_length)).abs();
+    let average_length = (p_length + q_length) / 2.0;
+    let scale = 2.0 / (average_length / p_length.min(q_length) + p_length.max(q_length) / average_length);
+    let midpoint_distance = ((p.start + p.end) / 2.0).distance((q.start + q.end) / 2.0);
+    let position = average_length / (average_length + midpoint_distance);
+    angle * scale * position
+}
+
+/// 按弧长把折线（包括两个端点）重新采样为 count 个等距的内部控制点
+fn resample(start: Vec2, interior: &[Vec2], end: Vec2, count: usize) -> Vec<Vec2> {
+    let polyline: Vec<Vec2> = std::iter::once(start).chain(interior.iter().copied()).chain(std::iter::once(end)).collect();
+    let total_length: f32 = polyline.windows(2).map(|segment| segment[0].distance(segment[1])).sum();
+    let spacing = total_length / (count + 1) as f32;
+    let mut points = Vec::with_capacity(count);
+    let mut segment = 0;
+    let mut walked = 0.0; // 当前线段起点的累计弧长
+    for k in 1..=count {
+        let target = spacing * k as f32;
+        while segment + 2 < polyline.len() && walked + polyline[segment].distance(polyline[segment + 1]) < target {
+            walked += polyline[segment].distance(polyline[segment + 1]);
+            segment += 1;
+        }
+        let segment_length = polyline[segment].distance(polyline[segment + 1]).max(f32::EPSILON);
+        points.push(polyline[segment].lerp(polyline[segment + 1], ((target - walked) / segment_length).clamp(0.0, 1.0)));
+    }
+    points
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BundlingPhase {
+    /// 计算第 next_edge 条链路的兼容链路
+    Compatibility { next_edge: usize },
+    /// 第 cycle 轮的第 iteration 次迭代，处理到第 next_edge 条链路
+    Iterating { cycle: u32, iteration: u32, next_edge: usize },
+}
+
+/// 未完成的边绑定计算
+#[derive(Debug)]
+pub struct EdgeBundlingJob {
+    /// 开始计算时的节点位置和链路，变化后调用方丢弃该计算
+    pub node_positions: Vec<Vec2>,
+    pub links: Vec<(usize, usize)>,
+    /// 链路数超过 MAX_BUNDLED_EDGES，结果不含任何绑定形状
+    pub over_edge_limit: bool,
+    edges: Vec<BundledEdge>,
+    /// 每条链路的兼容链路（索引，方向是否相反）
+    compatible: Vec<Vec<(u32, bool)>>,
+    points: Vec<Vec<Vec2>>,
+    next_points: Vec<Vec<Vec2>>,
+    step: f32,
+    phase: BundlingPhase,
+}
+
+impl EdgeBundlingJob {
+    /// links 为链路两端的节点索引（重复的链路和两端重合的链路被忽略）。去重后超过 MAX_BUNDLED_EDGES 条时立即完成，不绑定任何链路
+    pub fn new(node_positions: Vec<Vec2>, links: Vec<(usize, usize)>) -> Self {
+        let mut keys: Vec<(usize, usize)> = links
+            .iter()
+            .copied()
+            .filter(|&(a, b)| a != b && a.max(b) < node_positions.len())
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        let edges: Vec<BundledEdge> = keys
+            .into_iter()
+            .map(|key| BundledEdge { key, start: node_positions[key.0], end: node_positions[key.1] })
+            .filter(|edge| edge.vector().length() >= f32::EPSILON)
+            .collect();
+        let over_edge_limit = edges.len() > MAX_BUNDLED_EDGES;
+        let edges = if over_edge_limit { Vec::new() } else { edges };
+        let average_length = edges.iter().map(|edge| edge.vector().length()).sum::<f32>() / edges.len().max(1) as f32;
+        let points: Vec<Vec<Vec2>> = edges.iter().map(|edge| vec![(edge.start + edge.end) / 2.0]).collect();
+        Self {
+            node_positions,
+            links,
+            over_edge_limit,
+            compatible: Vec::with_capacity(edges.len()),
+            next_points: points.clone(),
+            points,
+            edges,
+            step: average_length * INITIAL_STEP_EDGE_LENGTHS,
+            phase: BundlingPhase::Compatibility { next_edge: 0 },
+        }
+    }
+
+    /// 在 budget_ms 毫秒内推进计算，全部完成时返回结果
+    pub fn advance(&mut self, budget_ms: f64) -> Option<EdgeBundles> {
+        let start_time = Instant::now();
+        let out_of_time = || start_time.elapsed().as_secs_f64() * 1000.0 >= budget_ms;
+        if self.edges.is_empty() {
+            return Some(self.bundles());
+        }
+        loop {
+            match self.phase {
+                BundlingPhase::Compatibility { next_edge } => {
+                    let end = (next_edge + EDGES_PER_TIME_CHECK).min(self.edges.len());
+                    for i in next_edge..end {
+                        let mut candidates: Vec<(u32, bool, f32)> = self.edges
+                            .iter()
+                            .enumerate()
+                            .filter(|&(j, _)| j != i)
+                            .map(|(j, other)| (j as u32, self.edges[i].vector().dot(other.vector()) < 0.0, compatibility(&self.edges[i], other)))
+                            .filter(|&(_, _, compatibility)| compatibility >= COMPATIBILITY_THRESHOLD)
+                            .collect();
+                        candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+                        candidates.truncate(MAX_COMPATIBLE_EDGES);
+                        self.compatible.push(candidates.into_iter().map(|(j, reversed, _)| (j, reversed)).collect());
+                    }
+                    self.phase = if end == self.edges.len() {
+                        BundlingPhase::Iterating { cycle: 0, iteration: 0, next_edge: 0 }
+                    } else {
+                        BundlingPhase::Compatibility { next_edge: end }
+                    };
+                }
+                BundlingPhase::Iterating { cycle, iteration, next_edge } => {
+                    let end = (next_edge + EDGES_PER_TIME_CHECK).min(self.edges.len());
+                    for i in next_edge..end {
+                        self.next_points[i] = self.moved_points(i);
+                    }
+                    if end < self.edges.len() {
+                        self.phase = BundlingPhase::Iterating { cycle, iteration, next_edge: end };
+                    } else {
+                        // 一次迭代中所有链路都按迭代前的位置计算受力
+                        std::mem::swap(&mut self.points, &mut self.next_points);
+                        let iterations = (INITIAL_ITERATIONS as f32 * (2.0f32 / 3.0).powi(cycle as i32)).round() as u32;
+                        if iteration + 1 < iterations {
+                            self.phase = BundlingPhase::Iterating { cycle, iteration: iteration + 1, next_edge: 0 };
+                        } else if cycle + 1 < CYCLES {
+                            self.subdivide();
+                            self.phase = BundlingPhase::Iterating { cycle: cycle + 1, iteration: 0, next_edge: 0 };
+                        } else {
+                            return Some(self.bundles());
+                        }
+                    }
+                }
+            }
+            if out_of_time() {
+                return None;
+            }
+        }
+    }
+
+    /// 第 i 条链路的控制点在弹簧力和兼容链路的吸引力作用下移动一步后的位置
+    fn moved_points(&self, i: usize) -> Vec<Vec2> {
+        let edge = &self.edges[i];
+        let points = &self.points[i];
+        let count = points.len();
+        let spring = SPRING_CONSTANT / (edge.vector().length() * (count + 1) as f32);
+        (0..count)
+            .map(|k| {
+                let point = points[k];
+                let previous = if k == 0 { edge.start } else { points[k - 1] };
+                let next = if k + 1 == count { edge.end } else { points[k + 1] };
+                let spring_force = (previous - point + next - point) * spring;
+                let electrostatic_force: Vec2 = self.compatible[i]
+                    .iter()
+                    .map(|&(j, reversed)| {
+                        let other = &self.points[j as usize];
+                        let other_point = if reversed { other[count - 1 - k] } else { other[k] };
+                        (other_point - point).normalize_or_zero()
+                    })
+                    .sum();
+                point + (spring_force + electrostatic_force) * self.step
+            })
+            .collect()
+    }
+
+    /// 进入下一轮：控制点数加倍，步长减半
+    fn subdivide(&mut self) {
+        for (edge, points) in self.edges.iter().zip(self.points.iter_mut()) {
+            *points = resample(edge.start, points, edge.end, points.len() * 2);
+        }
+        self.next_points = self.points.clone();
+        self.step /= 2.0;
+    }
+
+    fn bundles(&self) -> EdgeBundles {
+        let bundles = self.edges
+            .iter()
+            .zip(self.points.iter())
+            .map(|(edge, points)| {
+                let chord = edge.vector();
+                let control_points = points
+                    .iter()
+                    .map(|&point| {
+                        let s = ((point - edge.start).dot(chord) / chord.length_squared()).clamp(0.0, 1.0);
+                        (s, point - edge.start.lerp(edge.end, s))
+                    })
+                    .collect();
+                (edge.key, control_points)
+            })
+            .collect();
+        EdgeBundles { bundles, node_positions: self.node_positions.clone(), links: self.links.clone(), strength: 1.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(start: (f32, f32), end: (f32, f32)) -> BundledEdge {
+        BundledEdge { key: (0, 1), start: Vec2::new(start.0, start.1), end: Vec2::new(end.0, end.1) }
+    }
+
+    /// 兼容度：相同的链路为 1，反向不影响，垂直为 0；长度差异和中点距离越大兼容度越低，退化的链路为 0
+    #[test]
+    fn compatibility_metric() {
+        let p = edge((0.0, 0.0), (10.0, 0.0));
+        assert!((compatibility(&p, &p) - 1.0).abs() < 1e-6);
+        assert_eq!(compatibility(&p, &edge((10.0, 0.0), (0.0, 0.0))), compatibility(&p, &p));
+        assert!(compatibility(&p, &edge((5.0, -5.0), (5.0, 5.0))).abs() < 1e-6);
+
+        let near = compatibility(&p, &edge((0.0, 1.0), (10.0, 1.0)));
+        let far = compatibility(&p, &edge((0.0, 20.0), (10.0, 20.0)));
+        let shorter = compatibility(&p, &edge((2.5, 1.0), (7.5, 1.0)));
+        let tilted = compatibility(&p, &edge((0.0, 1.0), (10.0, 4.0)));
+        assert!(near > COMPATIBILITY_THRESHOLD && far < COMPATIBILITY_THRESHOLD, "{near} {far}");
+        assert!(shorter < near && tilted < near, "{shorter} {tilted} {near}");
+        for other in [edge((0.0, 1.0), (10.0, 1.0)), edge((2.5, 1.0), (7.5, 1.0)), edge((0.0, 1.0), (10.0, 4.0))] {
+            assert!((compatibility(&p, &other) - compatibility(&other, &p)).abs() < 1e-6);
+        }
+        assert_eq!(compatibility(&p, &edge((3.0, 3.0), (3.0, 3.0))), 0.0);
+    }
+
+    /// 三条平行链路（其中一条反向）和一条垂直链路
+    fn finished_bundles() -> EdgeBundles {
+        let node_positions = vec![
+            Vec2::new(0.0, 0.0), Vec2::new(100.0, 0.0),
+            Vec2::new(0.0, 10.0), Vec2::new(100.0, 10.0),
+            Vec2::new(0.0, 20.0), Vec2::new(100.0, 20.0),
+            Vec2::new(50.0, 200.0), Vec2::new(50.0, 300.0),
+        ];
+        let links = vec![(0, 1), (3, 2), (4, 5), (6, 7), (1, 0), (2, 2)];
+        let mut job = EdgeBundlingJob::new(node_positions, links);
+        assert!(!job.over_edge_limit);
+        job.advance(f64::INFINITY).expect("an unlimited budget finishes the job")
+    }
+
+    /// 迭代之后弯曲的折线两端与传入的端点完全相同（不论链路按哪个方向传入），平行的链路向彼此靠拢，
+    /// 没有兼容链路的链路保持直线
+    #[test]
+    fn bundling_preserves_endpoints() {
+        let bundles = finished_bundles();
+        let position = |idx: usize| bundles.node_positions[idx];
+        for (source, target) in [(0, 1), (1, 0), (2, 3), (3, 2), (4, 5)] {
+            let (start, end) = (position(source) + Vec2::new(1.5, 0.5), position(target) - Vec2::new(1.5, -0.5));
+            let points = bundles.bend(source, target, position(source), position(target), start, end).expect("parallel links are bundled");
+            assert_eq!((points[0], points[points.len() - 1]), (start, end));
+            assert!(points.windows(2).all(|pair| (pair[1] - pair[0]).dot(end - start) > 0.0), "{points:?}");
+        }
+        // 外侧的链路被吸向中间
+        let outer = bundles.bend(0, 1, position(0), position(1), position(0), position(1)).unwrap();
+        assert!(outer[outer.len() / 2].y > 1.0, "{outer:?}");
+        let lone = bundles.bend(6, 7, position(6), position(7), position(6), position(7)).unwrap();
+        assert!(lone.iter().all(|point| (point.x - 50.0).abs() < 1e-3), "{lone:?}");
+        // 没有绑定形状的节点对（两端重合的链路）和强度为 0 时不弯曲
+        assert!(bundles.bend(2, 2, position(2), position(2), position(2), position(3)).is_none());
+        let straight = EdgeBundles { strength: 0.0, ..bundles.clone() };
+        assert!(straight.bend(0, 1, position(0), position(1), position(0), position(1)).is_none());
+    }
+
+    /// 链路超过上限时立即完成，结果不含绑定形状
+    #[test]
+    fn too_many_edges_are_not_bundled() {
+        let node_positions: Vec<Vec2> = (0..=MAX_BUNDLED_EDGES).flat_map(|k| [Vec2::new(0.0, k as f32), Vec2::new(10.0, k as f32)]).collect();
+        let links: Vec<(usize, usize)> = (0..=MAX_BUNDLED_EDGES).map(|k| (2 * k, 2 * k + 1)).collect();
+        let mut job = EdgeBundlingJob::new(node_positions, links);
+        assert!(job.over_edge_limit);
+        let bundles = job.advance(1.0).expect("an over-limit job finishes immediately");
+        assert!(bundles.bend(0, 1, Vec2::ZERO, Vec2::new(10.0, 0.0), Vec2::ZERO, Vec2::new(10.0, 0.0)).is_none());
+    }
+}
@@ -1,19 +1,21 @@
 use serde::{Deserialize, Serialize};
 
 /// 表示地理位置的坐标
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Location {
     pub x: f32,
     pub y: f32,
 }
 
 /// 表示节点的元数据，其中包含位置信息
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Metadata {
-    pub location: Location,
+    /// GNPy 导出的逻辑节点（如 transceiver）可能缺少位置或为 null，加载时自动布局
+    #[serde(default)]
+    pub location: Option<Location>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ElementData {
     pub name: String,
     
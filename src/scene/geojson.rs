@@ -0,0 +1,151 @@
+// src/scene/geojson.rs
+// 将拓扑导出为 GeoJSON FeatureCollection（RFC 7946），用于在 GIS 工具中叠加显示。
+// 坐标使用 ElementData.metadata.location（调用方已替换为节点的当前位置，包括拖动），坐标约定与输入数据一致（未做任何投影变换）。
+// 目前只有 WasmApi::exportGeoJson 使用
+#![cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+use std::collections::HashMap;
+use serde::Serialize;
+use serde_json::json;
+
+use super::connection::ConnectionData;
+use super::element::ElementData;
+use super::service::ServiceData;
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum Geometry {
+    Point { coordinates: [f32; 2] },
+    LineString { coordinates: Vec<[f32; 2]> },
+    MultiLineString { coordinates: Vec<Vec<[f32; 2]>> },
+}
+
+#[derive(Serialize)]
+struct Feature {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// 没有位置信息的节点 geometry 为 null（RFC 7946 允许）
+    geometry: Option<Geometry>,
+    properties: serde_json::Value,
+}
+
+#[derive(Serialize)]
+pub struct FeatureCollection {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    features: Vec<Feature>,
+}
+
+/// 导出所需数据的快照：在事件循环中复制，在事件循环之外序列化
+#[derive(Debug)]
+pub struct GeoJsonSnapshot {
+    pub elements: Vec<ElementData>,
+    pub connections: Vec<ConnectionData>,
+    /// 当前时刻活跃的服务；不导出服务时为空
+    pub services: Vec<ServiceData>,
+}
+
+impl GeoJsonSnapshot {
+    pub fn to_feature_collection(&self) -> FeatureCollection {
+        let locations: HashMap<&str, [f32; 2]> = self.elements
+            .iter()
+            .filter_map(|element| {
+                let location = element.metadata.location.as_ref()?;
+                Some((element.element_id.as_str(), [location.x, location.y]))
+            })
+            .collect();
+
+        let mut features = Vec::with_capacity(self.elements.len() + self.connections.len() + self.services.len());
+        for element in &self.elements {
+            features.push(Feature {
+                kind: "Feature",
+                geometry: locations.get(element.element_id.as_str()).map(|&coordinates| Geometry::Point { coordinates }),
+                properties: json!({
+                    "element_id": element.element_id,
+                    "name": element.name,
+                    "node_type": element.node_type,
+                }),
+            });
+        }
+
+        for connection in &self.connections {
+            let (Some(&from), Some(&to)) = (locations.get(connection.from_node.as_str()), locations.get(connection.to_node.as_str())) else {
+                log::warn!("GeoJSON export skips connection {}: unknown endpoint.", connection.connection_id);
+                continue;
+            };
+            features.push(Feature {
+                kind: "Feature",
+                geometry: Some(Geometry::LineString { coordinates: vec![from, to] }),
+                properties: json!({ "connection_id": connection.connection_id }),
+            });
+        }
+
+        // 每条服务一个 MultiLineString，每一跳为一条 LineString
+        for service in &self.services {
+            let hops: Vec<Vec<[f32; 2]>> = service.path
+                .windows(2)
+                .filter_map(|hop| Some(vec![*locations.get(hop[0].as_str())?, *locations.get(hop[1].as_str())?]))
+                .collect();
+            if hops.is_empty() {
+                continue;
+            }
+            features.push(Feature {
+                kind: "Feature",
+                geometry: Some(Geometry::MultiLineString { coordinates: hops }),
+                properties: json!({
+                    "service_id": service.service_id,
+                    "wavelength": service.wavelength,
+                }),
+            });
+        }
+
+        FeatureCollection { kind: "FeatureCollection", features }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::element::{Location, Metadata};
+
+    fn element(id: &str, location: Option<(f32, f32)>) -> ElementData {
+        ElementData {
+            name: format!("node {}", id),
+            node_type: "Roadm".to_string(),
+            type_variety: String::new(),
+            metadata: Metadata { location: location.map(|(x, y)| Location { x, y }) },
+            element_id: id.to_string(),
+        }
+    }
+
+    fn connection(from: &str, to: &str) -> ConnectionData {
+        ConnectionData { from_node: from.to_string(), to_node: to.to_string(), connection_id: format!("{}-{}", from, to) }
+    }
+
+    #[test]
+    fn feature_collection_round_trips_through_json() {
+        let service: ServiceData = serde_json::from_value(json!({
+            "service_id": 7, "source_id": "A", "destination_id": "C", "arrival_time": 0.0, "departure_time": 1.0,
+            "bit_rate": 100.0, "power": 0.0, "path": ["A", "B", "C"], "wavelength": 3, "snr_requirement": 10.0, "gsnr": 15.0,
+        })).unwrap();
+        let snapshot = GeoJsonSnapshot {
+            elements: vec![element("A", Some((1.5, -2.0))), element("B", Some((10.0, 4.0))), element("C", Some((20.0, 0.0))), element("D", None)],
+            connections: vec![connection("A", "B"), connection("B", "C"), connection("C", "D")],
+            services: vec![service],
+        };
+        let text = serde_json::to_string(&snapshot.to_feature_collection()).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(parsed["type"], "FeatureCollection");
+        let features = parsed["features"].as_array().unwrap();
+        // 4 个节点、2 条链路（C-D 的端点没有位置）、1 条服务
+        assert_eq!(features.len(), 7);
+        assert_eq!(features[0]["geometry"], json!({"type": "Point", "coordinates": [1.5, -2.0]}));
+        assert_eq!(features[0]["properties"], json!({"element_id": "A", "name": "node A", "node_type": "Roadm"}));
+        assert!(features[3]["geometry"].is_null());
+        assert_eq!(features[4]["geometry"], json!({"type": "LineString", "coordinates": [[1.5, -2.0], [10.0, 4.0]]}));
+        assert_eq!(features[5]["properties"]["connection_id"], "B-C");
+        assert_eq!(features[6]["geometry"]["type"], "MultiLineString");
+        assert_eq!(features[6]["geometry"]["coordinates"], json!([[[1.5, -2.0], [10.0, 4.0]], [[10.0, 4.0], [20.0, 0.0]]]));
+        assert_eq!(features[6]["properties"], json!({"service_id": 7, "wavelength": 3}));
+    }
+}
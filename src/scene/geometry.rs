@@ -0,0 +1,1274 @@
+// src/scene/geometry.rs
+// 场景几何：节点实例、链路和服务线路的顶点以及拾取数据。只依赖拓扑数据和视图参数，不涉及 GPU，
+// 由 State 在拓扑、时间或高亮变化时重新生成，再交给 Renderer 上传。
+use std::collections::HashMap;
+use bevy_color::{ColorToComponents, LinearRgba, Oklcha, Srgba};
+use glam::Vec2;
+
+use crate::measurement::Measurement;
+use crate::models::{CircleInstance, LineVertex, ThickLineVertex};
+use crate::picking::{segment_pick_id, PickSegment, PICK_ID_NONE};
+use crate::settings::{ChannelPlan, ColorMode, HighlightLineStyle, HighlightStyle, LodLevel, OpacityMode, ServiceIntervalSemantics, ThicknessMode};
+use super::blocked_demand::{blocked_demands_in_range, BlockedDemand};
+use super::defrag_event::{reconstruct_state_at_time, time_averaged_link_occupancy, AnyEvent, EventCheckpoints, ServiceDiff};
+use super::edge_bundles::EdgeBundles;
+use super::path_index::{known_hop, path_link_keys, service_path_indices, undirected_link_key};
+use super::segment_grid::SegmentGrid;
+use super::wavelength_colors::{ServiceShade, WavelengthColorLut};
+use super::service::ServiceData;
+use super::text_label::TextLabel;
+
+/// 默认的节点半径（世界单位），实际半径见 State::node_radius 和 node_radius.rs
+pub const BASE_NODE_RADIUS: f32 = 20.0;
+/// 节点圆略大于服务线路起止的圆周，遮住线路端点
+pub const NODE_INSTANCE_RADIUS_FACTOR: f32 = 1.01;
+const PREVIEW_LINE_THICKNESS: f32 = 0.5; // 默认节点半径下的世界单位厚度
+const PATH_LINE_THICKNESS: f32 = 3.0; // 最短路径沿链路中心绘制，比服务线路更宽
+const MEASUREMENT_LINE_THICKNESS: f32 = 1.0;
+const HEAT_TRAIL_OVERLAY_ALPHA: f32 = 0.5;
+const DIFF_GHOST_LINE_THICKNESS: f32 = 1.0;
+const DIFF_UNCHANGED_ALPHA: f32 = 0.35;
+const BLOCKED_DEMAND_LINE_THICKNESS: f32 = 1.5; // 被阻塞需求的虚线，比残影更显眼
+// 高亮路径拐角处圆形连接的扇形数（线宽只有几个像素，12 段已经看不出棱角）
+const ROUND_JOIN_SEGMENTS: u32 = 12;
+// 服务路径在中间节点处的转接弧线的分段数
+const JUNCTION_ARC_SEGMENTS: u32 = 8;
+// 没有高亮时服务线路的 OKLCH 亮度和色度（图例使用相同的值）
+pub const SERVICE_LIGHTNESS: f32 = 0.6;
+pub const SERVICE_CHROMA: f32 = 0.11;
+// 按码率加粗时服务线路的像素宽度范围：当前活跃服务中码率最低的为最小宽度，最高的为最大宽度
+const BITRATE_MIN_WIDTH_PX: f32 = 1.0;
+const BITRATE_MAX_WIDTH_PX: f32 = 8.0;
+// 点击多选服务的色相（OKLCH），相邻两项相差较大
+const MULTI_SELECT_HUES: [f32; 6] = [25.0, 145.0, 265.0, 85.0, 205.0, 325.0];
+
+// Helper to generate a thick line (quad) from two points.
+// 返回线段长度，调用方据此累计沿路径的距离。
+pub fn push_thick_line_segment(
+    vertices: &mut Vec<ThickLineVertex>,
+    start_pos: Vec2,
+    end_pos: Vec2,
+    color: [f32; 4],
+    thickness: f32, // 世界单位厚度
+    start_distance: f32, // 起点沿路径的累计距离
+    dash_pattern: u32,
+) -> f32 {
+    let dir = end_pos - start_pos;
+    let length = dir.length();
+
+    if length < f32::EPSILON {
+        return 0.0; // Avoid division by zero for zero-length lines
+    }
+
+    let normalized_dir = dir.normalize();
+    let perpendicular_dir = Vec2::new(-normalized_dir.y, normalized_dir.x); // 旋转90度
+
+    let half_thickness_offset = perpendicular_dir * (thickness / 2.0); // 注意：厚度需要反比例于缩放，以在屏幕上保持一致的像素宽度
+
+    let p1_minus_offset = start_pos - half_thickness_offset;
+    let p1_plus_offset = start_pos + half_thickness_offset;
+    let p2_plus_offset = end_pos + half_thickness_offset;
+    let p2_minus_offset = end_pos - half_thickness_offset;
+
+    let end_distance = start_distance + length;
+    let vertex = |position: Vec2, path_distance: f32| ThickLineVertex {
+        position: position.into(),
+        color,
+        path_distance,
+        dash_pattern,
+    };
+
+    // 添加构成两个三角形的六个顶点
+    vertices.push(vertex(p1_minus_offset, start_distance));
+    vertices.push(vertex(p1_plus_offset, start_distance));
+    vertices.push(vertex(p2_plus_offset, end_distance)); // Triangle 1: (p1-, p1+, p2+)
+
+    vertices.push(vertex(p1_minus_offset, start_distance));
+    vertices.push(vertex(p2_plus_offset, end_distance));
+    vertices.push(vertex(p2_minus_offset, end_distance)); // Triangle 2: (p1-, p2+, p2-)
+
+    length
+}
+
+/// 圆形连接：以 center 为圆心、直径为线宽的扇形（三角形列表），盖住折线拐角处两段四边形之间的缺口。
+/// 与斜接不同，任何转角（包括接近 180° 的折返）生成的几何大小都不变
+pub fn push_round_join(
+    vertices: &mut Vec<ThickLineVertex>,
+    center: Vec2,
+    color: [f32; 4],
+    thickness: f32,
+    path_distance: f32, // 整个圆使用同一距离，虚线在拐角处要么整体显示要么整体隐藏
+    dash_pattern: u32,
+) {
+    let vertex = |position: Vec2| ThickLineVertex { position: position.into(), color, path_distance, dash_pattern };
+    let radius = thickness / 2.0;
+    for i in 0..ROUND_JOIN_SEGMENTS {
+        let angle = |k: u32| k as f32 / ROUND_JOIN_SEGMENTS as f32 * std::f32::consts::TAU;
+        vertices.push(vertex(center));
+        vertices.push(vertex(center + Vec2::from_angle(angle(i)) * radius));
+        vertices.push(vertex(center + Vec2::from_angle(angle(i + 1)) * radius));
+    }
+}
+
+/// 每 6 个顶点为一组时，push_round_join 生成的组（两个共用圆心的三角形，三个顶点到圆心的距离都等于半径）；
+/// push_thick_line_segment 的四边形中对角线比线宽长。SVG 导出据此跳过圆形连接，改用 stroke-linejoin
+pub fn is_round_join_chunk(chunk: &[ThickLineVertex]) -> bool {
+    let [center, a, b, _, _, _] = chunk else {
+        return false;
+    };
+    let center = Vec2::from_array(center.position);
+    let (radius_a, radius_b) = (center.distance(Vec2::from_array(a.position)), center.distance(Vec2::from_array(b.position)));
+    radius_a > 0.0 && (radius_a - radius_b).abs() <= radius_a * 1e-3
+}
+
+/// 粗线的颜色、线宽和虚线样式（ThickLineVertex::SOLID 等）
+#[derive(Debug, Clone, Copy)]
+pub struct StrokeStyle {
+    pub color: [f32; 4],
+    pub thickness: f32,
+    pub dash_pattern: u32,
+}
+
+/// 沿折线生成连续的粗线（三角形列表，每段一个四边形，顶点顺序与 push_thick_line_segment 相同）。
+/// 内部拐点使用斜接偏移，相邻两段共用拐点处的两个顶点，不留缝隙；两端的偏移垂直于 start_dir / end_dir，
+/// 传入相邻线段的方向时与其四边形的端点完全重合。返回折线长度
+pub fn push_thick_polyline(
+    vertices: &mut Vec<ThickLineVertex>,
+    points: &[Vec2],
+    start_dir: Vec2,
+    end_dir: Vec2,
+    style: StrokeStyle,
+    start_distance: f32,
+) -> f32 {
+    let StrokeStyle { color, thickness, dash_pattern } = style;
+    // 相邻的重合点（零长度的段）没有方向，去掉后再计算拐点偏移，否则相邻段在该点的偏移为零，四边形收缩成三角形
+    let mut deduped: Vec<Vec2> = Vec::with_capacity(points.len());
+    for &point in points {
+        if deduped.last().is_none_or(|last: &Vec2| last.distance(point) >= f32::EPSILON) {
+            deduped.push(point);
+        }
+    }
+    let points = &deduped[..];
+    // 斜接长度上限（半线宽的倍数），拐角接近折返时避免尖角伸得过长
+    const MITER_LIMIT: f32 = 4.0;
+    let Some(last) = points.len().checked_sub(1) else {
+        return 0.0;
+    };
+    let half_thickness = thickness / 2.0;
+    let perpendicular = |dir: Vec2| Vec2::new(-dir.y, dir.x);
+    let offsets: Vec<Vec2> = (0..=last)
+        .map(|i| {
+            let incoming = if i == 0 { Vec2::ZERO } else { (points[i] - points[i - 1]).normalize_or_zero() };
+            let outgoing = if i == last { Vec2::ZERO } else { (points[i + 1] - points[i]).normalize_or_zero() };
+            let end_tangent = match i {
+                0 => start_dir,
+                i if i == last => end_dir,
+                _ => Vec2::ZERO,
+            };
+            if let Some(tangent) = end_tangent.try_normalize() {
+                return perpendicular(tangent) * half_thickness;
+            }
+            let normal = perpendicular(if outgoing == Vec2::ZERO { incoming } else { outgoing });
+            let Some(miter) = perpendicular(incoming + outgoing).try_normalize() else {
+                return normal * half_thickness; // 原地折返
+            };
+            miter * half_thickness / miter.dot(normal).max(1.0 / MITER_LIMIT)
+        })
+        .collect();
+
+    let mut distance = start_distance;
+    for i in 0..last {
+        let length = points[i].distance(points[i + 1]);
+        if length < f32::EPSILON {
+            continue;
+        }
+        let end_distance = distance + length;
+        let vertex = |position: Vec2, path_distance: f32| ThickLineVertex { position: position.into(), color, path_distance, dash_pattern };
+        let (p1, p2) = (points[i], points[i + 1]);
+        vertices.push(vertex(p1 - offsets[i], distance));
+        vertices.push(vertex(p1 + offsets[i], distance));
+        vertices.push(vertex(p2 + offsets[i + 1], end_distance));
+        vertices.push(vertex(p1 - offsets[i], distance));
+        vertices.push(vertex(p2 + offsets[i + 1], end_distance));
+        vertices.push(vertex(p2 - offsets[i + 1], end_distance));
+        distance = end_distance;
+    }
+    distance - start_distance
+}
+
+/// 沿 points 生成粗线：两个点时与 push_thick_line_segment 相同，更多的点（边绑定后的折线）时两端垂直于首尾两段，
+/// 见 push_thick_polyline。返回长度
+pub fn push_thick_lane(
+    vertices: &mut Vec<ThickLineVertex>,
+    points: &[Vec2],
+    color: [f32; 4],
+    thickness: f32,
+    start_distance: f32,
+    dash_pattern: u32,
+) -> f32 {
+    match points {
+        &[start, end] => push_thick_line_segment(vertices, start, end, color, thickness, start_distance, dash_pattern),
+        _ => {
+            let (start_dir, end_dir) = polyline_end_directions(points);
+            push_thick_polyline(vertices, points, start_dir, end_dir, StrokeStyle { color, thickness, dash_pattern }, start_distance)
+        }
+    }
+}
+
+/// 折线首尾两段的方向（单位向量，退化时为零向量）
+fn polyline_end_directions(points: &[Vec2]) -> (Vec2, Vec2) {
+    match points {
+        [first, second, .., second_last, last] => ((*second - *first).normalize_or_zero(), (*last - *second_last).normalize_or_zero()),
+        _ => (Vec2::ZERO, Vec2::ZERO),
+    }
+}
+
+/// 服务路径在中间节点处的转接弧线：三次贝塞尔曲线从上一跳线路的终点 entry 沿 entry_dir 离开，
+/// 沿 exit_dir 到达下一跳线路的起点 exit，两端与线路相切。返回包括两个端点在内的折线（端点与传入的值完全相同）；
+/// 方向为零（退化的线路）时对应的控制点与端点重合，共线的两跳得到直线。entry 与 exit 重合时只返回一个点
+fn junction_arc(entry: Vec2, entry_dir: Vec2, exit: Vec2, exit_dir: Vec2) -> Vec<Vec2> {
+    let chord = entry.distance(exit);
+    if chord < f32::EPSILON {
+        return vec![entry];
+    }
+    let handle = chord / 3.0;
+    let (p1, p2) = (entry + entry_dir * handle, exit - exit_dir * handle);
+    let mut points = Vec::with_capacity(JUNCTION_ARC_SEGMENTS as usize + 1);
+    points.push(entry);
+    for k in 1..JUNCTION_ARC_SEGMENTS {
+        let t = k as f32 / JUNCTION_ARC_SEGMENTS as f32;
+        let s = 1.0 - t;
+        points.push(entry * (s * s * s) + p1 * (3.0 * s * s * t) + p2 * (3.0 * s * t * t) + exit * (t * t * t));
+    }
+    points.push(exit);
+    points
+}
+
+/// 波长对应的服务色相（OKLCH），图例与线路共用
+pub fn wavelength_hue(wavelength: i32, num_channels: u32) -> f32 {
+    let effective_wavelength = (wavelength as f32).min(num_channels.saturating_sub(1) as f32);
+    (effective_wavelength + 0.5) / (num_channels as f32) * 180.0 + 30.0
+}
+
+// 服务线路在链路内按波长展开成扇形：返回波长对应的旋转角
+fn wavelength_rotate_angle(wavelength: i32, num_channels: u32, max_spread_angle: f32) -> f32 {
+    if num_channels < 2 {
+        return 0.0; // 只有一个波长时沿链路中心
+    }
+    let effective_wavelength = (wavelength as f32).min((num_channels - 1) as f32);
+    let normalized_wavelength_factor = (effective_wavelength - ((num_channels as f32 - 1.0) / 2.0)) / ((num_channels as f32 - 1.0) / 2.0);
+    normalized_wavelength_factor * max_spread_angle
+}
+
+/// 服务线路和链路边界起止的圆周半径（节点半径可以逐个覆盖，见 node_radius.rs）
+pub fn lane_radius(instance: &CircleInstance) -> f32 {
+    instance.radius_scale / NODE_INSTANCE_RADIUS_FACTOR
+}
+
+// 计算一跳服务线路在两个节点圆周上的起止点（按波长旋转后的位置）
+fn service_lane_endpoints(source: &CircleInstance, target: &CircleInstance, wavelength_rotate_angle: f32) -> Option<(Vec2, Vec2)> {
+    let source_pos_center = Vec2::from_array(source.position);
+    let target_pos_center = Vec2::from_array(target.position);
+    let dir_vec = target_pos_center - source_pos_center;
+    if dir_vec.length() < f32::EPSILON {
+        return None;
+    }
+
+    let normalized_dir = dir_vec.normalize();
+
+    let upward_sacle: f32 = if normalized_dir.y >= 0.0 { 1.0 } else { -1.0 };
+    let service_start_pos = source_pos_center + (normalized_dir * lane_radius(source)).rotate(Vec2::from_angle(wavelength_rotate_angle * upward_sacle));
+    let service_end_pos = target_pos_center - (normalized_dir * lane_radius(target)).rotate(Vec2::from_angle( - wavelength_rotate_angle * upward_sacle));
+    Some((service_start_pos, service_end_pos))
+}
+
+// 链路在两个节点圆周之间的中心线段；节点重叠时为 None
+pub fn link_center_segment(source: &CircleInstance, target: &CircleInstance) -> Option<(Vec2, Vec2)> {
+    let source_pos_center = Vec2::from_array(source.position);
+    let target_pos_center = Vec2::from_array(target.position);
+    let dir_vec = target_pos_center - source_pos_center;
+    if dir_vec.length() < lane_radius(source) + lane_radius(target) {
+        return None;
+    }
+    let normalized_dir = dir_vec.normalize();
+    Some((source_pos_center + normalized_dir * lane_radius(source), target_pos_center - normalized_dir * lane_radius(target)))
+}
+
+/// 生成几何所需的输入（借用自 State）
+pub struct GeometryInputs<'a> {
+    /// 只用于没有预解析路径的服务（预览服务），见 path_index.rs
+    pub node_id_to_idx: &'a HashMap<String, usize>,
+    /// 与链路一一对应的两端节点索引（加载时解析，未知节点为 UNKNOWN_NODE）
+    pub connection_endpoints: &'a [(usize, usize)],
+    pub events: &'a [AnyEvent],
+    /// events 的回放检查点，热度轨迹模式下用于窗口平均占用率；为默认值时从第一个事件开始回放
+    pub event_checkpoints: &'a EventCheckpoints,
+    pub preview_services: &'a [ServiceData],
+    pub current_time: f64,
+    pub service_interval: ServiceIntervalSemantics,
+    pub channel_plan: ChannelPlan,
+    /// 节点的基础半径（世界单位），用于路径跳数和测量标签；线路按各节点实例的半径生成
+    pub node_radius: f32,
+    /// 服务线路按波长或 GSNR 余量着色；高亮、变暗等亮度规则在两种模式下相同
+    pub color_mode: ColorMode,
+    /// 按波长着色时的预计算颜色，过期时按原来的方式逐条计算，见 wavelength_colors.rs
+    pub wavelength_colors: &'a WavelengthColorLut,
+    pub thickness_mode: ThicknessMode,
+    /// 未高亮服务线路的透明度是否按利用率变化
+    pub opacity_mode: OpacityMode,
+    /// 生成时每个世界单位对应的像素数，用于把按码率计算的像素宽度换算为世界单位
+    pub world_to_pixels: f32,
+    /// 第一个为碎片整理服务本身，其余为被它移动的服务
+    pub highlight_service_ids: Option<&'a [i32]>,
+    /// 为 true 时高亮服务不区分碎片整理服务和被移动的服务，而是按在列表中的位置循环使用 MULTI_SELECT_HUES
+    pub highlight_palette: bool,
+    pub highlight_style: HighlightStyle,
+    pub highlight_line_style: HighlightLineStyle,
+    pub lod_level: LodLevel,
+    /// 最短路径高亮经过的节点索引（按顺序），与服务高亮互不影响
+    pub highlight_path: Option<&'a [usize]>,
+    pub measurement: Option<&'a Measurement>,
+    /// 热度轨迹模式的窗口长度（秒）：链路按 [current_time - window, current_time] 内的平均占用率着色；None 为瞬时模式
+    pub heat_trail_window: Option<f64>,
+    /// 被阻塞的需求（按时间排序）及以 current_time 为中心的窗口总长度；None 时不绘制
+    pub blocked_demands: &'a [BlockedDemand],
+    pub blocked_demand_window: Option<f64>,
+    /// 按节点索引的分组颜色（见 node_groups.rs），作为节点的基础颜色；没有分组时为空
+    pub node_group_colors: &'a [Option<[f32; 4]>],
+    /// 按节点索引的隐藏标记：隐藏的节点不绘制，相连的链路边界和服务线段跳过；没有分组时为空
+    pub hidden_nodes: &'a [bool],
+    /// 差异模式：按 t1 到 current_time 的变化给服务着色，并绘制已离开服务的残影
+    pub service_diff: Option<&'a ServiceDiff>,
+    /// 为流动动画记录每条绘制的服务线路的折线（SceneGeometry::flow_paths）
+    pub flow_animation: bool,
+    /// 开启边绑定且计算完成时，链路和服务线路沿绑定后的折线绘制（见 edge_bundling.rs）
+    pub edge_bundles: Option<&'a EdgeBundles>,
+}
+
+/// line_pick_ids / highlight_line_pick_ids 与对应的顶点数组一一对应
+#[derive(Debug, Default)]
+pub struct SceneGeometry {
+    pub circle_instances: Vec<CircleInstance>,
+    // 与 cull_region 相交的节点实例，只有这些实例上传到 GPU 并绘制（见 node_culling.rs）；导出、适应视图等仍使用 circle_instances
+    pub visible_circle_instances: Vec<CircleInstance>,
+    // 与 visible_circle_instances 一一对应的 circle_instances 索引，GPU 拾取时映射回节点索引
+    pub visible_circle_indices: Vec<u32>,
+    // 生成 visible_circle_instances 时的世界坐标范围（最小点、最大点），None 表示没有裁剪
+    pub cull_region: Option<(Vec2, Vec2)>,
+    // 生成时的 GeometryInputs::hidden_nodes，隐藏的节点不进入 visible_circle_instances
+    pub hidden_nodes: Vec<bool>,
+    pub line_vertices: Vec<LineVertex>,
+    pub highlight_line_vertices: Vec<ThickLineVertex>,
+    // 聚合 LOD 下每条链路一个按占用率着色的四边形（与高亮线路共用三角形管线）
+    pub link_occupancy_vertices: Vec<ThickLineVertex>,
+    pub preview_line_vertices: Vec<ThickLineVertex>, // 预览服务和差异模式中已离开服务的残影（虚线）
+    // 最短路径和测量线，与 annotation_labels 一起随高亮和测量变化
+    pub annotation_line_vertices: Vec<ThickLineVertex>,
+    pub line_pick_ids: Vec<u32>,
+    pub highlight_line_pick_ids: Vec<u32>,
+    pub service_quad_vertices: Vec<ThickLineVertex>, // 按码率加粗时未高亮的服务线路（四边形），代替 line_vertices 中的服务线段
+    pub service_quad_pick_ids: Vec<u32>,
+    pub pick_segments: Vec<PickSegment>,
+    // pick_segments 的网格索引，finish_regenerate 时建立（悬停和 CPU 命中测试）
+    pub pick_segment_grid: SegmentGrid,
+    // 节点名称和类型标签，在 start_geometry_update 中按节点位置和半径生成
+    pub node_labels: Vec<TextLabel>,
+    // 高亮服务经过各节点时的跳数，每次重新生成时重建；同一位置只保留一个标签（见 push_hop_label）
+    pub hop_labels: Vec<TextLabel>,
+    // 路径跳数等注释文字：随几何重新生成，以固定的屏幕字号绘制
+    pub annotation_labels: Vec<TextLabel>,
+    // 每条链路（按 connections 顺序，跳过引用不存在节点的链路）当前时刻占用的波长数
+    pub link_occupancy: Vec<LinkOccupancy>,
+    // 流动动画：每条绘制的服务线路从源到目的的折线，只在 GeometryInputs::flow_animation 时生成
+    pub flow_paths: Vec<FlowPath>,
+}
+
+/// 与 circles.wgsl 中的 GLOW_EXTENT 相同：有光晕的节点四边形向外扩大的比例
+const CIRCLE_GLOW_EXTENT: f32 = 0.5;
+
+impl SceneGeometry {
+    /// 按 region 重新生成 visible_circle_instances（节点四边形与 region 相交的实例，保持原来的顺序）；
+    /// region 为 None 时保留所有实例。节点颜色或位置变化后、上传之前调用
+    pub fn cull_circle_instances(&mut self, region: Option<(Vec2, Vec2)>) {
+        self.visible_circle_instances.clear();
+        self.visible_circle_indices.clear();
+        for (idx, instance) in self.circle_instances.iter().enumerate() {
+            if self.is_node_hidden(idx) {
+                continue;
+            }
+            let visible = region.is_none_or(|(region_min, region_max)| {
+                let position = Vec2::from_array(instance.position);
+                let half_extent = instance.radius_scale * if instance.glow > 0.0 { 1.0 + CIRCLE_GLOW_EXTENT } else { 1.0 };
+                position.cmpge(region_min - half_extent).all() && position.cmple(region_max + half_extent).all()
+            });
+            if visible {
+                self.visible_circle_instances.push(*instance);
+                self.visible_circle_indices.push(idx as u32);
+            }
+        }
+        self.cull_region = region;
+    }
+
+    pub fn is_node_hidden(&self, idx: usize) -> bool {
+        self.hidden_nodes.get(idx).copied().unwrap_or(false)
+    }
+}
+
+/// 服务线路的折线（世界坐标），流动动画的圆点每帧沿它移动
+#[derive(Debug, Clone)]
+pub struct FlowPath {
+    pub points: Vec<Vec2>,
+    pub length: f32,
+    pub bounds_min: Vec2,
+    pub bounds_max: Vec2,
+    pub color: [f32; 4],
+}
+
+impl FlowPath {
+    /// 少于两个点或长度为 0 时返回 None
+    pub fn new(points: Vec<Vec2>, color: [f32; 4]) -> Option<Self> {
+        let length: f32 = points.windows(2).map(|segment| segment[0].distance(segment[1])).sum();
+        if length < f32::EPSILON {
+            return None;
+        }
+        let bounds_min = points.iter().copied().reduce(Vec2::min)?;
+        let bounds_max = points.iter().copied().reduce(Vec2::max)?;
+        Some(Self { points, length, bounds_min, bounds_max, color })
+    }
+
+    /// 沿折线距起点 distance 处的位置（distance 超出长度时取终点）
+    pub fn point_at(&self, distance: f32) -> Vec2 {
+        let mut remaining = distance;
+        for segment in self.points.windows(2) {
+            let segment_length = segment[0].distance(segment[1]);
+            if remaining <= segment_length {
+                return segment[0].lerp(segment[1], remaining / segment_length.max(f32::EPSILON));
+            }
+            remaining -= segment_length;
+        }
+        self.points[self.points.len() - 1]
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LinkOccupancy {
+    pub source_idx: usize,
+    pub target_idx: usize,
+    pub occupied: u32,
+    /// 热度轨迹模式下窗口内平均占用的波长数
+    pub averaged: Option<f32>,
+}
+
+impl LinkOccupancy {
+    /// 当前时刻的占用率（0 到 1）
+    pub fn fraction(&self, num_channels: u32) -> f32 {
+        (self.occupied as f32 / num_channels.max(1) as f32).min(1.0)
+    }
+
+    /// 热度轨迹模式下的平均占用率，瞬时模式下与 fraction 相同
+    pub fn averaged_fraction(&self, num_channels: u32) -> f32 {
+        match self.averaged {
+            Some(averaged) => (averaged / num_channels.max(1) as f32).min(1.0),
+            None => self.fraction(num_channels),
+        }
+    }
+}
+
+fn with_alpha(color: [f32; 4], alpha: f32) -> [f32; 4] {
+    [color[0], color[1], color[2], color[3] * alpha]
+}
+
+/// 把半透明的图元（每个 stride 个顶点）稳定地移到前面，先于不透明的图元绘制，不透明的线路不会被半透明的盖住变暗。
+/// pick_ids 与顶点一一对应，一起重新排列
+fn order_translucent_first<V: Copy>(vertices: &mut Vec<V>, pick_ids: &mut Vec<u32>, stride: usize, is_translucent: impl Fn(&V) -> bool) {
+    debug_assert_eq!(vertices.len(), pick_ids.len());
+    let primitive_count = vertices.len() / stride;
+    let (translucent, opaque): (Vec<usize>, Vec<usize>) = (0..primitive_count).partition(|&i| is_translucent(&vertices[i * stride]));
+    if translucent.is_empty() || opaque.is_empty() {
+        return;
+    }
+    let order: Vec<usize> = translucent.into_iter().chain(opaque).collect();
+    *vertices = order.iter().flat_map(|&i| vertices[i * stride..(i + 1) * stride].iter().copied()).collect();
+    *pick_ids = order.iter().flat_map(|&i| pick_ids[i * stride..(i + 1) * stride].iter().copied()).collect();
+}
+
+/// 占用率的颜色：空闲为绿色，满载为红色
+pub fn occupancy_color(fraction: f32) -> [f32; 4] {
+    LinearRgba::from(Oklcha::lch(0.65, 0.15, 145.0 - 120.0 * fraction)).to_f32_array()
+}
+
+/// 分多帧生成几何（大型拓扑）时的进度：活跃服务按 vertex_budget 分批生成，
+/// 聚合 LOD 的链路占用率在所有服务处理完之后才能生成
+#[derive(Debug)]
+pub struct GeometryBuild {
+    services: Vec<ServiceData>,
+    next_service: usize,
+    link_occupancy: HashMap<(usize, usize), u32>,
+    /// 活跃服务中码率（取对数前）的最小值和最大值；均匀宽度或没有正码率时为 None
+    bit_rate_range: Option<(f32, f32)>,
+}
+
+impl GeometryBuild {
+    /// 已处理的服务数和活跃服务总数
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next_service, self.services.len())
+    }
+
+    /// 当前时刻的活跃服务（按 service_id 排序），与 State::active_services_at 的结果相同，
+    /// 同一帧中需要活跃服务的其他地方（例如服务列表面板）可以直接使用而不必再次重建状态
+    pub fn services(&self) -> &[ServiceData] {
+        &self.services
+    }
+
+    /// 按码率加粗时服务线路的世界单位宽度：码率取对数后在活跃服务的范围内线性插值到像素宽度，
+    /// 码率缺失（<= 0）的服务使用最小宽度。均匀宽度模式下返回 None（服务以 1 像素的线段绘制）
+    fn service_thickness(&self, service: &ServiceData, inputs: &GeometryInputs) -> Option<f32> {
+        if inputs.thickness_mode != ThicknessMode::Bitrate {
+            return None;
+        }
+        let fraction = match self.bit_rate_range {
+            Some((min, max)) if service.bit_rate > 0.0 && max > min => (service.bit_rate / min).ln() / (max / min).ln(),
+            Some(_) if service.bit_rate > 0.0 => 1.0, // 所有服务码率相同
+            _ => 0.0,
+        };
+        let width_px = BITRATE_MIN_WIDTH_PX + (BITRATE_MAX_WIDTH_PX - BITRATE_MIN_WIDTH_PX) * fraction.clamp(0.0, 1.0);
+        Some(width_px / inputs.world_to_pixels.max(f32::EPSILON))
+    }
+}
+
+impl SceneGeometry {
+    /// 一端节点隐藏的链路或服务线段
+    fn is_hop_hidden(&self, (source_idx, target_idx): (usize, usize)) -> bool {
+        self.is_node_hidden(source_idx) || self.is_node_hidden(target_idx)
+    }
+
+    /// 开启边绑定时把 start → end 弯成沿 source → target 链路绑定形状的折线，否则（或链路没有绑定形状时）只有两个端点
+    fn lane_points(&self, inputs: &GeometryInputs, source_idx: usize, target_idx: usize, start: Vec2, end: Vec2) -> Vec<Vec2> {
+        inputs.edge_bundles
+            .and_then(|bundles| bundles.bend(
+                source_idx,
+                target_idx,
+                Vec2::from_array(self.circle_instances[source_idx].position),
+                Vec2::from_array(self.circle_instances[target_idx].position),
+                start,
+                end,
+            ))
+            .unwrap_or_else(|| vec![start, end])
+    }
+
+    /// 为一条折线（一跳服务线路或转接弧线）的每一段登记拾取线段，GPU 拾取使用返回的第一段的 ID
+    fn push_pick_polyline(&mut self, service_id: i32, segment_index: usize, points: &[Vec2]) -> u32 {
+        let pick_id = segment_pick_id(self.pick_segments.len());
+        match points {
+            &[point] => self.pick_segments.push(PickSegment { service_id, segment_index, start: point, end: point }),
+            _ => self.pick_segments.extend(points.windows(2).map(|segment| PickSegment { service_id, segment_index, start: segment[0], end: segment[1] })),
+        }
+        pick_id
+    }
+
+    /// 以世界坐标绘制的全部文字标签：节点标签在前，跳数标签在后
+    pub fn world_text_labels(&self) -> impl Iterator<Item = &TextLabel> {
+        self.node_labels.iter().chain(self.hop_labels.iter())
+    }
+
+    /// 多条高亮服务经过同一节点时，跳数合并到同一个标签中（例如 "1,3"），避免文字重叠
+    fn push_hop_label(&mut self, hop: usize, position: Vec2, radius_scale: f32) {
+        let content = hop.to_string();
+        let position: [f32; 2] = position.into();
+        match self.hop_labels.iter_mut().find(|label| label.position == position) {
+            Some(label) => {
+                if !label.content.split(',').any(|existing| existing == content) {
+                    label.content.push(',');
+                    label.content.push_str(&content);
+                }
+            }
+            None => self.hop_labels.push(TextLabel { content, radius_scale, position, detail_lines: Vec::new() }),
+        }
+    }
+
+    /// 根据当前时间轴选择重新生成所有链接和服务的线条，并重新着色节点（节点位置不变）。分三步：
+    /// begin_regenerate 清空线路、重新着色节点、生成链路边界并收集当前时刻的活跃服务
+    /// （事件只重放一次，节点高亮和服务线路共用同一份重建结果）；
+    /// push_services 可分批调用；finish_regenerate 生成聚合四边形和预览服务
+    pub fn begin_regenerate(&mut self, inputs: &GeometryInputs) -> GeometryBuild {
+        self.line_vertices.clear();
+        self.highlight_line_vertices.clear(); // 清除高亮线条数据
+        self.link_occupancy_vertices.clear();
+        self.preview_line_vertices.clear();
+        self.annotation_line_vertices.clear();
+        self.annotation_labels.clear();
+        self.hop_labels.clear();
+        self.link_occupancy.clear();
+        self.flow_paths.clear();
+        self.line_pick_ids.clear();
+        self.highlight_line_pick_ids.clear();
+        self.service_quad_vertices.clear();
+        self.service_quad_pick_ids.clear();
+        self.pick_segments.clear();
+        self.hidden_nodes = inputs.hidden_nodes.to_vec();
+
+        let reconstructed_service_dict = reconstruct_state_at_time(inputs.events, inputs.current_time, inputs.service_interval);
+
+        // 追踪所有被高亮服务触及的节点
+        let mut nodes_in_highlighted_services: std::collections::HashSet<usize> = std::collections::HashSet::new();
+        if let Some(highlight_ids) = inputs.highlight_service_ids {
+            for service_id in highlight_ids {
+                if let Some(service) = reconstructed_service_dict.get(service_id) {
+                    // Collect all nodes in path for highlighting
+                    nodes_in_highlighted_services.extend(service_path_indices(service, inputs.node_id_to_idx).iter().copied());
+                }
+            }
+        }
+
+        // --- 1. 更新节点颜色 ---
+        // 首先恢复所有节点为所属组的颜色，不属于任何组的节点为默认颜色
+        let default_color = LinearRgba::from(Srgba::rgb_u8(0x00, 0x5d, 0x5d)).to_f32_array();
+        for (idx, instance) in self.circle_instances.iter_mut().enumerate() {
+            instance.color = inputs.node_group_colors.get(idx).copied().flatten().unwrap_or(default_color);
+            instance.glow = 0.0;
+        }
+        // 然后根据高亮列表重新着色，并加上光晕
+        for instance_idx in nodes_in_highlighted_services {
+            if let Some(instance) = self.circle_instances.get_mut(instance_idx) {
+                instance.color = inputs.highlight_style.node_color;
+                instance.glow = 1.0;
+            }
+        }
+
+
+        // --- 2. 渲染固定的链路边界 (普通细线) ---
+        for &(source_idx, target_idx) in inputs.connection_endpoints {
+            // 引用不存在节点的链路在加载时已经警告过；一端隐藏的链路不绘制
+            if let Some((source_idx, target_idx)) = known_hop(source_idx, target_idx).filter(|&hop| !self.is_hop_hidden(hop)) {
+                let link_boundary_color = LinearRgba::from(Srgba::rgb_u8(180, 180, 180));
+                let source_position_center = Vec2::from_array(self.circle_instances[source_idx].position);
+                let destination_position_center = Vec2::from_array(self.circle_instances[target_idx].position);
+                let dir_vec = destination_position_center - source_position_center;
+                let length = dir_vec.length();
+
+                if length < f32::EPSILON {
+                    continue;
+                }
+
+                let normalized_dir = dir_vec.normalize();
+                let source_radius_outward = normalized_dir * lane_radius(&self.circle_instances[source_idx]);
+                let destination_radius_outward = normalized_dir * lane_radius(&self.circle_instances[target_idx]);
+
+                let rotate_vector = Vec2::from_angle(inputs.channel_plan.boundary_angle);
+                let reverse_rotate_vector = Vec2::from_angle(-inputs.channel_plan.boundary_angle);
+
+                let boundaries = [
+                    (source_position_center + source_radius_outward.rotate(rotate_vector), destination_position_center - destination_radius_outward.rotate(reverse_rotate_vector)),
+                    (source_position_center + source_radius_outward.rotate(reverse_rotate_vector), destination_position_center - destination_radius_outward.rotate(rotate_vector)),
+                ];
+                for (boundary_start, boundary_end) in boundaries {
+                    for segment in self.lane_points(inputs, source_idx, target_idx, boundary_start, boundary_end).windows(2) {
+                        self.line_vertices.push(LineVertex { position: segment[0].into(), color: link_boundary_color.to_f32_array() });
+                        self.line_vertices.push(LineVertex { position: segment[1].into(), color: link_boundary_color.to_f32_array() });
+                    }
+                }
+            }
+        }
+
+        // 链路边界不可拾取
+        self.line_pick_ids.resize(self.line_vertices.len(), PICK_ID_NONE);
+
+        // --- 3. 当前时间活跃的服务，线条由 push_services 生成 ---
+        // 按 service_id 排序：HashMap 的遍历顺序每次都不同，排序后重叠线路的绘制顺序和拾取 ID 在每次重新生成时保持一致
+        let mut services: Vec<ServiceData> = reconstructed_service_dict
+            .into_values()
+            .filter(|service| inputs.service_interval.contains(service.arrival_time, service.departure_time, inputs.current_time))
+            .collect();
+        services.sort_by_key(|service| service.service_id);
+        let mut bit_rate_range: Option<(f32, f32)> = None;
+        if inputs.thickness_mode == ThicknessMode::Bitrate {
+            for bit_rate in services.iter().map(|service| service.bit_rate).filter(|&bit_rate| bit_rate > 0.0 && bit_rate.is_finite()) {
+                let (min, max) = bit_rate_range.get_or_insert((bit_rate, bit_rate));
+                *min = min.min(bit_rate);
+                *max = max.max(bit_rate);
+            }
+        }
+        GeometryBuild { services, next_service: 0, link_occupancy: HashMap::new(), bit_rate_range }
+    }
+
+    /// 生成下一批服务线条，新增顶点数达到 vertex_budget 后停止；所有服务都已处理时返回 true
+    pub fn push_services(&mut self, build: &mut GeometryBuild, inputs: &GeometryInputs, vertex_budget: usize) -> bool {
+        let vertex_count = |geometry: &Self| {
+            geometry.line_vertices.len() + geometry.highlight_line_vertices.len() + geometry.service_quad_vertices.len()
+        };
+        let start_vertex_count = vertex_count(self);
+        while let Some(service) = build.services.get(build.next_service) {
+            if vertex_count(self) - start_vertex_count >= vertex_budget {
+                return false;
+            }
+            build.next_service += 1;
+            let thickness = build.service_thickness(service, inputs);
+            self.push_service(service, inputs, thickness, &mut build.link_occupancy);
+        }
+        true
+    }
+
+    /// thickness 为 Some 时未高亮的服务以该宽度（世界单位）的四边形绘制，见 GeometryBuild::service_thickness
+    fn push_service(&mut self, service: &ServiceData, inputs: &GeometryInputs, thickness: Option<f32>, link_occupancy: &mut HashMap<(usize, usize), u32>) {
+        let num_channels = inputs.channel_plan.max_wavelengths;
+        let spread_angle = inputs.channel_plan.spread_angle;
+        let is_aggregated_lod = inputs.lod_level == LodLevel::Aggregated;
+        let highlight_dash_pattern = inputs.highlight_line_style.dash_pattern();
+
+        let wavelength = service.wavelength;
+        // GSNR 模式中没有 GSNR 数据的服务为中性灰：色度乘以 0
+        let (hue_color, chroma_scale) = match inputs.color_mode {
+            ColorMode::Wavelength => (wavelength_hue(wavelength, num_channels), 1.0),
+            ColorMode::Gsnr { ramp } => match service.gsnr_margin_db() {
+                Some(margin_db) => (ramp.hue(margin_db), 1.0),
+                None => (0.0, 0.0),
+            },
+        };
+
+        let highlight_position = inputs.highlight_service_ids
+            .and_then(|highlight_service_id_list| highlight_service_id_list.iter().position(|&srv_id| srv_id == service.service_id));
+        let is_highlighted = highlight_position.is_some();
+
+        // 每条链路上占用的波长数：聚合 LOD 的四边形和容量条共用
+        let path_indices = service_path_indices(service, inputs.node_id_to_idx);
+        for key in path_link_keys(&path_indices) {
+            *link_occupancy.entry(key).or_insert(0) += 1;
+        }
+        if is_aggregated_lod && !is_highlighted {
+            return;
+        }
+
+        let is_moved_service = !inputs.highlight_palette && highlight_position.is_some_and(|pos| pos > 0);
+
+        // 普通、变暗和高亮三种亮度规则：按波长着色时从查找表中取，否则（GSNR 模式或查找表已过期）逐条计算
+        let shade_color = |shade: ServiceShade| {
+            let lut_color = match inputs.color_mode {
+                ColorMode::Wavelength => inputs.wavelength_colors.get(wavelength, num_channels, &inputs.highlight_style, shade),
+                ColorMode::Gsnr { .. } => None,
+            };
+            lut_color.unwrap_or_else(|| {
+                let (lightness, chroma) = shade.lightness_chroma(&inputs.highlight_style);
+                LinearRgba::from(Oklcha::lch(lightness, chroma * chroma_scale, hue_color)).to_f32_array()
+            })
+        };
+        let mut service_color_f32 = if let Some(pos) = highlight_position.filter(|_| inputs.highlight_palette) {
+            // 多选：每条服务一个固定色相，重叠的路径也能区分
+            LinearRgba::from(Oklcha::lch(inputs.highlight_style.service_lightness, 0.2, MULTI_SELECT_HUES[pos % MULTI_SELECT_HUES.len()])).to_f32_array()
+        } else if is_moved_service {
+            // 被碎片整理移动的服务：更浅、饱和度更低，与碎片整理服务本身区分
+            LinearRgba::from(Oklcha::lch(inputs.highlight_style.moved_service_lightness, 0.08 * chroma_scale, hue_color)).to_f32_array()
+        } else if is_highlighted {
+            // 高亮服务的颜色可以更鲜明，例如保持高饱和度，但亮度适中，或者采用完全不同的颜色
+            shade_color(ServiceShade::Highlighted) // 更亮的颜色
+        } else if let Some(diff) = inputs.service_diff {
+            // 差异模式：新出现的为绿色，波长或路径变化的为橙色，未变化的变暗
+            let diff_color = if diff.appeared.contains(&service.service_id) {
+                Oklcha::lch(0.72, 0.2, 145.0)
+            } else if diff.changed.contains(&service.service_id) {
+                Oklcha::lch(0.75, 0.17, 60.0)
+            } else {
+                Oklcha::new(0.5, 0.04 * chroma_scale, hue_color, DIFF_UNCHANGED_ALPHA)
+            };
+            LinearRgba::from(diff_color).to_f32_array()
+        } else if inputs.highlight_service_ids.is_none() {
+            shade_color(ServiceShade::Normal)
+        } else {
+            shade_color(ServiceShade::Dimmed)
+        };
+        // 如果不是高亮服务，亮度调整回默认的0.6。
+        // `service_color_f32` will be determined by `is_highlighted`.
+        if !is_highlighted {
+            service_color_f32[3] *= inputs.opacity_mode.alpha(service.utilization); // 与差异模式的半透明相乘
+        }
+
+        let wavelength_rotate_angle = wavelength_rotate_angle(wavelength, num_channels, spread_angle);
+
+        // 高亮路径沿途的累计距离（包括节点内部的转接弧线），使虚线图案在相邻线段之间连续
+        let mut path_distance = 0.0;
+        // 流动动画的折线：按顺序连接每一跳线路的起止点和节点内部转接弧线上的点
+        let mut flow_points = Vec::new();
+        // 上一跳线路的终点和方向；上一跳被跳过时为 None，不绘制转接弧线
+        let mut previous_lane: Option<(Vec2, Vec2)> = None;
+
+        for (i, hop) in path_indices.windows(2).enumerate() {
+            // 路径中不存在的节点在加载事件时已经警告过；经过隐藏节点的线段跳过
+            if let Some((source_idx, target_idx)) = known_hop(hop[0], hop[1]).filter(|&hop| !self.is_hop_hidden(hop)) {
+                let (source, target) = (self.circle_instances[source_idx], self.circle_instances[target_idx]);
+                let Some((service_start_pos, service_end_pos)) = service_lane_endpoints(&source, &target, wavelength_rotate_angle) else {
+                    previous_lane = None;
+                    continue;
+                };
+                // 开启边绑定时线路是沿绑定形状的折线，转接弧线与其首尾两段相切
+                let lane_points = self.lane_points(inputs, source_idx, target_idx, service_start_pos, service_end_pos);
+                let (lane_start_dir, lane_end_dir) = polyline_end_directions(&lane_points);
+
+                // 节点内部的转接弧线：从上一跳线路的终点平滑过渡到这一跳线路的起点，归入进入该节点的那一跳
+                if let Some((entry_pos, entry_dir)) = previous_lane {
+                    let arc = junction_arc(entry_pos, entry_dir, service_start_pos, lane_start_dir);
+                    let pick_id = self.push_pick_polyline(service.service_id, i - 1, &arc);
+                    if inputs.flow_animation && arc.len() > 2 {
+                        flow_points.extend_from_slice(&arc[1..arc.len() - 1]);
+                    }
+
+                    if is_highlighted {
+                        let thickness = inputs.highlight_style.thickness;
+                        // 节点内的转接弧线转弯很急，斜接在 MITER_LIMIT 处截断后拐角外侧缺一块：在弧线的每个点补上圆形连接
+                        // （前后两跳在节点圆周上的端点重合、原路折返时弧线只有这一个点）
+                        let mut join_distance = path_distance;
+                        for (k, &point) in arc.iter().enumerate() {
+                            if k > 0 {
+                                join_distance += arc[k - 1].distance(point);
+                            }
+                            push_round_join(&mut self.highlight_line_vertices, point, service_color_f32, thickness, join_distance, highlight_dash_pattern);
+                        }
+                        let style = StrokeStyle { color: service_color_f32, thickness, dash_pattern: highlight_dash_pattern };
+                        path_distance += push_thick_polyline(&mut self.highlight_line_vertices, &arc, entry_dir, lane_start_dir, style, path_distance);
+                        self.highlight_line_pick_ids.resize(self.highlight_line_vertices.len(), pick_id);
+                    } else if let Some(thickness) = thickness {
+                        let style = StrokeStyle { color: service_color_f32, thickness, dash_pattern: ThickLineVertex::SOLID };
+                        push_thick_polyline(&mut self.service_quad_vertices, &arc, entry_dir, lane_start_dir, style, 0.0);
+                        self.service_quad_pick_ids.resize(self.service_quad_vertices.len(), pick_id);
+                    } else {
+                        for segment in arc.windows(2) {
+                            self.line_vertices.push(LineVertex { position: segment[0].into(), color: service_color_f32 });
+                            self.line_vertices.push(LineVertex { position: segment[1].into(), color: service_color_f32 });
+                        }
+                        self.line_pick_ids.resize(self.line_vertices.len(), pick_id);
+                    }
+                }
+                previous_lane = Some((service_end_pos, lane_end_dir));
+
+                let pick_id = self.push_pick_polyline(service.service_id, i, &lane_points);
+                if inputs.flow_animation {
+                    flow_points.extend_from_slice(&lane_points);
+                }
+
+                if is_highlighted {
+                    path_distance += push_thick_lane(&mut self.highlight_line_vertices, &lane_points, service_color_f32, inputs.highlight_style.thickness, path_distance, highlight_dash_pattern);
+                    self.highlight_line_pick_ids.resize(self.highlight_line_vertices.len(), pick_id);
+                    self.push_hop_label(i, Vec2::from_array(source.position), lane_radius(&source));
+                    if i == service.path.len() - 2 {
+                        self.push_hop_label(i + 1, Vec2::from_array(target.position), lane_radius(&target));
+                    }
+                } else if let Some(thickness) = thickness {
+                    push_thick_lane(&mut self.service_quad_vertices, &lane_points, service_color_f32, thickness, 0.0, ThickLineVertex::SOLID);
+                    self.service_quad_pick_ids.resize(self.service_quad_vertices.len(), pick_id);
+                } else {
+                    for segment in lane_points.windows(2) {
+                        self.line_vertices.push(LineVertex { position: segment[0].into(), color: service_color_f32 });
+                        self.line_vertices.push(LineVertex { position: segment[1].into(), color: service_color_f32 });
+                    }
+                    self.line_pick_ids.resize(self.line_vertices.len(), pick_id);
+                }
+            } else {
+                previous_lane = None;
+            }
+        }
+
+        if let Some(flow_path) = FlowPath::new(flow_points, LinearRgba::from(Oklcha::lch(0.88, 0.1, hue_color)).to_f32_array()) {
+            self.flow_paths.push(flow_path);
+        }
+    }
+
+    /// 分批生成的最后一步：聚合 LOD 的链路占用率、预览服务、最短路径和测量线
+    pub fn finish_regenerate(&mut self, build: GeometryBuild, inputs: &GeometryInputs) {
+        let num_channels = inputs.channel_plan.max_wavelengths;
+        let spread_angle = inputs.channel_plan.spread_angle;
+        let is_aggregated_lod = inputs.lod_level == LodLevel::Aggregated;
+        // 预览、残影、路径和测量线的宽度按节点半径缩放（常量针对默认半径）
+        let line_scale = inputs.node_radius / BASE_NODE_RADIUS;
+
+        // 差异模式中未变化的服务是半透明的，先绘制；高亮线路本来就在单独的缓冲区中最后绘制
+        order_translucent_first(&mut self.line_vertices, &mut self.line_pick_ids, 2, |vertex| vertex.color[3] < 1.0);
+        order_translucent_first(&mut self.service_quad_vertices, &mut self.service_quad_pick_ids, 6, |vertex| vertex.color[3] < 1.0);
+
+        // --- 4. 每条链路的占用波长数；聚合 LOD 和热度轨迹模式下每条链路一个按占用率着色的四边形 ---
+        let link_key = |service: &ServiceData, hop: usize| {
+            let path_indices = service_path_indices(service, inputs.node_id_to_idx);
+            let (source_idx, target_idx) = known_hop(path_indices[hop], path_indices[hop + 1])?;
+            Some(undirected_link_key(source_idx, target_idx))
+        };
+        let averaged_occupancy = inputs.heat_trail_window.map(|window| {
+            time_averaged_link_occupancy(inputs.events, inputs.event_checkpoints, inputs.current_time - window, inputs.current_time, link_key)
+        });
+        for &(source_idx, target_idx) in inputs.connection_endpoints {
+            let Some((source_idx, target_idx)) = known_hop(source_idx, target_idx) else {
+                continue; // 加载时已经警告过
+            };
+            let key = undirected_link_key(source_idx, target_idx);
+            let occupied = build.link_occupancy.get(&key).copied().unwrap_or(0);
+            let averaged = averaged_occupancy.as_ref().map(|averages| averages.get(&key).copied().unwrap_or(0.0));
+            self.link_occupancy.push(LinkOccupancy { source_idx, target_idx, occupied, averaged });
+        }
+        if is_aggregated_lod || averaged_occupancy.is_some() {
+            // 非聚合 LOD 下四边形覆盖在服务线路上，使用半透明颜色
+            let alpha = if is_aggregated_lod { 1.0 } else { HEAT_TRAIL_OVERLAY_ALPHA };
+            for link in &self.link_occupancy {
+                if self.is_hop_hidden((link.source_idx, link.target_idx)) {
+                    continue;
+                }
+                let (source, target) = (&self.circle_instances[link.source_idx], &self.circle_instances[link.target_idx]);
+                let Some((start_pos, end_pos)) = link_center_segment(source, target) else {
+                    continue; // 节点重叠，没有可绘制的链路段
+                };
+                let thickness = lane_radius(source).min(lane_radius(target));
+                let points = self.lane_points(inputs, link.source_idx, link.target_idx, start_pos, end_pos);
+
+                push_thick_lane(
+                    &mut self.link_occupancy_vertices,
+                    &points,
+                    with_alpha(occupancy_color(link.averaged_fraction(num_channels)), alpha),
+                    thickness,
+                    0.0,
+                    ThickLineVertex::SOLID,
+                );
+            }
+        }
+
+        // --- 5. 预览服务：与时间无关，以柔和颜色的虚线绘制 ---
+        let preview_color = LinearRgba::from(Srgba::rgba_u8(170, 170, 200, 220)).to_f32_array();
+        for service in inputs.preview_services {
+            let wavelength_rotate_angle = wavelength_rotate_angle(service.wavelength, num_channels, spread_angle);
+            let mut path_distance = 0.0;
+            for hop in service.path.windows(2) {
+                let (Some(&source_idx), Some(&target_idx)) = (
+                    inputs.node_id_to_idx.get(&hop[0]),
+                    inputs.node_id_to_idx.get(&hop[1]),
+                ) else {
+                    log::warn!("Preview service {} path references non-existent node ID. Segment: {} -> {}", service.service_id, hop[0], hop[1]);
+                    continue;
+                };
+                let Some((start_pos, end_pos)) = service_lane_endpoints(
+                    &self.circle_instances[source_idx], &self.circle_instances[target_idx], wavelength_rotate_angle,
+                ) else {
+                    continue;
+                };
+                let points = self.lane_points(inputs, source_idx, target_idx, start_pos, end_pos);
+                path_distance += push_thick_lane(
+                    &mut self.preview_line_vertices, &points, preview_color,
+                    PREVIEW_LINE_THICKNESS * line_scale, path_distance, ThickLineVertex::DASHED,
+                );
+            }
+        }
+
+        // --- 5.5 差异模式：t1 时活跃、现在已离开的服务，按 t1 时的路径和波长以红色虚线绘制残影 ---
+        if let Some(diff) = inputs.service_diff.filter(|_| !is_aggregated_lod) {
+            let ghost_color = LinearRgba::from(Oklcha::new(0.62, 0.2, 25.0, 0.7)).to_f32_array();
+            for service in &diff.departed {
+                let wavelength_rotate_angle = wavelength_rotate_angle(service.wavelength, num_channels, spread_angle);
+                let mut path_distance = 0.0;
+                for hop in service_path_indices(service, inputs.node_id_to_idx).windows(2) {
+                    let Some((source_idx, target_idx)) = known_hop(hop[0], hop[1]) else {
+                        continue; // 与活跃服务一样，事件加载时已经报告过未知节点
+                    };
+                    let Some((start_pos, end_pos)) = service_lane_endpoints(
+                        &self.circle_instances[source_idx], &self.circle_instances[target_idx], wavelength_rotate_angle,
+                    ) else {
+                        continue;
+                    };
+                    let points = self.lane_points(inputs, source_idx, target_idx, start_pos, end_pos);
+                    path_distance += push_thick_lane(
+                        &mut self.preview_line_vertices, &points, ghost_color,
+                        DIFF_GHOST_LINE_THICKNESS * line_scale, path_distance, ThickLineVertex::DASHED,
+                    );
+                }
+            }
+        }
+
+        // --- 5.6 被阻塞的需求：窗口内的需求以红色虚线直接连接源和目的节点（没有路径），按节点 ID 查找 ---
+        if let Some(window) = inputs.blocked_demand_window {
+            let blocked_color = LinearRgba::from(Oklcha::new(0.6, 0.22, 25.0, 0.85)).to_f32_array();
+            let demands = blocked_demands_in_range(inputs.blocked_demands, inputs.current_time - window / 2.0, inputs.current_time + window / 2.0);
+            for demand in demands {
+                let (Some(&source_idx), Some(&target_idx)) = (
+                    inputs.node_id_to_idx.get(&demand.source_id),
+                    inputs.node_id_to_idx.get(&demand.destination_id),
+                ) else {
+                    continue; // 加载时已经记录到验证报告
+                };
+                let Some((start_pos, end_pos)) = link_center_segment(&self.circle_instances[source_idx], &self.circle_instances[target_idx]) else {
+                    continue;
+                };
+                push_thick_line_segment(
+                    &mut self.preview_line_vertices, start_pos, end_pos, blocked_color,
+                    BLOCKED_DEMAND_LINE_THICKNESS * line_scale, 0.0, ThickLineVertex::DASHED,
+                );
+            }
+        }
+
+        // --- 6. 最短路径：沿链路中心线绘制，并在路径中点标注跳数 ---
+        if let Some(path) = inputs.highlight_path {
+            let path_color = LinearRgba::from(Oklcha::new(0.7, 0.2, 330.0, 0.8)).to_f32_array();
+            let mut path_distance = 0.0;
+            for hop in path.windows(2) {
+                let (Some(source), Some(target)) = (self.circle_instances.get(hop[0]), self.circle_instances.get(hop[1])) else {
+                    continue;
+                };
+                let Some((start_pos, end_pos)) = link_center_segment(source, target) else {
+                    continue; // 节点重叠，没有可绘制的链路段
+                };
+                let points = self.lane_points(inputs, hop[0], hop[1], start_pos, end_pos);
+                path_distance += push_thick_lane(
+                    &mut self.annotation_line_vertices,
+                    &points,
+                    path_color,
+                    PATH_LINE_THICKNESS * line_scale,
+                    path_distance,
+                    ThickLineVertex::SOLID,
+                );
+            }
+
+            let hop_count = path.len().saturating_sub(1);
+            // 奇数跳时标签放在中间一跳的中点，偶数跳时放在中间的节点上
+            let middle = hop_count / 2;
+            let node_position = |i: usize| path.get(i)
+                .and_then(|&idx| self.circle_instances.get(idx))
+                .map(|instance| Vec2::from_array(instance.position));
+            let label_position = if hop_count % 2 == 1 {
+                node_position(middle).zip(node_position(middle + 1)).map(|(a, b)| (a + b) / 2.0)
+            } else {
+                node_position(middle)
+            };
+            if let Some(label_position) = label_position {
+                self.annotation_labels.push(TextLabel {
+                    content: format!("{} hop{}", hop_count, if hop_count == 1 { "" } else { "s" }),
+                    radius_scale: inputs.node_radius,
+                    position: label_position.into(),
+                    detail_lines: Vec::new(),
+                });
+            }
+        }
+
+        // --- 7. 测量线：两个端点之间的虚线，中点标注距离和跳数 ---
+        if let Some(measurement) = inputs.measurement
+            && let (Some(from), Some(to)) = (self.circle_instances.get(measurement.from), self.circle_instances.get(measurement.to))
+        {
+            let from_pos = Vec2::from_array(from.position);
+            let to_pos = Vec2::from_array(to.position);
+            let measurement_color = LinearRgba::from(Srgba::rgb_u8(240, 240, 240)).to_f32_array();
+            push_thick_line_segment(
+                &mut self.annotation_line_vertices, from_pos, to_pos, measurement_color,
+                MEASUREMENT_LINE_THICKNESS * line_scale, 0.0, ThickLineVertex::DASHED,
+            );
+            let hops = match measurement.hop_count {
+                Some(hop_count) => format!("{} hop{}", hop_count, if hop_count == 1 { "" } else { "s" }),
+                None => "not connected".to_string(),
+            };
+            self.annotation_labels.push(TextLabel {
+                content: format!("{:.1}\n{}", from_pos.distance(to_pos), hops),
+                radius_scale: inputs.node_radius,
+                position: ((from_pos + to_pos) / 2.0).into(),
+                detail_lines: Vec::new(),
+            });
+        }
+
+        self.pick_segment_grid = SegmentGrid::new(&self.pick_segments);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::bench_support::GeometryFixture;
+
+    /// 高亮第一个碎片整理链，时间位于第一个重分配事件，使高亮服务处于活跃状态
+    fn highlighted_fixture() -> GeometryFixture {
+        let mut fixture = GeometryFixture::new(64, true, 7);
+        fixture.time = fixture.events().iter()
+            .find(|event| matches!(event, AnyEvent::Reallocation { .. }))
+            .expect("synthetic timeline has a reallocation")
+            .timestamp();
+        fixture
+    }
+
+    #[test]
+    fn hop_labels_are_rebuilt_on_each_regeneration() {
+        let fixture = highlighted_fixture();
+        let mut geometry = fixture.empty_geometry();
+        fixture.regenerate_into(&mut geometry, usize::MAX);
+        let label_count = geometry.hop_labels.len();
+        assert!(label_count > 0, "highlighted services should produce hop labels");
+        for _ in 0..3 {
+            fixture.regenerate_into(&mut geometry, usize::MAX);
+            assert_eq!(geometry.hop_labels.len(), label_count);
+        }
+    }
+
+    /// 分帧生成（共用 begin_regenerate 的一次重建）与一次性生成的线路和拾取 ID 逐位相同，
+    /// 重建的活跃服务与单独调用 reconstruct_state_at_time（服务列表面板原来的做法）一致
+    #[test]
+    fn chunked_regeneration_matches_single_pass() {
+        let fixture = highlighted_fixture();
+        let mut single_pass = fixture.empty_geometry();
+        let service_ids = fixture.regenerate_into(&mut single_pass, usize::MAX);
+        let mut chunked = fixture.empty_geometry();
+        assert_eq!(fixture.regenerate_into(&mut chunked, 64), service_ids);
+        assert!(!single_pass.highlight_line_vertices.is_empty());
+
+        let bytes = |vertices: &[LineVertex]| bytemuck::cast_slice::<_, u8>(vertices).to_vec();
+        let thick_bytes = |vertices: &[ThickLineVertex]| bytemuck::cast_slice::<_, u8>(vertices).to_vec();
+        assert_eq!(bytes(&chunked.line_vertices), bytes(&single_pass.line_vertices));
+        assert_eq!(thick_bytes(&chunked.highlight_line_vertices), thick_bytes(&single_pass.highlight_line_vertices));
+        assert_eq!(chunked.line_pick_ids, single_pass.line_pick_ids);
+        assert_eq!(chunked.highlight_line_pick_ids, single_pass.highlight_line_pick_ids);
+        assert_eq!(chunked.service_quad_pick_ids, single_pass.service_quad_pick_ids);
+
+        let semantics = ServiceIntervalSemantics::default();
+        let mut reconstructed: Vec<i32> = reconstruct_state_at_time(fixture.events(), fixture.time, semantics)
+            .into_values()
+            .filter(|service| semantics.contains(service.arrival_time, service.departure_time, fixture.time))
+            .map(|service| service.service_id)
+            .collect();
+        reconstructed.sort();
+        assert_eq!(service_ids, reconstructed);
+    }
+
+    /// 半线宽为 1 的折线顶点
+    fn stroke(points: &[Vec2], start_dir: Vec2, end_dir: Vec2) -> (Vec<ThickLineVertex>, f32) {
+        let mut vertices = Vec::new();
+        let style = StrokeStyle { color: [1.0; 4], thickness: 2.0, dash_pattern: 0 };
+        let length = push_thick_polyline(&mut vertices, points, start_dir, end_dir, style, 0.0);
+        (vertices, length)
+    }
+
+    fn position(vertex: &ThickLineVertex) -> Vec2 {
+        Vec2::from(vertex.position)
+    }
+
+    /// 每个四边形两端的顶点对 (p-, p+) 相距一个线宽
+    fn assert_full_width(vertices: &[ThickLineVertex]) {
+        for quad in vertices.chunks(6) {
+            let start_width = position(&quad[0]).distance(position(&quad[1]));
+            let end_width = position(&quad[5]).distance(position(&quad[2]));
+            assert!((start_width - 2.0).abs() < 1e-5 && (end_width - 2.0).abs() < 1e-5, "{start_width} {end_width}");
+        }
+    }
+
+    #[test]
+    fn polyline_skips_zero_length_segments() {
+        let (a, b) = (Vec2::new(1.0, 1.0), Vec2::new(4.0, 5.0));
+        for points in [&[a, a, b][..], &[a, b, b], &[a, a, b, b]] {
+            let (vertices, length) = stroke(points, Vec2::ZERO, Vec2::ZERO);
+            assert_eq!(vertices.len(), 6, "{points:?}");
+            assert!((length - 5.0).abs() < 1e-5);
+            assert!(vertices.iter().all(|vertex| position(vertex).is_finite()));
+            assert_full_width(&vertices);
+        }
+        let (vertices, length) = stroke(&[a, a], Vec2::ZERO, Vec2::ZERO);
+        assert!(vertices.is_empty());
+        assert_eq!(length, 0.0);
+        assert!(stroke(&[], Vec2::ZERO, Vec2::ZERO).0.is_empty());
+    }
+
+    /// 共线的点：偏移垂直于直线，线宽不变，路径距离连续递增
+    #[test]
+    fn collinear_polyline_has_constant_width() {
+        let points = [Vec2::ZERO, Vec2::new(1.0, 0.0), Vec2::new(3.0, 0.0), Vec2::new(6.0, 0.0)];
+        let (vertices, length) = stroke(&points, Vec2::ZERO, Vec2::ZERO);
+        assert_eq!(vertices.len(), 18);
+        assert!((length - 6.0).abs() < 1e-6);
+        for vertex in &vertices {
+            let p = position(vertex);
+            assert!((p.y.abs() - 1.0).abs() < 1e-6, "{p:?}");
+            assert!((vertex.path_distance - p.x).abs() < 1e-6, "{p:?} {}", vertex.path_distance);
+        }
+    }
+
+    /// 法线可能为 NaN 的输入（原地折返、接近折返、端点方向为 NaN 或零）只产生有限的顶点，斜接长度不超过上限
+    #[test]
+    fn degenerate_normals_stay_finite() {
+        let cases: [(&[Vec2], Vec2); 4] = [
+            (&[Vec2::ZERO, Vec2::new(2.0, 0.0), Vec2::ZERO], Vec2::ZERO),
+            (&[Vec2::ZERO, Vec2::new(2.0, 0.0), Vec2::new(0.0, 1e-3)], Vec2::ZERO),
+            (&[Vec2::ZERO, Vec2::new(2.0, 0.0), Vec2::new(2.0, 2.0)], Vec2::NAN),
+            (&[Vec2::ZERO, Vec2::new(2.0, 0.0), Vec2::new(2.0, 2.0)], Vec2::new(1e-30, 0.0)),
+        ];
+        for (points, dir) in cases {
+            let (vertices, _) = stroke(points, dir, dir);
+            assert_eq!(vertices.len(), 12, "{points:?}");
+            for vertex in &vertices {
+                let p = position(vertex);
+                assert!(p.is_finite() && vertex.path_distance.is_finite(), "{points:?} {dir:?}");
+                let offset = points.iter().map(|point| point.distance(p)).fold(f32::INFINITY, f32::min);
+                assert!(offset <= 4.0 + 1e-4, "{points:?} {p:?}");
+            }
+        }
+    }
+
+    /// 相邻两段在拐点处共用完全相同的两个顶点（p2± 与下一段的 p1±），拐角不留缝隙也不重叠
+    #[test]
+    fn polyline_joins_are_crack_free() {
+        let points = [Vec2::ZERO, Vec2::new(3.0, 0.0), Vec2::new(4.0, 2.0), Vec2::new(1.0, 3.0), Vec2::new(1.0, 3.0), Vec2::new(6.0, 7.0)];
+        let (vertices, _) = stroke(&points, Vec2::ZERO, Vec2::ZERO);
+        assert_eq!(vertices.len(), 4 * 6);
+        for (current, next) in vertices.chunks(6).zip(vertices.chunks(6).skip(1)) {
+            assert_eq!(current[2].position, next[1].position);
+            assert_eq!(current[4].position, next[1].position);
+            assert_eq!(current[5].position, next[0].position);
+            assert_eq!(current[5].path_distance, next[0].path_distance);
+        }
+    }
+
+    /// 端点方向与相邻直线段一致时，折线两端与 push_thick_line_segment 的端点完全重合
+    #[test]
+    fn polyline_ends_match_adjacent_segments() {
+        let points = [Vec2::ZERO, Vec2::new(3.0, 0.0), Vec2::new(3.0, 3.0)];
+        let (vertices, _) = stroke(&points, Vec2::new(1.0, 0.0), Vec2::new(0.0, 1.0));
+        assert_eq!(position(&vertices[0]), Vec2::new(0.0, -1.0));
+        assert_eq!(position(&vertices[1]), Vec2::new(0.0, 1.0));
+        assert_eq!(position(&vertices[11]), Vec2::new(4.0, 3.0));
+        assert_eq!(position(&vertices[8]), Vec2::new(2.0, 3.0));
+    }
+
+    #[test]
+    fn junction_arc_endpoints_and_degenerate_input() {
+        let (entry, exit) = (Vec2::new(0.1, 0.2), Vec2::new(3.3, 1.7));
+        let arc = junction_arc(entry, Vec2::new(1.0, 0.0), exit, Vec2::new(0.0, 1.0));
+        assert_eq!(arc.len(), JUNCTION_ARC_SEGMENTS as usize + 1);
+        assert_eq!((arc[0], arc[arc.len() - 1]), (entry, exit));
+
+        assert_eq!(junction_arc(entry, Vec2::X, entry, Vec2::Y), vec![entry]);
+
+        // 共线的两跳得到直线，点沿直线单调前进
+        let line = junction_arc(Vec2::ZERO, Vec2::X, Vec2::new(3.0, 0.0), Vec2::X);
+        assert!(line.iter().all(|p| p.y == 0.0));
+        assert!(line.windows(2).all(|pair| pair[0].x < pair[1].x));
+
+        // 方向为零（退化的线路）时控制点与端点重合，仍是有限的点，可以直接生成粗线
+        let arc = junction_arc(Vec2::ZERO, Vec2::ZERO, Vec2::new(0.0, 2.0), Vec2::ZERO);
+        assert!(arc.iter().all(|p| p.is_finite()));
+        let (vertices, _) = stroke(&arc, Vec2::ZERO, Vec2::ZERO);
+        assert_eq!(vertices.len(), JUNCTION_ARC_SEGMENTS as usize * 6);
+        assert_full_width(&vertices);
+    }
+
+    /// 边绑定只改变线路的形状：拾取线段对应的 (服务, 跳) 和每条链路的占用与直线绘制时相同，
+    /// 每个顶点的拾取 ID 都指向拾取表中的线段
+    #[test]
+    fn edge_bundling_keeps_pick_and_occupancy_mapping() {
+        let mut fixture = highlighted_fixture();
+        let mut straight = fixture.empty_geometry();
+        fixture.regenerate_into(&mut straight, usize::MAX);
+        fixture.bundle_edges();
+        let mut bundled = fixture.empty_geometry();
+        fixture.regenerate_into(&mut bundled, usize::MAX);
+        assert!(bundled.pick_segments.len() > straight.pick_segments.len(), "bundled lanes have more segments");
+
+        let hops = |geometry: &SceneGeometry| {
+            let mut hops: Vec<(i32, usize)> = geometry.pick_segments.iter().map(|segment| (segment.service_id, segment.segment_index)).collect();
+            hops.sort_unstable();
+            hops.dedup();
+            hops
+        };
+        assert_eq!(hops(&bundled), hops(&straight));
+        let occupancy = |geometry: &SceneGeometry| {
+            geometry.link_occupancy.iter().map(|link| (link.source_idx, link.target_idx, link.occupied)).collect::<Vec<_>>()
+        };
+        assert_eq!(occupancy(&bundled), occupancy(&straight));
+
+        let pick_ids = bundled.line_pick_ids.iter().chain(&bundled.highlight_line_pick_ids).chain(&bundled.service_quad_pick_ids);
+        for &pick_id in pick_ids.filter(|&&pick_id| pick_id != PICK_ID_NONE) {
+            assert!(crate::picking::decode_pick_id(pick_id, &bundled.pick_segments, &[]).is_some(), "{pick_id}");
+        }
+    }
+}
@@ -0,0 +1,202 @@
+// src/scene/graph.rs
+// 拓扑图：由 all_connections 构建的无向邻接表，用于节点之间的最短路径。
+// 链路视为双向；权重为跳数（BFS）或节点间的欧氏距离（Dijkstra）。
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use glam::Vec2;
+
+use super::connection::ConnectionData;
+
+/// 最短路径的边权
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathWeight {
+    /// 每条链路权重为 1，结果为跳数最少的路径
+    #[default]
+    Hops,
+    /// 链路权重为两端节点的欧氏距离（世界坐标）
+    Distance,
+}
+
+impl std::str::FromStr for PathWeight {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "hops" => Ok(PathWeight::Hops),
+            "distance" => Ok(PathWeight::Distance),
+            other => Err(format!("Unknown path weight '{}', expected \"hops\" or \"distance\"", other)),
+        }
+    }
+}
+
+pub struct TopologyGraph {
+    adjacency: Vec<Vec<usize>>,
+}
+
+/// Dijkstra 优先队列中的条目，按距离从小到大出队
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct QueueEntry {
+    distance: f32,
+    node: usize,
+}
+
+impl Eq for QueueEntry {}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance).then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl TopologyGraph {
+    /// 引用不存在节点的链路被忽略（加载拓扑时已经警告过）
+    pub fn new(node_count: usize, node_id_to_idx: &HashMap<String, usize>, connections: &[ConnectionData]) -> Self {
+        let mut adjacency = vec![Vec::new(); node_count];
+        for link in connections {
+            if let (Some(&source_idx), Some(&target_idx)) = (
+                node_id_to_idx.get(&link.from_node),
+                node_id_to_idx.get(&link.to_node),
+            ) {
+                if source_idx == target_idx || source_idx >= node_count || target_idx >= node_count {
+                    continue;
+                }
+                adjacency[source_idx].push(target_idx);
+                adjacency[target_idx].push(source_idx);
+            }
+        }
+        Self { adjacency }
+    }
+
+    /// 从 from 到 to 的最短路径（包含两端的节点索引）；不连通时返回 None。
+    /// positions 为节点的世界坐标，只在 PathWeight::Distance 时使用
+    pub fn shortest_path(&self, from: usize, to: usize, weight: PathWeight, positions: &[Vec2]) -> Option<Vec<usize>> {
+        if from >= self.adjacency.len() || to >= self.adjacency.len() {
+            return None;
+        }
+        let predecessors = match weight {
+            PathWeight::Hops => self.bfs_predecessors(from, to),
+            PathWeight::Distance => self.dijkstra_predecessors(from, to, positions),
+        };
+
+        let mut path = vec![to];
+        let mut node = to;
+        while node != from {
+            node = predecessors[node]?;
+            path.push(node);
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    fn bfs_predecessors(&self, from: usize, to: usize) -> Vec<Option<usize>> {
+        let mut predecessors = vec![None; self.adjacency.len()];
+        let mut visited = vec![false; self.adjacency.len()];
+        let mut queue = VecDeque::from([from]);
+        visited[from] = true;
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                break;
+            }
+            for &neighbor in &self.adjacency[node] {
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    predecessors[neighbor] = Some(node);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        predecessors
+    }
+
+    fn dijkstra_predecessors(&self, from: usize, to: usize, positions: &[Vec2]) -> Vec<Option<usize>> {
+        let mut predecessors = vec![None; self.adjacency.len()];
+        let mut distances = vec![f32::INFINITY; self.adjacency.len()];
+        let mut queue = BinaryHeap::from([QueueEntry { distance: 0.0, node: from }]);
+        distances[from] = 0.0;
+        while let Some(QueueEntry { distance, node }) = queue.pop() {
+            if node == to {
+                break;
+            }
+            if distance > distances[node] {
+                continue; // 已经以更短的距离处理过
+            }
+            for &neighbor in &self.adjacency[node] {
+                let link_length = match (positions.get(node), positions.get(neighbor)) {
+                    (Some(a), Some(b)) => a.distance(*b),
+                    _ => 1.0,
+                };
+                let next_distance = distance + link_length;
+                if next_distance < distances[neighbor] {
+                    distances[neighbor] = next_distance;
+                    predecessors[neighbor] = Some(node);
+                    queue.push(QueueEntry { distance: next_distance, node: neighbor });
+                }
+            }
+        }
+        predecessors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 节点 "0".."n-1"，links 为节点索引对
+    fn build_graph(node_count: usize, links: &[(usize, usize)]) -> TopologyGraph {
+        let node_id_to_idx: HashMap<String, usize> = (0..node_count).map(|idx| (idx.to_string(), idx)).collect();
+        let connections: Vec<ConnectionData> = links.iter()
+            .map(|&(a, b)| ConnectionData { from_node: a.to_string(), to_node: b.to_string(), connection_id: format!("{}-{}", a, b) })
+            .collect();
+        TopologyGraph::new(node_count, &node_id_to_idx, &connections)
+    }
+
+    const WEIGHTS: [PathWeight; 2] = [PathWeight::Hops, PathWeight::Distance];
+
+    #[test]
+    fn unreachable_target_has_no_path() {
+        // 0-1 和 2-3 两个连通分量
+        let graph = build_graph(4, &[(0, 1), (2, 3)]);
+        let positions = [Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ONE];
+        for weight in WEIGHTS {
+            assert_eq!(graph.shortest_path(0, 3, weight, &positions), None);
+            assert_eq!(graph.shortest_path(0, 9, weight, &positions), None);
+        }
+    }
+
+    #[test]
+    fn source_equal_to_target_is_a_single_node() {
+        let graph = build_graph(3, &[(0, 1), (1, 2)]);
+        for weight in WEIGHTS {
+            assert_eq!(graph.shortest_path(1, 1, weight, &[]), Some(vec![1]));
+        }
+        // 孤立节点到自身也有路径
+        assert_eq!(build_graph(1, &[]).shortest_path(0, 0, PathWeight::Hops, &[]), Some(vec![0]));
+    }
+
+    #[test]
+    fn ties_are_broken_deterministically() {
+        // 正方形 0-1-3 / 0-2-3：两条路径的跳数和长度都相同，取先加入邻接表的 0-1-3
+        let graph = build_graph(4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let positions = [Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ONE];
+        for weight in WEIGHTS {
+            for _ in 0..3 {
+                assert_eq!(graph.shortest_path(0, 3, weight, &positions), Some(vec![0, 1, 3]));
+            }
+        }
+    }
+
+    #[test]
+    fn distance_weight_prefers_shorter_route_over_fewer_hops() {
+        // 0-1-2-3 沿直线共 3 个单位；0-4-3 只有两跳，但 4 离得很远
+        let graph = build_graph(5, &[(0, 4), (4, 3), (0, 1), (1, 2), (2, 3)]);
+        let positions = [Vec2::ZERO, Vec2::X, Vec2::new(2.0, 0.0), Vec2::new(3.0, 0.0), Vec2::new(1.5, 10.0)];
+        assert_eq!(graph.shortest_path(0, 3, PathWeight::Hops, &positions), Some(vec![0, 4, 3]));
+        assert_eq!(graph.shortest_path(0, 3, PathWeight::Distance, &positions), Some(vec![0, 1, 2, 3]));
+    }
+}
@@ -4,3 +4,15 @@ pub mod network;
 pub mod service;
 pub mod defrag_event;
 pub mod text_label;
+pub mod auto_layout;
+pub mod dot;
+pub mod geojson;
+pub mod svg;
+pub mod validation;
+pub mod geometry;
+pub mod graph;
+pub mod edge_bundles;
+pub mod path_index;
+pub mod wavelength_colors;
+pub mod segment_grid;
+pub mod blocked_demand;
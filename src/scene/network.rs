@@ -1,13 +1,214 @@
-use serde::Deserialize;
+use std::fmt;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde::de::DeserializeOwned;
 
-use crate::scene::defrag_event::AnyEvent;
+use crate::scene::defrag_event::{locate_event_error, AnyEvent};
+use crate::settings::ChannelPlan;
 
+use super::blocked_demand::BlockedDemand;
 use super::element::ElementData;
 use super::connection::ConnectionData;
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct FullTopologyData {
     pub elements: Vec<ElementData>,
     pub connections: Vec<ConnectionData>,
     pub defrag_timeline_events: Vec<AnyEvent>,
-}
\ No newline at end of file
+    /// 可选的拓扑名称，原生窗口标题中使用（缺省为文件名）。放在最后，与 MessagePack 的数组编码保持兼容
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 可选的信道规划（波长数和展开角度），缺省时保持当前设置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_plan: Option<ChannelPlan>,
+    /// 可选的被阻塞需求（没有可用波长而未能建立的请求），见 blocked_demand.rs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_demands: Vec<BlockedDemand>,
+}
+
+/// setTopologyStructure 的参数：只有静态的节点和链路
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+#[derive(Deserialize, Debug)]
+pub struct TopologyStructureData {
+    pub elements: Vec<ElementData>,
+    pub connections: Vec<ConnectionData>,
+}
+
+/// setTimelineEvents 的参数：只有时间轴事件，格式与完整拓扑中的同名字段相同
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+#[derive(Deserialize, Debug)]
+pub struct TimelineEventsData {
+    pub defrag_timeline_events: Vec<AnyEvent>,
+}
+
+/// 拓扑 JSON 解析失败的位置，序列化为 `{ path, message, service_id? }` 供前端定位出错的记录
+#[derive(Debug, Clone, Serialize)]
+pub struct TopologyParseError {
+    /// 出错字段的路径，例如 `defrag_timeline_events[1523].details.wavelength`
+    pub path: String,
+    pub message: String,
+    /// 出错位置位于某个时间轴事件内时，该事件的 service_id（若能读出）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_id: Option<i64>,
+}
+
+impl fmt::Display for TopologyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Topology parsing error at {}: {}", self.path, self.message)?;
+        if let Some(service_id) = self.service_id {
+            write!(f, " (service_id {})", service_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TopologyParseError {}
+
+impl FullTopologyData {
+    pub fn from_json_str(text: &str) -> Result<Self, TopologyParseError> {
+        parse_topology_json(text)
+    }
+
+    /// 解析 MessagePack 编码的拓扑，字段名和 event_type 标签与 JSON 格式完全相同。
+    /// 线格式：顶层及所有结构体都必须编码为 map（键为字段名），而不是 rmp-serde 默认的数组形式，
+    /// 即 Rust 端用 `rmp_serde::to_vec_named`，JS 端用 `@msgpack/msgpack` 的 `encode` 直接编码 JSON 对象。
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, TopologyParseError> {
+        deserialize_topology(&mut rmp_serde::Deserializer::new(bytes), || rmp_serde::from_slice(bytes).ok())
+    }
+}
+
+/// 解析拓扑 JSON（完整拓扑或其中一部分），失败时给出出错字段的路径。
+/// 事件是按 event_type 区分的内部标签枚举，serde 会先缓冲整个事件，解析时的错误路径只能精确到事件本身；
+/// 出错后把该事件按 event_type 重新解析一次（见 locate_event_error），路径补全到事件内部的字段，
+/// 例如 `defrag_timeline_events[1523].details.wavelength`，并读出事件的 service_id。
+pub fn parse_topology_json<T: DeserializeOwned>(text: &str) -> Result<T, TopologyParseError> {
+    deserialize_topology(&mut serde_json::Deserializer::from_str(text), || serde_json::from_str(text).ok())
+}
+
+/// 用 serde_path_to_error 解析，失败时转换为 TopologyParseError。value 只在出错位置位于某个事件内时调用，
+/// 把整个输入重新解析为 serde_json::Value，从中取出出错的事件
+fn deserialize_topology<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+    value: impl FnOnce() -> Option<serde_json::Value>,
+) -> Result<T, TopologyParseError> {
+    serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        let mut path = e.path().to_string();
+        let mut service_id = None;
+        if let Some(pointer) = failed_event_pointer(e.path())
+            && let Some(value) = value()
+            && let Some(event) = value.pointer(&pointer)
+        {
+            service_id = event.get("service_id").and_then(serde_json::Value::as_i64);
+            let at_event = matches!(e.path().iter().next_back(), Some(serde_path_to_error::Segment::Seq { .. }));
+            if let Some(event_path) = locate_event_error(event).filter(|_| at_event) {
+                path = format!("{}.{}", path, event_path);
+            }
+        }
+        TopologyParseError { path, message: e.inner().to_string(), service_id }
+    })
+}
+
+/// 出错位置位于 defrag_timeline_events 中时，返回出错事件的 JSON Pointer，之后从整个输入中取出该事件。
+/// 拓扑本身为 `/defrag_timeline_events/N`，会话文档（session.rs）中拓扑位于 topology 字段下，为 `/topology/defrag_timeline_events/N`
+fn failed_event_pointer(path: &serde_path_to_error::Path) -> Option<String> {
+    use serde_path_to_error::Segment;
+    let mut segments = path.iter().peekable();
+    let prefix = match segments.next_if(|segment| matches!(segment, Segment::Map { key } if key == "topology")) {
+        Some(_) => "/topology",
+        None => "",
+    };
+    match (segments.next(), segments.next()) {
+        (Some(Segment::Map { key }), Some(Segment::Seq { index })) if key == "defrag_timeline_events" => {
+            Some(format!("{}/defrag_timeline_events/{}", prefix, index))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn service(service_id: i32, wavelength: i32) -> serde_json::Value {
+        json!({
+            "service_id": service_id, "source_id": "A", "destination_id": "C",
+            "arrival_time": 1.0, "departure_time": 9.0, "bit_rate": 100.0, "power": 0.0,
+            "path": ["A", "B", "C"], "wavelength": wavelength, "snr_requirement": 10.0, "gsnr": 15.5,
+        })
+    }
+
+    fn sample_topology() -> serde_json::Value {
+        let element = |id: &str, x: f32| json!({
+            "name": id, "type": "Roadm", "type_variety": "default", "metadata": {"location": {"x": x, "y": 0.0}}, "element_id": id,
+        });
+        let mut reallocated = service(5, 3);
+        reallocated["defrag_service_id"] = json!(9);
+        json!({
+            "elements": [element("A", 0.0), element("B", 100.0), element("C", 200.0)],
+            "connections": [
+                {"from_node": "A", "to_node": "B", "connection_id": "A-B"},
+                {"from_node": "B", "to_node": "C", "connection_id": "B-C"},
+            ],
+            "defrag_timeline_events": [
+                {"event_type": "ALLOCATION", "timestamp": 1.0, "service_id": 5, "details": service(5, 2)},
+                {"event_type": "REALLOCATION", "timestamp": 4.0, "service_id": 5, "details": reallocated},
+                {"event_type": "RELEASE_EXPIRED", "timestamp": 9.0, "service_id": 5, "details": {"departure_time": 9.0}},
+            ],
+            "name": "sample",
+        })
+    }
+
+    fn parse_error(topology: &serde_json::Value) -> TopologyParseError {
+        FullTopologyData::from_json_str(&topology.to_string()).unwrap_err()
+    }
+
+    #[test]
+    fn event_errors_point_inside_the_event() {
+        assert!(FullTopologyData::from_json_str(&sample_topology().to_string()).is_ok());
+
+        let mut topology = sample_topology();
+        topology["defrag_timeline_events"][0]["details"]["wavelength"] = json!("x");
+        let error = parse_error(&topology);
+        assert_eq!(error.path, "defrag_timeline_events[0].details.wavelength");
+        assert_eq!(error.service_id, Some(5));
+
+        let mut topology = sample_topology();
+        topology["defrag_timeline_events"][1]["details"]["gsnr"] = json!(null);
+        assert_eq!(parse_error(&topology).path, "defrag_timeline_events[1].details.gsnr");
+
+        let mut topology = sample_topology();
+        topology["defrag_timeline_events"][1]["details"]["defrag_service_id"] = json!(1.5);
+        assert_eq!(parse_error(&topology).path, "defrag_timeline_events[1].details.defrag_service_id");
+
+        let mut topology = sample_topology();
+        topology["defrag_timeline_events"][2]["details"] = json!({});
+        let error = parse_error(&topology);
+        assert_eq!(error.path, "defrag_timeline_events[2].details");
+        assert!(error.message.contains("departure_time"), "{}", error.message);
+    }
+
+    #[test]
+    fn msgpack_matches_json() {
+        let text = sample_topology().to_string();
+        let from_json = FullTopologyData::from_json_str(&text).unwrap();
+        let bytes = rmp_serde::to_vec_named(&from_json).unwrap();
+        let from_msgpack = FullTopologyData::from_msgpack(&bytes).unwrap();
+        assert_eq!(serde_json::to_value(&from_msgpack).unwrap(), serde_json::to_value(&from_json).unwrap());
+        assert_eq!(from_msgpack.defrag_timeline_events.len(), 3);
+
+        let mut topology = sample_topology();
+        topology["defrag_timeline_events"][0]["details"]["wavelength"] = json!("x");
+        let error = FullTopologyData::from_msgpack(&rmp_serde::to_vec_named(&topology).unwrap()).unwrap_err();
+        assert_eq!(error.path, "defrag_timeline_events[0].details.wavelength");
+        assert_eq!(error.service_id, Some(5));
+    }
+
+    #[test]
+    fn errors_outside_events_keep_their_path() {
+        let mut topology = sample_topology();
+        topology["elements"][1]["metadata"]["location"]["x"] = json!("far");
+        let error = parse_error(&topology);
+        assert_eq!(error.path, "elements[1].metadata.location.x");
+        assert_eq!(error.service_id, None);
+    }
+}
@@ -0,0 +1,83 @@
+// src/scene/path_index.rs
+// 节点 ID 到索引的预解析：重新生成几何时按字符串查 node_id_to_idx（每条链路两次、每个服务每跳两次）在大型拓扑中占了大部分时间。
+// 加载（或替换、合并）拓扑和事件时把链路两端和事件中服务的路径一次性解析为 circle_instances 的索引，
+// 路径按 (service_id, 路径哈希) 记忆，重复出现的路径（例如分配后又以相同路径重分配）共用同一份解析结果。
+// 未知的节点 ID 解析为 UNKNOWN_NODE，在解析时统一警告一次，生成几何时直接跳过。
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use super::connection::ConnectionData;
+use super::defrag_event::AnyEvent;
+use super::service::ServiceData;
+
+/// 不在拓扑中的节点
+pub const UNKNOWN_NODE: usize = usize::MAX;
+
+pub fn resolve_node_id(node_id: &str, node_id_to_idx: &HashMap<String, usize>) -> usize {
+    node_id_to_idx.get(node_id).copied().unwrap_or(UNKNOWN_NODE)
+}
+
+/// 一跳的两端都在拓扑中时返回其索引
+pub fn known_hop(source_idx: usize, target_idx: usize) -> Option<(usize, usize)> {
+    (source_idx != UNKNOWN_NODE && target_idx != UNKNOWN_NODE).then_some((source_idx, target_idx))
+}
+
+/// 链路占用按无向链路统计：路径以任一方向经过同一条链路都计入同一个键
+pub fn undirected_link_key(source_idx: usize, target_idx: usize) -> (usize, usize) {
+    (source_idx.min(target_idx), source_idx.max(target_idx))
+}
+
+/// 服务路径经过的链路（两端都在拓扑中的跳），见 undirected_link_key
+pub fn path_link_keys(path_indices: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    path_indices
+        .windows(2)
+        .filter_map(|hop| known_hop(hop[0], hop[1]))
+        .map(|(source_idx, target_idx)| undirected_link_key(source_idx, target_idx))
+}
+
+/// 与 all_connections 一一对应的两端节点索引；返回引用不存在节点的链路数
+pub fn resolve_connections(connections: &[ConnectionData], node_id_to_idx: &HashMap<String, usize>) -> (Vec<(usize, usize)>, usize) {
+    let endpoints: Vec<(usize, usize)> = connections
+        .iter()
+        .map(|connection| (resolve_node_id(&connection.from_node, node_id_to_idx), resolve_node_id(&connection.to_node, node_id_to_idx)))
+        .collect();
+    let unknown = endpoints.iter().filter(|&&(source_idx, target_idx)| known_hop(source_idx, target_idx).is_none()).count();
+    (endpoints, unknown)
+}
+
+fn path_hash(path: &[String]) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    path.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 为事件中的每个服务写入 ServiceData::path_indices；返回包含未知节点的服务路径数
+pub fn resolve_event_paths(events: &mut [AnyEvent], node_id_to_idx: &HashMap<String, usize>) -> usize {
+    let mut resolved: HashMap<(i32, u64), Arc<[usize]>> = HashMap::new();
+    let mut unknown = 0;
+    for event in events.iter_mut() {
+        let service = match event {
+            AnyEvent::Allocation { details, .. } => details,
+            AnyEvent::Reallocation { details, .. } => &mut details.service,
+            AnyEvent::ReleaseExpired { .. } | AnyEvent::Unknown { .. } => continue,
+        };
+        let indices = resolved
+            .entry((service.service_id, path_hash(&service.path)))
+            .or_insert_with(|| service.path.iter().map(|node_id| resolve_node_id(node_id, node_id_to_idx)).collect());
+        if indices.contains(&UNKNOWN_NODE) {
+            unknown += 1;
+        }
+        service.path_indices = Some(Arc::clone(indices));
+    }
+    unknown
+}
+
+/// 服务路径的节点索引：优先使用加载时解析的结果，没有时（例如预览服务）当场查找
+pub fn service_path_indices<'a>(service: &'a ServiceData, node_id_to_idx: &HashMap<String, usize>) -> Cow<'a, [usize]> {
+    match service.path_indices.as_deref() {
+        Some(indices) if indices.len() == service.path.len() => Cow::Borrowed(indices),
+        _ => Cow::Owned(service.path.iter().map(|node_id| resolve_node_id(node_id, node_id_to_idx)).collect()),
+    }
+}
@@ -0,0 +1,180 @@
+// src/scene/segment_grid.rs
+// 服务拾取线段的均匀网格索引：悬停高亮在每次光标移动时都要找到光标附近的线段，逐条计算距离在上万条线段时太慢。
+// 生成几何时（finish_regenerate）按 pick_segments 建立网格，每个格子记录包围盒与其相交的线段；
+// 查询时只检查光标容差范围覆盖的格子。包围盒跨越过多格子的长线段放在单独的列表中，每次查询都检查。
+// 网格与建立时的 pick_segments 一一对应，线段数不一致时（不应发生）退回逐条检查。
+use glam::Vec2;
+
+use crate::picking::PickSegment;
+
+/// 每个格子平均的线段数
+const SEGMENTS_PER_CELL: f32 = 4.0;
+/// 每个方向的格子数上限
+const MAX_CELLS_PER_AXIS: usize = 512;
+/// 包围盒跨越的格子数超过此值的线段不放入网格
+const MAX_CELLS_PER_SEGMENT: usize = 64;
+
+/// 点到线段的距离
+pub fn segment_distance(segment: &PickSegment, pos: Vec2) -> f32 {
+    let segment_vec = segment.end - segment.start;
+    let t = ((pos - segment.start).dot(segment_vec) / segment_vec.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    pos.distance(segment.start + segment_vec * t)
+}
+
+#[derive(Debug, Default)]
+pub struct SegmentGrid {
+    segment_count: usize,
+    origin: Vec2,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    /// 按行排列，每个格子为线段索引
+    cells: Vec<Vec<u32>>,
+    oversized: Vec<u32>,
+}
+
+impl SegmentGrid {
+    pub fn new(segments: &[PickSegment]) -> Self {
+        let Some((min, max)) = segments.iter().fold(None, |bounds: Option<(Vec2, Vec2)>, segment| {
+            let (segment_min, segment_max) = (segment.start.min(segment.end), segment.start.max(segment.end));
+            Some(bounds.map_or((segment_min, segment_max), |(min, max)| (min.min(segment_min), max.max(segment_max))))
+        }) else {
+            return Self::default();
+        };
+
+        let extent = (max - min).max(Vec2::splat(f32::EPSILON));
+        let cell_count = (segments.len() as f32 / SEGMENTS_PER_CELL).max(1.0);
+        let cell_size = (extent.x * extent.y / cell_count).sqrt()
+            .max(extent.max_element() / MAX_CELLS_PER_AXIS as f32)
+            .max(f32::EPSILON);
+        let columns = ((extent.x / cell_size).ceil() as usize).clamp(1, MAX_CELLS_PER_AXIS);
+        let rows = ((extent.y / cell_size).ceil() as usize).clamp(1, MAX_CELLS_PER_AXIS);
+
+        let mut grid = Self {
+            segment_count: segments.len(),
+            origin: min,
+            cell_size,
+            columns,
+            rows,
+            cells: vec![Vec::new(); columns * rows],
+            oversized: Vec::new(),
+        };
+        for (index, segment) in segments.iter().enumerate() {
+            let ((column_min, row_min), (column_max, row_max)) =
+                (grid.cell_of(segment.start.min(segment.end)), grid.cell_of(segment.start.max(segment.end)));
+            if (column_max - column_min + 1) * (row_max - row_min + 1) > MAX_CELLS_PER_SEGMENT {
+                grid.oversized.push(index as u32);
+                continue;
+            }
+            for row in row_min..=row_max {
+                for column in column_min..=column_max {
+                    grid.cells[row * columns + column].push(index as u32);
+                }
+            }
+        }
+        grid
+    }
+
+    fn cell_of(&self, pos: Vec2) -> (usize, usize) {
+        let cell = ((pos - self.origin) / self.cell_size).floor();
+        (
+            (cell.x.max(0.0) as usize).min(self.columns - 1),
+            (cell.y.max(0.0) as usize).min(self.rows - 1),
+        )
+    }
+
+    /// 距 pos 不超过 tolerance 的最近线段在 segments 中的索引
+    pub fn nearest(&self, segments: &[PickSegment], pos: Vec2, tolerance: f32) -> Option<usize> {
+        let nearest_of = |indices: &mut dyn Iterator<Item = usize>| {
+            indices
+                .map(|index| (index, segment_distance(&segments[index], pos)))
+                .filter(|&(_, distance)| distance <= tolerance)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+        };
+        if self.segment_count != segments.len() {
+            return nearest_of(&mut (0..segments.len())).map(|(index, _)| index);
+        }
+        if self.cells.is_empty() {
+            return None;
+        }
+
+        let query_min = pos - Vec2::splat(tolerance);
+        let query_max = pos + Vec2::splat(tolerance);
+        let grid_max = self.origin + Vec2::new(self.columns as f32, self.rows as f32) * self.cell_size;
+        let in_grid = query_max.cmpge(self.origin).all() && query_min.cmple(grid_max).all();
+        let ((column_min, row_min), (column_max, row_max)) = (self.cell_of(query_min), self.cell_of(query_max));
+        let mut cell_indices = (row_min..=row_max)
+            .flat_map(|row| (column_min..=column_max).map(move |column| row * self.columns + column))
+            .filter(|_| in_grid)
+            .flat_map(|cell| self.cells[cell].iter())
+            .chain(self.oversized.iter())
+            .map(|&index| index as usize);
+        nearest_of(&mut cell_indices).map(|(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// 逐条检查得到的最近距离
+    fn brute_force_distance(segments: &[PickSegment], pos: Vec2, tolerance: f32) -> Option<f32> {
+        segments.iter().map(|segment| segment_distance(segment, pos)).filter(|&distance| distance <= tolerance).min_by(f32::total_cmp)
+    }
+
+    /// 大部分是短线段，少数长线段（进入 oversized）和退化成点的线段
+    fn pick_segment() -> impl Strategy<Value = PickSegment> {
+        let point = || (-100.0..100.0f32, -100.0..100.0f32).prop_map(|(x, y)| Vec2::new(x, y));
+        let end_offset = prop_oneof![
+            8 => (-5.0..5.0f32, -5.0..5.0f32).prop_map(|(x, y)| Vec2::new(x, y)),
+            1 => (-200.0..200.0f32, -200.0..200.0f32).prop_map(|(x, y)| Vec2::new(x, y)),
+            1 => Just(Vec2::ZERO),
+        ];
+        (point(), end_offset).prop_map(|(start, offset)| PickSegment { service_id: 0, segment_index: 0, start, end: start + offset })
+    }
+
+    /// 网格的格子边界上、格子角上以及网格范围外的查询点
+    fn border_queries(grid: &SegmentGrid) -> Vec<Vec2> {
+        let corner = |column: usize, row: usize| grid.origin + Vec2::new(column as f32, row as f32) * grid.cell_size;
+        let mut queries = Vec::new();
+        for row in 0..=grid.rows.min(8) {
+            for column in 0..=grid.columns.min(8) {
+                queries.push(corner(column, row));
+                queries.push(corner(column, row) + Vec2::new(grid.cell_size / 2.0, 0.0));
+                queries.push(corner(column, row) + Vec2::new(0.0, grid.cell_size / 2.0));
+            }
+        }
+        let far = corner(grid.columns, grid.rows);
+        queries.extend([grid.origin - Vec2::splat(1.0), far + Vec2::splat(1.0), Vec2::new(grid.origin.x - 3.0, far.y), Vec2::splat(1e6)]);
+        queries
+    }
+
+    proptest! {
+        /// 网格查询与逐条检查的最近距离相同（距离相等的线段可能不同），包括格子边界上和网格范围外的点
+        #[test]
+        fn nearest_matches_brute_force(
+            segments in prop::collection::vec(pick_segment(), 0..300),
+            random_queries in prop::collection::vec((-250.0..250.0f32, -250.0..250.0f32), 1..20),
+            tolerance in 0.0..20.0f32,
+        ) {
+            let grid = SegmentGrid::new(&segments);
+            let queries = random_queries.into_iter().map(|(x, y)| Vec2::new(x, y)).chain(border_queries(&grid));
+            for pos in queries {
+                let found = grid.nearest(&segments, pos, tolerance).map(|index| segment_distance(&segments[index], pos));
+                prop_assert_eq!(found, brute_force_distance(&segments, pos, tolerance), "query {:?}", pos);
+            }
+        }
+    }
+
+    /// 线段数与建立网格时不一致时退回逐条检查
+    #[test]
+    fn stale_grid_falls_back_to_linear_scan() {
+        let segment = |x: f32| PickSegment { service_id: 0, segment_index: 0, start: Vec2::new(x, 0.0), end: Vec2::new(x, 1.0) };
+        let grid = SegmentGrid::new(&[segment(0.0)]);
+        let segments = [segment(0.0), segment(50.0)];
+        assert_eq!(grid.nearest(&segments, Vec2::new(50.5, 0.5), 1.0), Some(1));
+        assert_eq!(SegmentGrid::default().nearest(&[], Vec2::ZERO, 1.0), None);
+    }
+}
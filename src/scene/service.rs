@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -5,13 +7,32 @@ pub struct ServiceData {
     pub service_id: i32,
     pub source_id: String,
     pub destination_id: String,
-    pub arrival_time: f32,
-    pub departure_time: f32,
+    pub arrival_time: f64,
+    pub departure_time: f64,
     pub bit_rate: f32,
     pub power: f32,
     pub path: Vec<String>,
     pub wavelength: i32,
     pub snr_requirement: f32,
     pub gsnr: f32,
+    /// 0 到 1；缺失时为 NaN（按利用率设置透明度时视为不透明，见 OpacityMode）
+    #[serde(default = "missing_utilization", skip_serializing_if = "is_missing_utilization")]
     pub utilization: f32,
-}
\ No newline at end of file
+    /// 加载事件时解析的路径节点索引（与 path 一一对应），见 path_index.rs；重建状态时随服务一起克隆
+    #[serde(skip)]
+    pub path_indices: Option<Arc<[usize]>>,
+}
+fn missing_utilization() -> f32 {
+    f32::NAN
+}
+
+fn is_missing_utilization(utilization: &f32) -> bool {
+    utilization.is_nan()
+}
+
+impl ServiceData {
+    /// GSNR 余量（gsnr - snr_requirement，dB）；gsnr 为 NaN 或 0 表示未计算，返回 None
+    pub fn gsnr_margin_db(&self) -> Option<f32> {
+        (self.gsnr.is_finite() && self.gsnr != 0.0).then_some(self.gsnr - self.snr_requirement)
+    }
+}
@@ -0,0 +1,281 @@
+// src/scene/svg.rs
+// 将当前视图导出为 SVG 矢量图，使用与 GPU 渲染相同的几何数据（SceneGeometry），按相同的顺序绘制。
+// 坐标为世界坐标，只把 y 轴翻转为 SVG 向下的方向；颜色从线性空间转换回 sRGB 十六进制。
+use std::fmt::Write;
+use bevy_color::{ColorToComponents, LinearRgba, Srgba};
+use glam::Vec2;
+
+use crate::models::{CircleInstance, LineVertex, ThickLineVertex};
+use super::geometry::{is_round_join_chunk, SceneGeometry};
+use super::text_label::TextLabel;
+
+// 与 highlight_lines.wgsl 中的虚线参数一致
+const DASH_PERIOD_PX: f32 = 16.0;
+const DASH_ON_FRACTION: f32 = 0.6;
+// 与 render_to_view 中世界坐标文本的基准高度一致
+const LABEL_FONT_SIZE_WORLD: f32 = 8.0;
+const BACKGROUND_COLOR: &str = "#000000";
+
+/// SVG 的 viewBox 范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvgBounds {
+    /// 当前相机可见的区域
+    View,
+    /// 包含所有节点的区域
+    Topology,
+}
+
+/// 导出所需数据的快照：在事件循环中复制，在事件循环之外生成 SVG 文本
+#[derive(Debug)]
+pub struct SvgSnapshot {
+    pub circle_instances: Vec<CircleInstance>,
+    pub line_vertices: Vec<LineVertex>,
+    /// 聚合占用率、预览、最短路径、测量线和高亮线路的四边形，按绘制顺序排列
+    pub thick_line_vertices: Vec<ThickLineVertex>,
+    pub text_labels: Vec<TextLabel>,
+    pub bounds_min: Vec2,
+    pub bounds_max: Vec2,
+    /// 每个世界单位对应的屏幕像素数，用于把虚线图案换算为世界单位
+    pub world_to_pixels: f32,
+}
+
+impl SvgSnapshot {
+    pub fn new(geometry: &SceneGeometry, bounds: (Vec2, Vec2), world_to_pixels: f32) -> Self {
+        let thick_line_vertices = geometry.service_quad_vertices.iter()
+            .chain(geometry.link_occupancy_vertices.iter())
+            .chain(geometry.preview_line_vertices.iter())
+            .chain(geometry.annotation_line_vertices.iter())
+            .chain(geometry.highlight_line_vertices.iter())
+            .copied()
+            .collect();
+        Self {
+            circle_instances: geometry.circle_instances.iter()
+                .enumerate()
+                .filter(|&(idx, _)| !geometry.is_node_hidden(idx))
+                .map(|(_, instance)| *instance)
+                .collect(),
+            line_vertices: geometry.line_vertices.clone(),
+            thick_line_vertices,
+            text_labels: geometry.world_text_labels().chain(geometry.annotation_labels.iter()).cloned().collect(),
+            bounds_min: bounds.0,
+            bounds_max: bounds.1,
+            world_to_pixels,
+        }
+    }
+
+    /// 包含所有节点（含半径）的世界坐标范围；没有节点时返回 None
+    pub fn topology_bounds(circle_instances: &[CircleInstance]) -> Option<(Vec2, Vec2)> {
+        circle_instances.iter().fold(None, |bounds, instance| {
+            let center = Vec2::from_array(instance.position);
+            let radius = Vec2::splat(instance.radius_scale);
+            Some(match bounds {
+                Some((min, max)) => (Vec2::min(min, center - radius), Vec2::max(max, center + radius)),
+                None => (center - radius, center + radius),
+            })
+        })
+    }
+
+    pub fn to_svg(&self) -> String {
+        let size = self.bounds_max - self.bounds_min;
+        let mut svg = String::new();
+        // 世界坐标 y 轴向上，SVG 向下：viewBox 使用翻转后的 y 范围
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+            self.bounds_min.x, svg_y(self.bounds_max.y), size.x, size.y,
+            (size.x * self.world_to_pixels).round(), (size.y * self.world_to_pixels).round(),
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            self.bounds_min.x, svg_y(self.bounds_max.y), size.x, size.y, BACKGROUND_COLOR,
+        );
+
+        // 1. 节点
+        let _ = writeln!(svg, r#"<g id="nodes">"#);
+        for instance in &self.circle_instances {
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{}"{}/>"#,
+                instance.position[0], svg_y(instance.position[1]), instance.radius_scale, fill_attributes(instance.color),
+            );
+        }
+        let _ = writeln!(svg, "</g>");
+
+        // 2. 链路边界和普通服务（一像素宽的细线）
+        let _ = writeln!(svg, r#"<g id="lines" stroke-width="{}">"#, 1.0 / self.world_to_pixels);
+        for segment in self.line_vertices.chunks_exact(2) {
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}"{}/>"#,
+                segment[0].position[0], svg_y(segment[0].position[1]), segment[1].position[0], svg_y(segment[1].position[1]),
+                stroke_attributes(segment[0].color),
+            );
+        }
+        let _ = writeln!(svg, "</g>");
+
+        // 3. 占用率、预览和高亮线路：每个四边形还原为中心线段，首尾相接的线段合并为折线
+        let _ = writeln!(svg, r#"<g id="paths" fill="none" stroke-linecap="round" stroke-linejoin="round">"#);
+        let dash_on = DASH_PERIOD_PX * DASH_ON_FRACTION / self.world_to_pixels;
+        let dash_off = DASH_PERIOD_PX * (1.0 - DASH_ON_FRACTION) / self.world_to_pixels;
+        let mut polyline: Vec<Vec2> = Vec::new();
+        let mut polyline_style: Option<(String, f32, u32)> = None;
+        for quad in self.thick_line_vertices.chunks_exact(6).filter(|chunk| !is_round_join_chunk(chunk)) {
+            // 顶点顺序见 push_thick_line_segment：(p1-, p1+, p2+, p1-, p2+, p2-)
+            let start = (Vec2::from_array(quad[0].position) + Vec2::from_array(quad[1].position)) / 2.0;
+            let end = (Vec2::from_array(quad[2].position) + Vec2::from_array(quad[5].position)) / 2.0;
+            // 折线（转接弧线）的拐点使用斜接偏移，两个偏移点的连线不垂直于线段，取其在线段法向上的分量
+            let offset = Vec2::from_array(quad[1].position) - Vec2::from_array(quad[0].position);
+            let thickness = match (end - start).try_normalize() {
+                Some(axis) => axis.perp_dot(offset).abs(),
+                None => offset.length(),
+            };
+            let style = (stroke_attributes(quad[0].color), thickness, quad[0].dash_pattern);
+
+            let continues = polyline_style.as_ref().is_some_and(|(stroke, polyline_thickness, dash_pattern)| {
+                *stroke == style.0 && *dash_pattern == style.2 && (polyline_thickness - thickness).abs() <= polyline_thickness * 0.05
+            }) && polyline.last().is_some_and(|last| last.distance(start) <= thickness * 0.01);
+            if !continues {
+                write_polyline(&mut svg, &polyline, polyline_style.as_ref(), dash_on, dash_off);
+                polyline = vec![start];
+                polyline_style = Some(style);
+            }
+            polyline.push(end);
+        }
+        write_polyline(&mut svg, &polyline, polyline_style.as_ref(), dash_on, dash_off);
+        let _ = writeln!(svg, "</g>");
+
+        // 4. 世界坐标文本
+        let _ = writeln!(
+            svg,
+            r##"<g id="labels" font-family="sans-serif" font-size="{}" fill="#e6e6e6" text-anchor="middle" dominant-baseline="central">"##,
+            LABEL_FONT_SIZE_WORLD,
+        );
+        for label in &self.text_labels {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                label.position[0], svg_y(label.position[1]), escape_xml(&label.content),
+            );
+            // 附加行（节点类型）依次放在下方，行高为字号的 1.2 倍
+            let mut y = svg_y(label.position[1]) + LABEL_FONT_SIZE_WORLD * 0.6;
+            for line in &label.detail_lines {
+                let font_size = LABEL_FONT_SIZE_WORLD * line.font_scale;
+                y += font_size * 0.6;
+                let _ = writeln!(
+                    svg,
+                    r#"<text x="{}" y="{}" font-size="{}">{}</text>"#,
+                    label.position[0], y, font_size, escape_xml(&line.content),
+                );
+                y += font_size * 0.6;
+            }
+        }
+        let _ = writeln!(svg, "</g>");
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+fn write_polyline(svg: &mut String, points: &[Vec2], style: Option<&(String, f32, u32)>, dash_on: f32, dash_off: f32) {
+    let Some((stroke, thickness, dash_pattern)) = style else {
+        return;
+    };
+    if points.len() < 2 {
+        return;
+    }
+    let points: Vec<String> = points.iter().map(|point| format!("{},{}", point.x, svg_y(point.y))).collect();
+    let dash = if *dash_pattern == ThickLineVertex::SOLID {
+        String::new()
+    } else {
+        format!(r#" stroke-dasharray="{} {}""#, dash_on, dash_off)
+    };
+    let _ = writeln!(svg, r#"<polyline points="{}" stroke-width="{}"{}{}/>"#, points.join(" "), thickness, stroke, dash);
+}
+
+/// 世界坐标 y 轴向上，SVG 向下（写成 0.0 - y 以免输出 "-0"）
+fn svg_y(world_y: f32) -> f32 {
+    0.0 - world_y
+}
+
+/// 线性 RGBA 转为 sRGB 十六进制颜色和透明度
+fn srgb_hex(color: [f32; 4]) -> (String, f32) {
+    let srgba = Srgba::from(LinearRgba::from_f32_array(color));
+    (Srgba { alpha: 1.0, ..srgba }.to_hex(), srgba.alpha)
+}
+
+fn fill_attributes(color: [f32; 4]) -> String {
+    let (hex, alpha) = srgb_hex(color);
+    if alpha < 1.0 {
+        format!(r#" fill="{}" fill-opacity="{}""#, hex, alpha)
+    } else {
+        format!(r#" fill="{}""#, hex)
+    }
+}
+
+fn stroke_attributes(color: [f32; 4]) -> String {
+    let (hex, alpha) = srgb_hex(color);
+    if alpha < 1.0 {
+        format!(r#" stroke="{}" stroke-opacity="{}""#, hex, alpha)
+    } else {
+        format!(r#" stroke="{}""#, hex)
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::geometry::{push_round_join, push_thick_line_segment};
+    use crate::scene::text_label::TextLine;
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+    const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 0.5];
+
+    fn snapshot() -> SvgSnapshot {
+        let circle = |x: f32, y: f32| CircleInstance { position: [x, y], radius_scale: 3.0, color: [1.0; 4], glow: 0.0 };
+        let line_vertex = |x: f32, y: f32| LineVertex { position: [x, y], color: [0.5, 0.5, 0.5, 1.0] };
+        // 首尾相接、样式相同的两段（中间有圆形连接）合并为一条折线，虚线另起一条
+        let mut thick_line_vertices = Vec::new();
+        let length = push_thick_line_segment(&mut thick_line_vertices, Vec2::ZERO, Vec2::new(40.0, 0.0), RED, 2.0, 0.0, ThickLineVertex::SOLID);
+        push_round_join(&mut thick_line_vertices, Vec2::new(40.0, 0.0), RED, 2.0, length, ThickLineVertex::SOLID);
+        push_thick_line_segment(&mut thick_line_vertices, Vec2::new(40.0, 0.0), Vec2::new(40.0, 20.0), RED, 2.0, length, ThickLineVertex::SOLID);
+        push_thick_line_segment(&mut thick_line_vertices, Vec2::ZERO, Vec2::new(0.0, -10.0), BLUE, 1.0, 0.0, ThickLineVertex::DASHED);
+        SvgSnapshot {
+            circle_instances: vec![circle(0.0, 0.0), circle(40.0, 0.0), circle(40.0, 20.0)],
+            line_vertices: vec![line_vertex(0.0, 0.0), line_vertex(40.0, 0.0)],
+            thick_line_vertices,
+            text_labels: vec![TextLabel {
+                content: "<A&B>".to_string(),
+                radius_scale: 3.0,
+                position: [0.0, 0.0],
+                detail_lines: vec![TextLine { content: "\"ROADM\"".to_string(), font_scale: 0.8 }],
+            }],
+            bounds_min: Vec2::new(-10.0, -20.0),
+            bounds_max: Vec2::new(90.0, 30.0),
+            world_to_pixels: 2.0,
+        }
+    }
+
+    #[test]
+    fn svg_structure_matches_snapshot() {
+        let svg = snapshot().to_svg();
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-10 -30 100 50" width="200" height="100">"#), "{svg}");
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        assert_eq!(svg.matches("<circle ").count(), 3);
+        assert_eq!(svg.matches("<line ").count(), 1);
+        assert_eq!(svg.matches("<polyline ").count(), 2);
+        assert_eq!(svg.matches("<text ").count(), 2);
+        assert!(svg.contains(r##"<polyline points="0,0 40,0 40,-20" stroke-width="2" stroke="#FF0000"/>"##), "{svg}");
+        assert_eq!(svg.matches("stroke-dasharray").count(), 1);
+        assert!(svg.contains(r#"stroke-opacity="0.5""#));
+
+        assert!(svg.contains(">&lt;A&amp;B&gt;</text>"), "{svg}");
+        assert!(svg.contains(">&quot;ROADM&quot;</text>"), "{svg}");
+        assert!(!svg.contains("<A&B>"));
+    }
+}
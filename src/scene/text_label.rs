@@ -1,8 +1,49 @@
 use serde::{Deserialize, Serialize};
 
+use super::element::ElementData;
+use crate::models::CircleInstance;
+
+/// 节点标签中类型行的字号（相对名称行）
+const NODE_DETAIL_FONT_SCALE: f32 = 0.75;
+/// 节点标签的中心在节点中心下方这么多倍半径处，不遮挡节点内的图标和跳数
+const NODE_LABEL_OFFSET_RADII: f32 = 1.6;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TextLabel {
     pub content: String,
     pub radius_scale: f32,
     pub position: [f32; 2],
-}
\ No newline at end of file
+    /// content 之后的附加行（例如节点类型），按标签设置中的阈值在放大到一定程度后才显示
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detail_lines: Vec<TextLine>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TextLine {
+    pub content: String,
+    /// 字号相对首行的比例
+    pub font_scale: f32,
+}
+
+impl TextLabel {
+    /// 节点标签：首行为名称（为空时用 element_id），第二行为 "node_type / type_variety"
+    pub fn for_node(element: &ElementData, instance: &CircleInstance) -> Self {
+        let name = if element.name.is_empty() { &element.element_id } else { &element.name };
+        let detail = match (element.node_type.as_str(), element.type_variety.as_str()) {
+            ("", "") => None,
+            (node_type, "") => Some(node_type.to_string()),
+            ("", type_variety) => Some(type_variety.to_string()),
+            (node_type, type_variety) if node_type.eq_ignore_ascii_case(type_variety) => Some(node_type.to_string()),
+            (node_type, type_variety) => Some(format!("{} / {}", node_type, type_variety)),
+        };
+        Self {
+            content: name.clone(),
+            radius_scale: instance.radius_scale,
+            position: [instance.position[0], instance.position[1] - instance.radius_scale * NODE_LABEL_OFFSET_RADII],
+            detail_lines: detail
+                .map(|content| TextLine { content, font_scale: NODE_DETAIL_FONT_SCALE })
+                .into_iter()
+                .collect(),
+        }
+    }
+}
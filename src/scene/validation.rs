@@ -0,0 +1,312 @@
+// src/scene/validation.rs
+// 加载拓扑时发现的问题（数据可以显示，但与预期不符），随 TopologyValidated 通知发送给 JS
+use serde::Serialize;
+
+use crate::scene::defrag_event::AnyEvent;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    /// 机器可读的问题类别，例如 "missing_location"
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub element_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+    /// 按节点最近邻距离的中位数推算的节点半径（世界单位），开启 setAutoNodeRadius 且未用 setNodeRadius 固定半径时使用，见 node_radius.rs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub derived_node_radius: Option<f32>,
+    /// 位置（几乎）重合的节点，每组为若干 element_id，见 node_overlap.rs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub overlapping_nodes: Vec<Vec<String>>,
+}
+
+impl ValidationReport {
+    pub fn warn(&mut self, code: &'static str, message: String, element_id: Option<String>) {
+        log::warn!("{}", message);
+        self.issues.push(ValidationIssue { code, message, element_id });
+    }
+}
+
+/// check_timeline_events 记录的问题代码：信道数变化后据此移除旧的结果再重新检查
+pub const TIMELINE_CHECK_CODES: &[&str] = &[
+    "events_unknown_type",
+    "events_departure_before_arrival",
+    "events_wavelength_out_of_range",
+    "events_missing_gsnr",
+];
+
+/// 检查时间轴事件中的服务数据：路径少于 2 个节点的事件无法绘制，直接丢弃；其余检查见 check_timeline_events。
+/// 同一类问题只记录一条（包含数量和第一个出问题的服务），问题代码都以 events_ 开头
+pub fn validate_timeline_events(events: &mut Vec<AnyEvent>, num_channels: u32, report: &mut ValidationReport) {
+    let mut short_paths = IssueTally::default();
+    events.retain(|event| match event.service() {
+        Some(service) if service.path.len() < 2 => {
+            short_paths.record(service.service_id);
+            false
+        }
+        _ => true,
+    });
+    if let Some(first) = short_paths.first {
+        report.warn(
+            "events_empty_path",
+            format!("Dropped {} timeline event(s) whose service path has fewer than 2 nodes (first: service {}).", short_paths.count, first),
+            None,
+        );
+    }
+    check_timeline_events(events, num_channels, report);
+}
+
+/// 未知类型的事件、离开时间早于到达时间、波长不在 [0, num_channels) 内、gsnr 为 NaN 或 0 的事件保留，只记录警告
+pub fn check_timeline_events(events: &[AnyEvent], num_channels: u32, report: &mut ValidationReport) {
+    let mut unknown_types: Vec<&str> = Vec::new();
+    let mut unknown_count = 0;
+    for event in events.iter() {
+        if let AnyEvent::Unknown { raw, .. } = event {
+            unknown_count += 1;
+            let event_type = raw.get("event_type").and_then(|event_type| event_type.as_str()).unwrap_or_default();
+            if !unknown_types.contains(&event_type) {
+                unknown_types.push(event_type);
+            }
+        }
+    }
+    if unknown_count > 0 {
+        report.warn(
+            "events_unknown_type",
+            format!("Skipped {} timeline event(s) of unknown type: {}.", unknown_count, unknown_types.join(", ")),
+            None,
+        );
+    }
+
+    let mut reversed_times = IssueTally::default();
+    let mut bad_wavelengths = IssueTally::default();
+    let mut missing_gsnr = IssueTally::default();
+    for service in events.iter().filter_map(|event| event.service()) {
+        if service.departure_time < service.arrival_time {
+            reversed_times.record(service.service_id);
+        }
+        if service.wavelength < 0 || service.wavelength as u32 >= num_channels {
+            bad_wavelengths.record(service.service_id);
+        }
+        if service.gsnr_margin_db().is_none() {
+            missing_gsnr.record(service.service_id);
+        }
+    }
+    if let Some(first) = reversed_times.first {
+        report.warn(
+            "events_departure_before_arrival",
+            format!("{} timeline event(s) have departure_time < arrival_time (first: service {}).", reversed_times.count, first),
+            None,
+        );
+    }
+    if let Some(first) = bad_wavelengths.first {
+        report.warn(
+            "events_wavelength_out_of_range",
+            format!(
+                "{} timeline event(s) use a wavelength outside [0, {}) (first: service {}).",
+                bad_wavelengths.count, num_channels, first
+            ),
+            None,
+        );
+    }
+    if let Some(first) = missing_gsnr.first {
+        report.warn(
+            "events_missing_gsnr",
+            format!(
+                "{} timeline event(s) have no usable gsnr (NaN or 0) and are drawn gray in the GSNR color mode (first: service {}).",
+                missing_gsnr.count, first
+            ),
+            None,
+        );
+    }
+}
+
+#[derive(Default)]
+struct IssueTally {
+    count: usize,
+    first: Option<i32>, // 第一个出问题的服务 ID
+}
+
+impl IssueTally {
+    fn record(&mut self, service_id: i32) {
+        self.count += 1;
+        self.first.get_or_insert(service_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    use super::*;
+    use crate::scene::defrag_event::{ReallocationDetails, ReleaseExpiredDetails};
+    use crate::scene::network::FullTopologyData;
+    use crate::scene::service::ServiceData;
+
+    fn node_id() -> impl Strategy<Value = String> {
+        "[A-Z][a-z0-9]{0,6}"
+    }
+
+    fn service_data() -> impl Strategy<Value = ServiceData> {
+        (
+            (any::<i32>(), node_id(), node_id(), -1e9..1e9f64, 0.0..1e6f64, prop::collection::vec(node_id(), 0..6)),
+            (any::<i32>(), -1e6..1e6f32, -1e3..1e3f32, -50.0..50.0f32, -50.0..50.0f32, prop::option::of(0.0..=1.0f32)),
+        )
+            .prop_map(|((service_id, source_id, destination_id, arrival_time, duration, path), (wavelength, bit_rate, power, snr_requirement, gsnr, utilization))| {
+                ServiceData {
+                    service_id,
+                    source_id,
+                    destination_id,
+                    arrival_time,
+                    departure_time: arrival_time + duration,
+                    bit_rate,
+                    power,
+                    path,
+                    wavelength,
+                    snr_requirement,
+                    gsnr,
+                    utilization: utilization.unwrap_or(f32::NAN),
+                    path_indices: None,
+                }
+            })
+    }
+
+    fn known_event() -> impl Strategy<Value = AnyEvent> {
+        prop_oneof![
+            (-1e9..1e9f64, service_data()).prop_map(|(timestamp, details)| AnyEvent::Allocation { timestamp, service_id: details.service_id, details }),
+            (-1e9..1e9f64, any::<i32>(), -1e9..1e9f64).prop_map(|(timestamp, service_id, departure_time)| {
+                AnyEvent::ReleaseExpired { timestamp, service_id, details: ReleaseExpiredDetails { departure_time } }
+            }),
+            (-1e9..1e9f64, any::<i32>(), service_data()).prop_map(|(timestamp, defrag_service_id, service)| {
+                AnyEvent::Reallocation { timestamp, service_id: service.service_id, details: ReallocationDetails { defrag_service_id, service } }
+            }),
+        ]
+    }
+
+    fn topology() -> impl Strategy<Value = serde_json::Value> {
+        let element = (node_id(), prop::option::of((-1e4..1e4f32, -1e4..1e4f32))).prop_map(|(id, location)| json!({
+            "name": id, "type": "Roadm", "type_variety": "default", "element_id": id,
+            "metadata": { "location": location.map(|(x, y)| json!({ "x": x, "y": y })) },
+        }));
+        let connection = (node_id(), node_id()).prop_map(|(from, to)| json!({ "from_node": from, "to_node": to, "connection_id": format!("{}-{}", from, to) }));
+        (prop::collection::vec(element, 0..8), prop::collection::vec(connection, 0..8), prop::collection::vec(known_event(), 0..8))
+            .prop_map(|(elements, connections, events)| json!({ "elements": elements, "connections": connections, "defrag_timeline_events": events }))
+    }
+
+    proptest! {
+        #[test]
+        fn event_round_trips_through_json(event in known_event()) {
+            let text = serde_json::to_string(&event).unwrap();
+            let parsed: AnyEvent = serde_json::from_str(&text).unwrap();
+            prop_assert_eq!(parsed.kind(), event.kind());
+            prop_assert_eq!(serde_json::to_value(&parsed).unwrap(), serde_json::to_value(&event).unwrap());
+        }
+
+        #[test]
+        fn topology_round_trips_through_json(topology in topology()) {
+            let parsed = FullTopologyData::from_json_str(&topology.to_string()).unwrap();
+            let reparsed = FullTopologyData::from_json_str(&serde_json::to_string(&parsed).unwrap()).unwrap();
+            prop_assert_eq!(serde_json::to_value(&reparsed).unwrap(), serde_json::to_value(&parsed).unwrap());
+            prop_assert_eq!(parsed.elements.len(), topology["elements"].as_array().unwrap().len());
+            prop_assert_eq!(&serde_json::to_value(&parsed.defrag_timeline_events).unwrap(), &topology["defrag_timeline_events"]);
+        }
+
+        #[test]
+        fn truncated_topology_is_rejected(topology in topology(), cut in 1usize..64) {
+            let text = topology.to_string();
+            let truncated = &text[..text.len().saturating_sub(cut)];
+            prop_assert!(FullTopologyData::from_json_str(truncated).is_err());
+        }
+    }
+
+    fn parse_event(event: serde_json::Value) -> Result<AnyEvent, serde_json::Error> {
+        serde_json::from_value(event)
+    }
+
+    #[test]
+    fn malformed_events() {
+        let unknown = parse_event(json!({ "event_type": "SPECTRUM_RETUNE", "timestamp": 3.5, "service_id": 1 })).unwrap();
+        assert!(matches!(unknown, AnyEvent::Unknown { timestamp: 3.5, .. }));
+        assert!(parse_event(json!({ "event_type": "SPECTRUM_RETUNE" })).is_err());
+        assert!(parse_event(json!({ "event_type": 7, "timestamp": 1.0 })).is_err());
+        assert!(parse_event(json!({ "timestamp": 1.0, "service_id": 1 })).is_err());
+
+        let missing_details = parse_event(json!({ "event_type": "RELEASE_EXPIRED", "timestamp": 1.0, "service_id": 1 })).unwrap_err();
+        assert!(missing_details.to_string().contains("details"), "{}", missing_details);
+        assert!(parse_event(json!({ "event_type": "RELEASE_EXPIRED", "timestamp": "1.0", "service_id": 1, "details": { "departure_time": 1.0 } })).is_err());
+        assert!(parse_event(json!({ "event_type": "RELEASE_EXPIRED", "timestamp": 1.0, "service_id": 1.5, "details": { "departure_time": 1.0 } })).is_err());
+        assert!(parse_event(json!({ "event_type": "RELEASE_EXPIRED", "timestamp": 1.0, "service_id": 1, "details": { "departure_time": "soon" } })).is_err());
+    }
+
+    #[test]
+    fn malformed_topologies() {
+        assert!(FullTopologyData::from_json_str(r#"{"elements": [], "connections": []}"#).is_err());
+        assert!(FullTopologyData::from_json_str(r#"{"elements": {}, "connections": [], "defrag_timeline_events": []}"#).is_err());
+        let error = FullTopologyData::from_json_str(
+            r#"{"elements": [], "connections": [{"from_node": "A", "to_node": 2, "connection_id": "A-B"}], "defrag_timeline_events": []}"#,
+        ).unwrap_err();
+        assert_eq!(error.path, "connections[0].to_node");
+    }
+
+    fn service(service_id: i32, arrival_time: f64, departure_time: f64, wavelength: i32, path: &[&str]) -> AnyEvent {
+        AnyEvent::Allocation {
+            timestamp: arrival_time,
+            service_id,
+            details: ServiceData {
+                service_id,
+                source_id: "A".to_string(),
+                destination_id: "B".to_string(),
+                arrival_time,
+                departure_time,
+                bit_rate: 100.0,
+                power: 0.0,
+                path: path.iter().map(|id| id.to_string()).collect(),
+                wavelength,
+                snr_requirement: 10.0,
+                gsnr: 15.0,
+                utilization: f32::NAN,
+                path_indices: None,
+            },
+        }
+    }
+
+    fn issue_codes(mut events: Vec<AnyEvent>, num_channels: u32) -> (Vec<&'static str>, usize) {
+        let mut report = ValidationReport::default();
+        validate_timeline_events(&mut events, num_channels, &mut report);
+        (report.issues.iter().map(|issue| issue.code).collect(), events.len())
+    }
+
+    #[test]
+    fn valid_events_have_no_issues() {
+        assert_eq!(issue_codes(vec![service(1, 1.0, 2.0, 0, &["A", "B"]), service(2, 1.0, 1.0, 79, &["A", "B", "C"])], 80), (vec![], 2));
+    }
+
+    #[test]
+    fn reversed_times_are_reported() {
+        let (codes, kept) = issue_codes(vec![service(1, 5.0, 2.0, 0, &["A", "B"]), service(2, 1.0, 2.0, 0, &["A", "B"])], 80);
+        assert_eq!((codes, kept), (vec!["events_departure_before_arrival"], 2));
+    }
+
+    #[test]
+    fn out_of_range_wavelengths_are_reported() {
+        for wavelength in [-1, 80] {
+            let (codes, kept) = issue_codes(vec![service(1, 1.0, 2.0, wavelength, &["A", "B"])], 80);
+            assert_eq!((codes, kept), (vec!["events_wavelength_out_of_range"], 1), "wavelength {}", wavelength);
+        }
+    }
+
+    #[test]
+    fn empty_paths_are_dropped() {
+        let mut report = ValidationReport::default();
+        let mut events = vec![service(1, 1.0, 2.0, 0, &[]), service(2, 1.0, 2.0, 0, &["A"]), service(3, 1.0, 2.0, 0, &["A", "B"])];
+        validate_timeline_events(&mut events, 80, &mut report);
+        assert_eq!(events.iter().filter_map(AnyEvent::service_id).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].code, "events_empty_path");
+        assert!(report.issues[0].message.contains("Dropped 2") && report.issues[0].message.contains("service 1"), "{}", report.issues[0].message);
+    }
+}
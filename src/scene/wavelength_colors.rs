@@ -0,0 +1,137 @@
+// src/scene/wavelength_colors.rs
+// 波长颜色查找表：按波长着色时每条服务线路都要做一次 Oklcha → LinearRgba 转换，数万条服务时在性能分析中很明显。
+// 查找表为每个波长预先计算普通、变暗和高亮三种颜色，与逐条计算使用完全相同的 Oklcha 参数，结果逐位相同。
+// 查找表记录生成时的波长数和高亮样式中的亮度，不一致时（通道计划或高亮样式已改变）get 返回 None，调用方按原来的方式计算；
+// State 在重新生成几何之前调用 refresh_wavelength_colors 重建。
+use bevy_color::{ColorToComponents, LinearRgba, Oklcha};
+
+use super::geometry::{wavelength_hue, SERVICE_CHROMA, SERVICE_LIGHTNESS};
+use crate::settings::{HighlightStyle, MAX_WAVELENGTHS};
+
+/// 服务线路的亮度规则（按波长着色时），与 WavelengthColorLut 中每个波长的三种颜色一一对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceShade {
+    /// 没有高亮时的普通服务
+    Normal = 0,
+    /// 存在高亮时的其余服务
+    Dimmed = 1,
+    /// 高亮服务（碎片整理服务本身）
+    Highlighted = 2,
+}
+
+impl ServiceShade {
+    const ALL: [ServiceShade; 3] = [ServiceShade::Normal, ServiceShade::Dimmed, ServiceShade::Highlighted];
+
+    /// OKLCH 亮度和色度，与 geometry.rs 中逐条计算时使用的参数相同
+    pub fn lightness_chroma(self, style: &HighlightStyle) -> (f32, f32) {
+        match self {
+            ServiceShade::Normal => (SERVICE_LIGHTNESS, SERVICE_CHROMA),
+            ServiceShade::Dimmed => (style.dimmed_lightness, SERVICE_CHROMA),
+            ServiceShade::Highlighted => (style.service_lightness, 0.2),
+        }
+    }
+
+    /// 不经过查找表直接计算
+    pub fn color(self, hue: f32, style: &HighlightStyle) -> [f32; 4] {
+        let (lightness, chroma) = self.lightness_chroma(style);
+        LinearRgba::from(Oklcha::lch(lightness, chroma, hue)).to_f32_array()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct WavelengthColorLut {
+    num_channels: u32,
+    /// 生成时各 ServiceShade 的亮度
+    lightness: [f32; 3],
+    /// 按波长索引，每项为三种 ServiceShade 的颜色
+    colors: Vec<[[f32; 4]; 3]>,
+}
+
+impl WavelengthColorLut {
+    /// 最多为 MAX_WAVELENGTHS 个波长生成颜色（通道计划已按该上限验证），未经验证的更大通道数不会分配巨大的表
+    pub fn new(num_channels: u32, style: &HighlightStyle) -> Self {
+        let colors = (0..num_channels.min(MAX_WAVELENGTHS) as i32)
+            .map(|wavelength| {
+                let hue = wavelength_hue(wavelength, num_channels);
+                ServiceShade::ALL.map(|shade| shade.color(hue, style))
+            })
+            .collect();
+        Self { num_channels, lightness: Self::lightness_key(style), colors }
+    }
+
+    fn lightness_key(style: &HighlightStyle) -> [f32; 3] {
+        ServiceShade::ALL.map(|shade| shade.lightness_chroma(style).0)
+    }
+
+    /// 查找表是否按这些参数生成
+    pub fn matches(&self, num_channels: u32, style: &HighlightStyle) -> bool {
+        self.num_channels == num_channels && self.lightness == Self::lightness_key(style)
+    }
+
+    /// 波长的颜色；查找表已过期、波长为负数或超出表的范围（通道数超过 MAX_WAVELENGTHS）时返回 None。
+    /// 超出通道数的波长与 wavelength_hue 一样按最后一个通道着色
+    pub fn get(&self, wavelength: i32, num_channels: u32, style: &HighlightStyle, shade: ServiceShade) -> Option<[f32; 4]> {
+        if wavelength < 0 || !self.matches(num_channels, style) {
+            return None;
+        }
+        let index = (wavelength as u32).min(num_channels.checked_sub(1)?);
+        self.colors.get(index as usize).map(|colors| colors[shade as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_matches_direct_conversion_bitwise() {
+        let style = HighlightStyle::default();
+        let num_channels = 80;
+        let lut = WavelengthColorLut::new(num_channels, &style);
+        for wavelength in 0..num_channels as i32 {
+            let hue = wavelength_hue(wavelength, num_channels);
+            for shade in ServiceShade::ALL {
+                let (lightness, chroma) = shade.lightness_chroma(&style);
+                let expected = LinearRgba::from(Oklcha::lch(lightness, chroma, hue)).to_f32_array();
+                let actual = lut.get(wavelength, num_channels, &style, shade).unwrap();
+                assert_eq!(actual.map(f32::to_bits), expected.map(f32::to_bits), "wavelength {} {:?}", wavelength, shade);
+            }
+        }
+    }
+
+    #[test]
+    fn stale_lookup_returns_none() {
+        let style = HighlightStyle::default();
+        let lut = WavelengthColorLut::new(80, &style);
+        assert!(lut.get(0, 96, &style, ServiceShade::Normal).is_none());
+        let brighter = HighlightStyle { service_lightness: style.service_lightness + 0.1, ..style };
+        assert!(lut.get(0, 80, &brighter, ServiceShade::Highlighted).is_none());
+        assert!(lut.get(-1, 80, &style, ServiceShade::Normal).is_none());
+        assert!(WavelengthColorLut::default().get(0, 0, &style, ServiceShade::Normal).is_none());
+    }
+
+    /// 最大通道数时查找表与逐条计算（ServiceShade::color）在两端和中间的波长上逐位相同，超出通道数的波长按最后一个通道着色
+    #[test]
+    fn lookup_matches_per_call_colors_at_max_wavelengths() {
+        let style = HighlightStyle::default();
+        let lut = WavelengthColorLut::new(MAX_WAVELENGTHS, &style);
+        let last = MAX_WAVELENGTHS as i32 - 1;
+        for (wavelength, hue_wavelength) in [(0, 0), (last / 2, last / 2), (last, last), (last + 7, last)] {
+            let hue = wavelength_hue(hue_wavelength, MAX_WAVELENGTHS);
+            for shade in ServiceShade::ALL {
+                let actual = lut.get(wavelength, MAX_WAVELENGTHS, &style, shade).unwrap();
+                assert_eq!(actual.map(f32::to_bits), shade.color(hue, &style).map(f32::to_bits), "wavelength {} {:?}", wavelength, shade);
+            }
+        }
+    }
+
+    /// 超过上限的通道数只生成 MAX_WAVELENGTHS 项，表外的波长返回 None（调用方逐条计算）
+    #[test]
+    fn lookup_size_is_clamped() {
+        let style = HighlightStyle::default();
+        let lut = WavelengthColorLut::new(u32::MAX, &style);
+        assert_eq!(lut.colors.len(), MAX_WAVELENGTHS as usize);
+        assert!(lut.get(MAX_WAVELENGTHS as i32 - 1, u32::MAX, &style, ServiceShade::Normal).is_some());
+        assert!(lut.get(MAX_WAVELENGTHS as i32, u32::MAX, &style, ServiceShade::Normal).is_none());
+    }
+}
@@ -0,0 +1,104 @@
+// src/scene_description.rs
+// 供屏幕阅读器使用的场景描述（前端写入 aria-live 区域）：当前时间、可见节点、活跃服务数和高亮服务的路径。
+// 只在 JS 请求时生成，可见节点复用 State::visible_nodes。
+use serde::Serialize;
+
+use crate::app_state::State;
+
+/// visible_node_names 最多列出的节点数
+const MAX_LISTED_NODE_NAMES: usize = 20;
+/// 文字摘要中列出的名称数，其余以 "and N more nodes" 概括
+const SUMMARY_NAME_COUNT: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneDescription {
+    pub time: f64,
+    pub visible_node_count: usize,
+    /// 按绘制顺序的前 MAX_LISTED_NODE_NAMES 个可见节点名称
+    pub visible_node_names: Vec<String>,
+    pub active_service_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlighted_service: Option<HighlightedServiceDescription>,
+    /// 一段英文文字摘要
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HighlightedServiceDescription {
+    pub service_id: i32,
+    /// 从源到目的的节点名称（不在拓扑中的节点使用其 id）
+    pub path: Vec<String>,
+    /// 被该碎片整理移动的服务数
+    pub moved_service_count: usize,
+}
+
+impl State {
+    pub fn scene_description(&self) -> SceneDescription {
+        let visible_nodes = self.visible_nodes();
+        let active_services = self.active_services_at_current_time();
+
+        let highlighted_service = self.highlight_service_id_list.as_deref().and_then(|ids| {
+            let (&service_id, moved_ids) = ids.split_first()?;
+            let service = active_services.iter().find(|service| service.service_id == service_id)?;
+            let path = service.path
+                .iter()
+                .map(|node_id| {
+                    self.node_id_to_idx
+                        .get(node_id)
+                        .and_then(|&idx| self.all_elements.get(idx))
+                        .map_or_else(|| node_id.clone(), |element| element.name.clone())
+                })
+                .collect();
+            Some(HighlightedServiceDescription { service_id, path, moved_service_count: moved_ids.len() })
+        });
+
+        let visible_node_names: Vec<String> = visible_nodes.iter().map(|node| node.name.clone()).collect();
+        let mut summary = format!("Time {}. ", self.current_time_selection);
+        summary += &match visible_node_names.len() {
+            0 => "No nodes are visible.".to_string(),
+            count => format!("{} visible {}: {}.", count, plural(count, "node", "nodes"), list_names(&visible_node_names)),
+        };
+        summary += &format!(" {} active {}.", active_services.len(), plural(active_services.len(), "service", "services"));
+        if let Some(highlighted) = &highlighted_service {
+            if let (Some(source), Some(destination)) = (highlighted.path.first(), highlighted.path.last()) {
+                summary += &format!(" Highlighted service {} runs from {} to {}", highlighted.service_id, source, destination);
+                if highlighted.path.len() > 2 {
+                    summary += &format!(" via {}", list_names(&highlighted.path[1..highlighted.path.len() - 1]));
+                }
+                summary += ".";
+            }
+            if highlighted.moved_service_count > 0 {
+                summary += &format!(
+                    " It moved {} other {}.",
+                    highlighted.moved_service_count, plural(highlighted.moved_service_count, "service", "services")
+                );
+            }
+        }
+
+        SceneDescription {
+            time: self.current_time_selection,
+            visible_node_count: visible_nodes.len(),
+            visible_node_names: visible_node_names.into_iter().take(MAX_LISTED_NODE_NAMES).collect(),
+            active_service_count: active_services.len(),
+            highlighted_service,
+            summary,
+        }
+    }
+}
+
+fn plural<'a>(count: usize, singular: &'a str, plural: &'a str) -> &'a str {
+    if count == 1 { singular } else { plural }
+}
+
+/// "A"、"A and B"、"A, B and C"；超过 SUMMARY_NAME_COUNT 个时为 "A, B, C and 42 more nodes"
+fn list_names(names: &[String]) -> String {
+    if names.len() > SUMMARY_NAME_COUNT {
+        let remaining = names.len() - SUMMARY_NAME_COUNT;
+        return format!("{} and {} more {}", names[..SUMMARY_NAME_COUNT].join(", "), remaining, plural(remaining, "node", "nodes"));
+    }
+    match names {
+        [] => String::new(),
+        [only] => only.clone(),
+        [init @ .., last] => format!("{} and {}", init.join(", "), last),
+    }
+}
@@ -0,0 +1,38 @@
+// src/service_diff.rs
+// 差异模式：在同一个视图中比较 t1 和 t2 两个时刻的活跃服务。时间轴跳到 t2，新出现的服务为绿色，
+// 波长或路径变化的为橙色，未变化的变暗，t1 时活跃、t2 时已离开的服务按 t1 时的线路以红色虚线绘制残影。
+// 差异只在时间轴停留在 t2 时显示；重新加载事件或调用 clearDiff() 后清除。
+use crate::app_state::State;
+use crate::notifications::TimeChangeReason;
+use crate::scene::defrag_event::{compute_service_diff, ServiceDiff, ServiceDiffCounts};
+
+impl State {
+    /// 比较 t1 和 t2 的活跃服务（按 service_id 匹配，比较 path 和 wavelength），不改变视图
+    pub fn compute_service_diff(&self, t1: f64, t2: f64) -> ServiceDiff {
+        compute_service_diff(self.active_services_at(t1), t2, &self.active_services_at(t2))
+    }
+
+    /// 进入差异模式并把时间轴移动到 t2（发送 reason 为 "api" 的 timeChanged 通知）
+    pub fn set_diff_times(&mut self, t1: f64, t2: f64) -> Result<ServiceDiffCounts, String> {
+        if !t1.is_finite() || !t2.is_finite() {
+            return Err(format!("Diff times must be finite, got {} and {}", t1, t2));
+        }
+        let diff = self.compute_service_diff(t1, t2);
+        let counts = diff.counts();
+        log::info!("Service diff {} -> {}: {:?}", t1, t2, counts);
+        self.service_diff = Some(diff);
+        self.set_time_selection(t2, TimeChangeReason::Api);
+        Ok(counts)
+    }
+
+    pub fn clear_diff(&mut self) {
+        if self.service_diff.take().is_some() {
+            self.topology_needs_update = true;
+        }
+    }
+
+    /// 生成几何时使用的差异：时间轴离开 t2 后不再显示
+    pub fn active_service_diff(&self) -> Option<&ServiceDiff> {
+        self.service_diff.as_ref().filter(|diff| diff.t2 == self.current_time_selection)
+    }
+}
@@ -0,0 +1,65 @@
+// src/service_hover.rs
+// 服务悬停强调：光标靠近某个服务的任意线段时，整条服务叠加一条浅色强调线，离开后消失。
+// 与点击高亮不同，不修改 highlight_service_id_list，也不重新生成几何：命中测试使用 pick_segment_grid（见 scene/segment_grid.rs），
+// 强调线按该服务的拾取线段生成，放在单独的小顶点缓冲区中，在高亮线路之后绘制。悬停的服务变化时发送 serviceHovered 通知。
+// 强调线的宽度按屏幕像素换算为世界单位，相机或几何变化后按光标处重新检测并重建。
+// 链路占用检查（link_inspector.rs）的强调线也放在这个缓冲区中，一起重建。
+use bevy_color::{ColorToComponents, LinearRgba, Srgba};
+
+use crate::app_state::State;
+use crate::models::ThickLineVertex;
+use crate::notifications::ViewNotification;
+use crate::scene::geometry::push_thick_line_segment;
+
+/// 强调线的屏幕宽度
+const HOVER_LINE_WIDTH_PX: f32 = 4.0;
+
+impl State {
+    /// 光标移动后调用：按光标处的服务线段更新悬停的服务。拖动、平移或光标在服务列表面板、时间轴条或注释标签上时不悬停。
+    /// 返回悬停的服务是否变化（需要重绘）
+    pub fn update_service_hover(&mut self) -> bool {
+        let over_overlay = self.service_panel_contains(self.mouse_canvas_pos_screen)
+            || self.timeline_strip_contains(self.mouse_canvas_pos_screen)
+            || self.annotation_at(self.mouse_current_pos_screen).is_some();
+        let blocked = self.is_mouse_left_pressed || self.timeline_strip.scrubbing || self.dragged_node.is_some() || over_overlay;
+        let service_id = if blocked { None } else { self.service_segment_at(self.mouse_current_pos_screen).map(|segment| segment.service_id) };
+        self.set_hovered_service(service_id)
+    }
+
+    /// 设置悬停的服务（None 为取消）并通知 JS；没有变化时返回 false
+    pub fn set_hovered_service(&mut self, service_id: Option<i32>) -> bool {
+        if service_id == self.hovered_service_id {
+            return false;
+        }
+        self.hovered_service_id = service_id;
+        self.pending_notifications.push(ViewNotification::ServiceHovered { service_id });
+        self.rebuild_emphasis_lines();
+        true
+    }
+
+    /// 相机或几何变化后调用：线段位置和宽度可能已经变化，重新检测光标处的服务并重建强调线
+    pub fn refresh_service_hover(&mut self) {
+        if self.hovered_service_id.is_none() && self.inspected_link.is_none() {
+            return;
+        }
+        if self.hovered_service_id.is_none() || !self.update_service_hover() {
+            self.rebuild_emphasis_lines();
+        }
+    }
+
+    /// 重建悬停和链路检查的强调线并上传
+    pub fn rebuild_emphasis_lines(&mut self) {
+        let mut vertices = std::mem::take(&mut self.hover_line_vertices);
+        vertices.clear();
+        self.push_link_inspection_lines(&mut vertices);
+        if let Some(service_id) = self.hovered_service_id {
+            let thickness = HOVER_LINE_WIDTH_PX / self.camera.world_radius_to_screen_pixels(1.0);
+            let color = LinearRgba::from(Srgba::rgba_u8(255, 255, 255, 110)).to_f32_array();
+            for segment in self.geometry.pick_segments.iter().filter(|segment| segment.service_id == service_id) {
+                push_thick_line_segment(&mut vertices, segment.start, segment.end, color, thickness, 0.0, ThickLineVertex::SOLID);
+            }
+        }
+        self.renderer.upload_hover_line_vertices(&self.device, &self.queue, &vertices);
+        self.hover_line_vertices = vertices;
+    }
+}
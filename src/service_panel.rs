@@ -0,0 +1,257 @@
+// src/service_panel.rs
+// 画布内的活跃服务列表面板（没有外部 HTML 界面的展示屏 / 原生窗口使用）：画布右上角的半透明背景，
+// 每行一个当前时刻活跃的服务（编号、波长、源 → 宿），已高亮的服务行带底色。
+// 光标在面板上时滚轮滚动列表、点击行高亮该服务（Ctrl / Cmd + 点击切换，见 service_selection.rs），都不会平移或缩放画布。
+// 同时显示的行数有上限（MAX_VISIBLE_ROWS），其余以 "N more…" 表示，因此面板只占用一个大小有限的文字缓冲区。
+// 行内容过长时截断，悬停在行上时以提示框（overlay.rs）显示完整的服务信息。
+// 面板按整个画布的坐标布局，对比视图中也只显示一个。
+use glam::Vec2;
+
+use crate::app_state::{State, CLICK_MAX_DRAG_PX};
+use crate::models::LineVertex;
+use crate::overlay::{push_screen_rect, Tooltip};
+use crate::scene::service::ServiceData;
+
+const PANEL_WIDTH_PX: f32 = 320.0;
+const PANEL_MARGIN_PX: f32 = 8.0;
+const PANEL_PADDING_PX: f32 = 6.0;
+pub const PANEL_FONT_SIZE: f32 = 13.0;
+pub const PANEL_ROW_HEIGHT_PX: f32 = 18.0;
+/// 同时显示的最大行数（不含标题和 "N more…" 行）
+const MAX_VISIBLE_ROWS: usize = 40;
+/// 每行最多的字符数，超出部分截断为 "…"（等宽字体下约为面板宽度）
+const MAX_ROW_CHARS: usize = 38;
+
+#[derive(Debug, Default)]
+pub struct ServicePanel {
+    pub visible: bool,
+    /// 当前时刻活跃的服务，按 service_id 排序；时间或拓扑变化后在 update() 中重新生成
+    rows: Vec<ServicePanelRow>,
+    pub rows_need_update: bool,
+    first_row: usize,
+    scroll_remainder_px: f32, // 触控板的像素滚动不足一行时累积
+    pressed_at: Option<Vec2>, // 在面板内按下鼠标的位置，松开时据此判断点击
+    hovered_service_id: Option<i32>, // 悬停的行，显示未截断的服务详情
+}
+
+#[derive(Debug)]
+struct ServicePanelRow {
+    service: ServiceData,
+    text: String,
+}
+
+/// 面板在画布上的位置（像素），由 State::service_panel_layout 按当前尺寸计算
+#[derive(Debug, Clone, Copy)]
+pub struct ServicePanelLayout {
+    pub min: Vec2,
+    pub max: Vec2,
+    first_row: usize,
+    visible_rows: usize,
+    more_rows: usize,
+}
+
+impl ServicePanelLayout {
+    /// 第一行服务的顶部（标题行之下）
+    fn rows_top(&self) -> f32 {
+        self.min.y + PANEL_PADDING_PX + PANEL_ROW_HEIGHT_PX
+    }
+
+    pub fn text_origin(&self) -> Vec2 {
+        self.min + Vec2::splat(PANEL_PADDING_PX)
+    }
+
+    pub fn contains(&self, pos: Vec2) -> bool {
+        pos.cmpge(self.min).all() && pos.cmplt(self.max).all()
+    }
+
+    /// pos 处的服务行（面板内的索引）
+    fn row_at(&self, pos: Vec2) -> Option<usize> {
+        if !self.contains(pos) || pos.y < self.rows_top() {
+            return None;
+        }
+        let row = ((pos.y - self.rows_top()) / PANEL_ROW_HEIGHT_PX) as usize;
+        (row < self.visible_rows).then_some(self.first_row + row)
+    }
+}
+
+fn truncate_row(text: String) -> String {
+    if text.chars().count() <= MAX_ROW_CHARS {
+        return text;
+    }
+    let mut truncated: String = text.chars().take(MAX_ROW_CHARS - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+impl State {
+    /// setServicePanel / L 键
+    pub fn set_service_panel(&mut self, visible: bool) {
+        log::info!("Service panel {}.", if visible { "shown" } else { "hidden" });
+        self.service_panel.visible = visible;
+        self.service_panel.rows_need_update = visible;
+        self.service_panel.pressed_at = None;
+        if !visible {
+            self.update_service_panel_hover();
+        }
+    }
+
+    /// 按当前时刻重新生成面板的行
+    pub fn refresh_service_panel_rows(&mut self) {
+        let services = self.active_services_at_current_time();
+        self.set_service_panel_rows(services);
+    }
+
+    /// 用当前时刻的活跃服务生成面板的行；重新生成几何时直接使用 GeometryBuild 中已经重建的服务
+    pub fn set_service_panel_rows(&mut self, mut services: Vec<ServiceData>) {
+        services.sort_by_key(|service| service.service_id);
+        self.service_panel.rows = services
+            .into_iter()
+            .map(|service| ServicePanelRow {
+                text: truncate_row(format!(
+                    "#{:<5} λ{:<3} {} → {}",
+                    service.service_id, service.wavelength, service.source_id, service.destination_id
+                )),
+                service,
+            })
+            .collect();
+        self.service_panel.rows_need_update = false;
+        // 服务信息可能已经变化，下面的 scroll_service_panel 重新生成提示框
+        if self.service_panel.hovered_service_id.take().is_some() {
+            self.set_tooltip(None);
+        }
+        self.scroll_service_panel(0.0); // 行数减少后保持第一行有效
+    }
+
+    /// 面板隐藏、关闭文字渲染或画布过小时为 None
+    pub fn service_panel_layout(&self) -> Option<ServicePanelLayout> {
+        // 面板内容是文字，关闭文字渲染时连同背景一起隐藏，也不响应点击
+        if !self.service_panel.visible || !self.text_rendering_enabled {
+            return None;
+        }
+        let canvas_size = Vec2::new(self.config.width as f32, self.config.height as f32);
+        // 底部的时间轴条显示时面板在其上方结束
+        let available_height = canvas_size.y - 2.0 * (PANEL_MARGIN_PX + PANEL_PADDING_PX) - self.timeline_strip_reserved_height();
+        if canvas_size.x < PANEL_WIDTH_PX + 2.0 * PANEL_MARGIN_PX || available_height < PANEL_ROW_HEIGHT_PX {
+            return None;
+        }
+
+        // 标题和 "N more…" 各占一行
+        let total_rows = self.service_panel.rows.len();
+        let row_capacity = ((available_height / PANEL_ROW_HEIGHT_PX) as usize).saturating_sub(2).min(MAX_VISIBLE_ROWS);
+        let first_row = self.service_panel.first_row.min(total_rows.saturating_sub(row_capacity));
+        let visible_rows = row_capacity.min(total_rows - first_row);
+        let more_rows = total_rows - first_row - visible_rows;
+        let text_rows = 1 + visible_rows + usize::from(more_rows > 0);
+
+        let min = Vec2::new(canvas_size.x - PANEL_MARGIN_PX - PANEL_WIDTH_PX, PANEL_MARGIN_PX);
+        let max = min + Vec2::new(PANEL_WIDTH_PX, text_rows as f32 * PANEL_ROW_HEIGHT_PX + 2.0 * PANEL_PADDING_PX);
+        Some(ServicePanelLayout { min, max, first_row, visible_rows, more_rows })
+    }
+
+    /// pos 为画布坐标（mouse_canvas_pos_screen）
+    pub fn service_panel_contains(&self, pos: Vec2) -> bool {
+        self.service_panel_layout().is_some_and(|layout| layout.contains(pos))
+    }
+
+    fn service_panel_row_at(&self, pos: Vec2) -> Option<&ServicePanelRow> {
+        let row = self.service_panel_layout()?.row_at(pos)?;
+        self.service_panel.rows.get(row)
+    }
+
+    pub fn service_panel_service_at(&self, pos: Vec2) -> Option<i32> {
+        self.service_panel_row_at(pos).map(|row| row.service.service_id)
+    }
+
+    /// 光标移动、滚动或行变化后调用：悬停的服务变化时更新提示框
+    pub fn update_service_panel_hover(&mut self) {
+        let hovered = self.service_panel_row_at(self.mouse_canvas_pos_screen).map(|row| &row.service);
+        let hovered_service_id = hovered.map(|service| service.service_id);
+        if hovered_service_id == self.service_panel.hovered_service_id {
+            // 同一行内移动时提示框跟随光标
+            if let (Some(_), Some(tooltip)) = (hovered_service_id, self.tooltip.as_mut()) {
+                tooltip.anchor_screen = self.mouse_canvas_pos_screen;
+            }
+            return;
+        }
+        let tooltip = hovered.map(|service| Tooltip {
+            anchor_screen: self.mouse_canvas_pos_screen,
+            lines: self.service_tooltip_template.format(service).lines().map(str::to_string).collect(),
+        });
+        self.service_panel.hovered_service_id = hovered_service_id;
+        self.set_tooltip(tooltip);
+    }
+
+    /// delta_px 为正时向下滚动（显示后面的行）
+    pub fn scroll_service_panel(&mut self, delta_px: f32) {
+        let panel = &mut self.service_panel;
+        panel.scroll_remainder_px += delta_px;
+        let delta_rows = (panel.scroll_remainder_px / PANEL_ROW_HEIGHT_PX).trunc();
+        panel.scroll_remainder_px -= delta_rows * PANEL_ROW_HEIGHT_PX;
+        panel.first_row = panel.first_row.saturating_add_signed(delta_rows as isize);
+        // 按布局限制在最后一页之内，避免滚过末尾后需要反向滚动多次才能回来
+        if let Some(layout) = self.service_panel_layout() {
+            self.service_panel.first_row = layout.first_row;
+        }
+        self.update_service_panel_hover();
+    }
+
+    /// 左键按下：在面板内时返回 true，调用方不再开始平移或拖动节点
+    pub fn press_service_panel(&mut self, pos: Vec2) -> bool {
+        let inside = self.service_panel_contains(pos);
+        self.service_panel.pressed_at = inside.then_some(pos);
+        inside
+    }
+
+    /// 左键松开：按下发生在面板内时返回 true；移动距离小于点击阈值时高亮所点击的行
+    pub fn release_service_panel(&mut self, pos: Vec2) -> bool {
+        let Some(pressed_at) = self.service_panel.pressed_at.take() else {
+            return false;
+        };
+        if pos.distance(pressed_at) < CLICK_MAX_DRAG_PX && let Some(service_id) = self.service_panel_service_at(pos) {
+            let toggle = self.keyboard_modifiers.control_key() || self.keyboard_modifiers.super_key();
+            self.select_service(service_id, toggle);
+        }
+        true
+    }
+
+    /// 面板文字：标题、可见的服务行和 "N more…"
+    pub fn service_panel_text(&self, layout: &ServicePanelLayout) -> String {
+        let total_rows = self.service_panel.rows.len();
+        let mut text = if layout.visible_rows == 0 {
+            format!("Active services: {}", total_rows)
+        } else {
+            format!("Active services: {} ({}–{})", total_rows, layout.first_row + 1, layout.first_row + layout.visible_rows)
+        };
+        for row in &self.service_panel.rows[layout.first_row..layout.first_row + layout.visible_rows] {
+            text.push('\n');
+            text.push_str(&row.text);
+        }
+        if layout.more_rows > 0 {
+            text.push_str(&format!("\n{} more…", layout.more_rows));
+        }
+        text
+    }
+
+    /// 面板背景和已高亮行的底色（NDC 三角形）
+    pub fn push_service_panel_vertices(&self, layout: &ServicePanelLayout, vertices: &mut Vec<LineVertex>) {
+        let canvas_size = Vec2::new(self.config.width as f32, self.config.height as f32);
+        push_screen_rect(vertices, layout.min, layout.max, canvas_size, self.overlay_theme.background);
+
+        let highlighted = self.highlight_service_id_list.as_deref().unwrap_or_default();
+        let mut row_color = self.selection_accent_color;
+        row_color[3] = 0.35;
+        for (i, row) in self.service_panel.rows[layout.first_row..layout.first_row + layout.visible_rows].iter().enumerate() {
+            if !highlighted.contains(&row.service.service_id) {
+                continue;
+            }
+            let top = layout.rows_top() + i as f32 * PANEL_ROW_HEIGHT_PX;
+            push_screen_rect(
+                vertices,
+                Vec2::new(layout.min.x, top),
+                Vec2::new(layout.max.x, top + PANEL_ROW_HEIGHT_PX),
+                canvas_size,
+                row_color,
+            );
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// src/service_selection.rs
+// 点击服务线段选择高亮：普通点击以该服务替换当前高亮，Ctrl（macOS 上为 Cmd）+ 点击在高亮列表中加入或移除该服务，
+// 便于同时比较多个碎片整理候选。点击选择的服务按在列表中的顺序循环使用 MULTI_SELECT_HUES 中的色相，
+// 重叠的路径也能区分；setHighlightDefragService 的高亮仍使用"碎片整理服务 / 被移动服务"两种颜色。
+// 每次变化后发送 `{ type: "highlightChanged", service_ids }` 通知。
+use crate::app_state::State;
+use crate::notifications::ViewNotification;
+
+impl State {
+    /// 点击服务线段时调用；toggle 为 true（Ctrl+点击）时切换该服务，否则只高亮该服务
+    pub fn select_service(&mut self, service_id: i32, toggle: bool) {
+        let mut service_ids = match self.highlight_service_id_list.take() {
+            Some(service_ids) if toggle => service_ids,
+            _ => Vec::new(),
+        };
+        match service_ids.iter().position(|&id| id == service_id) {
+            Some(pos) if toggle => {
+                service_ids.remove(pos);
+            }
+            Some(_) => {}
+            None => service_ids.push(service_id),
+        }
+        // 由碎片整理高亮切换过来时，之前的服务也改用多选调色板
+        self.highlight_multi_select = true;
+        self.set_service_highlight((!service_ids.is_empty()).then_some(service_ids));
+    }
+
+    /// clearHighlight / Escape：一次清除所有高亮服务
+    pub fn clear_service_highlight(&mut self) {
+        if self.highlight_service_id_list.is_some() {
+            self.set_service_highlight(None);
+        }
+    }
+
+    fn set_service_highlight(&mut self, service_ids: Option<Vec<i32>>) {
+        log::info!("Highlighted services: {:?}", service_ids);
+        self.pending_notifications.push(ViewNotification::HighlightChanged {
+            service_ids: service_ids.clone().unwrap_or_default(),
+        });
+        self.highlight_service_id_list = service_ids;
+        self.topology_needs_update = true;
+    }
+}
@@ -0,0 +1,244 @@
+// src/service_template.rs
+// 服务提示框的文字模板（setServiceTooltipTemplate）：`{字段名}` 替换为 ServiceData 的同名字段，
+// 数值字段可指定小数位数 `{gsnr:.1}`，`{{` 和 `}}` 为字面量花括号，换行符分隔提示框的行。
+// 例如 "λ{wavelength} {source_id}→{destination_id} GSNR {gsnr:.1}dB"。
+// 模板在设置时解析，字段名错误时立即返回列出所有可用字段的错误，而不是在悬停时输出空白。
+// 缺失的 utilization（NaN）和未计算的 gsnr（见 ServiceData::gsnr_margin_db）显示为 MISSING_VALUE。
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::scene::service::ServiceData;
+
+pub const DEFAULT_SERVICE_TOOLTIP_TEMPLATE: &str = "Service #{service_id}  λ{wavelength}\n\
+    {source_id} → {destination_id}\n\
+    Path: {path}\n\
+    GSNR {gsnr:.1} dB  (required {snr_requirement:.1} dB)\n\
+    {bit_rate:.0} Gb/s  t = {arrival_time} – {departure_time}";
+
+/// 没有数值的字段的占位符
+const MISSING_VALUE: &str = "—";
+
+/// 模板可引用的 ServiceData 字段，与 JSON 中的字段名相同
+const FIELD_NAMES: [&str; 12] = [
+    "service_id", "source_id", "destination_id", "arrival_time", "departure_time", "bit_rate",
+    "power", "path", "wavelength", "snr_requirement", "gsnr", "utilization",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ServiceField {
+    ServiceId,
+    SourceId,
+    DestinationId,
+    ArrivalTime,
+    DepartureTime,
+    BitRate,
+    Power,
+    Path,
+    Wavelength,
+    SnrRequirement,
+    Gsnr,
+    Utilization,
+}
+
+impl ServiceField {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "service_id" => Self::ServiceId,
+            "source_id" => Self::SourceId,
+            "destination_id" => Self::DestinationId,
+            "arrival_time" => Self::ArrivalTime,
+            "departure_time" => Self::DepartureTime,
+            "bit_rate" => Self::BitRate,
+            "power" => Self::Power,
+            "path" => Self::Path,
+            "wavelength" => Self::Wavelength,
+            "snr_requirement" => Self::SnrRequirement,
+            "gsnr" => Self::Gsnr,
+            "utilization" => Self::Utilization,
+            _ => return None,
+        })
+    }
+
+    /// 只有浮点字段接受小数位数
+    fn takes_precision(self) -> bool {
+        !matches!(self, Self::ServiceId | Self::SourceId | Self::DestinationId | Self::Path | Self::Wavelength)
+    }
+
+    fn write(self, out: &mut String, service: &ServiceData, precision: Option<usize>) {
+        let float = match self {
+            Self::ServiceId => return out.push_str(&service.service_id.to_string()),
+            Self::SourceId => return out.push_str(&service.source_id),
+            Self::DestinationId => return out.push_str(&service.destination_id),
+            Self::Path => return out.push_str(&service.path.join(" → ")),
+            Self::Wavelength => return out.push_str(&service.wavelength.to_string()),
+            Self::ArrivalTime => service.arrival_time,
+            Self::DepartureTime => service.departure_time,
+            Self::BitRate => service.bit_rate as f64,
+            Self::Power => service.power as f64,
+            Self::SnrRequirement => service.snr_requirement as f64,
+            Self::Gsnr if service.gsnr_margin_db().is_none() => return out.push_str(MISSING_VALUE),
+            Self::Gsnr => service.gsnr as f64,
+            Self::Utilization if service.utilization.is_nan() => return out.push_str(MISSING_VALUE),
+            Self::Utilization => service.utilization as f64,
+        };
+        let _ = match precision {
+            Some(precision) => write!(out, "{:.*}", precision, float),
+            None => write!(out, "{}", float),
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Field { field: ServiceField, precision: Option<usize> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceTemplate {
+    segments: Vec<Segment>,
+}
+
+impl Default for ServiceTemplate {
+    fn default() -> Self {
+        DEFAULT_SERVICE_TOOLTIP_TEMPLATE.parse().expect("default service tooltip template is valid")
+    }
+}
+
+impl FromStr for ServiceTemplate {
+    type Err = String;
+
+    fn from_str(template: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|&(_, next)| next == '{').is_some() => literal.push('{'),
+                '}' if chars.next_if(|&(_, next)| next == '}').is_some() => literal.push('}'),
+                '{' => {
+                    let Some(end) = template[start..].find('}').map(|offset| start + offset) else {
+                        return Err(format!("Unclosed '{{' at position {} in service template", start));
+                    };
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(parse_placeholder(&template[start + 1..end])?);
+                    while chars.next_if(|&(i, _)| i <= end).is_some() {}
+                }
+                '}' => return Err(format!("Unmatched '}}' at position {} in service template; use '}}}}' for a literal brace", start)),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { segments })
+    }
+}
+
+/// 解析 `{}` 之间的内容：`字段名` 或 `字段名:.位数`
+fn parse_placeholder(placeholder: &str) -> Result<Segment, String> {
+    let (name, spec) = match placeholder.split_once(':') {
+        Some((name, spec)) => (name.trim(), Some(spec)),
+        None => (placeholder.trim(), None),
+    };
+    let Some(field) = ServiceField::from_name(name) else {
+        return Err(format!("Unknown service field '{}' in template; valid fields are: {}", name, FIELD_NAMES.join(", ")));
+    };
+    let precision = match spec {
+        None => None,
+        Some(spec) => {
+            let precision = spec
+                .strip_prefix('.')
+                .and_then(|digits| digits.parse::<usize>().ok())
+                .filter(|&precision| precision <= 12)
+                .ok_or_else(|| format!("Invalid format '{}' for field '{}'; expected a precision such as ':.1' (0 to 12 digits)", spec, name))?;
+            if !field.takes_precision() {
+                return Err(format!("Field '{}' is not a decimal number and does not take a precision", name));
+            }
+            Some(precision)
+        }
+    };
+    Ok(Segment::Field { field, precision })
+}
+
+impl ServiceTemplate {
+    pub fn format(&self, service: &ServiceData) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Field { field, precision } => field.write(&mut out, service, *precision),
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> ServiceData {
+        ServiceData {
+            service_id: 7,
+            source_id: "A".to_string(),
+            destination_id: "C".to_string(),
+            arrival_time: 1.5,
+            departure_time: 9.0,
+            bit_rate: 400.0,
+            power: 1.0,
+            path: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            wavelength: 12,
+            snr_requirement: 10.0,
+            gsnr: 14.256,
+            utilization: 0.5,
+            path_indices: None,
+        }
+    }
+
+    fn render(template: &str, service: &ServiceData) -> String {
+        template.parse::<ServiceTemplate>().unwrap().format(service)
+    }
+
+    #[test]
+    fn formats_fields_and_escaped_braces() {
+        let service = service();
+        assert_eq!(render("{{λ{wavelength}}} {source_id}→{destination_id}", &service), "{λ12} A→C");
+        assert_eq!(render("GSNR {gsnr:.2} dB", &service), "GSNR 14.26 dB");
+        assert_eq!(render("{bit_rate:.0} {path}", &service), "400 A → B → C");
+        assert!(!ServiceTemplate::default().format(&service).contains('{'));
+    }
+
+    #[test]
+    fn missing_values_render_placeholder() {
+        let mut service = service();
+        service.utilization = f32::NAN;
+        service.gsnr = 0.0;
+        assert_eq!(render("{utilization:.2} {gsnr:.1} {gsnr}", &service), "— — —");
+        service.gsnr = f32::NAN;
+        assert_eq!(render("{gsnr}", &service), "—");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        let error = "{gsnr:.x}".parse::<ServiceTemplate>().unwrap_err();
+        assert!(error.contains("Invalid format '.x'"), "{}", error);
+
+        let error = "{wavelength:.1}".parse::<ServiceTemplate>().unwrap_err();
+        assert!(error.contains("does not take a precision"), "{}", error);
+
+        let error = "{color}".parse::<ServiceTemplate>().unwrap_err();
+        assert!(error.contains("Unknown service field 'color'"), "{}", error);
+        for name in FIELD_NAMES {
+            assert!(error.contains(name), "{} missing from: {}", name, error);
+        }
+
+        let error = "GSNR {gsnr".parse::<ServiceTemplate>().unwrap_err();
+        assert!(error.contains("Unclosed '{' at position 5"), "{}", error);
+
+        let error = "a } b".parse::<ServiceTemplate>().unwrap_err();
+        assert!(error.contains("Unmatched '}'"), "{}", error);
+    }
+}
@@ -0,0 +1,279 @@
+// src/session.rs
+// 会话导出 / 导入：把拓扑（节点位置为当前位置，包含布局编辑）、相机、时刻、高亮、图层、样式设置、
+// 命名视图、时间书签和注释打包为一个带版本号的 JSON 文档，便于用户在报告问题时发送，并在本地（网页或原生命令行）重现。
+// 文档结构：`{ "version": 1, "settings": {...}, "topology": { elements, connections, defrag_timeline_events } }`，
+// topology 与 setFullTopology 的格式相同；导出时拓扑部分与 getFullTopology 一样分多帧序列化。
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::annotations::Annotation;
+use crate::app_state::State;
+use crate::bookmarks::TimeBookmark;
+use crate::notifications::ViewNotification;
+use crate::saved_views::SavedView;
+use crate::scene::network::{parse_topology_json, FullTopologyData};
+use crate::settings::{ChannelPlan, HighlightLineStyle, HighlightStyle, LabelSettings, LodSettings, ServiceIntervalSemantics};
+
+/// 当前的会话格式版本，格式发生不兼容的变化时递增
+pub const SESSION_VERSION: u32 = 1;
+/// 会话文档在拓扑之后的部分，拓扑之前的部分见 session_header
+const SESSION_FOOTER: &str = "}";
+
+/// 会话中除拓扑以外的部分
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSettings {
+    /// 与 channel_plan.max_wavelengths 相同，保留以兼容没有 channel_plan 的会话
+    pub num_channels: u32,
+    #[serde(default)]
+    pub channel_plan: Option<ChannelPlan>,
+    pub service_interval: ServiceIntervalSemantics,
+    pub highlight_style: HighlightStyle,
+    pub highlight_line_style: HighlightLineStyle,
+    pub lod_settings: LodSettings,
+    #[serde(default)]
+    pub label_settings: LabelSettings,
+    /// 相机、时刻、高亮和图层开关
+    pub view: SavedView,
+    #[serde(default)]
+    pub saved_views: BTreeMap<String, SavedView>,
+    #[serde(default)]
+    pub time_bookmarks: Vec<TimeBookmark>,
+    /// addAnnotation 添加的注释，锚定的注释位置为导出时节点的位置
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+}
+
+impl SessionSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.num_channels == 0 {
+            return Err("num_channels must be positive".to_string());
+        }
+        if let Some(channel_plan) = &self.channel_plan {
+            channel_plan.validate()?;
+        }
+        self.highlight_style.validate()?;
+        self.lod_settings.validate()?;
+        self.label_settings.validate()?;
+        self.view.validate()?;
+        for (name, view) in &self.saved_views {
+            view.validate().map_err(|e| format!("View '{}': {}", name, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// 版本号已由 parse_session_json 单独检查
+#[derive(Debug, Deserialize)]
+pub struct SessionDocument {
+    pub settings: SessionSettings,
+    pub topology: FullTopologyData,
+}
+
+/// 会话文档中拓扑之前的部分：版本号和设置
+fn session_header(settings: &SessionSettings) -> serde_json::Result<String> {
+    Ok(format!("{{\"version\":{},\"settings\":{},\"topology\":", SESSION_VERSION, serde_json::to_string(settings)?))
+}
+
+/// 只读取版本号，其余字段跳过，用于在完整解析之前给出明确的版本错误
+#[derive(Deserialize)]
+struct SessionVersion {
+    version: Option<u32>,
+}
+
+/// 解析会话 JSON。版本号缺失或不一致时直接拒绝，不尝试解析其余部分
+pub fn parse_session_json(text: &str) -> Result<SessionDocument, String> {
+    let version: SessionVersion = serde_json::from_str(text).map_err(|e| format!("JSON parsing error: {}", e))?;
+    match version.version {
+        None => return Err("Not a wdmview session: the document has no \"version\" field.".to_string()),
+        Some(version) if version != SESSION_VERSION => {
+            return Err(format!(
+                "Unsupported session version {} (this viewer reads version {}); export the session again with a matching viewer.",
+                version, SESSION_VERSION
+            ));
+        }
+        Some(_) => {}
+    }
+    let document: SessionDocument = parse_topology_json(text).map_err(|e| e.to_string())?;
+    document.settings.validate()?;
+    Ok(document)
+}
+
+impl State {
+    pub fn session_settings(&self) -> SessionSettings {
+        SessionSettings {
+            num_channels: self.channel_plan.max_wavelengths,
+            channel_plan: Some(self.channel_plan),
+            service_interval: self.service_interval,
+            highlight_style: self.highlight_style,
+            highlight_line_style: self.highlight_line_style,
+            lod_settings: self.lod_settings,
+            label_settings: self.label_settings,
+            view: self.current_view(),
+            saved_views: self.saved_views.clone(),
+            time_bookmarks: self.time_bookmarks.clone(),
+            annotations: self.list_annotations(),
+        }
+    }
+
+    /// 开始导出会话，完成时通过 reply 返回 JSON 文本（见 topology_export.rs）
+    pub fn start_session_export(&mut self, reply: flume::Sender<Result<String, String>>) {
+        let header = match session_header(&self.session_settings()) {
+            Ok(header) => header,
+            Err(e) => {
+                let _ = reply.send(Err(e.to_string()));
+                return;
+            }
+        };
+        self.start_wrapped_topology_export(reply, header, SESSION_FOOTER);
+    }
+
+    /// 替换整个场景为会话中的状态，并发送 TopologyValidated 通知
+    pub fn import_session(&mut self, document: SessionDocument) {
+        let SessionDocument { settings, topology } = document;
+        log::info!(
+            "Importing session with {} nodes, {} links and {} events.",
+            topology.elements.len(), topology.connections.len(), topology.defrag_timeline_events.len()
+        );
+        self.set_channel_plan(settings.channel_plan.unwrap_or(ChannelPlan { max_wavelengths: settings.num_channels, ..ChannelPlan::default() }));
+        self.service_interval = settings.service_interval;
+        self.highlight_style = settings.highlight_style;
+        self.highlight_line_style = settings.highlight_line_style;
+        self.lod_settings = settings.lod_settings;
+        self.label_settings = settings.label_settings;
+
+        self.finish_topology_export();
+        self.all_events.clear(); // 与 SetFullTopology 相同，避免结构检查针对旧事件报告问题
+        self.mark_events_changed();
+        self.apply_topology_structure(topology.elements, topology.connections);
+        self.apply_timeline_events(topology.defrag_timeline_events);
+        self.set_blocked_demands(topology.blocked_demands, false);
+        self.pending_notifications.push(ViewNotification::TopologyValidated {
+            report: self.validation_report.clone(),
+        });
+
+        // 加载事件会清空书签，因此在之后恢复
+        self.time_bookmarks = settings.time_bookmarks;
+        self.saved_views = settings.saved_views;
+        self.clear_annotations();
+        for annotation in settings.annotations {
+            if let Err(e) = self.add_annotation(annotation) {
+                log::warn!("Skipping session annotation: {}", e);
+            }
+        }
+        self.apply_view(settings.view, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::layout_history::LayoutPosition;
+    use crate::saved_views::ViewLayers;
+
+    fn topology() -> serde_json::Value {
+        let element = |id: &str, x: f32| json!({
+            "name": id, "type": "Roadm", "type_variety": "default", "metadata": { "location": { "x": x, "y": 0.0 } }, "element_id": id,
+        });
+        json!({
+            "elements": [element("A", 0.0), element("B", 100.0)],
+            "connections": [{ "from_node": "A", "to_node": "B", "connection_id": "A-B" }],
+            "defrag_timeline_events": [{
+                "event_type": "ALLOCATION", "timestamp": 1.0, "service_id": 4,
+                "details": {
+                    "service_id": 4, "source_id": "A", "destination_id": "B", "arrival_time": 1.0, "departure_time": 8.0,
+                    "bit_rate": 100.0, "power": 0.0, "path": ["A", "B"], "wavelength": 3, "snr_requirement": 10.0, "gsnr": 14.5,
+                },
+            }],
+        })
+    }
+
+    fn settings() -> SessionSettings {
+        let view = |time: f64| SavedView {
+            camera_x: 12.5,
+            camera_y: -40.25,
+            zoom: 2.5,
+            time,
+            highlight_service_ids: Some(vec![4, 9]),
+            highlighted_path: Some(vec!["A".to_string(), "B".to_string()]),
+            selected_node_id: Some("B".to_string()),
+            layers: ViewLayers { capacity_bars: true, heat_trail: true, heat_trail_window: Some(30.0), flow_animation: false },
+        };
+        SessionSettings {
+            num_channels: 96,
+            channel_plan: Some(ChannelPlan { max_wavelengths: 96, ..ChannelPlan::default() }),
+            service_interval: ServiceIntervalSemantics::Closed,
+            highlight_style: HighlightStyle { thickness: 6.0, pulse: true, ..HighlightStyle::default() },
+            highlight_line_style: HighlightLineStyle::Marching,
+            lod_settings: LodSettings { aggregate_below_node_px: 2.0, detail_above_node_px: 4.0 },
+            label_settings: LabelSettings { background_chips: false, ..LabelSettings::default() },
+            view: view(4.5),
+            saved_views: BTreeMap::from([("incident".to_string(), view(7.0))]),
+            time_bookmarks: vec![TimeBookmark { time: 2.0, label: "defrag start".to_string() }],
+            annotations: vec![Annotation {
+                id: "cut".to_string(),
+                world_position: LayoutPosition { x: 50.0, y: 0.0 },
+                text: "fiber cut here".to_string(),
+                color: [1.0, 0.0, 0.0, 1.0],
+                anchor_element_id: Some("B".to_string()),
+            }],
+        }
+    }
+
+    /// 与 start_session_export 相同的文档结构
+    fn session_json(settings: &SessionSettings, topology: &serde_json::Value) -> String {
+        session_header(settings).unwrap() + &topology.to_string() + SESSION_FOOTER
+    }
+
+    #[test]
+    fn exported_session_round_trips() {
+        let settings = settings();
+        let document = parse_session_json(&session_json(&settings, &topology())).unwrap();
+        let imported = &document.settings;
+
+        assert_eq!((imported.view.camera_x, imported.view.camera_y, imported.view.zoom), (12.5, -40.25, 2.5));
+        assert_eq!(imported.view.time, 4.5);
+        assert_eq!(imported.view.highlight_service_ids, Some(vec![4, 9]));
+        assert_eq!(imported.view.highlighted_path, settings.view.highlighted_path);
+        assert_eq!(imported.view.selected_node_id.as_deref(), Some("B"));
+        assert_eq!(imported.channel_plan, settings.channel_plan);
+        assert_eq!(imported.service_interval, ServiceIntervalSemantics::Closed);
+        assert_eq!(imported.highlight_style, settings.highlight_style);
+        assert_eq!(imported.highlight_line_style, HighlightLineStyle::Marching);
+        assert_eq!(imported.label_settings, settings.label_settings);
+        assert_eq!(imported.saved_views["incident"].time, 7.0);
+        assert_eq!(imported.time_bookmarks.len(), 1);
+        assert_eq!((imported.time_bookmarks[0].time, imported.time_bookmarks[0].label.as_str()), (2.0, "defrag start"));
+        assert_eq!(imported.annotations.len(), 1);
+        let annotation = &imported.annotations[0];
+        assert_eq!((annotation.id.as_str(), annotation.text.as_str(), annotation.anchor_element_id.as_deref()), ("cut", "fiber cut here", Some("B")));
+        assert_eq!(annotation.color, [1.0, 0.0, 0.0, 1.0]);
+        // 其余字段（LOD 阈值、图层开关等）逐项相同
+        assert_eq!(serde_json::to_value(imported).unwrap(), serde_json::to_value(&settings).unwrap());
+
+        assert_eq!(document.topology.elements.len(), 2);
+        assert_eq!(document.topology.defrag_timeline_events.len(), 1);
+    }
+
+    #[test]
+    fn version_mismatch_is_rejected() {
+        let text = session_json(&settings(), &topology()).replacen("\"version\":1", "\"version\":2", 1);
+        assert_eq!(
+            parse_session_json(&text).unwrap_err(),
+            "Unsupported session version 2 (this viewer reads version 1); export the session again with a matching viewer.",
+        );
+        let error = parse_session_json(&topology().to_string()).unwrap_err();
+        assert_eq!(error, "Not a wdmview session: the document has no \"version\" field.");
+    }
+
+    #[test]
+    fn event_errors_report_service_id() {
+        let mut topology = topology();
+        topology["defrag_timeline_events"][0]["details"]["wavelength"] = json!("three");
+        let error = parse_session_json(&session_json(&settings(), &topology)).unwrap_err();
+        assert!(error.contains("topology.defrag_timeline_events[0].details.wavelength"), "{}", error);
+        assert!(error.ends_with("(service_id 4)"), "{}", error);
+    }
+}
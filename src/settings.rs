@@ -0,0 +1,549 @@
+// src/settings.rs
+// 可在运行时通过命令调整的渲染设置
+use bevy_color::{ColorToComponents, LinearRgba, Srgba};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::models::ThickLineVertex;
+
+/// 有限且大于 0（NaN 和无穷大返回 false），用于校验来自 JS 的长度、窗口和缩放等参数
+pub fn is_positive_finite(x: impl Into<f64>) -> bool {
+    let x = x.into();
+    x.is_finite() && x > 0.0
+}
+
+/// 有限且不小于 0（NaN 和无穷大返回 false）
+pub fn is_non_negative_finite(x: impl Into<f64>) -> bool {
+    let x = x.into();
+    x.is_finite() && x >= 0.0
+}
+
+/// 服务线路的细节层级 (Level of Detail)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LodLevel {
+    /// 逐条绘制服务线路（完整的波长扇形）
+    Detailed,
+    /// 不绘制单条服务线路，每条链路只绘制一个按占用率着色的四边形
+    Aggregated,
+}
+
+impl LodLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LodLevel::Detailed => "detailed",
+            LodLevel::Aggregated => "aggregated",
+        }
+    }
+}
+
+/// LOD 切换阈值。缩放程度以节点在屏幕上的半径（像素）衡量，
+/// 这样阈值与拓扑的世界坐标尺度无关。
+/// 进入和离开聚合模式使用不同的阈值（迟滞），避免在阈值附近缩放时来回闪烁。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LodSettings {
+    /// 节点屏幕半径低于该值时切换到聚合模式
+    pub aggregate_below_node_px: f32,
+    /// 节点屏幕半径高于该值时恢复逐条绘制
+    pub detail_above_node_px: f32,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            aggregate_below_node_px: 3.0,
+            detail_above_node_px: 5.0,
+        }
+    }
+}
+
+impl LodSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !is_non_negative_finite(self.aggregate_below_node_px) || !is_non_negative_finite(self.detail_above_node_px) {
+            return Err("LOD thresholds must be finite and non-negative".to_string());
+        }
+        if self.detail_above_node_px < self.aggregate_below_node_px {
+            return Err(format!(
+                "detail_above_node_px ({}) must not be smaller than aggregate_below_node_px ({})",
+                self.detail_above_node_px, self.aggregate_below_node_px
+            ));
+        }
+        Ok(())
+    }
+
+    /// 根据当前层级和节点屏幕半径计算下一层级（带迟滞）
+    pub fn next_level(&self, current: LodLevel, node_screen_radius: f32) -> LodLevel {
+        match current {
+            LodLevel::Detailed if node_screen_radius < self.aggregate_below_node_px => LodLevel::Aggregated,
+            LodLevel::Aggregated if node_screen_radius > self.detail_above_node_px => LodLevel::Detailed,
+            level => level,
+        }
+    }
+}
+
+/// 高亮的颜色与粗细，JSON 中省略的字段取默认值（与最初硬编码的效果一致）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighlightStyle {
+    /// 高亮节点的颜色（线性 RGBA），JSON 中为 "#rrggbb" 或 "#rrggbbaa"
+    #[serde(serialize_with = "serialize_hex_color", deserialize_with = "deserialize_hex_color")]
+    pub node_color: [f32; 4],
+    /// 高亮服务（碎片整理服务本身）的 OKLCH 亮度
+    pub service_lightness: f32,
+    /// 被碎片整理移动的服务的 OKLCH 亮度
+    pub moved_service_lightness: f32,
+    /// 存在高亮时其余服务的 OKLCH 亮度
+    pub dimmed_lightness: f32,
+    /// 高亮路径的线宽（世界单位）
+    pub thickness: f32,
+    /// 高亮路径以约 1 Hz 的频率明暗脉冲（需要持续重绘）
+    pub pulse: bool,
+}
+
+impl Default for HighlightStyle {
+    fn default() -> Self {
+        Self {
+            node_color: LinearRgba::from(Srgba::rgb_u8(0xd2, 0xa1, 0x06)).to_f32_array(), // 黄色 40
+            service_lightness: 0.75,
+            moved_service_lightness: 0.9,
+            dimmed_lightness: 0.4,
+            thickness: 0.5,
+            pulse: false,
+        }
+    }
+}
+
+impl HighlightStyle {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, lightness) in [
+            ("service_lightness", self.service_lightness),
+            ("moved_service_lightness", self.moved_service_lightness),
+            ("dimmed_lightness", self.dimmed_lightness),
+        ] {
+            if !(0.0..=1.0).contains(&lightness) {
+                return Err(format!("{} must be between 0 and 1, got {}", name, lightness));
+            }
+        }
+        if !is_positive_finite(self.thickness) {
+            return Err(format!("thickness must be finite and positive, got {}", self.thickness));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn deserialize_hex_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[f32; 4], D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    let color = Srgba::hex(&hex)
+        .map_err(|e| serde::de::Error::custom(format!("invalid color '{}': {}", hex, e)))?;
+    Ok(LinearRgba::from(color).to_f32_array())
+}
+
+pub(crate) fn serialize_hex_color<S: Serializer>(color: &[f32; 4], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&Srgba::from(LinearRgba::from_f32_array(*color)).to_hex())
+}
+
+/// 高亮服务路径的线型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightLineStyle {
+    Solid,
+    Dashed,
+    /// 沿路径方向移动的虚线（需要持续重绘）
+    Marching,
+}
+
+impl HighlightLineStyle {
+    pub fn dash_pattern(&self) -> u32 {
+        match self {
+            HighlightLineStyle::Solid => ThickLineVertex::SOLID,
+            HighlightLineStyle::Dashed => ThickLineVertex::DASHED,
+            HighlightLineStyle::Marching => ThickLineVertex::MARCHING,
+        }
+    }
+}
+
+impl std::str::FromStr for HighlightLineStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "solid" => Ok(HighlightLineStyle::Solid),
+            "dashed" => Ok(HighlightLineStyle::Dashed),
+            "marching" => Ok(HighlightLineStyle::Marching),
+            other => Err(format!("Unknown highlight style '{}', expected \"solid\", \"dashed\" or \"marching\"", other)),
+        }
+    }
+}
+
+/// 未高亮服务线路的宽度：Uniform 为 1 像素的 LineList；Bitrate 按 bit_rate 的对数在
+/// 最小到最大像素宽度之间插值，以四边形绘制（与高亮线路共用管线）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThicknessMode {
+    #[default]
+    Uniform,
+    Bitrate,
+}
+
+impl std::str::FromStr for ThicknessMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(ThicknessMode::Uniform),
+            "bitrate" => Ok(ThicknessMode::Bitrate),
+            other => Err(format!("Unknown thickness mode '{}', expected \"uniform\" or \"bitrate\"", other)),
+        }
+    }
+}
+
+/// 未高亮服务线路的不透明度：Uniform 为不透明（默认），Utilization 按 ServiceData::utilization（截断到 [0, 1]）
+/// 在 MIN_UTILIZATION_ALPHA 到 1 之间线性插值，利用率缺失或为 NaN 的服务不透明。与差异模式等其他透明度相乘
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpacityMode {
+    #[default]
+    Uniform,
+    Utilization,
+}
+
+/// 利用率为 0 的服务的不透明度，保留一点轮廓
+pub const MIN_UTILIZATION_ALPHA: f32 = 0.15;
+
+impl OpacityMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpacityMode::Uniform => "uniform",
+            OpacityMode::Utilization => "utilization",
+        }
+    }
+
+    /// 服务线路颜色的 alpha 乘数
+    pub fn alpha(&self, utilization: f32) -> f32 {
+        match self {
+            OpacityMode::Utilization if !utilization.is_nan() => {
+                MIN_UTILIZATION_ALPHA + (1.0 - MIN_UTILIZATION_ALPHA) * utilization.clamp(0.0, 1.0)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+impl std::str::FromStr for OpacityMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(OpacityMode::Uniform),
+            "utilization" => Ok(OpacityMode::Utilization),
+            other => Err(format!("Unknown opacity mode '{}', expected \"uniform\" or \"utilization\"", other)),
+        }
+    }
+}
+
+/// 服务活跃区间在离开时刻（departure_time）的边界语义。
+/// 默认 HalfOpen：`[arrival, departure)`，离开时刻服务已释放，与 ReleaseExpired 事件的时间戳一致。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceIntervalSemantics {
+    /// `[arrival, departure)`
+    #[default]
+    HalfOpen,
+    /// `[arrival, departure]`：离开时刻仍视为活跃，同一时刻的 ReleaseExpired 事件延后生效
+    Closed,
+}
+
+impl ServiceIntervalSemantics {
+    pub fn contains(&self, arrival: f64, departure: f64, time: f64) -> bool {
+        match self {
+            ServiceIntervalSemantics::HalfOpen => time >= arrival && time < departure,
+            ServiceIntervalSemantics::Closed => time >= arrival && time <= departure,
+        }
+    }
+
+    /// 在 `target_time` 重建状态时，时间戳为 `release_time` 的释放事件是否已经生效
+    pub fn release_applies(&self, release_time: f64, target_time: f64) -> bool {
+        match self {
+            ServiceIntervalSemantics::HalfOpen => release_time <= target_time,
+            ServiceIntervalSemantics::Closed => release_time < target_time,
+        }
+    }
+}
+
+impl std::str::FromStr for ServiceIntervalSemantics {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "half_open" => Ok(ServiceIntervalSemantics::HalfOpen),
+            "closed" => Ok(ServiceIntervalSemantics::Closed),
+            other => Err(format!("Unknown service interval semantics '{}', expected \"half_open\" or \"closed\"", other)),
+        }
+    }
+}
+
+/// 服务线路的着色方式，JSON 中为 `{"mode": "wavelength"}` 或 `{"mode": "gsnr", "ramp": {...}}`
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ColorMode {
+    /// 按波长编号分配色相（默认）
+    #[default]
+    Wavelength,
+    /// 按 GSNR 余量（gsnr - snr_requirement）着色：低于要求为红色，经黄色过渡到余量充足的绿色；
+    /// gsnr 为 NaN 或 0（未计算）的服务为中性灰
+    Gsnr {
+        #[serde(default)]
+        ramp: GsnrColorRamp,
+    },
+}
+
+impl ColorMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorMode::Wavelength => "wavelength",
+            ColorMode::Gsnr { .. } => "gsnr",
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ColorMode::Wavelength => Ok(()),
+            ColorMode::Gsnr { ramp } => ramp.validate(),
+        }
+    }
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wavelength" => Ok(ColorMode::Wavelength),
+            "gsnr" => Ok(ColorMode::Gsnr { ramp: GsnrColorRamp::default() }),
+            other => Err(format!("Unknown color mode '{}', expected \"wavelength\" or \"gsnr\"", other)),
+        }
+    }
+}
+
+/// GSNR 余量（dB）到颜色的发散色阶：余量 <= red_below_db 为红色，0 dB（刚好满足要求）为黄色，
+/// >= green_above_db 为绿色，之间按 OKLCH 色相线性插值
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GsnrColorRamp {
+    pub red_below_db: f32,
+    pub green_above_db: f32,
+}
+
+impl Default for GsnrColorRamp {
+    fn default() -> Self {
+        Self {
+            red_below_db: -1.0,
+            green_above_db: 3.0,
+        }
+    }
+}
+
+impl GsnrColorRamp {
+    const RED_HUE: f32 = 25.0;
+    const YELLOW_HUE: f32 = 95.0;
+    const GREEN_HUE: f32 = 145.0;
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.red_below_db.is_finite() || !self.green_above_db.is_finite() {
+            return Err("GSNR ramp bounds must be finite".to_string());
+        }
+        if !(self.red_below_db < 0.0 && self.green_above_db > 0.0) {
+            return Err(format!(
+                "GSNR ramp must satisfy red_below_db < 0 < green_above_db, got {} and {}",
+                self.red_below_db, self.green_above_db
+            ));
+        }
+        Ok(())
+    }
+
+    /// GSNR 余量（dB）对应的 OKLCH 色相
+    pub fn hue(&self, margin_db: f32) -> f32 {
+        if margin_db < 0.0 {
+            let t = (margin_db / self.red_below_db).clamp(0.0, 1.0);
+            Self::YELLOW_HUE + (Self::RED_HUE - Self::YELLOW_HUE) * t
+        } else {
+            let t = (margin_db / self.green_above_db).clamp(0.0, 1.0);
+            Self::YELLOW_HUE + (Self::GREEN_HUE - Self::YELLOW_HUE) * t
+        }
+    }
+}
+
+/// 信道规划：每条链路的波长数，以及服务线路在链路内展开的角度。
+/// 角度为弧度，在节点圆周上相对链路方向测量：boundary_angle 为链路两条边界线的位置，
+/// spread_angle 为编号最小 / 最大的波长的位置（其余波长均匀分布在两者之间），应略小于 boundary_angle。
+/// JSON 中省略的字段取默认值（C 波段 80 波）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelPlan {
+    pub max_wavelengths: u32,
+    pub spread_angle: f32,
+    pub boundary_angle: f32,
+}
+
+/// max_wavelengths 的上限：远多于 C+L 波段的 12.5 GHz 栅格（约 960 个频隙），
+/// 更大的值会让波长颜色表和每条链路内的线路间距失去意义
+pub const MAX_WAVELENGTHS: u32 = 1024;
+
+impl Default for ChannelPlan {
+    fn default() -> Self {
+        let boundary_angle = std::f32::consts::PI / 16.0;
+        Self { max_wavelengths: 80, spread_angle: boundary_angle * 0.95, boundary_angle }
+    }
+}
+
+impl ChannelPlan {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_wavelengths == 0 {
+            return Err("max_wavelengths must be positive".to_string());
+        }
+        if self.max_wavelengths > MAX_WAVELENGTHS {
+            return Err(format!("max_wavelengths must be at most {}, got {}", MAX_WAVELENGTHS, self.max_wavelengths));
+        }
+        for (name, angle) in [("spread_angle", self.spread_angle), ("boundary_angle", self.boundary_angle)] {
+            if !(angle > 0.0 && angle < std::f32::consts::FRAC_PI_2) {
+                return Err(format!("{} must be between 0 and π/2 radians (exclusive), got {}", name, angle));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 画布上的文字标签（节点标签、路径注释、对比视图的时刻和统计信息）的显示方式，JSON 中省略的字段取默认值
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LabelSettings {
+    /// 在文字后面绘制半透明的底色块（颜色见 OverlayTheme::label_chip），避免文字与密集的服务线路混在一起
+    pub background_chips: bool,
+    /// 节点屏幕半径（像素）不小于该值时才显示标签的附加行（节点类型），高于名称行的显示阈值，缩小时保持整洁
+    pub detail_lines_min_node_px: f32,
+    /// 节点屏幕半径从 fade_start 增大到 fade_end 的过程中标签（文字和底色块）逐渐显现，低于 fade_start 时不显示，
+    /// 避免缩放经过阈值时标签突然出现或消失
+    pub fade_start_node_px: f32,
+    pub fade_end_node_px: f32,
+}
+
+impl Default for LabelSettings {
+    fn default() -> Self {
+        Self {
+            background_chips: true,
+            detail_lines_min_node_px: 90.0,
+            fade_start_node_px: 50.0,
+            fade_end_node_px: 70.0,
+        }
+    }
+}
+
+impl LabelSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("detail_lines_min_node_px", self.detail_lines_min_node_px),
+            ("fade_start_node_px", self.fade_start_node_px),
+            ("fade_end_node_px", self.fade_end_node_px),
+        ] {
+            if !is_non_negative_finite(value) {
+                return Err(format!("{} must be finite and non-negative, got {}", name, value));
+            }
+        }
+        if self.fade_end_node_px < self.fade_start_node_px {
+            return Err(format!(
+                "fade_end_node_px ({}) must not be smaller than fade_start_node_px ({})",
+                self.fade_end_node_px, self.fade_start_node_px
+            ));
+        }
+        Ok(())
+    }
+
+    /// 节点屏幕半径对应的标签不透明度：fade_start 以下为 0（不绘制），渐显区间内线性增加，fade_end 以上为 1
+    pub fn label_alpha(&self, node_screen_radius: f32) -> f32 {
+        if node_screen_radius < self.fade_start_node_px {
+            return 0.0;
+        }
+        let band = self.fade_end_node_px - self.fade_start_node_px;
+        if band <= 0.0 {
+            return 1.0;
+        }
+        ((node_screen_radius - self.fade_start_node_px) / band).clamp(0.0, 1.0)
+    }
+}
+
+/// 画布背景（setBackground），例如 `{"color": "#0b1020", "opacity": 0}`，省略的字段取默认值（不透明的黑色）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Background {
+    /// 线性 RGB，alpha 分量不使用；JSON 中为 "#rrggbb"
+    #[serde(serialize_with = "serialize_hex_color", deserialize_with = "deserialize_hex_color")]
+    pub color: [f32; 4],
+    /// 0 为完全透明（显示画布下面的页面内容），1 为不透明
+    pub opacity: f32,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self { color: [0.0, 0.0, 0.0, 1.0], opacity: 1.0 }
+    }
+}
+
+impl Background {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(format!("opacity must be between 0 and 1, got {}", self.opacity));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_interval_boundaries() {
+        let (arrival, departure) = (10.0, 20.0);
+        for semantics in [ServiceIntervalSemantics::HalfOpen, ServiceIntervalSemantics::Closed] {
+            assert!(semantics.contains(arrival, departure, arrival), "{:?} at arrival", semantics);
+            assert!(!semantics.contains(arrival, departure, arrival - 1e-9), "{:?} before arrival", semantics);
+            assert!(semantics.release_applies(departure, departure + 1e-9), "{:?} after departure", semantics);
+        }
+        assert!(!ServiceIntervalSemantics::HalfOpen.contains(arrival, departure, departure));
+        assert!(ServiceIntervalSemantics::Closed.contains(arrival, departure, departure));
+        assert!(ServiceIntervalSemantics::HalfOpen.release_applies(departure, departure));
+        assert!(!ServiceIntervalSemantics::Closed.release_applies(departure, departure));
+    }
+
+    #[test]
+    fn channel_plan_bounds() {
+        assert!(ChannelPlan::default().validate().is_ok());
+        let with_wavelengths = |max_wavelengths| ChannelPlan { max_wavelengths, ..ChannelPlan::default() };
+        assert!(with_wavelengths(0).validate().is_err());
+        assert!(with_wavelengths(1).validate().is_ok());
+        assert!(with_wavelengths(MAX_WAVELENGTHS).validate().is_ok());
+        assert!(with_wavelengths(MAX_WAVELENGTHS + 1).validate().is_err());
+        assert!(with_wavelengths(u32::MAX).validate().is_err());
+
+        let with_angles = |spread_angle, boundary_angle| ChannelPlan { spread_angle, boundary_angle, ..ChannelPlan::default() };
+        let half_pi = std::f32::consts::FRAC_PI_2;
+        assert!(with_angles(0.1, half_pi - 1e-3).validate().is_ok());
+        for (spread_angle, boundary_angle) in [(0.0, 0.2), (0.1, half_pi), (-0.1, 0.2), (f32::NAN, 0.2), (0.1, f32::INFINITY)] {
+            assert!(with_angles(spread_angle, boundary_angle).validate().is_err(), "spread {} boundary {}", spread_angle, boundary_angle);
+        }
+    }
+
+    #[test]
+    fn label_alpha_fades_across_band() {
+        let settings = LabelSettings { fade_start_node_px: 50.0, fade_end_node_px: 70.0, ..Default::default() };
+        assert_eq!(settings.label_alpha(49.9), 0.0);
+        assert_eq!(settings.label_alpha(50.0), 0.0);
+        assert_eq!(settings.label_alpha(60.0), 0.5);
+        assert_eq!(settings.label_alpha(70.0), 1.0);
+        assert_eq!(settings.label_alpha(500.0), 1.0);
+
+        let zero_width = LabelSettings { fade_start_node_px: 60.0, fade_end_node_px: 60.0, ..Default::default() };
+        assert!(zero_width.validate().is_ok());
+        assert_eq!(zero_width.label_alpha(59.9), 0.0);
+        assert_eq!(zero_width.label_alpha(60.0), 1.0);
+    }
+}
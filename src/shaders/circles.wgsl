@@ -6,6 +6,10 @@ struct CameraUniform {
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
 
+// 光晕向外延伸的范围（相对于半径），以及光晕的最大不透明度
+const GLOW_EXTENT: f32 = 0.5;
+const GLOW_MAX_ALPHA: f32 = 0.6;
+
 // 转换线性颜色到 sRGB 颜色（用于在非 sRGB 表面上正确显示）
 fn linear_to_srgb(c: f32) -> f32 {
     if c < 0.0031308 { // This value is 0.04045 / 12.92
@@ -25,13 +29,15 @@ struct CircleInstanceInput {
     @location(1) instance_world_position: vec2<f32>, // 圆心世界坐标
     @location(2) instance_radius_scale: f32,         // 半径 (世界单位)
     @location(3) instance_color: vec4<f32>,          // 颜色
+    @location(4) instance_glow: f32,                 // 光晕强度 (0 表示无光晕)
 };
 
 // 片元着色器输入结构
 struct CircleFragmentInput {
     @builtin(position) clip_position: vec4<f32>, // 裁剪空间位置
     @location(0) color: vec4<f32>,               // 传递给片元着色器的颜色
-    @location(1) uv: vec2<f32>,                 // 四边形内的 UV 坐标 (-1.0 到 1.0)
+    @location(1) uv: vec2<f32>,                 // 四边形内的 UV 坐标 (-1.0 到 1.0，有光晕时扩大)
+    @location(2) glow: f32,
 };
 
 @vertex
@@ -44,7 +50,9 @@ fn vs_main(
     // 将基础四边形的局部坐标按实例半径缩放
     // quad_position 从 -0.5 到 0.5，乘以 radius_scale * 2.0 后，表示四边形的实际半宽/高
     // 例如，如果 radius_scale 是 25.0，则四边形从 -25 到 25 (总宽 50.0)
-    let scaled_quad_local_offset = quad.quad_position * instance.instance_radius_scale * 2.0;
+    // 有光晕的节点需要放大四边形，否则光晕会被裁剪；无光晕时缩放系数恰为 1.0
+    let quad_scale = select(1.0, 1.0 + GLOW_EXTENT, instance.instance_glow > 0.0);
+    let scaled_quad_local_offset = quad.quad_position * quad_scale * instance.instance_radius_scale * 2.0;
 
     // 将缩放后的局部偏移量加到圆心世界坐标，得到最终的世界位置
     let world_position = instance.instance_world_position + scaled_quad_local_offset;
@@ -54,7 +62,9 @@ fn vs_main(
     out.color = instance.instance_color;
 
     // 计算并传递 UV 坐标给片元着色器，范围从 -1.0 到 1.0 (方便距离计算)
-    out.uv = quad.quad_position * 2.0;
+    // 圆边界始终对应 dist == 1.0
+    out.uv = quad.quad_position * 2.0 * quad_scale;
+    out.glow = instance.instance_glow;
     return out;
 }
 
@@ -67,7 +77,13 @@ fn fs_main(in: CircleFragmentInput) -> @location(0) vec4<f32> {
     // 使用 smoothstep 函数进行抗锯齿处理，使圆形边缘平滑
     // 当 dist 接近 1.0 (圆边界) 时，alpha 从 1.0 平滑过渡到 0.0
     // 参数 0.98 和 1.0 定义了平滑过渡的范围
-    let alpha = smoothstep(1.0, 0.98, dist);
+    var alpha = smoothstep(1.0, 0.98, dist);
+
+    // 光晕：圆外在 GLOW_EXTENT 范围内平滑衰减，使用节点自身（高亮）颜色
+    if in.glow > 0.0 {
+        let falloff = 1.0 - smoothstep(1.0, 1.0 + GLOW_EXTENT, dist);
+        alpha = max(alpha, falloff * falloff * GLOW_MAX_ALPHA * min(in.glow, 1.0));
+    }
 
     // 如果 alpha 极小，则丢弃该片元，优化性能（不绘制完全透明的像素）
     if alpha < 0.01 {
@@ -81,5 +97,6 @@ fn fs_main(in: CircleFragmentInput) -> @location(0) vec4<f32> {
         final_color.g = linear_to_srgb(final_color.g);
         final_color.b = linear_to_srgb(final_color.b);
     }
-    return final_color;
+        // 预乘 alpha，见 lines.wgsl
+    return vec4<f32>(final_color.rgb * final_color.a, final_color.a);
 }
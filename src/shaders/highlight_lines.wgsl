@@ -3,10 +3,20 @@
 struct CameraUniform {
     view_proj: mat4x4<f32>,
     needs_srgb_output_conversion: u32,
+    elapsed_time: f32,      // 动画时间 (秒)
+    world_to_pixels: f32,   // 每个世界单位对应的屏幕像素数
+    highlight_pulse: f32,   // 脉冲动画的透明度系数，1.0 为不闪烁
 };
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
 
+// 虚线图案 (对应 ThickLineVertex::SOLID / DASHED / MARCHING)
+const DASH_PATTERN_DASHED: u32 = 1u;
+const DASH_PATTERN_MARCHING: u32 = 2u;
+const DASH_PERIOD_PX: f32 = 16.0;      // 一个“实线 + 间隔”周期的屏幕像素长度
+const DASH_ON_FRACTION: f32 = 0.6;     // 周期内实线部分的比例
+const MARCHING_SPEED_PX: f32 = 32.0;   // 流动虚线的移动速度 (像素/秒)
+
 // 转换线性颜色到 sRGB 颜色
 fn linear_to_srgb(c: f32) -> f32 {
     if c < 0.0031308 {
@@ -16,16 +26,20 @@ fn linear_to_srgb(c: f32) -> f32 {
     }
 }
 
-// 顶点着色器输入结构 (对应 LineVertex)
+// 顶点着色器输入结构 (对应 ThickLineVertex)
 struct LineVertexInput {
     @location(0) position: vec2<f32>, // 顶点世界坐标
     @location(1) color: vec4<f32>,    // 顶点颜色
+    @location(2) path_distance: f32,  // 沿路径的累计距离 (世界单位)
+    @location(3) dash_pattern: u32,   // 虚线图案
 };
 
 // 片元着色器输入结构
 struct LineFragmentInput {
     @builtin(position) clip_position: vec4<f32>, // 裁剪空间位置
     @location(0) color: vec4<f32>,               // 传递给片元着色器的颜色
+    @location(1) path_distance: f32,
+    @location(2) @interpolate(flat) dash_pattern: u32,
 };
 
 @vertex
@@ -36,16 +50,31 @@ fn vs_main(
     // 将世界坐标转换为裁剪空间坐标
     out.clip_position = camera.view_proj * vec4<f32>(model.position, 0.0, 1.0);
     out.color = model.color; // 直接传递顶点颜色
+    out.path_distance = model.path_distance;
+    out.dash_pattern = model.dash_pattern;
     return out;
 }
 
 @fragment
 fn fs_main(in: LineFragmentInput) -> @location(0) vec4<f32> {
+    if in.dash_pattern == DASH_PATTERN_DASHED || in.dash_pattern == DASH_PATTERN_MARCHING {
+        // 以屏幕像素计算虚线，使缩放时虚线长度保持不变
+        var distance_px = in.path_distance * camera.world_to_pixels;
+        if in.dash_pattern == DASH_PATTERN_MARCHING {
+            distance_px = distance_px - camera.elapsed_time * MARCHING_SPEED_PX;
+        }
+        if fract(distance_px / DASH_PERIOD_PX) > DASH_ON_FRACTION {
+            discard;
+        }
+    }
+
     var final_color = in.color;
+    final_color.a = final_color.a * camera.highlight_pulse;
     if camera.needs_srgb_output_conversion == 1u {
         final_color.r = linear_to_srgb(final_color.r);
         final_color.g = linear_to_srgb(final_color.g);
         final_color.b = linear_to_srgb(final_color.b);
     }
-    return final_color;
+        // 预乘 alpha（包括闪烁系数），见 lines.wgsl
+    return vec4<f32>(final_color.rgb * final_color.a, final_color.a);
 }
@@ -47,6 +47,8 @@ fn fs_main(in: LineFragmentInput) -> @location(0) vec4<f32> {
         final_color.g = linear_to_srgb(final_color.g);
         final_color.b = linear_to_srgb(final_color.b);
     }
-    
-    return final_color;
+
+    // 输出预乘 alpha 的颜色（管线使用 PREMULTIPLIED_ALPHA_BLENDING）：颜色和 alpha 通道按同一个 over 运算合成，
+    // 半透明线路交叠处以及半透明的渲染目标上不会偏暗
+    return vec4<f32>(final_color.rgb * final_color.a, final_color.a);
 }
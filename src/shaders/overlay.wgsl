@@ -0,0 +1,46 @@
+// 屏幕空间叠加层（服务列表面板等）：顶点已是 NDC 坐标，不经过相机变换，只使用 sRGB 输出标志
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    needs_srgb_output_conversion: u32,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c < 0.0031308 {
+        return c * 12.92;
+    } else {
+        return 1.055 * pow(c, 1.0 / 2.4) - 0.055;
+    }
+}
+
+// 与 LineVertex 布局相同，position 为 NDC 坐标
+struct OverlayVertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct OverlayFragmentInput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(model: OverlayVertexInput) -> OverlayFragmentInput {
+    var out: OverlayFragmentInput;
+    out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+    out.color = model.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: OverlayFragmentInput) -> @location(0) vec4<f32> {
+    var final_color = in.color;
+    if camera.needs_srgb_output_conversion == 1u {
+        final_color.r = linear_to_srgb(final_color.r);
+        final_color.g = linear_to_srgb(final_color.g);
+        final_color.b = linear_to_srgb(final_color.b);
+    }
+        // 预乘 alpha，见 lines.wgsl
+    return vec4<f32>(final_color.rgb * final_color.a, final_color.a);
+}
@@ -0,0 +1,76 @@
+// src/shaders/picking.wgsl
+// 拾取 (picking) 着色器：将节点和服务线段渲染到离屏 R32Uint 纹理，每个片元写入对象 ID
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    needs_srgb_output_conversion: u32,
+    elapsed_time: f32,
+    world_to_pixels: f32,
+};
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+// 与 picking.rs 中的 PICK_NODE_FLAG 保持一致
+const PICK_NODE_FLAG: u32 = 0x80000000u;
+
+// --- 节点 (复用 Vertex2D + CircleInstance 顶点数据) ---
+struct CirclePickInput {
+    @location(0) quad_position: vec2<f32>,
+    @location(1) instance_world_position: vec2<f32>,
+    @location(2) instance_radius_scale: f32,
+};
+
+struct CirclePickFragmentInput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) pick_id: u32,
+};
+
+@vertex
+fn vs_circle(
+    model: CirclePickInput,
+    @builtin(instance_index) instance_index: u32,
+) -> CirclePickFragmentInput {
+    var out: CirclePickFragmentInput;
+    let world_position = model.instance_world_position + model.quad_position * model.instance_radius_scale * 2.0;
+    out.clip_position = camera.view_proj * vec4<f32>(world_position, 0.0, 1.0);
+    out.uv = model.quad_position * 2.0;
+    out.pick_id = PICK_NODE_FLAG | instance_index;
+    return out;
+}
+
+@fragment
+fn fs_circle(in: CirclePickFragmentInput) -> @location(0) u32 {
+    // 只有圆内的片元才算命中，与可见的圆形边界一致
+    if dot(in.uv, in.uv) > 1.0 {
+        discard;
+    }
+    return in.pick_id;
+}
+
+// --- 服务线段 (复用 LineVertex / ThickLineVertex，ID 来自单独的顶点缓冲区) ---
+struct LinePickInput {
+    @location(0) position: vec2<f32>,
+    @location(5) pick_id: u32,
+};
+
+struct LinePickFragmentInput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) @interpolate(flat) pick_id: u32,
+};
+
+@vertex
+fn vs_line(model: LinePickInput) -> LinePickFragmentInput {
+    var out: LinePickFragmentInput;
+    out.clip_position = camera.view_proj * vec4<f32>(model.position, 0.0, 1.0);
+    out.pick_id = model.pick_id;
+    return out;
+}
+
+@fragment
+fn fs_line(in: LinePickFragmentInput) -> @location(0) u32 {
+    // ID 为 0 的线（链路边界）不参与拾取
+    if in.pick_id == 0u {
+        discard;
+    }
+    return in.pick_id;
+}
@@ -0,0 +1,507 @@
+// src/synthetic.rs
+// 合成拓扑生成器（基准测试用）：N 个节点排成网格或随机扰动的网格（随机几何图），按长度从短到长连接 M 条链路
+// （先保证连通），再按泊松到达 / 指数持续时间生成 K 个服务请求的时间轴：路径为最少跳路径，波长按首次命中分配，
+// 部分到达会先把若干活跃服务重分配到更低的波长（碎片整理）。使用内置的 SplitMix64，
+// 相同参数和种子在所有平台上生成相同的数据；节点都有位置，事件只引用存在的节点，因此加载时没有验证警告。
+// 用法见 examples/generate_topology.rs 和原生命令行的 `--generate n=5000`。
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::str::FromStr;
+
+use glam::Vec2;
+
+use crate::scene::connection::ConnectionData;
+use crate::scene::defrag_event::{AnyEvent, ReallocationDetails, ReleaseExpiredDetails};
+use crate::scene::element::{ElementData, Location, Metadata};
+use crate::scene::graph::{PathWeight, TopologyGraph};
+pub use crate::scene::network::FullTopologyData;
+use crate::scene::service::ServiceData;
+use crate::settings::{is_positive_finite, ChannelPlan};
+
+/// 相邻网格点的距离（世界单位），远大于节点直径
+const NODE_SPACING: f32 = 100.0;
+/// 随机布局中每个节点在网格点附近的最大偏移（相对于 NODE_SPACING），保证节点互不重叠
+const RANDOM_JITTER: f32 = 0.25;
+/// 候选链路：每个节点到附近最近的这么多个节点
+const NEAREST_NEIGHBORS: usize = 6;
+/// 到达间隔的均值（秒）
+const MEAN_INTERARRIVAL_TIME: f64 = 1.0;
+/// 碎片整理到达时最多移动的服务数
+const MAX_MOVED_PER_DEFRAG: usize = 2;
+const BIT_RATES: [f32; 3] = [100.0, 200.0, 400.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticLayout {
+    Grid,
+    /// 每个节点在网格点附近随机偏移
+    Random,
+}
+
+impl FromStr for SyntheticLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grid" => Ok(SyntheticLayout::Grid),
+            "random" => Ok(SyntheticLayout::Random),
+            other => Err(format!("Unknown layout '{}', expected \"grid\" or \"random\"", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntheticParams {
+    pub nodes: usize,
+    /// 链路数，None 为 2 × nodes；至少 nodes - 1（保证连通），最多为候选链路数
+    pub connections: Option<usize>,
+    /// 服务请求（到达）数，None 为 2 × nodes；没有空闲波长的请求被阻塞，不产生事件
+    pub services: Option<usize>,
+    pub channels: u32,
+    pub layout: SyntheticLayout,
+    pub seed: u64,
+    /// 服务持续时间的均值（秒），None 为到达总时长的四分之一
+    pub mean_holding_time: Option<f64>,
+    /// 到达时触发碎片整理（重分配）的概率
+    pub reallocation_probability: f64,
+}
+
+impl Default for SyntheticParams {
+    fn default() -> Self {
+        Self {
+            nodes: 1000,
+            connections: None,
+            services: None,
+            channels: 80,
+            layout: SyntheticLayout::Grid,
+            seed: 1,
+            mean_holding_time: None,
+            reallocation_probability: 0.05,
+        }
+    }
+}
+
+impl SyntheticParams {
+    /// 解析逗号分隔的 `key=value` 列表，例如 `n=5000,m=12000,k=20000,layout=random,seed=7`
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let mut params = Self::default();
+        for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Expected key=value, got '{}'", entry))?;
+            params.set(key.trim(), value.trim())?;
+        }
+        Ok(params)
+    }
+
+    /// 设置一个参数，键可以用全名或缩写（n / m / k）
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+            value.parse().map_err(|_| format!("Invalid value '{}' for {}", value, key))
+        }
+        match key {
+            "n" | "nodes" => self.nodes = parse(key, value)?,
+            "m" | "connections" => self.connections = Some(parse(key, value)?),
+            "k" | "services" => self.services = Some(parse(key, value)?),
+            "channels" => self.channels = parse(key, value)?,
+            "layout" => self.layout = value.parse()?,
+            "seed" => self.seed = parse(key, value)?,
+            "holding" => self.mean_holding_time = Some(parse(key, value)?),
+            "realloc" => self.reallocation_probability = parse(key, value)?,
+            other => return Err(format!(
+                "Unknown parameter '{}', expected n, m, k, channels, layout, seed, holding or realloc", other
+            )),
+        }
+        self.validate()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.nodes < 2 {
+            return Err(format!("At least 2 nodes are required, got {}", self.nodes));
+        }
+        if self.channels == 0 {
+            return Err("channels must be positive".to_string());
+        }
+        if let Some(holding) = self.mean_holding_time && !is_positive_finite(holding) {
+            return Err(format!("holding must be positive, got {}", holding));
+        }
+        if !(0.0..=1.0).contains(&self.reallocation_probability) {
+            return Err(format!("realloc must be between 0 and 1, got {}", self.reallocation_probability));
+        }
+        Ok(())
+    }
+}
+
+/// SplitMix64：不依赖外部 crate，固定种子时在所有平台上生成相同的序列
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// [0, 1) 内的均匀分布
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// [0, n) 内的整数
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_f64() * n as f64) as usize
+    }
+
+    fn exponential(&mut self, mean: f64) -> f64 {
+        -mean * (1.0 - self.next_f64()).ln()
+    }
+}
+
+pub fn generate(params: &SyntheticParams) -> FullTopologyData {
+    let mut rng = SplitMix64(params.seed);
+    let columns = (params.nodes as f64).sqrt().ceil() as usize;
+    let positions = node_positions(params, columns, &mut rng);
+    let links = build_links(&positions, columns, params.connections.unwrap_or(params.nodes * 2), &mut rng);
+
+    let elements: Vec<ElementData> = positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| ElementData {
+            name: format!("Node {}", i),
+            node_type: "Roadm".to_string(),
+            type_variety: "default".to_string(),
+            metadata: Metadata { location: Some(Location { x: position.x, y: position.y }) },
+            element_id: node_id(i),
+        })
+        .collect();
+    let connections: Vec<ConnectionData> = links
+        .iter()
+        .enumerate()
+        .map(|(i, &(a, b))| ConnectionData { from_node: node_id(a), to_node: node_id(b), connection_id: format!("c{}", i) })
+        .collect();
+
+    let defrag_timeline_events = service_timeline(params, &elements, &connections, &mut rng);
+    log::info!(
+        "Generated synthetic topology: {} nodes, {} links, {} events (seed {}).",
+        elements.len(), connections.len(), defrag_timeline_events.len(), params.seed
+    );
+    let channel_plan = Some(ChannelPlan { max_wavelengths: params.channels, ..ChannelPlan::default() });
+    FullTopologyData { name: None, elements, connections, defrag_timeline_events, channel_plan, blocked_demands: Vec::new() }
+}
+
+fn node_id(idx: usize) -> String {
+    format!("n{}", idx)
+}
+
+fn node_positions(params: &SyntheticParams, columns: usize, rng: &mut SplitMix64) -> Vec<Vec2> {
+    (0..params.nodes)
+        .map(|i| {
+            let grid_point = Vec2::new((i % columns) as f32, (i / columns) as f32) * NODE_SPACING;
+            match params.layout {
+                SyntheticLayout::Grid => grid_point,
+                SyntheticLayout::Random => {
+                    let jitter = Vec2::new(rng.next_f64() as f32, rng.next_f64() as f32) * 2.0 - 1.0;
+                    grid_point + jitter * (RANDOM_JITTER * NODE_SPACING)
+                }
+            }
+        })
+        .collect()
+}
+
+/// 候选链路为每个节点到附近（两圈网格内）最近的 NEAREST_NEIGHBORS 个节点，按长度排序（等长的随机排列）。
+/// 先按 Kruskal 取生成树保证连通，再按长度补足到 target 条
+fn build_links(positions: &[Vec2], columns: usize, target: usize, rng: &mut SplitMix64) -> Vec<(usize, usize)> {
+    let node_count = positions.len();
+    let rows = node_count.div_ceil(columns);
+    let mut candidates = Vec::new();
+    for (i, &position) in positions.iter().enumerate() {
+        let (column, row) = ((i % columns) as isize, (i / columns) as isize);
+        let mut nearby: Vec<(f32, usize)> = Vec::new();
+        for neighbor_row in (row - 2).max(0)..=(row + 2).min(rows as isize - 1) {
+            for neighbor_column in (column - 2).max(0)..=(column + 2).min(columns as isize - 1) {
+                let j = neighbor_row as usize * columns + neighbor_column as usize;
+                if j != i && j < node_count {
+                    nearby.push((position.distance(positions[j]), j));
+                }
+            }
+        }
+        nearby.sort_by(|a, b| a.0.total_cmp(&b.0));
+        candidates.extend(nearby.into_iter().take(NEAREST_NEIGHBORS).map(|(_, j)| (i.min(j), i.max(j))));
+    }
+    candidates.sort_unstable();
+    candidates.dedup();
+    for i in (1..candidates.len()).rev() {
+        candidates.swap(i, rng.below(i + 1));
+    }
+    candidates.sort_by(|&(a1, b1), &(a2, b2)| positions[a1].distance(positions[b1]).total_cmp(&positions[a2].distance(positions[b2])));
+
+    let target = if target < node_count - 1 {
+        log::warn!("{} links cannot connect {} nodes; generating {}.", target, node_count, node_count - 1);
+        node_count - 1
+    } else {
+        target
+    };
+
+    let mut components: Vec<usize> = (0..node_count).collect();
+    fn find(components: &mut [usize], mut node: usize) -> usize {
+        while components[node] != node {
+            components[node] = components[components[node]];
+            node = components[node];
+        }
+        node
+    }
+    let mut in_tree = vec![false; candidates.len()];
+    let mut links = Vec::with_capacity(target);
+    for (used, &(a, b)) in in_tree.iter_mut().zip(&candidates) {
+        let (root_a, root_b) = (find(&mut components, a), find(&mut components, b));
+        if root_a != root_b {
+            components[root_a] = root_b;
+            *used = true;
+            links.push((a, b));
+        }
+    }
+    // 候选链路不连通时（随机布局下极少见）连接按编号相邻的节点
+    for i in 1..node_count {
+        let (root_a, root_b) = (find(&mut components, i - 1), find(&mut components, i));
+        if root_a != root_b {
+            components[root_a] = root_b;
+            links.push((i - 1, i));
+        }
+    }
+
+    let mut extra = candidates.iter().zip(&in_tree).filter(|(_, used)| !**used).map(|(&link, _)| link);
+    while links.len() < target {
+        match extra.next() {
+            Some(link) => links.push(link),
+            None => {
+                log::warn!("Only {} candidate links are available; generating {} instead of {}.", links.len(), links.len(), target);
+                break;
+            }
+        }
+    }
+    links
+}
+
+/// 每条链路上各波长是否被占用
+struct SpectrumOccupancy {
+    link_index: HashMap<(usize, usize), usize>,
+    used: Vec<Vec<bool>>,
+}
+
+impl SpectrumOccupancy {
+    fn hops<'a>(&'a self, path: &'a [usize]) -> impl Iterator<Item = usize> + 'a {
+        path.windows(2).filter_map(|hop| self.link_index.get(&(hop[0].min(hop[1]), hop[0].max(hop[1]))).copied())
+    }
+
+    /// 路径上所有链路都空闲的最低波长（波长连续性约束），只考虑低于 below 的波长
+    fn first_fit(&self, path: &[usize], below: usize) -> Option<usize> {
+        (0..below).find(|&wavelength| self.hops(path).all(|link| !self.used[link][wavelength]))
+    }
+
+    fn set(&mut self, path: &[usize], wavelength: usize, used: bool) {
+        let links: Vec<usize> = self.hops(path).collect();
+        for link in links {
+            self.used[link][wavelength] = used;
+        }
+    }
+}
+
+/// 生成时间轴时的状态
+struct TimelineBuilder {
+    spectrum: SpectrumOccupancy,
+    /// 活跃服务：service_id -> (服务数据, 路径的节点索引)
+    active: HashMap<i32, (ServiceData, Vec<usize>)>,
+    /// active 的键，按固定的顺序保存以便可复现地随机选择；active_positions 为每个键在其中的下标
+    active_ids: Vec<i32>,
+    active_positions: HashMap<i32, usize>,
+    /// 按离开时间排序；非负 f64 的位模式与数值同序
+    departures: BinaryHeap<Reverse<(u64, i32)>>,
+    events: Vec<AnyEvent>,
+}
+
+impl TimelineBuilder {
+    fn insert_active(&mut self, service: ServiceData, path: Vec<usize>) {
+        self.active_positions.insert(service.service_id, self.active_ids.len());
+        self.active_ids.push(service.service_id);
+        self.active.insert(service.service_id, (service, path));
+    }
+
+    fn remove_active(&mut self, service_id: i32) -> Option<(ServiceData, Vec<usize>)> {
+        let position = self.active_positions.remove(&service_id)?;
+        self.active_ids.swap_remove(position);
+        if let Some(&moved_id) = self.active_ids.get(position) {
+            self.active_positions.insert(moved_id, position);
+        }
+        self.active.remove(&service_id)
+    }
+
+    /// 为离开时间不晚于 time 的服务生成释放事件并释放波长
+    fn release_until(&mut self, time: f64) {
+        while let Some(&Reverse((departure_bits, service_id))) = self.departures.peek() {
+            let departure_time = f64::from_bits(departure_bits);
+            if departure_time > time {
+                break;
+            }
+            self.departures.pop();
+            if let Some((service, path)) = self.remove_active(service_id) {
+                self.spectrum.set(&path, service.wavelength as usize, false);
+                self.events.push(AnyEvent::ReleaseExpired { timestamp: departure_time, service_id, details: ReleaseExpiredDetails { departure_time } });
+            }
+        }
+    }
+}
+
+fn service_timeline(params: &SyntheticParams, elements: &[ElementData], connections: &[ConnectionData], rng: &mut SplitMix64) -> Vec<AnyEvent> {
+    let node_count = elements.len();
+    let requests = params.services.unwrap_or(node_count * 2);
+    let mean_holding_time = params.mean_holding_time.unwrap_or(requests as f64 * MEAN_INTERARRIVAL_TIME / 4.0);
+    let channels = params.channels as usize;
+
+    let node_id_to_idx: HashMap<String, usize> = elements.iter().enumerate().map(|(i, element)| (element.element_id.clone(), i)).collect();
+    let graph = TopologyGraph::new(node_count, &node_id_to_idx, connections);
+    let link_index = connections
+        .iter()
+        .enumerate()
+        .map(|(i, link)| {
+            let (a, b) = (node_id_to_idx[&link.from_node], node_id_to_idx[&link.to_node]);
+            ((a.min(b), a.max(b)), i)
+        })
+        .collect();
+    let spectrum = SpectrumOccupancy { link_index, used: vec![vec![false; channels]; connections.len()] };
+
+    let mut timeline = TimelineBuilder {
+        spectrum,
+        active: HashMap::new(),
+        active_ids: Vec::new(),
+        active_positions: HashMap::new(),
+        departures: BinaryHeap::new(),
+        events: Vec::new(),
+    };
+
+    let mut time = 0.0;
+    let mut next_service_id = 0;
+    let mut blocked = 0;
+    for _ in 0..requests {
+        time += rng.exponential(MEAN_INTERARRIVAL_TIME);
+        timeline.release_until(time);
+
+        let source = rng.below(node_count);
+        let destination = (source + 1 + rng.below(node_count - 1)) % node_count;
+        let Some(path) = graph.shortest_path(source, destination, PathWeight::Hops, &[]) else {
+            continue; // 生成的链路保证连通，不会发生
+        };
+        let service_id = next_service_id;
+
+        // 碎片整理：把几个活跃服务移到更低的空闲波长
+        if !timeline.active_ids.is_empty() && rng.next_f64() < params.reallocation_probability {
+            for _ in 0..MAX_MOVED_PER_DEFRAG {
+                let moved_id = timeline.active_ids[rng.below(timeline.active_ids.len())];
+                let (service, moved_path) = timeline.active.get_mut(&moved_id).expect("active_ids mirror the active map");
+                let Some(wavelength) = timeline.spectrum.first_fit(moved_path, service.wavelength as usize) else {
+                    continue;
+                };
+                timeline.spectrum.set(moved_path, service.wavelength as usize, false);
+                timeline.spectrum.set(moved_path, wavelength, true);
+                service.wavelength = wavelength as i32;
+                timeline.events.push(AnyEvent::Reallocation {
+                    timestamp: time,
+                    service_id: moved_id,
+                    details: ReallocationDetails { defrag_service_id: service_id, service: service.clone() },
+                });
+            }
+        }
+
+        let Some(wavelength) = timeline.spectrum.first_fit(&path, channels) else {
+            blocked += 1;
+            continue;
+        };
+        timeline.spectrum.set(&path, wavelength, true);
+        next_service_id += 1;
+
+        let departure_time = time + rng.exponential(mean_holding_time);
+        let bit_rate = BIT_RATES[rng.below(BIT_RATES.len())];
+        let snr_requirement = 10.0 + bit_rate / 40.0;
+        let service = ServiceData {
+            service_id,
+            source_id: node_id(source),
+            destination_id: node_id(destination),
+            arrival_time: time,
+            departure_time,
+            bit_rate,
+            power: 0.0,
+            path: path.iter().map(|&idx| node_id(idx)).collect(),
+            wavelength: wavelength as i32,
+            snr_requirement,
+            gsnr: snr_requirement + 2.0 + rng.next_f64() as f32 * 6.0,
+            utilization: 0.3 + rng.next_f64() as f32 * 0.7,
+            path_indices: None,
+        };
+        timeline.events.push(AnyEvent::Allocation { timestamp: time, service_id, details: service.clone() });
+        timeline.departures.push(Reverse((departure_time.to_bits(), service_id)));
+        timeline.insert_active(service, path);
+    }
+    timeline.release_until(f64::INFINITY);
+
+    if blocked > 0 {
+        log::info!("{} of {} synthetic service requests were blocked (no free wavelength).", blocked, requests);
+    }
+    timeline.events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// 通道少、重分配概率高：同时覆盖阻塞和碎片整理
+    fn params(seed: u64) -> SyntheticParams {
+        SyntheticParams {
+            nodes: 64,
+            services: Some(400),
+            channels: 8,
+            layout: SyntheticLayout::Random,
+            seed,
+            reallocation_probability: 0.3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fixed_seed_is_deterministic() {
+        let to_json = |topology: &FullTopologyData| serde_json::to_string(topology).unwrap();
+        assert_eq!(to_json(&generate(&params(7))), to_json(&generate(&params(7))));
+        assert_ne!(to_json(&generate(&params(7))), to_json(&generate(&params(8))));
+    }
+
+    #[test]
+    fn events_follow_existing_links_within_channel_plan() {
+        for seed in [1, 2, 3] {
+            let params = params(seed);
+            let topology = generate(&params);
+            let node_ids: HashSet<&str> = topology.elements.iter().map(|element| element.element_id.as_str()).collect();
+            let links: HashSet<(&str, &str)> = topology.connections.iter()
+                .flat_map(|link| [(link.from_node.as_str(), link.to_node.as_str()), (link.to_node.as_str(), link.from_node.as_str())])
+                .collect();
+            assert!(topology.connections.iter().all(|link| node_ids.contains(link.from_node.as_str()) && node_ids.contains(link.to_node.as_str())));
+            assert_eq!(topology.channel_plan.map(|plan| plan.max_wavelengths), Some(params.channels));
+
+            let mut reallocations = 0;
+            for event in &topology.defrag_timeline_events {
+                if matches!(event, AnyEvent::Reallocation { .. }) {
+                    reallocations += 1;
+                }
+                let Some(service) = event.service() else {
+                    continue;
+                };
+                assert!(service.path.len() >= 2, "service {} has a path of {} node(s)", service.service_id, service.path.len());
+                for hop in service.path.windows(2) {
+                    assert!(links.contains(&(hop[0].as_str(), hop[1].as_str())), "service {} uses missing link {:?}", service.service_id, hop);
+                }
+                assert!((0..params.channels as i32).contains(&service.wavelength), "service {} uses wavelength {}", service.service_id, service.wavelength);
+            }
+            assert!(reallocations > 0, "seed {} produced no reallocations", seed);
+            assert!(topology.defrag_timeline_events.windows(2).all(|pair| pair[0].timestamp() <= pair[1].timestamp()));
+        }
+    }
+}
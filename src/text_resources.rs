@@ -0,0 +1,115 @@
+// src/text_resources.rs
+// 文字渲染资源的延迟创建：解码三个内嵌字体、创建字形图集和上千个文字缓冲区在低端设备上要数百毫秒，
+// 而很多会话在放大到能看到标签之前根本不绘制文字。State 创建时不再初始化 glyphon，render_to_view 在第一帧真正有文字
+// （图标、标签、注释、对比标签、统计叠加层、服务列表面板或悬停提示）时才创建 TextResources，之前的帧只绘制节点和线路。
+// 节点标签的缓冲区按可见标签数增长（上限 MAX_LABEL_BUFFERS），不再预先分配。
+// 由页面自己绘制 HTML 标签的嵌入场景可以通过 setTextRendering(false) 关闭全部文字（排版、图集上传和 prepare/render），
+// 在第一帧之前关闭时 TextResources 不会被创建。
+use std::sync::Arc;
+
+use crate::app_state::State;
+use crate::overlay::{TOOLTIP_FONT_SIZE, TOOLTIP_LINE_HEIGHT_PX};
+use crate::service_panel::{PANEL_FONT_SIZE, PANEL_ROW_HEIGHT_PX};
+
+/// 同时绘制的节点标签数上限
+pub const MAX_LABEL_BUFFERS: usize = 4000;
+
+/// glyphon 的字体、图集、渲染器和各类文字缓冲区。State::text_resources 在第一帧有文字时才创建（见 get_or_create），
+/// 之后一直保留，setTextRendering(false) 也不释放
+pub struct TextResources {
+    pub font_system: glyphon::FontSystem,
+    pub viewport: glyphon::Viewport,
+    pub swash_cache: glyphon::SwashCache,
+    pub atlas: glyphon::TextAtlas,
+    pub renderer: glyphon::TextRenderer,
+    pub label_buffers: Vec<glyphon::Buffer>, // 节点标签，按需增长
+    pub stats_buffer: glyphon::Buffer,
+    pub icon_buffers: Vec<glyphon::Buffer>, // 节点图标，按需增长
+    pub annotation_buffers: Vec<glyphon::Buffer>, // 路径跳数等注释文字，按需增长
+    pub map_annotation_buffers: Vec<glyphon::Buffer>, // addAnnotation 添加的注释（见 annotations.rs），按需增长
+    pub compare_label_buffers: Vec<glyphon::Buffer>, // 对比视图两边的时刻标签
+    pub service_panel_buffer: glyphon::Buffer, // 服务列表面板，行数有上限（见 service_panel.rs）
+    pub tooltip_buffer: glyphon::Buffer, // 悬停提示（见 overlay.rs）
+}
+
+impl TextResources {
+    /// 解码内嵌字体并创建图集和渲染器（图集按 texture_format 绘制，须与 surface 格式一致）；
+    /// 节点标签、图标和注释的缓冲区为空，使用时按需增长。耗时记录在日志中
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, texture_format: wgpu::TextureFormat) -> Self {
+        let started = instant::Instant::now();
+        let mut font_system = glyphon::FontSystem::new_with_fonts([
+            glyphon::fontdb::Source::Binary(Arc::new(include_bytes!(
+                "../assets/fonts/Iced-Icons.ttf"
+            ))),
+            glyphon::fontdb::Source::Binary(Arc::new(include_bytes!(
+                "../assets/fonts/Roboto-Regular.ttf"
+            ))),
+            glyphon::fontdb::Source::Binary(Arc::new(include_bytes!(
+                "../assets/fonts/bootstrap-icons.ttf"
+            ))),
+        ]);
+        let swash_cache = glyphon::SwashCache::new();
+        let cache = glyphon::Cache::new(device);
+        let viewport = glyphon::Viewport::new(device, &cache);
+        // 非 sRGB 目标格式由着色器转换输出颜色（见 CameraUniform::needs_srgb_output_conversion）
+        let color_mode = if texture_format.is_srgb() {
+            glyphon::ColorMode::Accurate
+        } else {
+            glyphon::ColorMode::Web
+        };
+        let mut atlas = glyphon::TextAtlas::with_color_mode(device, queue, &cache, texture_format, color_mode);
+        let renderer = glyphon::TextRenderer::new(&mut atlas, device, wgpu::MultisampleState::default(), None);
+
+        let stats_buffer = glyphon::Buffer::new(&mut font_system, glyphon::Metrics::new(14.0, 18.0));
+        let service_panel_buffer = glyphon::Buffer::new(&mut font_system, glyphon::Metrics::new(PANEL_FONT_SIZE, PANEL_ROW_HEIGHT_PX));
+        let tooltip_buffer = glyphon::Buffer::new(&mut font_system, glyphon::Metrics::new(TOOLTIP_FONT_SIZE, TOOLTIP_LINE_HEIGHT_PX));
+        log::info!("Text rendering initialized in {:.1} ms.", started.elapsed().as_secs_f64() * 1000.0);
+
+        Self {
+            font_system,
+            viewport,
+            swash_cache,
+            atlas,
+            renderer,
+            label_buffers: Vec::new(),
+            stats_buffer,
+            icon_buffers: Vec::new(),
+            annotation_buffers: Vec::new(),
+            map_annotation_buffers: Vec::new(),
+            compare_label_buffers: Vec::new(),
+            service_panel_buffer,
+            tooltip_buffer,
+        }
+    }
+
+    /// slot 为空时创建（第一帧有文字时由 render_to_view 调用），否则返回已有的资源；
+    /// 之后的调用不会因 texture_format 不同而重建
+    pub fn get_or_create<'a>(
+        slot: &'a mut Option<TextResources>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_format: wgpu::TextureFormat,
+    ) -> &'a mut TextResources {
+        slot.get_or_insert_with(|| TextResources::new(device, queue, texture_format))
+    }
+
+    /// 节点标签缓冲区增长到至少 count 个（不超过 MAX_LABEL_BUFFERS）
+    pub fn reserve_label_buffers(&mut self, count: usize) {
+        let count = count.min(MAX_LABEL_BUFFERS);
+        while self.label_buffers.len() < count {
+            self.label_buffers.push(glyphon::Buffer::new(&mut self.font_system, glyphon::Metrics::relative(10.0, 16.0)));
+        }
+    }
+}
+
+impl State {
+    /// setTextRendering：关闭时下一帧起不再生成图标、标签、注释、统计叠加层、服务列表面板和悬停提示；
+    /// 已创建的 TextResources 保留，重新打开时下一帧重新排版
+    pub fn set_text_rendering(&mut self, enabled: bool) {
+        if enabled == self.text_rendering_enabled {
+            return;
+        }
+        self.text_rendering_enabled = enabled;
+        log::info!("Text rendering {}.", if enabled { "enabled" } else { "disabled" });
+    }
+}
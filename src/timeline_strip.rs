@@ -0,0 +1,258 @@
+// src/timeline_strip.rs
+// 画布内的时间轴条（原生窗口和没有 HTML 滑块的展示屏使用）：画布底部的半透明条，横向对应第一个到最后一个事件的时间范围，
+// 条内为每个时间段的事件数直方图、书签刻度和当前时刻的播放头。在条内按下鼠标跳到对应的时刻，按住拖动连续调整时间，
+// 期间不会平移画布或拖动节点。直方图在事件变化后（State::events_generation 改变）重新统计。
+// 只绘制屏幕空间的矩形（与服务列表面板共用叠加层缓冲区），不绘制文字，关闭文字渲染时仍然显示。
+// 条按整个画布的坐标布局，对比视图中也只显示一个。
+use glam::Vec2;
+
+use crate::app_state::State;
+use crate::models::LineVertex;
+use crate::notifications::TimeChangeReason;
+use crate::overlay::push_screen_rect;
+use crate::settings::is_positive_finite;
+
+const STRIP_MARGIN_PX: f32 = 8.0;
+const STRIP_HEIGHT_PX: f32 = 40.0;
+const STRIP_PADDING_PX: f32 = 4.0;
+/// 直方图的时间段数
+const HISTOGRAM_BUCKETS: usize = 120;
+const PLAYHEAD_WIDTH_PX: f32 = 2.0;
+const BOOKMARK_TICK_WIDTH_PX: f32 = 1.0;
+/// 画布宽度小于此值时不显示
+const MIN_STRIP_WIDTH_PX: f32 = 120.0;
+
+#[derive(Debug, Default)]
+pub struct TimelineStrip {
+    pub visible: bool,
+    /// 在条内按下鼠标后为 true，松开前光标移动都会调整时间
+    pub scrubbing: bool,
+    histogram: Vec<u32>,
+    /// 统计直方图时的 State::events_generation
+    histogram_generation: Option<u64>,
+}
+
+impl TimelineStrip {
+    pub fn new(visible: bool) -> Self {
+        Self { visible, ..Default::default() }
+    }
+}
+
+/// 时刻在 [start_time, end_time] 内的直方图时间段，end_time 计入最后一段
+fn histogram_bucket(time: f64, start_time: f64, end_time: f64) -> usize {
+    let fraction = (time - start_time) / (end_time - start_time);
+    ((fraction * HISTOGRAM_BUCKETS as f64) as usize).min(HISTOGRAM_BUCKETS - 1)
+}
+
+/// 时间轴条在画布上的位置（像素）和对应的时间范围，由 State::timeline_strip_layout 按当前尺寸计算
+#[derive(Debug, Clone, Copy)]
+pub struct TimelineStripLayout {
+    pub min: Vec2,
+    pub max: Vec2,
+    start_time: f64,
+    end_time: f64,
+}
+
+impl TimelineStripLayout {
+    pub fn contains(&self, pos: Vec2) -> bool {
+        pos.cmpge(self.min).all() && pos.cmplt(self.max).all()
+    }
+
+    fn track_min_x(&self) -> f32 {
+        self.min.x + STRIP_PADDING_PX
+    }
+
+    fn track_width(&self) -> f32 {
+        self.max.x - self.min.x - 2.0 * STRIP_PADDING_PX
+    }
+
+    fn time_to_x(&self, time: f64) -> f32 {
+        let fraction = (time - self.start_time) / (self.end_time - self.start_time);
+        self.track_min_x() + fraction.clamp(0.0, 1.0) as f32 * self.track_width()
+    }
+
+    /// 横坐标对应的时刻，条外的位置截断到两端
+    fn x_to_time(&self, x: f32) -> f64 {
+        let fraction = ((x - self.track_min_x()) / self.track_width()).clamp(0.0, 1.0) as f64;
+        self.start_time + fraction * (self.end_time - self.start_time)
+    }
+}
+
+impl State {
+    /// setTimelineStrip / T 键
+    pub fn set_timeline_strip(&mut self, visible: bool) {
+        log::info!("Timeline strip {}.", if visible { "shown" } else { "hidden" });
+        self.timeline_strip.visible = visible;
+        self.timeline_strip.scrubbing = false;
+    }
+
+    /// 时间轴条（包括外边距）在画布底部占用的高度，服务列表面板据此避开；不显示时为 0
+    pub fn timeline_strip_reserved_height(&self) -> f32 {
+        if self.timeline_strip_layout().is_some() { STRIP_HEIGHT_PX + STRIP_MARGIN_PX } else { 0.0 }
+    }
+
+    /// 条隐藏、没有事件（或所有事件在同一时刻）或画布过小时为 None
+    pub fn timeline_strip_layout(&self) -> Option<TimelineStripLayout> {
+        if !self.timeline_strip.visible {
+            return None;
+        }
+        let (start_time, end_time) = (self.all_events.first()?.timestamp(), self.all_events.last()?.timestamp());
+        if !is_positive_finite(end_time - start_time) {
+            return None;
+        }
+        let canvas_size = Vec2::new(self.config.width as f32, self.config.height as f32);
+        if canvas_size.x < MIN_STRIP_WIDTH_PX + 2.0 * STRIP_MARGIN_PX || canvas_size.y < 2.0 * (STRIP_HEIGHT_PX + STRIP_MARGIN_PX) {
+            return None;
+        }
+        let min = Vec2::new(STRIP_MARGIN_PX, canvas_size.y - STRIP_MARGIN_PX - STRIP_HEIGHT_PX);
+        let max = Vec2::new(canvas_size.x - STRIP_MARGIN_PX, canvas_size.y - STRIP_MARGIN_PX);
+        Some(TimelineStripLayout { min, max, start_time, end_time })
+    }
+
+    /// pos 为画布坐标（mouse_canvas_pos_screen）
+    pub fn timeline_strip_contains(&self, pos: Vec2) -> bool {
+        self.timeline_strip_layout().is_some_and(|layout| layout.contains(pos))
+    }
+
+    /// 每帧在 update() 中调用：事件变化后重新统计直方图
+    pub fn refresh_timeline_histogram(&mut self) {
+        if !self.timeline_strip.visible {
+            return;
+        }
+        if self.timeline_strip.histogram_generation == Some(self.events_generation) {
+            return;
+        }
+        self.timeline_strip.histogram_generation = Some(self.events_generation);
+        self.timeline_strip.histogram = vec![0; HISTOGRAM_BUCKETS];
+        let (Some(first), Some(last)) = (self.all_events.first(), self.all_events.last()) else {
+            return;
+        };
+        let (start_time, end_time) = (first.timestamp(), last.timestamp());
+        if end_time > start_time {
+            for event in &self.all_events {
+                self.timeline_strip.histogram[histogram_bucket(event.timestamp(), start_time, end_time)] += 1;
+            }
+        }
+    }
+
+    /// 左键按下：在条内时返回 true 并跳到对应的时刻，调用方不再开始平移或拖动节点
+    pub fn press_timeline_strip(&mut self, pos: Vec2) -> bool {
+        let Some(layout) = self.timeline_strip_layout().filter(|layout| layout.contains(pos)) else {
+            return false;
+        };
+        self.timeline_strip.scrubbing = true;
+        self.set_time_selection(layout.x_to_time(pos.x), TimeChangeReason::Scrub);
+        true
+    }
+
+    /// 光标移动：按下发生在条内时按横坐标调整时间（光标离开条后截断到两端）并返回 true
+    pub fn scrub_timeline_strip(&mut self, pos: Vec2) -> bool {
+        if !self.timeline_strip.scrubbing {
+            return false;
+        }
+        let Some(layout) = self.timeline_strip_layout() else {
+            self.timeline_strip.scrubbing = false;
+            return false;
+        };
+        let time = layout.x_to_time(pos.x);
+        if time != self.current_time_selection {
+            self.set_time_selection(time, TimeChangeReason::Scrub);
+        }
+        true
+    }
+
+    /// 左键松开：按下发生在条内时返回 true
+    pub fn release_timeline_strip(&mut self) -> bool {
+        std::mem::take(&mut self.timeline_strip.scrubbing)
+    }
+
+    /// 条的背景、直方图、书签刻度和播放头（NDC 三角形）
+    pub fn push_timeline_strip_vertices(&self, layout: &TimelineStripLayout, vertices: &mut Vec<LineVertex>) {
+        let canvas_size = Vec2::new(self.config.width as f32, self.config.height as f32);
+        push_screen_rect(vertices, layout.min, layout.max, canvas_size, self.overlay_theme.background);
+
+        let track_top = layout.min.y + STRIP_PADDING_PX;
+        let track_bottom = layout.max.y - STRIP_PADDING_PX;
+        let histogram = &self.timeline_strip.histogram;
+        if let Some(&max_count) = histogram.iter().max().filter(|&&max_count| max_count > 0) {
+            let bucket_width = layout.track_width() / histogram.len() as f32;
+            for (i, &count) in histogram.iter().enumerate().filter(|&(_, &count)| count > 0) {
+                let height = (track_bottom - track_top) * count as f32 / max_count as f32;
+                let left = layout.track_min_x() + i as f32 * bucket_width;
+                push_screen_rect(
+                    vertices,
+                    Vec2::new(left, track_bottom - height),
+                    Vec2::new(left + bucket_width.max(1.0), track_bottom),
+                    canvas_size,
+                    self.overlay_theme.border,
+                );
+            }
+        }
+
+        let mut bookmark_color = self.selection_accent_color;
+        bookmark_color[3] = 0.6;
+        for bookmark in &self.time_bookmarks {
+            let x = layout.time_to_x(bookmark.time);
+            push_screen_rect(
+                vertices,
+                Vec2::new(x - BOOKMARK_TICK_WIDTH_PX / 2.0, layout.min.y),
+                Vec2::new(x + BOOKMARK_TICK_WIDTH_PX / 2.0, layout.max.y),
+                canvas_size,
+                bookmark_color,
+            );
+        }
+
+        let playhead_x = layout.time_to_x(self.current_time_selection);
+        push_screen_rect(
+            vertices,
+            Vec2::new(playhead_x - PLAYHEAD_WIDTH_PX / 2.0, layout.min.y),
+            Vec2::new(playhead_x + PLAYHEAD_WIDTH_PX / 2.0, layout.max.y),
+            canvas_size,
+            self.selection_accent_color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(start_time: f64, end_time: f64) -> TimelineStripLayout {
+        TimelineStripLayout { min: Vec2::new(8.0, 552.0), max: Vec2::new(792.0, 592.0), start_time, end_time }
+    }
+
+    /// 时刻与横坐标互相转换后不变（f32 像素的精度内），条外的位置和时刻截断到两端
+    #[test]
+    fn time_and_x_round_trip() {
+        let layout = layout(1_000.0, 87_400.0);
+        let (left, right) = (layout.track_min_x(), layout.track_min_x() + layout.track_width());
+        assert_eq!(layout.time_to_x(1_000.0), left);
+        assert_eq!(layout.time_to_x(87_400.0), right);
+        assert_eq!(layout.x_to_time(left), 1_000.0);
+        assert_eq!(layout.x_to_time(right), 87_400.0);
+        let seconds_per_px = (87_400.0 - 1_000.0) / layout.track_width() as f64;
+        for k in 0..=100 {
+            let time = 1_000.0 + 864.0 * k as f64;
+            assert!((layout.x_to_time(layout.time_to_x(time)) - time).abs() <= seconds_per_px * 0.01, "{time}");
+        }
+        for x in [left + 0.5, left + 100.25, right - 1.0] {
+            assert!((layout.time_to_x(layout.x_to_time(x)) - x).abs() < 1e-3, "{x}");
+        }
+        assert_eq!(layout.x_to_time(0.0), 1_000.0);
+        assert_eq!(layout.x_to_time(10_000.0), 87_400.0);
+        assert_eq!(layout.time_to_x(-5.0), left);
+        assert_eq!(layout.time_to_x(1e12), right);
+    }
+
+    /// 第一个事件在第一段，最后一个事件（恰好在结束时刻）计入最后一段而不是越界，段边界上的时刻归入后一段
+    #[test]
+    fn histogram_buckets_at_the_edges() {
+        let (start, end) = (10.0, 130.0); // 每段 1 秒
+        assert_eq!(histogram_bucket(start, start, end), 0);
+        assert_eq!(histogram_bucket(end, start, end), HISTOGRAM_BUCKETS - 1);
+        assert_eq!(histogram_bucket(end - 1e-9, start, end), HISTOGRAM_BUCKETS - 1);
+        assert_eq!(histogram_bucket(start + 1.0, start, end), 1);
+        assert_eq!(histogram_bucket(start + 1.0 - 1e-9, start, end), 0);
+        assert_eq!(histogram_bucket(70.0, start, end), HISTOGRAM_BUCKETS / 2);
+    }
+}
@@ -0,0 +1,155 @@
+// src/topology_export.rs
+// 导出当前加载的拓扑（getFullTopology）：all_elements、all_connections、all_events、信道规划和被阻塞的需求序列化为与
+// setFullTopology 相同格式的 JSON，重新加载后得到相同的场景。节点位置取自当前几何（包括自动布局和拖动后的位置）。
+// 大型时间轴分多帧序列化，每帧最多占用 EXPORT_FRAME_BUDGET_MS 毫秒；期间如果拓扑或事件被替换，先同步完成导出。
+use instant::Instant;
+use serde::Serialize;
+
+use crate::app_state::State;
+use crate::scene::element::{ElementData, Location};
+
+/// 每帧用于序列化的时间上限（毫秒）
+const EXPORT_FRAME_BUDGET_MS: f64 = 4.0;
+/// 每序列化这么多条记录检查一次时间
+const EXPORT_ITEMS_PER_TIME_CHECK: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportSection {
+    Elements,
+    Connections,
+    Events,
+}
+
+/// 未完成的导出
+pub struct TopologyExport {
+    json: String,
+    footer: &'static str, // 拓扑对象之后追加的文本（导出会话时为外层对象的结尾）
+    section: ExportSection,
+    next_item: usize,
+    reply: flume::Sender<Result<String, String>>,
+}
+
+impl State {
+    pub fn is_topology_export_pending(&self) -> bool {
+        self.topology_export.is_some()
+    }
+
+    /// 开始导出；已有未完成的导出时先同步完成它
+    pub fn start_topology_export(&mut self, reply: flume::Sender<Result<String, String>>) {
+        self.start_wrapped_topology_export(reply, String::new(), "");
+    }
+
+    /// 与 start_topology_export 相同，但拓扑对象前后分别加上 header 和 footer（见 session.rs）
+    pub fn start_wrapped_topology_export(&mut self, reply: flume::Sender<Result<String, String>>, header: String, footer: &'static str) {
+        self.finish_topology_export();
+        self.topology_export = Some(TopologyExport {
+            json: header + "{\"elements\":[",
+            footer,
+            section: ExportSection::Elements,
+            next_item: 0,
+            reply,
+        });
+        self.advance_topology_export();
+    }
+
+    /// 每帧调用一次，最多序列化 EXPORT_FRAME_BUDGET_MS 毫秒
+    pub fn advance_topology_export(&mut self) {
+        self.serialize_topology_export(Some(EXPORT_FRAME_BUDGET_MS));
+    }
+
+    /// 在替换拓扑或事件之前调用，使导出的内容与发起请求时一致
+    pub fn finish_topology_export(&mut self) {
+        if self.is_topology_export_pending() {
+            self.serialize_topology_export(None);
+        }
+    }
+
+    /// 推进导出，budget_ms 为 None 时一直序列化到完成
+    fn serialize_topology_export(&mut self, budget_ms: Option<f64>) {
+        let Some(mut export) = self.topology_export.take() else {
+            return;
+        };
+        let start = Instant::now();
+        let mut serialized = 0;
+        loop {
+            if serialized % EXPORT_ITEMS_PER_TIME_CHECK == EXPORT_ITEMS_PER_TIME_CHECK - 1
+                && budget_ms.is_some_and(|budget_ms| start.elapsed().as_secs_f64() * 1000.0 >= budget_ms)
+            {
+                self.topology_export = Some(export);
+                return;
+            }
+            serialized += 1;
+
+            let result = match export.section {
+                ExportSection::Elements => match self.all_elements.get(export.next_item) {
+                    Some(element) => {
+                        let element = self.exported_element(export.next_item, element);
+                        push_item(&mut export, &element)
+                    }
+                    None => {
+                        export.json.push_str("],\"connections\":[");
+                        export.section = ExportSection::Connections;
+                        export.next_item = 0;
+                        continue;
+                    }
+                },
+                ExportSection::Connections => match self.all_connections.get(export.next_item) {
+                    Some(connection) => push_item(&mut export, connection),
+                    None => {
+                        export.json.push_str("],\"defrag_timeline_events\":[");
+                        export.section = ExportSection::Events;
+                        export.next_item = 0;
+                        continue;
+                    }
+                },
+                ExportSection::Events => match self.all_events.get(export.next_item) {
+                    Some(event) => push_item(&mut export, event),
+                    None => {
+                        // 信道规划随拓扑导出，重新加载后波长的位置和颜色不变；被阻塞的需求数量不大，一次序列化
+                        let tail = serde_json::to_string(&self.channel_plan).and_then(|channel_plan| {
+                            let mut tail = format!("],\"channel_plan\":{}", channel_plan);
+                            if !self.blocked_demands.is_empty() {
+                                tail += &format!(",\"blocked_demands\":{}", serde_json::to_string(&self.blocked_demands)?);
+                            }
+                            Ok(tail + "}")
+                        });
+                        match tail {
+                            Ok(tail) => export.json.push_str(&tail),
+                            Err(e) => {
+                                let _ = export.reply.send(Err(e.to_string()));
+                                return;
+                            }
+                        }
+                        export.json.push_str(export.footer);
+                        log::info!("Exported topology as {} bytes of JSON.", export.json.len());
+                        let _ = export.reply.send(Ok(export.json));
+                        return;
+                    }
+                },
+            };
+            if let Err(e) = result {
+                let _ = export.reply.send(Err(e.to_string()));
+                return;
+            }
+        }
+    }
+
+    /// 位置替换为节点当前的位置（几何中 y 轴与数据相反），不包括重合节点的分开偏移；GeoJSON 导出也使用
+    pub fn exported_element(&self, idx: usize, element: &ElementData) -> ElementData {
+        let mut element = element.clone();
+        if idx < self.geometry.circle_instances.len() {
+            let position = self.raw_node_position(idx);
+            element.metadata.location = Some(Location { x: position.x, y: -position.y });
+        }
+        element
+    }
+}
+
+fn push_item<T: Serialize>(export: &mut TopologyExport, item: &T) -> serde_json::Result<()> {
+    if export.next_item > 0 {
+        export.json.push(',');
+    }
+    export.json.push_str(&serde_json::to_string(item)?);
+    export.next_item += 1;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+// src/topology_file.rs
+// 原生命令行：按扩展名从文件加载拓扑（.dot / .gv 为 Graphviz，.msgpack 为 MessagePack，其余按 JSON 解析），
+// 以及 --session 指定的会话文件。拖放到窗口上的拓扑文件（WindowEvent::DroppedFile）使用同样的加载方式，
+// 按住 Shift 拖放时保持当前相机；文件无法解析时保留当前场景并报告错误。
+use std::path::Path;
+use anyhow::Context;
+
+use crate::app_state::State;
+use crate::errors::{self, ViewError};
+use crate::scene::dot::parse_dot;
+use crate::scene::network::FullTopologyData;
+use crate::session::{parse_session_json, SessionDocument};
+use crate::ui_events::UserCommand;
+
+/// 替换当前场景的 SetFullTopology 命令
+pub fn set_full_topology_command(topology: FullTopologyData, fit_view: bool) -> UserCommand {
+    UserCommand::SetFullTopology {
+        elements: topology.elements,
+        connections: topology.connections,
+        defrag_timeline_events: topology.defrag_timeline_events,
+        channel_plan: topology.channel_plan,
+        blocked_demands: topology.blocked_demands,
+        merge: false,
+        fit_view,
+    }
+}
+
+/// 窗口标题中的名称：数据中的 name 字段，缺省为文件名
+pub fn topology_display_name(topology: &FullTopologyData, path: &Path) -> Option<String> {
+    topology.name.clone().or_else(|| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+}
+
+/// 加载拖放到窗口上的拓扑文件并替换当前场景，返回窗口标题中的名称；
+/// 加载失败时场景保持不变，错误通过 errors::report 报告
+pub fn load_dropped_file(state: &mut State, path: &Path, preserve_camera: bool) -> Option<Option<String>> {
+    let topology = match load_topology_file(path) {
+        Ok(topology) => topology,
+        Err(e) => {
+            errors::report(ViewError::error("dropped_file_invalid", format!("{:#}", e)));
+            return None;
+        }
+    };
+    let name = topology_display_name(&topology, path);
+    log::info!("Loading dropped topology file {}{}.", path.display(), if preserve_camera { " (keeping the camera)" } else { "" });
+    state.process_command(set_full_topology_command(topology, !preserve_camera));
+
+    // 各个验证问题在记录时已经写入日志（ValidationReport::warn），这里只给出汇总
+    log::info!(
+        "Loaded {}: {} nodes, {} links, {} events, {} blocked demands, {} validation issue(s).",
+        path.display(), state.all_elements.len(), state.all_connections.len(), state.all_events.len(), state.blocked_demands.len(),
+        state.validation_report.issues.len()
+    );
+    Some(name)
+}
+
+pub fn load_topology_file(path: &Path) -> anyhow::Result<FullTopologyData> {
+    let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+    if matches!(extension.as_deref(), Some("msgpack") | Some("mp")) {
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read topology file {}", path.display()))?;
+        return FullTopologyData::from_msgpack(&bytes)
+            .with_context(|| format!("Failed to load topology file {}", path.display()));
+    }
+
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read topology file {}", path.display()))?;
+
+    match extension.as_deref() {
+        Some("dot") | Some("gv") => {
+            let import = parse_dot(&text)
+                .map_err(|e| anyhow::anyhow!("DOT parsing error in {}: {}", path.display(), e))?;
+            for warning in &import.warnings {
+                log::warn!("{}: {}", path.display(), warning);
+            }
+            Ok(import.topology)
+        }
+        _ => FullTopologyData::from_json_str(&text)
+            .with_context(|| format!("Failed to load topology file {}", path.display())),
+    }
+}
+
+pub fn load_session_file(path: &Path) -> anyhow::Result<SessionDocument> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read session file {}", path.display()))?;
+    parse_session_json(&text).map_err(|e| anyhow::anyhow!("Failed to load session file {}: {}", path.display(), e))
+}
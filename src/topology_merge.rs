@@ -0,0 +1,148 @@
+// src/topology_merge.rs
+// 合并加载：把另一份拓扑（例如共享边界节点的相邻区域）并入当前场景，而不是替换。
+// 新的 element_id 追加在末尾（已有节点的索引不变，撤销历史和高亮仍然有效），重复的节点保留现有的位置和数据；
+// 链路按 connection_id 去重；时间轴事件合并后重新按时间排序。冲突记录到验证报告中。
+use std::collections::{HashMap, HashSet};
+
+use glam::Vec2;
+
+use crate::app_state::{default_node_color, State};
+use crate::models::CircleInstance;
+use crate::scene::auto_layout::fill_missing_locations;
+use crate::scene::connection::ConnectionData;
+use crate::scene::defrag_event::AnyEvent;
+use crate::scene::element::ElementData;
+use crate::scene::geometry::NODE_INSTANCE_RADIUS_FACTOR;
+use crate::scene::validation::{validate_timeline_events, ValidationReport};
+
+impl State {
+    /// 合并拓扑。当前时刻保持不变；fit_view 为 true 时按合并后的全部节点重新适配视角
+    pub fn merge_topology(
+        &mut self,
+        elements: Vec<ElementData>,
+        connections: Vec<ConnectionData>,
+        defrag_timeline_events: Vec<AnyEvent>,
+        fit_view: bool,
+    ) {
+        self.finish_topology_export(); // 未完成的导出针对合并之前的拓扑
+        self.validation_report = ValidationReport::default();
+
+        let first_new_idx = self.all_elements.len();
+        for element in elements {
+            match self.node_id_to_idx.get(&element.element_id) {
+                Some(&idx) => {
+                    if self.all_elements[idx] != element {
+                        self.validation_report.warn(
+                            "merge_element_conflict",
+                            format!("Element {} already exists with different data; the existing element was kept.", element.element_id),
+                            Some(element.element_id),
+                        );
+                    }
+                }
+                None => {
+                    self.node_id_to_idx.insert(element.element_id.clone(), self.all_elements.len());
+                    self.all_elements.push(element);
+                }
+            }
+        }
+
+        let existing_connections: HashMap<String, usize> = self.all_connections
+            .iter()
+            .enumerate()
+            .map(|(i, connection)| (connection.connection_id.clone(), i))
+            .collect();
+        for connection in connections {
+            match existing_connections.get(&connection.connection_id) {
+                Some(&i) => {
+                    if self.all_connections[i] != connection {
+                        self.validation_report.warn(
+                            "merge_connection_conflict",
+                            format!("Connection {} already exists with different endpoints; the existing connection was kept.", connection.connection_id),
+                            None,
+                        );
+                    }
+                }
+                None => self.all_connections.push(connection),
+            }
+        }
+
+        self.place_merged_elements(first_new_idx);
+        self.update_auto_node_radius(); // 新节点可能改变典型间距
+        self.detect_overlapping_nodes();
+
+        let existing_service_ids: HashSet<i32> = self.all_events.iter().filter_map(|event| event.service_id()).collect();
+        let overlapping_service_ids: HashSet<i32> = defrag_timeline_events
+            .iter()
+            .filter_map(|event| event.service_id())
+            .filter(|service_id| existing_service_ids.contains(service_id))
+            .collect();
+        if !overlapping_service_ids.is_empty() {
+            self.validation_report.warn(
+                "events_merge_service_id_overlap",
+                format!(
+                    "{} service ID(s) appear in both timelines (e.g. {}); their events are interleaved.",
+                    overlapping_service_ids.len(),
+                    overlapping_service_ids.iter().min().copied().unwrap_or_default()
+                ),
+                None,
+            );
+        }
+        self.all_events.extend(defrag_timeline_events);
+        // 稳定排序：同一时刻的事件保持各自时间轴中的顺序
+        self.all_events.sort_by(|a, b| a.timestamp().total_cmp(&b.timestamp()));
+        validate_timeline_events(&mut self.all_events, self.channel_plan.max_wavelengths, &mut self.validation_report);
+        self.mark_events_changed();
+        if let Some(unknown_node_id) = self.first_unknown_event_node() {
+            self.validation_report.warn(
+                "events_unknown_node",
+                format!("Timeline events reference node {}, which is not in the topology; those hops are skipped.", unknown_node_id),
+                Some(unknown_node_id),
+            );
+        }
+
+        self.resolve_topology_indices();
+
+        log::info!(
+            "Merged topology: now {} nodes, {} links and {} events.",
+            self.all_elements.len(), self.all_connections.len(), self.all_events.len()
+        );
+        self.service_diff = None; // 差异针对合并之前的时间线
+        self.topology_needs_update = true;
+        self.selection_needs_update = true;
+        if fit_view {
+            self.fit_view_to_topology();
+        }
+    }
+
+    /// 为 first_new_idx 之后新加入的节点创建节点实例。缺少位置的新节点放在已连接的邻居
+    /// （包括已有节点）附近，已有节点保持当前位置
+    fn place_merged_elements(&mut self, first_new_idx: usize) {
+        let mut locations: Vec<Option<Vec2>> = self.geometry.circle_instances
+            .iter()
+            .map(|instance| Some(Vec2::new(instance.position[0], -instance.position[1])))
+            .collect();
+        for element in &self.all_elements[first_new_idx..] {
+            if element.metadata.location.is_none() {
+                self.validation_report.warn(
+                    "missing_location",
+                    format!("Element {} has no metadata.location and was placed automatically.", element.element_id),
+                    Some(element.element_id.clone()),
+                );
+            }
+            locations.push(element.metadata.location.as_ref().map(|location| Vec2::new(location.x, location.y)));
+        }
+        let element_ids: Vec<String> = self.all_elements.iter().map(|element| element.element_id.clone()).collect();
+        fill_missing_locations(&element_ids, &mut locations, &self.all_connections);
+
+        let default_node_color = default_node_color();
+        self.geometry.circle_instances.extend(locations[first_new_idx..].iter().map(|location| {
+            let location = location.unwrap_or(Vec2::ZERO);
+            CircleInstance {
+                position: [location.x, -location.y],
+                radius_scale: self.node_radius * NODE_INSTANCE_RADIUS_FACTOR,
+                color: default_node_color,
+                glow: 0.0,
+            }
+        }));
+    }
+}
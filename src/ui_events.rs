@@ -1,17 +1,42 @@
-use std::collections::HashMap;
-use std::f32::EPSILON;
+use std::collections::{BTreeMap, HashMap};
 use bevy_color::{Color, ColorToComponents, LinearRgba, Oklcha, Srgba};
 use glam::Vec2;
 use itertools::Itertools;
+use serde_json::json;
 use wgpu::util::DeviceExt;
 
-use crate::scene::defrag_event::AnyEvent;
+use crate::scene::defrag_event::{events_in_range, defrag_highlight, AnyEvent, EventKind, EventRange, ServiceDiffCounts};
 use crate::scene::network::FullTopologyData;
 use crate::scene::element::ElementData;
 use crate::scene::connection::ConnectionData;
 use crate::scene::service::ServiceData;
-use crate::app_state::{State, BASE_NODE_RADIUS};
-use crate::models::{Vertex2D, CircleInstance, LineVertex};
+use crate::scene::blocked_demand::BlockedDemand;
+use crate::app_state::State;
+use crate::errors::{self, ViewError};
+use crate::models::{Vertex2D, LineVertex};
+use crate::settings::{Background, ChannelPlan, ColorMode, HighlightLineStyle, HighlightStyle, LabelSettings, LodSettings, OpacityMode, ServiceIntervalSemantics, ThicknessMode};
+use crate::color_legend::ColorLegend;
+use crate::link_inspector::LinkService;
+use crate::node_icons::NodeIconOverrides;
+use crate::node_radius::NodeRadiusOverrides;
+use crate::notifications::{TimeChangeReason, ViewNotification};
+use crate::layout_history::{LayoutImportSummary, NodeLayout};
+use crate::node_groups::{NodeGroupSummary, NodeGroups};
+use crate::annotations::Annotation;
+use crate::scene::geojson::GeoJsonSnapshot;
+use crate::scene::svg::{SvgBounds, SvgSnapshot};
+use crate::bookmarks::TimeBookmark;
+use crate::saved_views::SavedView;
+use crate::session::SessionDocument;
+use crate::capture::Snapshot;
+use crate::recording::RecordingEvent;
+use crate::keymap::Keymap;
+use crate::viewport::VisibleNode;
+use crate::scene_description::SceneDescription;
+use crate::quality::RenderStats;
+use crate::service_template::ServiceTemplate;
+use crate::renderer_info::RendererInfo;
+use crate::scene::graph::PathWeight;
 
 
 #[allow(unused)]
@@ -22,56 +47,322 @@ pub enum UserCommand {
         elements: Vec<ElementData>,
         connections: Vec<ConnectionData>,
         defrag_timeline_events: Vec<AnyEvent>,
+        /// true 时并入当前场景而不是替换（见 topology_merge.rs）
+        merge: bool,
+        /// 替换或合并后是否重新适配视角；false 时保持当前相机
+        fit_view: bool,
+        /// 拓扑数据中的信道规划，在验证事件之前应用
+        channel_plan: Option<ChannelPlan>,
+        blocked_demands: Vec<BlockedDemand>,
     },
+    SetTopologyStructure {
+        elements: Vec<ElementData>,
+        connections: Vec<ConnectionData>,
+    },
+    SetTimelineEvents(Vec<AnyEvent>),
     SetNumChannels {
         num_channels: u32
     },
+    SetChannelPlan(ChannelPlan),
+    /// None 表示取消固定半径（自动推算或默认半径）
+    SetNodeRadius(Option<f32>),
+    SetAutoNodeRadius(bool),
+    SetNodeRadiusOverrides(NodeRadiusOverrides),
+    SetSeparateOverlappingNodes(bool),
+    SetEdgeBundling { enabled: bool, strength: f32 },
     StateInitialized, // Notifies App that State setup is complete
-    SetTimeSelection(f32), // 新增：设置时间轴选中的时刻
-    SetHighlightDefragService(i32),
+    SetTimeSelection(f64), // 新增：设置时间轴选中的时刻
+    SetHighlightDefragService {
+        service_id: i32,
+        /// 同时高亮被该碎片整理服务移动（重分配）的服务
+        include_moved: bool,
+    },
+    SetLodSettings(LodSettings),
+    SetLabelSettings(LabelSettings),
+    SetStatsOverlay(bool),
+    SetBackground(Background),
+    RenderColorTestPattern(bool),
+    /// None 表示按表面格式决定是否由着色器做 sRGB 编码
+    ForceSrgbConversion(Option<bool>),
+    SetServicePanel(bool),
+    SetServiceTooltipTemplate(ServiceTemplate),
+    SetNodeIconMapping(NodeIconOverrides),
+    SetHighlightLineStyle(HighlightLineStyle),
+    SetHighlightStyle(HighlightStyle),
+    SetColorMode(ColorMode),
+    SetThicknessMode(ThicknessMode),
+    SetOpacityMode(OpacityMode),
+    SetTimelineStrip(bool),
+    SetServiceIntervalSemantics(ServiceIntervalSemantics),
+    SetPreviewServices(Vec<ServiceData>),
+    SetSelectedNode(Option<String>),
+    SetCursorTracking(bool),
+    SetKeymap(Keymap),
+    SetNodePosition {
+        element_id: String,
+        x: f32,
+        y: f32,
+    },
+    UndoLayoutEdit,
+    RedoLayoutEdit,
+    ExportLayout(flume::Sender<NodeLayout>),
+    ExportGeoJson {
+        include_services: bool,
+        reply: flume::Sender<GeoJsonSnapshot>,
+    },
+    ExportSvg {
+        bounds: SvgBounds,
+        reply: flume::Sender<SvgSnapshot>,
+    },
+    /// 回复与 setFullTopology 格式相同的 JSON，或序列化错误
+    ExportFullTopology(flume::Sender<Result<String, String>>),
+    /// 回复会话 JSON（见 session.rs），或序列化错误
+    ExportSession(flume::Sender<Result<String, String>>),
+    ImportSession(Box<SessionDocument>),
+    CaptureFrame(flume::Sender<anyhow::Result<Snapshot>>),
+    RecordTimeline {
+        frame_times: Vec<f64>,
+        width: u32,
+        height: u32,
+        events: flume::Sender<RecordingEvent>,
+    },
+    CancelRecording,
+    ImportLayout {
+        layout: NodeLayout,
+        fit_view: bool,
+        reply: flume::Sender<LayoutImportSummary>,
+    },
+    QueryVisibleNodes(flume::Sender<Vec<VisibleNode>>),
+    QuerySceneDescription(flume::Sender<SceneDescription>),
+    HighlightPathBetween {
+        from_id: String,
+        to_id: String,
+        weight: PathWeight,
+        reply: flume::Sender<Result<Option<Vec<String>>, String>>,
+    },
+    ClearPathHighlight,
+    SetMeasurementMode(bool),
+    SetCapacityBars(bool),
+    SetCompareTimes {
+        t1: f64,
+        t2: f64,
+    },
+    ClearCompare,
+    SetDiffTimes {
+        t1: f64,
+        t2: f64,
+        reply: flume::Sender<Result<ServiceDiffCounts, String>>,
+    },
+    ClearDiff,
+    SetFlowAnimation(bool),
+    SetQualityGovernor {
+        enabled: bool,
+        /// 帧时间预算（毫秒），None 保持当前值
+        budget_ms: Option<f32>,
+    },
+    SetGpuProfiling(bool),
+    SetTextRendering(bool),
+    /// 宿主页面控制出帧：请求重绘，重绘请求发出后回复
+    RequestRedraw(flume::Sender<()>),
+    /// 请求重绘，下一次 render() 成功 present 之后回复
+    RenderOnce(flume::Sender<()>),
+    QueryRenderStats(flume::Sender<RenderStats>),
+    QueryRendererInfo(flume::Sender<RendererInfo>),
+    QueryColorLegend(flume::Sender<ColorLegend>),
+    SetHeatTrail {
+        enabled: bool,
+        /// 窗口长度（秒），None 为时间轴跨度的 10%
+        window: Option<f64>,
+    },
+    SetBlockedDemandLayer {
+        visible: bool,
+        /// 以当前时刻为中心的窗口总长度（秒），None 为时间轴跨度的 5%
+        window: Option<f64>,
+    },
+    QueryBlockedDemandsInRange {
+        start: f64,
+        end: f64,
+        reply: flume::Sender<Vec<BlockedDemand>>,
+    },
+    SetNodeGroups {
+        groups: NodeGroups,
+        reply: flume::Sender<NodeGroupSummary>,
+    },
+    SetGroupVisibility {
+        group: String,
+        visible: bool,
+    },
+    ClearMeasurement,
+    ClearHighlight,
+    QueryServicesAtTime {
+        time: f64,
+        reply: flume::Sender<Vec<ServiceData>>,
+    },
+    QueryActiveServiceIds {
+        time: f64,
+        reply: flume::Sender<Vec<i32>>,
+    },
+    QueryActiveServiceCounts {
+        times: Vec<f64>,
+        reply: flume::Sender<Vec<u32>>,
+    },
+    QueryServicesOnLink {
+        connection_id: String,
+        time: f64,
+        reply: flume::Sender<Result<Vec<LinkService>, String>>,
+    },
+    QueryEventsInRange {
+        start: f64,
+        end: f64,
+        kinds: Option<Vec<EventKind>>,
+        reply: flume::Sender<EventRange>,
+    },
+    AddTimeBookmark {
+        time: f64,
+        label: String,
+    },
+    ListTimeBookmarks(flume::Sender<Vec<TimeBookmark>>),
+    RemoveTimeBookmark(usize),
+    AddAnnotation(Annotation),
+    RemoveAnnotation(String),
+    ListAnnotations(flume::Sender<Vec<Annotation>>),
+    JumpToBookmark(usize),
+    SaveView(String),
+    RestoreView {
+        name: String,
+        reply: flume::Sender<Result<(), String>>,
+    },
+    ListViews(flume::Sender<Vec<String>>),
+    DeleteView(String),
+    ExportViews(flume::Sender<BTreeMap<String, SavedView>>),
+    ImportViews(BTreeMap<String, SavedView>),
+    StepToEvent {
+        direction: StepDirection,
+        filter: Option<EventKind>,
+        reply: flume::Sender<Option<AnyEvent>>,
+    },
     DestroyView,
+    /// 仅调试构建（WasmApi::debugPanic）：处理时 panic，用于验证崩溃报告
+    #[cfg(debug_assertions)]
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    DebugPanic(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub enum StepDirection {
+    Forward,
+    Backward,
+}
+
+impl UserCommand {
+    /// 只读取状态、不改变画面的命令，处理后不需要重绘
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            UserCommand::ExportLayout(_)
+                | UserCommand::ExportGeoJson { .. }
+                | UserCommand::ExportSvg { .. }
+                | UserCommand::QueryVisibleNodes(_)
+                | UserCommand::QuerySceneDescription(_)
+                | UserCommand::QueryRenderStats(_)
+                | UserCommand::QueryRendererInfo(_)
+                | UserCommand::QueryColorLegend(_)
+                | UserCommand::QueryServicesAtTime { .. }
+                | UserCommand::QueryActiveServiceIds { .. }
+                | UserCommand::QueryActiveServiceCounts { .. }
+                | UserCommand::QueryServicesOnLink { .. }
+                | UserCommand::QueryBlockedDemandsInRange { .. }
+                | UserCommand::QueryEventsInRange { .. }
+                | UserCommand::ListTimeBookmarks(_)
+                | UserCommand::ListAnnotations(_)
+                | UserCommand::ListViews(_)
+                | UserCommand::ExportViews(_)
+                | UserCommand::SetKeymap(_)
+        )
+    }
 }
 
 impl State {
     pub fn process_command(&mut self, command: UserCommand) {
         match command {
-            UserCommand::SetFullTopology { elements, connections, defrag_timeline_events } => {
-                log::info!("Setting full topology with {} nodes, {} links, and {} events.",
-                            elements.len(), connections.len(), defrag_timeline_events.len());
-
-                self.node_id_to_idx.clear();
-                self.node_id_to_idx = elements
-                    .iter()
-                    .enumerate()
-                    .map(|(i, element)| (element.element_id.clone(), i))
-                    .collect();
-
-                self.all_elements = elements;
-                self.all_connections = connections;
-                self.all_events = defrag_timeline_events;
-                
-                // 初始化（或重置）所有节点的默认颜色
-                let default_node_color = LinearRgba::from(Srgba::rgb_u8(0x00, 0x5d, 0x5d)).to_f32_array();
-                self.circle_instances = self.all_elements
-                    .iter()
-                    .map(|element| CircleInstance {
-                        position: [element.metadata.location.x, -element.metadata.location.y],
-                        radius_scale: BASE_NODE_RADIUS + 0.2, // 初始半径
-                        color: default_node_color, // 初始颜色
-                    })
-                    .collect();
-
-                self.line_vertices.clear();
-                self.highlight_line_vertices.clear(); // 清空高亮线条
-                self.world_text_labels.clear();
-
-                self.topology_needs_update = true;
-                self.current_time_selection = 0.0; // Reset time to 0
-                self.highlight_service_id_list = None; // Clear highlight
-                self.fit_view_to_topology();
+            UserCommand::SetFullTopology { elements, connections, defrag_timeline_events, merge: true, fit_view, channel_plan, blocked_demands } => {
+                log::info!("Merging topology with {} nodes, {} links, {} events, and {} blocked demands.",
+                            elements.len(), connections.len(), defrag_timeline_events.len(), blocked_demands.len());
+                if let Some(channel_plan) = channel_plan {
+                    self.set_channel_plan(channel_plan);
+                }
+                self.merge_topology(elements, connections, defrag_timeline_events, fit_view);
+                self.set_blocked_demands(blocked_demands, true);
+                self.pending_notifications.push(ViewNotification::TopologyValidated {
+                    report: self.validation_report.clone(),
+                });
+            }
+            UserCommand::SetFullTopology { elements, connections, defrag_timeline_events, merge: false, fit_view, channel_plan, blocked_demands } => {
+                log::info!("Setting full topology with {} nodes, {} links, {} events, and {} blocked demands.",
+                            elements.len(), connections.len(), defrag_timeline_events.len(), blocked_demands.len());
+                if let Some(channel_plan) = channel_plan {
+                    self.set_channel_plan(channel_plan);
+                }
+                // 先清空旧事件，避免结构检查针对即将被替换的事件报告问题
+                self.finish_topology_export();
+                self.all_events.clear();
+                self.mark_events_changed();
+                if fit_view {
+                    self.clear_annotations(); // 注释只在保持视角的重新加载中保留
+                }
+                // apply_topology_structure 总是适配视角，不适配时恢复原来的相机
+                let camera_view = (!fit_view).then_some((self.camera.position, self.camera.zoom));
+                self.apply_topology_structure(elements, connections);
+                if let Some((position, zoom)) = camera_view {
+                    self.camera.position = position;
+                    self.camera.zoom = zoom;
+                    self.camera_needs_update = true;
+                }
+                self.apply_timeline_events(defrag_timeline_events);
+                self.set_blocked_demands(blocked_demands, false);
+                self.pending_notifications.push(ViewNotification::TopologyValidated {
+                    report: self.validation_report.clone(),
+                });
+            }
+            UserCommand::SetTopologyStructure { elements, connections } => {
+                log::info!("Setting topology structure with {} nodes and {} links.", elements.len(), connections.len());
+                self.apply_topology_structure(elements, connections);
+                self.pending_notifications.push(ViewNotification::TopologyValidated {
+                    report: self.validation_report.clone(),
+                });
+            }
+            UserCommand::SetTimelineEvents(defrag_timeline_events) => {
+                log::info!("Setting {} timeline events.", defrag_timeline_events.len());
+                self.apply_timeline_events(defrag_timeline_events);
+                self.pending_notifications.push(ViewNotification::TopologyValidated {
+                    report: self.validation_report.clone(),
+                });
             }
             UserCommand::SetNumChannels { num_channels } => {
-                self.num_channels = num_channels;
+                if self.set_channel_plan(ChannelPlan { max_wavelengths: num_channels, ..self.channel_plan }) {
+                    self.pending_notifications.push(ViewNotification::TopologyValidated { report: self.validation_report.clone() });
+                }
+            }
+            UserCommand::SetChannelPlan(channel_plan) => {
+                if self.set_channel_plan(channel_plan) {
+                    self.pending_notifications.push(ViewNotification::TopologyValidated { report: self.validation_report.clone() });
+                }
+            }
+            UserCommand::SetNodeRadius(radius) => {
+                self.set_node_radius(radius);
+            }
+            UserCommand::SetAutoNodeRadius(enabled) => {
+                self.set_auto_node_radius(enabled);
+            }
+            UserCommand::SetNodeRadiusOverrides(overrides) => {
+                self.set_node_radius_overrides(overrides);
+            }
+            UserCommand::SetSeparateOverlappingNodes(enabled) => {
+                self.set_separate_overlapping_nodes(enabled);
+            }
+            UserCommand::SetEdgeBundling { enabled, strength } => {
+                self.set_edge_bundling(enabled, strength);
             }
             UserCommand::StateInitialized => {
                 // ...
@@ -83,61 +374,411 @@ impl State {
                 // ...
             }
             UserCommand::SetTimeSelection(time) => {
-                if (self.current_time_selection - time).abs() > f32::EPSILON {
-                    self.current_time_selection = time;
-                    self.highlight_service_id_list = None; // 清除高亮服务
-                    self.world_text_labels.clear();
-                    self.topology_needs_update = true;
+                if (self.current_time_selection - time).abs() > f64::EPSILON {
+                    self.set_time_selection(time, TimeChangeReason::Api);
+                    self.highlight_service_id_list = None; // 清除高亮服务，跳数标签在重新生成线路时一起清除
                     log::debug!("Time selection updated to: {}", time);
                 }
             }
-            UserCommand::SetHighlightDefragService(selected_service_id) => {
-                let mut highlight_service_id_vec = Vec::new();
-                let mut arrival_time_for_highlight = 0.0;
-                let mut found_service = false;
-
-                // 遍历所有事件，找出与 selected_service_id 相关的所有服务ID
-                for event in &self.all_events {
-                    match event {
-                        AnyEvent::Allocation { service_id, details, .. } => {
-                            if selected_service_id == *service_id {
-                                highlight_service_id_vec.push(*service_id);
-                                arrival_time_for_highlight = details.arrival_time;
-                                found_service = true;
-                            }
-                        }
-                        AnyEvent::Reallocation { service_id, details, .. } => {
-                            // 如果 re-allocation 的来源是 selected_service_id
-                            if selected_service_id == details.defrag_service_id {
-                                // highlight_service_id_vec.push(*service_id);
-                                // 如果主要服务还没找到，则将此 reallocation 的 arrival_time 作为时间起点
-                                if !found_service {
-                                    arrival_time_for_highlight = details.service.arrival_time;
-                                    // 注意：这里可能需要更复杂的逻辑来确定一个合理的起始时间，
-                                    // 比如找到所有相关服务中最早的 arrival_time
-                                    found_service = true;
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
+            UserCommand::SetLodSettings(lod_settings) => {
+                if let Err(e) = lod_settings.validate() {
+                    errors::report(ViewError::warning("invalid_lod_settings", format!("Ignoring invalid LOD settings: {}", e)));
+                    return;
                 }
-
-                if found_service && !highlight_service_id_vec.is_empty() {
-                    log::info!(
-                        "Highlight Service IDs: {:?}",
-                        highlight_service_id_vec,
+                log::info!("LOD settings updated: {:?}", lod_settings);
+                self.lod_settings = lod_settings;
+                self.camera_needs_update = true; // 在下一次 update() 中按新阈值重新计算 LOD 层级
+            }
+            UserCommand::SetLabelSettings(label_settings) => {
+                if let Err(e) = label_settings.validate() {
+                    errors::report(ViewError::warning("invalid_label_settings", format!("Ignoring invalid label settings: {}", e)));
+                    return;
+                }
+                log::info!("Label settings updated: {:?}", label_settings);
+                self.label_settings = label_settings;
+            }
+            UserCommand::RenderColorTestPattern(visible) => {
+                self.set_color_test_pattern(visible);
+            }
+            UserCommand::ForceSrgbConversion(force) => {
+                self.force_srgb_conversion(force);
+            }
+            UserCommand::SetBackground(background) => {
+                self.set_background(background);
+            }
+            UserCommand::SetStatsOverlay(visible) => {
+                self.show_stats_overlay = visible;
+            }
+            UserCommand::SetServicePanel(visible) => {
+                self.set_service_panel(visible);
+            }
+            UserCommand::SetServiceTooltipTemplate(template) => {
+                self.service_tooltip_template = template;
+                self.service_panel.rows_need_update = true; // 重新生成正在显示的提示框
+            }
+            UserCommand::SetNodeIconMapping(overrides) => {
+                log::info!("Applying {} node icon override(s).", overrides.0.len());
+                self.node_icon_mapping.apply_overrides(overrides);
+            }
+            UserCommand::SetHighlightLineStyle(style) => {
+                if self.highlight_line_style != style {
+                    self.highlight_line_style = style;
+                    self.animation_start_instant = instant::Instant::now();
+                    self.topology_needs_update = true; // 线型写在顶点数据里，需要重新生成
+                }
+            }
+            UserCommand::SetHighlightStyle(style) => {
+                if let Err(e) = style.validate() {
+                    errors::report(ViewError::warning("invalid_highlight_style", format!("Ignoring invalid highlight style: {}", e)));
+                    return;
+                }
+                self.highlight_style = style;
+                self.topology_needs_update = true; // 颜色和线宽写在顶点数据里，需要重新生成
+            }
+            UserCommand::SetColorMode(color_mode) => {
+                if let Err(e) = color_mode.validate() {
+                    errors::report(ViewError::warning("invalid_color_mode", format!("Ignoring invalid color mode: {}", e)));
+                    return;
+                }
+                log::info!("Service color mode set to {}.", color_mode.as_str());
+                self.color_mode = color_mode;
+                self.topology_needs_update = true;
+            }
+            UserCommand::SetThicknessMode(thickness_mode) => {
+                if self.thickness_mode != thickness_mode {
+                    log::info!("Service thickness mode set to {:?}.", thickness_mode);
+                    self.thickness_mode = thickness_mode;
+                    self.topology_needs_update = true;
+                }
+            }
+            UserCommand::SetTimelineStrip(visible) => {
+                self.set_timeline_strip(visible);
+            }
+            UserCommand::SetOpacityMode(opacity_mode) => {
+                if self.opacity_mode != opacity_mode {
+                    log::info!("Service opacity mode set to {}.", opacity_mode.as_str());
+                    self.opacity_mode = opacity_mode;
+                    self.topology_needs_update = true;
+                }
+            }
+            UserCommand::SetServiceIntervalSemantics(semantics) => {
+                if self.service_interval != semantics {
+                    log::info!("Service interval semantics set to {:?}.", semantics);
+                    self.service_interval = semantics;
+                    self.topology_needs_update = true;
+                }
+            }
+            UserCommand::SetPreviewServices(services) => {
+                log::info!("Setting {} preview service(s).", services.len());
+                self.preview_services = services;
+                self.topology_needs_update = true;
+            }
+            UserCommand::SetCursorTracking(enabled) => {
+                self.cursor_tracking_enabled = enabled;
+            }
+            UserCommand::SetKeymap(keymap) => {
+                self.keymap = keymap;
+            }
+            UserCommand::SetNodePosition { element_id, x, y } => {
+                // 与 SetFullTopology 相同的坐标约定：拓扑数据的 y 轴向下，世界坐标 y 轴向上
+                if !self.set_node_position(&element_id, Vec2::new(x, -y)) {
+                    errors::report(
+                        ViewError::warning("unknown_node", format!("Cannot move node {}: node ID not found.", element_id))
+                            .with_context(json!({ "node_id": element_id })),
+                    );
+                }
+            }
+            UserCommand::UndoLayoutEdit => {
+                if !self.undo_layout_edit() {
+                    log::debug!("Nothing to undo.");
+                }
+            }
+            UserCommand::RedoLayoutEdit => {
+                if !self.redo_layout_edit() {
+                    log::debug!("Nothing to redo.");
+                }
+            }
+            UserCommand::ExportLayout(reply) => {
+                let _ = reply.send(self.export_layout());
+            }
+            UserCommand::ExportGeoJson { include_services, reply } => {
+                // 只在事件循环中复制数据，序列化由调用方在事件循环之外完成；节点使用当前位置（包括拖动和导入的布局）
+                let _ = reply.send(GeoJsonSnapshot {
+                    elements: self.all_elements.iter().enumerate().map(|(idx, element)| self.exported_element(idx, element)).collect(),
+                    connections: self.all_connections.clone(),
+                    services: if include_services { self.active_services_at_current_time() } else { Vec::new() },
+                });
+            }
+            UserCommand::ExportSvg { bounds, reply } => {
+                // 与 ExportGeoJson 相同，SVG 文本在事件循环之外生成
+                let _ = reply.send(self.svg_snapshot(bounds));
+            }
+            UserCommand::ExportFullTopology(reply) => {
+                // 事件数量可能很大，分多帧在事件循环中序列化（见 topology_export.rs）
+                self.start_topology_export(reply);
+            }
+            UserCommand::ExportSession(reply) => {
+                self.start_session_export(reply);
+            }
+            UserCommand::ImportSession(document) => {
+                self.import_session(*document);
+            }
+            UserCommand::CaptureFrame(reply) => {
+                self.request_frame_capture(None, reply);
+            }
+            UserCommand::RecordTimeline { frame_times, width, height, events } => {
+                // 帧在之后的 update() 中逐一截取，见 recording.rs
+                self.start_recording(frame_times, (width, height), events);
+            }
+            UserCommand::CancelRecording => {
+                self.cancel_recording();
+            }
+            UserCommand::ImportLayout { layout, fit_view, reply } => {
+                let summary = self.import_layout(&layout);
+                log::info!("Imported layout: {} node(s) applied, {} unknown.", summary.applied, summary.unknown_ids);
+                if fit_view {
+                    self.fit_view_to_topology();
+                }
+                let _ = reply.send(summary);
+            }
+            UserCommand::QueryVisibleNodes(reply) => {
+                let _ = reply.send(self.visible_nodes());
+            }
+            UserCommand::QuerySceneDescription(reply) => {
+                let _ = reply.send(self.scene_description());
+            }
+            UserCommand::HighlightPathBetween { from_id, to_id, weight, reply } => {
+                let path = self.highlight_path_between(&from_id, &to_id, weight);
+                if let Err(e) = &path {
+                    errors::report(
+                        ViewError::warning("path_not_found", format!("Cannot highlight path: {}", e))
+                            .with_context(json!({ "from_id": from_id, "to_id": to_id })),
                     );
-                    // 将时间设置到找到服务的开始时间，稍微加一点 EPSILON 确保在活跃期内
-                    self.current_time_selection = arrival_time_for_highlight + EPSILON;
-                    self.highlight_service_id_list = Some(highlight_service_id_vec);
-                    self.topology_needs_update = true; // 标记需要更新拓扑以显示高亮
-                    self.fit_view_to_topology(); // 可能需要重新调整视角
+                }
+                let _ = reply.send(path);
+            }
+            UserCommand::ClearPathHighlight => {
+                self.clear_path_highlight();
+            }
+            UserCommand::SetMeasurementMode(enabled) => {
+                self.set_measurement_mode(enabled);
+            }
+            UserCommand::ClearHighlight => {
+                self.clear_service_highlight();
+            }
+            UserCommand::ClearMeasurement => {
+                self.clear_measurement();
+            }
+            UserCommand::SetCapacityBars(visible) => {
+                self.set_capacity_bars(visible);
+            }
+            UserCommand::SetCompareTimes { t1, t2 } => {
+                if let Err(e) = self.set_compare_times(t1, t2) {
+                    errors::report(ViewError::warning("invalid_compare_times", format!("Ignoring compare times: {}", e)));
+                }
+            }
+            UserCommand::ClearCompare => {
+                self.clear_compare();
+            }
+            UserCommand::SetDiffTimes { t1, t2, reply } => {
+                let counts = self.set_diff_times(t1, t2);
+                if let Err(e) = &counts {
+                    errors::report(ViewError::warning("invalid_diff_times", format!("Ignoring diff times: {}", e)));
+                }
+                let _ = reply.send(counts);
+            }
+            UserCommand::ClearDiff => {
+                self.clear_diff();
+            }
+            UserCommand::SetFlowAnimation(enabled) => {
+                self.set_flow_animation(enabled);
+            }
+            UserCommand::SetQualityGovernor { enabled, budget_ms } => {
+                if let Err(e) = self.set_quality_governor(enabled, budget_ms) {
+                    errors::report(ViewError::warning("invalid_quality_governor", format!("Ignoring quality governor settings: {}", e)));
+                }
+            }
+            UserCommand::SetGpuProfiling(enabled) => {
+                self.set_gpu_profiling(enabled);
+            }
+            UserCommand::SetTextRendering(enabled) => {
+                self.set_text_rendering(enabled);
+            }
+            // 两者都不是只读命令，App 处理完后调用 request_redraw，与内部按需重绘合并为同一帧
+            UserCommand::RequestRedraw(reply) => {
+                let _ = reply.send(());
+            }
+            UserCommand::RenderOnce(reply) => {
+                self.render_once_waiters.push(reply);
+            }
+            UserCommand::QueryRenderStats(reply) => {
+                let _ = reply.send(self.render_stats());
+            }
+            UserCommand::QueryRendererInfo(reply) => {
+                let _ = reply.send(self.renderer_info.clone());
+            }
+            UserCommand::QueryColorLegend(reply) => {
+                let _ = reply.send(self.color_legend());
+            }
+            UserCommand::SetBlockedDemandLayer { visible, window } => {
+                if let Err(e) = self.set_blocked_demand_layer(visible, window) {
+                    errors::report(ViewError::warning("invalid_blocked_demand_layer", format!("Ignoring blocked demand settings: {}", e)));
+                }
+            }
+            UserCommand::QueryBlockedDemandsInRange { start, end, reply } => {
+                let _ = reply.send(self.blocked_demands_between(start, end).to_vec());
+            }
+            UserCommand::SetNodeGroups { groups, reply } => {
+                let _ = reply.send(self.set_node_groups(groups));
+            }
+            UserCommand::SetGroupVisibility { group, visible } => {
+                if let Err(e) = self.set_group_visibility(&group, visible) {
+                    errors::report(ViewError::warning("invalid_node_group", format!("Ignoring group visibility: {}", e)));
+                }
+            }
+            UserCommand::SetHeatTrail { enabled, window } => {
+                if let Err(e) = self.set_heat_trail(enabled, window) {
+                    errors::report(ViewError::warning("invalid_heat_trail", format!("Ignoring heat trail settings: {}", e)));
+                }
+            }
+            UserCommand::QueryServicesOnLink { connection_id, time, reply } => {
+                let _ = reply.send(self.services_on_connection(&connection_id, time));
+            }
+            UserCommand::QueryServicesAtTime { time, reply } => {
+                let _ = reply.send(self.active_services_at(time));
+            }
+            UserCommand::QueryActiveServiceIds { time, reply } => {
+                let ids = self.active_services_at(time).iter().map(|service| service.service_id).collect();
+                let _ = reply.send(ids);
+            }
+            UserCommand::QueryActiveServiceCounts { times, reply } => {
+                let _ = reply.send(self.active_service_counts(&times));
+            }
+            UserCommand::QueryEventsInRange { start, end, kinds, reply } => {
+                // 只在事件循环中复制事件，序列化由调用方完成
+                let _ = reply.send(events_in_range(&self.all_events, start, end, kinds.as_deref()));
+            }
+            UserCommand::AddTimeBookmark { time, label } => {
+                self.time_bookmarks.push(TimeBookmark { time, label });
+            }
+            UserCommand::ListTimeBookmarks(reply) => {
+                let _ = reply.send(self.time_bookmarks.clone());
+            }
+            UserCommand::RemoveTimeBookmark(index) => {
+                if index < self.time_bookmarks.len() {
+                    self.time_bookmarks.remove(index);
                 } else {
-                    log::warn!("Service ID {} not found or is not a defragmentation service.", selected_service_id);
+                    errors::report(
+                        ViewError::warning("unknown_bookmark", format!("Cannot remove time bookmark {}: only {} bookmark(s).", index, self.time_bookmarks.len()))
+                            .with_context(json!({ "index": index })),
+                    );
+                }
+            }
+            UserCommand::JumpToBookmark(index) => {
+                let Some(bookmark) = self.time_bookmarks.get(index) else {
+                    errors::report(
+                        ViewError::warning("unknown_bookmark", format!("Cannot jump to time bookmark {}: only {} bookmark(s).", index, self.time_bookmarks.len()))
+                            .with_context(json!({ "index": index })),
+                    );
+                    return;
+                };
+                self.set_time_selection(bookmark.time, TimeChangeReason::Bookmark);
+                self.highlight_service_id_list = None;
+            }
+            #[cfg(debug_assertions)]
+            UserCommand::DebugPanic(message) => {
+                panic!("debugPanic: {}", message);
+            }
+            UserCommand::SaveView(name) => {
+                self.save_view(name);
+            }
+            UserCommand::RestoreView { name, reply } => {
+                let result = self.restore_view(&name);
+                if let Err(e) = &result {
+                    errors::report(ViewError::warning("unknown_view", format!("Cannot restore view: {}", e)).with_context(json!({ "name": name })));
+                }
+                let _ = reply.send(result);
+            }
+            UserCommand::ListViews(reply) => {
+                let _ = reply.send(self.saved_views.keys().cloned().collect());
+            }
+            UserCommand::AddAnnotation(annotation) => {
+                let annotation_id = annotation.id.clone();
+                if let Err(e) = self.add_annotation(annotation) {
+                    errors::report(
+                        ViewError::warning("invalid_annotation", format!("Ignoring annotation: {}", e))
+                            .with_context(json!({ "id": annotation_id })),
+                    );
+                }
+            }
+            UserCommand::RemoveAnnotation(id) => {
+                if !self.remove_annotation(&id) {
+                    errors::report(
+                        ViewError::warning("unknown_annotation", format!("Cannot remove annotation '{}': no such annotation.", id))
+                            .with_context(json!({ "id": id })),
+                    );
+                }
+            }
+            UserCommand::ListAnnotations(reply) => {
+                let _ = reply.send(self.list_annotations());
+            }
+            UserCommand::DeleteView(name) => {
+                self.delete_view(&name);
+            }
+            UserCommand::ExportViews(reply) => {
+                let _ = reply.send(self.saved_views.clone());
+            }
+            UserCommand::ImportViews(views) => {
+                log::info!("Imported {} view(s).", views.len());
+                self.saved_views.extend(views); // 同名视图被覆盖
+            }
+            UserCommand::StepToEvent { direction, filter, reply } => {
+                let event = self.step_to_event(direction, filter);
+                if event.is_none() {
+                    log::info!("No further event {:?} from time {}.", direction, self.current_time_selection);
+                }
+                let _ = reply.send(event);
+            }
+            UserCommand::SetSelectedNode(node_id) => {
+                self.selected_node_idx = match node_id {
+                    Some(node_id) => match self.node_id_to_idx.get(&node_id) {
+                        Some(&idx) => Some(idx),
+                        None => {
+                            errors::report(
+                                ViewError::warning("unknown_node", format!("Cannot select node {}: node ID not found.", node_id))
+                                    .with_context(json!({ "node_id": node_id })),
+                            );
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                self.selection_needs_update = true;
+            }
+            UserCommand::SetHighlightDefragService { service_id: selected_service_id, include_moved } => {
+                // 第一个 id 是碎片整理服务本身，其余为被它（直接或间接）移动的服务，绘制时使用不同的高亮颜色
+                let Some((highlight_service_id_vec, arrival_time_for_highlight)) = defrag_highlight(&self.all_events, selected_service_id, include_moved) else {
+                    errors::report(
+                        ViewError::warning(
+                            "unknown_service",
+                            format!("Service ID {} not found or is not a defragmentation service.", selected_service_id),
+                        )
+                        .with_context(json!({ "service_id": selected_service_id })),
+                    );
                     self.highlight_service_id_list = None; // 确保清除高亮
                     self.topology_needs_update = true;
-                }
+                    return;
+                };
+
+                log::info!(
+                    "Highlight Service IDs: {:?}",
+                    highlight_service_id_vec,
+                );
+                // 将时间设置到找到服务的开始时间，稍微加一点 EPSILON 确保在活跃期内
+                self.set_time_selection(arrival_time_for_highlight + f64::EPSILON, TimeChangeReason::Highlight);
+                self.highlight_service_id_list = Some(highlight_service_id_vec); // 拓扑随时间一起重新生成以显示高亮
+                self.highlight_multi_select = false;
+                self.fit_view_to_topology(); // 可能需要重新调整视角
             }
         }
     }
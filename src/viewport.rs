@@ -0,0 +1,40 @@
+// src/viewport.rs
+// 视口查询：当前相机可见区域内的节点（节点圆与可见区域相交即视为可见），供侧边栏列表和场景描述使用
+use glam::Vec2;
+use serde::Serialize;
+
+use crate::app_state::State;
+
+/// 可见节点及其屏幕坐标（物理像素，左上角为原点）
+#[derive(Debug, Clone, Serialize)]
+pub struct VisibleNode {
+    pub element_id: String,
+    pub name: String,
+    pub screen_x: f32,
+    pub screen_y: f32,
+}
+
+impl State {
+    /// 按绘制顺序返回与可见区域相交的节点，位置使用 circle_instances（包含拖动后的位置）
+    pub fn visible_nodes(&self) -> Vec<VisibleNode> {
+        let (world_visible_min, world_visible_max) = self.camera.get_world_clip_bounds();
+        self.geometry.circle_instances
+            .iter()
+            .zip(&self.all_elements)
+            .filter_map(|(instance, element)| {
+                let position = Vec2::from_array(instance.position);
+                let radius = Vec2::splat(instance.radius_scale);
+                let intersects = (position + radius).cmpge(world_visible_min).all() && (position - radius).cmple(world_visible_max).all();
+                intersects.then(|| {
+                    let screen_pos = self.camera.world_to_screen(position);
+                    VisibleNode {
+                        element_id: element.element_id.clone(),
+                        name: element.name.clone(),
+                        screen_x: screen_pos.x,
+                        screen_y: screen_pos.y,
+                    }
+                })
+            })
+            .collect()
+    }
+}
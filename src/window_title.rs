@@ -0,0 +1,57 @@
+// src/window_title.rs
+// 原生窗口标题：显示命令行加载（或拖放到窗口上）的拓扑名称（FullTopologyData::name，缺省为文件名），有时间轴事件时附加当前时刻，
+// 例如 "ring16.json — t=1523.4"，便于区分同时打开的多个窗口。
+// 播放或拖动时间轴时时刻每帧变化，标题最多每 TITLE_UPDATE_INTERVAL 更新一次。
+use std::time::Duration;
+
+use instant::Instant;
+use winit::window::Window;
+
+use crate::app_state::State;
+
+pub const DEFAULT_WINDOW_TITLE: &str = "WDMView Graph Topology";
+const TITLE_UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct WindowTitle {
+    topology_name: Option<String>,
+    shown: String,
+    last_update: Option<Instant>,
+}
+
+impl WindowTitle {
+    pub fn new(topology_name: Option<String>) -> Self {
+        Self { topology_name, shown: DEFAULT_WINDOW_TITLE.to_string(), last_update: None }
+    }
+
+    /// 加载了另一个拓扑文件，下一次 refresh 时更新标题
+    pub fn set_topology_name(&mut self, topology_name: Option<String>) {
+        self.topology_name = topology_name;
+    }
+
+    fn title_for(&self, state: &State) -> String {
+        let name = self.topology_name.as_deref().unwrap_or(DEFAULT_WINDOW_TITLE);
+        if state.all_events.is_empty() {
+            name.to_string()
+        } else {
+            format!("{} — t={:.1}", name, state.current_time_selection)
+        }
+    }
+
+    /// 标题需要变化时更新窗口标题；距上次更新不足 TITLE_UPDATE_INTERVAL 时不更新，
+    /// 返回应当再次检查的时刻（调用方据此设置 ControlFlow::WaitUntil）
+    pub fn refresh(&mut self, window: &Window, state: &State) -> Option<Instant> {
+        let title = self.title_for(state);
+        if title == self.shown {
+            return None;
+        }
+        if let Some(next_update) = self.last_update.map(|last_update| last_update + TITLE_UPDATE_INTERVAL)
+            && Instant::now() < next_update
+        {
+            return Some(next_update);
+        }
+        window.set_title(&title);
+        self.shown = title;
+        self.last_update = Some(Instant::now());
+        None
+    }
+}
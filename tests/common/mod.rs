@@ -0,0 +1,102 @@
+// tests/common/mod.rs
+// 浏览器测试（wasm-pack test --headless --chrome）共用的辅助函数：启动事件循环、创建固定大小的画布并附着视图。
+// 每个测试文件是单独的 wasm 模块，run_web 只能调用一次，需要独立事件循环的测试放在不同的文件中。
+#![allow(dead_code)] // 每个测试文件只用到其中一部分
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use wdmview::WasmApi;
+
+#[wasm_bindgen(inline_js = r#"
+export function call_catching(f) {
+    try {
+        f();
+        return undefined;
+    } catch (e) {
+        return e;
+    }
+}
+
+export function sleep(ms) {
+    return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+export async function webgpu_adapter_available() {
+    return !!(navigator.gpu && await navigator.gpu.requestAdapter());
+}
+
+export function create_fixed_canvas(id, width, height) {
+    const canvas = document.createElement("canvas");
+    canvas.id = id;
+    canvas.width = width;
+    canvas.height = height;
+    canvas.style.width = `${width}px`;
+    canvas.style.height = `${height}px`;
+    document.body.appendChild(canvas);
+    return canvas;
+}
+
+export function count_non_background_pixels(canvas) {
+    const copy = document.createElement("canvas");
+    copy.width = canvas.width;
+    copy.height = canvas.height;
+    const context = copy.getContext("2d");
+    context.drawImage(canvas, 0, 0);
+    const data = context.getImageData(0, 0, copy.width, copy.height).data;
+    let count = 0;
+    for (let i = 0; i < data.length; i += 4) {
+        if (data[i] !== data[0] || data[i + 1] !== data[1] || data[i + 2] !== data[2] || data[i + 3] !== data[3]) {
+            count++;
+        }
+    }
+    return count;
+}
+"#)]
+extern "C" {
+    /// 调用 f，返回它抛出的异常（没有异常时为 undefined）
+    pub fn call_catching(f: &js_sys::Function) -> JsValue;
+    fn sleep(ms: u32) -> js_sys::Promise;
+    fn webgpu_adapter_available() -> js_sys::Promise;
+    pub fn create_fixed_canvas(id: &str, width: u32, height: u32) -> web_sys::HtmlCanvasElement;
+    /// 与左上角像素（背景）不同的像素数
+    pub fn count_non_background_pixels(canvas: &web_sys::HtmlCanvasElement) -> u32;
+}
+
+/// 启动事件循环（winit 在网页上用异常跳出 run_app，与宿主页面一样忽略它）并返回 WasmApi
+pub fn start_app() -> WasmApi {
+    let run = Closure::once_into_js(|| {
+        let _ = wdmview::run_web();
+    });
+    call_catching(run.unchecked_ref());
+    wdmview::get_wasm_api().expect("run_web initializes the WasmApi")
+}
+
+/// 创建固定大小的画布并附着视图，返回时第一帧已经绘制完成。
+/// 浏览器没有 WebGPU 适配器时测试失败（而不是悄悄通过）：需要在启用了 WebGPU 的浏览器中运行
+pub async fn attach_fixed_canvas(id: &str, width: u32, height: u32) -> (WasmApi, web_sys::HtmlCanvasElement) {
+    let available = JsFuture::from(webgpu_adapter_available()).await.unwrap_or(JsValue::FALSE);
+    assert!(
+        available.is_truthy(),
+        "No WebGPU adapter available: run the browser tests in a browser with WebGPU enabled \
+         (e.g. Chrome with --enable-unsafe-webgpu)"
+    );
+    let api = start_app();
+    let canvas = create_fixed_canvas(id, width, height);
+    let attached = api.attach_canvas_to_dom(id).expect("attachCanvasToDom sends the command");
+    JsFuture::from(attached).await.expect("the view attaches and draws its first frame");
+    (api, canvas)
+}
+
+/// 每 20 ms 检查一次 condition，timeout_ms 内成立时返回 true
+pub async fn wait_until(condition: impl Fn() -> bool, timeout_ms: u32) -> bool {
+    let mut waited = 0;
+    while !condition() {
+        if waited >= timeout_ms {
+            return false;
+        }
+        let _ = JsFuture::from(sleep(20)).await;
+        waited += 20;
+    }
+    true
+}
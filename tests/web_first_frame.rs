@@ -0,0 +1,16 @@
+// tests/web_first_frame.rs
+// 固定大小的画布（不会再收到 resize 事件）附着后第一帧就显示示例场景，见 State::with_gpu 中按初始大小配置 surface
+#![cfg(target_arch = "wasm32")]
+
+mod common;
+
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+async fn fixed_size_canvas_shows_the_demo_on_the_first_frame() {
+    let (_api, canvas) = common::attach_fixed_canvas("wdmview-first-frame-test", 320, 240).await;
+    // attachCanvasToDom 在第一帧绘制完成后 resolve；surface 没有配置时 render() 直接返回，画布保持空白
+    assert!(common::count_non_background_pixels(&canvas) > 0, "the first frame left the canvas blank");
+}
@@ -0,0 +1,43 @@
+// tests/web_panic.rs
+// panic 上报（panic_report.rs）：debugPanic 让事件循环 panic，setPanicCallback 注册的回调收到消息，之后的 WasmApi 调用立即抛出错误。
+// panic 之后事件循环不再可用，因此单独一个测试文件。
+#![cfg(target_arch = "wasm32")]
+
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+async fn debug_panic_notifies_callback_and_poisons_the_api() {
+    let (api, _canvas) = common::attach_fixed_canvas("wdmview-panic-test", 320, 240).await;
+
+    let reported: Rc<RefCell<Option<JsValue>>> = Rc::default();
+    let callback = {
+        let reported = reported.clone();
+        Closure::<dyn FnMut(JsValue)>::new(move |detail| *reported.borrow_mut() = Some(detail))
+    };
+    api.set_panic_callback(Some(callback.as_ref().unchecked_ref::<js_sys::Function>().clone()));
+    api.debug_panic("from test".to_string()).unwrap();
+    assert!(common::wait_until(|| reported.borrow().is_some(), 5000).await, "the panic callback was not called");
+
+    let detail = reported.borrow().clone().unwrap();
+    let message = js_sys::Reflect::get(&detail, &JsValue::from_str("message")).unwrap().as_string().unwrap_or_default();
+    assert!(message.contains("debugPanic: from test"), "unexpected panic message {:?}", message);
+    let location = js_sys::Reflect::get(&detail, &JsValue::from_str("location")).unwrap();
+    assert!(location.is_object(), "the panic location is reported");
+
+    // 之后的调用同步抛出 POISONED_MESSAGE，而不是等待不会再处理命令的事件循环
+    let set_time = Closure::once_into_js(move || {
+        let _ = api.set_time_selection(1.0);
+    });
+    let error = common::call_catching(set_time.unchecked_ref());
+    let error_message = error.dyn_ref::<js_sys::Error>().map(|e| String::from(e.message())).unwrap_or_default();
+    assert!(error_message.contains("WDMView has crashed"), "unexpected error {:?}", error);
+}